@@ -0,0 +1,105 @@
+//*** START FILE: benches/parse_throughput.rs ***//
+// Parse and generation throughput on a 10k-sentence book built by repeating the golden
+// corpus stage file: cargo bench --bench parse_throughput
+// The book is parsed both as one file and as 30-sentence chapter files, since per-call
+// setup (such as compiling the parser's regexes) is paid once per file. Block simulation
+// is measured on large blocks, where the regen passes' per-lemma state tests dominate.
+
+use std::hint::black_box;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use weavelang_rust_gui::parsing::llm_parser::parse_llm_bytes;
+use weavelang_rust_gui::simulation::block::Block;
+use weavelang_rust_gui::profile::LemmaState;
+use weavelang_rust_gui::simulation::core_algo::{run_simulation_numerical, BlockSimulationSettings, L4Settings, L4Strategy, TokenCt};
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+use weavelang_rust_gui::simulation::numerical_types::NumericalLearnerProfile;
+use weavelang_rust_gui::simulation::preprocessor::to_numerical_chapter;
+use weavelang_rust_gui::simulation::level_policy::LevelPolicy;
+use weavelang_rust_gui::simulation::text_generator::generate_final_text_block;
+use weavelang_rust_gui::tokenizer::tokenizer_for_language;
+
+const BOOK_SENTENCES: usize = 10_000;
+const ROUNDS: u32 = 5;
+const SENTENCES_PER_BLOCK: usize = 10;
+const SENTENCES_PER_LARGE_BLOCK: usize = 500;
+
+fn report(label: &str, sentences: usize, elapsed: Duration) {
+    let per_round = elapsed / ROUNDS;
+    println!("{:<28} {:>9.2} ms/round {:>12.0} sentences/s", label, per_round.as_secs_f64() * 1000.0, sentences as f64 / per_round.as_secs_f64());
+}
+
+fn main() {
+    let chapter_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/corpus/stage/bookA.llm.txt");
+    let chapter_text = std::fs::read_to_string(&chapter_path).expect("golden corpus stage file");
+    let chapter_sentences = chapter_text.matches("END_SENTENCE").count();
+    let copies = BOOK_SENTENCES.div_ceil(chapter_sentences);
+    let book_text = chapter_text.repeat(copies);
+    let book_sentences = chapter_sentences * copies;
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(parse_llm_bytes("bench.llm.txt", book_text.as_bytes()).expect("book parses"));
+    }
+    report("parse, one file", book_sentences, start.elapsed());
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for _ in 0..copies {
+            black_box(parse_llm_bytes("bench.llm.txt", chapter_text.as_bytes()).expect("chapter parses"));
+        }
+    }
+    report(&format!("parse, {} chapter files", copies), book_sentences, start.elapsed());
+
+    let chapter = parse_llm_bytes("bench.llm.txt", book_text.as_bytes()).expect("book parses");
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let numerical_chapter = to_numerical_chapter(&chapter, &mut dictionary);
+    let profile = NumericalLearnerProfile::new();
+    let base_tokenizer = tokenizer_for_language(&chapter.language_pair.base);
+    let l4 = L4Settings {
+        min_diglot_confidence: 0.5,
+        base_tokenizer: base_tokenizer.as_ref(),
+        plural_rules: None,
+        strategy: L4Strategy::default(),
+        corpus_frequency: None,
+    };
+    let level_policy = LevelPolicy::default();
+    let whole_chapter = Block::whole_chapter(&chapter, &numerical_chapter).expect("chapters pair up");
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for block_start in (0..whole_chapter.len()).step_by(SENTENCES_PER_BLOCK) {
+            let block = whole_chapter.with_positions(block_start..(block_start + SENTENCES_PER_BLOCK).min(whole_chapter.len()));
+            black_box(generate_final_text_block(&block, &profile, &l4, false, &level_policy).expect("block renders"));
+        }
+    }
+    report("generate", book_sentences, start.elapsed());
+
+    // A third of the lemmas Known and a third Active, the rest offered for activation.
+    let mut learner = NumericalLearnerProfile::new();
+    let mut new_lemma_ids = Vec::new();
+    for lemma_id in 0..dictionary.size() as u32 {
+        match lemma_id % 3 {
+            0 => learner.set_lemma_state(lemma_id, LemmaState::Known),
+            1 => learner.set_lemma_state(lemma_id, LemmaState::Active),
+            _ => new_lemma_ids.push((lemma_id, 1)),
+        }
+    }
+    let settings = BlockSimulationSettings {
+        max_regeneration_attempts_per_block: 10,
+        target_ct_comprehensible_threshold: 0.98,
+        min_ct_comprehensible_threshold: 0.0,
+        max_words_to_activate_per_regen_attempt: 1,
+        l4,
+        ct_metric: &TokenCt,
+        level_policy: &level_policy,
+    };
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for block_start in (0..whole_chapter.len()).step_by(SENTENCES_PER_LARGE_BLOCK) {
+            let block = whole_chapter.with_positions(block_start..(block_start + SENTENCES_PER_LARGE_BLOCK).min(whole_chapter.len()));
+            black_box(run_simulation_numerical(&block, &learner, &new_lemma_ids, &settings).expect("block simulates"));
+        }
+    }
+    report(&format!("simulate, {}-sentence blocks", SENTENCES_PER_LARGE_BLOCK), book_sentences, start.elapsed());
+}
+//*** END FILE: benches/parse_throughput.rs ***//
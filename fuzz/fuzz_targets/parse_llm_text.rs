@@ -0,0 +1,15 @@
+//*** START FILE: fuzz/fuzz_targets/parse_llm_text.rs ***//
+// Feeds arbitrary bytes to the stage file parser; any panic is a bug. The seeded property
+// tests in tests/parser_fuzz.rs cover the same entry point in a normal `cargo test`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use weavelang_rust_gui::parsing::llm_parser::{parse_llm_bytes, validate_llm_text};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = parse_llm_bytes("fuzz.llm.txt", data);
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = validate_llm_text("fuzz.llm.txt", text);
+    }
+});
+//*** END FILE: fuzz/fuzz_targets/parse_llm_text.rs ***//
@@ -5,7 +5,7 @@ from typing import List, Tuple, Optional, Set # Added Set for type hinting
 RE_S_SEGMENT_LINE = re.compile(r"^(S\d+)\((.*)\)$")
 RE_PHRASE_ALIGN_LINE = re.compile(r"^(S\d+)\s*~\s*(.*?)\s*~\s*(.*)$")
 RE_S_LEMMA_LINE = re.compile(r"^(S\d+)\s*::\s*(.*)$") # For SimSL and DIGLOT_MAP segment headers
-RE_DIGLOT_ENTRY = re.compile(r"^(.*?)->(.*?)\((.*?)\)\s*\(([A-Za-z])\)$")
+RE_DIGLOT_ENTRY = re.compile(r"^(.*?)->(.*?)\((.*?)\)\s*\(([A-Za-z])(?:\s*:\s*([0-9]*\.?[0-9]+))?\)$")
 RE_S_ID_FORMAT = re.compile(r"^S\d+$") # To check S-ID format in LOCKED_PHRASE
 
 # --- Section Marker Constants ---
@@ -230,7 +230,7 @@ def validate_llm_block(block_text: str) -> List[str]:
                     if not match_entry:
                         errors.append(f"DIGLOT_MAP:: S-ID {s_id}, entry '{entry_part_stripped[:30]}...' malformed (failed basic regex).")
                     else:
-                        eng, spa_lemma, form, viability_char = match_entry.groups()
+                        eng, spa_lemma, form, viability_char, confidence_str = match_entry.groups()
                         if not eng.strip(): # Check if eng is not just whitespace
                             errors.append(f"DIGLOT_MAP:: S-ID {s_id}, entry '{entry_part_stripped[:30]}...' has empty EngWord.")
                         if not spa_lemma.strip():
@@ -239,6 +239,8 @@ def validate_llm_block(block_text: str) -> List[str]:
                             errors.append(f"DIGLOT_MAP:: S-ID {s_id}, entry '{entry_part_stripped[:30]}...' has empty ExactSpaForm.")
                         if viability_char not in "YN":
                             errors.append(f"DIGLOT_MAP:: S-ID {s_id}, entry '{entry_part_stripped[:30]}...' has invalid ViabilityFlag character: '{viability_char}'. Expected Y or N.")
+                        if confidence_str is not None and not 0.0 <= float(confidence_str) <= 1.0:
+                            errors.append(f"DIGLOT_MAP:: S-ID {s_id}, entry '{entry_part_stripped[:30]}...' has confidence {confidence_str} outside 0.0-1.0.")
         
         # After checking all lines in DIGLOT_MAP, ensure all S-IDs from SimS_Segments have a corresponding line
         for s_id_expected in s_segment_ids_set:
@@ -0,0 +1,331 @@
+//*** START FILE: src/bilingual_lexicon.rs ***//
+// Corpus-wide base-word -> target-lemma lexicon for diglot substitution. Entries come from
+// two sources:
+//
+// Harvested: the DIGLOT_MAP entries of the annotated books. An English word is kept when
+//   most of its viable entries agree on one lemma and form, so context-dependent words
+//   ("right" -> derecho / correcto) stay out. Used only for sentences without a DIGLOT_MAP
+//   of their own (plain-text books, see parsing::plain_text).
+// File: the project's lexicon file (bilingual_lexicon_path in the config). These entries
+//   win over harvested ones and also augment annotated sentences, so a common word the LLM
+//   annotation missed ("house" -> casa) can be substituted in every sentence.
+//
+// Lexicon file formats, chosen by extension:
+//
+// TSV/CSV (.tsv/.csv/.txt): "eng_word, spa_lemma[, exact_spa_form[, viability]]" per line,
+//   tab- or comma-separated; '#' comments and a header row are skipped. The form defaults to
+//   the lemma; viability is Y, N or Y:0.8 as in DIGLOT_MAP (default Y).
+//
+// TOML (.toml):
+//   [words]
+//   house = "casa"                                       # Lemma, used as the form too
+//   went = { lemma = "ir", form = "fue" }
+//   "ice cream" = { lemma = "helado", confidence = 0.8 }
+//   right = { lemma = "derecho", viable = false }        # Never substitute "right"
+//
+// A non-viable entry is never substituted and blocks the harvested entry of its word.
+
+use crate::types::llm_data::{DiglotEntry, DiglotSegmentMap, ProcessedChapter};
+use crate::session::content_hash;
+use crate::tokenizer::Tokenizer;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::error::WeaveLangError;
+use std::fs;
+use std::path::Path;
+
+// Share of a word's viable entries the winning pairing needs.
+const MIN_AGREEMENT: f32 = 0.75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LexiconSource {
+    Harvested,
+    File,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BilingualLexiconEntry {
+    pub eng_word: String, // Lowercase; may be several words ("ice cream")
+    pub spa_lemma: String,
+    pub exact_spa_form: String,
+    pub confidence: f32, // Harvested: mean confidence of the agreeing DIGLOT_MAP entries
+    pub source: LexiconSource,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TomlWord {
+    Lemma(String),
+    Detailed {
+        lemma: String,
+        form: Option<String>,
+        confidence: Option<f32>,
+        viable: Option<bool>,
+    },
+}
+
+#[derive(Deserialize)]
+struct LexiconTomlFile {
+    #[serde(default)]
+    words: BTreeMap<String, TomlWord>,
+}
+
+// Lowercase words separated by single spaces.
+fn normalize_eng_word(eng_word: &str) -> String {
+    eng_word.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+// "Y", "N" or "Y:0.8", as in DIGLOT_MAP entries.
+fn parse_viability(field: &str) -> Option<f32> {
+    let (flag, value) = match field.split_once(':') {
+        Some((flag, value)) => (flag.trim(), Some(value.trim().parse::<f32>().ok()?)),
+        None => (field.trim(), None),
+    };
+    match flag {
+        "Y" | "y" => Some(value.unwrap_or(1.0).clamp(0.0, 1.0)),
+        "N" | "n" => Some(0.0),
+        _ => None,
+    }
+}
+
+// DIGLOT_MAP entries of one English word agreeing on a (lemma, form) pairing.
+#[derive(Default)]
+struct PairingTally {
+    count: usize,
+    confidence_sum: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BilingualLexicon {
+    entries: BTreeMap<String, BilingualLexiconEntry>, // Keyed by eng_word
+    max_phrase_words: usize,
+}
+
+impl BilingualLexicon {
+    /// Harvests the DIGLOT_MAP entries of `chapters` marked viable. Their confidence is
+    /// carried over, so generation's --min-diglot-confidence still applies.
+    pub fn harvest<'a>(chapters: impl IntoIterator<Item = &'a ProcessedChapter>) -> Self {
+        let mut pairings: HashMap<String, BTreeMap<(String, String), PairingTally>> = HashMap::new();
+        for chapter in chapters {
+            for entry in chapter.sentences.iter().flat_map(|s| &s.diglot_map).flat_map(|dm| &dm.entries) {
+                let eng_word = normalize_eng_word(&entry.eng_word);
+                if !entry.is_viable(0.0) || eng_word.is_empty()
+                    || entry.spa_lemma.trim().is_empty() || entry.exact_spa_form.trim().is_empty() {
+                    continue;
+                }
+                let pairing = (entry.spa_lemma.trim().to_lowercase(), entry.exact_spa_form.trim().to_string());
+                let tally = pairings.entry(eng_word).or_default().entry(pairing).or_default();
+                tally.count += 1;
+                tally.confidence_sum += entry.confidence;
+            }
+        }
+        let mut lexicon = BilingualLexicon::default();
+        for (eng_word, candidates) in pairings {
+            let total: usize = candidates.values().map(|tally| tally.count).sum();
+            // Most frequent pairing; BTreeMap order breaks ties deterministically.
+            let mut best: Option<((String, String), PairingTally)> = None;
+            for candidate in candidates {
+                if best.as_ref().is_none_or(|(_, best_tally)| candidate.1.count > best_tally.count) {
+                    best = Some(candidate);
+                }
+            }
+            let Some(((spa_lemma, exact_spa_form), tally)) = best else { continue };
+            if (tally.count as f32) < MIN_AGREEMENT * total as f32 {
+                continue;
+            }
+            let confidence = tally.confidence_sum / tally.count as f32;
+            lexicon.insert(BilingualLexiconEntry { eng_word, spa_lemma, exact_spa_form, confidence, source: LexiconSource::Harvested });
+        }
+        lexicon
+    }
+
+    /// Loads a lexicon file (TSV/CSV or TOML, chosen by the extension).
+    pub fn load(file_path: &Path) -> Result<Self, WeaveLangError> {
+        let is_toml = file_path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+        let contents = fs::read_to_string(file_path)
+            .map_err(|e| WeaveLangError::config(format!("Failed to read bilingual lexicon {:?}: {}", file_path, e)).with_source(e))?;
+        if is_toml { Self::parse_toml(&contents, file_path) } else { Self::parse_tsv(&contents, file_path) }
+    }
+
+    fn parse_tsv(contents: &str, file_path: &Path) -> Result<Self, WeaveLangError> {
+        let mut lexicon = BilingualLexicon::default();
+        for (line_idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(['\t', ',']).map(str::trim).collect();
+            let eng_word = normalize_eng_word(fields[0]);
+            let spa_lemma = fields.get(1).copied().unwrap_or_default();
+            let exact_spa_form = fields.get(2).copied().filter(|f| !f.is_empty()).unwrap_or(spa_lemma);
+            let confidence = match fields.get(3).filter(|f| !f.is_empty()) {
+                Some(field) => parse_viability(field),
+                None => Some(1.0),
+            };
+            match confidence {
+                Some(confidence) if !eng_word.is_empty() && !spa_lemma.is_empty() => lexicon.insert(BilingualLexiconEntry {
+                    eng_word,
+                    spa_lemma: spa_lemma.to_lowercase(),
+                    exact_spa_form: exact_spa_form.to_string(),
+                    confidence,
+                    source: LexiconSource::File,
+                }),
+                _ if lexicon.is_empty() && line_idx == 0 => {} // Header row
+                _ => return Err(WeaveLangError::config(format!(
+                    "Invalid bilingual lexicon row at {:?} line {}: expected 'eng_word, spa_lemma[, exact_spa_form[, Y|N|Y:0.8]]'.",
+                    file_path, line_idx + 1
+                ))),
+            }
+        }
+        Ok(lexicon)
+    }
+
+    fn parse_toml(contents: &str, file_path: &Path) -> Result<Self, WeaveLangError> {
+        let parsed: LexiconTomlFile = toml::from_str(contents)
+            .map_err(|e| WeaveLangError::config(format!("Failed to parse bilingual lexicon {:?}: {}", file_path, e)).with_source(e))?;
+        let mut lexicon = BilingualLexicon::default();
+        for (eng_word, word) in parsed.words {
+            let (spa_lemma, form, confidence) = match word {
+                TomlWord::Lemma(lemma) => (lemma, None, 1.0),
+                TomlWord::Detailed { lemma, form, confidence, viable } => {
+                    let confidence = if viable == Some(false) { 0.0 } else { confidence.unwrap_or(1.0).clamp(0.0, 1.0) };
+                    (lemma, form, confidence)
+                }
+            };
+            let eng_word = normalize_eng_word(&eng_word);
+            let spa_lemma = spa_lemma.trim().to_lowercase();
+            if eng_word.is_empty() || spa_lemma.is_empty() {
+                return Err(WeaveLangError::config(format!("Bilingual lexicon {:?} has an entry with an empty word or lemma.", file_path)));
+            }
+            let exact_spa_form = form.map(|f| f.trim().to_string()).filter(|f| !f.is_empty()).unwrap_or_else(|| spa_lemma.clone());
+            lexicon.insert(BilingualLexiconEntry { eng_word, spa_lemma, exact_spa_form, confidence, source: LexiconSource::File });
+        }
+        Ok(lexicon)
+    }
+
+    /// Adds `other`'s entries, replacing entries for the same word.
+    pub fn merge(&mut self, other: BilingualLexicon) {
+        for entry in other.entries.into_values() {
+            self.insert(entry);
+        }
+    }
+
+    pub fn insert(&mut self, entry: BilingualLexiconEntry) {
+        self.max_phrase_words = self.max_phrase_words.max(entry.eng_word.split_whitespace().count());
+        self.entries.insert(entry.eng_word.clone(), entry);
+    }
+
+    pub fn get(&self, eng_word: &str) -> Option<&BilingualLexiconEntry> {
+        self.entries.get(&eng_word.to_lowercase())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn count_from(&self, source: LexiconSource) -> usize {
+        self.entries.values().filter(|entry| entry.source == source).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Hash of every entry, so caches of books the lexicon was applied to can tell whether
+    /// it changed since.
+    pub fn fingerprint(&self) -> String {
+        content_hash(format!("{:?}", self.entries).as_bytes())
+    }
+
+    // Viable entries found in `sim_e`, in order, as one segment each ("W1", "W2", ...) so L4
+    // may substitute several of them. Longer phrases win over the words inside them; tokens
+    // in `already_mapped` (lowercase) are skipped. Entries are lowercase, so L4 capitalizes
+    // sentence-initial words.
+    fn segments_for(
+        &self,
+        sim_e: &str,
+        base_tokenizer: &dyn Tokenizer,
+        include: impl Fn(&BilingualLexiconEntry) -> bool,
+        already_mapped: &HashSet<String>,
+    ) -> Vec<DiglotSegmentMap> {
+        let tokens = base_tokenizer.tokenize(sim_e);
+        let mut maps = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            let longest = self.max_phrase_words.min(tokens.len() - i);
+            let found = (1..=longest).rev().find_map(|len| {
+                let words: Vec<String> = tokens[i..i + len].iter().map(|t| t.text.to_lowercase()).collect();
+                if words.iter().any(|word| already_mapped.contains(word)) {
+                    return None;
+                }
+                self.entries.get(&words.join(" ")).map(|entry| (len, entry))
+            });
+            match found {
+                Some((len, entry)) if entry.confidence > 0.0 && include(entry) => {
+                    maps.push(DiglotSegmentMap {
+                        segment_id: format!("W{}", maps.len() + 1),
+                        entries: vec![DiglotEntry {
+                            eng_word: entry.eng_word.clone(),
+                            spa_lemma: entry.spa_lemma.clone(),
+                            exact_spa_form: entry.exact_spa_form.clone(),
+                            confidence: entry.confidence,
+                        }],
+                    });
+                    i += len;
+                }
+                Some((len, _)) => i += len, // Blocked or excluded phrase: its words stay English
+                None => i += 1,
+            }
+        }
+        maps
+    }
+
+    /// DIGLOT_MAP for a SimE sentence from every viable entry (see segments_for).
+    pub fn diglot_map_for(&self, sim_e: &str, base_tokenizer: &dyn Tokenizer) -> Vec<DiglotSegmentMap> {
+        self.segments_for(sim_e, base_tokenizer, |_| true, &HashSet::new())
+    }
+
+    /// Gives every sentence of `chapter` without a DIGLOT_MAP one from the lexicon
+    /// (used for plain-text books). Returns the number of entries added.
+    pub fn fill_missing_diglot_maps(&self, chapter: &mut ProcessedChapter, base_tokenizer: &dyn Tokenizer) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+        let mut added = 0;
+        for sentence in chapter.sentences.iter_mut().filter(|s| s.diglot_map.is_empty()) {
+            sentence.diglot_map = self.diglot_map_for(&sentence.sim_e, base_tokenizer);
+            added += sentence.diglot_map.len();
+        }
+        added
+    }
+
+    /// Adds lexicon-file entries to annotated sentences for SimE words their DIGLOT_MAP does
+    /// not cover. Harvested entries are left out: they come from these annotations already.
+    /// Returns the number of entries added.
+    pub fn augment_diglot_maps(&self, chapter: &mut ProcessedChapter, base_tokenizer: &dyn Tokenizer) -> usize {
+        if self.count_from(LexiconSource::File) == 0 {
+            return 0;
+        }
+        let mut added = 0;
+        for sentence in chapter.sentences.iter_mut() {
+            let already_mapped: HashSet<String> = sentence.diglot_map.iter()
+                .flat_map(|dm| &dm.entries)
+                .flat_map(|entry| base_tokenizer.tokenize(&entry.eng_word).into_iter().map(|t| t.text.to_lowercase()).collect::<Vec<_>>())
+                .collect();
+            let mut segments = self.segments_for(&sentence.sim_e, base_tokenizer, |entry| entry.source == LexiconSource::File, &already_mapped);
+            // Keep the new segment IDs clear of the sentence's own.
+            let mut next_id = 1;
+            for segment in &mut segments {
+                while sentence.diglot_map.iter().any(|dm| dm.segment_id == format!("W{}", next_id)) {
+                    next_id += 1;
+                }
+                segment.segment_id = format!("W{}", next_id);
+                next_id += 1;
+            }
+            added += segments.len();
+            sentence.diglot_map.extend(segments);
+        }
+        added
+    }
+}
+//*** END FILE: src/bilingual_lexicon.rs ***//
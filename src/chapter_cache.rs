@@ -0,0 +1,142 @@
+//*** START FILE: src/chapter_cache.rs ***//
+// On-disk cache of parsed and converted books, so `generate` does not re-parse and
+// re-convert every stage file on every run. One file per book stem in the cache directory
+// (<content_project_dir>/.cache/chapters by default) holds the string chapter after the
+// bilingual lexicon was applied, where its chapters lie, its numerical conversion and the
+// book's local dictionary.
+//
+// Entries are keyed by a hash of everything the conversion depends on: the stage file's
+// name and contents, the project's language pair, the diacritic mode, the lexicon and the
+// crate version. A file whose key doesn't match (or that can't be decoded) is treated as a
+// miss and overwritten, so a stale or corrupt cache never needs to be cleared by hand.
+
+use crate::error::{BoxedError, WeaveLangError};
+use crate::bilingual_lexicon::BilingualLexicon;
+use crate::config::Config;
+use crate::session::content_hash;
+use crate::simulation::dictionary::GlobalLemmaDictionary;
+use crate::simulation::numerical_types::NumericalChapter;
+use crate::types::llm_data::{ChapterSpan, LanguagePair, ProcessedChapter};
+use crate::unicode_norm;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Cache files start with this tag; the trailing byte is the layout version.
+const CHAPTER_CACHE_MAGIC: &[u8; 8] = b"WLCHAP\0\x03";
+
+// Distinguishes temporary files of workers storing the same book at the same time.
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A book as prepare_book leaves it, minus the validation issues (cheap to recompute).
+#[derive(Debug, Clone)]
+pub struct CachedChapter {
+    pub string_chapter: ProcessedChapter,
+    pub chapter_spans: Vec<ChapterSpan>,
+    pub numerical_chapter: NumericalChapter,
+    pub local_dictionary: GlobalLemmaDictionary,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChapterCache {
+    dir: PathBuf,
+}
+
+impl ChapterCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// <content_project_dir>/.cache/chapters
+    pub fn default_dir(project_config: &Config) -> PathBuf {
+        Path::new(&project_config.content_project_dir).join(".cache").join("chapters")
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, book_stem: &str) -> PathBuf {
+        self.dir.join(format!("{}.chapter.bin", book_stem))
+    }
+
+    /// The cached book for `book_stem` if it was stored under `key`; None on any miss.
+    pub fn load(&self, book_stem: &str, key: &str) -> Option<CachedChapter> {
+        read_entry(&self.entry_path(book_stem), Some(key))
+    }
+
+    /// Every book in the cache that can be decoded, whatever key it was stored under.
+    pub fn load_all(&self) -> Result<Vec<CachedChapter>, WeaveLangError> {
+        let entries = fs::read_dir(&self.dir)
+            .map_err(|e| WeaveLangError::generation(format!("Failed to read chapter cache directory {}: {}", self.dir.display(), e)).with_source(e))?;
+        let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.to_string_lossy().ends_with(".chapter.bin"))
+            .collect();
+        paths.sort();
+        Ok(paths.iter().filter_map(|path| read_entry(path, None)).collect())
+    }
+
+    /// Stores a book under `key`, replacing whatever was cached for `book_stem`. The file is
+    /// written next to its final path and renamed into place, so readers never see half of it.
+    pub fn store(
+        &self,
+        book_stem: &str,
+        key: &str,
+        string_chapter: &ProcessedChapter,
+        chapter_spans: &[ChapterSpan],
+        numerical_chapter: &NumericalChapter,
+        local_dictionary: &GlobalLemmaDictionary,
+    ) -> Result<(), WeaveLangError> {
+        let entry_path = self.entry_path(book_stem);
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| WeaveLangError::generation(format!("Failed to create chapter cache directory {}: {}", self.dir.display(), e)).with_source(e))?;
+        let temp_path = self.dir.join(format!(
+            "{}.chapter.bin.{}-{}.tmp",
+            book_stem, std::process::id(), TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let write_entry = || -> Result<(), BoxedError> {
+            let file = File::create(&temp_path)?;
+            let mut writer = BufWriter::new(file);
+            writer.write_all(CHAPTER_CACHE_MAGIC)?;
+            bincode::serialize_into(&mut writer, key)?;
+            bincode::serialize_into(&mut writer, &(string_chapter, chapter_spans, numerical_chapter, local_dictionary))?;
+            writer.flush()?;
+            drop(writer);
+            Ok(fs::rename(&temp_path, &entry_path)?)
+        };
+        write_entry().map_err(|e| {
+            let _ = fs::remove_file(&temp_path);
+            WeaveLangError::generation(format!("Failed to write chapter cache {}: {}", entry_path.display(), e)).with_source(e)
+        })
+    }
+}
+
+// Decodes a cache file; with `key`, only if it was stored under that key.
+fn read_entry(path: &Path, key: Option<&str>) -> Option<CachedChapter> {
+    let file = File::open(path).ok()?;
+    let mut reader = BufReader::new(file);
+    let mut header = [0u8; CHAPTER_CACHE_MAGIC.len()];
+    reader.read_exact(&mut header).ok()?;
+    if &header != CHAPTER_CACHE_MAGIC {
+        return None;
+    }
+    let stored_key: String = bincode::deserialize_from(&mut reader).ok()?;
+    if key.is_some_and(|key| key != stored_key) {
+        return None;
+    }
+    let (string_chapter, chapter_spans, numerical_chapter, local_dictionary) = bincode::deserialize_from(reader).ok()?;
+    Some(CachedChapter { string_chapter, chapter_spans, numerical_chapter, local_dictionary })
+}
+
+/// Cache key of a stage file read as `file_name` with `content`, converted for
+/// `language_pair` with `lexicon` applied, under the current diacritic mode.
+pub fn cache_key(file_name: &str, content: &str, language_pair: &LanguagePair, lexicon: &BilingualLexicon) -> String {
+    let header = format!(
+        "{}\n{}\n{}/{}\n{:?}\n{}\n",
+        env!("CARGO_PKG_VERSION"), file_name, language_pair.target, language_pair.base,
+        unicode_norm::diacritic_mode(), lexicon.fingerprint()
+    );
+    format!("{}-{}", content_hash(header.as_bytes()), content_hash(content.as_bytes()))
+}
+//*** END FILE: src/chapter_cache.rs ***//
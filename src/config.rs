@@ -1,32 +1,264 @@
+use crate::corpus_generator::TtsOutputFormat;
+use crate::error::WeaveLangError;
+use crate::profile_io::SnapshotFormat;
+use crate::simulation::core_algo::{CtMetricKind, L4Strategy};
+use crate::simulation::level_policy::LevelPolicy;
+use crate::types::llm_data::LanguagePair;
+use crate::unicode_norm::DiacriticMode;
 use serde::Deserialize;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+// Environment variables overriding config keys: WEAVELANG_TARGET_CT_THRESHOLD=0.95 sets
+// target_ct_threshold, and a double underscore reaches into tables
+// (WEAVELANG_LANGUAGE_PAIR__TARGET=fr sets language_pair.target).
+pub const ENV_PREFIX: &str = "WEAVELANG_";
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Config {
     pub content_project_dir: String,
+    // Directory of the stage files inside content_project_dir.
+    #[serde(default = "default_stage_subdir")]
+    pub stage_subdir: String,
+    // Optional Wiktionary JSONL extract used to gloss lemmas missing from DIGLOT_MAPs.
+    pub lexicon_dump_path: Option<String>,
+    // Optional per-lemma exposure threshold table (CSV or TOML, see exposure_thresholds.rs).
+    pub exposure_thresholds_path: Option<String>,
+    // Optional project-level English -> target lexicon (TSV or TOML, see bilingual_lexicon.rs)
+    // adding diglot words to every sentence whose DIGLOT_MAP misses them.
+    pub bilingual_lexicon_path: Option<String>,
+    // Target/base languages of the staged content; defaults to Spanish/English.
+    #[serde(default)]
+    pub language_pair: LanguagePair,
+    // Comprehensibility metric for the regen loop: token (default), type, frequency or sentence.
+    #[serde(default)]
+    pub ct_metric: CtMetricKind,
+    // Levels sentences may be rendered at, in the order tried, e.g. ["L1", "L2", "L3", "L5"];
+    // the last one is the fallback. Defaults to L1..L5.
+    #[serde(default)]
+    pub levels: LevelPolicy,
+    // Comprehensible share (Known target tokens plus base-language words) a sentence needs at
+    // any level but the fallback; below it the next level is tried. 0 (default) disables it.
+    #[serde(default)]
+    pub min_sentence_ct: f32,
+    // How L4 picks among a segment's viable diglot words: first, lowest-exposure (default)
+    // or highest-frequency.
+    #[serde(default)]
+    pub l4_strategy: L4Strategy,
+    // Let L4 replace plural SimE words ("dogs" for a "dog" entry) with the pluralized form.
+    #[serde(default)]
+    pub l4_match_plurals: bool,
+    // Count exposures per target-language form as well as per lemma, and report form coverage.
+    #[serde(default)]
+    pub track_forms: bool,
+    // Diacritics in lemma keys: "keep" (default; "el" and "él" are different lemmas) or "fold"
+    // (accents stripped, so they share an ID). Keep one setting for a project's snapshots.
+    #[serde(default)]
+    pub diacritics: DiacriticMode,
+    // Most lemmas introduced within any 100 consecutive sentences, across blocks and books.
+    #[serde(default)]
+    pub max_new_lemmas_per_100_sentences: Option<f32>,
+    // Simulation defaults; the matching `generate` flags override them. Unset values fall
+    // back to the built-in defaults of each mode (the GUI picks its own block size).
+    #[serde(default)]
+    pub sentences_per_block: Option<usize>,
+    // End blocks at the paragraph end closest to sentences_per_block, up to this many
+    // sentences shorter or longer. Unset = blocks of exactly sentences_per_block.
+    #[serde(default)]
+    pub paragraph_block_tolerance: Option<usize>,
+    #[serde(default)]
+    pub target_ct_threshold: Option<f32>,
+    #[serde(default)]
+    pub min_ct_threshold: Option<f32>,
+    // Exposures an Active lemma needs to become Known when the threshold table (if any) has
+    // no entry or default for it. Defaults to DEFAULT_EXPOSURE_THRESHOLD.
+    #[serde(default)]
+    pub exposure_threshold: Option<u32>,
+    // Most exposures a cognate ("hospital", "importante") needs to become Known; unset
+    // turns cognate detection off. See exposure_thresholds::is_cognate.
+    #[serde(default)]
+    pub cognate_exposure_threshold: Option<u32>,
+    // Output formats of `generate`: TTS text ("text" or "ssml") and profile snapshots
+    // ("json" or "binary").
+    #[serde(default)]
+    pub output_format: Option<TtsOutputFormat>,
+    #[serde(default)]
+    pub profile_format: Option<SnapshotFormat>,
+    // External lemmatizer for raw target-language text (one word form per line in, one
+    // lemma per line out); see lemmatizer::CommandLemmatizer.
+    #[serde(default)]
+    pub lemmatizer_command: Option<String>,
+    // Metadata written into exported books (EPUB); every field is optional.
+    #[serde(default)]
+    pub book_metadata: BookMetadata,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct BookMetadata {
+    pub series_title: Option<String>, // Prefixed to each book instance's title
+    pub author: Option<String>,
+    pub publisher: Option<String>,
+    pub language: Option<String>,     // Defaults to the target language code
+}
+
+fn default_stage_subdir() -> String {
+    "stage".to_string()
 }
 
-pub fn load_config_from_file(file_path: &str) -> Result<Config, String> {
+// ISO 639 language code, optionally with a region or script subtag ("es", "pt-BR").
+fn is_language_code(code: &str) -> bool {
+    let mut parts = code.split('-');
+    let language = parts.next().unwrap_or_default();
+    (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_lowercase())
+        && parts.all(|subtag| !subtag.is_empty() && subtag.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+impl Config {
+    pub fn stage_dir(&self) -> PathBuf {
+        PathBuf::from(&self.content_project_dir).join(&self.stage_subdir)
+    }
+
+    /// Checks the values serde cannot: ranges, the CT band and language codes.
+    pub fn validate(&self) -> Result<(), WeaveLangError> {
+        if self.stage_subdir.trim().is_empty() {
+            return Err(WeaveLangError::config("stage_subdir must not be empty."));
+        }
+        for (key, code) in [("language_pair.target", &self.language_pair.target), ("language_pair.base", &self.language_pair.base)] {
+            if !is_language_code(code) {
+                return Err(WeaveLangError::config(format!("{} '{}' is not an ISO 639 language code (e.g. \"es\" or \"pt-BR\").", key, code)));
+            }
+        }
+        if self.language_pair.target == self.language_pair.base {
+            return Err(WeaveLangError::config(format!("language_pair.target and language_pair.base are both '{}'.", self.language_pair.target)));
+        }
+        if self.sentences_per_block == Some(0) {
+            return Err(WeaveLangError::config("sentences_per_block must be at least 1."));
+        }
+        if self.exposure_threshold == Some(0) {
+            return Err(WeaveLangError::config("exposure_threshold must be at least 1."));
+        }
+        if self.cognate_exposure_threshold == Some(0) {
+            return Err(WeaveLangError::config("cognate_exposure_threshold must be at least 1."));
+        }
+        let fractions = [
+            ("target_ct_threshold", self.target_ct_threshold),
+            ("min_ct_threshold", self.min_ct_threshold),
+            ("min_sentence_ct", Some(self.min_sentence_ct)),
+        ];
+        for (key, value) in fractions {
+            if let Some(value) = value.filter(|v| !(0.0..=1.0).contains(v)) {
+                return Err(WeaveLangError::config(format!("{} must be between 0 and 1, got {}.", key, value)));
+            }
+        }
+        if let (Some(min), Some(target)) = (self.min_ct_threshold, self.target_ct_threshold) {
+            if min > target {
+                return Err(WeaveLangError::config(format!("min_ct_threshold ({}) must not exceed target_ct_threshold ({}).", min, target)));
+            }
+        }
+        if let Some(cap) = self.max_new_lemmas_per_100_sentences.filter(|cap| *cap < 0.0) {
+            return Err(WeaveLangError::config(format!("max_new_lemmas_per_100_sentences must not be negative, got {}.", cap)));
+        }
+        Ok(())
+    }
+}
+
+pub fn load_config_from_file(file_path: &str) -> Result<Config, WeaveLangError> {
     match fs::read_to_string(file_path) {
         Ok(contents) => match toml::from_str::<Config>(&contents) {
-            Ok(loaded_config) => {
-                let path = PathBuf::from(&loaded_config.content_project_dir);
-                if path.is_dir() {
-                    Ok(loaded_config)
-                } else {
-                    Err(format!(
-                        "Error: content_project_dir specified in {} ('{}') is not a valid directory.",
-                        file_path,
-                        loaded_config.content_project_dir
-                    ))
-                }
-            }
-            Err(e) => Err(format!("Failed to parse {}: {}", file_path, e)),
+            Ok(loaded_config) => check_loaded_config(loaded_config, file_path),
+            Err(e) => Err(WeaveLangError::config(format!("Failed to parse {}: {}", file_path, e)).with_source(e)),
         },
-        Err(e) => Err(format!(
+        Err(e) => Err(WeaveLangError::config(format!(
             "Failed to read {}: {}. Please ensure it exists.",
             file_path, e
-        )),
+        )).with_source(e)),
+    }
+}
+
+fn check_loaded_config(loaded_config: Config, source: &str) -> Result<Config, WeaveLangError> {
+    let path = PathBuf::from(&loaded_config.content_project_dir);
+    if let Err(e) = loaded_config.validate() {
+        Err(e.context(format_args!("Invalid {}", source)))
+    } else if path.is_dir() {
+        Ok(loaded_config)
+    } else {
+        Err(WeaveLangError::config(format!(
+            "Error: content_project_dir specified in {} ('{}') is not a valid directory.",
+            source,
+            loaded_config.content_project_dir
+        )))
+    }
+}
+
+/// Resolves the config in layers, each overriding the one before: the TOML file, WEAVELANG_*
+/// environment variables (see ENV_PREFIX), then `key=value` overrides from the command line
+/// (`--set target_ct_threshold=0.95`, dotted keys for tables). Subcommand flags such as
+/// --target-ct-threshold are applied on top by the caller. The file may be missing when the
+/// other layers provide content_project_dir, so CI and container runs need no config.toml.
+pub fn load_layered_config(file_path: &str, cli_overrides: &[String]) -> Result<Config, WeaveLangError> {
+    load_layered_config_from(file_path, std::env::vars(), cli_overrides)
+}
+
+pub fn load_layered_config_from(
+    file_path: &str,
+    env_vars: impl IntoIterator<Item = (String, String)>,
+    cli_overrides: &[String],
+) -> Result<Config, WeaveLangError> {
+    let mut layers = Vec::new();
+    for (name, raw) in env_vars {
+        if let Some(key) = name.strip_prefix(ENV_PREFIX).filter(|key| !key.is_empty()) {
+            let key_path: Vec<String> = key.to_ascii_lowercase().split("__").map(str::to_string).collect();
+            layers.push((format!("environment variable {}", name), key_path, raw));
+        }
+    }
+    // Sorted so nested keys land after the table they belong to, whatever the env order.
+    layers.sort_by(|a, b| a.1.cmp(&b.1));
+    for assignment in cli_overrides {
+        let (key, raw) = assignment.split_once('=')
+            .ok_or_else(|| WeaveLangError::config(format!("--set '{}' is not of the form key=value.", assignment)))?;
+        let key_path: Vec<String> = key.trim().split('.').map(str::to_string).collect();
+        layers.push((format!("--set {}", key.trim()), key_path, raw.to_string()));
+    }
+
+    let mut table = if Path::new(file_path).exists() || layers.is_empty() {
+        let contents = fs::read_to_string(file_path)
+            .map_err(|e| WeaveLangError::config(format!("Failed to read {}: {}. Please ensure it exists.", file_path, e)).with_source(e))?;
+        toml::from_str::<toml::Table>(&contents)
+            .map_err(|e| WeaveLangError::config(format!("Failed to parse {}: {}", file_path, e)).with_source(e))?
+    } else {
+        toml::Table::new()
+    };
+    for (source, key_path, raw) in &layers {
+        set_override(&mut table, key_path, parse_override_value(raw)).map_err(|e| e.context(source))?;
+    }
+
+    let source = if layers.is_empty() { file_path.to_string() } else { format!("{} with overrides", file_path) };
+    let loaded_config = Config::deserialize(toml::Value::Table(table))
+        .map_err(|e| WeaveLangError::config(format!("Failed to parse {}: {}", source, e)).with_source(e))?;
+    check_loaded_config(loaded_config, &source)
+}
+
+// A TOML literal ("0.95", "true", "[\"L1\", \"L5\"]"), or else the raw text as a string, so
+// paths and names need no quoting.
+fn parse_override_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut parsed| parsed.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+fn set_override(table: &mut toml::Table, key_path: &[String], value: toml::Value) -> Result<(), WeaveLangError> {
+    let (last, parents) = key_path.split_last().ok_or_else(|| WeaveLangError::config("empty key"))?;
+    let mut current = table;
+    for (depth, key) in parents.iter().enumerate() {
+        let entry = current.entry(key.clone()).or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        current = entry.as_table_mut()
+            .ok_or_else(|| WeaveLangError::config(format!("'{}' is not a table", key_path[..=depth].join("."))))?;
+    }
+    if last.is_empty() {
+        return Err(WeaveLangError::config(format!("empty key in '{}'", key_path.join("."))));
     }
+    current.insert(last.clone(), value);
+    Ok(())
 }
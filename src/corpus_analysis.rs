@@ -0,0 +1,248 @@
+//*** START FILE: src/corpus_analysis.rs ***//
+// Corpus statistics computed from the stage files alone, for `weavelang analyze`:
+// lemma frequency distribution, unique lemmas per book, how many lemmas each block
+// introduces and how quickly the most frequent lemmas cover the text.
+
+use crate::config::Config;
+use crate::corpus_generator::{load_book_sequence, prepare_book, sequence_bilingual_lexicon};
+use crate::simulation::{
+    dictionary::{describe_lemma_key, GlobalLemmaDictionary},
+    preprocessor,
+    scheduler::sentence_lemma_ids,
+};
+
+use std::collections::{HashMap, HashSet};
+use crate::error::WeaveLangError;
+use std::path::Path;
+use serde::Serialize;
+
+/// Top-N lemma counts reported on the coverage curve (those below the corpus size).
+const COVERAGE_POINTS: [usize; 8] = [10, 50, 100, 250, 500, 1000, 2000, 5000];
+/// Inclusive occurrence-count ranges for the frequency distribution.
+const FREQUENCY_BANDS: [(u32, u32); 5] = [(1, 1), (2, 4), (5, 9), (10, 49), (50, u32::MAX)];
+
+/// Statistics for one book, in sequence order. "New" lemmas are those no earlier book in
+/// the sequence contains.
+#[derive(Serialize, Debug, Clone)]
+pub struct BookAnalysis {
+    pub book_stem: String,
+    pub sentences: usize,
+    pub lemma_tokens: usize,      // Lemma occurrences the book can expose
+    pub unique_lemmas: usize,
+    pub new_lemmas: usize,
+    pub blocks: usize,
+    pub max_new_lemmas_per_block: usize,
+    pub prior_coverage: f64,      // Share of lemma tokens whose lemma an earlier book introduced
+    pub validation_issues: usize,
+}
+
+impl BookAnalysis {
+    pub fn new_lemmas_per_block(&self) -> f64 {
+        if self.blocks == 0 { 0.0 } else { self.new_lemmas as f64 / self.blocks as f64 }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LemmaCount {
+    pub lemma: String,
+    pub count: u32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FrequencyBand {
+    pub min_count: u32,
+    pub max_count: Option<u32>, // None for the open-ended top band
+    pub lemmas: usize,
+    pub tokens: u64,
+}
+
+impl FrequencyBand {
+    pub fn label(&self) -> String {
+        match self.max_count {
+            Some(max) if max == self.min_count => format!("{}", max),
+            Some(max) => format!("{}-{}", self.min_count, max),
+            None => format!("{}+", self.min_count),
+        }
+    }
+}
+
+/// Share of all lemma tokens covered by the `top_lemmas` most frequent lemmas.
+#[derive(Serialize, Debug, Clone)]
+pub struct CoveragePoint {
+    pub top_lemmas: usize,
+    pub coverage: f64,
+}
+
+/// Result of analyze_corpus. Repeated books in the sequence are analysed once; books that
+/// could not be read or parsed are listed in `skipped` with the reason.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct CorpusAnalysis {
+    pub sentences_per_block: usize,
+    pub books: Vec<BookAnalysis>,
+    pub skipped: Vec<(String, String)>, // (book stem, error)
+    pub repeated_instances: usize,
+    pub total_lemma_tokens: u64,
+    pub unique_lemmas: usize,
+    pub top_lemmas: Vec<LemmaCount>,
+    pub frequency_bands: Vec<FrequencyBand>,
+    pub coverage: Vec<CoveragePoint>,
+}
+
+/// Loads every book of the sequence and gathers its lemma statistics. Only DIGLOT_MAP
+/// entries at or above `min_diglot_confidence` count, as in the simulation.
+pub fn analyze_corpus(
+    project_config: &Config,
+    sequence_path: &Path,
+    sentences_per_block: usize,
+    min_diglot_confidence: f32,
+    top_n: usize,
+) -> Result<CorpusAnalysis, WeaveLangError> {
+    let sentences_per_block = sentences_per_block.max(1);
+    let mut analysis = CorpusAnalysis { sentences_per_block, ..Default::default() };
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let mut counts: HashMap<u32, u32> = HashMap::new();
+    let mut first_book: HashMap<u32, usize> = HashMap::new(); // Lemma -> index of the book introducing it
+    let mut seen_stems: HashSet<String> = HashSet::new();
+
+    let book_stems = load_book_sequence(sequence_path)?;
+    let lexicon = sequence_bilingual_lexicon(project_config, &book_stems)?;
+    for book_stem in book_stems {
+        if !seen_stems.insert(book_stem.clone()) {
+            analysis.repeated_instances += 1;
+            continue;
+        }
+        let book = match prepare_book(project_config, &book_stem, &lexicon) {
+            Ok(book) => book,
+            Err(e) => {
+                analysis.skipped.push((book_stem, e.to_string()));
+                continue;
+            }
+        };
+        let book_idx = analysis.books.len();
+        let validation_issues = book.validation_issues.len();
+        let numerical_chapter = preprocessor::merge_into_dictionary(book.numerical_chapter, &book.local_dictionary, &mut dictionary);
+
+        let mut book_lemmas: HashSet<u32> = HashSet::new();
+        let (mut lemma_tokens, mut prior_tokens, mut new_lemmas, mut max_new_lemmas_per_block) = (0usize, 0usize, 0usize, 0usize);
+        let blocks = numerical_chapter.sentences_numerical.chunks(sentences_per_block);
+        let block_count = blocks.len();
+        for block in blocks {
+            let mut new_in_block = 0;
+            for sentence in block {
+                for lemma_id in sentence_lemma_ids(sentence, min_diglot_confidence) {
+                    lemma_tokens += 1;
+                    *counts.entry(lemma_id).or_insert(0) += 1;
+                    book_lemmas.insert(lemma_id);
+                    match first_book.get(&lemma_id) {
+                        Some(&idx) if idx < book_idx => prior_tokens += 1,
+                        Some(_) => {}
+                        None => {
+                            first_book.insert(lemma_id, book_idx);
+                            new_in_block += 1;
+                        }
+                    }
+                }
+            }
+            new_lemmas += new_in_block;
+            max_new_lemmas_per_block = max_new_lemmas_per_block.max(new_in_block);
+        }
+
+        analysis.books.push(BookAnalysis {
+            book_stem,
+            sentences: numerical_chapter.sentences_numerical.len(),
+            lemma_tokens,
+            unique_lemmas: book_lemmas.len(),
+            new_lemmas,
+            blocks: block_count,
+            max_new_lemmas_per_block,
+            prior_coverage: if lemma_tokens == 0 { 0.0 } else { prior_tokens as f64 / lemma_tokens as f64 },
+            validation_issues,
+        });
+    }
+
+    let mut ranked: Vec<(u32, u32)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    analysis.unique_lemmas = ranked.len();
+    analysis.total_lemma_tokens = ranked.iter().map(|&(_, count)| count as u64).sum();
+
+    analysis.top_lemmas = ranked.iter().take(top_n)
+        .map(|&(lemma_id, count)| LemmaCount {
+            lemma: dictionary.get_str(lemma_id).map_or_else(|| format!("#{}", lemma_id), |key| describe_lemma_key(key)),
+            count,
+        })
+        .collect();
+
+    analysis.frequency_bands = FREQUENCY_BANDS.iter()
+        .map(|&(min_count, max_count)| {
+            let in_band = ranked.iter().filter(|&&(_, count)| count >= min_count && count <= max_count);
+            FrequencyBand {
+                min_count,
+                max_count: (max_count != u32::MAX).then_some(max_count),
+                lemmas: in_band.clone().count(),
+                tokens: in_band.map(|&(_, count)| count as u64).sum(),
+            }
+        })
+        .collect();
+
+    let mut covered: u64 = 0;
+    let mut points = COVERAGE_POINTS.iter().copied().filter(|&n| n < ranked.len()).peekable();
+    for (rank, &(_, count)) in ranked.iter().enumerate() {
+        covered += count as u64;
+        if points.peek() == Some(&(rank + 1)) {
+            points.next();
+            analysis.coverage.push(CoveragePoint { top_lemmas: rank + 1, coverage: covered as f64 / analysis.total_lemma_tokens as f64 });
+        }
+    }
+    if !ranked.is_empty() {
+        analysis.coverage.push(CoveragePoint { top_lemmas: ranked.len(), coverage: 1.0 });
+    }
+    Ok(analysis)
+}
+
+impl CorpusAnalysis {
+    /// Plain-text report printed by `weavelang analyze`.
+    pub fn report(&self) -> String {
+        let stem_width = self.books.iter().map(|b| b.book_stem.len())
+            .chain(self.skipped.iter().map(|(stem, _)| stem.len()))
+            .chain(std::iter::once("Book".len()))
+            .max()
+            .unwrap_or(0);
+        let mut report = format!("Books ({} sentences per block)\n", self.sentences_per_block);
+        report.push_str(&format!("{:<stem_width$}  {:>9}  {:>7}  {:>6}  {:>5}  {:>6}  {:>9}  {:>9}  {:>6}\n",
+                                 "Book", "Sentences", "Tokens", "Lemmas", "New", "Blocks", "New/block", "Max/block", "Prior"));
+        for book in &self.books {
+            report.push_str(&format!("{:<stem_width$}  {:>9}  {:>7}  {:>6}  {:>5}  {:>6}  {:>9.1}  {:>9}  {:>5.1}%{}\n",
+                                     book.book_stem, book.sentences, book.lemma_tokens, book.unique_lemmas, book.new_lemmas,
+                                     book.blocks, book.new_lemmas_per_block(), book.max_new_lemmas_per_block, book.prior_coverage * 100.0,
+                                     if book.validation_issues > 0 { format!("  ({} validation issue(s))", book.validation_issues) } else { String::new() }));
+        }
+        for (book_stem, error) in &self.skipped {
+            report.push_str(&format!("{:<stem_width$}  skipped: {}\n", book_stem, error));
+        }
+        report.push_str(&format!("{} book(s){}, {} lemma token(s), {} unique lemma(s).\n",
+                                 self.books.len(),
+                                 if self.repeated_instances > 0 { format!(" ({} repeated instance(s) not counted again)", self.repeated_instances) } else { String::new() },
+                                 self.total_lemma_tokens, self.unique_lemmas));
+
+        report.push_str("\nFrequency distribution\n");
+        report.push_str(&format!("{:>11}  {:>6}  {:>7}\n", "Occurrences", "Lemmas", "Tokens"));
+        for band in &self.frequency_bands {
+            report.push_str(&format!("{:>11}  {:>6}  {:>7}\n", band.label(), band.lemmas, band.tokens));
+        }
+
+        report.push_str("\nCoverage\n");
+        for point in &self.coverage {
+            report.push_str(&format!("  top {:>5} lemma(s): {:>5.1}% of tokens\n", point.top_lemmas, point.coverage * 100.0));
+        }
+
+        if !self.top_lemmas.is_empty() {
+            report.push_str(&format!("\nTop {} lemma(s)\n", self.top_lemmas.len()));
+            for (rank, lemma) in self.top_lemmas.iter().enumerate() {
+                report.push_str(&format!("  {:>3}. {} ({})\n", rank + 1, lemma.lemma, lemma.count));
+            }
+        }
+        report
+    }
+}
+
+//*** END FILE: src/corpus_analysis.rs ***//
@@ -1,22 +1,148 @@
 //*** START FILE: src/corpus_generator.rs ***//
 use crate::config::Config; // Assuming your config struct is named Config
-use crate::profile_io::{load_profile_snapshot, save_profile_snapshot};
-use crate::parsing::llm_parser; // Assuming this is how you access parse_llm_text_to_chapter
+use crate::profile_io::{import_known_lemmas, load_profile_snapshot, KnownLemmaImport, save_profile_delta, save_profile_snapshot_as, SnapshotFormat};
+use crate::exposure_thresholds::{LearnerVariability, ThresholdTable};
+use crate::determinism::reproducible_timestamp;
+use crate::progress::{ConsoleProgress, ProgressReporter, ProgressTracker};
+use crate::lemma_timeline::{LemmaTimeline, TimelinePoint};
+use crate::qa_report::{save_qa_report, QaReportBuilder};
+use crate::bilingual_lexicon::BilingualLexicon;
+use crate::lexicon::{self, Lexicon, LEXICON_CACHE_FILE_NAME};
+use crate::chapter_cache::{self, ChapterCache};
+use crate::parsing::chapter_loader::{self, ChapterFormat};
+use crate::parsing::validation::{self, ValidationIssue};
 use crate::simulation::{
+    core_algo::{CtMetricKind, L4Strategy, SimulationBlockResult},
     dictionary::GlobalLemmaDictionary,
-    numerical_types::{NumericalLearnerProfile, NumericalProcessedSentence},
+    numerical_types::{DecayParams, NumericalChapter, NumericalLearnerProfile},
+    block::{check_chapter_pair, BlockBoundaries},
+    orchestrator::{extend_introduction_history, run_chapters_observed, BlockInfo, ChapterInput, OrchestratorObserver, OrchestratorParams},
     preprocessor,
-    core_algo,
-    text_generator,
+    profile_view::LemmaTransition,
+    scheduler::{CorpusFrequency, RemainingCorpusFrequency, SchedulerParams},
+    level_policy::{LevelPolicy, SentenceLevel},
+    text_generator::{GeneratedTextBlock, SentenceLevelRecord},
+    trace::BlockTrace,
+    exporters::{anki::{self, AnkiCard}, epub::{self, EpubBook, EpubChapter, EpubChapterMode}, html, manifest::{self, ManifestBuilder}, ssml::{self, SsmlOptions}, parallel::{self, ParallelRow, ParallelTextFormat}, subtitles::{self, SubtitleFormat, SubtitleTiming}},
 };
-use crate::profile::LemmaState; // For checking new words for activation list
 
-use std::collections::HashMap;
+use crate::tokenizer;
+use crate::types::llm_data::{join_chapters, ChapterSpan, ProcessedChapter, ProcessedSentence};
+
+use std::collections::{HashMap, VecDeque};
 use std::fs;
 use std::fs::File;
-use std::path::PathBuf;
-use std::error::Error;
+use std::path::{Path, PathBuf};
+use crate::error::WeaveLangError;
 use std::io::BufRead; // For reading sequence file line by line
+use std::sync::{mpsc, Arc};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, info_span, warn};
+
+// Groups a book instance's rendered blocks into EPUB chapters.
+fn epub_chapters(blocks: &[RenderedBlockHtml], mode: EpubChapterMode, book_instance_id: &str) -> Vec<EpubChapter> {
+    match mode {
+        EpubChapterMode::Block => blocks.iter()
+            .map(|b| EpubChapter { title: format!("Block {}", b.block_in_book), body_html: b.html.clone() })
+            .collect(),
+        EpubChapterMode::Source => {
+            // One EPUB chapter per pass through a source chapter, titled after the source
+            // chapter (or the book instance), with the pass number when it was read repeatedly.
+            let mut chapters: Vec<(&str, EpubChapter)> = Vec::new();
+            for block in blocks {
+                if chapters.len() < block.pass_in_book {
+                    let source_title = block.chapter_title.as_deref().unwrap_or(book_instance_id);
+                    chapters.push((source_title, EpubChapter { title: String::new(), body_html: String::new() }));
+                }
+                if let Some((_, chapter)) = chapters.last_mut() {
+                    chapter.body_html.push_str(&block.html);
+                }
+            }
+            let mut passes_by_title: HashMap<&str, usize> = HashMap::new();
+            for (source_title, _) in &chapters {
+                *passes_by_title.entry(source_title).or_insert(0) += 1;
+            }
+            let mut passes_seen: HashMap<&str, usize> = HashMap::new();
+            chapters.into_iter()
+                .map(|(source_title, mut chapter)| {
+                    let pass = passes_seen.entry(source_title).or_insert(0);
+                    *pass += 1;
+                    chapter.title = if passes_by_title[source_title] > 1 {
+                        format!("{} (pass {})", source_title, pass)
+                    } else {
+                        source_title.to_string()
+                    };
+                    chapter
+                })
+                .collect()
+        }
+    }
+}
+
+/// Run manifest written to the profiles directory after every finished book instance.
+pub const RUN_STATE_FILE_NAME: &str = "run_state.json";
+
+/// How many times each book instance is read before moving to the next one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PassesPerBook {
+    /// Read the book exactly N times, wrapping around like the GUI does.
+    Fixed(usize),
+    /// Keep re-reading until a pass no longer grows the Known/Active counts
+    /// (saturation), capped at `max_auto_passes`.
+    Auto,
+}
+
+impl Default for PassesPerBook {
+    fn default() -> Self {
+        PassesPerBook::Fixed(1)
+    }
+}
+
+/// Format of the per-book TTS input file.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TtsOutputFormat {
+    /// Plain text (<stem>.txt).
+    #[default]
+    Text,
+    /// SSML with target-language stretches marked for the TTS engine (<stem>.ssml).
+    Ssml,
+}
+
+impl TtsOutputFormat {
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            TtsOutputFormat::Text => "txt",
+            TtsOutputFormat::Ssml => "ssml",
+        }
+    }
+}
+
+impl std::str::FromStr for TtsOutputFormat {
+    type Err = WeaveLangError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "text" | "txt" => Ok(TtsOutputFormat::Text),
+            "ssml" => Ok(TtsOutputFormat::Ssml),
+            _ => Err(WeaveLangError::config(format!("Invalid output format '{}': expected 'text' or 'ssml'.", s))),
+        }
+    }
+}
+
+impl std::str::FromStr for PassesPerBook {
+    type Err = WeaveLangError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("auto") {
+            return Ok(PassesPerBook::Auto);
+        }
+        match s.trim().parse::<usize>() {
+            Ok(n) if n >= 1 => Ok(PassesPerBook::Fixed(n)),
+            _ => Err(WeaveLangError::config(format!("Invalid passes-per-book value '{}': expected a positive integer or 'auto'.", s))),
+        }
+    }
+}
 
 // Define a struct for CLI arguments related to generation,
 // makes function signatures cleaner.
@@ -28,32 +154,874 @@ pub struct GenerationArgs {
     pub profiles_dir: PathBuf,
     pub start_profile_path: Option<PathBuf>,
     pub sentences_per_block: usize,
+    pub block_boundaries: BlockBoundaries, // Fixed-size blocks or blocks ending at paragraph ends
     pub max_regen_attempts_per_block: u32,
     pub target_ct_threshold: f32,
+    pub min_ct_threshold: f32, // Floor of the CT band; 0 = none
     pub max_words_to_activate_per_regen: usize,
+    pub min_diglot_confidence: f32,
+    pub decay: DecayParams,
+    pub ct_metric: CtMetricKind,
+    pub l4_strategy: L4Strategy,
+    pub l4_match_plurals: bool,
+    pub scheduler: SchedulerParams,
+    pub max_new_lemmas_per_100_sentences: Option<f32>, // Introduction cap carried across blocks and books
+    pub passes_per_book: PassesPerBook,
+    pub max_auto_passes: usize, // Upper bound for PassesPerBook::Auto
+    pub snapshot_every_blocks: Option<usize>, // Intra-book delta snapshots relative to the _in.profile
+    pub parallel_lookahead: usize, // Books prepared ahead on worker threads; 0 = strictly sequential
+    pub resume: bool, // Continue from run_state.json in profiles_dir instead of starting over
+    pub snapshot_format: SnapshotFormat, // Encoding of the _in/_out profile snapshots
+    pub level_tags: bool,    // Prefix each sentence of the TTS text with [L1]..[L5]
+    pub level_policy: LevelPolicy, // Enabled levels in the order they are tried
+    pub level_sidecar: bool, // Write <tts stem>.levels.json next to each TTS file
+    pub audio_manifest: bool, // Write <tts stem>.manifest.json (sentence order, IDs, levels, lemma IDs)
+    pub subtitle_format: Option<SubtitleFormat>, // Write <tts stem>.srt/.vtt with estimated timings
+    pub subtitle_timing: SubtitleTiming,
+    pub parallel_text_format: Option<ParallelTextFormat>, // Write <tts stem>.parallel.tsv/.html (woven | SimE)
+    pub trace: bool,         // Write <book instance>.trace.jsonl next to the profiles
+    pub html_output_dir: Option<PathBuf>, // Also write each book instance as <tts stem>.html with hover glosses
+    pub epub_output_dir: Option<PathBuf>, // Also write each book instance as <tts stem>.epub
+    pub epub_chapter_mode: EpubChapterMode,
+    pub output_format: TtsOutputFormat,
+    pub ssml_base_voice: Option<String>,   // <voice name> for the whole SSML document
+    pub ssml_target_voice: Option<String>, // <voice name> for target-language stretches instead of <lang>
+    pub exposure_thresholds: Option<PathBuf>, // Threshold table (CSV/TOML); None = DEFAULT_EXPOSURE_THRESHOLD for all
+    pub default_exposure_threshold: Option<u32>, // Replaces DEFAULT_EXPOSURE_THRESHOLD for lemmas the table leaves out
+    pub cognate_exposure_threshold: Option<u32>, // Cap for detected cognates; None = no cognate detection
+    pub learner_variability: Option<LearnerVariability>, // Randomize per-lemma thresholds as one simulated learner
+    pub anki_output_dir: Option<PathBuf>, // Write <tts stem>.anki.tsv with the lemmas each book instance activated
+    pub seed_dictionary: Option<PathBuf>, // Dictionary TSV whose lemmas get IDs before the first book is read
+    pub known_words: Option<PathBuf>, // Word list (plain text/CSV) marked Known in the starting profile
+    pub seed: Option<u64>, // Scheduler tie-break seed; also pins timestamps written into outputs
+    pub dry_run: bool, // Parse, convert and report on every book without simulating or writing anything
+    pub track_forms: bool, // Count exposures per target-language form and report form coverage
+    pub chapter_cache_dir: Option<PathBuf>, // Reuse parsed and converted books stored here (see chapter_cache); None = parse every run
     // Add other relevant params like config_path if not passed directly
 }
 
+// Logs per-block progress, records the lemma timeline and collects the woven text
+// for one book instance.
+struct CliBlockObserver<'a> {
+    book_instance_unique_id: &'a str,
+    dictionary: &'a GlobalLemmaDictionary,
+    lemma_timeline: &'a mut LemmaTimeline,
+    run_block_counter: &'a mut usize,
+    blocks_in_book: usize, // Counts across passes, unlike BlockInfo::block_index in auto mode
+    ct_sum: f32,
+    ct_metric_name: &'static str,
+    output_text_segments: Vec<String>,
+    sentence_levels: Vec<LevelSidecarEntry>,
+    audio_manifest: Option<ManifestBuilder>, // Set when the run writes audio manifests
+    subtitle_texts: Option<Vec<String>>, // Sentences and chapter headings in TTS order, when writing subtitles
+    parallel_rows: Option<Vec<ParallelRow>>, // Set when the run writes parallel text
+    qa_report: Option<QaReportBuilder>, // Taken when the book instance is finished
+    block_traces: Option<Vec<BlockTrace>>, // Set when the run writes traces
+    html_target_language: Option<&'a str>, // Set when HTML or EPUB export is enabled
+    html_blocks: Vec<RenderedBlockHtml>,
+    gloss_lexicon: Option<&'a Lexicon>, // Glosses lemmas no DIGLOT_MAP glosses in HTML and Anki output
+    ssml_options: Option<SsmlOptions>, // Set when the TTS output is SSML
+    ssml_blocks: Vec<String>,
+    collect_anki_cards: bool,
+    newly_activated_lemma_ids: Vec<u32>, // Of the current block, waiting for its sentences
+    anki_cards: Vec<AnkiCard>,
+    passes_in_book: usize, // Passes started so far, across orchestrator runs
+    last_pass_number: usize,
+    block_snapshots: Option<BlockSnapshotSettings<'a>>,
+    progress: &'a mut ProgressTracker,
+    progress_reporter: &'a mut dyn ProgressReporter,
+}
+
+// One sentence of the per-book level sidecar (<tts stem>.levels.json).
+#[derive(Serialize, Debug, Clone)]
+struct LevelSidecarEntry {
+    block_in_book: usize,
+    sentence_id: String,
+    level: SentenceLevel,
+}
+
+// One block rendered by exporters::html, with the pass and chapter it belongs to.
+struct RenderedBlockHtml {
+    block_in_book: usize,
+    pass_in_book: usize,
+    chapter_title: Option<String>,
+    html: String,
+}
+
+// Where and how often intra-book delta snapshots are written.
+struct BlockSnapshotSettings<'a> {
+    every_n_blocks: usize,
+    profiles_dir: &'a Path,
+    base_snapshot_path: &'a Path,
+    base_profile: &'a NumericalLearnerProfile,
+    base_dictionary_size: usize,
+}
+
+impl OrchestratorObserver for CliBlockObserver<'_> {
+    fn on_block_start(&mut self, block: &BlockInfo, _profile: &NumericalLearnerProfile) {
+        *self.run_block_counter += 1;
+        self.blocks_in_book += 1;
+        self.newly_activated_lemma_ids.clear();
+        if block.block_index == 1 || block.pass_number() != self.last_pass_number {
+            self.passes_in_book += 1;
+            self.last_pass_number = block.pass_number();
+        }
+        let last_sentence_idx = (block.first_sentence_position + block.sentence_count - 1) % block.chapter_sentence_count;
+        info!(block_in_book = self.blocks_in_book, first_sentence = block.first_chapter_sentence_idx(),
+              last_sentence = last_sentence_idx, "Processing block");
+    }
+
+    fn on_lemmas_decayed(&mut self, _block: &BlockInfo, lemma_ids: &[u32]) {
+        info!(decayed = lemma_ids.len(), "Known lemma(s) decayed back to Active before the next block");
+    }
+
+    fn on_block_simulated(&mut self, _block: &BlockInfo, profile_after: &NumericalLearnerProfile, result: &SimulationBlockResult, transitions: &[LemmaTransition]) {
+        info!(
+            block_in_book = self.blocks_in_book,
+            ct_metric = result.ct_metric_name,
+            ct = result.final_ct_for_block,
+            known = result.known_lemmas_in_block,
+            total_target = result.total_target_lemmas_in_block,
+            introduced = result.introduced_lemma_ids.len(),
+            regen_attempts = result.simulation_log_entries.iter().filter(|s| s.contains("Regen Attempt:")).count(),
+            levels = %result.level_counts.map(|count| count.to_string()).join("/"),
+            "Block simulated"
+        );
+        self.ct_sum += result.final_ct_for_block;
+        self.ct_metric_name = result.ct_metric_name;
+        if let Some(qa_report) = &mut self.qa_report {
+            qa_report.add_block_ct(self.blocks_in_book, result.final_ct_for_block, result.ct_metric_name);
+        }
+        let progress = self.progress.block_done(result.final_ct_for_block, result.ct_metric_name);
+        self.progress_reporter.on_block_done(&progress);
+        self.lemma_timeline.record_block(
+            transitions,
+            self.dictionary,
+            &TimelinePoint {
+                book_instance_id: self.book_instance_unique_id.to_string(),
+                block_in_book: self.blocks_in_book,
+                run_block_index: *self.run_block_counter,
+            },
+        );
+
+        if self.collect_anki_cards {
+            self.newly_activated_lemma_ids = anki::newly_activated_lemma_ids(transitions);
+        }
+        if let Some(block_traces) = &mut self.block_traces {
+            block_traces.push(BlockTrace {
+                book_instance_id: self.book_instance_unique_id.to_string(),
+                block_in_book: self.blocks_in_book,
+                run_block_index: *self.run_block_counter,
+                events: result.trace.clone(),
+            });
+        }
+
+        if let Some(snapshots) = &self.block_snapshots {
+            if self.blocks_in_book.is_multiple_of(snapshots.every_n_blocks) {
+                let delta_path = snapshots.profiles_dir.join(format!(
+                    "{}_blk{:04}.delta.json", self.book_instance_unique_id, self.blocks_in_book
+                ));
+                match save_profile_delta(
+                    snapshots.base_profile,
+                    snapshots.base_dictionary_size,
+                    snapshots.base_snapshot_path,
+                    profile_after,
+                    self.dictionary,
+                    &delta_path,
+                ) {
+                    Ok(_) => info!("Saved block snapshot to: {}", delta_path.display()),
+                    Err(e) => error!("Failed to save block snapshot {}: {}", delta_path.display(), e),
+                }
+            }
+        }
+    }
+
+    fn on_block_text(&mut self, _block: &BlockInfo, text: &str) {
+        if !text.trim().is_empty() {
+            self.output_text_segments.push(text.to_string());
+        }
+    }
+
+    fn on_block_levels(&mut self, _block: &BlockInfo, levels: &[SentenceLevelRecord]) {
+        self.sentence_levels.extend(levels.iter().map(|record| LevelSidecarEntry {
+            block_in_book: self.blocks_in_book,
+            sentence_id: record.sentence_id.clone(),
+            level: record.level,
+        }));
+    }
+
+    fn on_block_rendered(
+        &mut self,
+        block: &BlockInfo,
+        sentences: &[&ProcessedSentence],
+        generated: &GeneratedTextBlock,
+        profile_for_text: &NumericalLearnerProfile,
+    ) {
+        for lemma_id in std::mem::take(&mut self.newly_activated_lemma_ids) {
+            if let Some(card) = anki::build_card(lemma_id, sentences, self.dictionary, self.gloss_lexicon) {
+                self.anki_cards.push(card);
+            }
+        }
+        if let Some(options) = &self.ssml_options {
+            self.ssml_blocks.push(ssml::render_block_ssml(generated, options));
+        }
+        if let Some(audio_manifest) = &mut self.audio_manifest {
+            audio_manifest.add_block(sentences, generated, self.dictionary, profile_for_text, self.blocks_in_book);
+        }
+        if let Some(qa_report) = &mut self.qa_report {
+            qa_report.add_block(sentences, generated, self.dictionary, profile_for_text);
+        }
+        if let Some(parallel_rows) = &mut self.parallel_rows {
+            parallel_rows.extend(parallel::block_rows(sentences, generated, self.blocks_in_book));
+        }
+        if let Some(subtitle_texts) = &mut self.subtitle_texts {
+            for (heading, text) in generated.section_headings.iter().zip(&generated.sentence_texts) {
+                subtitle_texts.extend(heading.iter().cloned());
+                subtitle_texts.push(text.clone());
+            }
+        }
+        if let Some(target_language) = self.html_target_language {
+            self.html_blocks.push(RenderedBlockHtml {
+                block_in_book: self.blocks_in_book,
+                pass_in_book: self.passes_in_book,
+                chapter_title: block.chapter_title.clone(),
+                html: html::render_block_html(
+                    sentences, generated, self.dictionary, profile_for_text, target_language, self.blocks_in_book, self.gloss_lexicon,
+                ),
+            });
+        }
+    }
+
+    fn on_block_error(&mut self, _block: &BlockInfo, error: &str) {
+        error!(block_in_book = self.blocks_in_book, "{}. Trying to continue.", error);
+    }
+}
+
+/// Outcome of one book instance of a generation run. Output paths are only set for
+/// files that were written successfully.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct BookReport {
+    pub book_instance_id: String,
+    pub book_stem: String,
+    pub start_level: usize, // Known words / 100, as in the TTS file name
+    pub end_level: usize,
+    pub known_before: usize,
+    pub known_after: usize,
+    pub active_after: usize, // Active but not yet Known at the end of the book
+    pub sentences: usize,
+    pub blocks: usize,
+    pub average_ct: f32, // Mean of the block CTs; 0 when no block ran
+    pub ct_metric_name: String,
+    pub words_activated: usize, // Lemmas that became Known or Active during the book
+    pub level_counts: [usize; 5], // Sentences rendered at L1..L5, over all passes
+    pub form_coverage: Option<(usize, usize)>, // (forms read, forms of Known/Active lemmas) when tracking forms
+    pub tts_path: Option<PathBuf>,
+    pub levels_path: Option<PathBuf>,
+    pub manifest_path: Option<PathBuf>,
+    pub subtitles_path: Option<PathBuf>,
+    pub parallel_text_path: Option<PathBuf>,
+    pub qa_report_path: Option<PathBuf>,
+    pub trace_path: Option<PathBuf>,
+    pub html_path: Option<PathBuf>,
+    pub epub_path: Option<PathBuf>,
+    pub anki_path: Option<PathBuf>,
+    pub in_profile_path: Option<PathBuf>,
+    pub out_profile_path: Option<PathBuf>,
+}
+
+/// What run_corpus_generation did, book by book. Books skipped because they could not be
+/// read or parsed are listed in `skipped` with the reason. Empty for dry runs.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct CorpusGenerationReport {
+    pub books: Vec<BookReport>,
+    pub skipped: Vec<(String, String)>, // (book instance id, error)
+    pub resumed_instances: usize,       // Finished by an earlier run and not redone
+}
+
+impl CorpusGenerationReport {
+    pub fn total_blocks(&self) -> usize {
+        self.books.iter().map(|b| b.blocks).sum()
+    }
+
+    pub fn total_words_activated(&self) -> usize {
+        self.books.iter().map(|b| b.words_activated).sum()
+    }
+
+    /// Plain-text table with one row per book, printed by the CLI at the end of a run.
+    pub fn summary_table(&self) -> String {
+        let id_width = self.books.iter().map(|b| b.book_instance_id.len())
+            .chain(self.skipped.iter().map(|(id, _)| id.len()))
+            .chain(std::iter::once("Book instance".len()))
+            .max()
+            .unwrap_or(0);
+        let show_forms = self.books.iter().any(|b| b.form_coverage.is_some());
+        let mut table = format!("{:<id_width$}  {:>5}  {:>6}  {:>7}  {:>6}  {:>9}{}\n",
+                                "Book instance", "Level", "Blocks", "Avg CT", "Known", "Activated",
+                                if show_forms { format!("  {:>13}", "Forms read") } else { String::new() });
+        for book in &self.books {
+            let forms_column = match book.form_coverage {
+                Some((seen, total)) if show_forms => format!("  {:>13}", format!("{}/{}", seen, total)),
+                _ => String::new(),
+            };
+            table.push_str(&format!("{:<id_width$}  {:>5}  {:>6}  {:>6.2}%  {:>6}  {:>9}{}\n",
+                                    book.book_instance_id, format!("{}-{}", book.start_level, book.end_level),
+                                    book.blocks, book.average_ct * 100.0, book.known_after, book.words_activated, forms_column));
+        }
+        for (book_instance_id, error) in &self.skipped {
+            table.push_str(&format!("{:<id_width$}  skipped: {}\n", book_instance_id, error));
+        }
+        table.push_str(&format!("{} book instance(s), {} block(s), {} word(s) activated{}.",
+                                self.books.len(), self.total_blocks(), self.total_words_activated(),
+                                if self.resumed_instances > 0 { format!(" ({} finished earlier)", self.resumed_instances) } else { String::new() }));
+        table
+    }
+}
+
+/// Where a generation run stands, so an interrupted run can be resumed with `--resume`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RunState {
+    pub sequence_path: String,
+    pub sequence: Vec<String>,          // Book stems, as read from the sequence file
+    pub next_sequence_index: usize,     // First book instance that has not finished yet
+    pub last_out_profile_path: String,  // Profile to continue from
+    pub run_block_counter: usize,
+    pub completed_instances: Vec<String>,
+    #[serde(default)]
+    pub seed: Option<u64>, // --seed of the run, so a resume can warn about a different one
+    #[serde(default)]
+    pub recent_introductions: Vec<(usize, usize)>, // Window of the introduction cap at the resume point
+}
+
+impl RunState {
+    pub fn is_complete(&self) -> bool {
+        self.next_sequence_index >= self.sequence.len()
+    }
+}
+
+pub fn save_run_state(state: &RunState, profiles_dir: &Path) -> Result<(), WeaveLangError> {
+    let state_path = profiles_dir.join(RUN_STATE_FILE_NAME);
+    let json = serde_json::to_string_pretty(state)
+        .map_err(|e| WeaveLangError::profile_io(format!("Failed to serialize run state: {}", e)).with_source(e))?;
+    // Write then rename, so a kill mid-write never leaves a truncated manifest.
+    let tmp_path = state_path.with_extension("json.tmp");
+    fs::write(&tmp_path, json).map_err(|e| WeaveLangError::profile_io(format!("Failed to write run state {:?}: {}", tmp_path, e)).with_source(e))?;
+    fs::rename(&tmp_path, &state_path).map_err(|e| WeaveLangError::profile_io(format!("Failed to replace run state {:?}: {}", state_path, e)).with_source(e))?;
+    Ok(())
+}
+
+pub fn load_run_state(profiles_dir: &Path) -> Result<RunState, WeaveLangError> {
+    let state_path = profiles_dir.join(RUN_STATE_FILE_NAME);
+    let contents = fs::read_to_string(&state_path)
+        .map_err(|e| WeaveLangError::profile_io(format!("Failed to read run state {:?}: {}", state_path, e)).with_source(e))?;
+    let state = serde_json::from_str(&contents)
+        .map_err(|e| WeaveLangError::profile_io(format!("Failed to parse run state {:?}: {}", state_path, e)).with_source(e))?;
+    Ok(state)
+}
+
+// A book read, parsed, validated and converted to numerical form against its own
+// dictionary, ready to be merged into the run's global dictionary.
+pub struct PreparedBook {
+    pub llm_file_path: PathBuf,
+    pub string_chapter: ProcessedChapter, // Every chapter of the file, joined
+    pub chapter_spans: Vec<ChapterSpan>,  // One per chapter, in order
+    pub validation_issues: Vec<ValidationIssue>,
+    pub numerical_chapter: NumericalChapter,
+    pub local_dictionary: GlobalLemmaDictionary,
+}
+
+/// Plain-text books take their DIGLOT_MAP from `lexicon` (see sequence_bilingual_lexicon);
+/// annotated ones get its lexicon-file entries for words their DIGLOT_MAP misses.
+pub fn prepare_book(project_config: &Config, book_stem: &str, lexicon: &BilingualLexicon) -> Result<PreparedBook, WeaveLangError> {
+    prepare_book_cached(project_config, book_stem, lexicon, None)
+}
+
+/// prepare_book, reusing the parsed and converted book stored in `cache` while the stage
+/// file, language pair and lexicon are unchanged, and storing it there otherwise. A cache
+/// that cannot be written only costs a warning.
+pub fn prepare_book_cached(
+    project_config: &Config,
+    book_stem: &str,
+    lexicon: &BilingualLexicon,
+    cache: Option<&ChapterCache>,
+) -> Result<PreparedBook, WeaveLangError> {
+    let llm_file_path = stage_file_path(project_config, book_stem);
+    let llm_file_name = llm_file_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let content = fs::read_to_string(&llm_file_path)
+        .map_err(|e| WeaveLangError::parse(format!("Failed to read {}: {}", llm_file_path.display(), e)).with_source(e))?;
+    let cache_key = cache.map(|_| chapter_cache::cache_key(&llm_file_name, &content, &project_config.language_pair, lexicon));
+    if let (Some(cache), Some(key)) = (cache, &cache_key) {
+        if let Some(cached) = cache.load(book_stem, key) {
+            let validation_issues = validation::validate_chapter(&cached.string_chapter);
+            return Ok(PreparedBook {
+                llm_file_path,
+                string_chapter: cached.string_chapter,
+                chapter_spans: cached.chapter_spans,
+                validation_issues,
+                numerical_chapter: cached.numerical_chapter,
+                local_dictionary: cached.local_dictionary,
+            });
+        }
+    }
+    let chapters = chapter_loader::parse_chapters(&llm_file_name, &content)
+        .map_err(|e| e.context(format_args!("Failed to parse {}", llm_file_path.display())))?;
+    let (mut string_chapter, chapter_spans) = join_chapters(chapters);
+    string_chapter.language_pair = project_config.language_pair.clone();
+    if !lexicon.is_empty() {
+        let base_tokenizer = tokenizer::tokenizer_for_language(&string_chapter.language_pair.base);
+        if ChapterFormat::from_file_name(&llm_file_name) == Some(ChapterFormat::PlainText) {
+            lexicon.fill_missing_diglot_maps(&mut string_chapter, base_tokenizer.as_ref());
+        } else {
+            lexicon.augment_diglot_maps(&mut string_chapter, base_tokenizer.as_ref());
+        }
+    }
+
+    let validation_issues = validation::validate_chapter(&string_chapter);
+    let mut local_dictionary = GlobalLemmaDictionary::new();
+    let numerical_chapter = preprocessor::to_numerical_chapter(&string_chapter, &mut local_dictionary);
+    if let (Some(cache), Some(key)) = (cache, &cache_key) {
+        if let Err(e) = cache.store(book_stem, key, &string_chapter, &chapter_spans, &numerical_chapter, &local_dictionary) {
+            warn!("{}", e);
+        }
+    }
+    Ok(PreparedBook { llm_file_path, string_chapter, chapter_spans, validation_issues, numerical_chapter, local_dictionary })
+}
+
+/// The bilingual lexicon of a run: the project's lexicon file (bilingual_lexicon_path in
+/// the config), plus, for the plain-text books among `book_stems`, entries harvested from
+/// the DIGLOT_MAPs of the annotated ones. Nothing is harvested when there are no plain-text
+/// books. Unreadable books are left out here; preparing them reports the error.
+pub fn sequence_bilingual_lexicon(project_config: &Config, book_stems: &[String]) -> Result<BilingualLexicon, WeaveLangError> {
+    let file_lexicon = match &project_config.bilingual_lexicon_path {
+        Some(path) => {
+            let lexicon = BilingualLexicon::load(Path::new(path))?;
+            info!("Bilingual lexicon file {}: {} word(s).", path, lexicon.len());
+            lexicon
+        }
+        None => BilingualLexicon::default(),
+    };
+    let mut stage_paths: Vec<PathBuf> = book_stems.iter().map(|stem| stage_file_path(project_config, stem)).collect();
+    stage_paths.sort();
+    stage_paths.dedup();
+    let is_plain_text = |path: &PathBuf| path.file_name()
+        .and_then(|name| ChapterFormat::from_file_name(&name.to_string_lossy())) == Some(ChapterFormat::PlainText);
+    let plain_text_books = stage_paths.iter().filter(|path| is_plain_text(path)).count();
+    if plain_text_books == 0 {
+        return Ok(file_lexicon);
+    }
+    let annotated_chapters: Vec<ProcessedChapter> = stage_paths.iter()
+        .filter(|path| !is_plain_text(path))
+        .filter_map(|path| {
+            let file_name = path.file_name()?.to_string_lossy().into_owned();
+            chapter_loader::parse_chapter(&file_name, &fs::read_to_string(path).ok()?).ok()
+        })
+        .collect();
+    let mut lexicon = BilingualLexicon::harvest(&annotated_chapters);
+    info!("Bilingual lexicon: {} word(s) from {} annotated book(s) for {} plain-text book(s).",
+          lexicon.len(), annotated_chapters.len(), plain_text_books);
+    lexicon.merge(file_lexicon);
+    Ok(lexicon)
+}
+
+// Hands out prepared books in sequence order. With a lookahead above 0 the next books
+// are prepared on rayon worker threads while the current one is being simulated;
+// only the simulation itself has to stay sequential, since each block depends on the
+// profile left by the previous one.
+struct BookPrefetcher {
+    project_config: Arc<Config>,
+    lexicon: Arc<BilingualLexicon>,
+    cache: Option<Arc<ChapterCache>>,
+    book_stems: Vec<String>,
+    lookahead: usize,
+    pending: VecDeque<mpsc::Receiver<Result<PreparedBook, WeaveLangError>>>,
+    next_to_submit: usize,
+    next_to_take: usize,
+}
+
+impl BookPrefetcher {
+    fn new(project_config: &Config, lexicon: &Arc<BilingualLexicon>, cache: Option<&Arc<ChapterCache>>, book_stems: &[String], lookahead: usize) -> Self {
+        Self {
+            project_config: Arc::new(project_config.clone()),
+            lexicon: Arc::clone(lexicon),
+            cache: cache.cloned(),
+            book_stems: book_stems.to_vec(),
+            lookahead,
+            pending: VecDeque::new(),
+            next_to_submit: 0,
+            next_to_take: 0,
+        }
+    }
+
+    fn next_book(&mut self) -> Result<PreparedBook, WeaveLangError> {
+        let book_idx = self.next_to_take;
+        let book_stem = self.book_stems.get(book_idx).ok_or_else(|| WeaveLangError::generation("No more books in the sequence"))?;
+        self.next_to_take += 1;
+        if self.lookahead == 0 {
+            return prepare_book_cached(&self.project_config, book_stem, &self.lexicon, self.cache.as_deref());
+        }
+
+        while self.next_to_submit < self.book_stems.len() && self.next_to_submit <= book_idx + self.lookahead {
+            let (sender, receiver) = mpsc::channel();
+            let project_config = Arc::clone(&self.project_config);
+            let lexicon = Arc::clone(&self.lexicon);
+            let cache = self.cache.clone();
+            let stem = self.book_stems[self.next_to_submit].clone();
+            rayon::spawn(move || {
+                // The receiver is only gone if generation stopped early.
+                let _ = sender.send(prepare_book_cached(&project_config, &stem, &lexicon, cache.as_deref()));
+            });
+            self.pending.push_back(receiver);
+            self.next_to_submit += 1;
+        }
+        self.pending.pop_front()
+            .ok_or_else(|| WeaveLangError::generation(format!("No prepared book queued for {}", book_stem)))?
+            .recv()
+            .map_err(|_| WeaveLangError::generation(format!("Worker preparing {} stopped unexpectedly", book_stem)))?
+    }
+}
+
+/// Generation parameters one line of the sequence file sets for its book instance,
+/// e.g. `book1 ct=0.95 min_ct=0.85 spb=150`. Unset values use the run's.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BookOverrides {
+    pub target_ct_threshold: Option<f32>, // ct=
+    pub min_ct_threshold: Option<f32>,    // min_ct=
+    pub sentences_per_block: Option<usize>, // spb=
+}
+
+impl BookOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == BookOverrides::default()
+    }
+
+    /// The run's (sentences_per_block, target_ct_threshold, min_ct_threshold) with these overrides.
+    pub fn resolve(&self, sentences_per_block: usize, target_ct_threshold: f32, min_ct_threshold: f32) -> (usize, f32, f32) {
+        (
+            self.sentences_per_block.unwrap_or(sentences_per_block),
+            self.target_ct_threshold.unwrap_or(target_ct_threshold),
+            self.min_ct_threshold.unwrap_or(min_ct_threshold),
+        )
+    }
+
+    fn resolve_for(&self, args: &GenerationArgs) -> (usize, f32, f32) {
+        self.resolve(args.sentences_per_block, args.target_ct_threshold, args.min_ct_threshold)
+    }
+
+    fn parse_assignment(&mut self, assignment: &str) -> Result<(), WeaveLangError> {
+        let (key, value) = assignment.split_once('=')
+            .ok_or_else(|| WeaveLangError::config(format!("'{}' is not of the form key=value", assignment)))?;
+        let out_of_range = || WeaveLangError::config(format!("{} must be a number between 0 and 1, got '{}'", key, value));
+        let parse_ct = |value: &str| match value.parse::<f32>() {
+            Ok(ct) if (0.0..=1.0).contains(&ct) => Ok(ct),
+            Ok(_) => Err(out_of_range()),
+            Err(e) => Err(out_of_range().with_source(e)),
+        };
+        match key {
+            "ct" => self.target_ct_threshold = Some(parse_ct(value)?),
+            "min_ct" => self.min_ct_threshold = Some(parse_ct(value)?),
+            "spb" => {
+                let not_positive = || WeaveLangError::config(format!("spb must be a positive whole number, got '{}'", value));
+                match value.parse::<usize>() {
+                    Ok(spb) if spb > 0 => self.sentences_per_block = Some(spb),
+                    Ok(_) => return Err(not_positive()),
+                    Err(e) => return Err(not_positive().with_source(e)),
+                }
+            }
+            _ => return Err(WeaveLangError::config(format!("unknown parameter '{}' (expected ct, min_ct or spb)", key))),
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for BookOverrides {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(ct) = self.target_ct_threshold { parts.push(format!("ct={}", ct)); }
+        if let Some(min_ct) = self.min_ct_threshold { parts.push(format!("min_ct={}", min_ct)); }
+        if let Some(spb) = self.sentences_per_block { parts.push(format!("spb={}", spb)); }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceEntry {
+    pub book_stem: String,
+    pub overrides: BookOverrides,
+}
+
+/// Reads a sequence file: one book stem per line, optionally followed by per-book parameters
+/// (`book1 ct=0.95 spb=150`, see BookOverrides). Blank lines and # comments are ignored.
+pub fn load_sequence_entries(sequence_path: &Path) -> Result<Vec<SequenceEntry>, WeaveLangError> {
+    let sequence_file = File::open(sequence_path).map_err(|e| WeaveLangError::config(format!("Failed to open sequence file {:?}: {}", sequence_path, e)).with_source(e))?;
+    let reader = std::io::BufReader::new(sequence_file);
+    let mut entries: Vec<SequenceEntry> = Vec::new();
+    for (line_idx, line_result) in reader.lines().enumerate() {
+        let line = line_result.map_err(|e| WeaveLangError::config(format!("Failed to read line from sequence file: {}", e)).with_source(e))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') { // Ignore empty lines and comments
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let book_stem = fields.next().unwrap_or_default().to_string();
+        let mut overrides = BookOverrides::default();
+        for assignment in fields {
+            overrides.parse_assignment(assignment)
+                .map_err(|e| e.context(format_args!("Sequence file {:?}, line {}", sequence_path, line_idx + 1)))?;
+        }
+        entries.push(SequenceEntry { book_stem, overrides });
+    }
+    Ok(entries)
+}
+
+/// The book stems of a sequence file, without per-book parameters.
+pub fn load_book_sequence(sequence_path: &Path) -> Result<Vec<String>, WeaveLangError> {
+    Ok(load_sequence_entries(sequence_path)?.into_iter().map(|entry| entry.book_stem).collect())
+}
+
+// Every book instance must still have a valid CT band once its overrides are applied.
+fn check_sequence_overrides(entries: &[SequenceEntry], args: &GenerationArgs) -> Result<(), WeaveLangError> {
+    for entry in entries.iter().filter(|entry| !entry.overrides.is_empty()) {
+        let (_, target_ct_threshold, min_ct_threshold) = entry.overrides.resolve_for(args);
+        if min_ct_threshold > target_ct_threshold {
+            return Err(WeaveLangError::config(format!("Sequence entry '{} {}': min CT {} exceeds target CT {}.",
+                               entry.book_stem, entry.overrides, min_ct_threshold, target_ct_threshold)));
+        }
+    }
+    Ok(())
+}
+
+/// Location of a book's stage file inside the content project's stage directory: the first
+/// of <stem>.llm.txt, .json, .yaml or .yml that exists, else the .llm.txt path.
+pub fn stage_file_path(project_config: &Config, book_stem: &str) -> PathBuf {
+    let stage_dir = project_config.stage_dir();
+    chapter_loader::stage_file_extensions()
+        .map(|ext| stage_dir.join(format!("{}{}", book_stem, ext)))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| stage_dir.join(format!("{}.llm.txt", book_stem)))
+}
+
+/// One book instance of a `--dry-run`.
+#[derive(Debug, Clone, Default)]
+pub struct DryRunBook {
+    pub book_instance_id: String,
+    pub sentences: usize,
+    pub lemmas: usize,
+    pub new_to_dictionary: usize,
+    pub not_known_or_active: usize, // Per the starting profile
+    pub estimated_blocks: usize,    // An upper bound with auto passes
+    pub validation_issues: usize,
+    pub error: Option<String>,      // The book could not be read or parsed
+}
+
+/// What `--dry-run` found, for the caller to print (see to_text).
+#[derive(Debug, Clone, Default)]
+pub struct DryRunReport {
+    pub threshold_table: Option<(PathBuf, usize, usize)>, // (path, lemma entries, frequency bands)
+    pub known_words: Option<(PathBuf, KnownLemmaImport)>,
+    pub sequence_path: PathBuf,
+    pub auto_passes: bool,
+    pub books: Vec<DryRunBook>,
+    pub dictionary_size: usize, // After every book was converted
+}
+
+impl DryRunReport {
+    pub fn failed_books(&self) -> usize {
+        self.books.iter().filter(|book| book.error.is_some()).count()
+    }
+
+    /// Err when any book could not be read or parsed.
+    pub fn check_books(&self) -> Result<(), WeaveLangError> {
+        match self.failed_books() {
+            0 => Ok(()),
+            failed => Err(WeaveLangError::generation(format!("{} of {} book instance(s) could not be read or parsed.", failed, self.books.len()))),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::from("Dry run: nothing will be written.\n");
+        if let Some((path, lemma_entries, bands)) = &self.threshold_table {
+            out.push_str(&format!("Exposure threshold table {} OK ({} lemma entries, {} frequency bands).\n",
+                                  path.display(), lemma_entries, bands));
+        }
+        if let Some((path, import)) = &self.known_words {
+            out.push_str(&format!("Known-word list {} OK ({} word(s), {} lemma(s) marked Known).\n",
+                                  path.display(), import.words_read, import.lemmas_marked_known));
+        }
+        out.push_str(&format!("Sequence {}: {} book instance(s).\n", self.sequence_path.display(), self.books.len()));
+        for book in &self.books {
+            if let Some(e) = &book.error {
+                out.push_str(&format!("  {}: ERROR {}\n", book.book_instance_id, e));
+                continue;
+            }
+            out.push_str(&format!(
+                "  {}: {} sentences, {} lemmas ({} new to the dictionary, {} not yet Known/Active), ~{} block(s){}{}\n",
+                book.book_instance_id, book.sentences, book.lemmas, book.new_to_dictionary, book.not_known_or_active,
+                book.estimated_blocks,
+                if self.auto_passes { " at most" } else { "" },
+                if book.validation_issues == 0 { String::new() } else { format!(", {} validation issue(s)", book.validation_issues) },
+            ));
+        }
+        let total_sentences: usize = self.books.iter().map(|book| book.sentences).sum();
+        let total_blocks: usize = self.books.iter().map(|book| book.estimated_blocks).sum();
+        out.push_str(&format!("Total: {} sentences, ~{} block(s), {} lemma(s) in the dictionary after the run.\n",
+                              total_sentences, total_blocks, self.dictionary_size));
+        out
+    }
+}
+
+/// `--dry-run`: parses and converts every book in the sequence and reports sentence counts,
+/// new lemmas and estimated blocks per book instance, without simulating or writing files.
+/// Books that cannot be read or parsed are listed in the report (see check_books); an
+/// invalid input table or sequence file is an error.
+pub fn dry_run_corpus_generation(project_config: &Config, args: &GenerationArgs) -> Result<DryRunReport, WeaveLangError> {
+    let mut report = DryRunReport {
+        sequence_path: args.sequence_path.clone(),
+        auto_passes: matches!(args.passes_per_book, PassesPerBook::Auto),
+        ..DryRunReport::default()
+    };
+    if let Some(table_path) = &args.exposure_thresholds {
+        let table = ThresholdTable::load(table_path)?;
+        report.threshold_table = Some((table_path.clone(), table.lemma_thresholds.len(), table.bands.len()));
+    }
+    let (mut learner_profile, mut dictionary) = match &args.start_profile_path {
+        Some(start_profile_path) => load_profile_snapshot(start_profile_path)
+            .map_err(|e| e.context(format_args!("Failed to load starting profile {}", start_profile_path.display())))?,
+        None => (NumericalLearnerProfile::new(), GlobalLemmaDictionary::new()),
+    };
+    if let Some(seed_path) = &args.seed_dictionary {
+        dictionary.seed_from(&GlobalLemmaDictionary::import_tsv(seed_path)?);
+    }
+    if let Some(known_words_path) = &args.known_words {
+        let import = import_known_lemmas(known_words_path, &mut learner_profile, &mut dictionary)?;
+        report.known_words = Some((known_words_path.clone(), import));
+    }
+
+    let sequence_entries = load_sequence_entries(&args.sequence_path)?;
+    check_sequence_overrides(&sequence_entries, args)?;
+    let corpus_sequence: Vec<String> = sequence_entries.iter().map(|entry| entry.book_stem.clone()).collect();
+    let lexicon = sequence_bilingual_lexicon(project_config, &corpus_sequence)?;
+    let passes = match args.passes_per_book {
+        PassesPerBook::Fixed(n) => n.max(1),
+        PassesPerBook::Auto => args.max_auto_passes.max(1), // Upper bound
+    };
+
+    let mut book_instance_counter: HashMap<String, usize> = HashMap::new();
+    for (book_stem, entry) in corpus_sequence.iter().zip(&sequence_entries) {
+        let sentences_per_block = entry.overrides.resolve_for(args).0.max(1);
+        let count = book_instance_counter.entry(book_stem.clone()).or_insert(0);
+        *count += 1;
+        let book_instance_id = format!("{}_inst{:02}", book_stem, *count);
+        let book = match prepare_book(project_config, book_stem, &lexicon) {
+            Ok(book) => book,
+            Err(e) => {
+                report.books.push(DryRunBook { book_instance_id, error: Some(e.to_string()), ..DryRunBook::default() });
+                continue;
+            }
+        };
+        let dictionary_size_before = dictionary.size();
+        let numerical_chapter = preprocessor::merge_into_dictionary(book.numerical_chapter, &book.local_dictionary, &mut dictionary);
+        let sentences = numerical_chapter.sentences_numerical.len();
+        let not_known_or_active = book.local_dictionary.id_to_str.iter()
+            .filter_map(|lemma| dictionary.get_id(lemma))
+            .filter(|id| !learner_profile.is_lemma_known_or_active(*id))
+            .count();
+        report.books.push(DryRunBook {
+            book_instance_id,
+            sentences,
+            lemmas: book.local_dictionary.size(),
+            new_to_dictionary: dictionary.size() - dictionary_size_before,
+            not_known_or_active,
+            estimated_blocks: sentences.div_ceil(sentences_per_block) * passes,
+            validation_issues: book.validation_issues.len(),
+            error: None,
+        });
+    }
+    report.dictionary_size = dictionary.size();
+    Ok(report)
+}
+
 pub fn run_corpus_generation(
     project_config: &Config, // Loaded from config.toml
     args: &GenerationArgs,
-) -> Result<(), Box<dyn Error>> {
-    println!("Starting corpus generation run...");
+) -> Result<CorpusGenerationReport, WeaveLangError> {
+    run_corpus_generation_with_progress(project_config, args, &mut ConsoleProgress)
+}
+
+/// run_corpus_generation with progress events (book/block position, CT and ETAs) sent to
+/// `progress_reporter` instead of the console.
+pub fn run_corpus_generation_with_progress(
+    project_config: &Config,
+    args: &GenerationArgs,
+    progress_reporter: &mut dyn ProgressReporter,
+) -> Result<CorpusGenerationReport, WeaveLangError> {
+    if args.dry_run {
+        let dry_run = dry_run_corpus_generation(project_config, args)?;
+        info!("{}", dry_run.to_text().trim_end());
+        dry_run.check_books()?;
+        return Ok(CorpusGenerationReport::default());
+    }
+    let mut report = CorpusGenerationReport::default();
+    info!("Starting corpus generation run...");
+    let threshold_table = match &args.exposure_thresholds {
+        Some(table_path) => {
+            let table = ThresholdTable::load(table_path)?;
+            info!("Loaded exposure threshold table {} ({} lemma entries, {} frequency bands).",
+                  table_path.display(), table.lemma_thresholds.len(), table.bands.len());
+            Some(table)
+        }
+        None => None,
+    };
+    let threshold_table = ThresholdTable::with_fallback_default(threshold_table, args.default_exposure_threshold);
+    let threshold_table = ThresholdTable::with_cognate_threshold(threshold_table, args.cognate_exposure_threshold);
+    let mut threshold_table = ThresholdTable::with_variability(threshold_table, args.learner_variability);
 
     // --- 1. Initialize Profile and Dictionary ---
+    let resume_state = if args.resume {
+        match load_run_state(&args.profiles_dir) {
+            Ok(state) if state.is_complete() => {
+                info!("Run state in {} shows all {} book instance(s) finished. Nothing to resume.",
+                      args.profiles_dir.display(), state.sequence.len());
+                report.resumed_instances = state.sequence.len();
+                return Ok(report);
+            }
+            Ok(state) => {
+                if state.seed != args.seed {
+                    warn!("Resuming a run started with seed {:?} using seed {:?}; output will not match an uninterrupted run.",
+                          state.seed, args.seed);
+                }
+                Some(state)
+            }
+            Err(e) => {
+                warn!("Cannot resume ({}). Starting the run from the beginning.", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
     let mut learner_profile: NumericalLearnerProfile;
     let mut global_lemma_dictionary: GlobalLemmaDictionary;
 
-    if let Some(start_profile_path) = &args.start_profile_path {
-        println!("Attempting to load starting profile from: {}", start_profile_path.display());
+    if let Some(state) = &resume_state {
+        info!("Resuming at book instance {} of {} from profile: {}",
+              state.next_sequence_index + 1, state.sequence.len(), state.last_out_profile_path);
+        if args.start_profile_path.is_some() {
+            warn!("--start-profile is ignored when resuming.");
+        }
+        let (loaded_profile, loaded_dict) = load_profile_snapshot(Path::new(&state.last_out_profile_path))
+            .map_err(|e| e.context(format_args!("Failed to load resume profile {}", state.last_out_profile_path)))?;
+        learner_profile = loaded_profile;
+        global_lemma_dictionary = loaded_dict;
+    } else if let Some(start_profile_path) = &args.start_profile_path {
+        info!("Attempting to load starting profile from: {}", start_profile_path.display());
         match load_profile_snapshot(start_profile_path) {
             Ok((loaded_profile, loaded_dict)) => {
                 learner_profile = loaded_profile;
                 global_lemma_dictionary = loaded_dict;
-                println!("Successfully loaded starting profile and dictionary. Known words: {}", learner_profile.count_known());
+                info!(known = learner_profile.count_known(), "Successfully loaded starting profile and dictionary.");
             }
             Err(e) => {
-                eprintln!("Error loading starting profile/dictionary: {}. Starting with empty profile and dictionary.", e);
+                error!("Error loading starting profile/dictionary: {}. Starting with empty profile and dictionary.", e);
                 learner_profile = NumericalLearnerProfile::new();
                 global_lemma_dictionary = GlobalLemmaDictionary::new();
             }
@@ -61,175 +1029,353 @@ pub fn run_corpus_generation(
     } else {
         learner_profile = NumericalLearnerProfile::new();
         global_lemma_dictionary = GlobalLemmaDictionary::new();
-        println!("Starting with a new empty profile and dictionary.");
+        info!("Starting with a new empty profile and dictionary.");
+    }
+
+    if let Some(seed_path) = &args.seed_dictionary {
+        if resume_state.is_some() {
+            warn!("--seed-dictionary is ignored when resuming.");
+        } else {
+            let seed = GlobalLemmaDictionary::import_tsv(seed_path)?;
+            let added = global_lemma_dictionary.seed_from(&seed);
+            info!("Seeded dictionary from {}: {} of {} lemma(s) added.", seed_path.display(), added, seed.size());
+        }
+    }
+
+    if let Some(known_words_path) = &args.known_words {
+        if resume_state.is_some() {
+            warn!("--known-words is ignored when resuming.");
+        } else {
+            let import = import_known_lemmas(known_words_path, &mut learner_profile, &mut global_lemma_dictionary)?;
+            info!("Imported known words from {}: {} word(s), {} lemma(s) marked Known ({} already Known).",
+                  known_words_path.display(), import.words_read, import.lemmas_marked_known, import.already_known);
+        }
     }
 
     // Ensure output directories exist
-    fs::create_dir_all(&args.tts_output_dir).map_err(|e| format!("Failed to create TTS output directory {:?}: {}", args.tts_output_dir, e))?;
-    fs::create_dir_all(&args.profiles_dir).map_err(|e| format!("Failed to create profiles directory {:?}: {}", args.profiles_dir, e))?;
+    fs::create_dir_all(&args.tts_output_dir).map_err(|e| WeaveLangError::generation(format!("Failed to create TTS output directory {:?}: {}", args.tts_output_dir, e)).with_source(e))?;
+    if let Some(html_output_dir) = &args.html_output_dir {
+        fs::create_dir_all(html_output_dir).map_err(|e| WeaveLangError::generation(format!("Failed to create HTML output directory {:?}: {}", html_output_dir, e)).with_source(e))?;
+    }
+    if let Some(anki_output_dir) = &args.anki_output_dir {
+        fs::create_dir_all(anki_output_dir).map_err(|e| WeaveLangError::generation(format!("Failed to create Anki output directory {:?}: {}", anki_output_dir, e)).with_source(e))?;
+    }
+    let writes_glosses = args.anki_output_dir.is_some() || args.html_output_dir.is_some() || args.epub_output_dir.is_some();
+    let gloss_lexicon = match &project_config.lexicon_dump_path {
+        Some(dump_path) if writes_glosses => {
+            let cache_path = Path::new(&project_config.content_project_dir).join(LEXICON_CACHE_FILE_NAME);
+            match lexicon::load_with_cache(Path::new(dump_path), &cache_path, &project_config.language_pair.target) {
+                Ok(lexicon) => Some(lexicon),
+                Err(e) => {
+                    warn!("HTML, EPUB and Anki output is written without lexicon glosses: {}", e);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    if let Some(epub_output_dir) = &args.epub_output_dir {
+        fs::create_dir_all(epub_output_dir).map_err(|e| WeaveLangError::generation(format!("Failed to create EPUB output directory {:?}: {}", epub_output_dir, e)).with_source(e))?;
+    }
+    fs::create_dir_all(&args.profiles_dir).map_err(|e| WeaveLangError::generation(format!("Failed to create profiles directory {:?}: {}", args.profiles_dir, e)).with_source(e))?;
 
     // --- 2. Load Book Sequence ---
-    let sequence_file = File::open(&args.sequence_path).map_err(|e| format!("Failed to open sequence file {:?}: {}", args.sequence_path, e))?;
-    let reader = std::io::BufReader::new(sequence_file);
-    let mut corpus_sequence: Vec<String> = Vec::new();
-    for line_result in reader.lines() {
-        let line = line_result.map_err(|e| format!("Failed to read line from sequence file: {}", e))?;
-        let book_stem = line.trim();
-        if !book_stem.is_empty() && !book_stem.starts_with('#') { // Ignore empty lines and comments
-            corpus_sequence.push(book_stem.to_string());
+    let sequence_entries = load_sequence_entries(&args.sequence_path)?;
+    check_sequence_overrides(&sequence_entries, args)?;
+    let corpus_sequence: Vec<String> = sequence_entries.iter().map(|entry| entry.book_stem.clone()).collect();
+
+    if corpus_sequence.is_empty() {
+        info!("No book stems found in the sequence file. Exiting.");
+        return Ok(report);
+    }
+    info!("Processing sequence of {} book instance(s): {:?}", corpus_sequence.len(), corpus_sequence);
+    let lexicon = Arc::new(sequence_bilingual_lexicon(project_config, &corpus_sequence)?);
+    let chapter_cache = args.chapter_cache_dir.as_ref().map(|dir| Arc::new(ChapterCache::new(dir)));
+    if let Some(cache) = &chapter_cache {
+        info!("Caching parsed books in {}.", cache.dir().display());
+    }
+    // Lemma counts over every book read so far, used to rank activation candidates.
+    let mut corpus_frequency = CorpusFrequency::new();
+    let mut book_instance_counter: HashMap<String, usize> = HashMap::new();
+    let mut run_block_counter = 0;
+    let mut completed_instances: Vec<String> = Vec::new();
+    let mut recent_introductions: Vec<(usize, usize)> = Vec::new();
+
+    let start_index = match &resume_state {
+        Some(state) => {
+            let resumable = state.next_sequence_index <= corpus_sequence.len()
+                && state.sequence[..state.next_sequence_index] == corpus_sequence[..state.next_sequence_index];
+            if !resumable {
+                return Err(WeaveLangError::generation(format!(
+                    "Sequence file {} no longer matches the {} finished book instance(s) in {}; cannot resume.",
+                    args.sequence_path.display(), state.next_sequence_index, RUN_STATE_FILE_NAME
+                )));
+            }
+            // Replay the bookkeeping of the finished instances. Their lemmas are already in
+            // the loaded dictionary, so re-reading them only restores the corpus frequencies
+            // and detected cognates.
+            for book_stem in &corpus_sequence[..state.next_sequence_index] {
+                *book_instance_counter.entry(book_stem.clone()).or_insert(0) += 1;
+                match prepare_book_cached(project_config, book_stem, &lexicon, chapter_cache.as_deref()) {
+                    Ok(book) => {
+                        let numerical_chapter = preprocessor::merge_into_dictionary(book.numerical_chapter, &book.local_dictionary, &mut global_lemma_dictionary);
+                        corpus_frequency.add_chapter(&numerical_chapter, args.min_diglot_confidence);
+                        if let Some(table) = &mut threshold_table {
+                            table.learn_cognates(&book.string_chapter, args.min_diglot_confidence);
+                        }
+                    }
+                    Err(e) => warn!("{} (corpus frequencies for the resumed run will not include it).", e),
+                }
+            }
+            run_block_counter = state.run_block_counter;
+            completed_instances = state.completed_instances.clone();
+            recent_introductions = state.recent_introductions.clone();
+            info!("Skipping {} finished book instance(s). The lemma timeline only covers the resumed part of the run.", state.next_sequence_index);
+            report.resumed_instances = state.next_sequence_index;
+            state.next_sequence_index
         }
+        None => 0,
+    };
+
+    let mut book_prefetcher = BookPrefetcher::new(project_config, &lexicon, chapter_cache.as_ref(), &corpus_sequence[start_index..], args.parallel_lookahead);
+    if args.parallel_lookahead > 0 {
+        info!("Preparing up to {} upcoming book(s) on {} worker thread(s).", args.parallel_lookahead, rayon::current_num_threads());
     }
 
-    if corpus_sequence.is_empty() {
-        println!("No book stems found in the sequence file. Exiting.");
-        return Ok(());
+    // Pre-scan for the scheduler's payoff term: lemma counts of every book instance still
+    // to be read, removed book by book as the run goes on.
+    let use_remaining_frequency = args.scheduler.remaining_frequency_weight > 0.0;
+    let mut remaining_corpus_frequency = RemainingCorpusFrequency::new();
+    if use_remaining_frequency {
+        info!("Pre-scanning {} book instance(s) for remaining-corpus frequencies...", corpus_sequence.len() - start_index);
+        for book_stem in &corpus_sequence[start_index..] {
+            match prepare_book_cached(project_config, book_stem, &lexicon, chapter_cache.as_deref()) {
+                Ok(book) => remaining_corpus_frequency.add_chapter(&book.numerical_chapter, &book.local_dictionary, args.min_diglot_confidence),
+                Err(e) => warn!("{} (left out of the remaining-corpus frequencies).", e),
+            }
+        }
     }
-    println!("Processing sequence of {} book instance(s): {:?}", corpus_sequence.len(), corpus_sequence);
 
-    let mut book_instance_counter: HashMap<String, usize> = HashMap::new();
+    let mut lemma_timeline = LemmaTimeline::new();
+    let mut progress = ProgressTracker::new(corpus_sequence.len());
 
     // --- 3. Iterate Through the Book Sequence ---
-    for book_stem_orig in &corpus_sequence {
+    for (sequence_index, book_stem_orig) in corpus_sequence.iter().enumerate().skip(start_index) {
         let count = book_instance_counter.entry(book_stem_orig.clone()).or_insert(0);
         *count += 1;
         let book_instance_unique_id = format!("{}_inst{:02}", book_stem_orig, *count);
+        // Everything logged until the next book instance carries its ID.
+        let _book_span = info_span!("book", instance = %book_instance_unique_id).entered();
         
-        println!("\n--- Processing book instance: {} (Original stem: {}) ---", book_instance_unique_id, book_stem_orig);
+        info!(stem = %book_stem_orig, "Processing book instance");
+        progress_reporter.on_book_start(&progress.start_book(sequence_index + 1, &book_instance_unique_id));
 
         // --- 3a. Save "_in.profile" for this instance ---
-        let in_profile_filename = format!("{}_in.profile.json", book_instance_unique_id);
+        let in_profile_filename = format!("{}_in.{}", book_instance_unique_id, args.snapshot_format.file_suffix());
         let in_profile_path = args.profiles_dir.join(&in_profile_filename);
-        if let Err(e) = save_profile_snapshot(&learner_profile, &global_lemma_dictionary, &in_profile_path) {
-            eprintln!("  ERROR: Failed to save in-profile for {}: {}. Continuing without saving this snapshot.", book_instance_unique_id, e);
-        } else {
-            println!("  Saved in-profile to: {}", in_profile_path.display());
-        }
+        let mut book_report = BookReport {
+            book_instance_id: book_instance_unique_id.clone(),
+            book_stem: book_stem_orig.clone(),
+            known_before: learner_profile.count_known(),
+            ..BookReport::default()
+        };
+        let known_or_active_before_book = learner_profile.count_total_known_or_active();
+        let in_profile_saved = match save_profile_snapshot_as(&learner_profile, &global_lemma_dictionary, &in_profile_path, args.snapshot_format) {
+            Ok(_) => {
+                book_report.in_profile_path = Some(in_profile_path.clone());
+                info!("Saved in-profile to: {}", in_profile_path.display());
+                true
+            }
+            Err(e) => {
+                error!("Failed to save in-profile: {}. Continuing without saving this snapshot.", e);
+                false
+            }
+        };
+        // Block snapshots are deltas against the in-profile, so they need its exact contents.
+        let in_profile_base = (in_profile_saved && args.snapshot_every_blocks.is_some())
+            .then(|| (learner_profile.clone(), global_lemma_dictionary.size()));
         
         let learner_level_at_book_instance_start = learner_profile.count_known() / 100; // Integer division
 
-        // --- 3b. Load and Parse .llm.txt file ---
-        let llm_file_name = format!("{}.llm.txt", book_stem_orig);
-        let llm_file_path = PathBuf::from(&project_config.content_project_dir)
-            .join("stage") // Assuming .llm.txt files are in "project_config.content_project_dir/stage/"
-            .join(&llm_file_name);
-
-        let string_chapter = match fs::read_to_string(&llm_file_path) {
-            Ok(content) => {
-                match llm_parser::parse_llm_text_to_chapter(&llm_file_name, &content) {
-                    Ok(ch) => ch,
-                    Err(e) => {
-                        eprintln!("  ERROR: Failed to parse {}: {}. Skipping this book instance.", llm_file_path.display(), e);
-                        continue; 
-                    }
-                }
-            }
+        // --- 3b. Load, Parse and Convert .llm.txt file (possibly prepared ahead on a worker) ---
+        let prepared_book = match book_prefetcher.next_book() {
+            Ok(book) => book,
             Err(e) => {
-                eprintln!("  ERROR: Failed to read {}: {}. Skipping this book instance.", llm_file_path.display(), e);
+                error!("{}. Skipping this book instance.", e);
+                report.skipped.push((book_instance_unique_id, e.to_string()));
                 continue;
             }
         };
+        if !prepared_book.validation_issues.is_empty() {
+            warn!("{} validation issue(s) in {}:", prepared_book.validation_issues.len(), prepared_book.llm_file_path.display());
+            for issue in &prepared_book.validation_issues {
+                warn!("{}", issue);
+            }
+        }
+        let mut string_chapter = prepared_book.string_chapter;
+        let chapter_spans = prepared_book.chapter_spans;
 
-        // Convert to numerical, updating the global dictionary
-        // Note: global_lemma_dictionary is cumulative across all book instances
-        let numerical_chapter = preprocessor::to_numerical_chapter(&string_chapter, &mut global_lemma_dictionary);
-        println!("  Parsed {} sentences for {}.", numerical_chapter.sentences_numerical.len(), book_instance_unique_id);
-
+        // Move onto the global dictionary (cumulative across all book instances)
+        let numerical_chapter = preprocessor::merge_into_dictionary(
+            prepared_book.numerical_chapter,
+            &prepared_book.local_dictionary,
+            &mut global_lemma_dictionary,
+        );
+        match (&string_chapter.book_title, chapter_spans.len()) {
+            (Some(book_title), chapter_count) => info!("Parsed {} sentences in {} chapter(s) of \"{}\".",
+                numerical_chapter.sentences_numerical.len(), chapter_count, book_title),
+            (None, chapter_count) if chapter_count > 1 => info!("Parsed {} sentences in {} chapters.",
+                numerical_chapter.sentences_numerical.len(), chapter_count),
+            _ => info!("Parsed {} sentences.", numerical_chapter.sentences_numerical.len()),
+        }
+        if args.track_forms {
+            let target_tokenizer = tokenizer::tokenizer_for_language(&string_chapter.language_pair.target);
+            let added = global_lemma_dictionary.learn_forms_from_chapter(&string_chapter, target_tokenizer.as_ref());
+            info!("Tracking {} new form(s) ({} in total).", added, global_lemma_dictionary.form_count());
+        }
+        corpus_frequency.add_chapter(&numerical_chapter, args.min_diglot_confidence);
+        let remaining_frequency = use_remaining_frequency.then(|| remaining_corpus_frequency.for_dictionary(&global_lemma_dictionary));
+        if let Some(table) = &mut threshold_table {
+            let cognates = table.learn_cognates(&string_chapter, args.min_diglot_confidence);
+            if cognates > 0 {
+                info!("Detected {} new cognate(s) ({} in total).", cognates, table.cognates.len());
+            }
+            learner_profile.set_exposure_thresholds(Arc::new(table.resolve(&global_lemma_dictionary)));
+        }
 
         // --- 3c. Process Book in Blocks ---
-        let mut this_book_instance_output_text_segments: Vec<String> = Vec::new();
-        let num_sentences_in_book = numerical_chapter.sentences_numerical.len();
-        let mut current_sentence_idx_in_book = 0;
-        let mut block_counter = 0;
-
-        while current_sentence_idx_in_book < num_sentences_in_book {
-            block_counter += 1;
-            let end_block_idx_in_book = std::cmp::min(
-                current_sentence_idx_in_book + args.sentences_per_block,
-                num_sentences_in_book,
-            );
-            
-            println!("    Processing block {} (sentences {} to {}) for {}.", 
-                     block_counter, current_sentence_idx_in_book, end_block_idx_in_book -1, book_instance_unique_id);
-
-            let current_block_numerical_sentences_refs: Vec<&NumericalProcessedSentence> =
-                numerical_chapter.sentences_numerical[current_sentence_idx_in_book..end_block_idx_in_book].iter().collect();
-            
-            let current_block_string_sentences_refs: Vec<&crate::types::llm_data::ProcessedSentence> =
-                string_chapter.sentences[current_sentence_idx_in_book..end_block_idx_in_book].iter().collect();
-
-            if current_block_numerical_sentences_refs.is_empty() {
-                break; 
-            }
-            
-            // Prepare available_new_lemma_ids_for_activation for this specific block
-            let mut block_new_lemma_freq: HashMap<u32, u32> = HashMap::new();
-            for num_sentence_ref in &current_block_numerical_sentences_refs {
-                let mut sentence_lemma_ids_for_freq_check: Vec<u32> = Vec::new();
-                sentence_lemma_ids_for_freq_check.extend(&num_sentence_ref.adv_s_lemma_ids);
-                for nsl in &num_sentence_ref.sim_s_lemmas_numerical {
-                    sentence_lemma_ids_for_freq_check.extend(&nsl.lemma_ids);
-                }
-                for ndsm in &num_sentence_ref.diglot_map_numerical {
-                    for nde in &ndsm.entries {
-                        if nde.viable { sentence_lemma_ids_for_freq_check.push(nde.spa_lemma_id); }
-                    }
-                }
-                for &lemma_id in &sentence_lemma_ids_for_freq_check {
-                    // Check against the *current state* of the evolving learner_profile
-                    if learner_profile.get_lemma_info(lemma_id).map_or(true, |info| info.state == LemmaState::New) {
-                        *block_new_lemma_freq.entry(lemma_id).or_insert(0) += 1;
-                    }
+        // Fixed(N) reads the book N times in one wrap-around stream; Auto runs one pass at a
+        // time and stops once a pass no longer grows the learner's vocabulary. A book with
+        // CHAPTER:: headers is simulated chapter by chapter, so no block straddles two
+        // chapters; with Fixed(N) each chapter is read N times before the next one.
+        let (passes_per_orchestrator_run, max_orchestrator_runs) = match args.passes_per_book {
+            PassesPerBook::Fixed(n) => (n.max(1), 1),
+            PassesPerBook::Auto => (1, args.max_auto_passes.max(1)),
+        };
+        if let Err(e) = check_chapter_pair(&string_chapter, &numerical_chapter) {
+            error!("{}. Skipping this book instance.", e);
+            report.skipped.push((book_instance_unique_id, e.to_string()));
+            continue;
+        }
+        if let [only_chapter] = chapter_spans.as_slice() {
+            string_chapter.chapter_title = only_chapter.title.clone();
+        }
+        let chapter_parts: Vec<(ProcessedChapter, NumericalChapter)> = if chapter_spans.len() > 1 {
+            chapter_spans.iter().map(|span| (string_chapter.slice(span), numerical_chapter.slice(span))).collect()
+        } else {
+            Vec::new()
+        };
+        let chapters: Vec<ChapterInput> = if chapter_parts.is_empty() {
+            vec![ChapterInput { string_chapter: &string_chapter, numerical_chapter: &numerical_chapter }]
+        } else {
+            chapter_parts.iter().map(|(string_part, numerical_part)| ChapterInput { string_chapter: string_part, numerical_chapter: numerical_part }).collect()
+        };
+        let book_overrides = sequence_entries[sequence_index].overrides;
+        if !book_overrides.is_empty() {
+            info!("Sequence file overrides for this book: {}", book_overrides);
+        }
+        let (sentences_per_block, target_ct_threshold, min_ct_threshold) = book_overrides.resolve_for(args);
+        let mut orchestrator_params = OrchestratorParams {
+            sentences_per_block,
+            block_boundaries: args.block_boundaries,
+            passes: passes_per_orchestrator_run,
+            max_regen_attempts_per_block: args.max_regen_attempts_per_block,
+            target_ct_threshold,
+            min_ct_threshold,
+            max_words_to_activate_per_regen: args.max_words_to_activate_per_regen,
+            min_diglot_confidence: args.min_diglot_confidence,
+            l4_strategy: args.l4_strategy,
+            l4_match_plurals: args.l4_match_plurals,
+            decay: args.decay,
+            scheduler: args.scheduler,
+            ct_metric: args.ct_metric,
+            prefix_level_tags: args.level_tags,
+            level_policy: args.level_policy.clone(),
+            halt_on_block_error: false, // Log and continue with the profile *before* a failed block
+            max_new_lemmas_per_100_sentences: args.max_new_lemmas_per_100_sentences,
+            recent_introductions: Vec::new(),
+            track_forms: args.track_forms,
+        };
+        let mut block_observer = CliBlockObserver {
+            book_instance_unique_id: &book_instance_unique_id,
+            dictionary: &global_lemma_dictionary,
+            lemma_timeline: &mut lemma_timeline,
+            run_block_counter: &mut run_block_counter,
+            blocks_in_book: 0,
+            ct_sum: 0.0,
+            ct_metric_name: "",
+            output_text_segments: Vec::new(),
+            sentence_levels: Vec::new(),
+            audio_manifest: args.audio_manifest
+                .then(|| ManifestBuilder::new(&book_instance_unique_id, &string_chapter.language_pair.target)),
+            subtitle_texts: args.subtitle_format.map(|_| Vec::new()),
+            parallel_rows: args.parallel_text_format.map(|_| Vec::new()),
+            qa_report: Some(QaReportBuilder::new(&book_instance_unique_id, &string_chapter, args.min_diglot_confidence)),
+            block_traces: args.trace.then(Vec::new),
+            html_target_language: (args.html_output_dir.is_some() || args.epub_output_dir.is_some())
+                .then_some(string_chapter.language_pair.target.as_str()),
+            html_blocks: Vec::new(),
+            gloss_lexicon: gloss_lexicon.as_ref(),
+            ssml_options: (args.output_format == TtsOutputFormat::Ssml).then(|| SsmlOptions {
+                base_language: string_chapter.language_pair.base.clone(),
+                target_language: string_chapter.language_pair.target.clone(),
+                base_voice: args.ssml_base_voice.clone(),
+                target_voice: args.ssml_target_voice.clone(),
+            }),
+            ssml_blocks: Vec::new(),
+            collect_anki_cards: args.anki_output_dir.is_some(),
+            newly_activated_lemma_ids: Vec::new(),
+            anki_cards: Vec::new(),
+            passes_in_book: 0,
+            last_pass_number: 0,
+            block_snapshots: in_profile_base.as_ref().map(|(base_profile, base_dictionary_size)| BlockSnapshotSettings {
+                every_n_blocks: args.snapshot_every_blocks.unwrap_or(1).max(1),
+                profiles_dir: &args.profiles_dir,
+                base_snapshot_path: &in_profile_path,
+                base_profile,
+                base_dictionary_size: *base_dictionary_size,
+            }),
+            progress: &mut progress,
+            progress_reporter: &mut *progress_reporter,
+        };
+        let blocks_per_orchestrator_run: usize = chapters.iter()
+            .map(|chapter| (chapter.numerical_chapter.sentences_numerical.len() * passes_per_orchestrator_run).div_ceil(sentences_per_block.max(1)))
+            .sum();
+        for run_number in 1..=max_orchestrator_runs {
+            block_observer.progress.add_book_blocks(blocks_per_orchestrator_run);
+            let known_before_pass = learner_profile.count_known();
+            let known_or_active_before_pass = learner_profile.count_total_known_or_active();
+            orchestrator_params.recent_introductions = recent_introductions.clone();
+            match run_chapters_observed(&chapters, &mut learner_profile, &global_lemma_dictionary,
+                                        &orchestrator_params, Some(&corpus_frequency), remaining_frequency.as_ref(),
+                                        &mut block_observer) {
+                Ok(results) => extend_introduction_history(&mut recent_introductions, &results),
+                Err(e) => {
+                    error!("{}", e);
+                    break;
                 }
             }
-            let mut sorted_block_specific_new_lemma_ids_for_activation: Vec<(u32, u32)> = 
-                block_new_lemma_freq.into_iter().collect();
-            sorted_block_specific_new_lemma_ids_for_activation.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
-
-
-            match core_algo::run_simulation_numerical(
-                &current_block_numerical_sentences_refs,
-                learner_profile.clone(), // Pass a clone for the block's simulation cycle
-                &sorted_block_specific_new_lemma_ids_for_activation,
-                args.max_regen_attempts_per_block,
-                args.target_ct_threshold,
-                args.max_words_to_activate_per_regen,
-            ) {
-                Ok(block_simulation_result) => {
-                    // Log CT for the block
-                    println!("      Block {} CT: {:.2}%. Known: {}, Total Spanish: {}. Words Activated: {}. Regen Loops: {}.",
-                             block_counter,
-                             block_simulation_result.final_ct_for_block * 100.0,
-                             block_simulation_result.known_lemmas_in_block,
-                             block_simulation_result.total_spanish_lemmas_in_block,
-                             block_simulation_result.profile_state_for_text_generation.count_active_only() - learner_profile.count_active_only(), // A bit approximative for "activated in this block"
-                             block_simulation_result.simulation_log_entries.iter().filter(|s| s.contains("Regen Attempt:")).count()
-                    );
-
-
-                    match text_generator::generate_final_text_block(
-                        &current_block_string_sentences_refs,
-                        &global_lemma_dictionary,
-                        &block_simulation_result.profile_state_for_text_generation, // Use this profile for text
-                    ) {
-                        Ok(generated_text_for_block) => {
-                            if !generated_text_for_block.trim().is_empty() {
-                                this_book_instance_output_text_segments.push(generated_text_for_block);
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("    ERROR: Text generation failed for block {} in {}: {}. Skipping text for this block.", block_counter, book_instance_unique_id, e);
-                        }
-                    }
-                    // CRITICAL: Update the main, persistent learner_profile
-                    learner_profile = block_simulation_result.profile_state_after_block_exposure;
-                }
-                Err(e) => {
-                    eprintln!("    ERROR: Core simulation failed for block {} in {}: {}. Profile not updated for this block. Trying to continue.", block_counter, book_instance_unique_id, e);
-                    // Decide if a block failure should halt the entire book or just skip the block.
-                    // For now, we log and continue with the profile *before* this failed block.
+
+            if args.passes_per_book == PassesPerBook::Auto {
+                let saturated = learner_profile.count_known() <= known_before_pass
+                    && learner_profile.count_total_known_or_active() <= known_or_active_before_pass;
+                if saturated {
+                    info!("Saturation reached after pass {}.", run_number);
+                    break;
+                } else if run_number == max_orchestrator_runs {
+                    info!("Stopped after max auto passes ({}) without reaching saturation.", max_orchestrator_runs);
                 }
             }
-            current_sentence_idx_in_book = end_block_idx_in_book;
+        }
+        if use_remaining_frequency {
+            remaining_corpus_frequency.remove_chapter(&numerical_chapter, &global_lemma_dictionary, args.min_diglot_confidence);
+        }
+        let this_book_instance_output_text_segments = std::mem::take(&mut block_observer.output_text_segments);
+        book_report.blocks = block_observer.blocks_in_book;
+        book_report.average_ct = if book_report.blocks > 0 { block_observer.ct_sum / book_report.blocks as f32 } else { 0.0 };
+        book_report.ct_metric_name = block_observer.ct_metric_name.to_string();
+        for record in &block_observer.sentence_levels {
+            book_report.level_counts[record.level as usize] += 1;
         }
 
         // --- 3d. Record Ending Level & Save TTS Output Text File ---
@@ -240,27 +1386,180 @@ pub fn run_corpus_generation(
             learner_level_at_book_instance_start,
             learner_level_at_book_instance_end
         );
-        let tts_output_file_path = args.tts_output_dir.join(format!("{}.txt", tts_filename_stem));
+        let tts_output_file_path = args.tts_output_dir.join(format!("{}.{}", tts_filename_stem, args.output_format.file_extension()));
         
         // Join text segments with double newlines
-        let final_tts_text = this_book_instance_output_text_segments.join("\n\n");
+        let final_tts_text = match &block_observer.ssml_options {
+            Some(options) => ssml::render_ssml_document(&block_observer.ssml_blocks, options),
+            None => this_book_instance_output_text_segments.join("\n\n"),
+        };
         match fs::write(&tts_output_file_path, final_tts_text) {
-            Ok(_) => println!("  Saved TTS input to: {}", tts_output_file_path.display()),
-            Err(e) => eprintln!("  ERROR: Failed to write TTS input file {}: {}", tts_output_file_path.display(), e),
+            Ok(_) => {
+                info!("Saved TTS input to: {}", tts_output_file_path.display());
+                book_report.tts_path = Some(tts_output_file_path);
+            }
+            Err(e) => error!("Failed to write TTS input file {}: {}", tts_output_file_path.display(), e),
+        }
+        if args.level_sidecar {
+            let levels_file_path = args.tts_output_dir.join(format!("{}.levels.json", tts_filename_stem));
+            let write_result = serde_json::to_string_pretty(&block_observer.sentence_levels)
+                .map_err(|e| e.to_string())
+                .and_then(|json| fs::write(&levels_file_path, json).map_err(|e| e.to_string()));
+            match write_result {
+                Ok(_) => {
+                    info!("Saved sentence levels to: {}", levels_file_path.display());
+                    book_report.levels_path = Some(levels_file_path);
+                }
+                Err(e) => error!("Failed to write sentence levels {}: {}", levels_file_path.display(), e),
+            }
+        }
+        if let Some(audio_manifest) = &mut block_observer.audio_manifest {
+            let manifest_file_path = args.tts_output_dir.join(format!("{}.manifest.json", tts_filename_stem));
+            audio_manifest.manifest.tts_file = format!("{}.{}", tts_filename_stem, args.output_format.file_extension());
+            match manifest::write_manifest(&manifest_file_path, &audio_manifest.manifest) {
+                Ok(_) => {
+                    info!("Saved audio manifest to: {}", manifest_file_path.display());
+                    book_report.manifest_path = Some(manifest_file_path);
+                }
+                Err(e) => error!("{}", e),
+            }
+        }
+        if let (Some(subtitle_texts), Some(subtitle_format)) = (&block_observer.subtitle_texts, args.subtitle_format) {
+            let subtitles_file_path = args.tts_output_dir.join(format!("{}.{}", tts_filename_stem, subtitle_format.file_extension()));
+            match subtitles::write_subtitles(&subtitles_file_path, subtitle_texts, &args.subtitle_timing, subtitle_format) {
+                Ok(_) => {
+                    info!("Saved subtitles to: {}", subtitles_file_path.display());
+                    book_report.subtitles_path = Some(subtitles_file_path);
+                }
+                Err(e) => error!("{}", e),
+            }
+        }
+        if let (Some(parallel_rows), Some(parallel_format)) = (&block_observer.parallel_rows, args.parallel_text_format) {
+            let parallel_file_path = args.tts_output_dir.join(format!("{}.{}", tts_filename_stem, parallel_format.file_extension()));
+            match parallel::write_parallel_text(&parallel_file_path, &book_instance_unique_id, parallel_rows, parallel_format) {
+                Ok(_) => {
+                    info!("Saved parallel text to: {}", parallel_file_path.display());
+                    book_report.parallel_text_path = Some(parallel_file_path);
+                }
+                Err(e) => error!("{}", e),
+            }
+        }
+
+        if let Some(qa_builder) = block_observer.qa_report.take() {
+            let qa_report = qa_builder.finish(&global_lemma_dictionary, &learner_profile);
+            info!("QA: {}", qa_report.summary());
+            let qa_file_path = args.profiles_dir.join(format!("{}.qa.json", book_instance_unique_id));
+            match save_qa_report(&qa_report, &qa_file_path) {
+                Ok(_) => {
+                    info!("Saved QA report to: {}", qa_file_path.display());
+                    book_report.qa_report_path = Some(qa_file_path);
+                }
+                Err(e) => error!("{}", e),
+            }
+        }
+
+        if let Some(block_traces) = &block_observer.block_traces {
+            let trace_file_path = args.profiles_dir.join(format!("{}.trace.jsonl", book_instance_unique_id));
+            let write_result = block_traces.iter()
+                .map(serde_json::to_string)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())
+                .and_then(|lines| fs::write(&trace_file_path, lines.join("\n") + "\n").map_err(|e| e.to_string()));
+            match write_result {
+                Ok(_) => {
+                    info!("Saved simulation trace to: {}", trace_file_path.display());
+                    book_report.trace_path = Some(trace_file_path);
+                }
+                Err(e) => error!("Failed to write simulation trace {}: {}", trace_file_path.display(), e),
+            }
+        }
+
+        if let Some(html_output_dir) = &args.html_output_dir {
+            let html_file_path = html_output_dir.join(format!("{}.html", tts_filename_stem));
+            let block_fragments: Vec<String> = block_observer.html_blocks.iter().map(|b| b.html.clone()).collect();
+            match html::write_chapter_html(&html_file_path, &book_instance_unique_id, &block_fragments) {
+                Ok(_) => {
+                    info!("Saved HTML chapter to: {}", html_file_path.display());
+                    book_report.html_path = Some(html_file_path);
+                }
+                Err(e) => error!("{}", e),
+            }
+        }
+        if let Some(epub_output_dir) = &args.epub_output_dir {
+            let epub_file_path = epub_output_dir.join(format!("{}.epub", tts_filename_stem));
+            let mut book = EpubBook::new(&book_instance_unique_id, &project_config.book_metadata, &string_chapter.language_pair.target);
+            book.modified = reproducible_timestamp(args.seed);
+            book.chapters = epub_chapters(&block_observer.html_blocks, args.epub_chapter_mode, &book_instance_unique_id);
+            match epub::write_epub(&book, &epub_file_path) {
+                Ok(_) => {
+                    info!("Saved EPUB to: {}", epub_file_path.display());
+                    book_report.epub_path = Some(epub_file_path);
+                }
+                Err(e) => error!("{}", e),
+            }
+        }
+
+        if let Some(anki_output_dir) = &args.anki_output_dir {
+            let deck_file_path = anki_output_dir.join(format!("{}.anki.tsv", tts_filename_stem));
+            match anki::write_tsv_deck(&deck_file_path, &block_observer.anki_cards, &book_instance_unique_id) {
+                Ok(_) => {
+                    info!("Saved Anki deck ({} newly activated lemmas) to: {}", block_observer.anki_cards.len(), deck_file_path.display());
+                    book_report.anki_path = Some(deck_file_path);
+                }
+                Err(e) => error!("{}", e),
+            }
         }
 
         // --- 3e. Save "_out.profile" for this instance ---
-        let out_profile_filename = format!("{}_out.profile.json", book_instance_unique_id);
+        let out_profile_filename = format!("{}_out.{}", book_instance_unique_id, args.snapshot_format.file_suffix());
         let out_profile_path = args.profiles_dir.join(&out_profile_filename);
-        if let Err(e) = save_profile_snapshot(&learner_profile, &global_lemma_dictionary, &out_profile_path) {
-             eprintln!("  ERROR: Failed to save out-profile for {}: {}. Profile state for next book might be inaccurate if run is interrupted here.", book_instance_unique_id, e);
+        if let Err(e) = save_profile_snapshot_as(&learner_profile, &global_lemma_dictionary, &out_profile_path, args.snapshot_format) {
+             error!("Failed to save out-profile: {}. Profile state for next book might be inaccurate if run is interrupted here.", e);
         } else {
-            println!("  Saved out-profile to: {}", out_profile_path.display());
+            info!("Saved out-profile to: {}", out_profile_path.display());
+            book_report.out_profile_path = Some(out_profile_path.clone());
+            completed_instances.push(book_instance_unique_id.clone());
+            let run_state = RunState {
+                sequence_path: args.sequence_path.to_string_lossy().into_owned(),
+                sequence: corpus_sequence.clone(),
+                next_sequence_index: sequence_index + 1,
+                last_out_profile_path: out_profile_path.to_string_lossy().into_owned(),
+                run_block_counter,
+                completed_instances: completed_instances.clone(),
+                seed: args.seed,
+                recent_introductions: recent_introductions.clone(),
+            };
+            if let Err(e) = save_run_state(&run_state, &args.profiles_dir) {
+                error!("{}. A resumed run would restart before this book instance.", e);
+            }
         }
-        println!("  Finished book instance: {}. Profile Known Words: {}", book_instance_unique_id, learner_profile.count_known());
+        info!(known = learner_profile.count_known(), active = learner_profile.count_active_only(), "Finished book instance");
+        if !learner_profile.grammar.is_empty() {
+            let (grammar_known, grammar_active) = learner_profile.count_grammar();
+            info!(grammar_known, grammar_active, "Grammar features");
+        }
+        progress_reporter.on_book_done(&progress.finish_book());
+
+        book_report.start_level = learner_level_at_book_instance_start;
+        book_report.end_level = learner_level_at_book_instance_end;
+        book_report.known_after = learner_profile.count_known();
+        book_report.active_after = learner_profile.count_active_only();
+        book_report.sentences = string_chapter.sentences.len();
+        book_report.words_activated = learner_profile.count_total_known_or_active().saturating_sub(known_or_active_before_book);
+        book_report.form_coverage = args.track_forms.then(|| learner_profile.form_coverage(&global_lemma_dictionary));
+        report.books.push(book_report);
     }
 
-    println!("\nCorpus generation run finished.");
-    Ok(())
+    // --- 4. Write Run Reports ---
+    let timeline_csv_path = args.profiles_dir.join("lemma_timeline.csv");
+    let timeline_html_path = args.profiles_dir.join("lemma_timeline.html");
+    match lemma_timeline.write_csv(&timeline_csv_path).and_then(|_| lemma_timeline.write_html(&timeline_html_path)) {
+        Ok(_) => info!("Saved lemma introduction timeline ({} lemmas) to: {} and {}",
+                       lemma_timeline.len(), timeline_csv_path.display(), timeline_html_path.display()),
+        Err(e) => error!("Failed to write lemma introduction timeline: {}", e),
+    }
+
+    info!("Corpus generation run finished.");
+    Ok(report)
 }
 //*** END FILE: src/corpus_generator.rs ***//
\ No newline at end of file
@@ -0,0 +1,200 @@
+//*** START FILE: src/corpus_planner.rs ***//
+// Suggests a reading order for a set of books, for `weavelang plan`. Books are picked
+// greedily: at each step the book whose blocks would introduce the fewest new lemmas at
+// their worst point (then on average) goes next, given every lemma the books before it
+// contain. Books sharing much vocabulary with what was read so far therefore come early,
+// and vocabulary-heavy books are pushed back until overlap has made them gentler.
+
+use crate::config::Config;
+use crate::corpus_generator::{prepare_book, sequence_bilingual_lexicon};
+use crate::parsing::chapter_loader;
+use crate::profile_io::load_profile_snapshot;
+use crate::simulation::{
+    dictionary::GlobalLemmaDictionary,
+    numerical_types::NumericalLearnerProfile,
+    preprocessor,
+    scheduler::sentence_lemma_ids,
+};
+
+use std::collections::HashSet;
+use crate::error::WeaveLangError;
+use std::fs;
+use std::path::Path;
+use serde::Serialize;
+
+/// One book at its place in an ordering, with the new-lemma load it has there.
+#[derive(Serialize, Debug, Clone)]
+pub struct PlannedBook {
+    pub book_stem: String,
+    pub blocks: usize,
+    pub new_lemmas: usize,
+    pub max_new_lemmas_per_block: usize,
+}
+
+impl PlannedBook {
+    pub fn new_lemmas_per_block(&self) -> f64 {
+        if self.blocks == 0 { 0.0 } else { self.new_lemmas as f64 / self.blocks as f64 }
+    }
+}
+
+/// Suggested ordering next to the order the books were given in. Books that could not be
+/// read or parsed are left out and listed in `skipped` with the reason.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SequencePlan {
+    pub sentences_per_block: usize,
+    pub planned: Vec<PlannedBook>,
+    pub input_order: Vec<PlannedBook>,
+    pub skipped: Vec<(String, String)>, // (book stem, error)
+    pub duplicates_dropped: usize,
+}
+
+impl SequencePlan {
+    pub fn peak_new_lemmas_per_block(books: &[PlannedBook]) -> usize {
+        books.iter().map(|b| b.max_new_lemmas_per_block).max().unwrap_or(0)
+    }
+
+    /// Sequence file contents for the planned order, loadable with `generate --sequence`.
+    pub fn sequence_file_contents(&self) -> String {
+        let mut contents = format!("# Reading order suggested by `weavelang plan` ({} sentences per block)\n", self.sentences_per_block);
+        for book in &self.planned {
+            contents.push_str(&book.book_stem);
+            contents.push('\n');
+        }
+        contents
+    }
+
+    /// Plain-text summary printed by `weavelang plan`.
+    pub fn report(&self) -> String {
+        let stem_width = self.planned.iter().map(|b| b.book_stem.len())
+            .chain(self.skipped.iter().map(|(stem, _)| stem.len()))
+            .chain(std::iter::once("Book".len()))
+            .max()
+            .unwrap_or(0);
+        let mut report = format!("{:>3}  {:<stem_width$}  {:>6}  {:>5}  {:>9}  {:>9}\n", "#", "Book", "Blocks", "New", "New/block", "Max/block");
+        for (position, book) in self.planned.iter().enumerate() {
+            report.push_str(&format!("{:>3}  {:<stem_width$}  {:>6}  {:>5}  {:>9.1}  {:>9}\n",
+                                     position + 1, book.book_stem, book.blocks, book.new_lemmas,
+                                     book.new_lemmas_per_block(), book.max_new_lemmas_per_block));
+        }
+        for (book_stem, error) in &self.skipped {
+            report.push_str(&format!("{:>3}  {:<stem_width$}  skipped: {}\n", "-", book_stem, error));
+        }
+        if self.duplicates_dropped > 0 {
+            report.push_str(&format!("{} repeated book(s) dropped; add rereads back to the sequence by hand.\n", self.duplicates_dropped));
+        }
+        report.push_str(&format!("Peak new lemmas per block: {} in the given order, {} in the planned order.\n",
+                                 Self::peak_new_lemmas_per_block(&self.input_order), Self::peak_new_lemmas_per_block(&self.planned)));
+        report
+    }
+}
+
+// A book reduced to the lemmas each of its blocks can expose.
+struct BookLemmaBlocks {
+    book_stem: String,
+    blocks: Vec<Vec<u32>>,
+}
+
+impl BookLemmaBlocks {
+    // New-lemma load of the book if read after `known` lemmas. Does not modify `known`.
+    fn evaluate(&self, known: &HashSet<u32>) -> PlannedBook {
+        let mut introduced: HashSet<u32> = HashSet::new();
+        let mut max_new_lemmas_per_block = 0;
+        for block in &self.blocks {
+            let before = introduced.len();
+            introduced.extend(block.iter().filter(|lemma_id| !known.contains(lemma_id)));
+            max_new_lemmas_per_block = max_new_lemmas_per_block.max(introduced.len() - before);
+        }
+        PlannedBook {
+            book_stem: self.book_stem.clone(),
+            blocks: self.blocks.len(),
+            new_lemmas: introduced.len(),
+            max_new_lemmas_per_block,
+        }
+    }
+
+    fn mark_known(&self, known: &mut HashSet<u32>) {
+        for block in &self.blocks {
+            known.extend(block.iter().copied());
+        }
+    }
+}
+
+/// Every book stem with a stage file in the project's stage directory, sorted by name.
+pub fn stage_book_stems(project_config: &Config) -> Result<Vec<String>, WeaveLangError> {
+    let stage_dir = project_config.stage_dir();
+    let mut stems: Vec<String> = fs::read_dir(&stage_dir)
+        .map_err(|e| WeaveLangError::generation(format!("Failed to read stage directory {}: {}", stage_dir.display(), e)).with_source(e))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str().and_then(chapter_loader::book_stem).map(str::to_string))
+        .collect();
+    stems.sort();
+    stems.dedup(); // A stem present in several formats is one book
+    Ok(stems)
+}
+
+/// Orders `book_stems` to keep per-block new-lemma counts low (see the module comment).
+/// Lemmas already Known or Active in `start_profile_path` never count as new. Ties keep
+/// the given order.
+pub fn plan_sequence(
+    project_config: &Config,
+    book_stems: &[String],
+    sentences_per_block: usize,
+    min_diglot_confidence: f32,
+    start_profile_path: Option<&Path>,
+) -> Result<SequencePlan, WeaveLangError> {
+    let sentences_per_block = sentences_per_block.max(1);
+    let mut plan = SequencePlan { sentences_per_block, ..Default::default() };
+    let (learner_profile, mut dictionary) = match start_profile_path {
+        Some(path) => load_profile_snapshot(path)
+            .map_err(|e| e.context(format_args!("Failed to load starting profile {}", path.display())))?,
+        None => (NumericalLearnerProfile::new(), GlobalLemmaDictionary::new()),
+    };
+
+    let lexicon = sequence_bilingual_lexicon(project_config, book_stems)?;
+    let mut seen_stems: HashSet<&str> = HashSet::new();
+    let mut books: Vec<BookLemmaBlocks> = Vec::new();
+    for book_stem in book_stems {
+        if !seen_stems.insert(book_stem) {
+            plan.duplicates_dropped += 1;
+            continue;
+        }
+        let book = match prepare_book(project_config, book_stem, &lexicon) {
+            Ok(book) => book,
+            Err(e) => {
+                plan.skipped.push((book_stem.clone(), e.to_string()));
+                continue;
+            }
+        };
+        let numerical_chapter = preprocessor::merge_into_dictionary(book.numerical_chapter, &book.local_dictionary, &mut dictionary);
+        let blocks = numerical_chapter.sentences_numerical.chunks(sentences_per_block)
+            .map(|block| block.iter().flat_map(|sentence| sentence_lemma_ids(sentence, min_diglot_confidence)).collect())
+            .collect();
+        books.push(BookLemmaBlocks { book_stem: book_stem.clone(), blocks });
+    }
+
+    let initially_known: HashSet<u32> = (0..dictionary.size() as u32)
+        .filter(|&lemma_id| learner_profile.is_lemma_known_or_active(lemma_id))
+        .collect();
+
+    let mut known = initially_known.clone();
+    for book in &books {
+        plan.input_order.push(book.evaluate(&known));
+        book.mark_known(&mut known);
+    }
+
+    let mut known = initially_known;
+    let mut remaining: Vec<&BookLemmaBlocks> = books.iter().collect();
+    while !remaining.is_empty() {
+        let (best_idx, best) = remaining.iter()
+            .map(|book| book.evaluate(&known))
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.max_new_lemmas_per_block.cmp(&b.max_new_lemmas_per_block)
+                .then(a.new_lemmas_per_block().total_cmp(&b.new_lemmas_per_block())))
+            .expect("remaining is not empty");
+        remaining.remove(best_idx).mark_known(&mut known);
+        plan.planned.push(best);
+    }
+    Ok(plan)
+}
+
+//*** END FILE: src/corpus_planner.rs ***//
@@ -0,0 +1,42 @@
+//*** START FILE: src/determinism.rs ***//
+// Helpers for reproducible runs: the same inputs and --seed must give byte-identical
+// corpora, profiles and exports.
+// - Choices that could go either way (e.g. activation candidates with equal scores) are
+//   ordered by tie_break_key, a hash of the run seed and the item, instead of a stateful
+//   RNG, so resumed and lookahead runs see the same order as a straight run.
+// - HashMaps are serialized through sorted_map, since their iteration order changes
+//   from process to process.
+// - Timestamps written into outputs come from reproducible_timestamp.
+
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Seeded sort key for breaking ties between items identified by `value` (SplitMix64).
+/// Different seeds give different, but fixed, orders.
+pub fn tie_break_key(seed: u64, value: u64) -> u64 {
+    let mut z = seed ^ value.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// `serialize_with` helper that writes a HashMap with its keys in ascending order.
+pub fn sorted_map<S, K, V>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Ord + Serialize,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+/// Time to stamp into generated files: SOURCE_DATE_EPOCH (seconds) when set, the Unix
+/// epoch for seeded runs, otherwise None for "now".
+pub fn reproducible_timestamp(seed: Option<u64>) -> Option<SystemTime> {
+    let source_date_epoch = std::env::var("SOURCE_DATE_EPOCH").ok()
+        .and_then(|secs| secs.trim().parse::<u64>().ok())
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+    source_date_epoch.or(seed.map(|_| UNIX_EPOCH))
+}
+//*** END FILE: src/determinism.rs ***//
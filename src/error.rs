@@ -0,0 +1,100 @@
+//*** START FILE: src/error.rs ***//
+// Errors returned by the library API, one variant per stage of the pipeline:
+//   ParseError       - stage files, chapter JSON and plain-text books that cannot be read
+//   ProfileIoError   - profile snapshots, deltas, run state and known-lemma imports
+//   SimulationError  - chapters and blocks the simulation cannot run on
+//   ConfigError      - project configuration and the resources it names (lexicons,
+//                      threshold tables, book sequences, level policies)
+//   GenerationError  - corpus generation and the files it exports
+//
+// The message is what gets shown to the user and already names the cause (e.g. "Failed to
+// open profile snapshot file at ...: No such file or directory"); `source()` hands the
+// underlying error to callers that need to act on it rather than print it.
+
+use std::error::Error;
+use thiserror::Error;
+
+pub type BoxedError = Box<dyn Error + Send + Sync + 'static>;
+
+#[derive(Debug, Error)]
+pub enum WeaveLangError {
+    #[error("{message}")]
+    ParseError { message: String, #[source] source: Option<BoxedError> },
+    #[error("{message}")]
+    ProfileIoError { message: String, #[source] source: Option<BoxedError> },
+    #[error("{message}")]
+    SimulationError { message: String, #[source] source: Option<BoxedError> },
+    #[error("{message}")]
+    ConfigError { message: String, #[source] source: Option<BoxedError> },
+    #[error("{message}")]
+    GenerationError { message: String, #[source] source: Option<BoxedError> },
+}
+
+impl WeaveLangError {
+    pub fn parse(message: impl Into<String>) -> Self {
+        WeaveLangError::ParseError { message: message.into(), source: None }
+    }
+
+    pub fn profile_io(message: impl Into<String>) -> Self {
+        WeaveLangError::ProfileIoError { message: message.into(), source: None }
+    }
+
+    pub fn simulation(message: impl Into<String>) -> Self {
+        WeaveLangError::SimulationError { message: message.into(), source: None }
+    }
+
+    pub fn config(message: impl Into<String>) -> Self {
+        WeaveLangError::ConfigError { message: message.into(), source: None }
+    }
+
+    pub fn generation(message: impl Into<String>) -> Self {
+        WeaveLangError::GenerationError { message: message.into(), source: None }
+    }
+
+    /// Attaches the error this one was caused by.
+    pub fn with_source(mut self, cause: impl Into<BoxedError>) -> Self {
+        let (WeaveLangError::ParseError { source, .. }
+        | WeaveLangError::ProfileIoError { source, .. }
+        | WeaveLangError::SimulationError { source, .. }
+        | WeaveLangError::ConfigError { source, .. }
+        | WeaveLangError::GenerationError { source, .. }) = &mut self;
+        *source = Some(cause.into());
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            WeaveLangError::ParseError { message, .. }
+            | WeaveLangError::ProfileIoError { message, .. }
+            | WeaveLangError::SimulationError { message, .. }
+            | WeaveLangError::ConfigError { message, .. }
+            | WeaveLangError::GenerationError { message, .. } => message,
+        }
+    }
+
+    /// Keeps the variant and cause, putting `context` in front of the message
+    /// (e.g. the file an error came from).
+    pub fn context(mut self, context: impl std::fmt::Display) -> Self {
+        let (WeaveLangError::ParseError { message, .. }
+        | WeaveLangError::ProfileIoError { message, .. }
+        | WeaveLangError::SimulationError { message, .. }
+        | WeaveLangError::ConfigError { message, .. }
+        | WeaveLangError::GenerationError { message, .. }) = &mut self;
+        *message = format!("{}: {}", context, message);
+        self
+    }
+
+    /// The first cause of type `E` in the source chain, e.g. an `std::io::Error` to test
+    /// for `ErrorKind::NotFound`.
+    pub fn find_source<E: Error + 'static>(&self) -> Option<&E> {
+        let mut cause = self.source();
+        while let Some(error) = cause {
+            if let Some(found) = error.downcast_ref::<E>() {
+                return Some(found);
+            }
+            cause = error.source();
+        }
+        None
+    }
+}
+//*** END FILE: src/error.rs ***//
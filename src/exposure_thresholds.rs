@@ -0,0 +1,320 @@
+//*** START FILE: src/exposure_thresholds.rs ***//
+// Threshold tables: how many exposures an Active lemma needs before it counts as Known,
+// so frequent function words can become Known sooner than rare content words.
+// Two file formats are accepted, chosen by extension:
+//
+// CSV (.csv/.tsv/.txt): "lemma,threshold" per line; '#' comments and a header row are skipped.
+//
+// TOML (.toml):
+//   default = 20                   # Optional, otherwise DEFAULT_EXPOSURE_THRESHOLD
+//   frequency_list = "freq.txt"    # One lemma per line, most frequent first (a "lemma,count"
+//                                  # CSV works too); relative to the TOML file
+//   [[bands]]                      # Lemmas ranked 1..=max_rank in the frequency list
+//   max_rank = 100
+//   threshold = 8
+//   [lemmas]                       # Explicit per-lemma values win over bands
+//   de = 4
+//
+// Resolution order: explicit lemma entry, then the first band containing the lemma's rank
+// (bands are sorted by max_rank), then the default. POS-tagged dictionary keys ("banco#noun")
+// match a "banco#noun" entry first and fall back to the plain "banco" entry and rank.
+//
+// Cognates ("animal", "hospital", "importante") need fewer exposures. With a cognate
+// threshold set, lemmas whose viable DIGLOT_MAP glosses are spelled almost the same (see
+// is_cognate) get at most that threshold, unless the table lists the lemma explicitly.
+//
+// Real learners differ. With a LearnerVariability set, every lemma's threshold is scaled by
+// a factor drawn for that lemma from the chosen distribution. The draw is a hash of the
+// learner seed and the lemma, so it does not depend on the order lemmas enter the
+// dictionary and the same seed always describes the same learner.
+
+use crate::determinism::tie_break_key;
+use crate::profile::DEFAULT_EXPOSURE_THRESHOLD;
+use crate::types::llm_data::ProcessedChapter;
+use crate::simulation::dictionary::{edit_distance, fold_accents, normalize_lemma_key, split_lemma_key, GlobalLemmaDictionary};
+use crate::simulation::numerical_types::ExposureThresholds;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use crate::error::WeaveLangError;
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct FrequencyBand {
+    pub max_rank: usize, // Inclusive, 1-based
+    pub threshold: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ThresholdTable {
+    pub default_threshold: Option<u32>,
+    pub lemma_thresholds: HashMap<String, u32>, // Lowercase lemma -> threshold
+    pub bands: Vec<FrequencyBand>,              // Sorted by max_rank
+    pub frequency_ranks: HashMap<String, usize>, // Lowercase lemma -> 1-based rank
+    pub cognate_threshold: Option<u32>,          // None = no cognate detection
+    pub cognates: HashSet<String>,               // Lemma keys found to be cognates, see learn_cognates
+    pub variability: Option<LearnerVariability>, // None = every learner uses the table as is
+}
+
+/// Distribution of the per-lemma factors a simulated learner's thresholds are scaled by.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ThresholdDistribution {
+    #[default]
+    Uniform,   // Factor uniform in [1 - spread, 1 + spread]
+    LogNormal, // Factor exp(spread * z) with z standard normal: skewed towards slow lemmas
+}
+
+impl std::str::FromStr for ThresholdDistribution {
+    type Err = WeaveLangError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "uniform" => Ok(ThresholdDistribution::Uniform),
+            "lognormal" | "log-normal" => Ok(ThresholdDistribution::LogNormal),
+            _ => Err(WeaveLangError::config(format!("Invalid threshold distribution '{}': expected 'uniform' or 'lognormal'.", s))),
+        }
+    }
+}
+
+/// One simulated learner's deviation from the threshold table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LearnerVariability {
+    pub distribution: ThresholdDistribution,
+    pub spread: f32, // Half-width (uniform) or sigma (log-normal) of the factor; 0 = no variation
+    pub seed: u64,
+}
+
+impl LearnerVariability {
+    // Uniform in (0, 1), from the learner seed, the lemma and which of the draws it is.
+    fn unit_draw(&self, lemma: &str, draw: u64) -> f64 {
+        let lemma_hash = lemma.bytes().fold(0xCBF2_9CE4_8422_2325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100_0000_01B3));
+        let bits = tie_break_key(tie_break_key(self.seed, lemma_hash), draw);
+        ((bits >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+    }
+
+    /// Factor this learner's threshold for `lemma` is scaled by; never below 0.
+    pub fn factor(&self, lemma: &str) -> f32 {
+        let spread = self.spread.max(0.0) as f64;
+        let factor = match self.distribution {
+            ThresholdDistribution::Uniform => 1.0 + spread * (2.0 * self.unit_draw(lemma, 0) - 1.0),
+            ThresholdDistribution::LogNormal => {
+                // Box-Muller
+                let z = (-2.0 * self.unit_draw(lemma, 0).ln()).sqrt() * (std::f64::consts::TAU * self.unit_draw(lemma, 1)).cos();
+                (spread * z).exp()
+            }
+        };
+        factor.max(0.0) as f32
+    }
+
+    /// `threshold` scaled for `lemma`, rounded and at least 1.
+    pub fn apply(&self, lemma: &str, threshold: u32) -> u32 {
+        ((threshold as f32 * self.factor(lemma)).round() as u32).max(1)
+    }
+}
+
+// Shortest lemma and gloss compared; short function words match by accident ("a", "no").
+const COGNATE_MIN_LENGTH: usize = 4;
+// Smallest 1 - edit distance / longer length for a lemma and gloss to count as cognates.
+const COGNATE_MIN_SIMILARITY: f32 = 0.75;
+
+/// Whether a target lemma and its base-language gloss look like cognates: both at least
+/// COGNATE_MIN_LENGTH letters and, ignoring case and accents, an edit distance of at most a
+/// quarter of the longer word ("hospital"/"hospital", "nación"/"nation", "importante"/"important").
+pub fn is_cognate(target_lemma: &str, base_word: &str) -> bool {
+    let target = fold_accents(split_lemma_key(target_lemma).0);
+    let base = fold_accents(base_word.trim());
+    let longer = target.len().max(base.len());
+    if target.len() < COGNATE_MIN_LENGTH || base.len() < COGNATE_MIN_LENGTH || target.contains(&' ') || base.contains(&' ') {
+        return false;
+    }
+    1.0 - edit_distance(&target, &base) as f32 / longer as f32 >= COGNATE_MIN_SIMILARITY
+}
+
+#[derive(Deserialize)]
+struct ThresholdTomlFile {
+    default: Option<u32>,
+    frequency_list: Option<String>,
+    #[serde(default)]
+    bands: Vec<FrequencyBand>,
+    #[serde(default)]
+    lemmas: HashMap<String, u32>,
+}
+
+fn normalize_lemma(lemma: &str) -> String {
+    normalize_lemma_key(lemma)
+}
+
+// Trimmed fields of a CSV/TSV line, or None for blank and comment lines.
+fn split_row(line: &str) -> Option<Vec<&str>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    Some(line.split([',', '\t']).map(|f| f.trim()).collect())
+}
+
+/// Ranks from a frequency list: one lemma per line (extra columns are ignored),
+/// most frequent first. Repeated lemmas keep their first rank.
+pub fn load_frequency_ranks(file_path: &Path) -> Result<HashMap<String, usize>, WeaveLangError> {
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| WeaveLangError::config(format!("Failed to read frequency list {:?}: {}", file_path, e)).with_source(e))?;
+    let mut ranks: HashMap<String, usize> = HashMap::new();
+    for fields in contents.lines().filter_map(split_row) {
+        let lemma = normalize_lemma(fields[0]);
+        if !lemma.is_empty() {
+            let next_rank = ranks.len() + 1;
+            ranks.entry(lemma).or_insert(next_rank);
+        }
+    }
+    Ok(ranks)
+}
+
+impl ThresholdTable {
+    /// Adds a fallback for lemmas the table does not cover (exposure_threshold in the config or
+    /// --exposure-threshold). A table's own default wins; without a table one is made from it.
+    pub fn with_fallback_default(table: Option<Self>, fallback: Option<u32>) -> Option<Self> {
+        match (table, fallback) {
+            (Some(mut table), Some(threshold)) => {
+                table.default_threshold.get_or_insert(threshold);
+                Some(table)
+            }
+            (None, Some(threshold)) => Some(ThresholdTable { default_threshold: Some(threshold), ..Default::default() }),
+            (table, None) => table,
+        }
+    }
+
+    /// Turns on cognate detection with `cognate_threshold` (cognate_exposure_threshold in the
+    /// config or --cognate-threshold). Without a table one is made for it.
+    pub fn with_cognate_threshold(table: Option<Self>, cognate_threshold: Option<u32>) -> Option<Self> {
+        match (table, cognate_threshold) {
+            (table, None) => table,
+            (table, Some(threshold)) => Some(ThresholdTable { cognate_threshold: Some(threshold), ..table.unwrap_or_default() }),
+        }
+    }
+
+    /// Records the lemmas of `chapter` that are cognates of one of their viable DIGLOT_MAP
+    /// glosses (confidence at least `min_confidence`). Does nothing unless a cognate threshold
+    /// is set. Returns the number of lemmas newly found.
+    pub fn learn_cognates(&mut self, chapter: &ProcessedChapter, min_confidence: f32) -> usize {
+        if self.cognate_threshold.is_none() {
+            return 0;
+        }
+        let before = self.cognates.len();
+        let entries = chapter.sentences.iter().flat_map(|s| &s.diglot_map).flat_map(|dm| &dm.entries);
+        for entry in entries.filter(|e| e.confidence >= min_confidence) {
+            if is_cognate(&entry.spa_lemma, &entry.eng_word) {
+                self.cognates.insert(normalize_lemma(&entry.spa_lemma));
+            }
+        }
+        self.cognates.len() - before
+    }
+
+    /// Makes the table describe one simulated learner (see LearnerVariability). Without a
+    /// table one is made for it.
+    pub fn with_variability(table: Option<Self>, variability: Option<LearnerVariability>) -> Option<Self> {
+        match (table, variability) {
+            (table, None) => table,
+            (table, Some(variability)) => Some(ThresholdTable { variability: Some(variability), ..table.unwrap_or_default() }),
+        }
+    }
+
+    /// Loads a CSV or TOML table, chosen by the file extension.
+    pub fn load(file_path: &Path) -> Result<Self, WeaveLangError> {
+        let is_toml = file_path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+        if is_toml { Self::load_toml(file_path) } else { Self::load_csv(file_path) }
+    }
+
+    /// Loads 'lemma,threshold' rows (tabs work too). The first row may be a header; any other
+    /// row without a lemma and a whole-number threshold is an error listing every such line.
+    pub fn load_csv(file_path: &Path) -> Result<Self, WeaveLangError> {
+        let contents = fs::read_to_string(file_path)
+            .map_err(|e| WeaveLangError::config(format!("Failed to read threshold table {:?}: {}", file_path, e)).with_source(e))?;
+        let mut table = ThresholdTable::default();
+        let mut invalid_lines: Vec<usize> = Vec::new();
+        let rows = contents.lines().enumerate().filter_map(|(i, l)| split_row(l).map(|f| (i, f)));
+        for (row_idx, (line_idx, fields)) in rows.enumerate() {
+            let threshold = fields.get(1).and_then(|t| t.parse::<u32>().ok());
+            match threshold {
+                Some(threshold) if !fields[0].is_empty() => {
+                    table.lemma_thresholds.insert(normalize_lemma(fields[0]), threshold);
+                }
+                None if row_idx == 0 && fields.len() >= 2 => {} // Header row
+                _ => invalid_lines.push(line_idx + 1),
+            }
+        }
+        if !invalid_lines.is_empty() {
+            let lines: Vec<String> = invalid_lines.iter().map(|line| line.to_string()).collect();
+            return Err(WeaveLangError::config(format!(
+                "Invalid threshold rows at {:?} (line {}): expected 'lemma,threshold'.", file_path, lines.join(", ")
+            )));
+        }
+        Ok(table)
+    }
+
+    pub fn load_toml(file_path: &Path) -> Result<Self, WeaveLangError> {
+        let contents = fs::read_to_string(file_path)
+            .map_err(|e| WeaveLangError::config(format!("Failed to read threshold table {:?}: {}", file_path, e)).with_source(e))?;
+        let parsed: ThresholdTomlFile = toml::from_str(&contents)
+            .map_err(|e| WeaveLangError::config(format!("Failed to parse threshold table {:?}: {}", file_path, e)).with_source(e))?;
+
+        let frequency_ranks = match &parsed.frequency_list {
+            Some(list_path) => {
+                let list_path = file_path.parent().unwrap_or(Path::new("")).join(list_path);
+                load_frequency_ranks(&list_path)?
+            }
+            None => HashMap::new(),
+        };
+        if !parsed.bands.is_empty() && frequency_ranks.is_empty() {
+            warn!("Threshold table {:?} defines frequency bands but no (or an empty) frequency_list; bands are ignored.", file_path);
+        }
+        let mut bands = parsed.bands;
+        bands.sort_by_key(|b| b.max_rank);
+
+        Ok(ThresholdTable {
+            default_threshold: parsed.default,
+            lemma_thresholds: parsed.lemmas.into_iter().map(|(lemma, t)| (normalize_lemma(&lemma), t)).collect(),
+            bands,
+            frequency_ranks,
+            ..Default::default()
+        })
+    }
+
+    /// Threshold for a lemma, or None when the table has no specific value for it.
+    pub fn lookup(&self, lemma: &str) -> Option<u32> {
+        let lemma = normalize_lemma(lemma);
+        if let Some(&threshold) = self.lemma_thresholds.get(&lemma) {
+            return Some(threshold);
+        }
+        let (bare_lemma, pos) = split_lemma_key(&lemma);
+        if pos.is_some() {
+            if let Some(&threshold) = self.lemma_thresholds.get(bare_lemma) {
+                return Some(threshold);
+            }
+        }
+        let rank = self.frequency_ranks.get(&lemma).or_else(|| self.frequency_ranks.get(bare_lemma));
+        let band_threshold = rank.and_then(|&rank| self.bands.iter().find(|band| rank <= band.max_rank).map(|band| band.threshold));
+        match self.cognate_threshold {
+            Some(cognate_threshold) if self.cognates.contains(&lemma) => {
+                let otherwise = band_threshold.or(self.default_threshold).unwrap_or(DEFAULT_EXPOSURE_THRESHOLD);
+                Some(cognate_threshold.min(otherwise))
+            }
+            _ => band_threshold,
+        }
+    }
+
+    /// Resolves the table against the dictionary's current lemmas. Call again after
+    /// the dictionary grows so new lemmas pick up their thresholds.
+    pub fn resolve(&self, dictionary: &GlobalLemmaDictionary) -> ExposureThresholds {
+        let default_threshold = self.default_threshold.unwrap_or(DEFAULT_EXPOSURE_THRESHOLD);
+        let by_lemma_id = dictionary.id_to_str.iter().enumerate()
+            .filter_map(|(id, lemma)| match &self.variability {
+                // Every lemma deviates from the default differently, so each gets an entry.
+                Some(variability) => Some((id as u32, variability.apply(lemma, self.lookup(lemma).unwrap_or(default_threshold)))),
+                None => self.lookup(lemma).map(|threshold| (id as u32, threshold)),
+            })
+            .collect();
+        ExposureThresholds { default_threshold, by_lemma_id }
+    }
+}
+//*** END FILE: src/exposure_thresholds.rs ***//
@@ -0,0 +1,81 @@
+//*** START FILE: src/ffi.rs ***//
+// C ABI for embedding the weaving engine in non-Rust authoring tools (`--features ffi`).
+// Build a shared library with
+//
+//   cargo rustc --lib --release --features ffi --crate-type cdylib
+//
+// Every function takes one NUL-terminated UTF-8 JSON request (see json_api) and returns a
+// newly allocated NUL-terminated JSON response, {"ok": ...} or {"error": "..."}, which the
+// caller must pass to weavelang_string_free. Null is only returned if the response cannot
+// be allocated.
+//
+//   char *weavelang_parse(const char *request);
+//   char *weavelang_simulate_block(const char *request);
+//   char *weavelang_generate_text(const char *request);
+//   void weavelang_string_free(char *response);
+
+use crate::error::WeaveLangError;
+use crate::json_api;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+
+// Decodes the request, runs `handler` and encodes its result. Panics are caught here,
+// since unwinding into a C caller is undefined behaviour.
+// SAFETY: `request` is null or a NUL-terminated string that outlives the call.
+unsafe fn respond(request: *const c_char, handler: fn(&str) -> Result<serde_json::Value, WeaveLangError>) -> *mut c_char {
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| -> Result<serde_json::Value, WeaveLangError> {
+        if request.is_null() {
+            return Err(WeaveLangError::parse("The request is a null pointer."));
+        }
+        let request = CStr::from_ptr(request).to_str()
+            .map_err(|e| WeaveLangError::parse(format!("The request is not valid UTF-8: {}", e)).with_source(e))?;
+        handler(request)
+    }));
+    let response = match outcome {
+        Ok(Ok(value)) => serde_json::json!({ "ok": value }),
+        Ok(Err(error)) => serde_json::json!({ "error": error.to_string() }),
+        Err(_) => serde_json::json!({ "error": "Internal error: the engine panicked." }),
+    };
+    // serde_json escapes control characters, so the JSON text holds no NUL byte.
+    CString::new(response.to_string()).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// json_api::parse: a stage file's chapters (one per CHAPTER:: header).
+///
+/// # Safety
+/// `request` must be null or a NUL-terminated string that stays valid during the call;
+/// the same holds for the other request-taking functions.
+#[no_mangle]
+pub unsafe extern "C" fn weavelang_parse(request: *const c_char) -> *mut c_char {
+    respond(request, json_api::parse)
+}
+
+/// json_api::simulate_block: a run of sentences simulated as one block.
+///
+/// # Safety
+/// See weavelang_parse.
+#[no_mangle]
+pub unsafe extern "C" fn weavelang_simulate_block(request: *const c_char) -> *mut c_char {
+    respond(request, json_api::simulate_block)
+}
+
+/// json_api::generate_text: a run of sentences rendered against the given profile.
+///
+/// # Safety
+/// See weavelang_parse.
+#[no_mangle]
+pub unsafe extern "C" fn weavelang_generate_text(request: *const c_char) -> *mut c_char {
+    respond(request, json_api::generate_text)
+}
+
+/// Frees a response returned by any weavelang_* function. Null is ignored.
+///
+/// # Safety
+/// `response` must come from one of these functions and must not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn weavelang_string_free(response: *mut c_char) {
+    if !response.is_null() {
+        drop(CString::from_raw(response));
+    }
+}
+//*** END FILE: src/ffi.rs ***//
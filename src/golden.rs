@@ -0,0 +1,251 @@
+//*** START FILE: src/golden.rs ***//
+// Golden-corpus regression harness (feature "golden"). Runs a fixed mini-corpus through the
+// full generate pipeline with pinned parameters and compares the result with checked-in
+// golden files, so an algorithm change shows its corpus-level effect as a test failure
+// instead of going unnoticed.
+//
+// A corpus directory holds:
+//   stage/<book>.llm.txt   The books
+//   sequence.txt           Reading order (same format as `generate --sequence`)
+//   expected/              Golden files: every TTS text file of the run plus summary.json
+//                          (final profile counts and level distribution)
+//
+// After an intended change, rerun with WEAVELANG_UPDATE_GOLDEN=1 to rewrite expected/ and
+// review the diff like any other change.
+
+use crate::config::Config;
+use crate::error::WeaveLangError;
+use crate::corpus_generator::{self, GenerationArgs, PassesPerBook, TtsOutputFormat};
+use crate::profile_io::{load_profile_snapshot, SnapshotFormat};
+use crate::progress::NoProgress;
+use crate::simulation::block::BlockBoundaries;
+use crate::simulation::core_algo::{self, CtMetricKind, L4Strategy};
+use crate::simulation::numerical_types::DecayParams;
+use crate::simulation::exporters::epub::EpubChapterMode;
+use crate::simulation::exporters::subtitles::SubtitleTiming;
+use crate::simulation::scheduler::SchedulerParams;
+use crate::simulation::level_policy::LevelPolicy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const UPDATE_GOLDEN_ENV: &str = "WEAVELANG_UPDATE_GOLDEN";
+const SUMMARY_FILE_NAME: &str = "summary.json";
+const GOLDEN_SEED: u64 = 1;
+
+/// Corpus-level numbers compared besides the text.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoldenSummary {
+    pub book_instances: usize,
+    pub blocks: usize,
+    pub final_known: usize,
+    pub final_active: usize, // Active but not yet Known
+    pub dictionary_size: usize,
+    pub level_counts: BTreeMap<String, usize>, // "L1".."L5" over every rendered sentence
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GoldenOutcome {
+    pub texts: BTreeMap<String, String>, // TTS file name -> contents
+    pub summary: GoldenSummary,
+}
+
+/// Pinned generation parameters of the harness: the CLI defaults with small blocks so the
+/// mini-corpus spans several of them, a fixed seed and the level sidecar turned on.
+pub fn golden_generation_args(sequence_path: &Path, work_dir: &Path) -> GenerationArgs {
+    GenerationArgs {
+        sequence_path: sequence_path.to_path_buf(),
+        tts_output_dir: work_dir.join("tts"),
+        profiles_dir: work_dir.join("profiles"),
+        start_profile_path: None,
+        sentences_per_block: 10,
+        block_boundaries: BlockBoundaries::Fixed,
+        max_regen_attempts_per_block: 25,
+        target_ct_threshold: 0.98,
+        min_ct_threshold: 0.0,
+        max_words_to_activate_per_regen: 3,
+        min_diglot_confidence: core_algo::DEFAULT_MIN_DIGLOT_CONFIDENCE,
+        decay: DecayParams { half_life_blocks: 0.0, min_retention: 0.5 },
+        ct_metric: CtMetricKind::default(),
+        l4_strategy: L4Strategy::default(),
+        l4_match_plurals: false,
+        scheduler: SchedulerParams { tie_break_seed: Some(GOLDEN_SEED), ..SchedulerParams::default() },
+        max_new_lemmas_per_100_sentences: None,
+        passes_per_book: PassesPerBook::Fixed(1),
+        max_auto_passes: 10,
+        snapshot_every_blocks: None,
+        parallel_lookahead: 0,
+        resume: false,
+        snapshot_format: SnapshotFormat::Json,
+        level_tags: false,
+        level_policy: LevelPolicy::default(),
+        level_sidecar: true,
+        audio_manifest: false,
+        subtitle_format: None,
+        subtitle_timing: SubtitleTiming::default(),
+        parallel_text_format: None,
+        trace: false,
+        html_output_dir: None,
+        epub_output_dir: None,
+        epub_chapter_mode: EpubChapterMode::Source,
+        output_format: TtsOutputFormat::Text,
+        ssml_base_voice: None,
+        ssml_target_voice: None,
+        exposure_thresholds: None,
+        default_exposure_threshold: None,
+        cognate_exposure_threshold: None,
+        learner_variability: None,
+        anki_output_dir: None,
+        seed_dictionary: None,
+        known_words: None,
+        seed: Some(GOLDEN_SEED),
+        dry_run: false,
+        track_forms: false,
+        chapter_cache_dir: None,
+    }
+}
+
+#[derive(Deserialize)]
+struct SidecarEntry {
+    level: String,
+}
+
+fn read_file(path: &Path) -> Result<String, WeaveLangError> {
+    fs::read_to_string(path)
+        .map_err(|e| WeaveLangError::generation(format!("Failed to read {:?}: {}", path, e)).with_source(e))
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), WeaveLangError> {
+    fs::write(path, contents)
+        .map_err(|e| WeaveLangError::generation(format!("Failed to write {:?}: {}", path, e)).with_source(e))
+}
+
+/// Runs the corpus in `corpus_dir`, writing into `work_dir` (cleared first). Books are
+/// re-parsed unless `chapter_cache_dir` is given.
+pub fn run_golden_corpus(corpus_dir: &Path, work_dir: &Path, chapter_cache_dir: Option<&Path>) -> Result<GoldenOutcome, WeaveLangError> {
+    if work_dir.exists() {
+        fs::remove_dir_all(work_dir)
+            .map_err(|e| WeaveLangError::generation(format!("Failed to clear {:?}: {}", work_dir, e)).with_source(e))?;
+    }
+    let config: Config = toml::from_str(&format!("content_project_dir = {:?}", corpus_dir.display().to_string()))
+        .map_err(|e| WeaveLangError::config(format!("Failed to build the golden corpus config: {}", e)).with_source(e))?;
+    let mut args = golden_generation_args(&corpus_dir.join("sequence.txt"), work_dir);
+    args.chapter_cache_dir = chapter_cache_dir.map(Path::to_path_buf);
+    let report = corpus_generator::run_corpus_generation_with_progress(&config, &args, &mut NoProgress)?;
+    if let Some((book_instance_id, error)) = report.skipped.first() {
+        return Err(WeaveLangError::generation(format!("Golden corpus book instance {} failed: {}", book_instance_id, error)));
+    }
+
+    let mut outcome = GoldenOutcome::default();
+    let mut level_counts: BTreeMap<String, usize> = BTreeMap::new();
+    for book in &report.books {
+        let tts_path = book.tts_path.as_ref()
+            .ok_or_else(|| WeaveLangError::generation(format!("No TTS file for {}", book.book_instance_id)))?;
+        let file_name = tts_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        outcome.texts.insert(file_name, read_file(tts_path)?);
+        let levels_path = book.levels_path.as_ref()
+            .ok_or_else(|| WeaveLangError::generation(format!("No level sidecar for {}", book.book_instance_id)))?;
+        let sidecar: Vec<SidecarEntry> = serde_json::from_str(&read_file(levels_path)?)
+            .map_err(|e| WeaveLangError::generation(format!("Failed to parse level sidecar {:?}: {}", levels_path, e)).with_source(e))?;
+        for entry in sidecar {
+            *level_counts.entry(entry.level).or_insert(0) += 1;
+        }
+    }
+    let last_profile_path = report.books.last().and_then(|book| book.out_profile_path.clone())
+        .ok_or_else(|| WeaveLangError::generation("The golden corpus run saved no out-profile."))?;
+    let (profile, dictionary) = load_profile_snapshot(&last_profile_path)?;
+    outcome.summary = GoldenSummary {
+        book_instances: report.books.len(),
+        blocks: report.total_blocks(),
+        final_known: profile.count_known(),
+        final_active: profile.count_active_only(),
+        dictionary_size: dictionary.size(),
+        level_counts,
+    };
+    Ok(outcome)
+}
+
+/// Rewrites `expected_dir` from `outcome`.
+pub fn write_golden(outcome: &GoldenOutcome, expected_dir: &Path) -> Result<(), WeaveLangError> {
+    if expected_dir.exists() {
+        fs::remove_dir_all(expected_dir)
+            .map_err(|e| WeaveLangError::generation(format!("Failed to clear {:?}: {}", expected_dir, e)).with_source(e))?;
+    }
+    fs::create_dir_all(expected_dir)
+        .map_err(|e| WeaveLangError::generation(format!("Failed to create {:?}: {}", expected_dir, e)).with_source(e))?;
+    for (file_name, text) in &outcome.texts {
+        write_file(&expected_dir.join(file_name), text)?;
+    }
+    let summary = serde_json::to_string_pretty(&outcome.summary)
+        .map_err(|e| WeaveLangError::generation(format!("Failed to serialize the golden summary: {}", e)).with_source(e))?;
+    write_file(&expected_dir.join(SUMMARY_FILE_NAME), &(summary + "\n"))
+}
+
+// First differing line of two texts, for a readable failure message.
+fn first_difference(expected: &str, actual: &str) -> String {
+    let (mut expected_lines, mut actual_lines) = (expected.lines(), actual.lines());
+    for line_number in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => continue,
+            (None, None) => break,
+            (e, a) => return format!("line {}: expected {:?}, got {:?}", line_number, e.unwrap_or("<end>"), a.unwrap_or("<end>")),
+        }
+    }
+    "line endings differ".to_string()
+}
+
+/// Every difference between `outcome` and the golden files, one per entry (empty = match).
+pub fn compare_with_golden(outcome: &GoldenOutcome, expected_dir: &Path) -> Result<Vec<String>, WeaveLangError> {
+    let mut differences = Vec::new();
+    let mut expected_texts: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let read_dir_error = |e: std::io::Error| WeaveLangError::generation(format!("Failed to read golden files in {:?}: {}", expected_dir, e)).with_source(e);
+    for entry in fs::read_dir(expected_dir).map_err(read_dir_error)? {
+        let path = entry.map_err(read_dir_error)?.path();
+        let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        if file_name != SUMMARY_FILE_NAME {
+            expected_texts.insert(file_name, path);
+        }
+    }
+    for (file_name, path) in &expected_texts {
+        match outcome.texts.get(file_name) {
+            Some(actual) => {
+                let expected = read_file(path)?;
+                if &expected != actual {
+                    differences.push(format!("{}: {}", file_name, first_difference(&expected, actual)));
+                }
+            }
+            None => differences.push(format!("{}: not produced by the run", file_name)),
+        }
+    }
+    for file_name in outcome.texts.keys().filter(|name| !expected_texts.contains_key(*name)) {
+        differences.push(format!("{}: produced by the run but has no golden file", file_name));
+    }
+    let summary_path = expected_dir.join(SUMMARY_FILE_NAME);
+    let expected_summary: GoldenSummary = serde_json::from_str(&read_file(&summary_path)?)
+        .map_err(|e| WeaveLangError::generation(format!("Failed to parse {:?}: {}", summary_path, e)).with_source(e))?;
+    if expected_summary != outcome.summary {
+        differences.push(format!("{}: expected {:?}, got {:?}", SUMMARY_FILE_NAME, expected_summary, outcome.summary));
+    }
+    Ok(differences)
+}
+
+/// Runs the corpus and checks it against `<corpus_dir>/expected`, or rewrites the golden
+/// files when WEAVELANG_UPDATE_GOLDEN is set. The error lists every difference.
+pub fn check_golden_corpus(corpus_dir: &Path, work_dir: &Path) -> Result<(), WeaveLangError> {
+    let outcome = run_golden_corpus(corpus_dir, work_dir, None)?;
+    let expected_dir = corpus_dir.join("expected");
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+        return write_golden(&outcome, &expected_dir);
+    }
+    let differences = compare_with_golden(&outcome, &expected_dir)?;
+    if differences.is_empty() {
+        Ok(())
+    } else {
+        Err(WeaveLangError::generation(format!(
+            "Golden corpus {:?} changed ({} difference(s); rerun with {}=1 to accept):\n  {}",
+            corpus_dir, differences.len(), UPDATE_GOLDEN_ENV, differences.join("\n  ")
+        )))
+    }
+}
+//*** END FILE: src/golden.rs ***//
@@ -0,0 +1,220 @@
+//*** START FILE: src/json_api.rs ***//
+// JSON requests and responses of the embedding front-ends (the `serve` HTTP server, ffi
+// for C hosts, wasm for the browser). Each call is stateless: the learner profile and the lemma dictionary travel in
+// the requests and come back in the simulate response, so a host keeps them between blocks.
+// Lemma IDs in a profile only mean something with the dictionary they were assigned by.
+//
+//   parse:          {"file_name", "contents"} -> the stage file's chapters
+//   simulate_block: {"chapter", "dictionary"?, "profile"?, "first_sentence"?,
+//                    "sentence_count"?, "settings"?} -> profile and dictionary after reading
+//                   those sentences as one block, its CT and the rendered text
+//   generate_text:  same request -> the sentences rendered against the profile as it is
+
+use crate::simulation::block::{Block, BlockBoundaries};
+use crate::simulation::core_algo::{CtMetricKind, L4Settings, L4Strategy, DEFAULT_MIN_DIGLOT_CONFIDENCE};
+use crate::simulation::dictionary::GlobalLemmaDictionary;
+use crate::simulation::numerical_types::{DecayParams, NumericalLearnerProfile};
+use crate::simulation::orchestrator::{run_chapters, ChapterInput, OrchestratorParams};
+use crate::simulation::preprocessor;
+use crate::simulation::scheduler::{CorpusFrequency, SchedulerParams};
+use crate::simulation::level_policy::LevelPolicy;
+use crate::simulation::text_generator::{self, SentenceLevelRecord};
+use crate::error::WeaveLangError;
+use crate::parsing::chapter_loader;
+use crate::tokenizer;
+use crate::types::llm_data::{ChapterSpan, ProcessedChapter};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+struct ParseRequest {
+    file_name: String, // Picks the stage format, as in a stage directory
+    contents: String,
+}
+
+/// Simulation settings of a request; missing fields take the `generate` defaults.
+#[derive(Deserialize)]
+#[serde(default)]
+struct BlockSettings {
+    max_regen_attempts_per_block: u32,
+    target_ct_threshold: f32,
+    min_ct_threshold: f32,
+    max_words_to_activate_per_regen: usize,
+    min_diglot_confidence: f32,
+    l4_strategy: L4Strategy,
+    l4_match_plurals: bool,
+    ct_metric: CtMetricKind,
+    level_policy: LevelPolicy,
+    prefix_level_tags: bool,
+    seed: Option<u64>, // Scheduler tie-break seed
+}
+
+impl Default for BlockSettings {
+    fn default() -> Self {
+        Self {
+            max_regen_attempts_per_block: 25,
+            target_ct_threshold: 0.98,
+            min_ct_threshold: 0.0,
+            max_words_to_activate_per_regen: 3,
+            min_diglot_confidence: DEFAULT_MIN_DIGLOT_CONFIDENCE,
+            l4_strategy: L4Strategy::default(),
+            l4_match_plurals: false,
+            ct_metric: CtMetricKind::default(),
+            level_policy: LevelPolicy::default(),
+            prefix_level_tags: false,
+            seed: None,
+        }
+    }
+}
+
+/// A block of a chapter with the learner state it is read against.
+#[derive(Deserialize)]
+struct BlockRequest {
+    chapter: ProcessedChapter,
+    #[serde(default)]
+    dictionary: GlobalLemmaDictionary,
+    #[serde(default)]
+    profile: NumericalLearnerProfile,
+    #[serde(default)]
+    first_sentence: usize,
+    sentence_count: Option<usize>, // None = to the end of the chapter
+    #[serde(default)]
+    settings: BlockSettings,
+}
+
+impl BlockRequest {
+    // The requested sentences, clipped to the chapter.
+    fn span(&self) -> Result<ChapterSpan, WeaveLangError> {
+        let chapter_len = self.chapter.sentences.len();
+        if self.first_sentence >= chapter_len {
+            return Err(WeaveLangError::simulation(format!("first_sentence {} is past the chapter's {} sentences.", self.first_sentence, chapter_len)));
+        }
+        let end = self.sentence_count.map_or(chapter_len, |count| chapter_len.min(self.first_sentence + count));
+        if end == self.first_sentence {
+            return Err(WeaveLangError::simulation("sentence_count must be at least 1."));
+        }
+        Ok(ChapterSpan { title: self.chapter.chapter_title.clone(), sentences: self.first_sentence..end })
+    }
+}
+
+#[derive(Serialize)]
+struct SimulateResponse {
+    profile: NumericalLearnerProfile, // After the block's exposures
+    dictionary: GlobalLemmaDictionary, // With IDs for lemmas the chapter added
+    ct: f32,
+    ct_metric: &'static str,
+    known_lemmas: usize,
+    total_target_lemmas: usize,
+    new_lemmas: usize,
+    text: String,
+    levels: Vec<SentenceLevelRecord>,
+}
+
+#[derive(Serialize)]
+struct GenerateResponse {
+    text: String,
+    sentence_texts: Vec<String>,
+    levels: Vec<SentenceLevelRecord>,
+}
+
+fn parse_request(request: ParseRequest) -> Result<Vec<ProcessedChapter>, WeaveLangError> {
+    chapter_loader::parse_chapters(&request.file_name, &request.contents)
+}
+
+// One orchestrator block over the requested sentences: decay, activation, text and exposures.
+fn simulate_block_request(request: BlockRequest) -> Result<SimulateResponse, WeaveLangError> {
+    let span = request.span()?;
+    let BlockRequest { chapter, mut dictionary, mut profile, settings, .. } = request;
+    let string_chapter = chapter.slice(&span);
+    let numerical_chapter = preprocessor::to_numerical_chapter(&string_chapter, &mut dictionary);
+    let params = OrchestratorParams {
+        sentences_per_block: string_chapter.sentences.len(),
+        block_boundaries: BlockBoundaries::Fixed,
+        passes: 1,
+        max_regen_attempts_per_block: settings.max_regen_attempts_per_block,
+        target_ct_threshold: settings.target_ct_threshold,
+        min_ct_threshold: settings.min_ct_threshold,
+        max_words_to_activate_per_regen: settings.max_words_to_activate_per_regen,
+        min_diglot_confidence: settings.min_diglot_confidence,
+        l4_strategy: settings.l4_strategy,
+        l4_match_plurals: settings.l4_match_plurals,
+        decay: DecayParams::default(),
+        scheduler: SchedulerParams { tie_break_seed: settings.seed, ..SchedulerParams::default() },
+        ct_metric: settings.ct_metric,
+        prefix_level_tags: settings.prefix_level_tags,
+        level_policy: settings.level_policy,
+        halt_on_block_error: true,
+        max_new_lemmas_per_100_sentences: None,
+        recent_introductions: Vec::new(),
+        track_forms: false,
+    };
+    let input = ChapterInput { string_chapter: &string_chapter, numerical_chapter: &numerical_chapter };
+    let results = run_chapters(&[input], &mut profile, &dictionary, &params)?;
+    let block = results.into_iter().flat_map(|result| result.blocks).next()
+        .ok_or_else(|| WeaveLangError::simulation("The block produced no result."))?;
+    if let Some(error) = block.error {
+        return Err(WeaveLangError::simulation(error));
+    }
+    Ok(SimulateResponse {
+        profile,
+        dictionary,
+        ct: block.ct,
+        ct_metric: block.ct_metric_name,
+        known_lemmas: block.known_lemmas,
+        total_target_lemmas: block.total_target_lemmas,
+        new_lemmas: block.new_lemmas,
+        text: block.text,
+        levels: block.levels,
+    })
+}
+
+// Renders the requested sentences against the profile as it is, without simulating them.
+fn generate_text_request(request: BlockRequest) -> Result<GenerateResponse, WeaveLangError> {
+    let span = request.span()?;
+    let BlockRequest { chapter, mut dictionary, profile, settings, .. } = request;
+    let numerical_chapter = preprocessor::to_numerical_chapter(&chapter, &mut dictionary);
+    let block = Block::whole_chapter(&chapter, &numerical_chapter)?.with_positions(span.sentences);
+    let frequency = (settings.l4_strategy == L4Strategy::HighestFrequency).then(|| {
+        let mut own = CorpusFrequency::new();
+        own.add_chapter(&numerical_chapter, settings.min_diglot_confidence);
+        own
+    });
+    let base_tokenizer = tokenizer::tokenizer_for_language(&chapter.language_pair.base);
+    let target_tokenizer = tokenizer::tokenizer_for_language(&chapter.language_pair.target);
+    let l4 = L4Settings {
+        min_diglot_confidence: settings.min_diglot_confidence,
+        base_tokenizer: base_tokenizer.as_ref(),
+        plural_rules: settings.l4_match_plurals.then_some(target_tokenizer.as_ref()),
+        strategy: settings.l4_strategy,
+        corpus_frequency: frequency.as_ref(),
+    };
+    let generated = text_generator::generate_final_text_block(
+        &block, &profile, &l4, settings.prefix_level_tags, &settings.level_policy,
+    )?;
+    Ok(GenerateResponse { text: generated.text, sentence_texts: generated.sentence_texts, levels: generated.sentence_levels })
+}
+
+// Decodes a request, runs `handler` and encodes its response.
+fn handle<Req: DeserializeOwned, Resp: Serialize>(
+    request: &str,
+    handler: impl FnOnce(Req) -> Result<Resp, WeaveLangError>,
+) -> Result<serde_json::Value, WeaveLangError> {
+    let request: Req = serde_json::from_str(request)
+        .map_err(|e| WeaveLangError::parse(format!("Invalid request: {}", e)).with_source(e))?;
+    let response = handler(request)?;
+    serde_json::to_value(response)
+        .map_err(|e| WeaveLangError::generation(format!("Failed to encode the response: {}", e)).with_source(e))
+}
+
+pub fn parse(request: &str) -> Result<serde_json::Value, WeaveLangError> {
+    handle(request, parse_request)
+}
+
+pub fn simulate_block(request: &str) -> Result<serde_json::Value, WeaveLangError> {
+    handle(request, simulate_block_request)
+}
+
+pub fn generate_text(request: &str) -> Result<serde_json::Value, WeaveLangError> {
+    handle(request, generate_text_request)
+}
+//*** END FILE: src/json_api.rs ***//
@@ -0,0 +1,195 @@
+//*** START FILE: src/lemma_timeline.rs ***//
+use crate::profile::LemmaState;
+use crate::simulation::dictionary::GlobalLemmaDictionary;
+use crate::simulation::profile_view::LemmaTransition;
+use serde::Serialize;
+use std::collections::HashMap;
+use crate::error::WeaveLangError;
+use std::fs;
+use std::path::Path;
+
+/// A position in the corpus run: which book instance and block a transition happened in.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimelinePoint {
+    pub book_instance_id: String,
+    pub block_in_book: usize,  // 1-based, as printed in the generation log
+    pub run_block_index: usize, // 1-based, counted across the whole run (used for ordering)
+}
+
+/// When a single lemma was first activated and when it first became Known.
+#[derive(Debug, Clone, Serialize)]
+pub struct LemmaTimelineEntry {
+    pub lemma_id: u32,
+    pub lemma: String,
+    pub first_active: Option<TimelinePoint>,
+    pub first_known: Option<TimelinePoint>,
+}
+
+/// Accumulates lemma state transitions over a corpus generation run.
+/// Lemmas that were already Active/Known in the starting profile never get an entry.
+#[derive(Debug, Clone, Default)]
+pub struct LemmaTimeline {
+    entries: HashMap<u32, LemmaTimelineEntry>,
+}
+
+impl LemmaTimeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the New->Active/Known and Active->Known transitions of a block at the given point.
+    pub fn record_block(&mut self, transitions: &[LemmaTransition], dictionary: &GlobalLemmaDictionary, point: &TimelinePoint) {
+        for transition in transitions.iter().filter(|t| t.before != LemmaState::Known) {
+            let lemma_id = transition.lemma_id;
+            let entry = self.entries.entry(lemma_id).or_insert_with(|| LemmaTimelineEntry {
+                lemma_id,
+                lemma: dictionary.get_str(lemma_id).cloned().unwrap_or_default(),
+                first_active: None,
+                first_known: None,
+            });
+            if transition.before == LemmaState::New && entry.first_active.is_none() {
+                // A word can jump New -> Known within one block; it was still introduced here.
+                entry.first_active = Some(point.clone());
+            }
+            if transition.after == LemmaState::Known && entry.first_known.is_none() {
+                entry.first_known = Some(point.clone());
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries ordered by activation point, then by lemma ID for stable output.
+    pub fn sorted_entries(&self) -> Vec<&LemmaTimelineEntry> {
+        let mut sorted: Vec<&LemmaTimelineEntry> = self.entries.values().collect();
+        sorted.sort_by_key(|e| (
+            e.first_active.as_ref().map_or(usize::MAX, |p| p.run_block_index),
+            e.first_known.as_ref().map_or(usize::MAX, |p| p.run_block_index),
+            e.lemma_id,
+        ));
+        sorted
+    }
+
+    /// Writes the timeline as CSV (one row per lemma).
+    pub fn write_csv(&self, file_path: &Path) -> Result<(), WeaveLangError> {
+        let mut csv = String::from(
+            "lemma_id,lemma,activated_book,activated_block,activated_run_block,known_book,known_block,known_run_block,blocks_to_known\n",
+        );
+        for entry in self.sorted_entries() {
+            let (a_book, a_block, a_run) = point_columns(entry.first_active.as_ref());
+            let (k_book, k_block, k_run) = point_columns(entry.first_known.as_ref());
+            let blocks_to_known = match (&entry.first_active, &entry.first_known) {
+                (Some(a), Some(k)) => k.run_block_index.saturating_sub(a.run_block_index).to_string(),
+                _ => String::new(),
+            };
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                entry.lemma_id, csv_field(&entry.lemma),
+                csv_field(&a_book), a_block, a_run,
+                csv_field(&k_book), k_block, k_run,
+                blocks_to_known
+            ));
+        }
+        fs::write(file_path, csv)
+            .map_err(|e| WeaveLangError::generation(format!("Failed to write lemma timeline CSV to {:?}: {}", file_path, e)).with_source(e))?;
+        Ok(())
+    }
+
+    /// Writes a self-contained HTML page with a filterable timeline (one bar per lemma,
+    /// spanning from activation to Known on the run-wide block axis).
+    pub fn write_html(&self, file_path: &Path) -> Result<(), WeaveLangError> {
+        let entries = self.sorted_entries();
+        let max_run_block = entries.iter()
+            .flat_map(|e| [e.first_active.as_ref(), e.first_known.as_ref()])
+            .flatten()
+            .map(|p| p.run_block_index)
+            .max()
+            .unwrap_or(1);
+        // Escape "</" so lemma text can never close the script element early.
+        let data_json = serde_json::to_string(&entries)
+            .map_err(|e| WeaveLangError::generation(format!("Failed to serialize lemma timeline: {}", e)).with_source(e))?
+            .replace("</", "<\\/");
+
+        let html = HTML_TEMPLATE
+            .replace("%%MAX_BLOCK%%", &max_run_block.to_string())
+            .replace("%%DATA%%", &data_json);
+        fs::write(file_path, html)
+            .map_err(|e| WeaveLangError::generation(format!("Failed to write lemma timeline HTML to {:?}: {}", file_path, e)).with_source(e))?;
+        Ok(())
+    }
+}
+
+fn point_columns(point: Option<&TimelinePoint>) -> (String, String, String) {
+    match point {
+        Some(p) => (p.book_instance_id.clone(), p.block_in_book.to_string(), p.run_block_index.to_string()),
+        None => (String::new(), String::new(), String::new()),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>WeaveLang Lemma Introduction Timeline</title>
+<style>
+body { font-family: sans-serif; margin: 1em; }
+#rows div.row { display: flex; align-items: center; height: 16px; font-size: 12px; }
+#rows div.label { width: 160px; overflow: hidden; white-space: nowrap; }
+#rows div.track { position: relative; flex: 1; height: 10px; background: #eee; }
+#rows div.bar { position: absolute; height: 10px; background: #f0a030; }
+#rows div.bar.known { background: #40a040; }
+</style>
+</head>
+<body>
+<h2>Lemma Introduction Timeline</h2>
+<p>Bars run from the block where a lemma was activated to the block where it became Known (green). Orange bars never reached Known. Hover for details.</p>
+<input id="filter" placeholder="Filter by lemma or book..." size="40">
+<span id="count"></span>
+<div id="rows"></div>
+<script>
+const MAX_BLOCK = %%MAX_BLOCK%%;
+const DATA = %%DATA%%;
+function describe(p) { return p ? p.book_instance_id + " block " + p.block_in_book + " (run block " + p.run_block_index + ")" : "-"; }
+function render() {
+  const f = document.getElementById("filter").value.toLowerCase();
+  const rows = document.getElementById("rows");
+  rows.innerHTML = "";
+  let shown = 0;
+  for (const e of DATA) {
+    const books = (e.first_active ? e.first_active.book_instance_id : "") + " " + (e.first_known ? e.first_known.book_instance_id : "");
+    if (f && !e.lemma.toLowerCase().includes(f) && !books.toLowerCase().includes(f)) continue;
+    const start = e.first_active ? e.first_active.run_block_index : (e.first_known ? e.first_known.run_block_index : 1);
+    const end = e.first_known ? e.first_known.run_block_index : MAX_BLOCK;
+    const row = document.createElement("div"); row.className = "row";
+    const label = document.createElement("div"); label.className = "label"; label.textContent = e.lemma;
+    const track = document.createElement("div"); track.className = "track";
+    const bar = document.createElement("div"); bar.className = e.first_known ? "bar known" : "bar";
+    bar.style.left = ((start - 1) / MAX_BLOCK * 100) + "%";
+    bar.style.width = (Math.max(end - start + 1, 1) / MAX_BLOCK * 100) + "%";
+    row.title = e.lemma + " (ID " + e.lemma_id + ")\nActivated: " + describe(e.first_active) + "\nKnown: " + describe(e.first_known);
+    track.appendChild(bar); row.appendChild(label); row.appendChild(track); rows.appendChild(row);
+    shown++;
+  }
+  document.getElementById("count").textContent = shown + " / " + DATA.length + " lemmas";
+}
+document.getElementById("filter").addEventListener("input", render);
+render();
+</script>
+</body>
+</html>
+"#;
+//*** END FILE: src/lemma_timeline.rs ***//
@@ -0,0 +1,193 @@
+//*** START FILE: src/lemmatizer.rs ***//
+// Lemmatizers turn target-language word forms into lemmas, so raw text can get SimSL-style
+// lemma lists without an LLM annotating every word (see preprocessor::lemmatize_raw).
+// Implementations:
+//   FormTableLemmatizer     Forms the stage files already pair with lemmas (DIGLOT_MAP
+//                           form->lemma and SimSL lines that line up with their segment)
+//   SpanishPluralLemmatizer Pure-Rust rules reducing regular Spanish plurals ("casas" -> "casa")
+//   CommandLemmatizer       An external program (spaCy, Stanza, a stemmer...) that reads one
+//                           word form per line on stdin and answers one lemma per line
+//   LemmatizerChain         Tries several in order; the first answer wins
+use crate::error::WeaveLangError;
+use crate::tokenizer::{self, Tokenizer};
+use crate::types::llm_data::ProcessedChapter;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Mutex;
+use tracing::warn;
+
+pub trait Lemmatizer: Send + Sync {
+    /// Lemma of a lowercased word form, or None if this lemmatizer does not know it.
+    fn lemma_of(&self, form: &str) -> Option<String>;
+}
+
+/// Maps inflected forms to lemmas using what the stage files already state:
+/// DIGLOT_MAP form->lemma pairs and SimSL lines whose word count matches their segment.
+pub struct FormTableLemmatizer {
+    tokenizer: Box<dyn Tokenizer>, // Target-language rules
+    form_to_lemma: HashMap<String, String>,
+}
+
+impl FormTableLemmatizer {
+    pub fn new(target_language: &str) -> Self {
+        Self { tokenizer: tokenizer::tokenizer_for_language(target_language), form_to_lemma: HashMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.form_to_lemma.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.form_to_lemma.is_empty()
+    }
+
+    pub fn learn_from_chapter(&mut self, chapter: &ProcessedChapter) {
+        for sentence in &chapter.sentences {
+            for entry in sentence.diglot_map.iter().flat_map(|dm| &dm.entries) {
+                let form = entry.exact_spa_form.trim().to_lowercase();
+                let lemma = entry.spa_lemma.trim().to_lowercase();
+                if !form.is_empty() && !lemma.is_empty() && !form.contains(' ') {
+                    self.form_to_lemma.entry(form).or_insert(lemma);
+                }
+            }
+            for segment in &sentence.sim_s_segments {
+                let Some(sl) = sentence.sim_s_lemmas.iter().find(|sl| sl.segment_id == segment.id) else { continue };
+                let forms = self.tokenizer.tokenize(&segment.text);
+                if forms.len() == sl.lemmas.len() {
+                    for (form, lemma) in forms.iter().zip(&sl.lemmas) {
+                        self.form_to_lemma.entry(form.text.to_lowercase()).or_insert_with(|| lemma.to_lowercase());
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Lemmatizer for FormTableLemmatizer {
+    fn lemma_of(&self, form: &str) -> Option<String> {
+        self.form_to_lemma.get(form).cloned()
+    }
+}
+
+/// Regular Spanish plurals only: "-ces" -> "-z" (luces -> luz), "-es" after a consonant
+/// (ciudades -> ciudad), "-s" after a vowel (casas -> casa). Anything else, including verb
+/// forms, is left to other lemmatizers; put this one last in a chain.
+pub struct SpanishPluralLemmatizer;
+
+impl Lemmatizer for SpanishPluralLemmatizer {
+    fn lemma_of(&self, form: &str) -> Option<String> {
+        let chars: Vec<char> = form.chars().collect();
+        if chars.len() < 4 || !form.ends_with('s') {
+            return None;
+        }
+        let is_vowel = |c: char| "aeiouáéíóú".contains(c);
+        if let Some(stem) = form.strip_suffix("ces") {
+            return Some(format!("{}z", stem));
+        }
+        if let Some(stem) = form.strip_suffix("es") {
+            if stem.chars().last().is_some_and(|c| !is_vowel(c)) {
+                return Some(stem.to_string());
+            }
+        }
+        let stem = &form[..form.len() - 1];
+        stem.chars().last().filter(|c| is_vowel(*c)).map(|_| stem.to_string())
+    }
+}
+
+/// Runs an external lemmatizer as a long-lived child process: each lookup writes the form
+/// and a newline to its stdin and reads one line back. An empty answer means "unknown".
+/// Answers are cached, and the first I/O error disables the command for the rest of the run.
+pub struct CommandLemmatizer {
+    command_line: String,
+    process: Mutex<Option<(Child, ChildStdin, BufReader<ChildStdout>)>>,
+    cache: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl CommandLemmatizer {
+    /// Starts `command_line` (program and arguments separated by whitespace, no quoting).
+    pub fn spawn(command_line: &str) -> Result<Self, WeaveLangError> {
+        let mut parts = command_line.split_whitespace();
+        let program = parts.next().ok_or_else(|| WeaveLangError::config("The lemmatizer command is empty."))?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| WeaveLangError::config(format!("Failed to start lemmatizer command '{}': {}", command_line, e)).with_source(e))?;
+        let stdin = child.stdin.take().ok_or_else(|| WeaveLangError::config("Lemmatizer command has no stdin."))?;
+        let stdout = BufReader::new(child.stdout.take().ok_or_else(|| WeaveLangError::config("Lemmatizer command has no stdout."))?);
+        Ok(Self {
+            command_line: command_line.to_string(),
+            process: Mutex::new(Some((child, stdin, stdout))),
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn ask(&self, form: &str) -> Option<String> {
+        let mut process = self.process.lock().unwrap_or_else(|e| e.into_inner());
+        let (_, stdin, stdout) = process.as_mut()?;
+        let mut answer = String::new();
+        let result = writeln!(stdin, "{}", form)
+            .and_then(|_| stdin.flush())
+            .and_then(|_| stdout.read_line(&mut answer));
+        match result {
+            Ok(bytes_read) if bytes_read > 0 => {
+                let lemma = answer.trim().to_lowercase();
+                (!lemma.is_empty()).then_some(lemma)
+            }
+            Ok(_) | Err(_) => {
+                warn!("Lemmatizer command '{}' stopped answering; continuing without it.", self.command_line);
+                if let Some((mut child, _, _)) = process.take() {
+                    let _ = child.kill();
+                }
+                None
+            }
+        }
+    }
+}
+
+impl Lemmatizer for CommandLemmatizer {
+    fn lemma_of(&self, form: &str) -> Option<String> {
+        if let Some(cached) = self.cache.lock().unwrap_or_else(|e| e.into_inner()).get(form) {
+            return cached.clone();
+        }
+        let lemma = self.ask(form);
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).insert(form.to_string(), lemma.clone());
+        lemma
+    }
+}
+
+impl Drop for CommandLemmatizer {
+    fn drop(&mut self) {
+        // Closing stdin lets well-behaved commands exit; wait so no zombie is left.
+        if let Some((mut child, stdin, _)) = self.process.get_mut().unwrap_or_else(|e| e.into_inner()).take() {
+            drop(stdin);
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Tries each lemmatizer in order.
+#[derive(Default)]
+pub struct LemmatizerChain {
+    lemmatizers: Vec<Box<dyn Lemmatizer>>,
+}
+
+impl LemmatizerChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, lemmatizer: Box<dyn Lemmatizer>) -> Self {
+        self.lemmatizers.push(lemmatizer);
+        self
+    }
+}
+
+impl Lemmatizer for LemmatizerChain {
+    fn lemma_of(&self, form: &str) -> Option<String> {
+        self.lemmatizers.iter().find_map(|lemmatizer| lemmatizer.lemma_of(form))
+    }
+}
+//*** END FILE: src/lemmatizer.rs ***//
@@ -0,0 +1,241 @@
+//*** START FILE: src/lexicon.rs ***//
+// Optional offline target-language lexicon with English glosses, used to gloss
+// lemmas that never appear in a DIGLOT_MAP. The source is a Wiktionary extract in kaikki.org JSONL form
+// (one JSON object per line with "word", "pos", "lang_code" and "senses[].glosses").
+// Parsing a full dump is slow, so the result is cached as compact JSON in the
+// content project directory and reused until the dump changes.
+
+use crate::simulation::dictionary::{normalize_lemma_key, split_lemma_key, GlobalLemmaDictionary};
+use crate::types::llm_data::ProcessedChapter;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::error::WeaveLangError;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Once, OnceLock};
+use std::time::UNIX_EPOCH;
+use tracing::warn;
+
+pub const LEXICON_CACHE_FILE_NAME: &str = "lexicon_cache.json";
+// Glosses kept per part of speech; Wiktionary lists many rare senses.
+const MAX_GLOSSES_PER_ENTRY: usize = 5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LexiconEntry {
+    pub pos: String,
+    pub glosses: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Lexicon {
+    entries: HashMap<String, Vec<LexiconEntry>>, // Keyed by lowercase lemma
+}
+
+impl Lexicon {
+    /// Parses a kaikki.org-style JSONL dump. Lines for other languages than `lang_code`
+    /// or without glosses are skipped; malformed lines are counted and reported once.
+    pub fn load_wiktionary_jsonl(file_path: &Path, lang_code: &str) -> Result<Self, WeaveLangError> {
+        let file = fs::File::open(file_path)
+            .map_err(|e| WeaveLangError::config(format!("Failed to open lexicon dump {:?}: {}", file_path, e)).with_source(e))?;
+        let mut lexicon = Lexicon::default();
+        let mut malformed_lines = 0usize;
+
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| WeaveLangError::config(format!("Failed to read lexicon dump {:?}: {}", file_path, e)).with_source(e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value: serde_json::Value = match serde_json::from_str(&line) {
+                Ok(v) => v,
+                Err(_) => { malformed_lines += 1; continue; }
+            };
+            if value.get("lang_code").and_then(|c| c.as_str()).is_some_and(|c| c != lang_code) {
+                continue;
+            }
+            let Some(word) = value.get("word").and_then(|w| w.as_str()) else { continue };
+            let pos = value.get("pos").and_then(|p| p.as_str()).unwrap_or("").to_string();
+            let glosses: Vec<String> = value.get("senses")
+                .and_then(|s| s.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|sense| sense.get("glosses").and_then(|g| g.as_array()))
+                .filter_map(|g| g.first().and_then(|first| first.as_str()))
+                .map(|g| g.trim().to_string())
+                .filter(|g| !g.is_empty())
+                .take(MAX_GLOSSES_PER_ENTRY)
+                .collect();
+            if !glosses.is_empty() {
+                lexicon.insert(word, LexiconEntry { pos, glosses });
+            }
+        }
+
+        if malformed_lines > 0 {
+            warn!("Skipped {} malformed line(s) in lexicon dump {:?}.", malformed_lines, file_path);
+        }
+        Ok(lexicon)
+    }
+
+    pub fn insert(&mut self, lemma: &str, entry: LexiconEntry) {
+        let senses = self.entries.entry(lemma.trim().to_lowercase()).or_default();
+        if !senses.contains(&entry) {
+            senses.push(entry);
+        }
+    }
+
+    /// All entries (one per part of speech) for a lemma, case-insensitively.
+    /// A POS-tagged dictionary key ("perro#noun") looks up its bare lemma.
+    pub fn lookup(&self, lemma: &str) -> Option<&[LexiconEntry]> {
+        self.entries.get(&split_lemma_key(lemma).0.to_lowercase()).map(|v| v.as_slice())
+    }
+
+    /// Short one-line gloss, e.g. "dog (noun)". For a POS-tagged key the entry with
+    /// that part of speech is preferred.
+    pub fn short_gloss(&self, lemma: &str) -> Option<String> {
+        let entries = self.lookup(lemma)?;
+        let entry = entry_for_pos(entries, split_lemma_key(lemma).1)?;
+        let gloss = entry.glosses.first()?;
+        if entry.pos.is_empty() {
+            Some(gloss.clone())
+        } else {
+            Some(format!("{} ({})", gloss, entry.pos))
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Lexicon entries for dictionary lemmas that are not in `covered_lemmas`
+    /// (typically the ones already glossed by a DIGLOT_MAP), keyed by lemma ID.
+    pub fn fill_missing(
+        &self,
+        dictionary: &GlobalLemmaDictionary,
+        covered_lemmas: &HashSet<String>,
+    ) -> BTreeMap<u32, &[LexiconEntry]> {
+        dictionary.id_to_str.iter().enumerate()
+            .filter(|(_, lemma)| !covered_lemmas.contains(*lemma))
+            .filter_map(|(id, lemma)| self.lookup(lemma).map(|entries| (id as u32, entries)))
+            .collect()
+    }
+}
+
+/// The entry whose part of speech matches `pos` (case-insensitively), else the first one.
+pub fn entry_for_pos<'a>(entries: &'a [LexiconEntry], pos: Option<&str>) -> Option<&'a LexiconEntry> {
+    pos.and_then(|pos| entries.iter().find(|e| e.pos.eq_ignore_ascii_case(pos)))
+        .or_else(|| entries.first())
+}
+
+/// Target-language lemmas that a chapter's DIGLOT_MAP already pairs with a base-language word.
+pub fn diglot_glossed_lemmas(chapter: &ProcessedChapter) -> HashSet<String> {
+    chapter.sentences.iter()
+        .flat_map(|s| &s.diglot_map)
+        .flat_map(|segment_map| &segment_map.entries)
+        .filter(|entry| !entry.eng_word.trim().is_empty())
+        .map(|entry| normalize_lemma_key(&entry.spa_lemma))
+        .filter(|lemma| !lemma.is_empty())
+        .collect()
+}
+
+#[derive(Serialize, Deserialize)]
+struct LexiconCacheFile {
+    source_path: String,
+    lang_code: String,
+    source_len: u64,
+    source_modified_secs: u64,
+    lexicon: Lexicon,
+}
+
+fn source_fingerprint(file_path: &Path) -> Result<(u64, u64), WeaveLangError> {
+    let metadata = fs::metadata(file_path)
+        .map_err(|e| WeaveLangError::config(format!("Failed to stat lexicon dump {:?}: {}", file_path, e)).with_source(e))?;
+    let modified_secs = metadata.modified().ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs());
+    Ok((metadata.len(), modified_secs))
+}
+
+/// Loads the lexicon from `cache_path` if it was built from the current `dump_path`
+/// for `lang_code`, otherwise parses the dump and rewrites the cache.
+pub fn load_with_cache(dump_path: &Path, cache_path: &Path, lang_code: &str) -> Result<Lexicon, WeaveLangError> {
+    let (source_len, source_modified_secs) = source_fingerprint(dump_path)?;
+    let source_path = dump_path.to_string_lossy().into_owned();
+
+    if let Ok(contents) = fs::read_to_string(cache_path) {
+        match serde_json::from_str::<LexiconCacheFile>(&contents) {
+            Ok(cache) if cache.source_path == source_path
+                && cache.lang_code == lang_code
+                && cache.source_len == source_len
+                && cache.source_modified_secs == source_modified_secs => return Ok(cache.lexicon),
+            Ok(_) => {} // Stale; rebuild below
+            Err(e) => warn!("Ignoring unreadable lexicon cache {:?}: {}", cache_path, e),
+        }
+    }
+
+    let lexicon = Lexicon::load_wiktionary_jsonl(dump_path, lang_code)?;
+    let cache = LexiconCacheFile { source_path, lang_code: lang_code.to_string(), source_len, source_modified_secs, lexicon };
+    match serde_json::to_string(&cache) {
+        Ok(json) => {
+            if let Err(e) = fs::write(cache_path, json) {
+                warn!("Failed to write lexicon cache {:?}: {}", cache_path, e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize lexicon cache: {}", e),
+    }
+    Ok(cache.lexicon)
+}
+
+/// A lexicon that is only read from disk the first time it is needed, either on the
+/// calling thread (get) or on a background thread (start_loading, then try_get).
+/// A failed load is remembered so the dump is not re-parsed on every lookup.
+pub struct LazyLexicon {
+    dump_path: PathBuf,
+    cache_path: PathBuf,
+    lang_code: String,
+    loaded: Arc<OnceLock<Result<Lexicon, String>>>,
+    background_load: Once,
+}
+
+impl LazyLexicon {
+    pub fn new(dump_path: PathBuf, cache_path: PathBuf, lang_code: &str) -> Self {
+        Self { dump_path, cache_path, lang_code: lang_code.to_string(), loaded: Arc::new(OnceLock::new()), background_load: Once::new() }
+    }
+
+    /// Uses the standard cache location inside a content project directory.
+    pub fn for_project(dump_path: PathBuf, content_project_dir: &Path, lang_code: &str) -> Self {
+        Self::new(dump_path, content_project_dir.join(LEXICON_CACHE_FILE_NAME), lang_code)
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.get().is_some()
+    }
+
+    /// The lexicon, loading it on this thread if needed (waiting for a background load).
+    pub fn get(&self) -> Result<&Lexicon, &str> {
+        self.loaded
+            .get_or_init(|| load_with_cache(&self.dump_path, &self.cache_path, &self.lang_code).map_err(|e| e.to_string()))
+            .as_ref()
+            .map_err(|e| e.as_str())
+    }
+
+    /// Starts loading on a background thread; later calls do nothing.
+    pub fn start_loading(&self) {
+        self.background_load.call_once(|| {
+            let (loaded, dump_path, cache_path, lang_code) =
+                (Arc::clone(&self.loaded), self.dump_path.clone(), self.cache_path.clone(), self.lang_code.clone());
+            std::thread::spawn(move || {
+                loaded.get_or_init(|| load_with_cache(&dump_path, &cache_path, &lang_code).map_err(|e| e.to_string()));
+            });
+        });
+    }
+
+    /// The lexicon if loading has finished; never blocks.
+    pub fn try_get(&self) -> Option<Result<&Lexicon, &str>> {
+        self.loaded.get().map(|loaded| loaded.as_ref().map_err(|e| e.as_str()))
+    }
+}
+//*** END FILE: src/lexicon.rs ***//
@@ -1,23 +1,73 @@
 //*** START FILE: src/lib.rs ***//
 
 // Declare all modules that are part of this library
+pub mod error; // WeaveLangError, returned by the library API
 pub mod config;
 pub mod types {
+    pub mod lemma_key;
     pub mod llm_data;
+    pub mod protected_span;
 }
 pub mod parsing {
     pub mod llm_parser;
+    pub mod validation;
+    pub mod llm_writer;
+    pub mod chapter_loader;
+    pub mod plain_text;
 }
 pub mod simulation {
+    pub mod block;
     pub mod dictionary;
     pub mod numerical_types;
     pub mod preprocessor;
     pub mod core_algo;
+    pub mod lemma_bitset;
+    pub mod profile_view;
+    pub mod level_policy;
     pub mod text_generator;
+    pub mod scheduler;
+    pub mod orchestrator;
+    pub mod trace;
+    pub mod exporters {
+        pub mod html;
+        pub mod epub;
+        pub mod ssml;
+        pub mod anki;
+        pub mod manifest;
+        pub mod subtitles;
+        pub mod parallel;
+    }
 }
 pub mod profile;
 pub mod profile_io;       // We added this
 pub mod corpus_generator; // We added this
+pub mod chapter_cache;
+pub mod lemma_timeline;
+pub mod qa_report;
+pub mod session;
+pub mod tokenizer;
+pub mod unicode_norm;
+pub mod lemmatizer;
+pub mod lexicon;
+pub mod bilingual_lexicon;
+pub mod exposure_thresholds;
+pub mod stage_repair;
+pub mod determinism;
+pub mod progress;
+pub mod corpus_analysis;
+pub mod corpus_planner;
+pub mod sweep;
+pub mod monte_carlo;
+#[cfg(feature = "golden")]
+pub mod golden; // Golden-corpus regression harness used by tests/golden_corpus.rs
+pub mod json_api; // Stateless JSON requests shared by serve, ffi and wasm
+pub mod server;
+#[cfg(feature = "ffi")]
+pub mod ffi; // C ABI with JSON in/out for non-Rust hosts
+#[cfg(feature = "wasm")]
+pub mod wasm; // wasm-bindgen exports for a browser demo
+#[cfg(feature = "python")]
+pub mod python; // PyO3 module for notebooks and parameter sweeps
 
 // You might also choose to re-export key items for convenience if main.rs
 // or other external crates were to use this library, e.g.:
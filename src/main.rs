@@ -2,32 +2,63 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 // --- Standard Library Imports ---
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
 use std::fs; // Renamed from std_fs for direct use
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
 
 // --- External Crate Imports ---
 use clap::Parser;
 use eframe::{egui, App as EframeApp, NativeOptions};
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+use tracing_subscriber::EnvFilter;
 
 // --- Crate-Specific Imports (from our library `weavelang_rust_gui`) ---
 use weavelang_rust_gui::config::{Config}; // Import specific item and module
+use weavelang_rust_gui::chapter_cache::ChapterCache;
 use weavelang_rust_gui::corpus_generator;
-// profile_io is used by corpus_generator
+use weavelang_rust_gui::corpus_analysis;
+use weavelang_rust_gui::corpus_planner;
+use weavelang_rust_gui::lexicon::{self, LazyLexicon};
+use weavelang_rust_gui::monte_carlo;
+use weavelang_rust_gui::exposure_thresholds::{ThresholdDistribution, ThresholdTable};
+use weavelang_rust_gui::profile_io;
+use weavelang_rust_gui::parsing::llm_parser::{DiagnosticSeverity, ParseDiagnostic};
+use weavelang_rust_gui::parsing::chapter_loader;
+use weavelang_rust_gui::stage_repair;
+use weavelang_rust_gui::sweep;
+use weavelang_rust_gui::session::{self, GuiSession, GuiSimulationSettings, StageFileStatus, ValidationOutcome};
 
 // For the GUI (WeaveLangApp and its methods)
-use weavelang_rust_gui::types::llm_data::{
-    ProcessedChapter as GuiStringProcessedChapter,
-    ProcessedSentence as GuiStringProcessedSentence, // If used by GUI state/methods
-};
+use weavelang_rust_gui::types::llm_data::ProcessedChapter as GuiStringProcessedChapter;
 use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary as GuiGlobalLemmaDictionary;
+use weavelang_rust_gui::simulation::dictionary::{describe_lemma_key, split_lemma_key};
+use weavelang_rust_gui::profile::LemmaState;
 use weavelang_rust_gui::simulation::numerical_types::{
+    DecayParams,
     NumericalChapter as GuiNumericalChapter,
     NumericalLearnerProfile as GuiNumericalLearnerProfile,
-    NumericalProcessedSentence as GuiNumericalProcessedSentence, // For Vec type in orchestrator
 };
-use weavelang_rust_gui::profile::LemmaState as GuiLemmaState; // For orchestrator logic
+use weavelang_rust_gui::simulation::block::BlockBoundaries;
+use weavelang_rust_gui::simulation::core_algo::{self, CtMetricKind, L4Strategy, SimulationBlockResult};
+use weavelang_rust_gui::simulation::orchestrator::{
+    extend_introduction_history, run_chapters_observed, BlockInfo, ChapterInput, OrchestratorObserver, OrchestratorParams,
+};
+use weavelang_rust_gui::simulation::preprocessor;
+use weavelang_rust_gui::simulation::profile_view::LemmaTransition;
+use weavelang_rust_gui::simulation::scheduler::{self, SchedulerParams};
+use weavelang_rust_gui::simulation::trace::TraceEvent;
+use weavelang_rust_gui::simulation::level_policy::LevelPolicy;
+use weavelang_rust_gui::simulation::text_generator::GeneratedTextBlock;
+use weavelang_rust_gui::simulation::exporters::epub::EpubChapterMode;
+use weavelang_rust_gui::simulation::exporters::html;
+use weavelang_rust_gui::simulation::exporters::parallel::ParallelTextFormat;
+use weavelang_rust_gui::simulation::exporters::subtitles::{SubtitleFormat, SubtitleTiming};
+use weavelang_rust_gui::tokenizer::{self, Tokenizer};
+use weavelang_rust_gui::types::llm_data::ProcessedSentence;
 
 
 // --- CLI Argument Structures ---
@@ -38,16 +69,190 @@ struct Cli {
     command: Option<Commands>,
     #[arg(short, long, value_name = "FILE", default_value = "config.toml")]
     config: PathBuf,
+    /// Override a config key for this run, e.g. --set target_ct_threshold=0.95 or
+    /// --set language_pair.target=fr (repeatable; wins over the file and WEAVELANG_* variables)
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true)]
+    config_overrides: Vec<String>,
+    /// Log verbosity: error, warn, info, debug (adds regen attempts) or trace (adds every activation
+    /// and withheld lemma), or a filter such as "info,weavelang_rust_gui::simulation=debug"
+    /// (default: RUST_LOG, else info)
+    #[arg(long, value_name = "LEVEL", global = true)]
+    log_level: Option<String>,
+    /// Log one JSON object per line, with the book, block and regen_attempt spans of each event
+    #[arg(long, global = true)]
+    log_json: bool,
+}
+
+// Logs go to stderr, so commands that print results (validate --json, analyze) keep stdout clean.
+fn init_logging(log_level: Option<&str>, json: bool) -> Result<(), Box<dyn Error>> {
+    let filter = match log_level {
+        Some(directives) => EnvFilter::try_new(directives)?,
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+    };
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter).with_writer(std::io::stderr);
+    let installed = if json {
+        subscriber.json().with_current_span(true).with_span_list(true).try_init()
+    } else {
+        subscriber.with_target(false).try_init()
+    };
+    installed.map_err(|e| e as Box<dyn Error>)
 }
 
 #[derive(Parser, Debug)]
 enum Commands {
     Gui,
-    Generate(GenerateCliArgs),
+    Generate(Box<GenerateCliArgs>),
+    /// Drop dictionary lemmas no profile references and compact IDs across linked snapshots
+    Gc(GcCliArgs),
+    /// Re-lemmatize, normalize and validate existing .llm.txt files into a separate directory
+    RepairStage(RepairStageCliArgs),
+    /// Strictly parse stage files and report malformed lines before generation
+    Validate(ValidateCliArgs),
+    /// Report lemma frequencies, new lemmas per block and coverage for a book sequence
+    Analyze(AnalyzeCliArgs),
+    /// Suggest a reading order that keeps new lemmas per block low and write it as a sequence file
+    Plan(PlanCliArgs),
+    /// Inspect the lemma dictionary of a profile snapshot
+    #[command(subcommand)]
+    Dict(DictCommands),
+    /// Compare learner profile snapshots
+    #[command(subcommand)]
+    Profile(ProfileCommands),
+    /// Serve parsing, block simulation and text generation as JSON over HTTP (see server.rs)
+    Serve(ServeCliArgs),
+    /// Run the corpus once per combination of a parameter grid and write a CSV comparing the outcomes
+    Sweep(Box<SweepCliArgs>),
+    /// Run the corpus for many simulated learners with randomized per-lemma thresholds and report
+    /// the spread of their vocabulary growth
+    MonteCarlo(Box<MonteCarloCliArgs>),
+}
+
+#[derive(Parser, Debug, Clone)]
+struct MonteCarloCliArgs {
+    /// Settings shared by every learner; learner i writes into <tts-output-dir>/learner_<i> and
+    /// <profiles-dir>/learner_<i>. --seed also seeds the learners' thresholds
+    #[command(flatten)]
+    generate: GenerateCliArgs,
+    /// Number of simulated learners
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    learners: usize,
+    /// Distribution of the factor each lemma's threshold is scaled by: "uniform" or "lognormal"
+    #[arg(long, value_name = "uniform|lognormal", default_value = "uniform")]
+    threshold_distribution: ThresholdDistribution,
+    /// Half-width (uniform) or sigma (lognormal) of the threshold factor, e.g. 0.5 = thresholds
+    /// between 50% and 150% of the table's
+    #[arg(long, value_name = "SPREAD", default_value_t = 0.5)]
+    threshold_spread: f32,
+    /// CSV with the spread of Known lemmas after each book instance across learners
+    #[arg(long, value_name = "FILE", default_value = "monte_carlo.csv")]
+    output: PathBuf,
+    /// Learners simulated at the same time (0 = one per CPU)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    jobs: usize,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct SweepCliArgs {
+    /// Settings shared by every combination; each one writes into <tts-output-dir>/<combination>
+    /// and <profiles-dir>/<combination>
+    #[command(flatten)]
+    generate: GenerateCliArgs,
+    /// Target CT thresholds to try, e.g. "0.95,0.97,0.99" (default: the run's --target-ct-threshold)
+    #[arg(long, value_name = "CT,...", value_delimiter = ',')]
+    ct_values: Vec<f32>,
+    /// Exposure thresholds to try for lemmas the threshold table leaves out (default: the run's --exposure-threshold)
+    #[arg(long, value_name = "N,...", value_delimiter = ',')]
+    exposure_values: Vec<u32>,
+    /// Activation rates (--max-words-to-activate-per-regen) to try (default: the run's)
+    #[arg(long, value_name = "N,...", value_delimiter = ',')]
+    activation_values: Vec<usize>,
+    /// CSV with one row per combination: final Known/Active counts and sentences per level
+    #[arg(long, value_name = "FILE", default_value = "sweep.csv")]
+    output: PathBuf,
+    /// Combinations run at the same time (0 = one per CPU)
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    jobs: usize,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct ServeCliArgs {
+    /// Address to listen on
+    #[arg(long, value_name = "HOST:PORT", default_value = "127.0.0.1:8787")]
+    address: String,
+}
+
+#[derive(Parser, Debug)]
+enum ProfileCommands {
+    /// Report lemmas added, removed, changing state or exposures between snapshot A and B
+    Diff(ProfileDiffCliArgs),
+    /// Combine two snapshots into one learner state (further state and higher exposures win)
+    Merge(ProfileMergeCliArgs),
+}
+
+#[derive(Parser, Debug, Clone)]
+struct ProfileMergeCliArgs {
+    /// First snapshot; its dictionary IDs are kept
+    #[arg(value_name = "A")]
+    a: PathBuf,
+    /// Second snapshot; its lemmas are remapped onto A's dictionary
+    #[arg(value_name = "B")]
+    b: PathBuf,
+    /// Output snapshot
+    #[arg(short, long, value_name = "FILE")]
+    output: PathBuf,
+    /// Encoding of the merged snapshot: "json" or "binary"
+    #[arg(long, value_name = "json|binary", default_value = "json")]
+    format: profile_io::SnapshotFormat,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct ProfileDiffCliArgs {
+    /// Earlier snapshot (*.profile.json, *.profile.bin or *.delta.json)
+    #[arg(value_name = "A")]
+    before: PathBuf,
+    /// Later snapshot
+    #[arg(value_name = "B")]
+    after: PathBuf,
+    /// Print the diff as JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Parser, Debug)]
+enum DictCommands {
+    /// Write a snapshot's id<->lemma mapping as TSV (importable with generate --seed-dictionary)
+    Export(DictExportCliArgs),
+    /// Report near-duplicate lemmas (accent variants, one-letter typos) and optionally merge them
+    Audit(DictAuditCliArgs),
+}
+
+#[derive(Parser, Debug, Clone)]
+struct DictAuditCliArgs {
+    /// Full profile snapshots (*.profile.json or *.profile.bin) sharing one dictionary lineage
+    #[arg(required = true, value_name = "SNAPSHOT")]
+    snapshots: Vec<PathBuf>,
+    /// Most edits between two lemmas of 5+ letters for them to be reported (accent-only differences always are)
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    max_distance: usize,
+    /// Merge each cluster into its lowest-ID lemma and write the reconciled snapshots here
+    #[arg(long, value_name = "DIR")]
+    merge_into: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct DictExportCliArgs {
+    /// Full profile snapshot (*.profile.json or *.profile.bin)
+    #[arg(value_name = "SNAPSHOT")]
+    snapshot: PathBuf,
+    /// Output file; prints to stdout when omitted
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
 }
 
 #[derive(Parser, Debug, Clone)]
 struct GenerateCliArgs {
+    /// Sequence file: one book stem per line, optionally with per-book parameters that override
+    /// the run's, e.g. "book1 ct=0.95 min_ct=0.85 spb=150"
     #[arg(short, long, value_name = "FILE")]
     sequence: PathBuf,
     #[arg(long, value_name = "DIR", default_value = "./tts_output")]
@@ -56,22 +261,332 @@ struct GenerateCliArgs {
     profiles_dir: PathBuf,
     #[arg(long, value_name = "FILE")]
     start_profile: Option<PathBuf>,
-    #[arg(long, default_value_t = 200)]
-    sentences_per_block: usize,
+    /// Sentences per simulation block (default: sentences_per_block in the config, else 200)
+    #[arg(long, value_name = "N")]
+    sentences_per_block: Option<usize>,
+    /// End each block at the paragraph end closest to --sentences-per-block, at most N sentences
+    /// shorter or longer (default: paragraph_block_tolerance in the config, else fixed-size blocks)
+    #[arg(long, value_name = "N")]
+    paragraph_block_tolerance: Option<usize>,
     #[arg(long, default_value_t = 25)]
     max_regen_attempts_per_block: u32,
-    #[arg(long, default_value_t = 0.98)]
-    target_ct_threshold: f32,
+    /// Ceiling of the CT band: blocks at or above it activate new words
+    /// (default: target_ct_threshold in the config, else 0.98)
+    #[arg(long, value_name = "CT")]
+    target_ct_threshold: Option<f32>,
+    /// Floor of the CT band: blocks below it withhold their least exposed Active words,
+    /// dropping sentences to lower levels (default: min_ct_threshold in the config, else 0 = no floor)
+    #[arg(long, value_name = "CT")]
+    min_ct_threshold: Option<f32>,
     #[arg(long, default_value_t = 3)]
     max_words_to_activate_per_regen: usize,
+    /// Minimum DIGLOT_MAP confidence for an L4 substitution ((Y) = 1.0, (N) = 0.0, (Y:0.8) = 0.8)
+    #[arg(long, default_value_t = core_algo::DEFAULT_MIN_DIGLOT_CONFIDENCE)]
+    min_diglot_confidence: f32,
+    /// Blocks after which an unseen Known lemma's retention halves (0 disables forgetting)
+    #[arg(long, default_value_t = 0.0)]
+    decay_half_life_blocks: f32,
+    /// Known lemmas whose retention falls below this slide back to Active
+    #[arg(long, default_value_t = 0.5)]
+    decay_min_retention: f32,
+    /// Preferred gap, in sentences, between activating a lemma and its next occurrence
+    #[arg(long, default_value_t = SchedulerParams::default().target_interval_sentences)]
+    activation_target_interval: usize,
+    /// Weight of block frequency x remaining-corpus frequency when choosing words to activate
+    /// (0 = off; above 0 pre-scans the whole sequence)
+    #[arg(long, default_value_t = SchedulerParams::default().remaining_frequency_weight)]
+    remaining_frequency_weight: f32,
+    /// Most new lemmas introduced within any 100 consecutive sentences, across blocks and books
+    /// (default: max_new_lemmas_per_100_sentences in the config, else no cap)
+    #[arg(long, value_name = "N")]
+    max_new_lemmas_per_100_sentences: Option<f32>,
+    /// Times each book is read (wrapping around), or "auto" to repeat until saturation
+    #[arg(long, value_name = "N|auto", default_value = "1")]
+    passes_per_book: corpus_generator::PassesPerBook,
+    #[arg(long, default_value_t = 10)]
+    max_auto_passes: usize,
+    /// Also write a delta profile snapshot every N blocks inside each book
+    #[arg(long, value_name = "N")]
+    snapshot_every: Option<usize>,
+    /// Parse and convert up to N upcoming books on worker threads while the current one is simulated
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    parallel_lookahead: usize,
+    /// Continue an interrupted run from run_state.json in the profiles directory
+    #[arg(long)]
+    resume: bool,
+    /// Encoding of profile snapshots: "json" (inspectable) or "binary" (compact, for large dictionaries)
+    /// (default: profile_format in the config, else json)
+    #[arg(long, value_name = "json|binary")]
+    profile_format: Option<profile_io::SnapshotFormat>,
+    /// Prefix every sentence of the TTS text with the level it was rendered at ([L1]..[L5])
+    #[arg(long)]
+    level_tags: bool,
+    /// Levels sentences may be rendered at, in the order tried, e.g. "L1,L2,L3,L5" (default: levels in the config, else L1..L5).
+    /// The last level is the fallback used when no other applies and must be L1, L2 or L5
+    #[arg(long, value_name = "LEVELS")]
+    levels: Option<LevelPolicy>,
+    /// Comprehensible share (Known target tokens plus base-language words) a sentence needs at a
+    /// level; below it the sentence falls back a level (default: min_sentence_ct in the config, else 0 = off)
+    #[arg(long, value_name = "CT")]
+    min_sentence_ct: Option<f32>,
+    /// Write a <tts file>.levels.json sidecar mapping each sentence ID to its level
+    #[arg(long)]
+    level_sidecar: bool,
+    /// Write a <tts file>.manifest.json listing the sentences in TTS order with their sentence ID,
+    /// level and lemma IDs, for mapping audio timestamps back to sentences and vocabulary
+    #[arg(long)]
+    audio_manifest: bool,
+    /// Also write subtitles for each TTS file (<tts file>.srt or .vtt), one cue per sentence, with
+    /// timings estimated from --subtitle-wpm
+    #[arg(long, value_name = "srt|vtt")]
+    subtitles: Option<SubtitleFormat>,
+    /// Reading speed the subtitle timings assume, in words per minute
+    #[arg(long, value_name = "WPM", default_value_t = SubtitleTiming::default().words_per_minute)]
+    subtitle_wpm: f32,
+    /// Silence between subtitle cues, in seconds
+    #[arg(long, value_name = "SECONDS", default_value_t = SubtitleTiming::default().gap_seconds)]
+    subtitle_gap: f32,
+    /// Also write each woven sentence next to its pure SimE for review, as <tts file>.parallel.tsv
+    /// or an HTML table (<tts file>.parallel.html)
+    #[arg(long, value_name = "tsv|html")]
+    parallel_text: Option<ParallelTextFormat>,
+    /// Write a <book instance>.trace.jsonl per book instance into --profiles-dir: one JSON object
+    /// per block with its structured simulation events (regen attempts, activations, level choices)
+    #[arg(long)]
+    trace: bool,
+    /// Also write each book instance as an HTML page whose target-language words show their gloss on hover
+    #[arg(long, value_name = "DIR")]
+    html_output_dir: Option<PathBuf>,
+    /// Also write each book instance as an EPUB, with metadata from [book_metadata] in the config
+    #[arg(long, value_name = "DIR")]
+    epub_output_dir: Option<PathBuf>,
+    /// EPUB chapter layout: "source" (one chapter per pass through the book) or "block"
+    #[arg(long, value_name = "source|block", default_value = "source")]
+    epub_chapters: EpubChapterMode,
+    /// TTS input format: "text", or "ssml" with target-language stretches wrapped in <lang>/<voice>
+    /// (default: output_format in the config, else text)
+    #[arg(long, value_name = "text|ssml")]
+    output_format: Option<corpus_generator::TtsOutputFormat>,
+    /// SSML voice name for the whole document (base language)
+    #[arg(long, value_name = "NAME")]
+    ssml_base_voice: Option<String>,
+    /// SSML voice name for target-language stretches; without it they get <lang xml:lang>
+    #[arg(long, value_name = "NAME")]
+    ssml_target_voice: Option<String>,
+    /// Per-lemma exposure threshold table (CSV or TOML); overrides exposure_thresholds_path in the config
+    #[arg(long, value_name = "FILE")]
+    exposure_thresholds: Option<PathBuf>,
+    /// Exposures an Active lemma needs to become Known when the threshold table has no entry or
+    /// default for it (default: exposure_threshold in the config, else 20)
+    #[arg(long, value_name = "N")]
+    exposure_threshold: Option<u32>,
+    /// Most exposures a detected cognate needs to become Known (default: cognate_exposure_threshold in the config, else no cognate detection)
+    #[arg(long, value_name = "N")]
+    cognate_threshold: Option<u32>,
+    /// Comprehensibility metric compared against --target-ct-threshold: token, type, frequency or sentence (default: ct_metric in the config, else token)
+    #[arg(long, value_name = "METRIC")]
+    ct_metric: Option<CtMetricKind>,
+    /// How L4 picks among a segment's viable diglot words: first, lowest-exposure or highest-frequency (default: l4_strategy in the config, else lowest-exposure)
+    #[arg(long, value_name = "STRATEGY")]
+    l4_strategy: Option<L4Strategy>,
+    /// Let L4 replace plural words ("dogs" for a "dog" entry) with the pluralized target form (also l4_match_plurals in the config)
+    #[arg(long)]
+    l4_match_plurals: bool,
+    /// Write an Anki-importable TSV deck per book instance with the lemmas it activated and their first sentence
+    #[arg(long, value_name = "DIR")]
+    anki_output_dir: Option<PathBuf>,
+    /// Dictionary TSV (from `dict export`, or one lemma per line) whose lemmas get IDs before the first book
+    #[arg(long, value_name = "FILE")]
+    seed_dictionary: Option<PathBuf>,
+    /// Word list (one word per line, or CSV with the word first) marked Known in the starting profile
+    #[arg(long, value_name = "FILE")]
+    known_words: Option<PathBuf>,
+    /// Seed for tie-breaking between equally ranked choices; the same inputs and seed give byte-identical output
+    #[arg(long, value_name = "N")]
+    seed: Option<u64>,
+    /// Parse and convert every book in the sequence and report counts and estimated blocks, writing nothing
+    #[arg(long)]
+    dry_run: bool,
+    /// Count exposures per target-language form ("tengo", "tienes") besides per lemma and report form coverage (also track_forms in the config)
+    #[arg(long)]
+    track_forms: bool,
+    /// Parse and convert every book again instead of reusing the copies cached in <content_project_dir>/.cache/chapters
+    #[arg(long)]
+    no_chapter_cache: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct GcCliArgs {
+    /// Full profile snapshots (*.profile.json or *.profile.bin) sharing one dictionary lineage
+    #[arg(required = true, value_name = "SNAPSHOT")]
+    snapshots: Vec<PathBuf>,
+    #[arg(long, value_name = "DIR")]
+    output_dir: PathBuf,
+    /// Chapter cache directory whose books' lemmas are kept (repeatable; e.g. <content_project_dir>/.cache/chapters)
+    #[arg(long = "chapter-cache", value_name = "DIR")]
+    chapter_caches: Vec<PathBuf>,
+    /// Stage file whose lemmas are kept (repeatable)
+    #[arg(long = "stage", value_name = "FILE")]
+    stage_files: Vec<PathBuf>,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct RepairStageCliArgs {
+    /// Directory containing the .llm.txt stage files
+    #[arg(value_name = "DIR")]
+    stage_dir: PathBuf,
+    /// Where repaired copies and repair_report.txt are written (default: <DIR>/repaired)
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+    /// External lemmatizer for words the stage files never pair with a lemma: reads one word
+    /// form per line on stdin and prints its lemma (empty line if unknown)
+    /// (default: lemmatizer_command in the config, else none)
+    #[arg(long, value_name = "COMMAND")]
+    lemmatizer_command: Option<String>,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct ValidateCliArgs {
+    /// Validate every book listed in this sequence file (resolved against the project stage directory)
+    #[arg(short, long, value_name = "FILE")]
+    sequence: Option<PathBuf>,
+    /// Individual stage files (.llm.txt or .json) to validate
+    #[arg(value_name = "STAGE_FILE")]
+    files: Vec<PathBuf>,
+    /// Print diagnostics as JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct AnalyzeCliArgs {
+    /// Sequence file listing the books to analyze (resolved against the project stage directory)
+    #[arg(short, long, value_name = "FILE")]
+    sequence: PathBuf,
+    /// Sentences per block used for the new-lemmas-per-block rate
+    #[arg(long, default_value_t = 10)]
+    sentences_per_block: usize,
+    /// Minimum DIGLOT_MAP confidence for an entry's lemma to count
+    #[arg(long, default_value_t = core_algo::DEFAULT_MIN_DIGLOT_CONFIDENCE)]
+    min_diglot_confidence: f32,
+    /// Number of most frequent lemmas to list
+    #[arg(long, default_value_t = 20)]
+    top: usize,
+    /// Print the analysis as JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+struct PlanCliArgs {
+    /// Sequence file listing the books to order (default: every book in the project stage directory)
+    #[arg(short, long, value_name = "FILE")]
+    sequence: Option<PathBuf>,
+    /// Book stems to order, in addition to those of --sequence
+    #[arg(value_name = "BOOK_STEM")]
+    books: Vec<String>,
+    /// Where to write the suggested sequence file (printed to stdout if omitted)
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+    /// Learner profile snapshot whose Known/Active lemmas do not count as new
+    #[arg(long, value_name = "FILE")]
+    start_profile: Option<PathBuf>,
+    /// Sentences per block used to measure new-lemma spikes
+    #[arg(long, default_value_t = 10)]
+    sentences_per_block: usize,
+    /// Minimum DIGLOT_MAP confidence for an entry's lemma to count
+    #[arg(long, default_value_t = core_algo::DEFAULT_MIN_DIGLOT_CONFIDENCE)]
+    min_diglot_confidence: f32,
+}
+
+#[derive(serde::Serialize)]
+struct FileValidationReport {
+    file: PathBuf,
+    parse_error: Option<String>,
+    diagnostics: Vec<ParseDiagnostic>,
+}
+
+fn run_validate_command(validate_args: &ValidateCliArgs, config: Option<&Config>) -> Result<bool, Box<dyn Error>> {
+    let mut paths: Vec<PathBuf> = validate_args.files.clone();
+    if let Some(sequence_path) = &validate_args.sequence {
+        let config = config.ok_or("A project config is required to resolve --sequence book stems.")?;
+        let mut seen = std::collections::HashSet::new();
+        for stem in corpus_generator::load_book_sequence(sequence_path)? {
+            if seen.insert(stem.clone()) { // Books repeated in the sequence are validated once
+                paths.push(corpus_generator::stage_file_path(config, &stem));
+            }
+        }
+    }
+    if paths.is_empty() {
+        return Err("Nothing to validate: pass --sequence and/or stage files.".into());
+    }
+
+    let mut reports: Vec<FileValidationReport> = Vec::new();
+    for path in paths {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let report = match fs::read_to_string(&path) {
+            Ok(contents) => {
+                let (chapter_result, diagnostics) = chapter_loader::validate_chapter_file(&file_name, &contents);
+                FileValidationReport { file: path, parse_error: chapter_result.err().map(|e| e.to_string()), diagnostics }
+            }
+            Err(e) => FileValidationReport { file: path, parse_error: Some(format!("Failed to read file: {}", e)), diagnostics: Vec::new() },
+        };
+        reports.push(report);
+    }
+
+    if validate_args.json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        for report in &reports {
+            println!("{}", report.file.display());
+            if let Some(e) = &report.parse_error {
+                println!("  ERROR: {}", e);
+            }
+            for diagnostic in &report.diagnostics {
+                println!("  {}", diagnostic);
+            }
+        }
+    }
+    let has_errors = reports.iter().any(|r| {
+        r.parse_error.is_some() || r.diagnostics.iter().any(|d| d.severity == DiagnosticSeverity::Error)
+    });
+    Ok(!has_errors)
 }
 
 // --- GUI Application (WeaveLangApp struct) ---
+const PROFILE_HISTORY_LIMIT: usize = 20; // Undo steps kept
+
+// The learner profile and dictionary as they were before an action that replaced them.
+struct ProfileSnapshot {
+    action: String, // What the snapshot undoes, e.g. "Simulation run"
+    profile: GuiNumericalLearnerProfile,
+    dictionary: GuiGlobalLemmaDictionary,
+}
+
+// Bounded undo/redo stacks of profile snapshots. Recording a new action clears redo.
+#[derive(Default)]
+struct ProfileHistory {
+    undo: VecDeque<ProfileSnapshot>,
+    redo: Vec<ProfileSnapshot>,
+}
+
+impl ProfileHistory {
+    fn record(&mut self, action: &str, profile: &GuiNumericalLearnerProfile, dictionary: &GuiGlobalLemmaDictionary) {
+        if self.undo.len() == PROFILE_HISTORY_LIMIT {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(ProfileSnapshot { action: action.to_string(), profile: profile.clone(), dictionary: dictionary.clone() });
+        self.redo.clear();
+    }
+}
+
 struct WeaveLangApp {
     config: Option<Config>,
     config_error: Option<String>,
     content_path_display: String,
     stage_files: Vec<PathBuf>,
+    stage_file_statuses: HashMap<PathBuf, StageFileStatus>,
+    session: GuiSession,
     selected_stage_file: Option<PathBuf>,
     selected_file_content: String,
     current_string_chapter: Option<GuiStringProcessedChapter>,
@@ -79,16 +594,54 @@ struct WeaveLangApp {
     global_lemma_dictionary: GuiGlobalLemmaDictionary,
     learner_profile: GuiNumericalLearnerProfile,
     parser_display_error: Option<String>,
+    validation_warnings: Vec<String>,
     scan_error: Option<String>,
     processed_json_output: String,
     woven_text_output: String,
+    woven_preview: Vec<PreviewSentence>,
+    show_woven_preview: bool,
+    inspected_lemma: Option<u32>, // Word clicked in the colored preview
+    profile_history: ProfileHistory,
     simulation_log_output: String,
     generation_error: Option<String>,
     sentences_per_block: usize,
     max_simulation_loops: u32,
     max_regen_attempts_per_block: u32,
     target_ct_threshold: f32,
+    min_ct_threshold: f32,
+    min_sentence_ct: f32,
     max_words_to_activate_per_regen: usize,
+    min_diglot_confidence: f32,
+    decay_params: DecayParams,
+    scheduler_params: SchedulerParams,
+    max_new_lemmas_per_100_sentences: Option<f32>,
+    vocabulary_growth: Vec<VocabularyGrowthPoint>,
+    block_traces: Vec<TracedBlock>,
+    trace_block_filter: String,
+    trace_lemma_filter: String,
+    trace_kind_filter: Option<&'static str>,
+    trace_selection: Option<(usize, u32)>, // (index into block_traces, lemma ID) of the clicked event
+    sequence_path: String,
+    sequence_books: Vec<SequenceBookRun>,
+    sequence_status: Option<String>,
+    simulation_job: Option<SimulationJob>,
+    prefix_level_tags: bool,
+    level_policy: LevelPolicy,
+    ct_metric: CtMetricKind,
+    l4_strategy: L4Strategy,
+    l4_match_plurals: bool,
+    track_forms: bool, // From the config; the dictionary learns forms from loaded chapters
+    block_boundaries: BlockBoundaries, // From the config's paragraph_block_tolerance
+    lexicon: Option<LazyLexicon>,
+    exposure_thresholds: Option<ThresholdTable>,
+    lexicon_query: String,
+    lexicon_output: String,
+    pending_lexicon_action: Option<LexiconAction>, // Runs once the background load finishes
+    word_state_query: String,
+    known_words_path: String,
+    known_words_status: Option<String>,
+    profile_snapshot_path: String,
+    profile_snapshot_status: Option<String>,
 }
 
 impl WeaveLangApp {
@@ -101,11 +654,50 @@ impl WeaveLangApp {
             Some(conf) => format!("Content Dir: {}", conf.content_project_dir),
             None => config_error_msg.clone().unwrap_or_else(|| "Config not loaded or error during load.".to_string()),
         };
-        Self {
+        // The session lives under the content project; without a config nothing is persisted.
+        let session_val = app_config.as_ref().map_or_else(GuiSession::default, |conf| {
+            session::load_session(&session::session_file_path(conf)).unwrap_or_else(|e| {
+                eprintln!("Warning: {}. Starting with an empty session.", e);
+                GuiSession::default()
+            })
+        });
+        let lexicon_val = app_config.as_ref().and_then(|conf| {
+            conf.lexicon_dump_path.as_ref().map(|dump| {
+                LazyLexicon::for_project(PathBuf::from(dump), Path::new(&conf.content_project_dir), &conf.language_pair.target)
+            })
+        });
+        let ct_metric_val = app_config.as_ref().map(|conf| conf.ct_metric).unwrap_or_default();
+        let l4_strategy_val = app_config.as_ref().map(|conf| conf.l4_strategy).unwrap_or_default();
+        let l4_match_plurals_val = app_config.as_ref().is_some_and(|conf| conf.l4_match_plurals);
+        let track_forms_val = app_config.as_ref().is_some_and(|conf| conf.track_forms);
+        let block_boundaries_val = app_config.as_ref().and_then(|conf| conf.paragraph_block_tolerance)
+            .map_or(BlockBoundaries::Fixed, |tolerance| BlockBoundaries::Paragraphs { tolerance });
+        let max_new_lemmas_per_100_sentences_val = app_config.as_ref().and_then(|conf| conf.max_new_lemmas_per_100_sentences);
+        let level_policy_val = app_config.as_ref().map(|conf| conf.levels.clone()).unwrap_or_default();
+        let min_sentence_ct_val = app_config.as_ref().map_or(0.0, |conf| conf.min_sentence_ct);
+        let exposure_thresholds_val = app_config.as_ref()
+            .and_then(|conf| conf.exposure_thresholds_path.as_ref())
+            .and_then(|table_path| match ThresholdTable::load(Path::new(table_path)) {
+                Ok(table) => Some(table),
+                Err(e) => {
+                    eprintln!("Warning: {}. Using the default exposure threshold for every lemma.", e);
+                    None
+                }
+            });
+        let exposure_thresholds_val = ThresholdTable::with_fallback_default(
+            exposure_thresholds_val, app_config.as_ref().and_then(|conf| conf.exposure_threshold));
+        let exposure_thresholds_val = ThresholdTable::with_cognate_threshold(
+            exposure_thresholds_val, app_config.as_ref().and_then(|conf| conf.cognate_exposure_threshold));
+        let sentences_per_block_val = app_config.as_ref().and_then(|conf| conf.sentences_per_block).unwrap_or(100);
+        let target_ct_threshold_val = app_config.as_ref().and_then(|conf| conf.target_ct_threshold).unwrap_or(0.98);
+        let min_ct_threshold_val = app_config.as_ref().and_then(|conf| conf.min_ct_threshold).unwrap_or(0.0);
+        let mut app = Self {
             config: app_config,
             config_error: config_error_msg,
             content_path_display: content_path_display_val,
             stage_files: Vec::new(),
+            stage_file_statuses: HashMap::new(),
+            session: session_val,
             selected_stage_file: None,
             selected_file_content: String::new(),
             current_string_chapter: None,
@@ -113,65 +705,289 @@ impl WeaveLangApp {
             global_lemma_dictionary: GuiGlobalLemmaDictionary::new(),
             learner_profile: GuiNumericalLearnerProfile::new(),
             parser_display_error: None,
+            validation_warnings: Vec::new(),
             scan_error: None,
             processed_json_output: String::new(),
             woven_text_output: String::new(),
+            woven_preview: Vec::new(),
+            show_woven_preview: false,
+            inspected_lemma: None,
+            profile_history: ProfileHistory::default(),
             simulation_log_output: String::new(),
             generation_error: None,
-            sentences_per_block: 100,
+            sentences_per_block: sentences_per_block_val,
             max_simulation_loops: 10,
             max_regen_attempts_per_block: 25,
-            target_ct_threshold: 0.98,
+            target_ct_threshold: target_ct_threshold_val,
+            min_ct_threshold: min_ct_threshold_val,
+            min_sentence_ct: min_sentence_ct_val,
             max_words_to_activate_per_regen: 3,
+            min_diglot_confidence: core_algo::DEFAULT_MIN_DIGLOT_CONFIDENCE,
+            decay_params: DecayParams::default(),
+            scheduler_params: SchedulerParams::default(),
+            max_new_lemmas_per_100_sentences: max_new_lemmas_per_100_sentences_val,
+            vocabulary_growth: Vec::new(),
+            block_traces: Vec::new(),
+            trace_block_filter: String::new(),
+            trace_lemma_filter: String::new(),
+            trace_kind_filter: None,
+            trace_selection: None,
+            sequence_path: String::new(),
+            sequence_books: Vec::new(),
+            sequence_status: None,
+            simulation_job: None,
+            prefix_level_tags: false,
+            level_policy: level_policy_val,
+            ct_metric: ct_metric_val,
+            l4_strategy: l4_strategy_val,
+            l4_match_plurals: l4_match_plurals_val,
+            track_forms: track_forms_val,
+            block_boundaries: block_boundaries_val,
+            lexicon: lexicon_val,
+            exposure_thresholds: exposure_thresholds_val,
+            lexicon_query: String::new(),
+            lexicon_output: String::new(),
+            pending_lexicon_action: None,
+            word_state_query: String::new(),
+            known_words_path: String::new(),
+            known_words_status: None,
+            profile_snapshot_path: String::new(),
+            profile_snapshot_status: None,
+        };
+        app.restore_session_state();
+        app
+    }
+
+    fn simulation_settings(&self) -> GuiSimulationSettings {
+        GuiSimulationSettings {
+            sentences_per_block: self.sentences_per_block,
+            max_simulation_loops: self.max_simulation_loops,
+            max_regen_attempts_per_block: self.max_regen_attempts_per_block,
+            target_ct_threshold: self.target_ct_threshold,
+            min_ct_threshold: self.min_ct_threshold,
+            min_sentence_ct: self.min_sentence_ct,
+            max_words_to_activate_per_regen: self.max_words_to_activate_per_regen,
+            max_new_lemmas_per_100_sentences: self.max_new_lemmas_per_100_sentences,
+            min_diglot_confidence: self.min_diglot_confidence,
+            decay_half_life_blocks: self.decay_params.half_life_blocks,
+            decay_min_retention: self.decay_params.min_retention,
+            prefix_level_tags: self.prefix_level_tags,
+            ct_metric: self.ct_metric,
+            l4_strategy: self.l4_strategy,
+            l4_match_plurals: self.l4_match_plurals,
+            target_interval_sentences: self.scheduler_params.target_interval_sentences,
+            remaining_frequency_weight: self.scheduler_params.remaining_frequency_weight,
+            tie_break_seed: self.scheduler_params.tie_break_seed,
+            sequence_path: self.sequence_path.clone(),
+        }
+    }
+
+    fn apply_simulation_settings(&mut self, settings: &GuiSimulationSettings) {
+        self.sentences_per_block = settings.sentences_per_block;
+        self.max_simulation_loops = settings.max_simulation_loops;
+        self.max_regen_attempts_per_block = settings.max_regen_attempts_per_block;
+        self.target_ct_threshold = settings.target_ct_threshold;
+        self.min_ct_threshold = settings.min_ct_threshold;
+        self.min_sentence_ct = settings.min_sentence_ct;
+        self.max_words_to_activate_per_regen = settings.max_words_to_activate_per_regen;
+        self.max_new_lemmas_per_100_sentences = settings.max_new_lemmas_per_100_sentences;
+        self.min_diglot_confidence = settings.min_diglot_confidence;
+        self.decay_params.half_life_blocks = settings.decay_half_life_blocks;
+        self.decay_params.min_retention = settings.decay_min_retention;
+        self.prefix_level_tags = settings.prefix_level_tags;
+        self.ct_metric = settings.ct_metric;
+        self.l4_strategy = settings.l4_strategy;
+        self.l4_match_plurals = settings.l4_match_plurals;
+        self.scheduler_params.target_interval_sentences = settings.target_interval_sentences;
+        self.scheduler_params.remaining_frequency_weight = settings.remaining_frequency_weight;
+        self.scheduler_params.tie_break_seed = settings.tie_break_seed;
+        self.sequence_path = settings.sequence_path.clone();
+    }
+
+    // Picks up where the last session left off: its parameters, learner profile and
+    // dictionary, and the stage file that was selected.
+    fn restore_session_state(&mut self) {
+        if let Some(settings) = self.session.settings.clone() {
+            self.apply_simulation_settings(&settings);
+        }
+        if let Some(snapshot_path) = self.session.profile_snapshot.clone() {
+            match profile_io::load_profile_snapshot(Path::new(&snapshot_path)) {
+                Ok((profile, dictionary)) => {
+                    self.learner_profile = profile;
+                    self.global_lemma_dictionary = dictionary;
+                }
+                Err(e) => eprintln!("Warning: Cannot restore the session's learner profile ({}). Starting with an empty profile.", e),
+            }
+        }
+        let Some(selected_key) = self.session.selected_stage_file.clone() else { return };
+        if self.config.is_none() {
+            return;
+        }
+        self.scan_stage_directory();
+        let selected = self.stage_files.iter()
+            .find(|path| self.stage_file_key(path) == selected_key)
+            .cloned();
+        if let Some(path) = selected {
+            // Loading fits sentences_per_block to the chapter; keep the session's value.
+            let sentences_per_block = self.sentences_per_block;
+            self.load_and_parse_selected_file(&path);
+            self.sentences_per_block = sentences_per_block;
+        }
+    }
+
+    // Writes the learner profile and dictionary to session::session_profile_path and the
+    // rest of the session (parameters, selected file, stage file records) to
+    // session::session_file_path, both under the content project.
+    fn save_session_state(&mut self) {
+        let Some(conf) = self.config.clone() else { return };
+        self.session.settings = Some(self.simulation_settings());
+        self.session.selected_stage_file = self.selected_stage_file.as_ref().map(|path| self.stage_file_key(path));
+        self.session.profile_snapshot = None;
+        if self.global_lemma_dictionary.size() > 0 {
+            let profile_path = session::session_profile_path(&conf);
+            if let Some(cache_dir) = profile_path.parent() {
+                let _ = fs::create_dir_all(cache_dir);
+            }
+            match profile_io::save_profile_snapshot(&self.learner_profile, &self.global_lemma_dictionary, &profile_path) {
+                Ok(()) => self.session.profile_snapshot = Some(profile_path.to_string_lossy().into_owned()),
+                Err(e) => eprintln!("Warning: {}", e),
+            }
+        }
+        if let Err(e) = session::save_session(&self.session, &session::session_file_path(&conf)) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+
+    // Key of a stage file's session record (see session::stage_file_key).
+    fn stage_file_key(&self, path: &Path) -> String {
+        match &self.config {
+            Some(conf) => session::stage_file_key(Path::new(&conf.content_project_dir), path),
+            None => path.to_string_lossy().into_owned(),
+        }
+    }
+
+    // Runs `action` now if the lexicon is loaded; otherwise loads it on a background thread
+    // and leaves the action to poll_lexicon, so a large dump never stalls the UI.
+    fn run_lexicon_action(&mut self, action: LexiconAction) {
+        let Some(lazy_lexicon) = &self.lexicon else { return };
+        if lazy_lexicon.try_get().is_none() {
+            lazy_lexicon.start_loading();
+            self.pending_lexicon_action = Some(action);
+            self.lexicon_output = "Loading lexicon...".to_string();
+            return;
+        }
+        match action {
+            LexiconAction::LookUp => self.lookup_lexicon_query(),
+            LexiconAction::GlossMissing => self.fill_missing_glosses_from_lexicon(),
         }
     }
 
+    fn poll_lexicon(&mut self, ctx: &egui::Context) {
+        let Some(lazy_lexicon) = &self.lexicon else { return };
+        if self.pending_lexicon_action.is_none() {
+            return;
+        }
+        if lazy_lexicon.try_get().is_none() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+            return;
+        }
+        if let Some(action) = self.pending_lexicon_action.take() {
+            self.run_lexicon_action(action);
+        }
+    }
+
+    fn lookup_lexicon_query(&mut self) {
+        let Some(Some(loaded)) = self.lexicon.as_ref().map(LazyLexicon::try_get) else { return };
+        self.lexicon_output = match loaded {
+            Ok(lex) => match lex.lookup(&self.lexicon_query) {
+                Some(entries) => entries.iter()
+                    .map(|e| format!("[{}] {}", e.pos, e.glosses.join("; ")))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                None => format!("'{}' not found in lexicon.", self.lexicon_query.trim()),
+            },
+            Err(e) => format!("Lexicon unavailable: {}", e),
+        };
+    }
+
+    fn fill_missing_glosses_from_lexicon(&mut self) {
+        let Some(Some(loaded)) = self.lexicon.as_ref().map(LazyLexicon::try_get) else { return };
+        let lex = match loaded {
+            Ok(lex) => lex,
+            Err(e) => { self.lexicon_output = format!("Lexicon unavailable: {}", e); return; }
+        };
+        let covered = self.current_string_chapter.as_ref()
+            .map(lexicon::diglot_glossed_lemmas)
+            .unwrap_or_default();
+        let filled = lex.fill_missing(&self.global_lemma_dictionary, &covered);
+        let mut lines = vec![format!(
+            "{} of {} dictionary lemmas lack a DIGLOT_MAP gloss in this chapter and were found in the lexicon:",
+            filled.len(), self.global_lemma_dictionary.size()
+        )];
+        for (id, entries) in &filled {
+            let lemma = self.global_lemma_dictionary.get_str(*id).cloned().unwrap_or_default();
+            let Some(entry) = lexicon::entry_for_pos(entries, split_lemma_key(&lemma).1) else { continue };
+            lines.push(format!("  {} [{}]: {}", describe_lemma_key(&lemma), entry.pos, entry.glosses.join("; ")));
+        }
+        self.lexicon_output = lines.join("\n");
+    }
+
     fn reset_chapter_specific_data(&mut self) {
         self.selected_file_content.clear();
         self.current_string_chapter = None;
         self.current_numerical_chapter = None;
         self.processed_json_output.clear();
         self.parser_display_error = None;
+        self.validation_warnings.clear();
         self.generation_error = None;
     }
 
     fn reset_simulation_outputs(&mut self) {
         self.woven_text_output.clear();
+        self.woven_preview.clear();
         self.simulation_log_output.clear();
         self.generation_error = None;
+        self.vocabulary_growth.clear();
+        self.block_traces.clear();
+        self.trace_selection = None;
     }
 
     fn scan_stage_directory(&mut self) {
         self.stage_files.clear();
+        self.stage_file_statuses.clear();
         self.selected_stage_file = None;
         self.scan_error = None;
         self.reset_chapter_specific_data();
         self.reset_simulation_outputs();
 
         if let Some(conf) = &self.config {
-            let stage_path = PathBuf::from(&conf.content_project_dir).join("stage");
+            let stage_path = conf.stage_dir();
             if !stage_path.is_dir() {
                 self.scan_error = Some(format!("Stage directory not found: {:?}", stage_path));
                 return;
             }
             match fs::read_dir(stage_path) { // Using fs directly from `use std::fs;`
                 Ok(entries) => {
-                    for entry in entries {
-                        if let Ok(entry) = entry {
-                            let path = entry.path();
-                            if path.is_file() {
-                                if let Some(name_str) = path.file_name().and_then(|n| n.to_str()) {
-                                    if name_str.ends_with(".llm.txt") {
-                                        self.stage_files.push(path);
-                                    }
+                    for entry in entries.flatten() {
+                        let path = entry.path();
+                        if path.is_file() {
+                            if let Some(name_str) = path.file_name().and_then(|n| n.to_str()) {
+                                if chapter_loader::is_stage_file_name(name_str) {
+                                    self.stage_files.push(path);
                                 }
                             }
                         }
                     }
                     if self.stage_files.is_empty() {
-                        self.scan_error = Some("No .llm.txt files found.".to_string());
+                        self.scan_error = Some("No stage files (.llm.txt, .json) found.".to_string());
                     }
                     self.stage_files.sort();
+                    for path in &self.stage_files {
+                        if let Ok(bytes) = fs::read(path) {
+                            let status = self.session.stage_file_status(&self.stage_file_key(path), &session::content_hash(&bytes));
+                            self.stage_file_statuses.insert(path.clone(), status);
+                        }
+                    }
                 }
                 Err(e) => { self.scan_error = Some(format!("Failed to read stage directory: {}", e)); }
             }
@@ -188,17 +1004,39 @@ impl WeaveLangApp {
                 self.selected_file_content = contents.clone();
                 let file_name = path_to_load.file_name().unwrap_or_default().to_string_lossy().into_owned();
 
-                match weavelang_rust_gui::parsing::llm_parser::parse_llm_text_to_chapter(&file_name, &contents) {
-                    Ok(parsed_string_chapter) => {
+                let contents_hash = session::content_hash(contents.as_bytes());
+
+                match chapter_loader::parse_chapter(&file_name, &contents) {
+                    Ok(mut parsed_string_chapter) => {
+                        if let Some(conf) = &self.config {
+                            parsed_string_chapter.language_pair = conf.language_pair.clone();
+                        }
+                        self.validation_warnings = weavelang_rust_gui::parsing::validation::validate_chapter(&parsed_string_chapter)
+                            .iter()
+                            .map(|issue| issue.to_string())
+                            .collect();
+                        let outcome = if self.validation_warnings.is_empty() {
+                            ValidationOutcome::Clean
+                        } else {
+                            ValidationOutcome::Warnings(self.validation_warnings.len())
+                        };
+                        self.record_stage_file_outcome(path_to_load, contents_hash, outcome);
                         // Populate GUI's dictionary instance
                         self.global_lemma_dictionary.populate_from_chapter(&parsed_string_chapter);
+                        if let Some(table) = &mut self.exposure_thresholds {
+                            table.learn_cognates(&parsed_string_chapter, self.min_diglot_confidence);
+                        }
+                        if self.track_forms {
+                            let target_tokenizer = tokenizer::tokenizer_for_language(&parsed_string_chapter.language_pair.target);
+                            self.global_lemma_dictionary.learn_forms_from_chapter(&parsed_string_chapter, target_tokenizer.as_ref());
+                        }
                         let numerical_version = weavelang_rust_gui::simulation::preprocessor::to_numerical_chapter(
                             &parsed_string_chapter,
                             &mut self.global_lemma_dictionary,
                         );
 
                         if !parsed_string_chapter.sentences.is_empty() {
-                            let new_spb = (parsed_string_chapter.sentences.len()).max(1).min(5000); // ensure it's at least 1, max 5000
+                            let new_spb = parsed_string_chapter.sentences.len().clamp(1, 5000); // ensure it's at least 1, max 5000
                             if new_spb != self.sentences_per_block {
                                 self.simulation_log_output.push_str(&format!(
                                     "[INFO] GUI: Auto-adjusted sentences_per_block from {} to {} for chapter '{}'.\n",
@@ -218,6 +1056,7 @@ impl WeaveLangApp {
                     }
                     Err(e) => {
                         self.parser_display_error = Some(format!("Parser Error for {}: {}", file_name, e));
+                        self.record_stage_file_outcome(path_to_load, contents_hash, ValidationOutcome::ParseError);
                     }
                 }
             }
@@ -227,163 +1066,954 @@ impl WeaveLangApp {
         }
     }
 
-    fn run_simulation_orchestrator(&mut self) {
+    fn record_stage_file_outcome(&mut self, path: &Path, contents_hash: String, outcome: ValidationOutcome) {
+        let key = self.stage_file_key(path);
+        self.session.record_stage_file(&key, contents_hash, outcome);
+        self.stage_file_statuses.insert(path.to_path_buf(), StageFileStatus::Unchanged(outcome));
+        let Some(conf) = &self.config else { return };
+        if let Err(e) = session::save_session(&self.session, &session::session_file_path(conf)) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+
+    fn orchestrator_params(&self) -> OrchestratorParams {
+        OrchestratorParams {
+            sentences_per_block: self.sentences_per_block,
+            block_boundaries: self.block_boundaries,
+            passes: self.max_simulation_loops as usize,
+            max_regen_attempts_per_block: self.max_regen_attempts_per_block,
+            target_ct_threshold: self.target_ct_threshold,
+            min_ct_threshold: self.min_ct_threshold,
+            max_words_to_activate_per_regen: self.max_words_to_activate_per_regen,
+            min_diglot_confidence: self.min_diglot_confidence,
+            l4_strategy: self.l4_strategy,
+            l4_match_plurals: self.l4_match_plurals,
+            decay: self.decay_params,
+            scheduler: self.scheduler_params,
+            ct_metric: self.ct_metric,
+            prefix_level_tags: self.prefix_level_tags,
+            level_policy: self.level_policy.clone().with_min_sentence_ct(self.min_sentence_ct),
+            halt_on_block_error: true,
+            max_new_lemmas_per_100_sentences: self.max_new_lemmas_per_100_sentences,
+            recent_introductions: Vec::new(),
+            track_forms: self.track_forms,
+        }
+    }
+
+    fn run_simulation_orchestrator(&mut self, ctx: &egui::Context) {
         self.reset_simulation_outputs();
 
-        let numerical_chapter_ref: &GuiNumericalChapter = match &self.current_numerical_chapter {
-            Some(nc_ref) => nc_ref,
-            None => {
-                self.simulation_log_output.push_str("\nERROR: Numerical chapter not loaded for simulation.");
-                self.generation_error = Some("Numerical chapter is not loaded. Please load a file first.".to_string());
+        let (Some(string_chapter), Some(numerical_chapter)) = (&self.current_string_chapter, &self.current_numerical_chapter) else {
+            self.simulation_log_output.push_str("\nERROR: Chapter not loaded for simulation.");
+            self.generation_error = Some("Chapter is not loaded. Please load a file first.".to_string());
+            return;
+        };
+        if numerical_chapter.sentences_numerical.is_empty() {
+            self.generation_error = Some("GUI: Current numerical chapter has no sentences.".to_string());
+            self.simulation_log_output.push_str("\nERROR: Numerical chapter has no sentences.");
+            return;
+        }
+        let book = SimulationBook {
+            label: string_chapter.source_file_name.clone(),
+            stage_path: self.selected_stage_file.clone().unwrap_or_default(),
+            chapters: Ok((string_chapter.clone(), numerical_chapter.clone())),
+            overrides: corpus_generator::BookOverrides::default(),
+        };
+        self.start_simulation(ctx, vec![book], false);
+    }
+
+    // Loads every book of the sequence file and simulates them in order, carrying the
+    // learner profile and the introduction cap's window from book to book like `generate`
+    // does. Books that fail to load are reported and skipped. A book whose simulation fails
+    // is rolled back to the profile the previous book left and the run goes on with the
+    // next book; cancelling rolls back the current book and ends the run. Once it finishes, the last book is loaded (so the trace inspector shows its
+    // blocks) while the log and woven text cover the whole sequence.
+    fn run_book_sequence(&mut self, ctx: &egui::Context) {
+        self.sequence_books.clear();
+        self.sequence_status = None;
+        let Some(conf) = self.config.clone() else {
+            self.sequence_status = Some("Config not loaded.".to_string());
+            return;
+        };
+        let sequence_entries = match corpus_generator::load_sequence_entries(Path::new(self.sequence_path.trim())) {
+            Ok(entries) if entries.is_empty() => {
+                self.sequence_status = Some("No book stems found in the sequence file.".to_string());
+                return;
+            }
+            Ok(entries) => entries,
+            Err(e) => {
+                self.sequence_status = Some(e.to_string());
                 return;
             }
         };
-
-        let string_chapter_ref: &GuiStringProcessedChapter = match &self.current_string_chapter {
-            Some(sc_ref) => sc_ref,
-            None => {
-                self.simulation_log_output.push_str("\nERROR: String chapter not loaded for simulation.");
-                self.generation_error = Some("String chapter is not loaded. Please load a file first.".to_string());
+        self.reset_simulation_outputs();
+        // Parsing is quick next to simulating, so books are loaded here; the dictionary only
+        // grows, as it does when a single file is loaded.
+        let book_stems: Vec<String> = sequence_entries.iter().map(|entry| entry.book_stem.clone()).collect();
+        let lexicon = match corpus_generator::sequence_bilingual_lexicon(&conf, &book_stems) {
+            Ok(lexicon) => lexicon,
+            Err(e) => {
+                self.sequence_status = Some(e.to_string());
                 return;
             }
         };
+        let books = sequence_entries.iter().map(|entry| {
+            let book_stem = &entry.book_stem;
+            let prepared = corpus_generator::prepare_book(&conf, book_stem, &lexicon);
+            SimulationBook {
+                label: book_stem.clone(),
+                stage_path: prepared.as_ref().map_or_else(|_| corpus_generator::stage_file_path(&conf, book_stem), |book| book.llm_file_path.clone()),
+                chapters: prepared.map_err(|e| e.to_string()).map(|book| {
+                    let numerical_chapter = preprocessor::merge_into_dictionary(book.numerical_chapter, &book.local_dictionary, &mut self.global_lemma_dictionary);
+                    if let Some(table) = &mut self.exposure_thresholds {
+                        table.learn_cognates(&book.string_chapter, self.min_diglot_confidence);
+                    }
+                    if self.track_forms {
+                        let target_tokenizer = tokenizer::tokenizer_for_language(&book.string_chapter.language_pair.target);
+                        self.global_lemma_dictionary.learn_forms_from_chapter(&book.string_chapter, target_tokenizer.as_ref());
+                    }
+                    (book.string_chapter, numerical_chapter)
+                }),
+                overrides: entry.overrides,
+            }
+        }).collect();
+        self.start_simulation(ctx, books, true);
+    }
 
-        if numerical_chapter_ref.sentences_numerical.is_empty() {
-            self.generation_error = Some("GUI: Current numerical chapter has no sentences.".to_string());
-            self.simulation_log_output.push_str("\nERROR: Numerical chapter has no sentences.");
-            return;
+    // Hands `books` to a worker thread that simulates them on a copy of the learner profile.
+    // poll_simulation_job picks up its progress and commits the profile once it finishes.
+    fn start_simulation(&mut self, ctx: &egui::Context, books: Vec<SimulationBook>, sequence: bool) {
+        if let Some(table) = &self.exposure_thresholds {
+            self.learner_profile.set_exposure_thresholds(Arc::new(table.resolve(&self.global_lemma_dictionary)));
         }
+        let (updates_sender, updates) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let worker = SimulationWorker {
+            books,
+            profile: self.learner_profile.clone(),
+            dictionary: self.global_lemma_dictionary.clone(),
+            params: self.orchestrator_params(),
+            updates: updates_sender,
+            cancel: Arc::clone(&cancel),
+            ctx: ctx.clone(),
+        };
+        std::thread::spawn(move || worker.run());
+        self.simulation_job = Some(SimulationJob { updates, cancel, sequence, progress: None });
+    }
 
-        let mut accumulated_log_for_display: Vec<String> = Vec::new();
-        let mut accumulated_woven_text_for_display: String = String::new();
+    fn poll_simulation_job(&mut self) {
+        let Some(job) = &mut self.simulation_job else { return };
+        let outcome = loop {
+            match job.updates.try_recv() {
+                Ok(SimulationUpdate::Progress(progress)) => job.progress = Some(progress),
+                Ok(SimulationUpdate::Finished(outcome)) => break Some(*outcome),
+                Err(mpsc::TryRecvError::Empty) => return,
+                Err(mpsc::TryRecvError::Disconnected) => break None,
+            }
+        };
+        let sequence = job.sequence;
+        self.simulation_job = None;
+        match outcome {
+            Some(outcome) => self.apply_simulation_outcome(outcome, sequence),
+            None => self.generation_error = Some("Simulation worker stopped unexpectedly; the learner profile was left unchanged.".to_string()),
+        }
+    }
 
-        let initial_profile_stats = format!(
-            "INITIAL PROFILE for Run: Known: {}, Active (only): {}, Total K/A: {}, Vocab Size (Profile): {}, Global Dict Size: {}, Total Exposures: {}\n",
-            self.learner_profile.count_known(), self.learner_profile.count_active_only(),
-            self.learner_profile.count_total_known_or_active(), self.learner_profile.vocabulary_size(),
-            self.global_lemma_dictionary.size(), self.learner_profile.total_exposure_count()
+    // Replaces the profile with the worker's as soon as at least one book completed. Books
+    // that failed or were cancelled part-way were already rolled back by the worker, so the
+    // committed profile holds exactly the completed books' exposures.
+    fn apply_simulation_outcome(&mut self, outcome: SimulationOutcome, sequence: bool) {
+        let committed = outcome.completed_books > 0;
+        if committed {
+            let action = if sequence { "Sequence run" } else { "Simulation run" };
+            self.profile_history.record(action, &self.learner_profile, &self.global_lemma_dictionary);
+            self.learner_profile = outcome.profile;
+        }
+        let book_count = outcome.books.len();
+        let mut log_sections: Vec<String> = Vec::new();
+        let mut woven_sections: Vec<String> = Vec::new();
+        let mut books = outcome.books;
+        for (book_idx, book) in books.iter_mut().enumerate() {
+            self.woven_preview.append(&mut book.preview);
+            log_sections.push(if sequence {
+                format!("===== Book {}: {} =====\n{}", book_idx + 1, book.run.book_stem, book.log)
+            } else {
+                book.log.clone()
+            });
+            if !book.woven_text.is_empty() {
+                woven_sections.push(book.woven_text.clone());
+            }
+        }
+        let kept = if committed {
+            format!("progress from the {} completed book(s) was kept", outcome.completed_books)
+        } else {
+            "the learner profile was left unchanged".to_string()
+        };
+        if outcome.cancelled {
+            log_sections.push(format!("[GUI] Simulation cancelled; {}.", kept));
+        } else if outcome.error.is_some() {
+            log_sections.push(format!("[GUI] Simulation failed; {}.", kept));
+        }
+        self.simulation_log_output = log_sections.join("\n\n");
+        self.woven_text_output = woven_sections.join("\n\n");
+        self.generation_error = outcome.error;
+        if let Some(last) = books.last_mut() {
+            self.vocabulary_growth = std::mem::take(&mut last.vocabulary_growth);
+            self.block_traces = std::mem::take(&mut last.block_traces);
+        }
+        self.trace_selection = None;
+        if !sequence {
+            return;
+        }
+
+        if let Some((stage_path, string_chapter, numerical_chapter)) = outcome.last_book {
+            self.reset_chapter_specific_data();
+            self.selected_stage_file = Some(stage_path);
+            self.current_string_chapter = Some(string_chapter);
+            self.current_numerical_chapter = Some(numerical_chapter);
+        }
+        self.sequence_books = books.into_iter().map(|book| book.run).collect();
+        let failed = self.sequence_books.iter().filter(|b| b.error.is_some()).count();
+        let last_failure = self.sequence_books.iter().rev().find_map(|b| b.error.as_ref().map(|e| format!(" Last failure, {}: {}", b.book_stem, e)))
+            .unwrap_or_default();
+        let totals = format!(
+            "{} blocks, {} sentences, {} new lemmas",
+            self.sequence_books.iter().map(|b| b.blocks).sum::<usize>(),
+            self.sequence_books.iter().map(|b| b.sentences).sum::<usize>(),
+            self.sequence_books.iter().map(|b| b.new_lemmas).sum::<usize>(),
         );
-        accumulated_log_for_display.push(initial_profile_stats.clone());
-        accumulated_woven_text_for_display.push_str(&format!("%%WEAVELANG_STAT%% {}", initial_profile_stats));
-
-        let total_sentences_in_source_chapter = numerical_chapter_ref.sentences_numerical.len();
-        let mut overall_sentences_processed_this_run = 0;
-        let mut current_source_sentence_idx = 0;
-        let total_sentences_to_simulate_overall = total_sentences_in_source_chapter * self.max_simulation_loops as usize;
-        let mut measurement_block_counter = 0;
-
-        while overall_sentences_processed_this_run < total_sentences_to_simulate_overall {
-            measurement_block_counter += 1;
-            accumulated_log_for_display.push(format!(
-                "\n--- GUI Orchestrator: Preparing Measurement Block {} ---",
-                measurement_block_counter
-            ));
-
-            let mut block_numerical_sentences_refs: Vec<&GuiNumericalProcessedSentence> = Vec::new();
-            let mut block_string_sentences_refs: Vec<&GuiStringProcessedSentence> = Vec::new();
-
-            for _ in 0..self.sentences_per_block {
-                if overall_sentences_processed_this_run >= total_sentences_to_simulate_overall { break; }
-                if current_source_sentence_idx >= total_sentences_in_source_chapter { break; } // Safety for empty chapters after first loop
-
-                block_numerical_sentences_refs.push(&numerical_chapter_ref.sentences_numerical[current_source_sentence_idx]);
-                if current_source_sentence_idx < string_chapter_ref.sentences.len() {
-                    block_string_sentences_refs.push(&string_chapter_ref.sentences[current_source_sentence_idx]);
-                } else {
-                    eprintln!("Mismatch between numerical and string sentence counts in GUI orchestrator!");
-                    break;
+        self.sequence_status = Some(if outcome.cancelled {
+            format!("Cancelled after {} book(s) ({}); {}.{}", book_count, totals, kept, last_failure)
+        } else if committed {
+            format!("{} book(s), {} failed and skipped: {}. Profile K: {}, A: {}.{}", book_count, failed, totals,
+                    self.learner_profile.count_known(), self.learner_profile.count_active_only(), last_failure)
+        } else {
+            format!("{} book(s), all failed ({}); profile unchanged.{}", book_count, totals, last_failure)
+        })
+    }
+
+    fn show_sequence_results(&self, ui: &mut egui::Ui) {
+        if self.sequence_books.is_empty() {
+            return;
+        }
+        egui::ScrollArea::vertical()
+            .id_source("sequence_results_scroll")
+            .max_height(150.0)
+            .show(ui, |ui| {
+                egui::Grid::new("sequence_results_grid").striped(true).show(ui, |ui| {
+                    for header in ["Book", "Blocks", "Sentences", "New", "Avg CT", "K / A"] {
+                        ui.strong(header);
+                    }
+                    ui.end_row();
+                    for book in &self.sequence_books {
+                        match &book.error {
+                            Some(err) => { ui.colored_label(egui::Color32::RED, &book.book_stem).on_hover_text(err); }
+                            None => { ui.label(&book.book_stem); }
+                        }
+                        ui.label(book.blocks.to_string());
+                        ui.label(book.sentences.to_string());
+                        ui.label(book.new_lemmas.to_string());
+                        ui.label(format!("{:.1}%", book.average_ct * 100.0));
+                        ui.label(format!("{} / {}", book.known_after, book.active_after));
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    // Replaces the learner profile with a snapshot, remapped onto the GUI dictionary if the
+    // snapshot was written with a different one.
+    fn load_profile_snapshot(&mut self) {
+        let path = PathBuf::from(self.profile_snapshot_path.trim());
+        self.profile_history.record("Load profile snapshot", &self.learner_profile, &self.global_lemma_dictionary);
+        self.profile_snapshot_status = Some(match profile_io::load_profile_snapshot_into(&path, &mut self.global_lemma_dictionary) {
+            Ok((profile, remap)) => {
+                self.learner_profile = profile;
+                self.reset_simulation_outputs();
+                match remap {
+                    Some(report) => format!("Loaded; dictionaries differed: {}.", report.summary()),
+                    None => "Loaded.".to_string(),
                 }
-                
-                current_source_sentence_idx = (current_source_sentence_idx + 1) % total_sentences_in_source_chapter;
-                if total_sentences_in_source_chapter == 0 { break; } // Avoid infinite loop on empty chapter after first pass
-                overall_sentences_processed_this_run += 1;
             }
+            Err(e) => format!("Load failed: {}", e),
+        });
+    }
 
-            if block_numerical_sentences_refs.is_empty() {
-                accumulated_log_for_display.push("GUI Orchestrator: No more sentences to form a new block. Ending run.".to_string());
-                break;
-            }
+    // Word lists (.txt/.csv) in the content project directory, offered by the known-word picker.
+    fn known_word_list_candidates(&self) -> Vec<PathBuf> {
+        let Some(conf) = &self.config else { return Vec::new() };
+        let mut candidates: Vec<PathBuf> = fs::read_dir(&conf.content_project_dir)
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        candidates.retain(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt" || ext == "csv"));
+        candidates.sort();
+        candidates
+    }
 
-            accumulated_log_for_display.push(format!(
-                "GUI Orchestrator: Calling core_algo for block {} ({} sentences). Profile K: {}, A: {}",
-                measurement_block_counter,
-                block_numerical_sentences_refs.len(),
-                self.learner_profile.count_known(),
-                self.learner_profile.count_active_only()
-            ));
-
-            let mut block_new_lemma_freq: HashMap<u32, u32> = HashMap::new();
-            for num_sentence_ref in &block_numerical_sentences_refs {
-                let mut sentence_lemma_ids_for_freq_check : Vec<u32> = Vec::new();
-                sentence_lemma_ids_for_freq_check.extend(&num_sentence_ref.adv_s_lemma_ids);
-                for nsl in &num_sentence_ref.sim_s_lemmas_numerical {
-                    sentence_lemma_ids_for_freq_check.extend(&nsl.lemma_ids);
-                }
-                for ndsm in &num_sentence_ref.diglot_map_numerical {
-                    for nde in &ndsm.entries {
-                        if nde.viable { sentence_lemma_ids_for_freq_check.push(nde.spa_lemma_id); }
+    fn import_known_words(&mut self) {
+        let path = PathBuf::from(self.known_words_path.trim());
+        self.profile_history.record("Import known words", &self.learner_profile, &self.global_lemma_dictionary);
+        self.known_words_status = Some(
+            match profile_io::import_known_lemmas(&path, &mut self.learner_profile, &mut self.global_lemma_dictionary) {
+                Ok(import) => format!("{} word(s) read, {} lemma(s) marked Known ({} already Known).",
+                                      import.words_read, import.lemmas_marked_known, import.already_known),
+                Err(e) => format!("Import failed: {}", e),
+            },
+        );
+    }
+
+    fn reset_learner_profile(&mut self) {
+        self.profile_history.record("Reset", &self.learner_profile, &self.global_lemma_dictionary);
+        self.learner_profile = GuiNumericalLearnerProfile::new();
+        self.global_lemma_dictionary = GuiGlobalLemmaDictionary::new();
+        self.reset_simulation_outputs();
+        self.reset_chapter_specific_data();
+        self.selected_stage_file = None;
+    }
+
+    fn undo_profile_change(&mut self) {
+        if let Some(snapshot) = self.profile_history.undo.pop_back() {
+            let replaced = self.swap_in_profile_snapshot(snapshot);
+            self.profile_history.redo.push(replaced);
+        }
+    }
+
+    fn redo_profile_change(&mut self) {
+        if let Some(snapshot) = self.profile_history.redo.pop() {
+            let replaced = self.swap_in_profile_snapshot(snapshot);
+            self.profile_history.undo.push_back(replaced);
+        }
+    }
+
+    // Installs `snapshot` and returns the state it replaced under the same action name.
+    // Dictionaries only grow between resets, so the current dictionary is kept when it
+    // extends the snapshot's (the loaded chapter's lemma IDs stay valid); otherwise the
+    // snapshot's dictionary comes back and the loaded chapter and outputs are dropped.
+    fn swap_in_profile_snapshot(&mut self, snapshot: ProfileSnapshot) -> ProfileSnapshot {
+        let keep_dictionary = self.global_lemma_dictionary.id_to_str.starts_with(&snapshot.dictionary.id_to_str);
+        let profile = std::mem::replace(&mut self.learner_profile, snapshot.profile);
+        let dictionary = if keep_dictionary {
+            self.global_lemma_dictionary.clone()
+        } else {
+            let dictionary = std::mem::replace(&mut self.global_lemma_dictionary, snapshot.dictionary);
+            self.reset_simulation_outputs();
+            self.reset_chapter_specific_data();
+            self.selected_stage_file = None;
+            dictionary
+        };
+        ProfileSnapshot { action: snapshot.action, profile, dictionary }
+    }
+
+    // Searchable list of dictionary lemmas with their profile state, where states can be set
+    // by hand before running the orchestrator.
+    fn show_word_state_editor(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Known-word list:");
+            ui.text_edit_singleline(&mut self.known_words_path);
+            let candidates = self.known_word_list_candidates();
+            egui::ComboBox::from_id_source("known_words_picker")
+                .selected_text("Browse")
+                .show_ui(ui, |ui| {
+                    if candidates.is_empty() {
+                        ui.label("No .txt/.csv files in the content directory.");
                     }
-                }
-                for &lemma_id in &sentence_lemma_ids_for_freq_check {
-                    if self.learner_profile.get_lemma_info(lemma_id).map_or(true, |info| info.state == GuiLemmaState::New) {
-                        *block_new_lemma_freq.entry(lemma_id).or_insert(0) += 1;
+                    for path in candidates {
+                        let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                        if ui.selectable_label(false, file_name).clicked() {
+                            self.known_words_path = path.display().to_string();
+                        }
+                    }
+                });
+            if ui.add_enabled(!self.known_words_path.trim().is_empty(), egui::Button::new("Import")).clicked() {
+                self.import_known_words();
+            }
+        });
+        if let Some(status) = &self.known_words_status {
+            ui.label(status);
+        }
+        if self.global_lemma_dictionary.size() == 0 {
+            ui.label("Load a chapter to list its lemmas.");
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Search:");
+            ui.text_edit_singleline(&mut self.word_state_query);
+        });
+        let query = self.word_state_query.trim().to_lowercase();
+        let matching_ids: Vec<u32> = self.global_lemma_dictionary.id_to_str.iter()
+            .enumerate()
+            .filter(|(_, lemma)| query.is_empty() || lemma.contains(&query))
+            .map(|(lemma_id, _)| lemma_id as u32)
+            .collect();
+        const STATES: [(LemmaState, &str); 3] = [(LemmaState::New, "New"), (LemmaState::Active, "Active"), (LemmaState::Known, "Known")];
+        ui.horizontal(|ui| {
+            ui.label(format!("{} lemma(s). Mark all shown:", matching_ids.len()));
+            for (state, name) in STATES {
+                if ui.small_button(name).clicked() {
+                    for &lemma_id in &matching_ids {
+                        self.learner_profile.override_lemma_state(lemma_id, state);
                     }
                 }
             }
-            let mut sorted_block_specific_new_lemma_ids_for_activation: Vec<(u32, u32)> = block_new_lemma_freq.into_iter().collect();
-            sorted_block_specific_new_lemma_ids_for_activation.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
-
-            match weavelang_rust_gui::simulation::core_algo::run_simulation_numerical(
-                &block_numerical_sentences_refs,
-                self.learner_profile.clone(),
-                &sorted_block_specific_new_lemma_ids_for_activation,
-                self.max_regen_attempts_per_block,
-                self.target_ct_threshold,
-                self.max_words_to_activate_per_regen,
-            ) {
-                Ok(block_simulation_result) => {
-                    accumulated_log_for_display.extend(block_simulation_result.simulation_log_entries.clone());
-                    // Important: Update the app's main learner_profile for the GUI simulation
-                    self.learner_profile = block_simulation_result.profile_state_after_block_exposure;
-
-                    match weavelang_rust_gui::simulation::text_generator::generate_final_text_block(
-                        &block_string_sentences_refs,
-                        &self.global_lemma_dictionary, // Use GUI's dictionary
-                        &block_simulation_result.profile_state_for_text_generation,
-                    ) {
-                        Ok(generated_text_for_block) => {
-                            accumulated_woven_text_for_display.push_str(&generated_text_for_block);
-                            if !generated_text_for_block.trim().is_empty() && !accumulated_woven_text_for_display.ends_with("\n\n") {
-                                 accumulated_woven_text_for_display.push_str("\n\n");
+        });
+        let row_height = ui.spacing().interact_size.y;
+        egui::ScrollArea::vertical()
+            .id_source("word_state_editor_scroll")
+            .max_height(250.0)
+            .show_rows(ui, row_height, matching_ids.len(), |ui, row_range| {
+                for &lemma_id in &matching_ids[row_range] {
+                    let (current_state, exposures) = self.learner_profile.get_lemma_info(lemma_id)
+                        .map_or((LemmaState::New, 0), |info| (info.state, info.exposure_count));
+                    let lemma = self.global_lemma_dictionary.get_str(lemma_id).map(|key| describe_lemma_key(key)).unwrap_or_default();
+                    ui.horizontal(|ui| {
+                        for (state, name) in STATES {
+                            if ui.selectable_label(current_state == state, &name[..1]).on_hover_text(name).clicked() && current_state != state {
+                                self.learner_profile.override_lemma_state(lemma_id, state);
                             }
-                            // ... (stat logging as before) ...
                         }
-                        Err(e_text_gen) => {
-                            let err_msg = format!("[GUI Orchestrator Error] Text generation for block {}: {}", measurement_block_counter, e_text_gen);
-                            accumulated_log_for_display.push(err_msg.clone());
-                            self.generation_error = Some(err_msg);
-                            break;
+                        ui.label(format!("{} ({} exposure(s))", lemma, exposures));
+                    });
+                }
+            });
+    }
+
+    // Trace events of the last GUI run, filtered by block, lemma and event type. Clicking an
+    // Activation or Withhold event lists the block's sentences containing that lemma.
+    fn show_trace_inspector(&mut self, ui: &mut egui::Ui) {
+        if self.block_traces.is_empty() {
+            ui.label("Run the simulation to inspect its trace.");
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Block:");
+            ui.add(egui::TextEdit::singleline(&mut self.trace_block_filter).desired_width(40.0));
+            ui.label("Lemma:");
+            ui.add(egui::TextEdit::singleline(&mut self.trace_lemma_filter).desired_width(100.0));
+            egui::ComboBox::from_id_source("trace_kind_filter_combo")
+                .selected_text(self.trace_kind_filter.unwrap_or("All events"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.trace_kind_filter, None, "All events");
+                    for kind in TraceEvent::KINDS {
+                        ui.selectable_value(&mut self.trace_kind_filter, Some(kind), kind);
+                    }
+                });
+        });
+        let block_filter = self.trace_block_filter.trim().parse::<usize>().ok();
+        let lemma_query = self.trace_lemma_filter.trim().to_lowercase();
+        let lemma_matches = |lemma_id: Option<u32>| lemma_query.is_empty() || lemma_id
+            .and_then(|id| self.global_lemma_dictionary.get_str(id))
+            .is_some_and(|key| key.contains(&lemma_query));
+        let rows: Vec<(usize, usize)> = self.block_traces.iter().enumerate()
+            .filter(|(_, traced)| block_filter.is_none_or(|n| traced.info.block_index == n))
+            .flat_map(|(block_idx, traced)| traced.events.iter().enumerate()
+                .filter(|(_, event)| self.trace_kind_filter.is_none_or(|kind| event.kind() == kind) && lemma_matches(event.lemma_id()))
+                .map(move |(event_idx, _)| (block_idx, event_idx)))
+            .collect();
+        ui.label(format!("{} event(s).", rows.len()));
+
+        let mut clicked = None;
+        let row_height = ui.spacing().interact_size.y;
+        egui::ScrollArea::vertical()
+            .id_source("trace_inspector_scroll")
+            .max_height(250.0)
+            .show_rows(ui, row_height, rows.len(), |ui, row_range| {
+                for &(block_idx, event_idx) in &rows[row_range] {
+                    let traced = &self.block_traces[block_idx];
+                    let event = &traced.events[event_idx];
+                    let text = format!("B{} {}: {}", traced.info.block_index, event.kind(), describe_trace_event(event, &self.global_lemma_dictionary));
+                    match event.lemma_id() {
+                        Some(lemma_id) => {
+                            let selected = self.trace_selection == Some((block_idx, lemma_id));
+                            if ui.selectable_label(selected, text).clicked() {
+                                clicked = Some((block_idx, lemma_id));
+                            }
                         }
+                        None => { ui.label(text); }
                     }
-                    // ... (log profile stats after block) ...
                 }
-                Err(e_sim) => {
-                    let err_msg = format!("[GUI Orchestrator Error] Core simulation for block {}: {}", measurement_block_counter, e_sim);
-                    accumulated_log_for_display.push(err_msg.clone());
-                    self.generation_error = Some(err_msg);
-                    break;
+            });
+        if clicked.is_some() {
+            self.trace_selection = clicked;
+        }
+
+        let (Some((block_idx, lemma_id)), Some(string_chapter), Some(numerical_chapter)) =
+            (self.trace_selection, &self.current_string_chapter, &self.current_numerical_chapter) else { return };
+        let Some(traced) = self.block_traces.get(block_idx) else { return };
+        let chapter_len = numerical_chapter.sentences_numerical.len().min(string_chapter.sentences.len());
+        if chapter_len == 0 {
+            return;
+        }
+        ui.separator();
+        let lemma = self.global_lemma_dictionary.get_str(lemma_id).map(|key| describe_lemma_key(key)).unwrap_or_default();
+        ui.label(format!("Sentences of block {} containing {}:", traced.info.block_index, lemma));
+        let first = traced.info.first_sentence_position;
+        for position in first..first + traced.info.sentence_count {
+            let sentence_idx = position % chapter_len;
+            if scheduler::sentence_lemma_ids(&numerical_chapter.sentences_numerical[sentence_idx], self.min_diglot_confidence).contains(&lemma_id) {
+                let sentence = &string_chapter.sentences[sentence_idx];
+                ui.label(format!("{}: {}", sentence.sentence_id, sentence.adv_s));
+            }
+        }
+    }
+
+    // Popup for the lemma of a word clicked in the colored preview: its current state in the
+    // learner profile and every sentence of the loaded chapter that contains it.
+    fn show_lemma_inspector(&mut self, ctx: &egui::Context) {
+        let Some(lemma_id) = self.inspected_lemma else { return };
+        let mut open = true;
+        egui::Window::new("Lemma Inspector")
+            .open(&mut open)
+            .default_width(400.0)
+            .show(ctx, |ui| {
+                let lemma = self.global_lemma_dictionary.get_str(lemma_id).map(|key| describe_lemma_key(key)).unwrap_or_default();
+                let (state, exposures) = self.learner_profile.get_lemma_info(lemma_id)
+                    .map_or((LemmaState::New, 0), |info| (info.state, info.exposure_count));
+                let threshold = self.learner_profile.required_exposure_threshold(lemma_id);
+                let remaining = if state == LemmaState::Known { 0 } else { threshold.saturating_sub(exposures) };
+                ui.heading(&lemma);
+                ui.label(format!("Dictionary ID: {}", lemma_id));
+                ui.label(format!("State: {:?}", state));
+                ui.label(format!("Exposures: {} of {} ({} to go)", exposures, threshold, remaining));
+                ui.separator();
+
+                let (Some(string_chapter), Some(numerical_chapter)) = (&self.current_string_chapter, &self.current_numerical_chapter) else {
+                    ui.label("No chapter loaded.");
+                    return;
+                };
+                let sentences: Vec<&ProcessedSentence> = string_chapter.sentences.iter()
+                    .zip(&numerical_chapter.sentences_numerical)
+                    .filter(|(_, numerical)| scheduler::sentence_lemma_ids(numerical, self.min_diglot_confidence).contains(&lemma_id))
+                    .map(|(sentence, _)| sentence)
+                    .collect();
+                ui.label(format!("{} sentence(s) in {}:", sentences.len(), string_chapter.source_file_name));
+                egui::ScrollArea::vertical()
+                    .id_source("lemma_inspector_sentences_scroll")
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for sentence in sentences {
+                            ui.label(format!("{}: {}", sentence.sentence_id, sentence.adv_s));
+                        }
+                    });
+            });
+        if !open {
+            self.inspected_lemma = None;
+        }
+    }
+
+    fn show_vocabulary_growth_plot(&self, ui: &mut egui::Ui) {
+        if self.vocabulary_growth.len() < 2 {
+            ui.label("Run the simulation to chart Known/Active/New lemmas per block.");
+            return;
+        }
+        let series = |value: fn(&VocabularyGrowthPoint) -> usize| -> PlotPoints {
+            self.vocabulary_growth.iter().map(|p| [p.block_index as f64, value(p) as f64]).collect()
+        };
+        Plot::new("vocabulary_growth_plot")
+            .height(200.0)
+            .legend(Legend::default())
+            .x_axis_label("Block")
+            .y_axis_label("Lemmas")
+            .allow_scroll(false)
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(series(|p| p.known)).name("Known").color(egui::Color32::from_rgb(60, 170, 90)));
+                plot_ui.line(Line::new(series(|p| p.active)).name("Active").color(egui::Color32::from_rgb(230, 160, 40)));
+                plot_ui.line(Line::new(series(|p| p.new)).name("New").color(egui::Color32::from_rgb(120, 120, 200)));
+            });
+    }
+}
+
+// Outcome of one book of a GUI sequence run; profile counts are taken after the book.
+#[derive(Debug, Clone, Default)]
+struct SequenceBookRun {
+    book_stem: String,
+    sentences: usize,
+    blocks: usize,
+    new_lemmas: usize,
+    average_ct: f32,
+    known_after: usize,
+    active_after: usize,
+    error: Option<String>,
+}
+
+// A rendered sentence for the colored preview: its traced target-language words with the
+// learner's state when the block was rendered, and which bytes are target-language text.
+#[derive(Debug, Clone)]
+struct PreviewSentence {
+    block_index: usize,
+    text: String,
+    words: Vec<html::TracedWord>,
+    target_ranges: Vec<Range<usize>>,
+}
+
+impl PreviewSentence {
+    // Known green, Active orange, New red, base-language text gray; target-language text
+    // that could not be traced to a lemma keeps the default color.
+    fn layout_job(&self, default_color: egui::Color32) -> egui::text::LayoutJob {
+        let color_at = |pos: usize| match self.words.iter().find(|word| word.range.contains(&pos)).map(|word| word.state) {
+            Some(Some(LemmaState::Known)) => egui::Color32::from_rgb(60, 170, 90),
+            Some(Some(LemmaState::Active)) => egui::Color32::from_rgb(230, 140, 30),
+            Some(Some(LemmaState::New) | None) => egui::Color32::from_rgb(220, 60, 60),
+            None if self.target_ranges.iter().any(|range| range.contains(&pos)) => default_color,
+            None => egui::Color32::GRAY,
+        };
+        let mut job = egui::text::LayoutJob::default();
+        let mut append = |text: &str, color: egui::Color32| {
+            job.append(text, 0.0, egui::TextFormat { color, ..Default::default() });
+        };
+        let mut run: Option<(usize, egui::Color32)> = None;
+        for (pos, _) in self.text.char_indices() {
+            let color = color_at(pos);
+            match run {
+                Some((_, run_color)) if run_color == color => {}
+                Some((start, run_color)) => {
+                    append(&self.text[start..pos], run_color);
+                    run = Some((pos, color));
                 }
+                None => run = Some((pos, color)),
             }
-            if overall_sentences_processed_this_run >= total_sentences_to_simulate_overall { break; }
-            // ... (log end of block / start of next block) ...
         }
-        self.simulation_log_output = accumulated_log_for_display.join("\n");
-        self.woven_text_output = accumulated_woven_text_for_display.trim_end().to_string();
+        if let Some((start, run_color)) = run {
+            append(&self.text[start..], run_color);
+        }
+        job
+    }
+
+    // The traced word at (or ending at) character `char_index`.
+    fn word_at(&self, char_index: usize) -> Option<&html::TracedWord> {
+        let pos = self.text.char_indices().nth(char_index).map_or(self.text.len(), |(pos, _)| pos);
+        self.words.iter().find(|word| word.range.start <= pos && pos <= word.range.end)
+    }
+}
+
+// Profile counts after one simulated block; block 0 is the profile before the run.
+#[derive(Debug, Clone, Copy)]
+struct VocabularyGrowthPoint {
+    block_index: usize,
+    known: usize,
+    active: usize,
+    new: usize, // Dictionary lemmas that are neither Known nor Active
+}
+
+impl VocabularyGrowthPoint {
+    fn from_profile(block_index: usize, profile: &GuiNumericalLearnerProfile, dictionary_size: usize) -> Self {
+        let known = profile.count_known();
+        let active = profile.count_active_only();
+        Self { block_index, known, active, new: dictionary_size.saturating_sub(known + active) }
+    }
+}
+
+// One simulated block's trace events, with the block they belong to.
+struct TracedBlock {
+    info: BlockInfo,
+    events: Vec<TraceEvent>,
+}
+
+// One-line description of a trace event for the trace inspector.
+fn describe_trace_event(event: &TraceEvent, dictionary: &GuiGlobalLemmaDictionary) -> String {
+    let lemma = |lemma_id: u32| dictionary.get_str(lemma_id).map_or_else(|| format!("#{}", lemma_id), |key| describe_lemma_key(key));
+    match event {
+        TraceEvent::BlockStart { sentences, known, active_only, target_ct, min_ct, candidates } =>
+            format!("{} sentence(s), K={} A={}, CT band {:.0}%-{:.0}%, {} candidate(s)",
+                    sentences, known, active_only, min_ct * 100.0, target_ct * 100.0, candidates),
+        TraceEvent::RegenAttempt { attempt, ct_metric, ct, known_tokens, total_tokens } =>
+            format!("attempt {}: {} CT {:.2}% ({}/{})", attempt, ct_metric, ct * 100.0, known_tokens, total_tokens),
+        TraceEvent::Activation { attempt, lemma_id, block_frequency } =>
+            format!("attempt {}: {} ({}x in block)", attempt, lemma(*lemma_id), block_frequency),
+        TraceEvent::Withhold { attempt, lemma_id, exposures } =>
+            format!("attempt {}: {} ({} exposure(s))", attempt, lemma(*lemma_id), exposures),
+        TraceEvent::LevelChoice { sentence_id, level, target_tokens, known_tokens } =>
+            format!("{} at {} ({}/{} Known)", sentence_id, level.name(), known_tokens, target_tokens),
+        TraceEvent::Finalize { attempts, ct_metric, ct, reason, .. } =>
+            format!("after {} attempt(s): {} CT {:.2}%. {}", attempts, ct_metric, ct * 100.0, reason),
+    }
+}
+
+// --- Background simulation ---
+
+// A book to simulate, or why it could not be loaded.
+struct SimulationBook {
+    label: String, // Book stem, or file name for a single chapter
+    stage_path: PathBuf,
+    chapters: Result<(GuiStringProcessedChapter, GuiNumericalChapter), String>,
+    overrides: corpus_generator::BookOverrides, // Per-book parameters from the sequence file
+}
+
+#[derive(Debug, Clone)]
+struct SimulationProgress {
+    book_index: usize, // 1-based
+    book_count: usize,
+    book_label: String,
+    block_index: usize, // Block being simulated, 1-based
+    estimated_blocks: usize,
+}
+
+// What one book produced on the worker thread.
+struct BookSimulationOutcome {
+    run: SequenceBookRun,
+    log: String,
+    woven_text: String,
+    vocabulary_growth: Vec<VocabularyGrowthPoint>,
+    block_traces: Vec<TracedBlock>,
+    preview: Vec<PreviewSentence>,
+}
+
+struct SimulationOutcome {
+    books: Vec<BookSimulationOutcome>,
+    profile: GuiNumericalLearnerProfile, // The worker's copy after the last book it completed
+    completed_books: usize,
+    cancelled: bool,
+    error: Option<String>, // The last book error
+    last_book: Option<(PathBuf, GuiStringProcessedChapter, GuiNumericalChapter)>,
+}
+
+// Lexicon panel requests, deferred while the lexicon loads.
+#[derive(Debug, Clone, Copy)]
+enum LexiconAction {
+    LookUp,
+    GlossMissing,
+}
+
+enum SimulationUpdate {
+    Progress(SimulationProgress),
+    Finished(Box<SimulationOutcome>),
+}
+
+// The GUI's handle on a running worker.
+struct SimulationJob {
+    updates: mpsc::Receiver<SimulationUpdate>,
+    cancel: Arc<AtomicBool>,
+    sequence: bool,
+    progress: Option<SimulationProgress>,
+}
+
+struct SimulationWorker {
+    books: Vec<SimulationBook>,
+    profile: GuiNumericalLearnerProfile,
+    dictionary: GuiGlobalLemmaDictionary,
+    params: OrchestratorParams,
+    updates: mpsc::Sender<SimulationUpdate>,
+    cancel: Arc<AtomicBool>,
+    ctx: egui::Context,
+}
+
+impl SimulationWorker {
+    // Simulates the books in order on the worker's own profile and sends the outcome back.
+    // A book that fails or is cancelled part-way leaves the profile as the previous book
+    // left it; after a failure the run goes on with the next book.
+    fn run(self) {
+        let SimulationWorker { books, mut profile, dictionary, mut params, updates, cancel, ctx } = self;
+        let book_count = books.len();
+        let run_params = (params.sentences_per_block, params.target_ct_threshold, params.min_ct_threshold);
+        let mut book_outcomes: Vec<BookSimulationOutcome> = Vec::new();
+        let mut cancelled = false;
+        let mut error = None;
+        let mut completed_books = 0;
+        let mut last_book = None;
+        for (book_idx, book) in books.into_iter().enumerate() {
+            if cancel.load(Ordering::Relaxed) {
+                cancelled = true;
+                break;
+            }
+            let mut run = SequenceBookRun { book_stem: book.label.clone(), ..Default::default() };
+            (params.sentences_per_block, params.target_ct_threshold, params.min_ct_threshold) =
+                book.overrides.resolve(run_params.0, run_params.1, run_params.2);
+            let (string_chapter, numerical_chapter) = match book.chapters {
+                Ok(chapters) => chapters,
+                Err(e) => {
+                    run.error = Some(e.clone());
+                    book_outcomes.push(BookSimulationOutcome {
+                        run,
+                        log: format!("ERROR: {}", e),
+                        woven_text: String::new(),
+                        vocabulary_growth: Vec::new(),
+                        block_traces: Vec::new(),
+                        preview: Vec::new(),
+                    });
+                    continue;
+                }
+            };
+
+            let initial_profile_stats = format!(
+                "INITIAL PROFILE for Run: Known: {}, Active (only): {}, Total K/A: {}, Vocab Size (Profile): {}, Global Dict Size: {}, Total Exposures: {}\n",
+                profile.count_known(), profile.count_active_only(),
+                profile.count_total_known_or_active(), profile.vocabulary_size(),
+                dictionary.size(), profile.total_exposure_count()
+            );
+            let mut gui_observer = GuiLogObserver {
+                log: vec![initial_profile_stats.clone()],
+                woven_text: format!("%%WEAVELANG_STAT%% {}", initial_profile_stats),
+                error: None,
+                dictionary_size: dictionary.size(),
+                vocabulary_growth: vec![VocabularyGrowthPoint::from_profile(0, &profile, dictionary.size())],
+                block_traces: Vec::new(),
+                preview: Vec::new(),
+                dictionary: &dictionary,
+                form_tokenizer: tokenizer::tokenizer_for_language(&string_chapter.language_pair.target),
+                progress: SimulationProgress {
+                    book_index: book_idx + 1,
+                    book_count,
+                    book_label: book.label.clone(),
+                    block_index: 0,
+                    estimated_blocks: (numerical_chapter.sentences_numerical.len() * params.passes.max(1))
+                        .div_ceil(params.sentences_per_block.max(1)),
+                },
+                updates: &updates,
+                cancel: &cancel,
+                ctx: &ctx,
+            };
+            let chapters = [ChapterInput { string_chapter: &string_chapter, numerical_chapter: &numerical_chapter }];
+            let profile_before_book = profile.clone();
+            match run_chapters_observed(&chapters, &mut profile, &dictionary, &params, None, None, &mut gui_observer) {
+                Ok(results) => {
+                    extend_introduction_history(&mut params.recent_introductions, &results);
+                    for result in &results {
+                        run.sentences += result.summary.sentences_processed;
+                        run.blocks += result.summary.blocks_processed;
+                        run.new_lemmas += result.summary.new_lemmas;
+                        run.average_ct += result.blocks.iter().map(|b| b.ct).sum::<f32>();
+                        cancelled |= result.summary.cancelled;
+                    }
+                    if run.blocks > 0 {
+                        run.average_ct /= run.blocks as f32;
+                    }
+                }
+                Err(e) => {
+                    gui_observer.log.push(format!("\nERROR: {}", e));
+                    gui_observer.error = Some(e.to_string());
+                }
+            }
+            if gui_observer.error.is_some() || cancelled {
+                profile = profile_before_book;
+            } else {
+                completed_books += 1;
+            }
+            run.known_after = profile.count_known();
+            run.active_after = profile.count_active_only();
+            run.error = gui_observer.error.clone();
+            if gui_observer.error.is_some() {
+                error = gui_observer.error;
+            }
+            book_outcomes.push(BookSimulationOutcome {
+                run,
+                log: gui_observer.log.join("\n"),
+                woven_text: gui_observer.woven_text.trim_end().to_string(),
+                vocabulary_growth: gui_observer.vocabulary_growth,
+                block_traces: gui_observer.block_traces,
+                preview: gui_observer.preview,
+            });
+            last_book = Some((book.stage_path, string_chapter, numerical_chapter));
+            if cancelled {
+                break;
+            }
+        }
+        let outcome = SimulationOutcome { books: book_outcomes, profile, completed_books, cancelled, error, last_book };
+        // The receiver is only gone if the GUI was closed.
+        let _ = updates.send(SimulationUpdate::Finished(Box::new(outcome)));
+        ctx.request_repaint();
+    }
+}
+
+// Collects the orchestrator's per-block log lines, woven text, vocabulary counts and trace
+// events for display, reports progress to the GUI and stops the run once cancelled.
+struct GuiLogObserver<'a> {
+    log: Vec<String>,
+    woven_text: String,
+    error: Option<String>,
+    dictionary_size: usize,
+    vocabulary_growth: Vec<VocabularyGrowthPoint>,
+    block_traces: Vec<TracedBlock>,
+    preview: Vec<PreviewSentence>,
+    dictionary: &'a GuiGlobalLemmaDictionary,
+    form_tokenizer: Box<dyn Tokenizer>, // For the chapter's target language
+    progress: SimulationProgress,
+    updates: &'a mpsc::Sender<SimulationUpdate>,
+    cancel: &'a AtomicBool,
+    ctx: &'a egui::Context,
+}
+
+impl OrchestratorObserver for GuiLogObserver<'_> {
+    fn on_block_start(&mut self, block: &BlockInfo, profile: &GuiNumericalLearnerProfile) {
+        self.progress.block_index = block.block_index;
+        let _ = self.updates.send(SimulationUpdate::Progress(self.progress.clone()));
+        self.ctx.request_repaint();
+        self.log.push(format!(
+            "\n--- GUI Orchestrator: Preparing Measurement Block {} ---",
+            block.block_index
+        ));
+        self.log.push(format!(
+            "GUI Orchestrator: Calling core_algo for block {} ({} sentences). Profile K: {}, A: {}",
+            block.block_index,
+            block.sentence_count,
+            profile.count_known(),
+            profile.count_active_only()
+        ));
+    }
+
+    fn on_lemmas_decayed(&mut self, block: &BlockInfo, lemma_ids: &[u32]) {
+        self.log.push(format!(
+            "GUI Orchestrator: {} Known lemma(s) decayed back to Active before block {}.",
+            lemma_ids.len(),
+            block.block_index
+        ));
+    }
+
+    fn on_block_simulated(&mut self, block: &BlockInfo, profile_after: &GuiNumericalLearnerProfile, result: &SimulationBlockResult, _transitions: &[LemmaTransition]) {
+        self.log.extend(result.simulation_log_entries.iter().cloned());
+        self.block_traces.push(TracedBlock { info: block.clone(), events: result.trace.clone() });
+        self.vocabulary_growth.push(VocabularyGrowthPoint::from_profile(
+            block.block_index,
+            profile_after,
+            self.dictionary_size,
+        ));
+    }
+
+    fn on_block_text(&mut self, _block: &BlockInfo, text: &str) {
+        self.woven_text.push_str(text);
+        if !text.trim().is_empty() && !self.woven_text.ends_with("\n\n") {
+            self.woven_text.push_str("\n\n");
+        }
+    }
+
+    fn on_block_error(&mut self, block: &BlockInfo, error: &str) {
+        let err_msg = format!("[GUI Orchestrator Error] Block {}: {}", block.block_index, error);
+        self.log.push(err_msg.clone());
+        self.error = Some(err_msg);
+    }
+
+    fn on_block_rendered(
+        &mut self,
+        block: &BlockInfo,
+        sentences: &[&ProcessedSentence],
+        generated: &GeneratedTextBlock,
+        profile_for_text: &GuiNumericalLearnerProfile,
+    ) {
+        let rendered = sentences.iter()
+            .zip(&generated.sentence_texts)
+            .zip(&generated.sentence_levels)
+            .zip(&generated.target_language_ranges);
+        for (((sentence, text), record), target_ranges) in rendered {
+            let words = html::trace_sentence_words(sentence, text, record.level, self.dictionary, profile_for_text, self.form_tokenizer.as_ref());
+            self.preview.push(PreviewSentence {
+                block_index: block.block_index,
+                text: text.clone(),
+                words,
+                target_ranges: target_ranges.clone(),
+            });
+        }
+    }
+
+    fn should_stop(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
     }
 }
 
 impl EframeApp for WeaveLangApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_simulation_job();
+        self.poll_lexicon(ctx);
+        // Settings, profile and file controls stay locked while a worker simulates.
+        let idle = self.simulation_job.is_none();
+
         // This is the FULL GUI layout from your previous working version
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
@@ -392,15 +2022,42 @@ impl EframeApp for WeaveLangApp {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
                 });
+                ui.menu_button("Edit", |ui| {
+                    let undo_label = self.profile_history.undo.back().map_or("Undo".to_string(), |s| format!("Undo {}", s.action));
+                    if ui.add_enabled(idle && !self.profile_history.undo.is_empty(), egui::Button::new(undo_label)).clicked() {
+                        self.undo_profile_change();
+                        ui.close_menu();
+                    }
+                    let redo_label = self.profile_history.redo.last().map_or("Redo".to_string(), |s| format!("Redo {}", s.action));
+                    if ui.add_enabled(idle && !self.profile_history.redo.is_empty(), egui::Button::new(redo_label)).clicked() {
+                        self.redo_profile_change();
+                        ui.close_menu();
+                    }
+                });
                 ui.menu_button("Profile", |ui| {
-                    if ui.button("Reset Learner Profile & Global Dictionary").clicked() {
-                        self.learner_profile = GuiNumericalLearnerProfile::new();
-                        self.global_lemma_dictionary = GuiGlobalLemmaDictionary::new();
-                        self.reset_simulation_outputs();
-                        self.reset_chapter_specific_data();
-                        self.selected_stage_file = None;
+                    if ui.add_enabled(idle, egui::Button::new("Reset Learner Profile & Global Dictionary")).clicked() {
+                        self.reset_learner_profile();
                     }
                 });
+                if let Some(job) = &self.simulation_job {
+                    ui.separator();
+                    match &job.progress {
+                        Some(progress) => {
+                            let fraction = progress.block_index.saturating_sub(1) as f32 / progress.estimated_blocks.max(1) as f32;
+                            let text = if job.sequence {
+                                format!("Book {}/{} {}: block {}/{}", progress.book_index, progress.book_count,
+                                        progress.book_label, progress.block_index, progress.estimated_blocks)
+                            } else {
+                                format!("Block {}/{}", progress.block_index, progress.estimated_blocks)
+                            };
+                            ui.add(egui::ProgressBar::new(fraction).desired_width(300.0).text(text));
+                        }
+                        None => { ui.spinner(); }
+                    }
+                    if ui.button("Cancel").clicked() {
+                        job.cancel.store(true, Ordering::Relaxed);
+                    }
+                }
             });
         });
 
@@ -410,6 +2067,7 @@ impl EframeApp for WeaveLangApp {
             .show(ctx, |ui| {
                 ui.heading("Controls & Info");
                 ui.separator();
+                ui.set_enabled(idle);
                 ui.collapsing("Configuration", |ui| {
                     if let Some(err) = &self.config_error {
                         ui.colored_label(egui::Color32::RED, format!("Config: {}", err));
@@ -419,6 +2077,26 @@ impl EframeApp for WeaveLangApp {
                         ui.label(&self.content_path_display);
                     }
                 });
+                if self.lexicon.is_some() {
+                    ui.collapsing("Lexicon", |ui| {
+                        ui.horizontal(|ui| {
+                            let response = ui.text_edit_singleline(&mut self.lexicon_query);
+                            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                            if ui.button("Look Up").clicked() || submitted {
+                                self.run_lexicon_action(LexiconAction::LookUp);
+                            }
+                        });
+                        if ui.button("Gloss Lemmas Missing From DIGLOT_MAP").clicked() {
+                            self.run_lexicon_action(LexiconAction::GlossMissing);
+                        }
+                        if !self.lexicon_output.is_empty() {
+                            egui::ScrollArea::vertical()
+                                .id_source("lexicon_output_scroll")
+                                .max_height(150.0)
+                                .show(ui, |ui| ui.label(&self.lexicon_output));
+                        }
+                    });
+                }
                 ui.separator();
 
                 if ui.button("Scan Stage Directory").clicked() {
@@ -429,21 +2107,31 @@ impl EframeApp for WeaveLangApp {
                 }
 
                 ui.add_space(5.0);
-                ui.label("Found Stage Files (.llm.txt):");
+                ui.label("Found Stage Files (.llm.txt, .json):");
                 egui::ScrollArea::vertical()
                     .id_source("stage_files_scroll_gui") // Unique ID
                     .max_height(150.0)
                     .show(ui, |ui| {
                         let mut path_to_load_onclick = None;
                         let files_clone = self.stage_files.clone();
-                        for p_idx in 0..files_clone.len() {
-                            let p = &files_clone[p_idx];
+                        for p in &files_clone {
                             let fname = p.file_name().unwrap_or_default().to_string_lossy();
                             let is_selected = self.selected_stage_file.as_ref() == Some(p);
-                            if ui.selectable_label(is_selected, fname).clicked() {
-                                if !is_selected {
-                                    path_to_load_onclick = Some(p.clone());
+                            let label = match self.stage_file_statuses.get(p) {
+                                Some(status) => {
+                                    let color = match status {
+                                        StageFileStatus::New => egui::Color32::LIGHT_BLUE,
+                                        StageFileStatus::Changed => egui::Color32::YELLOW,
+                                        StageFileStatus::Unchanged(ValidationOutcome::Clean) => egui::Color32::GREEN,
+                                        StageFileStatus::Unchanged(ValidationOutcome::Warnings(_)) => egui::Color32::GOLD,
+                                        StageFileStatus::Unchanged(ValidationOutcome::ParseError) => egui::Color32::RED,
+                                    };
+                                    egui::RichText::new(format!("{} [{}]", fname, status.label())).color(color)
                                 }
+                                None => egui::RichText::new(fname),
+                            };
+                            if ui.selectable_label(is_selected, label).clicked() && !is_selected {
+                                path_to_load_onclick = Some(p.clone());
                             }
                         }
                         if let Some(p_clicked) = path_to_load_onclick {
@@ -471,36 +2159,167 @@ impl EframeApp for WeaveLangApp {
                         ui.label("Target CT% (0.5-1.0):");
                         ui.add(egui::DragValue::new(&mut self.target_ct_threshold).speed(0.01).clamp_range(0.50..=1.0));
                     });
+                    ui.horizontal(|ui| {
+                        ui.label("Min CT% (floor, 0 = off):");
+                        ui.add(egui::DragValue::new(&mut self.min_ct_threshold).speed(0.01).clamp_range(0.0..=self.target_ct_threshold));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Min Sentence CT% (0 = off):");
+                        ui.add(egui::DragValue::new(&mut self.min_sentence_ct).speed(0.01).clamp_range(0.0..=1.0));
+                    });
                     ui.horizontal(|ui| {
                         ui.label("Max Activate/Regen:");
                         ui.add(egui::DragValue::new(&mut self.max_words_to_activate_per_regen).speed(1.0).clamp_range(1..=10));
                     });
+                    ui.horizontal(|ui| {
+                        let mut capped = self.max_new_lemmas_per_100_sentences.is_some();
+                        ui.checkbox(&mut capped, "Max New Lemmas/100 Sentences:");
+                        if capped != self.max_new_lemmas_per_100_sentences.is_some() {
+                            self.max_new_lemmas_per_100_sentences = capped.then_some(10.0);
+                        }
+                        if let Some(cap) = &mut self.max_new_lemmas_per_100_sentences {
+                            ui.add(egui::DragValue::new(cap).speed(0.5).clamp_range(0.0..=100.0));
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Min Diglot Confidence:");
+                        ui.add(egui::DragValue::new(&mut self.min_diglot_confidence).speed(0.05).clamp_range(0.0..=1.0));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Decay Half-Life (blocks, 0 = off):");
+                        ui.add(egui::DragValue::new(&mut self.decay_params.half_life_blocks).speed(0.5).clamp_range(0.0..=1000.0));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Decay Min Retention:");
+                        ui.add(egui::DragValue::new(&mut self.decay_params.min_retention).speed(0.05).clamp_range(0.0..=1.0));
+                    });
+                    ui.checkbox(&mut self.prefix_level_tags, "Prefix sentences with level tags ([L1]..[L5])");
+                    ui.horizontal(|ui| {
+                        ui.label("CT Metric:");
+                        egui::ComboBox::from_id_source("ct_metric_combo")
+                            .selected_text(self.ct_metric.name())
+                            .show_ui(ui, |ui| {
+                                for kind in CtMetricKind::ALL {
+                                    ui.selectable_value(&mut self.ct_metric, kind, kind.name());
+                                }
+                            });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("L4 Strategy:");
+                        egui::ComboBox::from_id_source("l4_strategy_combo")
+                            .selected_text(self.l4_strategy.name())
+                            .show_ui(ui, |ui| {
+                                for strategy in L4Strategy::ALL {
+                                    ui.selectable_value(&mut self.l4_strategy, strategy, strategy.name());
+                                }
+                            });
+                    });
+                    ui.checkbox(&mut self.l4_match_plurals, "L4: match plural words and pluralize the substituted form");
+                    ui.horizontal(|ui| {
+                        ui.label("Activation Target Interval (sentences):");
+                        ui.add(egui::DragValue::new(&mut self.scheduler_params.target_interval_sentences).speed(1.0).clamp_range(1..=1000));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Remaining-Corpus Frequency Weight (0 = off):");
+                        ui.add(egui::DragValue::new(&mut self.scheduler_params.remaining_frequency_weight).speed(0.05).clamp_range(0.0..=5.0));
+                    });
+                    ui.horizontal(|ui| {
+                        let mut seeded = self.scheduler_params.tie_break_seed.is_some();
+                        ui.checkbox(&mut seeded, "Tie-Break Seed:");
+                        if seeded != self.scheduler_params.tie_break_seed.is_some() {
+                            self.scheduler_params.tie_break_seed = seeded.then_some(0);
+                        }
+                        if let Some(seed) = &mut self.scheduler_params.tie_break_seed {
+                            ui.add(egui::DragValue::new(seed).speed(1.0));
+                        }
+                    });
                 });
                 ui.separator();
 
                 if self.current_numerical_chapter.is_some() {
                     if ui.button("Run Simulation Orchestrator (GUI)").clicked() {
-                        self.run_simulation_orchestrator();
+                        self.run_simulation_orchestrator(ctx);
                     }
                 } else if self.selected_stage_file.is_some() {
                     ui.label("File selected, but not parsed or error during parsing/conversion.");
                 }
 
+                ui.collapsing("Book Sequence (GUI Sim)", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Sequence file:");
+                        ui.text_edit_singleline(&mut self.sequence_path);
+                    });
+                    let can_run = self.config.is_some() && !self.sequence_path.trim().is_empty();
+                    if ui.add_enabled(can_run, egui::Button::new("Run Sequence (profile carries over)")).clicked() {
+                        self.run_book_sequence(ctx);
+                    }
+                    if let Some(status) = &self.sequence_status {
+                        ui.label(status);
+                    }
+                    self.show_sequence_results(ui);
+                });
+
                 if let Some(err) = &self.generation_error {
                     ui.colored_label(egui::Color32::RED, format!("Runtime Err: {}", err));
                 }
                 if let Some(err) = &self.parser_display_error {
                     ui.colored_label(egui::Color32::RED, format!("Parser/Load Err: {}", err));
                 }
+                if !self.validation_warnings.is_empty() {
+                    ui.collapsing(format!("Validation Warnings ({})", self.validation_warnings.len()), |ui| {
+                        egui::ScrollArea::vertical()
+                            .id_source("validation_warnings_scroll_gui")
+                            .max_height(150.0)
+                            .show(ui, |ui| {
+                                for warning in &self.validation_warnings {
+                                    ui.colored_label(egui::Color32::YELLOW, warning);
+                                }
+                            });
+                    });
+                }
                 ui.separator();
 
                 ui.collapsing("Learner Profile Stats (GUI Sim)", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Snapshot:");
+                        ui.text_edit_singleline(&mut self.profile_snapshot_path);
+                        if ui.add_enabled(!self.profile_snapshot_path.trim().is_empty(), egui::Button::new("Load")).clicked() {
+                            self.load_profile_snapshot();
+                        }
+                    });
+                    if let Some(status) = &self.profile_snapshot_status {
+                        ui.label(status);
+                    }
                     ui.label(format!("Known Lemmas: {}", self.learner_profile.count_known()));
                     ui.label(format!("Active (only) Lemmas: {}", self.learner_profile.count_active_only()));
                     ui.label(format!("Total Known or Active: {}", self.learner_profile.count_total_known_or_active()));
                     ui.label(format!("Total Vocabulary Size (Global Dict): {}", self.global_lemma_dictionary.size()));
                     ui.label(format!("Profile Vocab Size (Tracked Lemmas): {}", self.learner_profile.vocabulary_size()));
                     ui.label(format!("Sum of all Exposures in Profile: {}", self.learner_profile.total_exposure_count()));
+                    if !self.learner_profile.grammar.is_empty() {
+                        let (grammar_known, grammar_active) = self.learner_profile.count_grammar();
+                        ui.label(format!("Grammar Features Known / Active: {} / {}", grammar_known, grammar_active));
+                    }
+                    if self.global_lemma_dictionary.form_count() > 0 {
+                        let (forms_seen, learned_forms) = self.learner_profile.form_coverage(&self.global_lemma_dictionary);
+                        ui.label(format!("Tracked Forms (Global Dict): {}", self.global_lemma_dictionary.form_count()));
+                        ui.label(format!("Forms Read of Known/Active Lemmas: {} / {}", forms_seen, learned_forms));
+                    }
+                });
+                ui.separator();
+
+                ui.collapsing("Word States (GUI Sim)", |ui| {
+                    self.show_word_state_editor(ui);
+                });
+                ui.separator();
+
+                ui.collapsing("Vocabulary Growth (GUI Sim)", |ui| {
+                    self.show_vocabulary_growth_plot(ui);
+                });
+                ui.separator();
+
+                ui.collapsing("Simulation Trace (GUI Sim)", |ui| {
+                    self.show_trace_inspector(ui);
                 });
                 ui.separator();
 
@@ -570,8 +2389,31 @@ impl EframeApp for WeaveLangApp {
                     .auto_shrink([false, false])
                     .show(&mut columns[2], |ui| {
                         ui.heading("Generated Woven Text (GUI Sim)");
+                        ui.add_enabled(!self.woven_preview.is_empty(),
+                                       egui::Checkbox::new(&mut self.show_woven_preview, "Color words by learner state (click one to inspect it)"));
                         ui.separator();
-                        if !self.woven_text_output.is_empty() {
+                        if self.show_woven_preview && !self.woven_preview.is_empty() {
+                            let default_color = ui.visuals().text_color();
+                            let mut previous_block = None;
+                            for sentence in &self.woven_preview {
+                                if previous_block.is_some_and(|block| block != sentence.block_index) {
+                                    ui.add_space(8.0);
+                                }
+                                previous_block = Some(sentence.block_index);
+                                let (pos, galley, response) = egui::Label::new(sentence.layout_job(default_color))
+                                    .selectable(false)
+                                    .sense(egui::Sense::click())
+                                    .layout_in_ui(ui);
+                                ui.painter().galley(pos, galley.clone(), default_color);
+                                if response.clicked() {
+                                    let clicked_word = response.interact_pointer_pos()
+                                        .and_then(|pointer| sentence.word_at(galley.cursor_from_pos(pointer - pos).ccursor.index));
+                                    if let Some(word) = clicked_word {
+                                        self.inspected_lemma = self.global_lemma_dictionary.get_id(&word.lemma);
+                                    }
+                                }
+                            }
+                        } else if !self.woven_text_output.is_empty() {
                             let mut s_display = self.woven_text_output.clone();
                             ui.add(
                                 egui::TextEdit::multiline(&mut s_display)
@@ -596,18 +2438,126 @@ impl EframeApp for WeaveLangApp {
                     });
             });
         });
+        self.show_lemma_inspector(ctx);
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.save_session_state();
     }
 }
 
 // --- Main Function ---
+// GenerationArgs from the generate flags, falling back to the project config for the
+// settings it can provide; shared by generate and sweep.
+fn generation_args_from_cli(generate_args: GenerateCliArgs, config: &Config) -> Result<corpus_generator::GenerationArgs, Box<dyn Error>> {
+    let sentences_per_block = generate_args.sentences_per_block
+        .or(config.sentences_per_block).unwrap_or(200);
+    let block_boundaries = generate_args.paragraph_block_tolerance
+        .or(config.paragraph_block_tolerance)
+        .map_or(BlockBoundaries::Fixed, |tolerance| BlockBoundaries::Paragraphs { tolerance });
+    let target_ct_threshold = generate_args.target_ct_threshold
+        .or(config.target_ct_threshold).unwrap_or(0.98);
+    let min_ct_threshold = generate_args.min_ct_threshold
+        .or(config.min_ct_threshold).unwrap_or(0.0);
+    if sentences_per_block == 0 {
+        return Err("--sentences-per-block must be at least 1.".into());
+    }
+    if generate_args.subtitle_wpm <= 0.0 || generate_args.subtitle_gap < 0.0 {
+        return Err("--subtitle-wpm must be positive and --subtitle-gap must not be negative.".into());
+    }
+    if generate_args.exposure_threshold == Some(0) {
+        return Err("--exposure-threshold must be at least 1.".into());
+    }
+    if generate_args.cognate_threshold == Some(0) {
+        return Err("--cognate-threshold must be at least 1.".into());
+    }
+    if min_ct_threshold > target_ct_threshold {
+        return Err(format!("--min-ct-threshold ({}) must not exceed --target-ct-threshold ({}).",
+                           min_ct_threshold, target_ct_threshold).into());
+    }
+
+    Ok(corpus_generator::GenerationArgs {
+        sequence_path: generate_args.sequence,
+        tts_output_dir: generate_args.tts_output_dir,
+        profiles_dir: generate_args.profiles_dir,
+        start_profile_path: generate_args.start_profile,
+        sentences_per_block,
+        block_boundaries,
+        max_regen_attempts_per_block: generate_args.max_regen_attempts_per_block,
+        target_ct_threshold,
+        min_ct_threshold,
+        max_words_to_activate_per_regen: generate_args.max_words_to_activate_per_regen,
+        min_diglot_confidence: generate_args.min_diglot_confidence,
+        decay: DecayParams {
+            half_life_blocks: generate_args.decay_half_life_blocks,
+            min_retention: generate_args.decay_min_retention,
+        },
+        ct_metric: generate_args.ct_metric.unwrap_or(config.ct_metric),
+        l4_strategy: generate_args.l4_strategy.unwrap_or(config.l4_strategy),
+        l4_match_plurals: generate_args.l4_match_plurals || config.l4_match_plurals,
+        scheduler: SchedulerParams {
+            target_interval_sentences: generate_args.activation_target_interval,
+            remaining_frequency_weight: generate_args.remaining_frequency_weight,
+            tie_break_seed: generate_args.seed,
+            ..SchedulerParams::default()
+        },
+        max_new_lemmas_per_100_sentences: generate_args.max_new_lemmas_per_100_sentences
+            .or(config.max_new_lemmas_per_100_sentences),
+        passes_per_book: generate_args.passes_per_book,
+        max_auto_passes: generate_args.max_auto_passes,
+        snapshot_every_blocks: generate_args.snapshot_every,
+        parallel_lookahead: generate_args.parallel_lookahead,
+        resume: generate_args.resume,
+        snapshot_format: generate_args.profile_format
+            .or(config.profile_format).unwrap_or_default(),
+        level_tags: generate_args.level_tags,
+        level_policy: generate_args.levels.unwrap_or_else(|| config.levels.clone())
+            .with_min_sentence_ct(generate_args.min_sentence_ct.unwrap_or(config.min_sentence_ct)),
+        level_sidecar: generate_args.level_sidecar,
+        audio_manifest: generate_args.audio_manifest,
+        subtitle_format: generate_args.subtitles,
+        parallel_text_format: generate_args.parallel_text,
+        subtitle_timing: SubtitleTiming {
+            words_per_minute: generate_args.subtitle_wpm,
+            gap_seconds: generate_args.subtitle_gap,
+            ..SubtitleTiming::default()
+        },
+        trace: generate_args.trace,
+        html_output_dir: generate_args.html_output_dir,
+        epub_output_dir: generate_args.epub_output_dir,
+        epub_chapter_mode: generate_args.epub_chapters,
+        output_format: generate_args.output_format
+            .or(config.output_format).unwrap_or_default(),
+        ssml_base_voice: generate_args.ssml_base_voice,
+        ssml_target_voice: generate_args.ssml_target_voice,
+        anki_output_dir: generate_args.anki_output_dir,
+        seed_dictionary: generate_args.seed_dictionary,
+        known_words: generate_args.known_words,
+        seed: generate_args.seed,
+        dry_run: generate_args.dry_run,
+        track_forms: generate_args.track_forms || config.track_forms,
+        chapter_cache_dir: (!generate_args.no_chapter_cache)
+            .then(|| ChapterCache::default_dir(config)),
+        exposure_thresholds: generate_args.exposure_thresholds
+            .or_else(|| config.exposure_thresholds_path.as_ref().map(PathBuf::from)),
+        default_exposure_threshold: generate_args.exposure_threshold
+            .or(config.exposure_threshold),
+        cognate_exposure_threshold: generate_args.cognate_threshold
+            .or(config.cognate_exposure_threshold),
+        learner_variability: None,
+    })
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
+    init_logging(cli.log_level.as_deref(), cli.log_json)?;
 
-    let project_app_config_result = weavelang_rust_gui::config::load_config_from_file(
+    let project_app_config_result = weavelang_rust_gui::config::load_layered_config(
         cli.config.to_str().unwrap_or("config.toml"),
+        &cli.config_overrides,
     );
 
-    let mut project_app_config_for_gui: Option<Config> = None;
+    let project_app_config_for_gui: Option<Config>;
     let mut config_error_msg_for_gui: Option<String> = None;
 
     let config_for_generate_mode: Option<Config>;
@@ -615,16 +2565,17 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     match project_app_config_result {
         Ok(loaded_config) => {
-            println!("Successfully loaded project configuration from: {:?}", cli.config);
+            eprintln!("Successfully loaded project configuration from: {:?}", cli.config); // stderr keeps `validate --json` output clean
+            weavelang_rust_gui::unicode_norm::set_diacritic_mode(loaded_config.diacritics);
             project_app_config_for_gui = Some(loaded_config.clone()); // Clone for GUI
             config_for_generate_mode = Some(loaded_config); // Move for generate mode
         }
         Err(err_msg) => {
             eprintln!("Error loading project configuration from {:?}: {}", cli.config, err_msg);
-            config_error_msg_for_gui = Some(err_msg.clone());
+            config_error_msg_for_gui = Some(err_msg.to_string());
             project_app_config_for_gui = None;
             config_for_generate_mode = None; // No config available for generate mode
-            if matches!(cli.command, Some(Commands::Generate(_))) {
+            if matches!(cli.command, Some(Commands::Generate(_) | Commands::Sweep(_) | Commands::MonteCarlo(_))) {
                 return Err(Box::new(std::io::Error::new(
                     std::io::ErrorKind::NotFound,
                     format!("Failed to load config file {:?}: {}", cli.config, err_msg),
@@ -654,25 +2605,236 @@ fn main() -> Result<(), Box<dyn Error>> {
             // ... (print args as before) ...
 
             let final_config_for_generate = config_for_generate_mode.ok_or_else(|| {
-                std::io::Error::new(std::io::ErrorKind::Other, "Project config is required for generate mode but was not loaded successfully.")
+                std::io::Error::other("Project config is required for generate mode but was not loaded successfully.")
             })?;
+            let corpus_gen_args = generation_args_from_cli(*generate_args, &final_config_for_generate)?;
 
-            let corpus_gen_args = corpus_generator::GenerationArgs {
-                sequence_path: generate_args.sequence,
-                tts_output_dir: generate_args.tts_output_dir,
-                profiles_dir: generate_args.profiles_dir,
-                start_profile_path: generate_args.start_profile,
-                sentences_per_block: generate_args.sentences_per_block,
-                max_regen_attempts_per_block: generate_args.max_regen_attempts_per_block,
-                target_ct_threshold: generate_args.target_ct_threshold,
-                max_words_to_activate_per_regen: generate_args.max_words_to_activate_per_regen,
+            if corpus_gen_args.dry_run {
+                let dry_run = corpus_generator::dry_run_corpus_generation(&final_config_for_generate, &corpus_gen_args)
+                    .inspect(|report| print!("{}", report.to_text()))
+                    .and_then(|report| report.check_books());
+                if let Err(e) = dry_run {
+                    eprintln!("Corpus generation failed: {}", e);
+                    std::process::exit(1);
+                }
+                println!("Dry run completed: all books parsed.");
+            } else {
+                match corpus_generator::run_corpus_generation(&final_config_for_generate, &corpus_gen_args) {
+                    Err(e) => {
+                        eprintln!("Corpus generation failed: {}", e);
+                        std::process::exit(1);
+                    }
+                    Ok(report) => {
+                        println!("\n{}", report.summary_table());
+                        println!("Corpus generation completed successfully.");
+                    }
+                }
+            }
+        }
+        Commands::Sweep(sweep_args) => {
+            let config = config_for_generate_mode.ok_or("A project config is required for sweep mode.")?;
+            if sweep_args.exposure_values.contains(&0) || sweep_args.activation_values.contains(&0) {
+                return Err("--exposure-values and --activation-values must be at least 1.".into());
+            }
+            let grid = sweep::SweepGrid {
+                target_ct_thresholds: sweep_args.ct_values,
+                exposure_thresholds: sweep_args.exposure_values,
+                activation_rates: sweep_args.activation_values,
             };
-
-            if let Err(e) = corpus_generator::run_corpus_generation(&final_config_for_generate, &corpus_gen_args) {
-                eprintln!("Corpus generation failed: {}", e);
+            let base_args = generation_args_from_cli(sweep_args.generate, &config)?;
+            let points = grid.points(&base_args);
+            println!("Sweeping {} combination(s).", points.len());
+            let rows = sweep::run_sweep(&config, &base_args, &points, sweep_args.jobs)?;
+            sweep::write_sweep_csv(&rows, &sweep_args.output)?;
+            let failed = rows.iter().filter(|row| row.error.is_some()).count();
+            println!("Sweep completed: {} combination(s), {} failed. Results in {}.", rows.len(), failed, sweep_args.output.display());
+            if failed > 0 {
                 std::process::exit(1);
+            }
+        }
+        Commands::MonteCarlo(monte_carlo_args) => {
+            let config = config_for_generate_mode.ok_or("A project config is required for Monte Carlo mode.")?;
+            let settings = monte_carlo::MonteCarloSettings {
+                learners: monte_carlo_args.learners,
+                distribution: monte_carlo_args.threshold_distribution,
+                spread: monte_carlo_args.threshold_spread,
+                seed: monte_carlo_args.generate.seed.unwrap_or(0),
+            };
+            let base_args = generation_args_from_cli(monte_carlo_args.generate, &config)?;
+            println!("Simulating {} learner(s).", settings.learners);
+            let runs = monte_carlo::run_learners(&config, &base_args, &settings, monte_carlo_args.jobs)?;
+            let spread = monte_carlo::growth_spread(&runs);
+            monte_carlo::write_growth_spread_csv(&spread, &monte_carlo_args.output)?;
+            if let Some(last) = spread.last() {
+                println!("Known lemmas after {}: median {}, 10th-90th percentile {}-{}, range {}-{}.",
+                         last.book_instance_id, last.median, last.p10, last.p90, last.min, last.max);
+            }
+            let failed = runs.iter().filter(|run| run.error.is_some()).count();
+            println!("Monte Carlo run completed: {} learner(s), {} failed. Results in {}.", runs.len(), failed, monte_carlo_args.output.display());
+            if failed > 0 {
+                std::process::exit(1);
+            }
+        }
+        Commands::Gc(gc_args) => {
+            let mut chapters: Vec<(GuiNumericalChapter, GuiGlobalLemmaDictionary)> = Vec::new();
+            for cache_dir in &gc_args.chapter_caches {
+                let cached = ChapterCache::new(cache_dir).load_all()
+                    .map_err(|e| format!("Dictionary GC failed: {}", e))?;
+                chapters.extend(cached.into_iter().map(|book| (book.numerical_chapter, book.local_dictionary)));
+            }
+            for stage_file in &gc_args.stage_files {
+                let file_name = stage_file.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                let contents = fs::read_to_string(stage_file)
+                    .map_err(|e| format!("Dictionary GC failed: cannot read {}: {}", stage_file.display(), e))?;
+                let string_chapter = chapter_loader::parse_chapter(&file_name, &contents)
+                    .map_err(|e| format!("Dictionary GC failed: {}", e))?;
+                let mut chapter_dictionary = GuiGlobalLemmaDictionary::new();
+                let numerical_chapter = preprocessor::to_numerical_chapter(&string_chapter, &mut chapter_dictionary);
+                chapters.push((numerical_chapter, chapter_dictionary));
+            }
+            let chapter_refs: Vec<_> = chapters.iter().map(|(chapter, dictionary)| (chapter, dictionary)).collect();
+            match profile_io::gc_profile_snapshots(&gc_args.snapshots, &chapter_refs, &gc_args.output_dir) {
+                Ok(report) => {
+                    println!("Dictionary GC: {} -> {} lemmas ({} removed).",
+                             report.dictionary_size_before, report.dictionary_size_after, report.removed_lemmas.len());
+                    for removed in &report.removed_lemmas {
+                        println!("  removed: {}", removed);
+                    }
+                    for written in &report.written_files {
+                        println!("  wrote: {}", written.display());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Dictionary GC failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Profile(ProfileCommands::Diff(diff_args)) => {
+            let diff = profile_io::diff_profile_snapshots(&diff_args.before, &diff_args.after)
+                .map_err(|e| format!("Profile diff failed: {}", e))?;
+            if diff_args.json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
             } else {
-                println!("Corpus generation completed successfully.");
+                print!("{}", diff.report());
+            }
+        }
+        Commands::Profile(ProfileCommands::Merge(merge_args)) => {
+            let (profile, dictionary) = profile_io::merge_profile_snapshots(&merge_args.a, &merge_args.b, &merge_args.output, merge_args.format)
+                .map_err(|e| format!("Profile merge failed: {}", e))?;
+            println!("Merged profile written to {}: {} Known, {} Active, {} lemma(s) in the dictionary.",
+                     merge_args.output.display(), profile.count_known(), profile.count_active_only(), dictionary.size());
+        }
+        Commands::Dict(DictCommands::Export(export_args)) => {
+            let exported = profile_io::load_profile_snapshot(&export_args.snapshot)
+                .and_then(|(_, dictionary)| match &export_args.output {
+                    Some(output_path) => dictionary.export_tsv(output_path).map(|_| {
+                        eprintln!("Exported {} lemma(s) to {}", dictionary.size(), output_path.display());
+                    }),
+                    None => { print!("{}", dictionary.to_tsv()); Ok(()) }
+                });
+            if let Err(e) = exported {
+                eprintln!("Dictionary export failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Dict(DictCommands::Audit(audit_args)) => {
+            let clusters = profile_io::audit_snapshot_lemmas(&audit_args.snapshots, audit_args.max_distance)
+                .map_err(|e| format!("Dictionary audit failed: {}", e))?;
+            println!("{} near-duplicate lemma cluster(s):", clusters.len());
+            for cluster in &clusters {
+                let members: Vec<String> = cluster.iter()
+                    .map(|member| format!("{} (#{}, {} exposures)", member.lemma, member.lemma_id, member.exposures))
+                    .collect();
+                println!("  {}", members.join(" ~ "));
+            }
+            if let Some(output_dir) = &audit_args.merge_into {
+                match profile_io::merge_duplicate_lemmas_in_snapshots(&audit_args.snapshots, audit_args.max_distance, output_dir) {
+                    Ok(report) => {
+                        println!("Merged {} lemma(s): {} -> {} lemmas.",
+                                 report.removed_lemmas.len(), report.dictionary_size_before, report.dictionary_size_after);
+                        for written in &report.written_files {
+                            println!("  wrote: {}", written.display());
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Merging duplicate lemmas failed: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        Commands::Serve(serve_args) => {
+            let listener = std::net::TcpListener::bind(&serve_args.address)
+                .map_err(|e| format!("Failed to listen on {}: {}", serve_args.address, e))?;
+            println!("Serving on http://{} (POST /parse, /simulate-block, /generate-text, /rpc)", serve_args.address);
+            weavelang_rust_gui::server::serve(listener)?;
+        }
+        Commands::Validate(validate_args) => {
+            match run_validate_command(&validate_args, config_for_generate_mode.as_ref()) {
+                Ok(true) => {}
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("Validation failed: {}", e);
+                    std::process::exit(2);
+                }
+            }
+        }
+        Commands::Analyze(analyze_args) => {
+            let config = config_for_generate_mode.as_ref().ok_or("A project config is required to resolve --sequence book stems.")?;
+            let analysis = corpus_analysis::analyze_corpus(config, &analyze_args.sequence, analyze_args.sentences_per_block,
+                                                           analyze_args.min_diglot_confidence, analyze_args.top)?;
+            if analyze_args.json {
+                println!("{}", serde_json::to_string_pretty(&analysis)?);
+            } else {
+                print!("{}", analysis.report());
+            }
+        }
+        Commands::Plan(plan_args) => {
+            let config = config_for_generate_mode.as_ref().ok_or("A project config is required to locate the stage directory.")?;
+            let mut book_stems = match &plan_args.sequence {
+                Some(sequence_path) => corpus_generator::load_book_sequence(sequence_path)?,
+                None if plan_args.books.is_empty() => corpus_planner::stage_book_stems(config)?,
+                None => Vec::new(),
+            };
+            book_stems.extend(plan_args.books.iter().cloned());
+            let plan = corpus_planner::plan_sequence(config, &book_stems, plan_args.sentences_per_block,
+                                                     plan_args.min_diglot_confidence, plan_args.start_profile.as_deref())?;
+            match &plan_args.output {
+                Some(output_path) => {
+                    fs::write(output_path, plan.sequence_file_contents())
+                        .map_err(|e| format!("Failed to write sequence file {}: {}", output_path.display(), e))?;
+                    print!("{}", plan.report());
+                    println!("Sequence written to {}", output_path.display());
+                }
+                None => {
+                    eprint!("{}", plan.report()); // stdout carries only the sequence file
+                    print!("{}", plan.sequence_file_contents());
+                }
+            }
+        }
+        Commands::RepairStage(repair_args) => {
+            let output_dir = repair_args.output_dir.unwrap_or_else(|| repair_args.stage_dir.join("repaired"));
+            let language_pair = config_for_generate_mode.as_ref().map(|c| c.language_pair.clone()).unwrap_or_default();
+            let lemmatizer_command = repair_args.lemmatizer_command.clone()
+                .or_else(|| config_for_generate_mode.as_ref().and_then(|c| c.lemmatizer_command.clone()));
+            match stage_repair::repair_stage_directory(&repair_args.stage_dir, &output_dir, &language_pair, lemmatizer_command.as_deref()) {
+                Ok(report) => {
+                    for file in &report.files {
+                        match &file.error {
+                            Some(e) => println!("  {}: {}", file.file_name, e),
+                            None => println!("  {}: {} change(s), {} validation warning(s)",
+                                             file.file_name, file.changes.len(), file.validation_issues.len()),
+                        }
+                    }
+                    println!("Stage repair: {} change(s) across {} file(s). Report: {}",
+                             report.total_changes(), report.files.len(),
+                             output_dir.join(stage_repair::REPAIR_REPORT_FILE_NAME).display());
+                }
+                Err(e) => {
+                    eprintln!("Stage repair failed: {}", e);
+                    std::process::exit(1);
+                }
             }
         }
     }
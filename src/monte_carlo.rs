@@ -0,0 +1,159 @@
+//*** START FILE: src/monte_carlo.rs ***//
+// `weavelang monte-carlo`: runs the same corpus sequence for N simulated learners whose
+// per-lemma exposure thresholds are randomized (see LearnerVariability), then reports how
+// widely their vocabulary growth spreads book by book. A sequence whose spread stays
+// narrow works for slow and fast learners alike; a wide one depends on the learner.
+//
+// Learners differ only in their thresholds: the scheduler seed and every other setting
+// come from the base GenerationArgs, so the spread is the thresholds' effect alone.
+
+use crate::config::Config;
+use crate::corpus_generator::{self, GenerationArgs};
+use crate::determinism::tie_break_key;
+use crate::exposure_thresholds::{LearnerVariability, ThresholdDistribution};
+use crate::progress::NoProgress;
+use rayon::prelude::*;
+use crate::error::WeaveLangError;
+use std::fs;
+use std::path::Path;
+use tracing::{error, info};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonteCarloSettings {
+    pub learners: usize,
+    pub distribution: ThresholdDistribution,
+    pub spread: f32,
+    pub seed: u64, // Learner i draws its thresholds with tie_break_key(seed, i)
+}
+
+impl MonteCarloSettings {
+    pub fn learner_variability(&self, learner: usize) -> LearnerVariability {
+        LearnerVariability { distribution: self.distribution, spread: self.spread, seed: tie_break_key(self.seed, learner as u64) }
+    }
+}
+
+/// One simulated learner's run. `error` is set when the run failed as a whole.
+#[derive(Debug, Clone, Default)]
+pub struct LearnerRun {
+    pub learner: usize, // 1-based
+    pub known_after: Vec<(String, usize)>, // (book instance id, Known lemmas after it)
+    pub final_known: usize,
+    pub final_active: usize,
+    pub error: Option<String>,
+}
+
+/// Spread of the learners' Known counts after one book instance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrowthSpread {
+    pub book_instance_id: String,
+    pub learners: usize, // Learners that finished the book instance
+    pub min: usize,
+    pub p10: usize,
+    pub median: usize,
+    pub p90: usize,
+    pub max: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+}
+
+impl GrowthSpread {
+    fn from_counts(book_instance_id: &str, counts: &mut [usize]) -> Self {
+        counts.sort_unstable();
+        let n = counts.len();
+        // Nearest-rank percentile
+        let percentile = |p: f64| counts[((p * n as f64).ceil() as usize).clamp(1, n) - 1];
+        let mean = counts.iter().sum::<usize>() as f64 / n as f64;
+        let variance = counts.iter().map(|&c| (c as f64 - mean).powi(2)).sum::<f64>() / n as f64;
+        Self {
+            book_instance_id: book_instance_id.to_string(),
+            learners: n,
+            min: counts[0],
+            p10: percentile(0.1),
+            median: percentile(0.5),
+            p90: percentile(0.9),
+            max: counts[n - 1],
+            mean,
+            std_dev: variance.sqrt(),
+        }
+    }
+}
+
+/// Runs the base generation once per learner, at most `jobs` at a time (0 = one per CPU).
+/// Learner i writes into `<tts_output_dir>/learner_<i>` and `<profiles_dir>/learner_<i>`
+/// of the base args.
+pub fn run_learners(config: &Config, base: &GenerationArgs, settings: &MonteCarloSettings, jobs: usize) -> Result<Vec<LearnerRun>, WeaveLangError> {
+    if base.dry_run || base.resume {
+        return Err(WeaveLangError::generation("A Monte Carlo run cannot be a dry run or resume an earlier run."));
+    }
+    if settings.learners == 0 {
+        return Err(WeaveLangError::generation("At least one learner is needed."));
+    }
+    if settings.spread.is_nan() || settings.spread < 0.0 {
+        return Err(WeaveLangError::generation(format!("The threshold spread must not be negative (got {}).", settings.spread)));
+    }
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs).build()
+        .map_err(|e| WeaveLangError::generation(format!("Failed to start the Monte Carlo worker threads: {}", e)).with_source(e))?;
+    let runs = pool.install(|| {
+        (1..=settings.learners).into_par_iter().map(|learner| {
+            let label = format!("learner_{:03}", learner);
+            let args = GenerationArgs {
+                tts_output_dir: base.tts_output_dir.join(&label),
+                profiles_dir: base.profiles_dir.join(&label),
+                learner_variability: Some(settings.learner_variability(learner)),
+                // Lookahead books would be prepared on this pool, whose threads are all busy
+                // with learners waiting for them.
+                parallel_lookahead: 0,
+                ..base.clone()
+            };
+            let mut run = LearnerRun { learner, ..LearnerRun::default() };
+            match corpus_generator::run_corpus_generation_with_progress(config, &args, &mut NoProgress) {
+                Ok(report) => {
+                    run.known_after = report.books.iter().map(|book| (book.book_instance_id.clone(), book.known_after)).collect();
+                    if let Some(last) = report.books.last() {
+                        run.final_known = last.known_after;
+                        run.final_active = last.active_after;
+                    }
+                    info!("Monte Carlo: {} finished ({} Known, {} Active)", label, run.final_known, run.final_active);
+                }
+                Err(e) => {
+                    error!("Monte Carlo: {} failed: {}", label, e);
+                    run.error = Some(e.to_string());
+                }
+            }
+            run
+        }).collect()
+    });
+    Ok(runs)
+}
+
+/// Spread of the Known counts per book instance, in sequence order. Failed runs are left out.
+pub fn growth_spread(runs: &[LearnerRun]) -> Vec<GrowthSpread> {
+    let mut counts_by_book: Vec<(String, Vec<usize>)> = Vec::new();
+    for (book_instance_id, known) in runs.iter().filter(|run| run.error.is_none()).flat_map(|run| &run.known_after) {
+        match counts_by_book.iter_mut().find(|(id, _)| id == book_instance_id) {
+            Some((_, counts)) => counts.push(*known),
+            None => counts_by_book.push((book_instance_id.clone(), vec![*known])),
+        }
+    }
+    counts_by_book.iter_mut().map(|(id, counts)| GrowthSpread::from_counts(id, counts)).collect()
+}
+
+/// The spread as CSV, one line per book instance.
+pub fn growth_spread_csv(spread: &[GrowthSpread]) -> String {
+    let mut csv = String::from("book_instance_id,learners,known_min,known_p10,known_median,known_p90,known_max,known_mean,known_std_dev\n");
+    for row in spread {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{:.2},{:.2}\n",
+            row.book_instance_id, row.learners, row.min, row.p10, row.median, row.p90, row.max, row.mean, row.std_dev
+        ));
+    }
+    csv
+}
+
+/// Writes growth_spread_csv(spread) to `file_path`.
+pub fn write_growth_spread_csv(spread: &[GrowthSpread], file_path: &Path) -> Result<(), WeaveLangError> {
+    fs::write(file_path, growth_spread_csv(spread))
+        .map_err(|e| WeaveLangError::generation(format!("Failed to write Monte Carlo CSV to {:?}: {}", file_path, e)).with_source(e))?;
+    Ok(())
+}
+//*** END FILE: src/monte_carlo.rs ***//
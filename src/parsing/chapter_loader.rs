@@ -0,0 +1,115 @@
+//*** START FILE: src/parsing/chapter_loader.rs ***//
+// Stage files in any supported format. Besides the .llm.txt marker format, a .json file
+// holding a serialized ProcessedChapter (the shape llm_parser produces), or an array of
+// them for a multi-chapter book, is accepted; the format is picked from the file extension,
+// so stage directories may mix formats. .yaml and .yml files are recognized but rejected
+// until a YAML parser is linked. Any other .txt file is a plain base-language book without
+// markers (see plain_text).
+
+use crate::error::WeaveLangError;
+use crate::types::llm_data::{join_chapters, ProcessedChapter};
+use super::llm_parser::{self, ParseDiagnostic};
+use super::plain_text;
+use super::validation;
+use crate::unicode_norm::to_nfc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChapterFormat {
+    LlmText,
+    Json,
+    Yaml,
+    PlainText,
+}
+
+impl ChapterFormat {
+    /// In lookup order: a book stem present in several formats resolves to the first.
+    /// ".txt" comes last, so "book.llm.txt" is never taken for a plain-text book.
+    pub const ALL: [ChapterFormat; 4] = [ChapterFormat::LlmText, ChapterFormat::Json, ChapterFormat::Yaml, ChapterFormat::PlainText];
+
+    /// File name suffixes, with the leading dot.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ChapterFormat::LlmText => &[".llm.txt"],
+            ChapterFormat::Json => &[".json"],
+            ChapterFormat::Yaml => &[".yaml", ".yml"],
+            ChapterFormat::PlainText => &[".txt"],
+        }
+    }
+
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.extensions().iter().any(|ext| file_name.ends_with(ext)))
+    }
+}
+
+/// Every stage file suffix, in lookup order.
+pub fn stage_file_extensions() -> impl Iterator<Item = &'static str> {
+    ChapterFormat::ALL.into_iter().flat_map(|format| format.extensions().iter().copied())
+}
+
+/// Whether a file name has one of the stage file suffixes.
+pub fn is_stage_file_name(file_name: &str) -> bool {
+    ChapterFormat::from_file_name(file_name).is_some()
+}
+
+/// The book stem of a stage file name ("bookA.json" -> "bookA"), if it is one.
+pub fn book_stem(file_name: &str) -> Option<&str> {
+    stage_file_extensions().find_map(|ext| file_name.strip_suffix(ext)).filter(|stem| !stem.is_empty())
+}
+
+/// Parses stage file contents in the format given by the file name's extension. An empty
+/// `source_file_name` in a JSON chapter is replaced by the actual file name. Contents are
+/// NFC-composed first, so decomposed accents match the dictionary's keys and forms.
+/// A multi-chapter book comes back as one chapter; see parse_chapters.
+pub fn parse_chapter(source_file_name: &str, contents: &str) -> Result<ProcessedChapter, WeaveLangError> {
+    let contents = &*to_nfc(contents);
+    match ChapterFormat::from_file_name(source_file_name).unwrap_or(ChapterFormat::LlmText) {
+        ChapterFormat::LlmText => llm_parser::parse_llm_text_to_chapter(source_file_name, contents),
+        ChapterFormat::Json => parse_json_chapters(source_file_name, contents).map(|chapters| join_chapters(chapters).0),
+        ChapterFormat::PlainText => plain_text::parse_plain_text_to_chapter(source_file_name, contents),
+        ChapterFormat::Yaml => Err(WeaveLangError::parse("YAML chapter input is not available in this build (no YAML parser is linked); convert the file to JSON.")),
+    }
+}
+
+/// parse_chapter, keeping a book's chapters apart: .llm.txt files are split at their
+/// CHAPTER:: headers (see llm_parser::parse_llm_text_to_chapters) and a JSON array gives
+/// one chapter per element. Other formats are always a single chapter.
+pub fn parse_chapters(source_file_name: &str, contents: &str) -> Result<Vec<ProcessedChapter>, WeaveLangError> {
+    let contents = &*to_nfc(contents);
+    match ChapterFormat::from_file_name(source_file_name).unwrap_or(ChapterFormat::LlmText) {
+        ChapterFormat::LlmText => llm_parser::parse_llm_text_to_chapters(source_file_name, contents),
+        ChapterFormat::Json => parse_json_chapters(source_file_name, contents),
+        _ => parse_chapter(source_file_name, contents).map(|chapter| vec![chapter]),
+    }
+}
+
+fn parse_json_chapters(source_file_name: &str, contents: &str) -> Result<Vec<ProcessedChapter>, WeaveLangError> {
+    let mut chapters: Vec<ProcessedChapter> = if contents.trim_start().starts_with('[') {
+        serde_json::from_str(contents).map_err(|e| WeaveLangError::parse(format!("Invalid JSON chapter list: {}", e)).with_source(e))?
+    } else {
+        vec![serde_json::from_str(contents).map_err(|e| WeaveLangError::parse(format!("Invalid JSON chapter: {}", e)).with_source(e))?]
+    };
+    for chapter in chapters.iter_mut().filter(|chapter| chapter.source_file_name.is_empty()) {
+        chapter.source_file_name = source_file_name.to_string();
+    }
+    Ok(chapters)
+}
+
+/// Strict validation in the file's format. .llm.txt files get llm_parser's line-level
+/// diagnostics; other formats only chapter-level checks, with line number 0.
+pub fn validate_chapter_file(source_file_name: &str, contents: &str) -> (Result<ProcessedChapter, WeaveLangError>, Vec<ParseDiagnostic>) {
+    let contents = &*to_nfc(contents);
+    if ChapterFormat::from_file_name(source_file_name).is_none_or(|format| format == ChapterFormat::LlmText) {
+        return llm_parser::validate_llm_text(source_file_name, contents);
+    }
+    match parse_chapter(source_file_name, contents) {
+        Ok(chapter) => {
+            let diagnostics = validation::validate_chapter(&chapter).iter()
+                .map(|issue| llm_parser::chapter_issue_diagnostic(issue, 0))
+                .collect();
+            (Ok(chapter), diagnostics)
+        }
+        Err(e) => (Err(e), Vec::new()),
+    }
+}
+
+//*** END FILE: src/parsing/chapter_loader.rs ***//
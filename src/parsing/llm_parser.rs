@@ -1,14 +1,281 @@
 //*** START FILE: src/parsing/llm_parser.rs ***//
+use crate::error::WeaveLangError;
 use crate::types::llm_data::*; // Use the structs from the new types module
+use crate::types::lemma_key::MWE_JOINER;
+use super::validation;
 use regex::Regex;
+use serde::Serialize;
+use std::fmt;
+use std::sync::OnceLock;
+use tracing::warn;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiagnosticSeverity {
+    Warning, // Content was skipped or looks suspicious, but the sentence is usable
+    Error,   // The sentence is missing data the simulation relies on
+}
+
+/// A problem found while parsing a stage file, located by line.
+#[derive(Serialize, Debug, Clone)]
+pub struct ParseDiagnostic {
+    pub sentence_index: usize, // 0-based index of the sentence in the parsed chapter
+    pub sentence_id: String,
+    pub line_number: usize,    // 1-based line in the stage file
+    pub severity: DiagnosticSeverity,
+    pub marker: String,        // Section the problem is in, e.g. "DIGLOT_MAP"
+    pub message: String,
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {:?} [{}] {}: {}", self.line_number, self.severity, self.marker, self.sentence_id, self.message)
+    }
+}
 
 // This enum stays local to the parser's logic
 #[derive(Debug, PartialEq, Clone, Copy)]
-enum ParsingSection { None, AdvS, SimS, SimE, SimSSegments, PhraseAlign, SimSL, AdvSL, DiglotMap, LockedPhrase }
+enum ParsingSection { None, AdvS, SimS, SimE, SimSSegments, PhraseAlign, SimSL, AdvSL, DiglotMap, LockedPhrase, Grammar }
+
+impl ParsingSection {
+    fn marker(&self) -> &'static str {
+        match self {
+            ParsingSection::None => "(none)",
+            ParsingSection::AdvS => "AdvS",
+            ParsingSection::SimS => "SimS",
+            ParsingSection::SimE => "SimE",
+            ParsingSection::SimSSegments => "SimS_Segments",
+            ParsingSection::PhraseAlign => "PHRASE_ALIGN",
+            ParsingSection::SimSL => "SimSL",
+            ParsingSection::AdvSL => "AdvSL",
+            ParsingSection::DiglotMap => "DIGLOT_MAP",
+            ParsingSection::LockedPhrase => "LOCKED_PHRASE",
+            ParsingSection::Grammar => "GRAM",
+        }
+    }
+}
+
+// Sections every sentence block is expected to have; checked in strict mode only.
+const REQUIRED_SECTIONS: [ParsingSection; 7] = [
+    ParsingSection::AdvS, ParsingSection::SimS, ParsingSection::SimE, ParsingSection::SimSSegments,
+    ParsingSection::SimSL, ParsingSection::AdvSL, ParsingSection::DiglotMap,
+];
+
+// Language-neutral spellings of the section markers, accepted alongside the original
+// Spanish/English-flavoured ones (AdvS, SimS, SimE, ...).
+const MARKER_ALIASES: [(&str, &str); 6] = [
+    ("AdvTarget::", "AdvS::"),
+    ("SimTarget::", "SimS::"),
+    ("SimBase::", "SimE::"),
+    ("SimTarget_Segments::", "SimS_Segments::"),
+    ("SimTargetL::", "SimSL::"),
+    ("AdvTargetL::", "AdvSL::"),
+];
+
+fn canonical_marker_line(line: &str) -> std::borrow::Cow<'_, str> {
+    for (alias, canonical) in MARKER_ALIASES {
+        if let Some(rest) = line.strip_prefix(alias) {
+            return std::borrow::Cow::Owned(format!("{}{}", canonical, rest));
+        }
+    }
+    std::borrow::Cow::Borrowed(line)
+}
+
+/// Grammar feature names of a GRAM:: list, separated by whitespace or commas, lowercased
+/// and without repeats ("Preterite, object_pronoun" -> ["preterite", "object_pronoun"]).
+pub fn split_grammar_features(list: &str) -> Vec<String> {
+    let mut features: Vec<String> = Vec::new();
+    for feature in list.split(|c: char| c.is_whitespace() || c == ',').filter(|f| !f.is_empty()) {
+        let feature = feature.to_lowercase();
+        if !features.contains(&feature) {
+            features.push(feature);
+        }
+    }
+    features
+}
+
+/// Splits a SimSL/AdvSL lemma list on whitespace, keeping bracketed multi-word expressions
+/// together as one underscore-joined lemma: "[por favor] gracias" -> ["por_favor", "gracias"].
+/// A POS tag after the bracket is kept ("[a pesar de]#ADP" -> "a_pesar_de#ADP"). A list
+/// that is one bracket group ("[por favor]") is one expression.
+/// The second value is true if a bracket was left unclosed.
+fn split_lemma_list(list: &str) -> (Vec<String>, bool) {
+    let mut lemmas = Vec::new();
+    let mut open_group: Option<Vec<&str>> = None;
+    for word in list.split_whitespace() {
+        match open_group.as_mut() {
+            None if word.starts_with('[') => open_group = Some(vec![word.trim_start_matches('[')]),
+            None => { lemmas.push(word.to_string()); continue; }
+            Some(group) => group.push(word),
+        }
+        let closes = open_group.as_ref().and_then(|g| g.last()).is_some_and(|w| w.contains(']'));
+        if closes {
+            let group = open_group.take().unwrap_or_default();
+            let joined = group.iter().filter(|w| !w.is_empty()).copied().collect::<Vec<_>>().join(&MWE_JOINER.to_string());
+            lemmas.push(joined.replacen(']', "", 1));
+        }
+    }
+    let unclosed = open_group.is_some();
+    if let Some(group) = open_group {
+        lemmas.push(group.iter().filter(|w| !w.is_empty()).copied().collect::<Vec<_>>().join(&MWE_JOINER.to_string()));
+    }
+    (lemmas.into_iter().filter(|l| !l.is_empty()).collect(), unclosed)
+}
+
+/// Parses a stage file as one chapter. CHAPTER:: headers only become section headings;
+/// parse_llm_text_to_chapters splits the file at them. A PARA:: line at the top of a
+/// sentence block marks the start of a paragraph (ProcessedSentence::paragraph_start).
+pub fn parse_llm_text_to_chapter(source_file_name: &str, llm_content: &str) -> Result<ProcessedChapter, WeaveLangError> {
+    parse_reporting_diagnostics(source_file_name, llm_content).map(|parsed| parsed.chapter)
+}
+
+/// Parses a stage file holding a whole book, one chapter per CHAPTER:: header:
+///
+///   BOOK:: The Little Prince
+///   CHAPTER:: One
+///   AdvS:: ...
+///   END_SENTENCE
+///
+/// Headers go at the top of a sentence block, before its first section marker. Sentences
+/// before the first CHAPTER:: form an untitled chapter; a file without headers is a single
+/// chapter. Sentence IDs are numbered across the whole file, so they stay unique.
+pub fn parse_llm_text_to_chapters(source_file_name: &str, llm_content: &str) -> Result<Vec<ProcessedChapter>, WeaveLangError> {
+    parse_reporting_diagnostics(source_file_name, llm_content).map(ParsedFile::into_chapters)
+}
+
+fn parse_reporting_diagnostics(source_file_name: &str, llm_content: &str) -> Result<ParsedFile, WeaveLangError> {
+    let (parse_result, diagnostics) = parse_with_diagnostics(source_file_name, llm_content, false);
+    for diagnostic in &diagnostics {
+        warn!("{} (line {}, block for ID {})", diagnostic.message, diagnostic.line_number, diagnostic.sentence_id);
+    }
+    parse_result
+}
+
+/// Entry point for fuzzing and other untrusted input: arbitrary bytes in, a Result out.
+/// Invalid UTF-8 is an Err rather than a panic, and diagnostics are dropped instead of
+/// logged. Parses strictly so chapter-level validation runs too; no input may panic.
+pub fn parse_llm_bytes(source_file_name: &str, bytes: &[u8]) -> Result<ProcessedChapter, WeaveLangError> {
+    let llm_content = std::str::from_utf8(bytes)
+        .map_err(|e| WeaveLangError::parse(format!("Stage file is not valid UTF-8: {}", e)).with_source(e))?;
+    parse_with_diagnostics(source_file_name, llm_content, true).0.map(|parsed| parsed.chapter)
+}
+
+/// Strict validation: parses the file and returns every diagnostic instead of
+/// printing it, including checks the lenient parser skips (missing sections,
+/// SimSL/DIGLOT_MAP lines for undeclared segments, stray lines in SimSL/DIGLOT_MAP).
+/// Chapter-level checks from `validation` are included as warnings.
+pub fn validate_llm_text(source_file_name: &str, llm_content: &str) -> (Result<ProcessedChapter, WeaveLangError>, Vec<ParseDiagnostic>) {
+    let (parse_result, diagnostics) = parse_with_diagnostics(source_file_name, llm_content, true);
+    (parse_result.map(|parsed| parsed.chapter), diagnostics)
+}
+
+/// A chapter-level validation issue reported as a warning diagnostic.
+pub fn chapter_issue_diagnostic(issue: &validation::ValidationIssue, line_number: usize) -> ParseDiagnostic {
+    ParseDiagnostic {
+        sentence_index: issue.sentence_index,
+        sentence_id: issue.sentence_id.clone(),
+        line_number,
+        severity: DiagnosticSeverity::Warning,
+        marker: "(chapter)".to_string(),
+        message: format!("{:?}: {}", issue.kind, issue.message),
+    }
+}
+
+// "S1(El gato)" in SimS_Segments.
+fn segment_regex() -> &'static Regex {
+    static SEGMENT_RE: OnceLock<Regex> = OnceLock::new();
+    SEGMENT_RE.get_or_init(|| Regex::new(r"^(S\d+)\((.*?)\)$").expect("segment pattern is valid"))
+}
+
+// "cat->gato(gato)(Y)" or "cat->gato(gato)(Y:0.8)" in DIGLOT_MAP. The form may also be in
+// square brackets, "cat->gato [gato] (Y)", as older content was written; such entries are
+// read the same and reported so the file can be normalized.
+fn diglot_entry_regex() -> &'static Regex {
+    static DIGLOT_ENTRY_RE: OnceLock<Regex> = OnceLock::new();
+    DIGLOT_ENTRY_RE.get_or_init(|| {
+        Regex::new(r"^(.*?)->(.*?)\s*(?:\((.*?)\)|\[(.*?)\])\s*\((?:([YNyn])(?:\s*:\s*([0-9]*\.?[0-9]+))?|([0-9]*\.?[0-9]+))\)$").expect("diglot entry pattern is valid")
+    })
+}
 
-pub fn parse_llm_text_to_chapter(source_file_name: &str, llm_content: &str) -> Result<ProcessedChapter, String> {
-    let mut chapter = ProcessedChapter { source_file_name: source_file_name.to_string(), sentences: Vec::new() };
-    let base_sentence_id = source_file_name.replace(".llm.txt", "");
+// A BOOK::, CHAPTER:: or PARA:: header line at the top of a sentence block.
+enum BlockHeader<'a> {
+    Book(&'a str),
+    Chapter(&'a str),
+    Paragraph,
+}
+
+// Takes the header on the first line of `block`, returning it and the rest of the block.
+fn take_block_header(block: &str) -> Option<(BlockHeader<'_>, &str)> {
+    let (line, rest) = block.split_once('\n').unwrap_or((block, ""));
+    let line = line.trim();
+    let header = if let Some(title) = line.strip_prefix("BOOK::") {
+        BlockHeader::Book(title.trim())
+    } else if line.strip_prefix("PARA::").is_some_and(|rest| rest.trim().is_empty()) {
+        BlockHeader::Paragraph
+    } else {
+        BlockHeader::Chapter(line.strip_prefix("CHAPTER::")?.trim())
+    };
+    Some((header, rest.trim_start()))
+}
+
+// Adds a chapter or section title to the heading waiting for the next sentence.
+fn push_pending_heading(pending_heading: &mut Option<String>, title: &str) {
+    if title.is_empty() {
+        return;
+    }
+    *pending_heading = Some(match pending_heading.take() {
+        Some(heading) => format!("{} / {}", heading, title),
+        None => title.to_string(),
+    });
+}
+
+// A whole stage file parsed as one chapter, with the sentence index and title of every
+// CHAPTER:: header in it.
+struct ParsedFile {
+    chapter: ProcessedChapter,
+    chapter_starts: Vec<(usize, Option<String>)>,
+}
+
+impl ParsedFile {
+    fn into_chapters(self) -> Vec<ProcessedChapter> {
+        let ParsedFile { mut chapter, mut chapter_starts } = self;
+        if chapter_starts.is_empty() {
+            return vec![chapter];
+        }
+        if chapter_starts[0].0 > 0 {
+            chapter_starts.insert(0, (0, None));
+        }
+        // Split from the back, so every split_off leaves the earlier chapters in place.
+        let mut chapters = Vec::with_capacity(chapter_starts.len());
+        for (start, title) in chapter_starts.into_iter().rev() {
+            let sentences = chapter.sentences.split_off(start);
+            if !sentences.is_empty() {
+                chapters.push(ProcessedChapter { sentences, chapter_title: title, ..chapter.clone() });
+            }
+        }
+        chapters.reverse();
+        if chapters.is_empty() {
+            chapters.push(chapter);
+        }
+        chapters
+    }
+}
+
+/// ID of the sentence parsed from a file's `block_index`-th (0-based) END_SENTENCE block,
+/// counting marker and comment blocks.
+pub fn sentence_id_for_block(source_file_name: &str, block_index: usize) -> String {
+    format!("{}_{}", source_file_name.replace(".llm.txt", ""), block_index + 1)
+}
+
+fn parse_with_diagnostics(
+    source_file_name: &str,
+    llm_content: &str,
+    strict: bool,
+) -> (Result<ParsedFile, WeaveLangError>, Vec<ParseDiagnostic>) {
+    let mut diagnostics: Vec<ParseDiagnostic> = Vec::new();
+    let mut sentence_first_lines: Vec<usize> = Vec::new();
+    let mut chapter = ProcessedChapter { source_file_name: source_file_name.to_string(), sentences: Vec::new(), ..Default::default() };
+    let segment_re = segment_regex();
+    let entry_re = diglot_entry_regex();
     
     let sentence_blocks: Vec<&str> = llm_content
         .split("END_SENTENCE")
@@ -17,20 +284,76 @@ pub fn parse_llm_text_to_chapter(source_file_name: &str, llm_content: &str) -> R
         .collect();
 
     if sentence_blocks.is_empty() && !llm_content.trim().is_empty() { 
-        return Err("No processable blocks found (missing END_SENTENCE markers or empty content between them).".to_string());
+        return (Err(WeaveLangError::parse("No processable blocks found (missing END_SENTENCE markers or empty content between them).")), diagnostics);
     }
 
+    // Chapter markers attach to the next sentence; markers after the last sentence are dropped.
+    let mut pending_heading: Option<String> = None;
+    let mut pending_paragraph = false;
+    let mut chapter_starts: Vec<(usize, Option<String>)> = Vec::new();
+    let mut lines_before_offset: (usize, usize) = (0, 0); // (byte offset, newlines before it)
     for (index, block_str) in sentence_blocks.iter().enumerate() {
-        if block_str.starts_with("CHAPTER_MARKER_DIRECT::") || block_str.starts_with("//") {
+        let mut block_str = *block_str;
+        while let Some((header, rest)) = take_block_header(block_str) {
+            match header {
+                BlockHeader::Book(title) => chapter.book_title = (!title.is_empty()).then(|| title.to_string()),
+                BlockHeader::Paragraph => pending_paragraph = true,
+                BlockHeader::Chapter(title) => {
+                    pending_paragraph = true;
+                    // The chapter title heads its first sentence; markers left over from the
+                    // previous chapter are dropped, like those after a file's last sentence.
+                    pending_heading = None;
+                    push_pending_heading(&mut pending_heading, title);
+                    // A header right after another one replaces it instead of starting an empty chapter.
+                    if chapter_starts.last().is_some_and(|(start, _)| *start == chapter.sentences.len()) {
+                        chapter_starts.pop();
+                    }
+                    chapter_starts.push((chapter.sentences.len(), (!title.is_empty()).then(|| title.to_string())));
+                }
+            }
+            block_str = rest;
+        }
+        if block_str.is_empty() {
+            continue;
+        }
+        if let Some(marker) = block_str.strip_prefix("CHAPTER_MARKER_DIRECT::") {
+            push_pending_heading(&mut pending_heading, marker.lines().next().unwrap_or_default().trim());
             continue;
         }
+        if block_str.starts_with("//") {
+            continue;
+        }
+        // Blocks are trimmed subslices of llm_content, so their offset gives the starting line.
+        // Newlines are counted from the previous block on; recounting from the start of the
+        // file made parsing quadratic in its length.
+        let block_offset = block_str.as_ptr() as usize - llm_content.as_ptr() as usize;
+        lines_before_offset.1 += llm_content[lines_before_offset.0..block_offset].matches('\n').count();
+        lines_before_offset.0 = block_offset;
+        let block_first_line = lines_before_offset.1 + 1;
+        let sentence_index = chapter.sentences.len();
 
-        let mut sentence = ProcessedSentence { sentence_id: format!("{}_{}", base_sentence_id, index + 1), ..Default::default() };
+        let mut sentence = ProcessedSentence {
+            sentence_id: sentence_id_for_block(source_file_name, index),
+            section_heading: pending_heading.take(),
+            paragraph_start: std::mem::take(&mut pending_paragraph),
+            ..Default::default()
+        };
+        let sentence_id = sentence.sentence_id.clone();
         let mut current_section = ParsingSection::None;
+        let mut seen_sections: Vec<ParsingSection> = Vec::new();
         
-        for line in block_str.lines() {
-            let line_trimmed = line.trim();
+        for (line_offset, line) in block_str.lines().enumerate() {
+            let line_canonical = canonical_marker_line(line.trim());
+            let line_trimmed: &str = &line_canonical;
             if line_trimmed.is_empty() { continue; }
+            let diag = |severity: DiagnosticSeverity, section: ParsingSection, message: String| ParseDiagnostic {
+                sentence_index,
+                sentence_id: sentence_id.clone(),
+                line_number: block_first_line + line_offset,
+                severity,
+                marker: section.marker().to_string(),
+                message,
+            };
 
             let mut is_marker_line = true; 
             match line_trimmed {
@@ -47,7 +370,11 @@ pub fn parse_llm_text_to_chapter(source_file_name: &str, llm_content: &str) -> R
                    } else {
                        content_without_marker
                    };
-                   sentence.adv_s_lemmas.extend(lemmas_str_cleaned.split_whitespace().map(String::from));
+                   let (lemmas, unclosed) = split_lemma_list(lemmas_str_cleaned);
+                   if unclosed {
+                       diagnostics.push(diag(DiagnosticSeverity::Warning, ParsingSection::AdvSL, format!("Unclosed '[' in AdvSL '{}'; the expression runs to the end of the line.", lemmas_str_cleaned)));
+                   }
+                   sentence.adv_s_lemmas.extend(lemmas);
                 }
                 s if s.starts_with("DIGLOT_MAP::") => { current_section = ParsingSection::DiglotMap; }
                 s if s.starts_with("LOCKED_PHRASE::") => { current_section = ParsingSection::LockedPhrase; 
@@ -57,14 +384,28 @@ pub fn parse_llm_text_to_chapter(source_file_name: &str, llm_content: &str) -> R
                     } else {
                         content_without_marker
                     };
+                    // The prompt template shows the IDs in brackets: "LOCKED_PHRASE :: [S1 S2]".
+                    let ids_str_cleaned = ids_str_cleaned.trim_start_matches('[').trim_end_matches(']').trim();
                     if !ids_str_cleaned.is_empty() {
                         sentence.locked_phrases = Some(ids_str_cleaned.split_whitespace().map(String::from).collect());
                     }
                 }
+                // "GRAM:: preterite" lists features of both AdvS and SimS; a bare GRAM:: is
+                // followed by "AdvS: ..." and "SimS: ..." lines when they differ.
+                s if s.starts_with("GRAM::") => { current_section = ParsingSection::Grammar;
+                    let content_without_marker = s.trim_start_matches("GRAM::").trim();
+                    let features_str_cleaned = content_without_marker.split(" //").next().unwrap_or_default();
+                    let features = split_grammar_features(features_str_cleaned);
+                    if !features.is_empty() {
+                        sentence.adv_s_grammar = features.clone();
+                        sentence.sim_s_grammar = features;
+                    }
+                }
                 _ => { is_marker_line = false; } 
             }
 
             if is_marker_line { 
+                seen_sections.push(current_section);
                 continue;
             }
 
@@ -73,14 +414,13 @@ pub fn parse_llm_text_to_chapter(source_file_name: &str, llm_content: &str) -> R
                 ParsingSection::SimS => sentence.sim_s.push_str(&format!(" {}", line_trimmed)),
                 ParsingSection::SimE => sentence.sim_e.push_str(&format!(" {}", line_trimmed)),
                 ParsingSection::SimSSegments => {
-                    let re = Regex::new(r"^(S\d+)\((.*?)\)$").unwrap();
-                    if let Some(caps) = re.captures(line_trimmed) {
+                    if let Some(caps) = segment_re.captures(line_trimmed) {
                         sentence.sim_s_segments.push(SegmentData {
                             id: caps.get(1).map_or_else(String::new, |m| m.as_str().to_string()),
                             text: caps.get(2).map_or_else(String::new, |m| m.as_str().trim().to_string()),
                         });
                     } else if !line_trimmed.is_empty() {
-                        eprintln!("Warning: Malformed SimS_Segments line: '{}' in block for ID {}", line_trimmed, sentence.sentence_id);
+                        diagnostics.push(diag(DiagnosticSeverity::Error, current_section, format!("Malformed SimS_Segments line: '{}'", line_trimmed)));
                     }
                 }
                 ParsingSection::PhraseAlign => {
@@ -92,7 +432,7 @@ pub fn parse_llm_text_to_chapter(source_file_name: &str, llm_content: &str) -> R
                             sim_e_span: parts[2].to_string(),
                         });
                     } else if !line_trimmed.is_empty() {
-                         eprintln!("Warning: Malformed PHRASE_ALIGN line: '{}' in block for ID {}", line_trimmed, sentence.sentence_id);
+                         diagnostics.push(diag(DiagnosticSeverity::Error, current_section, format!("Malformed PHRASE_ALIGN line: '{}'", line_trimmed)));
                     }
                 }
                 ParsingSection::SimSL => {
@@ -105,17 +445,21 @@ pub fn parse_llm_text_to_chapter(source_file_name: &str, llm_content: &str) -> R
                         } else {
                             lemmas_str_raw
                         };
+                        let (lemmas, unclosed) = split_lemma_list(lemmas_str_cleaned);
+                        if unclosed {
+                            diagnostics.push(diag(DiagnosticSeverity::Warning, current_section, format!("Unclosed '[' in SimSL line '{}'; the expression runs to the end of the line.", line_trimmed)));
+                        }
                         sentence.sim_s_lemmas.push(SegmentLemmas {
                             segment_id: segment_id_str.to_string(),
-                            lemmas: lemmas_str_cleaned.split_whitespace().map(String::from).collect(),
+                            lemmas,
                         });
-                    } else if !line_trimmed.is_empty() && line_trimmed.starts_with('S') {
-                         eprintln!("Warning: Malformed SimSL line: '{}' in block for ID {}", line_trimmed, sentence.sentence_id);
+                    } else if strict || line_trimmed.starts_with('S') {
+                        diagnostics.push(diag(DiagnosticSeverity::Error, current_section, format!("Malformed SimSL line: '{}'", line_trimmed)));
                     }
                 }
                 ParsingSection::AdvSL => {
                     if !line_trimmed.is_empty() {
-                        eprintln!("Warning: Unexpected content line '{}' under AdvSL section for ID {}. AdvSL should be single line.", line_trimmed, sentence.sentence_id);
+                        diagnostics.push(diag(DiagnosticSeverity::Warning, current_section, format!("Unexpected content line '{}'; AdvSL should be a single line.", line_trimmed)));
                     }
                 }
                 ParsingSection::DiglotMap => {
@@ -130,48 +474,116 @@ pub fn parse_llm_text_to_chapter(source_file_name: &str, llm_content: &str) -> R
                         };
 
                         let mut current_segment_map = DiglotSegmentMap { segment_id: segment_id_str.to_string(), entries: Vec::new() };
-                        let entry_re = Regex::new(r"^(.*?)->(.*?)\((.*?)\)\s*\(([YNyn])\)$").unwrap();
+                        let mut bracket_form_entries: Vec<&str> = Vec::new();
 
                         for entry_part_str in entries_str_cleaned.split('|').map(|e| e.trim()) {
                             if entry_part_str.is_empty() { continue; }
                             if let Some(caps) = entry_re.captures(entry_part_str) {
                                 let eng_word = caps.get(1).map_or("", |m| m.as_str().trim()).to_string();
                                 let spa_lemma = caps.get(2).map_or("", |m| m.as_str().trim()).to_string();
-                                let exact_spa_form = caps.get(3).map_or("", |m| m.as_str().trim()).to_string();
-                                let viability_char_str = caps.get(4).map_or("N", |m| m.as_str());
+                                let bracket_form = caps.get(4);
+                                if bracket_form.is_some() {
+                                    bracket_form_entries.push(entry_part_str);
+                                }
+                                let exact_spa_form = caps.get(3).or(bracket_form).map_or("", |m| m.as_str().trim()).to_string();
+                                // (Y) is 1.0 and (Y:0.8) or a bare (0.8) the score; (N) is 0.0 even with a score.
+                                let score = caps.get(6).or(caps.get(7))
+                                    .and_then(|m| m.as_str().parse::<f32>().ok())
+                                    .map(|c| c.clamp(0.0, 1.0));
+                                let confidence = match caps.get(5).map(|m| m.as_str()) {
+                                    Some(flag) if flag.eq_ignore_ascii_case("N") => {
+                                        if score.is_some_and(|c| c > 0.0) {
+                                            diagnostics.push(diag(DiagnosticSeverity::Warning, current_section, format!("Diglot entry '{}' for segment {} is marked N; its score is ignored and the entry is not viable.", entry_part_str, segment_id_str)));
+                                        }
+                                        0.0
+                                    }
+                                    _ => score.unwrap_or(1.0),
+                                };
                                 
                                 if eng_word.is_empty() && spa_lemma.is_empty() && exact_spa_form.is_empty() {
-                                     eprintln!("Warning: Parsed completely empty diglot entry (Eng, Spa, Form all empty) for segment {} from part '{}'. Skipping.", segment_id_str, entry_part_str);
+                                     diagnostics.push(diag(DiagnosticSeverity::Warning, current_section, format!("Empty diglot entry (Eng, Spa, Form all empty) for segment {} from part '{}'. Skipping.", segment_id_str, entry_part_str)));
                                      continue;
                                 }
                                 current_segment_map.entries.push(DiglotEntry {
                                     eng_word, spa_lemma, exact_spa_form,
-                                    viable: viability_char_str.eq_ignore_ascii_case("Y"),
+                                    confidence,
                                 });
                             } else {
-                                eprintln!("Warning: Could not parse diglot entry part: '{}' for segment {} in block ID {}", entry_part_str, segment_id_str, sentence.sentence_id);
+                                diagnostics.push(diag(DiagnosticSeverity::Error, current_section, format!("Could not parse diglot entry part: '{}' for segment {}", entry_part_str, segment_id_str)));
                             }
                         }
+                        if !bracket_form_entries.is_empty() {
+                            diagnostics.push(diag(DiagnosticSeverity::Warning, current_section, format!(
+                                "DIGLOT_MAP entries for segment {} write the form in [brackets] ({}); read as eng->spa(form)(Y/N), the canonical syntax.",
+                                segment_id_str, bracket_form_entries.join(" | ")
+                            )));
+                        }
                         sentence.diglot_map.push(current_segment_map);
-                    } else if !line_trimmed.is_empty() && line_trimmed.starts_with('S') {
-                         eprintln!("Warning: Malformed DIGLOT_MAP S-ID line: '{}' in block for ID {}", line_trimmed, sentence.sentence_id);
+                    } else if strict || line_trimmed.starts_with('S') {
+                        diagnostics.push(diag(DiagnosticSeverity::Error, current_section, format!("Malformed DIGLOT_MAP S-ID line: '{}'", line_trimmed)));
                     }
                 }
                 ParsingSection::LockedPhrase => {
                     if !line_trimmed.is_empty() {
-                         eprintln!("Warning: Unexpected content line '{}' under LockedPhrase section for ID {}. LockedPhrase should be single line.", line_trimmed, sentence.sentence_id);
+                         diagnostics.push(diag(DiagnosticSeverity::Warning, current_section, format!("Unexpected content line '{}'; LOCKED_PHRASE should be a single line.", line_trimmed)));
+                    }
+                }
+                ParsingSection::Grammar => {
+                    let features_str_cleaned = line_trimmed.split(" //").next().unwrap_or_default();
+                    match features_str_cleaned.split_once(':') {
+                        Some((text, features)) if text.trim() == "AdvS" => sentence.adv_s_grammar = split_grammar_features(features),
+                        Some((text, features)) if text.trim() == "SimS" => sentence.sim_s_grammar = split_grammar_features(features),
+                        _ => diagnostics.push(diag(DiagnosticSeverity::Error, current_section, format!("Malformed GRAM line: '{}' (expected 'AdvS: ...' or 'SimS: ...')", line_trimmed))),
                     }
                 }
                 ParsingSection::None => {
-                     eprintln!("Warning: Content found ('{}') before any section marker in block for ID {}", line_trimmed, sentence.sentence_id);
+                     diagnostics.push(diag(DiagnosticSeverity::Warning, current_section, format!("Content found ('{}') before any section marker", line_trimmed)));
                 }
             }
         }
+        let block_diag = |severity: DiagnosticSeverity, marker: &str, message: String| ParseDiagnostic {
+            sentence_index,
+            sentence_id: sentence_id.clone(),
+            line_number: block_first_line,
+            severity,
+            marker: marker.to_string(),
+            message,
+        };
         if sentence.adv_s.is_empty() && sentence.sim_s.is_empty() && sentence.sim_e.is_empty() && sentence.sim_s_segments.is_empty() {
-            eprintln!("Warning: Sentence ID {} appears to be mostly empty or malformed after parsing. Key fields are empty.", sentence.sentence_id);
+            diagnostics.push(block_diag(DiagnosticSeverity::Error, "(block)", "Sentence appears to be mostly empty or malformed after parsing. Key fields are empty.".to_string()));
+        }
+        if strict {
+            for required in REQUIRED_SECTIONS {
+                if !seen_sections.contains(&required) {
+                    diagnostics.push(block_diag(DiagnosticSeverity::Error, required.marker(), format!("Missing {}:: section.", required.marker())));
+                }
+            }
+            let declared: Vec<&str> = sentence.sim_s_segments.iter().map(|seg| seg.id.as_str()).collect();
+            let references = sentence.sim_s_lemmas.iter().map(|sl| ("SimSL", sl.segment_id.as_str()))
+                .chain(sentence.phrase_alignments.iter().map(|pa| ("PHRASE_ALIGN", pa.segment_id.as_str())))
+                .chain(sentence.diglot_map.iter().map(|dm| ("DIGLOT_MAP", dm.segment_id.as_str())));
+            for (marker, segment_id) in references {
+                if !declared.contains(&segment_id) {
+                    diagnostics.push(block_diag(DiagnosticSeverity::Warning, marker, format!("References segment {} which is not declared in SimS_Segments.", segment_id)));
+                }
+            }
+            for segment_id in &declared {
+                if !sentence.sim_s_lemmas.iter().any(|sl| sl.segment_id == *segment_id) {
+                    diagnostics.push(block_diag(DiagnosticSeverity::Error, "SimSL", format!("No SimSL line for segment {}.", segment_id)));
+                }
+            }
         }
         chapter.sentences.push(sentence);
+        sentence_first_lines.push(block_first_line);
+    }
+
+    if strict {
+        for issue in validation::validate_chapter(&chapter) {
+            let line_number = sentence_first_lines.get(issue.sentence_index).copied().unwrap_or(0);
+            diagnostics.push(chapter_issue_diagnostic(&issue, line_number));
+        }
+        diagnostics.sort_by_key(|d| d.line_number);
     }
-    Ok(chapter)
+    (Ok(ParsedFile { chapter, chapter_starts }), diagnostics)
 }
 //*** END FILE: src/parsing/llm_parser.rs ***//
\ No newline at end of file
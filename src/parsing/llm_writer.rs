@@ -0,0 +1,67 @@
+//*** START FILE: src/parsing/llm_writer.rs ***//
+// Serializes parsed sentences back to the .llm.txt stage format, in the canonical
+// section order and DIGLOT_MAP syntax. Inline " //" comments are not preserved.
+use crate::types::llm_data::{DiglotEntry, ProcessedChapter, ProcessedSentence};
+
+/// Canonical viability flag: (Y)/(N) for 1.0/0.0, otherwise the score is kept, e.g. (Y:0.8).
+/// A score is always written with Y: the parser reads any N as 0.0.
+pub fn format_viability(confidence: f32) -> String {
+    if confidence >= 1.0 {
+        "(Y)".to_string()
+    } else if confidence <= 0.0 {
+        "(N)".to_string()
+    } else {
+        let score = format!("{:.2}", confidence);
+        format!("(Y:{})", score.trim_end_matches('0').trim_end_matches('.'))
+    }
+}
+
+pub fn format_diglot_entry(entry: &DiglotEntry) -> String {
+    format!("{}->{}({}){}", entry.eng_word, entry.spa_lemma, entry.exact_spa_form, format_viability(entry.confidence))
+}
+
+/// One sentence block, including the trailing END_SENTENCE line.
+pub fn write_sentence_block(sentence: &ProcessedSentence) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    lines.push(format!("AdvS:: {}", sentence.adv_s));
+    lines.push(format!("SimS:: {}", sentence.sim_s));
+    lines.push(format!("SimE:: {}", sentence.sim_e));
+    lines.push("SimS_Segments::".to_string());
+    lines.extend(sentence.sim_s_segments.iter().map(|seg| format!("{}({})", seg.id, seg.text)));
+    lines.push("PHRASE_ALIGN::".to_string());
+    lines.extend(sentence.phrase_alignments.iter().map(|pa| format!("{} ~ {} ~ {}", pa.segment_id, pa.adv_s_span, pa.sim_e_span)));
+    lines.push("SimSL::".to_string());
+    lines.extend(sentence.sim_s_lemmas.iter().map(|sl| format!("{}:: {}", sl.segment_id, sl.lemmas.join(" "))));
+    lines.push(format!("AdvSL:: {}", sentence.adv_s_lemmas.join(" ")));
+    lines.push("DIGLOT_MAP::".to_string());
+    for segment_map in &sentence.diglot_map {
+        let entries: Vec<String> = segment_map.entries.iter().map(format_diglot_entry).collect();
+        lines.push(format!("{}:: {}", segment_map.segment_id, entries.join(" | ")));
+    }
+    if let Some(locked) = &sentence.locked_phrases {
+        lines.push(format!("LOCKED_PHRASE:: {}", locked.join(" ")));
+    }
+    if sentence.adv_s_grammar == sentence.sim_s_grammar {
+        if !sentence.adv_s_grammar.is_empty() {
+            lines.push(format!("GRAM:: {}", sentence.adv_s_grammar.join(" ")));
+        }
+    } else {
+        lines.push("GRAM::".to_string());
+        lines.push(format!("AdvS: {}", sentence.adv_s_grammar.join(" ")));
+        lines.push(format!("SimS: {}", sentence.sim_s_grammar.join(" ")));
+    }
+    lines.push("END_SENTENCE".to_string());
+    lines.iter().map(|l| l.trim_end()).collect::<Vec<_>>().join("\n")
+}
+
+pub fn write_chapter_to_llm_text(chapter: &ProcessedChapter) -> String {
+    let blocks: Vec<String> = chapter.sentences.iter().map(|sentence| {
+        let paragraph = if sentence.paragraph_start { "PARA::\n" } else { "" };
+        match &sentence.section_heading {
+            Some(heading) => format!("CHAPTER_MARKER_DIRECT:: {}\nEND_SENTENCE\n\n{}{}", heading, paragraph, write_sentence_block(sentence)),
+            None => format!("{}{}", paragraph, write_sentence_block(sentence)),
+        }
+    }).collect();
+    format!("{}\n", blocks.join("\n\n"))
+}
+//*** END FILE: src/parsing/llm_writer.rs ***//
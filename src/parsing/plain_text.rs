@@ -0,0 +1,126 @@
+//*** START FILE: src/parsing/plain_text.rs ***//
+// Plain base-language books (.txt without markers). The text is split into sentences and
+// each becomes a SimE-only ProcessedSentence: AdvS, SimS and their lemmas stay empty, so
+// only L4 (words from the bilingual lexicon, see bilingual_lexicon) or L5 can render it.
+//
+// Paragraphs are separated by blank lines; line breaks inside a paragraph are spaces. The
+// first sentence of each paragraph is marked as a paragraph start.
+// A one-line paragraph starting with "Chapter", "Part", "Book", "Prologue" or "Epilogue"
+// and not ending in sentence punctuation is taken as a heading for the next sentence.
+
+use crate::error::WeaveLangError;
+use crate::types::llm_data::{ProcessedChapter, ProcessedSentence};
+
+const HEADING_WORDS: [&str; 5] = ["chapter", "part", "book", "prologue", "epilogue"];
+const MAX_HEADING_CHARS: usize = 80;
+// Words ending in a period that do not end a sentence (compared lowercase, without the period).
+const ABBREVIATIONS: [&str; 13] = ["mr", "mrs", "ms", "dr", "st", "jr", "sr", "prof", "vs", "etc", "e.g", "i.e", "mt"];
+
+fn is_sentence_end(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '…')
+}
+
+// Closing quotes and brackets that belong to the sentence before them.
+fn is_closing(c: char) -> bool {
+    matches!(c, '"' | '\'' | ')' | ']' | '”' | '’' | '»')
+}
+
+fn is_heading(paragraph: &str) -> bool {
+    let first_word = paragraph.split_whitespace().next().unwrap_or_default().to_lowercase();
+    !paragraph.contains('\n')
+        && paragraph.chars().count() <= MAX_HEADING_CHARS
+        && !paragraph.ends_with(|c: char| is_sentence_end(c) || is_closing(c))
+        && HEADING_WORDS.iter().any(|word| first_word.trim_end_matches(|c: char| !c.is_alphanumeric()) == *word)
+}
+
+// Whether the period at the end of `text_so_far` belongs to an abbreviation or an initial ("J.").
+fn ends_with_abbreviation(text_so_far: &str) -> bool {
+    let Some(without_period) = text_so_far.strip_suffix('.') else { return false };
+    let last_word = without_period.rsplit(char::is_whitespace).next().unwrap_or_default()
+        .trim_start_matches(|c: char| !c.is_alphanumeric());
+    let mut chars = last_word.chars();
+    let single_capital = matches!((chars.next(), chars.next()), (Some(c), None) if c.is_uppercase());
+    single_capital || ABBREVIATIONS.contains(&last_word.to_lowercase().as_str())
+}
+
+/// Sentences of one paragraph, trimmed. A sentence ends at '.', '!', '?' or '…' (plus any
+/// closing quotes or brackets) followed by whitespace, unless the period ends an abbreviation.
+pub fn split_sentences(paragraph: &str) -> Vec<String> {
+    let text = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        current.push(c);
+        if !is_sentence_end(c) {
+            continue;
+        }
+        while let Some(&next) = chars.peek() {
+            if is_sentence_end(next) || is_closing(next) {
+                current.push(next);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        let at_boundary = chars.peek().is_none_or(|next| next.is_whitespace());
+        if at_boundary && !ends_with_abbreviation(&current) {
+            sentences.push(std::mem::take(&mut current).trim().to_string());
+        }
+    }
+    if !current.trim().is_empty() {
+        sentences.push(current.trim().to_string());
+    }
+    sentences
+}
+
+// Paragraphs (lines joined with '\n'), split at lines that are empty or only whitespace.
+fn paragraphs(contents: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join("\n"));
+                current.clear();
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join("\n"));
+    }
+    paragraphs
+}
+
+/// Parses a plain-text book into SimE-only sentences with IDs "<stem>_<n>".
+pub fn parse_plain_text_to_chapter(source_file_name: &str, contents: &str) -> Result<ProcessedChapter, WeaveLangError> {
+    let stem = source_file_name.strip_suffix(".txt").unwrap_or(source_file_name);
+    let mut chapter = ProcessedChapter { source_file_name: source_file_name.to_string(), ..Default::default() };
+    let mut pending_heading: Option<String> = None;
+    for paragraph in paragraphs(contents) {
+        let paragraph = paragraph.as_str();
+        if is_heading(paragraph) {
+            pending_heading = Some(match pending_heading.take() {
+                Some(heading) => format!("{} / {}", heading, paragraph),
+                None => paragraph.to_string(),
+            });
+            continue;
+        }
+        for (index, sim_e) in split_sentences(paragraph).into_iter().enumerate() {
+            chapter.sentences.push(ProcessedSentence {
+                sentence_id: format!("{}_{}", stem, chapter.sentences.len() + 1),
+                sim_e,
+                section_heading: pending_heading.take(),
+                paragraph_start: index == 0,
+                ..Default::default()
+            });
+        }
+    }
+    if chapter.sentences.is_empty() {
+        return Err(WeaveLangError::parse("No sentences found in the plain-text book."));
+    }
+    Ok(chapter)
+}
+//*** END FILE: src/parsing/plain_text.rs ***//
@@ -0,0 +1,278 @@
+//*** START FILE: src/parsing/validation.rs ***//
+use crate::tokenizer::{self, EnglishTokenizer, Token, Tokenizer};
+use crate::types::llm_data::{DiglotEntry, ProcessedChapter, ProcessedSentence};
+pub use crate::types::protected_span::{ProtectedSpan, ProtectedSpanKind};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+
+// Sentences shorter than this (in words) are ignored by the duplicate checks;
+// short lines like "Yes." legitimately repeat throughout a book.
+const MIN_WORDS_FOR_DUPLICATE_CHECK: usize = 4;
+// How many following sentences are compared for near-duplicates. LLM staging
+// mistakes repeat a block right after itself, so a small window is enough.
+const NEAR_DUPLICATE_WINDOW: usize = 3;
+const NEAR_DUPLICATE_SIMILARITY: f32 = 0.85;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationIssueKind {
+    DuplicateSegmentId,
+    DuplicateSentence,
+    NearDuplicateSentence,
+    EmptyAdvSL,
+    ProtectedDiglotEntry,
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidationIssue {
+    pub sentence_index: usize, // 0-based index into chapter.sentences
+    pub sentence_id: String,
+    pub kind: ValidationIssueKind,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:?}] {}: {}", self.kind, self.sentence_id, self.message)
+    }
+}
+
+/// Runs chapter-level checks for common LLM staging mistakes that the parser
+/// accepts silently: repeated segment IDs, repeated sentence blocks, AdvS text
+/// without AdvSL lemmas and DIGLOT_MAP words that only occur inside names, quotes
+/// or another segment's locked phrase.
+pub fn validate_chapter(chapter: &ProcessedChapter) -> Vec<ValidationIssue> {
+    let mut issues: Vec<ValidationIssue> = Vec::new();
+    let base_tokenizer = tokenizer::tokenizer_for_language(&chapter.language_pair.base);
+
+    for (index, sentence) in chapter.sentences.iter().enumerate() {
+        check_duplicate_segment_ids(index, sentence, &mut issues);
+
+        let protected_spans = protected_sim_e_spans(sentence, base_tokenizer.as_ref());
+        for (segment_id, entry, kind) in protected_diglot_entries(sentence, &protected_spans, base_tokenizer.as_ref()) {
+            issues.push(ValidationIssue {
+                sentence_index: index,
+                sentence_id: sentence.sentence_id.clone(),
+                kind: ValidationIssueKind::ProtectedDiglotEntry,
+                message: format!(
+                    "DIGLOT_MAP word '{}' of segment {} only occurs inside {} in SimE; it is treated as non-viable for L4.",
+                    entry.eng_word, segment_id, kind.describe()
+                ),
+            });
+        }
+
+        if !sentence.adv_s.trim().is_empty() && sentence.adv_s_lemmas.iter().all(|l| l.trim().is_empty()) {
+            issues.push(ValidationIssue {
+                sentence_index: index,
+                sentence_id: sentence.sentence_id.clone(),
+                kind: ValidationIssueKind::EmptyAdvSL,
+                message: "AdvS text is present but AdvSL has no lemmas; L1 can never be chosen for this sentence.".to_string(),
+            });
+        }
+    }
+
+    check_duplicate_sentences(chapter, &mut issues);
+    issues.sort_by_key(|issue| issue.sentence_index);
+    issues
+}
+
+fn check_duplicate_segment_ids(index: usize, sentence: &ProcessedSentence, issues: &mut Vec<ValidationIssue>) {
+    let sections: [(&str, Vec<&str>); 4] = [
+        ("SimS_Segments", sentence.sim_s_segments.iter().map(|s| s.id.as_str()).collect()),
+        ("PHRASE_ALIGN", sentence.phrase_alignments.iter().map(|pa| pa.segment_id.as_str()).collect()),
+        ("SimSL", sentence.sim_s_lemmas.iter().map(|sl| sl.segment_id.as_str()).collect()),
+        ("DIGLOT_MAP", sentence.diglot_map.iter().map(|dm| dm.segment_id.as_str()).collect()),
+    ];
+
+    for (section_name, segment_ids) in sections.iter() {
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut reported: HashSet<&str> = HashSet::new();
+        for &segment_id in segment_ids {
+            if !seen.insert(segment_id) && reported.insert(segment_id) {
+                issues.push(ValidationIssue {
+                    sentence_index: index,
+                    sentence_id: sentence.sentence_id.clone(),
+                    kind: ValidationIssueKind::DuplicateSegmentId,
+                    message: format!("Segment ID '{}' appears more than once in {}.", segment_id, section_name),
+                });
+            }
+        }
+    }
+}
+
+// Words English capitalizes by convention although they are not names: weekdays, months,
+// languages and nationalities ("on Monday", "she speaks Spanish"). Their Spanish forms are
+// ordinary lowercase words, so SimE capitalization alone doesn't make them proper nouns.
+const CONVENTIONALLY_CAPITALIZED: &[&str] = &[
+    "monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday",
+    "january", "february", "march", "april", "may", "june", "july", "august",
+    "september", "october", "november", "december",
+    "english", "spanish", "french", "german", "italian", "portuguese", "chinese", "japanese",
+    "russian", "arabic", "american", "mexican", "british", "european", "african", "asian",
+    "latin", "catholic", "christian", "muslim", "jewish",
+];
+
+/// Stretches of the sentence's SimE that L4 must leave alone: proper nouns, quotes and
+/// the SimE spans of locked phrases.
+pub fn protected_sim_e_spans(sentence: &ProcessedSentence, base_tokenizer: &dyn Tokenizer) -> Vec<ProtectedSpan> {
+    let sim_e = &sentence.sim_e;
+    let tokens = base_tokenizer.tokenize(sim_e);
+    let mut spans: Vec<ProtectedSpan> = Vec::new();
+
+    // Proper nouns: capitalized words not at the start of a sentence, plus a sentence-initial
+    // capitalized word directly followed by one ("New York"). Words on the
+    // CONVENTIONALLY_CAPITALIZED list never count.
+    let capitalized = |token: &Token| token.text.chars().next().is_some_and(char::is_uppercase)
+        && token.text != "I" && !token.text.starts_with("I'")
+        && !CONVENTIONALLY_CAPITALIZED.contains(&token.text.to_lowercase().as_str());
+    let starts_sentence = |token: &Token| sim_e[..token.start].trim_end()
+        .chars().last()
+        .is_none_or(|c| ".!?:\"“«".contains(c));
+    let mut is_name: Vec<bool> = tokens.iter().map(|t| capitalized(t) && !starts_sentence(t)).collect();
+    for i in 0..tokens.len().saturating_sub(1) {
+        if capitalized(&tokens[i]) && is_name[i + 1] {
+            is_name[i] = true;
+        }
+    }
+    for (i, token) in tokens.iter().enumerate().filter(|(i, _)| is_name[*i]) {
+        match spans.last_mut() {
+            Some(previous) if i > 0 && is_name[i - 1] => previous.range.end = token.end,
+            _ => spans.push(ProtectedSpan { range: token.start..token.end, kind: ProtectedSpanKind::ProperNoun }),
+        }
+    }
+
+    // Quotes
+    let mut open_quote: Option<usize> = None;
+    for (idx, c) in sim_e.char_indices() {
+        match (c, open_quote) {
+            ('"', None) | ('“', None) | ('«', None) => open_quote = Some(idx + c.len_utf8()),
+            ('"', Some(start)) | ('”', Some(start)) | ('»', Some(start)) => {
+                spans.push(ProtectedSpan { range: start..idx, kind: ProtectedSpanKind::Quote });
+                open_quote = None;
+            }
+            _ => {}
+        }
+    }
+
+    // Locked phrases, located through their PHRASE_ALIGN SimE spans.
+    for locked_id in sentence.locked_phrases.iter().flatten() {
+        let aligned = sentence.phrase_alignments.iter()
+            .find(|pa| pa.segment_id == *locked_id)
+            .and_then(|pa| base_tokenizer.find_phrase(sim_e, &pa.sim_e_span));
+        if let Some((start, end)) = aligned {
+            spans.push(ProtectedSpan { range: start..end, kind: ProtectedSpanKind::LockedPhrase });
+        }
+    }
+    spans
+}
+
+/// Byte ranges of SimE where `phrase` occurs on whole tokens, ignoring case.
+pub fn phrase_occurrences(sim_e: &str, phrase: &str, base_tokenizer: &dyn Tokenizer) -> Vec<Range<usize>> {
+    let phrase_tokens: Vec<String> = base_tokenizer.tokenize(phrase).iter().map(|t| t.text.to_lowercase()).collect();
+    if phrase_tokens.is_empty() {
+        return Vec::new();
+    }
+    let tokens = base_tokenizer.tokenize(sim_e);
+    tokens.windows(phrase_tokens.len())
+        .filter(|window| window.iter().zip(&phrase_tokens).all(|(t, p)| t.text.to_lowercase() == *p))
+        .map(|window| window[0].start..window[window.len() - 1].end)
+        .collect()
+}
+
+/// If `eng_word` occurs in `sim_e` but only inside protected spans, the kind of the span
+/// around its first occurrence.
+pub fn protected_occurrence_kind(
+    sim_e: &str,
+    eng_word: &str,
+    entry_segment_locked: bool,
+    protected_spans: &[ProtectedSpan],
+    base_tokenizer: &dyn Tokenizer,
+) -> Option<ProtectedSpanKind> {
+    if protected_spans.is_empty() {
+        return None;
+    }
+    let blocking_span = |occurrence: &Range<usize>| protected_spans.iter().find(|span| span.blocks(occurrence, entry_segment_locked));
+    let occurrences = phrase_occurrences(sim_e, eng_word, base_tokenizer);
+    if occurrences.iter().any(|occurrence| blocking_span(occurrence).is_none()) {
+        return None;
+    }
+    occurrences.first().and_then(blocking_span).map(|span| span.kind)
+}
+
+/// DIGLOT_MAP entries whose English word occurs in SimE, but only inside protected spans,
+/// as (segment ID, entry, kind of the span).
+pub fn protected_diglot_entries<'s>(
+    sentence: &'s ProcessedSentence,
+    protected_spans: &[ProtectedSpan],
+    base_tokenizer: &dyn Tokenizer,
+) -> Vec<(&'s str, &'s DiglotEntry, ProtectedSpanKind)> {
+    let mut found = Vec::new();
+    for segment_map in &sentence.diglot_map {
+        let segment_locked = sentence.is_segment_locked(&segment_map.segment_id);
+        for entry in &segment_map.entries {
+            if let Some(kind) = protected_occurrence_kind(&sentence.sim_e, &entry.eng_word, segment_locked, protected_spans, base_tokenizer) {
+                found.push((segment_map.segment_id.as_str(), entry, kind));
+            }
+        }
+    }
+    found
+}
+
+fn normalized_words(text: &str) -> Vec<String> {
+    EnglishTokenizer.tokenize(text).iter().map(|t| t.text.to_lowercase()).collect()
+}
+
+fn word_set_similarity(a: &HashSet<&str>, b: &HashSet<&str>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+fn check_duplicate_sentences(chapter: &ProcessedChapter, issues: &mut Vec<ValidationIssue>) {
+    let normalized: Vec<Vec<String>> = chapter.sentences.iter().map(|s| normalized_words(&s.sim_e)).collect();
+    let mut first_seen_at: HashMap<String, usize> = HashMap::new();
+
+    for (index, words) in normalized.iter().enumerate() {
+        if words.len() < MIN_WORDS_FOR_DUPLICATE_CHECK {
+            continue;
+        }
+        let sentence_id = &chapter.sentences[index].sentence_id;
+        let key = words.join(" ");
+
+        if let Some(&first_index) = first_seen_at.get(&key) {
+            issues.push(ValidationIssue {
+                sentence_index: index,
+                sentence_id: sentence_id.clone(),
+                kind: ValidationIssueKind::DuplicateSentence,
+                message: format!("SimE is identical to sentence {}.", chapter.sentences[first_index].sentence_id),
+            });
+            continue;
+        }
+        first_seen_at.insert(key, index);
+
+        let words_set: HashSet<&str> = words.iter().map(String::as_str).collect();
+        let window_start = index.saturating_sub(NEAR_DUPLICATE_WINDOW);
+        for (earlier_index, earlier_words) in normalized.iter().enumerate().take(index).skip(window_start) {
+            if earlier_words.len() < MIN_WORDS_FOR_DUPLICATE_CHECK || *earlier_words == *words {
+                continue;
+            }
+            let earlier_set: HashSet<&str> = earlier_words.iter().map(String::as_str).collect();
+            let similarity = word_set_similarity(&words_set, &earlier_set);
+            if similarity >= NEAR_DUPLICATE_SIMILARITY {
+                issues.push(ValidationIssue {
+                    sentence_index: index,
+                    sentence_id: sentence_id.clone(),
+                    kind: ValidationIssueKind::NearDuplicateSentence,
+                    message: format!(
+                        "SimE is {:.0}% similar to nearby sentence {}.",
+                        similarity * 100.0, chapter.sentences[earlier_index].sentence_id
+                    ),
+                });
+                break;
+            }
+        }
+    }
+}
+//*** END FILE: src/parsing/validation.rs ***//
@@ -1,7 +1,14 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use crate::determinism::sorted_map;
+use crate::simulation::dictionary::GlobalLemmaDictionary;
+use crate::simulation::numerical_types::NumericalLearnerProfile;
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Exposures an Active lemma needs to become Known when no threshold table applies.
+pub const DEFAULT_EXPOSURE_THRESHOLD: u32 = 20;
+
+// Ordered by progress: New < Active < Known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum LemmaState { New, Active, Known }
 
 // Added PartialEq here to allow HashMaps of LearnerLemmaInfo to be compared
@@ -9,7 +16,13 @@ pub enum LemmaState { New, Active, Known }
 pub struct LearnerLemmaInfo { 
     pub state: LemmaState, 
     pub exposure_count: u32, 
-    pub required_exposure_threshold: u32 
+    pub required_exposure_threshold: u32,
+    // Block clock value at the last exposure; see NumericalLearnerProfile::apply_decay.
+    #[serde(default)]
+    pub last_exposure_block: u64,
+    // Set when the lemma slid from Known back to Active; cleared on the next exposure.
+    #[serde(default)]
+    pub decayed: bool,
 }
 
 impl Default for LearnerLemmaInfo { 
@@ -17,9 +30,11 @@ impl Default for LearnerLemmaInfo {
         Self { 
             state: LemmaState::New, 
             exposure_count: 0, 
-            // Default threshold for a word to become "Known" after being "Active"
-            // This can be overridden per lemma if adaptive thresholds are implemented later.
-            required_exposure_threshold: 20 
+            // Default threshold for a word to become "Known" after being "Active";
+            // NumericalLearnerProfile overrides it per lemma when a threshold table is loaded.
+            required_exposure_threshold: DEFAULT_EXPOSURE_THRESHOLD,
+            last_exposure_block: 0,
+            decayed: false,
         }
     }
 }
@@ -28,6 +43,7 @@ impl Default for LearnerLemmaInfo {
 pub struct LearnerProfile { 
     // Made vocabulary public to allow direct comparison in main.rs for the saturation check.
     // This is acceptable for this prototype's internal logic.
+    #[serde(serialize_with = "sorted_map")]
     pub vocabulary: HashMap<String, LearnerLemmaInfo> 
 }
 
@@ -107,4 +123,28 @@ impl LearnerProfile {
     pub fn total_exposure_count(&self) -> u32 {
         self.vocabulary.values().map(|info| info.exposure_count).sum()
     }
+
+    // --- Conversion to and from the numerical pipeline ---
+    // String-keyed profiles come from the retired string pipeline; the simulation only runs
+    // on NumericalLearnerProfile, so they are converted through the lemma dictionary.
+
+    // The same lemmas keyed by dictionary ID; lemmas the dictionary lacks are added to it.
+    pub fn to_numerical(&self, dictionary: &mut GlobalLemmaDictionary) -> NumericalLearnerProfile {
+        let mut keys: Vec<&String> = self.vocabulary.keys().filter(|key| !key.trim().is_empty()).collect();
+        keys.sort(); // New IDs are handed out in a stable order
+        let mut numerical = NumericalLearnerProfile::new();
+        for key in keys {
+            numerical.vocabulary.insert(dictionary.get_id_or_insert(key), self.vocabulary[key].clone());
+        }
+        numerical.sync_state_bitsets();
+        numerical
+    }
+
+    // The numerical profile keyed by lemma string; IDs missing from `dictionary` are skipped.
+    pub fn from_numerical(profile: &NumericalLearnerProfile, dictionary: &GlobalLemmaDictionary) -> Self {
+        let vocabulary = profile.vocabulary.iter()
+            .filter_map(|(&lemma_id, info)| dictionary.get_str(lemma_id).map(|key| (Self::get_key(key), info.clone())))
+            .collect();
+        Self { vocabulary }
+    }
 }
\ No newline at end of file
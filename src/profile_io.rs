@@ -1,62 +1,883 @@
 //*** START FILE: src/profile_io.rs ***//
-use crate::simulation::numerical_types::NumericalLearnerProfile;
-use crate::simulation::dictionary::GlobalLemmaDictionary;
+use crate::simulation::numerical_types::{NumericalChapter, NumericalLearnerProfile};
+use crate::simulation::dictionary::{self, GlobalLemmaDictionary};
+use crate::profile::{LearnerLemmaInfo, LemmaState};
+use crate::determinism::sorted_map;
 use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Error as IoError, ErrorKind as IoErrorKind}; // Import IoError and ErrorKind
-use std::path::Path;
-use std::error::Error; // For Box<dyn Error>
+use std::io::{BufReader, BufWriter, Error as IoError, ErrorKind as IoErrorKind, Read, Write}; // Import IoError and ErrorKind
+use std::path::{Path, PathBuf};
+use crate::error::WeaveLangError;
+use tracing::{info, warn};
 
-// This struct will be serialized to/from JSON
+// Snapshot schema history. Bump SNAPSHOT_SCHEMA_VERSION and add a JSON migration step
+// whenever LearnerLemmaInfo, NumericalLearnerProfile or the snapshot layout change.
+//   1 - unversioned snapshots (no schema_version field, no forgetting-curve fields)
+//   2 - schema_version; LearnerLemmaInfo.last_exposure_block/decayed, profile block_clock
+//   3 - profile form_exposures and dictionary forms (form-level tracking)
+//   4 - profile grammar (GRAM:: feature progress)
+//   5 - MWE lemma keys: brackets dropped and inner whitespace joined ("[Por favor]" -> "por_favor").
+//       Same layout as 4; older dictionaries are re-keyed after decoding (migrate_lemma_keys).
+pub const SNAPSHOT_SCHEMA_VERSION: u32 = 5;
+const MWE_KEY_SCHEMA_VERSION: u32 = 5;
+// Oldest binary schema whose bincode layout matches the current structs.
+const OLDEST_BINARY_SCHEMA_VERSION: u32 = 4;
+const UNVERSIONED_SCHEMA_VERSION: u32 = 1;
+
+fn unversioned_schema_version() -> u32 {
+    UNVERSIONED_SCHEMA_VERSION
+}
+
+// This struct will be serialized to/from JSON (or the binary format below).
+// schema_version must stay the first field: binary loads read it before decoding the rest.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ProfileSnapshot {
+    #[serde(default = "unversioned_schema_version")]
+    pub schema_version: u32,
     pub profile: NumericalLearnerProfile,
     pub dictionary: GlobalLemmaDictionary,
 }
 
+// Adds the forgetting-curve fields to every LearnerLemmaInfo object in a vocabulary map.
+fn add_decay_fields_to_vocabulary(vocabulary: Option<&mut serde_json::Value>) {
+    let Some(entries) = vocabulary.and_then(|v| v.as_object_mut()) else { return };
+    for info in entries.values_mut().filter_map(|info| info.as_object_mut()) {
+        info.entry("last_exposure_block").or_insert(serde_json::json!(0));
+        info.entry("decayed").or_insert(serde_json::json!(false));
+    }
+}
+
+fn migrate_snapshot_v1_to_v2(snapshot: &mut serde_json::Value) {
+    if let Some(profile) = snapshot.get_mut("profile").and_then(|p| p.as_object_mut()) {
+        profile.entry("block_clock").or_insert(serde_json::json!(0));
+        add_decay_fields_to_vocabulary(profile.get_mut("vocabulary"));
+    }
+    add_decay_fields_to_vocabulary(snapshot.get_mut("changed_vocabulary")); // Delta snapshots
+}
+
+fn migrate_snapshot_v2_to_v3(snapshot: &mut serde_json::Value) {
+    if let Some(profile) = snapshot.get_mut("profile").and_then(|p| p.as_object_mut()) {
+        profile.entry("form_exposures").or_insert(serde_json::json!({}));
+    }
+    if let Some(dictionary) = snapshot.get_mut("dictionary").and_then(|d| d.as_object_mut()) {
+        dictionary.entry("forms").or_insert(serde_json::json!({}));
+    }
+}
+
+fn migrate_snapshot_v3_to_v4(snapshot: &mut serde_json::Value) {
+    if let Some(profile) = snapshot.get_mut("profile").and_then(|p| p.as_object_mut()) {
+        profile.entry("grammar").or_insert(serde_json::json!({}));
+    }
+}
+
+type SnapshotMigration = fn(&mut serde_json::Value);
+
+// Re-keys a dictionary written before MWE_KEY_SCHEMA_VERSION in place, so delta snapshots on
+// top of it still line up. Where two old keys now coincide ("por favor" and "por_favor"), the
+// lower ID keeps the key and the other's progress is folded into it, as in
+// merge_duplicate_lemmas_in_snapshots. Returns the number of merged lemmas.
+fn migrate_lemma_keys(profile: &mut NumericalLearnerProfile, dictionary: &mut GlobalLemmaDictionary) -> usize {
+    let collisions = dictionary.renormalize_keys();
+    for &(lemma_id, kept_id) in &collisions {
+        let Some(info) = profile.vocabulary.remove(&lemma_id) else { continue };
+        match profile.vocabulary.get_mut(&kept_id) {
+            None => { profile.vocabulary.insert(kept_id, info); }
+            Some(existing) => {
+                existing.exposure_count += info.exposure_count;
+                merge_progress(existing, &info);
+            }
+        }
+    }
+    profile.sync_state_bitsets();
+    collisions.len()
+}
+
+// Step N upgrades JSON of schema version N to N + 1.
+const SNAPSHOT_MIGRATIONS: &[(u32, SnapshotMigration)] = &[
+    (1, migrate_snapshot_v1_to_v2),
+    (2, migrate_snapshot_v2_to_v3),
+    (3, migrate_snapshot_v3_to_v4),
+];
+
+/// Upgrades the JSON of a full or delta snapshot to SNAPSHOT_SCHEMA_VERSION.
+/// Returns the version the JSON was written with. Snapshots from a newer schema are
+/// rejected rather than loaded with fields silently dropped.
+pub fn migrate_snapshot_json(snapshot: &mut serde_json::Value) -> Result<u32, WeaveLangError> {
+    let original_version = match snapshot.get("schema_version") {
+        None => UNVERSIONED_SCHEMA_VERSION,
+        Some(v) => v.as_u64().map(|v| v as u32).ok_or_else(|| WeaveLangError::profile_io(format!("Invalid schema_version {}", v)))?,
+    };
+    if original_version > SNAPSHOT_SCHEMA_VERSION {
+        return Err(WeaveLangError::profile_io(format!(
+            "Snapshot schema version {} is newer than the supported version {}; update WeaveLang to load it",
+            original_version, SNAPSHOT_SCHEMA_VERSION
+        )));
+    }
+    for (from_version, migrate) in SNAPSHOT_MIGRATIONS {
+        if *from_version >= original_version {
+            migrate(snapshot);
+        }
+    }
+    if let Some(fields) = snapshot.as_object_mut() {
+        fields.insert("schema_version".to_string(), serde_json::json!(SNAPSHOT_SCHEMA_VERSION));
+    }
+    Ok(original_version)
+}
+
+// Binary snapshots start with this tag followed by a bincode-encoded ProfileSnapshot.
+// The trailing byte is the binary layout version.
+const BINARY_SNAPSHOT_MAGIC: &[u8; 8] = b"WLPROF\0\x01";
+
+/// On-disk encoding of a full profile snapshot. JSON stays the default because it can be
+/// inspected and diffed; the binary form is much smaller and faster for large dictionaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotFormat {
+    #[default]
+    Json,
+    Binary,
+}
+
+impl SnapshotFormat {
+    /// File name suffix used for snapshots in this format, e.g. "profile.json".
+    pub fn file_suffix(&self) -> &'static str {
+        match self {
+            SnapshotFormat::Json => "profile.json",
+            SnapshotFormat::Binary => "profile.bin",
+        }
+    }
+}
+
+impl std::str::FromStr for SnapshotFormat {
+    type Err = WeaveLangError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "json" => Ok(SnapshotFormat::Json),
+            "binary" | "bin" | "bincode" => Ok(SnapshotFormat::Binary),
+            _ => Err(WeaveLangError::config(format!("Invalid snapshot format '{}': expected 'json' or 'binary'.", s))),
+        }
+    }
+}
+
 /// Saves the learner profile and global dictionary to a JSON file.
 pub fn save_profile_snapshot(
     profile: &NumericalLearnerProfile,
     dictionary: &GlobalLemmaDictionary,
     file_path: &Path,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<(), WeaveLangError> {
+    save_profile_snapshot_as(profile, dictionary, file_path, SnapshotFormat::Json)
+}
+
+/// Saves the learner profile and global dictionary in the given format.
+pub fn save_profile_snapshot_as(
+    profile: &NumericalLearnerProfile,
+    dictionary: &GlobalLemmaDictionary,
+    file_path: &Path,
+    format: SnapshotFormat,
+) -> Result<(), WeaveLangError> {
     let snapshot = ProfileSnapshot {
+        schema_version: SNAPSHOT_SCHEMA_VERSION,
         profile: profile.clone(), 
         dictionary: dictionary.clone(),
     };
 
     let file = File::create(file_path).map_err(|e| 
-        format!("Failed to create profile snapshot file at {:?}: {}", file_path, e)
+        WeaveLangError::profile_io(format!("Failed to create profile snapshot file at {:?}: {}", file_path, e)).with_source(e)
     )?;
-    let writer = BufWriter::new(file);
+    let mut writer = BufWriter::new(file);
     
-    serde_json::to_writer_pretty(writer, &snapshot).map_err(|e| 
-        format!("Failed to serialize profile snapshot to {:?}: {}", file_path, e)
-    )?;
+    match format {
+        SnapshotFormat::Json => serde_json::to_writer_pretty(&mut writer, &snapshot).map_err(|e| 
+            WeaveLangError::profile_io(format!("Failed to serialize profile snapshot to {:?}: {}", file_path, e)).with_source(e)
+        )?,
+        SnapshotFormat::Binary => {
+            writer.write_all(BINARY_SNAPSHOT_MAGIC).map_err(|e|
+                WeaveLangError::profile_io(format!("Failed to write profile snapshot to {:?}: {}", file_path, e)).with_source(e)
+            )?;
+            bincode::serialize_into(&mut writer, &snapshot).map_err(|e|
+                WeaveLangError::profile_io(format!("Failed to serialize profile snapshot to {:?}: {}", file_path, e)).with_source(e)
+            )?;
+        }
+    }
+    writer.flush().map_err(|e| WeaveLangError::profile_io(format!("Failed to write profile snapshot to {:?}: {}", file_path, e)).with_source(e))?;
     
     Ok(())
 }
 
-/// Loads the learner profile and global dictionary from a JSON file.
+/// Detects a snapshot's format from its first bytes (the file extension is not trusted).
+pub fn detect_snapshot_format(file_path: &Path) -> Result<SnapshotFormat, WeaveLangError> {
+    let mut file = File::open(file_path).map_err(|e| 
+        WeaveLangError::profile_io(format!("Failed to open profile snapshot file at {:?}: {}", file_path, e)).with_source(e)
+    )?;
+    let mut header = [0u8; BINARY_SNAPSHOT_MAGIC.len()];
+    let is_binary = file.read_exact(&mut header).is_ok() && &header == BINARY_SNAPSHOT_MAGIC;
+    Ok(if is_binary { SnapshotFormat::Binary } else { SnapshotFormat::Json })
+}
+
+/// Loads the learner profile and global dictionary from a JSON or binary snapshot.
 pub fn load_profile_snapshot(
     file_path: &Path,
-) -> Result<(NumericalLearnerProfile, GlobalLemmaDictionary), Box<dyn Error>> {
+) -> Result<(NumericalLearnerProfile, GlobalLemmaDictionary), WeaveLangError> {
     if !file_path.exists() {
-        return Err(Box::new(IoError::new(
-            IoErrorKind::NotFound,
-            format!("Profile snapshot file not found at {:?}", file_path),
-        )));
+        let message = format!("Profile snapshot file not found at {:?}", file_path);
+        return Err(WeaveLangError::profile_io(message.clone()).with_source(IoError::new(IoErrorKind::NotFound, message)));
     }
 
+    let format = detect_snapshot_format(file_path)?;
     let file = File::open(file_path).map_err(|e| 
-        format!("Failed to open profile snapshot file at {:?}: {}", file_path, e)
+        WeaveLangError::profile_io(format!("Failed to open profile snapshot file at {:?}: {}", file_path, e)).with_source(e)
     )?;
-    let reader = BufReader::new(file);
+    let mut reader = BufReader::new(file);
     
-    let snapshot: ProfileSnapshot = serde_json::from_reader(reader).map_err(|e| 
-        format!("Failed to deserialize profile snapshot from {:?}: {}", file_path, e)
-    )?;
+    let original_version: u32;
+    let mut snapshot: ProfileSnapshot = match format {
+        SnapshotFormat::Json => {
+            let mut value: serde_json::Value = serde_json::from_reader(reader).map_err(|e| 
+                WeaveLangError::profile_io(format!("Failed to deserialize profile snapshot from {:?}: {}", file_path, e)).with_source(e)
+            )?;
+            original_version = migrate_snapshot_json(&mut value)
+                .map_err(|e| e.context(format_args!("Cannot load profile snapshot {:?}", file_path)))?;
+            if original_version < SNAPSHOT_SCHEMA_VERSION {
+                info!("Migrated profile snapshot {:?} from schema version {} to {}.",
+                      file_path, original_version, SNAPSHOT_SCHEMA_VERSION);
+            }
+            serde_json::from_value(value).map_err(|e| 
+                WeaveLangError::profile_io(format!("Failed to deserialize profile snapshot from {:?}: {}", file_path, e)).with_source(e)
+            )?
+        }
+        SnapshotFormat::Binary => {
+            reader.seek_relative(BINARY_SNAPSHOT_MAGIC.len() as i64).map_err(|e|
+                WeaveLangError::profile_io(format!("Failed to read profile snapshot from {:?}: {}", file_path, e)).with_source(e)
+            )?;
+            // bincode is not self-describing, so older layouts cannot be patched up like JSON;
+            // a schema bump that changes the structs needs to decode the old struct and convert it here.
+            let schema_version: u32 = bincode::deserialize_from(&mut reader).map_err(|e|
+                WeaveLangError::profile_io(format!("Failed to read binary profile snapshot header from {:?}: {}", file_path, e)).with_source(e)
+            )?;
+            if !(OLDEST_BINARY_SCHEMA_VERSION..=SNAPSHOT_SCHEMA_VERSION).contains(&schema_version) {
+                return Err(WeaveLangError::profile_io(format!(
+                    "Binary profile snapshot {:?} has schema version {}, but only versions {} to {} can be read.",
+                    file_path, schema_version, OLDEST_BINARY_SCHEMA_VERSION, SNAPSHOT_SCHEMA_VERSION
+                )));
+            }
+            original_version = schema_version;
+            let (profile, dictionary): (NumericalLearnerProfile, GlobalLemmaDictionary) = bincode::deserialize_from(reader).map_err(|e|
+                WeaveLangError::profile_io(format!("Failed to deserialize binary profile snapshot from {:?}: {}", file_path, e)).with_source(e)
+            )?;
+            ProfileSnapshot { schema_version, profile, dictionary }
+        }
+    };
+    if original_version < MWE_KEY_SCHEMA_VERSION {
+        let merged = migrate_lemma_keys(&mut snapshot.profile, &mut snapshot.dictionary);
+        if merged > 0 {
+            info!("Merged {} lemmas of {:?} whose keys now coincide.", merged, file_path);
+        }
+    }
     
     Ok((snapshot.profile, snapshot.dictionary))
 }
+// A snapshot stored relative to a full base snapshot: only the lemmas whose info changed
+// and the dictionary entries appended since the base was written.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProfileDeltaSnapshot {
+    #[serde(default = "unversioned_schema_version")]
+    pub schema_version: u32,
+    pub base_snapshot_file: String, // File name, resolved relative to the delta's directory
+    pub base_dictionary_size: usize,
+    pub added_lemmas: Vec<String>,  // In ID order, starting at base_dictionary_size
+    #[serde(serialize_with = "sorted_map")]
+    pub changed_vocabulary: HashMap<u32, LearnerLemmaInfo>,
+    #[serde(default)]
+    pub block_clock: u64,
+    #[serde(default)]
+    pub changed_form_exposures: BTreeMap<String, u32>,
+    #[serde(default)]
+    pub forms: BTreeMap<String, Vec<String>>, // The whole form map; the delta does not know the base's
+    #[serde(default)]
+    pub aliases: BTreeMap<String, u32>, // Likewise every lemma alias (POS sense sharing a bare lemma's ID)
+    #[serde(default)]
+    pub changed_grammar: BTreeMap<String, LearnerLemmaInfo>,
+}
+
+/// Saves the difference between `profile`/`dictionary` and the base snapshot at
+/// `base_snapshot_path` (whose contents are `base_profile` and a dictionary of
+/// `base_dictionary_size` lemmas) as a delta JSON file.
+pub fn save_profile_delta(
+    base_profile: &NumericalLearnerProfile,
+    base_dictionary_size: usize,
+    base_snapshot_path: &Path,
+    profile: &NumericalLearnerProfile,
+    dictionary: &GlobalLemmaDictionary,
+    file_path: &Path,
+) -> Result<(), WeaveLangError> {
+    let base_snapshot_file = base_snapshot_path
+        .file_name()
+        .ok_or_else(|| WeaveLangError::profile_io(format!("Base snapshot path {:?} has no file name", base_snapshot_path)))?
+        .to_string_lossy()
+        .into_owned();
+
+    let changed_vocabulary: HashMap<u32, LearnerLemmaInfo> = profile.vocabulary.iter()
+        .filter(|(id, info)| base_profile.vocabulary.get(id) != Some(*info))
+        .map(|(id, info)| (*id, info.clone()))
+        .collect();
+    let changed_form_exposures: BTreeMap<String, u32> = profile.form_exposures.iter()
+        .filter(|(form, count)| base_profile.form_exposures.get(*form) != Some(*count))
+        .map(|(form, count)| (form.clone(), *count))
+        .collect();
+
+    let delta = ProfileDeltaSnapshot {
+        schema_version: SNAPSHOT_SCHEMA_VERSION,
+        base_snapshot_file,
+        base_dictionary_size,
+        added_lemmas: dictionary.id_to_str.iter().skip(base_dictionary_size).cloned().collect(),
+        changed_vocabulary,
+        block_clock: profile.block_clock,
+        changed_form_exposures,
+        forms: dictionary.forms.clone(),
+        aliases: dictionary.aliases(),
+        changed_grammar: profile.grammar.iter()
+            .filter(|(feature, info)| base_profile.grammar.get(*feature) != Some(*info))
+            .map(|(feature, info)| (feature.clone(), info.clone()))
+            .collect(),
+    };
+
+    let file = File::create(file_path).map_err(|e|
+        WeaveLangError::profile_io(format!("Failed to create profile delta file at {:?}: {}", file_path, e)).with_source(e)
+    )?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &delta).map_err(|e|
+        WeaveLangError::profile_io(format!("Failed to serialize profile delta to {:?}: {}", file_path, e)).with_source(e)
+    )?;
+    Ok(())
+}
+
+/// Loads a delta snapshot by loading its base snapshot and applying the delta on top.
+pub fn load_profile_delta(
+    file_path: &Path,
+) -> Result<(NumericalLearnerProfile, GlobalLemmaDictionary), WeaveLangError> {
+    let file = File::open(file_path).map_err(|e|
+        WeaveLangError::profile_io(format!("Failed to open profile delta file at {:?}: {}", file_path, e)).with_source(e)
+    )?;
+    let mut value: serde_json::Value = serde_json::from_reader(BufReader::new(file)).map_err(|e|
+        WeaveLangError::profile_io(format!("Failed to deserialize profile delta from {:?}: {}", file_path, e)).with_source(e)
+    )?;
+    let original_version = migrate_snapshot_json(&mut value).map_err(|e| e.context(format_args!("Cannot load profile delta {:?}", file_path)))?;
+    let delta: ProfileDeltaSnapshot = serde_json::from_value(value).map_err(|e|
+        WeaveLangError::profile_io(format!("Failed to deserialize profile delta from {:?}: {}", file_path, e)).with_source(e)
+    )?;
+
+    let base_path = file_path.parent().unwrap_or_else(|| Path::new(".")).join(&delta.base_snapshot_file);
+    let (mut profile, mut dictionary) = load_profile_snapshot(&base_path)?;
+
+    if dictionary.size() != delta.base_dictionary_size {
+        return Err(WeaveLangError::profile_io(format!(
+            "Base snapshot {:?} has {} dictionary entries but the delta expects {}",
+            base_path, dictionary.size(), delta.base_dictionary_size
+        )));
+    }
+    let rekey = original_version < MWE_KEY_SCHEMA_VERSION;
+    for lemma in &delta.added_lemmas {
+        if rekey {
+            dictionary.push_legacy_key(lemma); // Keeps changed_vocabulary's IDs valid until re-keyed
+        } else {
+            dictionary.insert_key(lemma);
+        }
+    }
+    for (alias, lemma_id) in &delta.aliases {
+        dictionary.add_alias(alias, *lemma_id);
+    }
+    profile.vocabulary.extend(delta.changed_vocabulary);
+    profile.sync_state_bitsets();
+    if rekey {
+        migrate_lemma_keys(&mut profile, &mut dictionary);
+    }
+    profile.block_clock = delta.block_clock.max(profile.block_clock);
+    profile.form_exposures.extend(delta.changed_form_exposures);
+    profile.grammar.extend(delta.changed_grammar);
+    for (form, lemmas) in &delta.forms {
+        for lemma in lemmas {
+            dictionary.add_form(form, lemma);
+        }
+    }
+
+    Ok((profile, dictionary))
+}
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotGcReport {
+    pub dictionary_size_before: usize,
+    pub dictionary_size_after: usize,
+    pub removed_lemmas: Vec<String>,
+    pub written_files: Vec<PathBuf>,
+}
+
+/// Garbage-collects the dictionary shared by a set of linked profile snapshots.
+/// All snapshots must use the same dictionary lineage (each dictionary a prefix of the
+/// largest one). Lemmas referenced neither by a snapshot's profile nor by one of `chapters`
+/// (numerical chapters with the dictionary they were converted with, e.g. cached books)
+/// are dropped, IDs are compacted, and every snapshot is rewritten into `output_dir` (in
+/// its original format) with the shared compacted dictionary and remapped profile.
+pub fn gc_profile_snapshots(
+    snapshot_paths: &[PathBuf],
+    chapters: &[(&NumericalChapter, &GlobalLemmaDictionary)],
+    output_dir: &Path,
+) -> Result<SnapshotGcReport, WeaveLangError> {
+    let Some((loaded, master_dictionary)) = load_linked_snapshots(snapshot_paths, "GC")? else {
+        return Ok(SnapshotGcReport::default());
+    };
+    let mut referenced_ids: HashSet<u32> = loaded.iter().flat_map(|(_, _, profile, _)| profile.vocabulary.keys().copied()).collect();
+    // Chapters have their own IDs; their lemmas are matched by key.
+    for (chapter, chapter_dictionary) in chapters {
+        referenced_ids.extend(chapter.lemma_ids()
+            .filter_map(|lemma_id| chapter_dictionary.get_str(lemma_id))
+            .filter_map(|lemma| master_dictionary.get_id(lemma)));
+    }
+
+    let collected = dictionary::gc(&master_dictionary, &referenced_ids);
+    std::fs::create_dir_all(output_dir).map_err(|e|
+        WeaveLangError::profile_io(format!("Failed to create GC output directory {:?}: {}", output_dir, e)).with_source(e)
+    )?;
+
+    let mut report = SnapshotGcReport {
+        dictionary_size_before: master_dictionary.size(),
+        dictionary_size_after: collected.dictionary.size(),
+        removed_lemmas: collected.removed_lemmas.clone(),
+        written_files: Vec::new(),
+    };
+    for (path, format, mut profile, _) in loaded {
+        profile.remap_lemma_ids(&collected.id_remap);
+        let file_name = path.file_name().ok_or_else(|| WeaveLangError::profile_io(format!("Snapshot path {:?} has no file name", path)))?;
+        let output_path = output_dir.join(file_name);
+        save_profile_snapshot_as(&profile, &collected.dictionary, &output_path, format)?;
+        report.written_files.push(output_path);
+    }
+    Ok(report)
+}
+
+type LinkedSnapshot = (PathBuf, SnapshotFormat, NumericalLearnerProfile, GlobalLemmaDictionary);
+type LinkedSnapshots = (Vec<LinkedSnapshot>, GlobalLemmaDictionary); // With the largest dictionary
+
+// Loads snapshots that share one dictionary lineage and returns them with the largest
+// dictionary, or None for no paths. `operation` names the caller in the lineage error.
+fn load_linked_snapshots(
+    snapshot_paths: &[PathBuf],
+    operation: &str,
+) -> Result<Option<LinkedSnapshots>, WeaveLangError> {
+    let mut loaded: Vec<LinkedSnapshot> = Vec::new();
+    for path in snapshot_paths {
+        let (profile, dictionary) = load_profile_snapshot(path)?;
+        loaded.push((path.clone(), detect_snapshot_format(path)?, profile, dictionary));
+    }
+    let master_dictionary = match loaded.iter().max_by_key(|(_, _, _, d)| d.size()) {
+        Some((_, _, _, d)) => d.clone(),
+        None => return Ok(None),
+    };
+    for (path, _, _, dictionary) in &loaded {
+        if !master_dictionary.id_to_str.starts_with(&dictionary.id_to_str) {
+            return Err(WeaveLangError::profile_io(format!(
+                "Snapshot {:?} uses a dictionary that is not a prefix of the largest one; refusing to {} unrelated profiles together.",
+                path, operation
+            )));
+        }
+    }
+    Ok(Some((loaded, master_dictionary)))
+}
+
+/// One member of a near-duplicate cluster found by audit_snapshot_lemmas.
+#[derive(Debug, Clone)]
+pub struct DuplicateLemma {
+    pub lemma_id: u32,
+    pub lemma: String,
+    pub exposures: u32, // Summed over the audited snapshots
+}
+
+/// Near-duplicate lemma clusters (dictionary::find_near_duplicates) in the shared dictionary
+/// of linked snapshots.
+pub fn audit_snapshot_lemmas(
+    snapshot_paths: &[PathBuf],
+    max_distance: usize,
+) -> Result<Vec<Vec<DuplicateLemma>>, WeaveLangError> {
+    let Some((loaded, master_dictionary)) = load_linked_snapshots(snapshot_paths, "audit")? else {
+        return Ok(Vec::new());
+    };
+    let exposures = |lemma_id: u32| loaded.iter()
+        .filter_map(|(_, _, profile, _)| profile.get_lemma_info(lemma_id))
+        .map(|info| info.exposure_count)
+        .sum::<u32>();
+    Ok(dictionary::find_near_duplicates(&master_dictionary, max_distance).into_iter()
+        .map(|ids| ids.into_iter()
+            .map(|id| DuplicateLemma {
+                lemma_id: id,
+                lemma: master_dictionary.get_str(id).cloned().unwrap_or_default(),
+                exposures: exposures(id),
+            })
+            .collect())
+        .collect())
+}
+
+/// Merges every near-duplicate cluster of linked snapshots into its lowest-ID lemma
+/// (dictionary::merge_lemma_clusters) and rewrites the snapshots into `output_dir`, in
+/// their original format. Where a profile tracked several lemmas of a cluster, their
+/// exposures are added up and the further state wins. `removed_lemmas` lists the merged-away lemmas.
+pub fn merge_duplicate_lemmas_in_snapshots(
+    snapshot_paths: &[PathBuf],
+    max_distance: usize,
+    output_dir: &Path,
+) -> Result<SnapshotGcReport, WeaveLangError> {
+    let Some((loaded, master_dictionary)) = load_linked_snapshots(snapshot_paths, "merge lemmas of")? else {
+        return Ok(SnapshotGcReport::default());
+    };
+    let clusters = dictionary::find_near_duplicates(&master_dictionary, max_distance);
+    let merged = dictionary::merge_lemma_clusters(&master_dictionary, &clusters);
+    std::fs::create_dir_all(output_dir).map_err(|e|
+        WeaveLangError::profile_io(format!("Failed to create output directory {:?}: {}", output_dir, e)).with_source(e)
+    )?;
+
+    let mut report = SnapshotGcReport {
+        dictionary_size_before: master_dictionary.size(),
+        dictionary_size_after: merged.dictionary.size(),
+        removed_lemmas: merged.removed_lemmas.clone(),
+        written_files: Vec::new(),
+    };
+    for (path, format, mut profile, _) in loaded {
+        let mut old_ids: Vec<u32> = profile.vocabulary.keys().copied().collect();
+        old_ids.sort_unstable(); // The kept (lowest) ID's entry comes first and keeps its threshold
+        let mut vocabulary: HashMap<u32, LearnerLemmaInfo> = HashMap::new();
+        for old_id in old_ids {
+            let (Some(info), Some(&new_id)) = (profile.vocabulary.remove(&old_id), merged.id_remap.get(&old_id)) else { continue };
+            match vocabulary.get_mut(&new_id) {
+                None => { vocabulary.insert(new_id, info); }
+                Some(existing) => {
+                    existing.exposure_count += info.exposure_count; // Reading either spelling was reading the word
+                    merge_progress(existing, &info);
+                }
+            }
+        }
+        profile.vocabulary = vocabulary;
+        profile.sync_state_bitsets();
+        let file_name = path.file_name().ok_or_else(|| WeaveLangError::profile_io(format!("Snapshot path {:?} has no file name", path)))?;
+        let output_path = output_dir.join(file_name);
+        save_profile_snapshot_as(&profile, &merged.dictionary, &output_path, format)?;
+        report.written_files.push(output_path);
+    }
+    Ok(report)
+}
+
+/// What import_known_lemmas did with a word list.
+#[derive(Debug, Clone, Default)]
+pub struct KnownLemmaImport {
+    pub words_read: usize,
+    pub lemmas_marked_known: usize, // Newly Known; a bare word may mark several POS senses
+    pub already_known: usize,
+}
+
+/// Marks the words of a known-word list Known in `profile`, adding them to `dictionary`
+/// if needed. The file is plain text with one word per line, or CSV/TSV whose first
+/// column is the word; a "word" or "lemma" header row, empty lines and # comments are
+/// skipped. Words may carry a POS tag ("banco#noun"); a bare word also marks every
+/// POS-tagged sense the dictionary already has.
+pub fn import_known_lemmas(
+    file_path: &Path,
+    profile: &mut NumericalLearnerProfile,
+    dictionary: &mut GlobalLemmaDictionary,
+) -> Result<KnownLemmaImport, WeaveLangError> {
+    let contents = std::fs::read_to_string(file_path)
+        .map_err(|e| WeaveLangError::profile_io(format!("Failed to read known-word list {:?}: {}", file_path, e)).with_source(e))?;
+    let mut import = KnownLemmaImport::default();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let word = line.split([',', '\t', ';']).next().unwrap_or_default().trim().trim_matches('"').trim();
+        if word.is_empty() || word.eq_ignore_ascii_case("word") || word.eq_ignore_ascii_case("lemma") {
+            continue;
+        }
+        import.words_read += 1;
+        let lemma_id = dictionary.get_id_or_insert(word);
+        let lemma_ids = match dictionary::split_lemma_key(word).1 {
+            Some(_) => vec![lemma_id],
+            None => dictionary.sense_ids(word).to_vec(), // Includes the bare key itself
+        };
+        for lemma_id in lemma_ids {
+            if profile.get_lemma_info(lemma_id).is_some_and(|info| info.state == LemmaState::Known) {
+                import.already_known += 1;
+            } else {
+                profile.override_lemma_state(lemma_id, LemmaState::Known);
+                import.lemmas_marked_known += 1;
+            }
+        }
+    }
+    Ok(import)
+}
+
+/// Outcome of remapping a profile onto another dictionary.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileRemapReport {
+    pub remapped: usize,       // Entries whose ID changed
+    pub unchanged: usize,      // Entries whose lemma already had the same ID
+    pub added_lemmas: usize,   // Lemmas appended to the target dictionary
+    pub unmapped: Vec<String>, // Dropped entries: lemma, or "#<id>" when the source dictionary lacks the ID
+}
+
+impl ProfileRemapReport {
+    pub fn summary(&self) -> String {
+        let mut summary = format!("{} lemma(s) remapped, {} unchanged, {} added to the dictionary, {} unmapped",
+                                  self.remapped, self.unchanged, self.added_lemmas, self.unmapped.len());
+        if !self.unmapped.is_empty() {
+            summary.push_str(&format!(" ({})", self.unmapped.join(", ")));
+        }
+        summary
+    }
+}
+
+/// Rewrites `profile`'s lemma IDs, which refer to `source_dictionary`, to the IDs the same
+/// lemma strings have in `target_dictionary`. Lemmas the target lacks are appended to it
+/// when `add_missing` is set and dropped otherwise; entries whose ID the source dictionary
+/// does not know are always dropped. Dropped entries are listed in the report.
+pub fn remap_profile_to_dictionary(
+    profile: &mut NumericalLearnerProfile,
+    source_dictionary: &GlobalLemmaDictionary,
+    target_dictionary: &mut GlobalLemmaDictionary,
+    add_missing: bool,
+) -> ProfileRemapReport {
+    let mut report = ProfileRemapReport::default();
+    let mut id_remap: HashMap<u32, u32> = HashMap::new();
+    let mut source_ids: Vec<u32> = profile.vocabulary.keys().copied().collect();
+    source_ids.sort_unstable(); // Missing lemmas are appended in source ID order
+    for source_id in source_ids {
+        let Some(lemma) = source_dictionary.get_str(source_id) else {
+            report.unmapped.push(format!("#{}", source_id));
+            continue;
+        };
+        let target_id = match target_dictionary.get_id(lemma) {
+            Some(target_id) => target_id,
+            None if add_missing => {
+                report.added_lemmas += 1;
+                target_dictionary.get_id_or_insert(lemma)
+            }
+            None => {
+                report.unmapped.push(lemma.clone());
+                continue;
+            }
+        };
+        if target_id == source_id { report.unchanged += 1 } else { report.remapped += 1 }
+        id_remap.insert(source_id, target_id);
+    }
+    profile.remap_lemma_ids(&id_remap);
+    report
+}
+
+/// Loads a snapshot (full or delta) for use with `target_dictionary`. When the snapshot's
+/// dictionary is not a prefix of the target (or the other way round), IDs would point at
+/// different lemmas, so the profile is remapped by lemma string and the lemmas the target
+/// lacks are appended to it. Otherwise the longer dictionary is kept and IDs stay as they are.
+pub fn load_profile_snapshot_into(
+    file_path: &Path,
+    target_dictionary: &mut GlobalLemmaDictionary,
+) -> Result<(NumericalLearnerProfile, Option<ProfileRemapReport>), WeaveLangError> {
+    let (mut profile, snapshot_dictionary) = load_any_profile_snapshot(file_path)?;
+    if target_dictionary.id_to_str.starts_with(&snapshot_dictionary.id_to_str) {
+        return Ok((profile, None));
+    }
+    if snapshot_dictionary.id_to_str.starts_with(&target_dictionary.id_to_str) {
+        *target_dictionary = snapshot_dictionary;
+        return Ok((profile, None));
+    }
+    let report = remap_profile_to_dictionary(&mut profile, &snapshot_dictionary, target_dictionary, true);
+    Ok((profile, Some(report)))
+}
+
+/// Combines two profiles into one learner state, e.g. branches generated from separate
+/// book series. The dictionary is `a_dictionary` with B's missing lemmas appended, and B's
+/// lemma IDs are remapped onto it. A lemma tracked by both takes the further state and the
+/// higher exposure count and last exposure; its threshold comes from A. Active lemmas
+/// whose merged exposures reach the threshold become Known. The block clock is the later one.
+pub fn merge_profiles(
+    a: &NumericalLearnerProfile,
+    a_dictionary: &GlobalLemmaDictionary,
+    b: &NumericalLearnerProfile,
+    b_dictionary: &GlobalLemmaDictionary,
+) -> (NumericalLearnerProfile, GlobalLemmaDictionary) {
+    let mut dictionary = a_dictionary.clone();
+    dictionary.merge_forms_from(b_dictionary);
+    let mut b = b.clone();
+    let remap = remap_profile_to_dictionary(&mut b, b_dictionary, &mut dictionary, true);
+    if !remap.unmapped.is_empty() {
+        warn!("Profile merge dropped {} lemma(s) of B whose IDs are missing from its dictionary.", remap.unmapped.len());
+    }
+
+    let mut profile = a.clone();
+    profile.block_clock = a.block_clock.max(b.block_clock);
+    for (form, b_count) in &b.form_exposures {
+        let count = profile.form_exposures.entry(form.clone()).or_insert(0);
+        *count = (*count).max(*b_count);
+    }
+    for (lemma_id, b_info) in b.vocabulary {
+        match profile.vocabulary.get_mut(&lemma_id) {
+            None => { profile.vocabulary.insert(lemma_id, b_info); }
+            Some(info) => merge_progress(info, &b_info),
+        }
+    }
+    profile.sync_state_bitsets();
+    for (feature, b_info) in b.grammar {
+        match profile.grammar.get_mut(&feature) {
+            None => { profile.grammar.insert(feature, b_info); }
+            Some(info) => merge_progress(info, &b_info),
+        }
+    }
+    (profile, dictionary)
+}
+
+// Progress of a lemma (or grammar feature) tracked by both merged profiles.
+fn merge_progress(info: &mut LearnerLemmaInfo, b_info: &LearnerLemmaInfo) {
+    info.decayed = info.decayed && b_info.decayed;
+    info.state = info.state.max(b_info.state);
+    info.exposure_count = info.exposure_count.max(b_info.exposure_count);
+    info.last_exposure_block = info.last_exposure_block.max(b_info.last_exposure_block);
+    if info.state == LemmaState::Active && info.exposure_count >= info.required_exposure_threshold {
+        info.state = LemmaState::Known;
+    }
+    if info.state == LemmaState::Known {
+        info.decayed = false;
+    }
+}
+
+/// merge_profiles on two snapshot files (full or delta); writes the result to `output_path`.
+pub fn merge_profile_snapshots(
+    a_path: &Path,
+    b_path: &Path,
+    output_path: &Path,
+    format: SnapshotFormat,
+) -> Result<(NumericalLearnerProfile, GlobalLemmaDictionary), WeaveLangError> {
+    let (a, a_dictionary) = load_any_profile_snapshot(a_path)?;
+    let (b, b_dictionary) = load_any_profile_snapshot(b_path)?;
+    let (profile, dictionary) = merge_profiles(&a, &a_dictionary, &b, &b_dictionary);
+    save_profile_snapshot_as(&profile, &dictionary, output_path, format)?;
+    Ok((profile, dictionary))
+}
+
+/// Loads a full snapshot, or a delta snapshot (*.delta.json) on top of its base.
+pub fn load_any_profile_snapshot(
+    file_path: &Path,
+) -> Result<(NumericalLearnerProfile, GlobalLemmaDictionary), WeaveLangError> {
+    if file_path.to_string_lossy().ends_with(".delta.json") {
+        load_profile_delta(file_path)
+    } else {
+        load_profile_snapshot(file_path)
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LemmaChangeKind {
+    Added,           // Tracked in B only
+    Removed,         // Tracked in A only
+    StateChanged,
+    ExposureChanged, // Same state, different exposure count
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LemmaChange {
+    pub lemma: String,
+    pub kind: LemmaChangeKind,
+    pub before: Option<LearnerLemmaInfo>,
+    pub after: Option<LearnerLemmaInfo>,
+}
+
+impl LemmaChange {
+    pub fn exposure_delta(&self) -> i64 {
+        self.after.as_ref().map_or(0, |info| info.exposure_count as i64) - self.before.as_ref().map_or(0, |info| info.exposure_count as i64)
+    }
+}
+
+/// Lemma-by-lemma difference between two profiles. Lemmas are matched by dictionary key,
+/// so the snapshots need not share a dictionary lineage.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ProfileDiff {
+    pub known_before: usize,
+    pub known_after: usize,
+    pub active_before: usize,
+    pub active_after: usize,
+    pub block_clock_before: u64,
+    pub block_clock_after: u64,
+    pub changes: Vec<LemmaChange>, // By kind, then lemma
+}
+
+impl ProfileDiff {
+    pub fn count(&self, kind: LemmaChangeKind) -> usize {
+        self.changes.iter().filter(|change| change.kind == kind).count()
+    }
+
+    pub fn total_exposure_delta(&self) -> i64 {
+        self.changes.iter().map(LemmaChange::exposure_delta).sum()
+    }
+
+    /// Plain-text report printed by `profile diff`.
+    pub fn report(&self) -> String {
+        let state = |info: &Option<LearnerLemmaInfo>| info.as_ref().map_or("-".to_string(), |info| format!("{:?}/{}", info.state, info.exposure_count));
+        let mut report = format!(
+            "Known {} -> {}, Active {} -> {}, block clock {} -> {}.\n{} added, {} removed, {} changed state, {} changed exposures only; exposures {:+}.\n",
+            self.known_before, self.known_after, self.active_before, self.active_after,
+            self.block_clock_before, self.block_clock_after,
+            self.count(LemmaChangeKind::Added), self.count(LemmaChangeKind::Removed),
+            self.count(LemmaChangeKind::StateChanged), self.count(LemmaChangeKind::ExposureChanged),
+            self.total_exposure_delta(),
+        );
+        for change in &self.changes {
+            report.push_str(&format!("  {:<15} {}: {} -> {} ({:+})\n", format!("{:?}", change.kind),
+                                     dictionary::describe_lemma_key(&change.lemma), state(&change.before), state(&change.after),
+                                     change.exposure_delta()));
+        }
+        report
+    }
+}
+
+/// Compares profile A (`before`) with profile B (`after`).
+pub fn diff_profiles(
+    before: &NumericalLearnerProfile,
+    before_dictionary: &GlobalLemmaDictionary,
+    after: &NumericalLearnerProfile,
+    after_dictionary: &GlobalLemmaDictionary,
+) -> ProfileDiff {
+    let by_lemma = |profile: &NumericalLearnerProfile, dictionary: &GlobalLemmaDictionary| -> HashMap<String, LearnerLemmaInfo> {
+        profile.vocabulary.iter()
+            .map(|(id, info)| (dictionary.get_str(*id).cloned().unwrap_or_else(|| format!("#{}", id)), info.clone()))
+            .collect()
+    };
+    let before_lemmas = by_lemma(before, before_dictionary);
+    let after_lemmas = by_lemma(after, after_dictionary);
+    let all_lemmas: HashSet<&String> = before_lemmas.keys().chain(after_lemmas.keys()).collect();
+
+    let mut changes: Vec<LemmaChange> = all_lemmas.into_iter()
+        .filter_map(|lemma| {
+            let (b, a) = (before_lemmas.get(lemma), after_lemmas.get(lemma));
+            let kind = match (b, a) {
+                (None, Some(_)) => LemmaChangeKind::Added,
+                (Some(_), None) => LemmaChangeKind::Removed,
+                (Some(b), Some(a)) if b.state != a.state => LemmaChangeKind::StateChanged,
+                (Some(b), Some(a)) if b.exposure_count != a.exposure_count => LemmaChangeKind::ExposureChanged,
+                _ => return None,
+            };
+            Some(LemmaChange { lemma: lemma.clone(), kind, before: b.cloned(), after: a.cloned() })
+        })
+        .collect();
+    changes.sort_by(|x, y| x.kind.cmp(&y.kind).then_with(|| x.lemma.cmp(&y.lemma)));
+
+    ProfileDiff {
+        known_before: before.count_known(),
+        known_after: after.count_known(),
+        active_before: before.count_active_only(),
+        active_after: after.count_active_only(),
+        block_clock_before: before.block_clock,
+        block_clock_after: after.block_clock,
+        changes,
+    }
+}
+
+/// diff_profiles on two snapshot files (full or delta).
+pub fn diff_profile_snapshots(before_path: &Path, after_path: &Path) -> Result<ProfileDiff, WeaveLangError> {
+    let (before, before_dictionary) = load_any_profile_snapshot(before_path)?;
+    let (after, after_dictionary) = load_any_profile_snapshot(after_path)?;
+    Ok(diff_profiles(&before, &before_dictionary, &after, &after_dictionary))
+}
 //*** END FILE: src/profile_io.rs ***//
\ No newline at end of file
@@ -0,0 +1,155 @@
+//*** START FILE: src/progress.rs ***//
+// Progress reporting for corpus generation runs. run_corpus_generation_with_progress feeds
+// a ProgressReporter with book and block events; ETAs are extrapolated from the average
+// time per block so far and the estimated block count of each book (sentences / block size
+// per pass). Books not parsed yet are assumed to be as long as the average book so far.
+
+use std::time::{Duration, Instant};
+use tracing::info;
+
+#[derive(Debug, Clone)]
+pub struct BookProgress {
+    pub book_index: usize, // 1-based position in the sequence
+    pub book_count: usize,
+    pub book_instance_id: String,
+    pub estimated_blocks: usize,
+    pub elapsed: Duration, // Since the book started; zero in on_book_start
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockProgress {
+    pub book_index: usize,
+    pub book_count: usize,
+    pub book_instance_id: String,
+    pub block_in_book: usize,
+    pub estimated_blocks_in_book: usize, // May grow in auto-pass mode as passes are added
+    pub run_block: usize,                // Blocks finished by this process, across books
+    pub ct: f32,
+    pub ct_metric_name: &'static str,
+    pub elapsed: Duration, // Since the run started
+    pub book_eta: Duration,
+    pub run_eta: Duration,
+}
+
+/// Receives progress events from a generation run. All methods default to doing nothing.
+pub trait ProgressReporter {
+    fn on_book_start(&mut self, _book: &BookProgress) {}
+    fn on_block_done(&mut self, _block: &BlockProgress) {}
+    fn on_book_done(&mut self, _book: &BookProgress) {}
+}
+
+pub struct NoProgress;
+impl ProgressReporter for NoProgress {}
+
+/// Logs one progress line per block and one per finished book, used by the CLI.
+pub struct ConsoleProgress;
+
+impl ProgressReporter for ConsoleProgress {
+    fn on_block_done(&mut self, block: &BlockProgress) {
+        info!("Progress: book {}/{}, block {}/{}, elapsed {}, book ETA {}, run ETA {}.",
+              block.book_index, block.book_count, block.block_in_book, block.estimated_blocks_in_book.max(block.block_in_book),
+              format_duration(block.elapsed), format_duration(block.book_eta), format_duration(block.run_eta));
+    }
+
+    fn on_book_done(&mut self, book: &BookProgress) {
+        info!("Book {}/{} ({}) took {}.", book.book_index, book.book_count, book.book_instance_id, format_duration(book.elapsed));
+    }
+}
+
+/// "1h02m05s", "3m07s" or "42s".
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, s) => format!("{}h{:02}m{:02}s", h, m, s),
+    }
+}
+
+/// Keeps the timing state of a run and turns it into progress events.
+pub struct ProgressTracker {
+    run_start: Instant,
+    book_count: usize,
+    book_index: usize,
+    book_instance_id: String,
+    book_start: Instant,
+    book_estimated_blocks: usize,
+    books_started: usize,
+    estimated_blocks_started_books: usize, // Sum of the estimates of every book started so far
+    blocks_done: usize,
+    blocks_done_before_book: usize,
+}
+
+impl ProgressTracker {
+    pub fn new(book_count: usize) -> Self {
+        let now = Instant::now();
+        Self {
+            run_start: now,
+            book_count,
+            book_index: 0,
+            book_instance_id: String::new(),
+            book_start: now,
+            book_estimated_blocks: 0,
+            books_started: 0,
+            estimated_blocks_started_books: 0,
+            blocks_done: 0,
+            blocks_done_before_book: 0,
+        }
+    }
+
+    fn book_progress(&self) -> BookProgress {
+        BookProgress {
+            book_index: self.book_index,
+            book_count: self.book_count,
+            book_instance_id: self.book_instance_id.clone(),
+            estimated_blocks: self.book_estimated_blocks,
+            elapsed: self.book_start.elapsed(),
+        }
+    }
+
+    /// `book_index` is 1-based.
+    pub fn start_book(&mut self, book_index: usize, book_instance_id: &str) -> BookProgress {
+        self.book_index = book_index;
+        self.book_instance_id = book_instance_id.to_string();
+        self.book_start = Instant::now();
+        self.book_estimated_blocks = 0;
+        self.books_started += 1;
+        self.blocks_done_before_book = self.blocks_done;
+        self.book_progress()
+    }
+
+    /// Adds the blocks of another orchestrator run (one per book, or one per pass in auto mode).
+    pub fn add_book_blocks(&mut self, estimated_blocks: usize) {
+        self.book_estimated_blocks += estimated_blocks;
+        self.estimated_blocks_started_books += estimated_blocks;
+    }
+
+    pub fn block_done(&mut self, ct: f32, ct_metric_name: &'static str) -> BlockProgress {
+        self.blocks_done += 1;
+        let block_in_book = self.blocks_done - self.blocks_done_before_book;
+        let elapsed = self.run_start.elapsed();
+        let per_block = elapsed / self.blocks_done as u32;
+        let book_blocks_left = self.book_estimated_blocks.saturating_sub(block_in_book);
+        let blocks_per_book = self.estimated_blocks_started_books as f64 / self.books_started.max(1) as f64;
+        let later_books = self.book_count.saturating_sub(self.book_index);
+        let book_eta = per_block * book_blocks_left as u32;
+        BlockProgress {
+            book_index: self.book_index,
+            book_count: self.book_count,
+            book_instance_id: self.book_instance_id.clone(),
+            block_in_book,
+            estimated_blocks_in_book: self.book_estimated_blocks,
+            run_block: self.blocks_done,
+            ct,
+            ct_metric_name,
+            elapsed,
+            book_eta,
+            run_eta: book_eta + per_block.mul_f64(blocks_per_book * later_books as f64),
+        }
+    }
+
+    pub fn finish_book(&self) -> BookProgress {
+        self.book_progress()
+    }
+}
+//*** END FILE: src/progress.rs ***//
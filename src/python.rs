@@ -0,0 +1,261 @@
+//*** START FILE: src/python.rs ***//
+// Python bindings (`--features python`) for scripting runs and parameter sweeps from
+// notebooks. Build the extension module with
+//
+//   cargo rustc --lib --release --features python,pyo3/extension-module --crate-type cdylib
+//
+// and copy target/release/libweavelang_rust_gui.so to weavelang.so (weavelang.pyd on
+// Windows) next to the notebook, or build it with maturin using the same features.
+//
+//   import weavelang
+//   books = weavelang.run_corpus_generation("config.toml", "sequence.txt", sentences_per_block=150, seed=7)
+//   profile, dictionary = weavelang.load_profile("profiles/bookA_inst01_out.profile.json")
+//   [dictionary.lemma(i) for i in profile.lemma_ids("known")]
+//
+// Results are plain Python values (dicts, lists, str, int, float, None).
+#![allow(clippy::useless_conversion)] // Raised inside the #[pyfunction] / #[pymethods] expansions
+
+use crate::config;
+use crate::corpus_generator::{self, BookReport, GenerationArgs, PassesPerBook};
+use crate::profile::LemmaState;
+use crate::profile_io;
+use crate::progress::NoProgress;
+use crate::simulation::block::BlockBoundaries;
+use crate::simulation::core_algo::DEFAULT_MIN_DIGLOT_CONFIDENCE;
+use crate::simulation::dictionary::GlobalLemmaDictionary;
+use crate::simulation::exporters::epub::EpubChapterMode;
+use crate::simulation::exporters::subtitles::SubtitleTiming;
+use crate::simulation::numerical_types::{DecayParams, NumericalLearnerProfile};
+use crate::simulation::scheduler::SchedulerParams;
+use crate::chapter_cache::ChapterCache;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::path::PathBuf;
+
+/// A learner profile: the state and exposure count of every lemma seen so far.
+#[pyclass(name = "Profile", module = "weavelang")]
+#[derive(Clone)]
+pub struct PyProfile {
+    profile: NumericalLearnerProfile,
+}
+
+#[pymethods]
+impl PyProfile {
+    #[getter]
+    fn known_count(&self) -> usize {
+        self.profile.count_known()
+    }
+
+    /// Active but not yet Known.
+    #[getter]
+    fn active_count(&self) -> usize {
+        self.profile.count_active_only()
+    }
+
+    /// "new", "active" or "known"; lemmas the profile never saw are "new".
+    fn state(&self, lemma_id: u32) -> &'static str {
+        state_name(self.profile.get_lemma_info(lemma_id).map_or(LemmaState::New, |info| info.state))
+    }
+
+    fn exposures(&self, lemma_id: u32) -> u32 {
+        self.profile.get_lemma_info(lemma_id).map_or(0, |info| info.exposure_count)
+    }
+
+    /// IDs of the lemmas in `state` ("new", "active" or "known"), ascending.
+    fn lemma_ids(&self, state: &str) -> PyResult<Vec<u32>> {
+        let state = parse_state(state)?;
+        let mut ids: Vec<u32> = self.profile.vocabulary.iter()
+            .filter(|(_, info)| info.state == state)
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Profile(known={}, active={})", self.profile.count_known(), self.profile.count_active_only())
+    }
+}
+
+/// The lemma <-> ID mapping a profile's IDs refer to.
+#[pyclass(name = "Dictionary", module = "weavelang")]
+#[derive(Clone)]
+pub struct PyDictionary {
+    dictionary: GlobalLemmaDictionary,
+}
+
+#[pymethods]
+impl PyDictionary {
+    #[new]
+    fn new() -> Self {
+        Self { dictionary: GlobalLemmaDictionary::new() }
+    }
+
+    fn lemma_id(&self, lemma: &str) -> Option<u32> {
+        self.dictionary.get_id(lemma)
+    }
+
+    fn lemma(&self, lemma_id: u32) -> Option<String> {
+        self.dictionary.get_str(lemma_id).cloned()
+    }
+
+    /// Every lemma, in ID order.
+    fn lemmas(&self) -> Vec<String> {
+        (0..self.dictionary.size() as u32).filter_map(|id| self.dictionary.get_str(id).cloned()).collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.dictionary.size()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Dictionary({} lemmas)", self.dictionary.size())
+    }
+}
+
+fn state_name(state: LemmaState) -> &'static str {
+    match state {
+        LemmaState::New => "new",
+        LemmaState::Active => "active",
+        LemmaState::Known => "known",
+    }
+}
+
+fn parse_state(state: &str) -> PyResult<LemmaState> {
+    match state.to_ascii_lowercase().as_str() {
+        "new" => Ok(LemmaState::New),
+        "active" => Ok(LemmaState::Active),
+        "known" => Ok(LemmaState::Known),
+        _ => Err(PyValueError::new_err(format!("Invalid lemma state '{}': expected new, active or known.", state))),
+    }
+}
+
+/// Loads a profile snapshot (JSON or binary) and the dictionary stored with it.
+#[pyfunction]
+fn load_profile(path: PathBuf) -> PyResult<(PyProfile, PyDictionary)> {
+    let (profile, dictionary) = profile_io::load_profile_snapshot(&path)
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+    Ok((PyProfile { profile }, PyDictionary { dictionary }))
+}
+
+fn book_report_dict<'py>(py: Python<'py>, book: &BookReport) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("book_instance_id", &book.book_instance_id)?;
+    dict.set_item("book_stem", &book.book_stem)?;
+    dict.set_item("start_level", book.start_level)?;
+    dict.set_item("end_level", book.end_level)?;
+    dict.set_item("known_before", book.known_before)?;
+    dict.set_item("known_after", book.known_after)?;
+    dict.set_item("active_after", book.active_after)?;
+    dict.set_item("sentences", book.sentences)?;
+    dict.set_item("blocks", book.blocks)?;
+    dict.set_item("average_ct", book.average_ct)?;
+    dict.set_item("ct_metric", &book.ct_metric_name)?;
+    dict.set_item("words_activated", book.words_activated)?;
+    dict.set_item("level_counts", book.level_counts.to_vec())?;
+    dict.set_item("form_coverage", book.form_coverage)?;
+    dict.set_item("tts_path", &book.tts_path)?;
+    dict.set_item("qa_report_path", &book.qa_report_path)?;
+    Ok(dict)
+}
+
+/// Runs `generate` over a sequence file and returns one dict per book instance. Settings
+/// not passed here come from the config (like the CLI's) or the CLI's defaults; outputs
+/// beyond the TTS text, QA reports and profiles are off.
+#[pyfunction]
+#[pyo3(signature = (
+    config_path, sequence_path, *, tts_output_dir = PathBuf::from("./tts_output"), profiles_dir = PathBuf::from("./profiles"),
+    start_profile = None, sentences_per_block = None, target_ct_threshold = None, min_ct_threshold = None,
+    max_words_to_activate_per_regen = 3, passes_per_book = "1".to_string(), seed = None, parallel_lookahead = 0,
+))]
+#[allow(clippy::too_many_arguments)]
+fn run_corpus_generation<'py>(
+    py: Python<'py>,
+    config_path: String,
+    sequence_path: PathBuf,
+    tts_output_dir: PathBuf,
+    profiles_dir: PathBuf,
+    start_profile: Option<PathBuf>,
+    sentences_per_block: Option<usize>,
+    target_ct_threshold: Option<f32>,
+    min_ct_threshold: Option<f32>,
+    max_words_to_activate_per_regen: usize,
+    passes_per_book: String,
+    seed: Option<u64>,
+    parallel_lookahead: usize,
+) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    let project_config = config::load_layered_config(&config_path, &[]).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+    let passes_per_book: PassesPerBook = passes_per_book.parse::<PassesPerBook>().map_err(|e| PyValueError::new_err(e.to_string()))?;
+    let sentences_per_block = sentences_per_block.or(project_config.sentences_per_block).unwrap_or(200);
+    if sentences_per_block == 0 {
+        return Err(PyValueError::new_err("sentences_per_block must be at least 1."));
+    }
+    let args = GenerationArgs {
+        sequence_path,
+        tts_output_dir,
+        profiles_dir,
+        start_profile_path: start_profile,
+        sentences_per_block,
+        block_boundaries: project_config.paragraph_block_tolerance
+            .map_or(BlockBoundaries::Fixed, |tolerance| BlockBoundaries::Paragraphs { tolerance }),
+        max_regen_attempts_per_block: 25,
+        target_ct_threshold: target_ct_threshold.or(project_config.target_ct_threshold).unwrap_or(0.98),
+        min_ct_threshold: min_ct_threshold.or(project_config.min_ct_threshold).unwrap_or(0.0),
+        max_words_to_activate_per_regen,
+        min_diglot_confidence: DEFAULT_MIN_DIGLOT_CONFIDENCE,
+        decay: DecayParams::default(),
+        ct_metric: project_config.ct_metric,
+        l4_strategy: project_config.l4_strategy,
+        l4_match_plurals: project_config.l4_match_plurals,
+        scheduler: SchedulerParams { tie_break_seed: seed, ..SchedulerParams::default() },
+        max_new_lemmas_per_100_sentences: project_config.max_new_lemmas_per_100_sentences,
+        passes_per_book,
+        max_auto_passes: 10,
+        snapshot_every_blocks: None,
+        parallel_lookahead,
+        resume: false,
+        snapshot_format: project_config.profile_format.unwrap_or_default(),
+        level_tags: false,
+        level_policy: project_config.levels.clone().with_min_sentence_ct(project_config.min_sentence_ct),
+        level_sidecar: false,
+        audio_manifest: false,
+        subtitle_format: None,
+        subtitle_timing: SubtitleTiming::default(),
+        parallel_text_format: None,
+        trace: false,
+        html_output_dir: None,
+        epub_output_dir: None,
+        epub_chapter_mode: EpubChapterMode::Source,
+        output_format: project_config.output_format.unwrap_or_default(),
+        ssml_base_voice: None,
+        ssml_target_voice: None,
+        exposure_thresholds: project_config.exposure_thresholds_path.as_ref().map(PathBuf::from),
+        default_exposure_threshold: project_config.exposure_threshold,
+        cognate_exposure_threshold: project_config.cognate_exposure_threshold,
+        learner_variability: None,
+        anki_output_dir: None,
+        seed_dictionary: None,
+        known_words: None,
+        seed,
+        dry_run: false,
+        track_forms: project_config.track_forms,
+        chapter_cache_dir: Some(ChapterCache::default_dir(&project_config)),
+    };
+    // Generation can take minutes; other Python threads keep running meanwhile.
+    let report = py.allow_threads(|| {
+        corpus_generator::run_corpus_generation_with_progress(&project_config, &args, &mut NoProgress).map_err(|e| e.to_string())
+    }).map_err(PyRuntimeError::new_err)?;
+    report.books.iter().map(|book| book_report_dict(py, book)).collect()
+}
+
+#[pymodule]
+#[pyo3(name = "weavelang")]
+fn weavelang_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyProfile>()?;
+    module.add_class::<PyDictionary>()?;
+    module.add_function(wrap_pyfunction!(load_profile, module)?)?;
+    module.add_function(wrap_pyfunction!(run_corpus_generation, module)?)?;
+    Ok(())
+}
+//*** END FILE: src/python.rs ***//
@@ -0,0 +1,160 @@
+//*** START FILE: src/qa_report.rs ***//
+use crate::profile::LemmaState;
+use crate::simulation::dictionary::{normalize_lemma_key, GlobalLemmaDictionary};
+use crate::simulation::exporters::html::trace_sentence_words;
+use crate::simulation::numerical_types::NumericalLearnerProfile;
+use crate::simulation::level_policy::SentenceLevel;
+use crate::simulation::text_generator::GeneratedTextBlock;
+use crate::tokenizer::{self, Tokenizer};
+use crate::types::llm_data::{ProcessedChapter, ProcessedSentence};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use crate::error::WeaveLangError;
+use std::fs;
+use std::path::Path;
+
+/// CT of one block, as printed in the generation log.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockCt {
+    pub block_in_book: usize,
+    pub ct: f32,
+}
+
+/// A lemma with viable DIGLOT_MAP entries that no L4 sentence of the book instance
+/// substituted. Known/Active ones are the suspicious cases: the substitution could have
+/// fired but never did, which usually means the entry's word does not occur in its SimE.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnsubstitutedLemma {
+    pub lemma: String,
+    pub diglot_entries: usize,     // Viable entries across the book
+    pub sentence_ids: Vec<String>, // Sentences with such an entry
+    pub final_state: LemmaState,   // In the profile at the end of the book instance
+}
+
+/// Quality report of one book instance (<book instance>.qa.json next to its profiles),
+/// pointing at authoring problems in the source .llm.txt.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BookQaReport {
+    pub book_instance_id: String,
+    pub sentences_rendered: usize,             // Counts every pass
+    pub level_counts: BTreeMap<String, usize>, // "L1".."L5" -> sentences rendered at that level
+    pub ct_metric_name: String,
+    pub block_cts: Vec<BlockCt>,
+    pub sime_fallbacks: usize,                 // Sentences rendered as pure SimE (L5)
+    pub sime_fallback_sentence_ids: Vec<String>, // Distinct, in first-seen order
+    pub never_substituted: Vec<UnsubstitutedLemma>, // Known/Active lemmas first, then by lemma
+}
+
+// Viable DIGLOT_MAP entries of one lemma in the book.
+#[derive(Default)]
+struct DiglotCandidate {
+    entries: usize,
+    sentence_ids: Vec<String>,
+}
+
+/// Collects a BookQaReport while a book instance is simulated.
+pub struct QaReportBuilder {
+    report: BookQaReport,
+    form_tokenizer: Box<dyn Tokenizer>,
+    candidates: BTreeMap<String, DiglotCandidate>,
+    substituted: HashSet<String>,
+    fallback_ids_seen: HashSet<String>,
+}
+
+impl QaReportBuilder {
+    pub fn new(book_instance_id: &str, chapter: &ProcessedChapter, min_diglot_confidence: f32) -> Self {
+        let mut candidates: BTreeMap<String, DiglotCandidate> = BTreeMap::new();
+        for sentence in &chapter.sentences {
+            for entry in sentence.diglot_map.iter().flat_map(|segment_map| &segment_map.entries) {
+                if !entry.is_viable(min_diglot_confidence) || entry.spa_lemma.trim().is_empty() {
+                    continue;
+                }
+                let candidate = candidates.entry(normalize_lemma_key(&entry.spa_lemma)).or_default();
+                candidate.entries += 1;
+                if candidate.sentence_ids.last() != Some(&sentence.sentence_id) {
+                    candidate.sentence_ids.push(sentence.sentence_id.clone());
+                }
+            }
+        }
+        let level_counts = [SentenceLevel::L1, SentenceLevel::L2, SentenceLevel::L3, SentenceLevel::L4, SentenceLevel::L5]
+            .iter().map(|level| (level.name().to_string(), 0)).collect();
+        Self {
+            report: BookQaReport { book_instance_id: book_instance_id.to_string(), level_counts, ..BookQaReport::default() },
+            form_tokenizer: tokenizer::tokenizer_for_language(&chapter.language_pair.target),
+            candidates,
+            substituted: HashSet::new(),
+            fallback_ids_seen: HashSet::new(),
+        }
+    }
+
+    pub fn add_block_ct(&mut self, block_in_book: usize, ct: f32, ct_metric_name: &str) {
+        self.report.ct_metric_name = ct_metric_name.to_string();
+        self.report.block_cts.push(BlockCt { block_in_book, ct });
+    }
+
+    /// Records the levels of one generated block and the lemmas its L4 sentences
+    /// substituted. `profile` should be the profile the block was rendered against.
+    pub fn add_block(
+        &mut self,
+        sentences: &[&ProcessedSentence],
+        generated: &GeneratedTextBlock,
+        dictionary: &GlobalLemmaDictionary,
+        profile: &NumericalLearnerProfile,
+    ) {
+        for ((sentence, text), record) in sentences.iter().zip(&generated.sentence_texts).zip(&generated.sentence_levels) {
+            self.report.sentences_rendered += 1;
+            *self.report.level_counts.entry(record.level.name().to_string()).or_insert(0) += 1;
+            match record.level {
+                SentenceLevel::L5 => {
+                    self.report.sime_fallbacks += 1;
+                    if self.fallback_ids_seen.insert(record.sentence_id.clone()) {
+                        self.report.sime_fallback_sentence_ids.push(record.sentence_id.clone());
+                    }
+                }
+                SentenceLevel::L4 => {
+                    for word in trace_sentence_words(sentence, text, record.level, dictionary, profile, self.form_tokenizer.as_ref()) {
+                        self.substituted.insert(word.lemma);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The finished report; `profile` is the profile at the end of the book instance.
+    pub fn finish(mut self, dictionary: &GlobalLemmaDictionary, profile: &NumericalLearnerProfile) -> BookQaReport {
+        let mut never_substituted: Vec<UnsubstitutedLemma> = self.candidates.into_iter()
+            .filter(|(lemma, _)| !self.substituted.contains(lemma))
+            .map(|(lemma, candidate)| {
+                let final_state = dictionary.get_id(&lemma)
+                    .and_then(|id| profile.get_lemma_info(id))
+                    .map_or(LemmaState::New, |info| info.state);
+                UnsubstitutedLemma { lemma, diglot_entries: candidate.entries, sentence_ids: candidate.sentence_ids, final_state }
+            })
+            .collect();
+        // Stable sort keeps the BTreeMap's lemma order within each state.
+        never_substituted.sort_by_key(|entry| std::cmp::Reverse(entry.final_state));
+        self.report.never_substituted = never_substituted;
+        self.report
+    }
+}
+
+impl BookQaReport {
+    /// One line for the generation log.
+    pub fn summary(&self) -> String {
+        let levels = self.level_counts.iter().map(|(level, count)| format!("{} {}", level, count)).collect::<Vec<_>>().join(", ");
+        let suspicious = self.never_substituted.iter().filter(|entry| entry.final_state != LemmaState::New).count();
+        format!(
+            "{} sentence(s) rendered ({}); {} pure SimE fallback(s); {} lemma(s) never substituted, {} of them Known/Active.",
+            self.sentences_rendered, levels, self.sime_fallbacks, self.never_substituted.len(), suspicious
+        )
+    }
+}
+
+pub fn save_qa_report(report: &BookQaReport, file_path: &Path) -> Result<(), WeaveLangError> {
+    let json = serde_json::to_string_pretty(report)
+        .map_err(|e| WeaveLangError::generation(format!("Failed to serialize QA report: {}", e)).with_source(e))?;
+    fs::write(file_path, json).map_err(|e| WeaveLangError::generation(format!("Failed to write QA report to {:?}: {}", file_path, e)).with_source(e))?;
+    Ok(())
+}
+//*** END FILE: src/qa_report.rs ***//
@@ -0,0 +1,171 @@
+//*** START FILE: src/server.rs ***//
+// `serve`: the json_api calls over HTTP, so a web authoring front-end can drive the engine
+// without shelling out to the CLI. Plain HTTP/1.1 on std::net, one thread per connection,
+// one request per connection (responses close it). Every response is JSON.
+//
+//   GET  /health          {"ok": {"version": "..."}}
+//   POST /parse           json_api::parse
+//   POST /simulate-block  json_api::simulate_block
+//   POST /generate-text   json_api::generate_text
+//   POST /rpc             JSON-RPC 2.0 with the methods "parse", "simulate_block" and
+//                         "generate_text", whose params are the request objects above
+//
+// The REST routes answer {"ok": ...} with 200 or {"error": "..."} with 400. Responses
+// allow any origin (CORS), since the server is meant to run next to a local front-end;
+// bind it to a public address only behind something that authenticates.
+
+use crate::error::WeaveLangError;
+use crate::json_api;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
+use tracing::warn;
+
+// Largest request body accepted; a whole book as JSON stays well below it.
+const MAX_BODY_BYTES: usize = 64 * 1024 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+type Handler = fn(&str) -> Result<Value, WeaveLangError>;
+
+fn method_handler(method: &str) -> Option<Handler> {
+    match method {
+        "parse" => Some(json_api::parse),
+        "simulate_block" => Some(json_api::simulate_block),
+        "generate_text" => Some(json_api::generate_text),
+        _ => None,
+    }
+}
+
+/// An HTTP response before it is written: status code and JSON body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Response {
+    fn new(status: u16, body: Value) -> Self {
+        Self { status, body }
+    }
+}
+
+fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+fn json_rpc(body: &str) -> Value {
+    let request: Value = match serde_json::from_str(body) {
+        Ok(request) => request,
+        Err(e) => return json!({ "jsonrpc": "2.0", "id": null, "error": { "code": -32700, "message": format!("Parse error: {}", e) } }),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let Some(handler) = request.get("method").and_then(Value::as_str).and_then(method_handler) else {
+        return json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32601, "message": "Method not found" } });
+    };
+    let params = request.get("params").cloned().unwrap_or_else(|| json!({}));
+    match handler(&params.to_string()) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32602, "message": error.to_string() } }),
+    }
+}
+
+/// Answers one request. Separate from the socket handling so it can be called directly.
+pub fn route(method: &str, path: &str, body: &str) -> Response {
+    let path = path.split('?').next().unwrap_or_default();
+    match (method, path) {
+        ("OPTIONS", _) => Response::new(204, Value::Null),
+        ("GET", "/health") => Response::new(200, json!({ "ok": { "version": env!("CARGO_PKG_VERSION") } })),
+        ("POST", "/rpc") => Response::new(200, json_rpc(body)),
+        ("POST", _) => {
+            let Some(handler) = path.strip_prefix('/').map(|name| name.replace('-', "_")).and_then(|name| method_handler(&name)) else {
+                return Response::new(404, json!({ "error": format!("No endpoint {}", path) }));
+            };
+            match handler(body) {
+                Ok(value) => Response::new(200, json!({ "ok": value })),
+                Err(error) => Response::new(400, json!({ "error": error.to_string() })),
+            }
+        }
+        (_, "/health" | "/rpc" | "/parse" | "/simulate-block" | "/generate-text") => {
+            Response::new(405, json!({ "error": format!("{} is not allowed on {}", method, path) }))
+        }
+        _ => Response::new(404, json!({ "error": format!("No endpoint {}", path) })),
+    }
+}
+
+// Reads the request line, headers and body; the error is the response to send instead.
+fn read_request(stream: &TcpStream) -> Result<(String, String, String), Response> {
+    let bad_request = |message: &str| Response::new(400, json!({ "error": message }));
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).map_err(|e| bad_request(&e.to_string()))?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(bad_request("Malformed request line"));
+    };
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).map_err(|e| bad_request(&e.to_string()))? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(|_| bad_request("Invalid Content-Length"))?;
+            }
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Err(Response::new(413, json!({ "error": format!("Request bodies are limited to {} bytes", MAX_BODY_BYTES) })));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|e| bad_request(&e.to_string()))?;
+    let body = String::from_utf8(body).map_err(|_| bad_request("The request body is not valid UTF-8"))?;
+    Ok((method.to_string(), path.to_string(), body))
+}
+
+fn write_response(mut stream: &TcpStream, response: &Response) -> std::io::Result<()> {
+    let body = if response.status == 204 { String::new() } else { response.body.to_string() };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Methods: GET, POST, OPTIONS\r\nAccess-Control-Allow-Headers: Content-Type\r\nConnection: close\r\n\r\n{}",
+        response.status, status_text(response.status), body.len(), body
+    )?;
+    stream.flush()
+}
+
+fn handle_connection(stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let response = match read_request(&stream) {
+        Ok((method, path, body)) => panic::catch_unwind(AssertUnwindSafe(|| route(&method, &path, &body)))
+            .unwrap_or_else(|_| Response::new(500, json!({ "error": "Internal error: the engine panicked." }))),
+        Err(response) => response,
+    };
+    if let Err(e) = write_response(&stream, &response) {
+        warn!("Failed to send a response: {}", e);
+    }
+}
+
+/// Serves requests from `listener` until the process ends, each connection on its own thread.
+pub fn serve(listener: TcpListener) -> std::io::Result<()> {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                std::thread::spawn(move || handle_connection(stream));
+            }
+            Err(e) => warn!("Failed to accept a connection: {}", e),
+        }
+    }
+    Ok(())
+}
+//*** END FILE: src/server.rs ***//
@@ -0,0 +1,156 @@
+//*** START FILE: src/session.rs ***//
+use crate::config::Config;
+use crate::simulation::core_algo::{CtMetricKind, L4Strategy};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::error::WeaveLangError;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// <content_project_dir>/.cache/session.json
+pub fn session_file_path(project_config: &Config) -> PathBuf {
+    Path::new(&project_config.content_project_dir).join(".cache").join("session.json")
+}
+
+/// <content_project_dir>/.cache/session_profile.json, the profile snapshot written on exit.
+pub fn session_profile_path(project_config: &Config) -> PathBuf {
+    Path::new(&project_config.content_project_dir).join(".cache").join("session_profile.json")
+}
+
+/// Key of a stage file's record: its path relative to the content project, with '/'
+/// separators ("stage/bookA.llm.txt"), or its canonical path if it lies outside the project.
+pub fn stage_file_key(content_project_dir: &Path, stage_file: &Path) -> String {
+    let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let stage_file = canonical(stage_file);
+    match stage_file.strip_prefix(canonical(content_project_dir)) {
+        Ok(relative) => relative.components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => stage_file.to_string_lossy().into_owned(),
+    }
+}
+
+/// Stable 64-bit FNV-1a hash of file contents, hex encoded. Used to notice when a
+/// stage file changed on disk; not meant to be cryptographically strong.
+pub fn content_hash(content: &[u8]) -> String {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+    let mut hash = FNV_OFFSET_BASIS;
+    for byte in content {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    format!("{:016x}", hash)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ValidationOutcome {
+    Clean,
+    Warnings(usize),
+    ParseError,
+}
+
+/// What the GUI remembers about a stage file from the last time it was parsed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StageFileRecord {
+    pub content_hash: String,
+    pub validation: ValidationOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StageFileStatus {
+    New,                            // Never parsed in a previous session
+    Changed,                        // Content differs from the last parse
+    Unchanged(ValidationOutcome),   // Same content as the last parse
+}
+
+impl StageFileStatus {
+    pub fn label(&self) -> String {
+        match self {
+            StageFileStatus::New => "new".to_string(),
+            StageFileStatus::Changed => "changed".to_string(),
+            StageFileStatus::Unchanged(ValidationOutcome::Clean) => "clean".to_string(),
+            StageFileStatus::Unchanged(ValidationOutcome::Warnings(n)) => format!("{} warning(s)", n),
+            StageFileStatus::Unchanged(ValidationOutcome::ParseError) => "parse error".to_string(),
+        }
+    }
+}
+
+/// The GUI's simulation parameters, saved on exit so the next launch starts from them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GuiSimulationSettings {
+    pub sentences_per_block: usize,
+    pub max_simulation_loops: u32,
+    pub max_regen_attempts_per_block: u32,
+    pub target_ct_threshold: f32,
+    pub min_ct_threshold: f32,
+    pub min_sentence_ct: f32,
+    pub max_words_to_activate_per_regen: usize,
+    pub max_new_lemmas_per_100_sentences: Option<f32>,
+    pub min_diglot_confidence: f32,
+    pub decay_half_life_blocks: f32,
+    pub decay_min_retention: f32,
+    pub prefix_level_tags: bool,
+    pub ct_metric: CtMetricKind,
+    pub l4_strategy: L4Strategy,
+    pub l4_match_plurals: bool,
+    pub target_interval_sentences: usize,
+    pub remaining_frequency_weight: f32,
+    #[serde(default)]
+    pub tie_break_seed: Option<u64>,
+    pub sequence_path: String,
+}
+
+/// GUI state persisted between launches.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GuiSession {
+    #[serde(default)]
+    pub stage_files: HashMap<String, StageFileRecord>, // Keyed by stage_file_key
+    #[serde(default)]
+    pub selected_stage_file: Option<String>, // stage_file_key of the selected file
+    #[serde(default)]
+    pub settings: Option<GuiSimulationSettings>,
+    // Profile snapshot (see profile_io) holding the learner profile and dictionary at exit.
+    #[serde(default)]
+    pub profile_snapshot: Option<String>,
+}
+
+impl GuiSession {
+    pub fn stage_file_status(&self, key: &str, current_hash: &str) -> StageFileStatus {
+        match self.stage_files.get(key) {
+            None => StageFileStatus::New,
+            Some(record) if record.content_hash != current_hash => StageFileStatus::Changed,
+            Some(record) => StageFileStatus::Unchanged(record.validation),
+        }
+    }
+
+    pub fn record_stage_file(&mut self, key: &str, content_hash: String, validation: ValidationOutcome) {
+        self.stage_files.insert(key.to_string(), StageFileRecord { content_hash, validation });
+    }
+}
+
+/// Loads the session file, returning an empty session if it does not exist yet.
+pub fn load_session(file_path: &Path) -> Result<GuiSession, WeaveLangError> {
+    if !file_path.exists() {
+        return Ok(GuiSession::default());
+    }
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| WeaveLangError::config(format!("Failed to read session file {:?}: {}", file_path, e)).with_source(e))?;
+    let session = serde_json::from_str(&contents)
+        .map_err(|e| WeaveLangError::config(format!("Failed to parse session file {:?}: {}", file_path, e)).with_source(e))?;
+    Ok(session)
+}
+
+pub fn save_session(session: &GuiSession, file_path: &Path) -> Result<(), WeaveLangError> {
+    if let Some(parent) = file_path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| WeaveLangError::config(format!("Failed to create session directory {:?}: {}", parent, e)).with_source(e))?;
+    }
+    let json = serde_json::to_string_pretty(session)
+        .map_err(|e| WeaveLangError::config(format!("Failed to serialize session: {}", e)).with_source(e))?;
+    fs::write(file_path, json)
+        .map_err(|e| WeaveLangError::config(format!("Failed to write session file {:?}: {}", file_path, e)).with_source(e))?;
+    Ok(())
+}
+//*** END FILE: src/session.rs ***//
@@ -0,0 +1,115 @@
+//*** START FILE: src/simulation/block.rs ***//
+// A block is a run of sentence positions over a chapter and its numerical conversion.
+// core_algo, the scheduler and text_generator read the block's sentences through it
+// instead of through parallel Vec<&ProcessedSentence> / Vec<&NumericalProcessedSentence>
+// lists, so the string and numerical sentence at a position can never come from
+// different indexes. Positions past the end of the chapter wrap around, as in multi-pass
+// runs (OrchestratorParams::passes).
+
+use super::numerical_types::{NumericalChapter, NumericalProcessedSentence};
+use crate::error::WeaveLangError;
+use crate::types::llm_data::{ProcessedChapter, ProcessedSentence};
+use std::ops::Range;
+
+/// Errors if a string chapter and its numerical conversion don't line up sentence for sentence.
+pub fn check_chapter_pair(string_chapter: &ProcessedChapter, numerical_chapter: &NumericalChapter) -> Result<(), WeaveLangError> {
+    if string_chapter.sentences.len() != numerical_chapter.sentences_numerical.len() {
+        return Err(WeaveLangError::simulation(format!(
+            "Mismatch between string ({}) and numerical ({}) sentence counts for {}.",
+            string_chapter.sentences.len(),
+            numerical_chapter.sentences_numerical.len(),
+            string_chapter.source_file_name
+        )));
+    }
+    Ok(())
+}
+
+/// Where the orchestrator ends its blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlockBoundaries {
+    /// Every sentences_per_block sentences, wherever that falls.
+    #[default]
+    Fixed,
+    /// At the paragraph end closest to sentences_per_block, at most `tolerance` sentences
+    /// earlier or later; a fixed-size block where no paragraph ends in that range.
+    Paragraphs { tolerance: usize },
+}
+
+#[derive(Debug, Clone)]
+pub struct Block<'a> {
+    string_chapter: &'a ProcessedChapter,
+    numerical_chapter: &'a NumericalChapter,
+    positions: Range<usize>,
+}
+
+impl<'a> Block<'a> {
+    /// Every sentence of the chapter, once. Errors if the chapters don't pair up.
+    pub fn whole_chapter(string_chapter: &'a ProcessedChapter, numerical_chapter: &'a NumericalChapter) -> Result<Self, WeaveLangError> {
+        check_chapter_pair(string_chapter, numerical_chapter)?;
+        Ok(Self { string_chapter, numerical_chapter, positions: 0..string_chapter.sentences.len() })
+    }
+
+    /// The sentences at `positions` of the same chapters, wrapping around the chapter's end.
+    /// A block of an empty chapter stays empty.
+    pub fn with_positions(&self, positions: Range<usize>) -> Self {
+        let positions = if self.numerical_chapter.sentences_numerical.is_empty() { 0..0 } else { positions };
+        Self { positions, ..self.clone() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn positions(&self) -> Range<usize> {
+        self.positions.clone()
+    }
+
+    /// Whether a paragraph starts at `position`, wrapping around like the block's positions.
+    /// The chapter's first sentence always starts one.
+    pub fn paragraph_starts_at(&self, position: usize) -> bool {
+        let sentences = &self.string_chapter.sentences;
+        sentences.is_empty() || sentences[position % sentences.len()].starts_paragraph() || position.is_multiple_of(sentences.len())
+    }
+
+    /// End position of the block that starts at `start`, never past `stream_end` (the end of
+    /// all passes). On a tie between two paragraph ends the shorter block wins.
+    pub fn block_end(&self, start: usize, sentences_per_block: usize, boundaries: BlockBoundaries, stream_end: usize) -> usize {
+        let fixed_end = std::cmp::min(start + sentences_per_block, stream_end);
+        let BlockBoundaries::Paragraphs { tolerance } = boundaries else {
+            return fixed_end;
+        };
+        let target = start + sentences_per_block;
+        let lowest = std::cmp::max(target.saturating_sub(tolerance), start + 1);
+        let highest = std::cmp::min(target + tolerance, stream_end);
+        (lowest..=highest)
+            .filter(|&end| end == stream_end || self.paragraph_starts_at(end))
+            .min_by_key(|&end| (end.abs_diff(target), end))
+            .unwrap_or(fixed_end)
+    }
+
+    fn chapter_indexes(&self) -> impl ExactSizeIterator<Item = usize> + Clone {
+        let chapter_len = self.numerical_chapter.sentences_numerical.len();
+        self.positions.clone().map(move |position| position % chapter_len)
+    }
+
+    pub fn string_sentences(&self) -> impl ExactSizeIterator<Item = &'a ProcessedSentence> + Clone {
+        let sentences = &self.string_chapter.sentences;
+        self.chapter_indexes().map(move |index| &sentences[index])
+    }
+
+    pub fn numerical_sentences(&self) -> impl ExactSizeIterator<Item = &'a NumericalProcessedSentence> + Clone {
+        let sentences = &self.numerical_chapter.sentences_numerical;
+        self.chapter_indexes().map(move |index| &sentences[index])
+    }
+
+    /// Each position's string sentence with its numerical conversion.
+    pub fn sentences(&self) -> impl ExactSizeIterator<Item = (&'a ProcessedSentence, &'a NumericalProcessedSentence)> + Clone {
+        let (strings, numericals) = (&self.string_chapter.sentences, &self.numerical_chapter.sentences_numerical);
+        self.chapter_indexes().map(move |index| (&strings[index], &numericals[index]))
+    }
+}
+//*** END FILE: src/simulation/block.rs ***//
@@ -1,237 +1,456 @@
 // Current src/simulation/core_algo.rs for context before modification
 
-use super::numerical_types::{
-    NumericalLearnerProfile,
-    NumericalProcessedSentence, 
-};
+use super::numerical_types::NumericalLearnerProfile;
+use super::block::Block;
+use super::level_policy::{blocking_grammar_features, decide_level, level_counts, LevelDecision, LevelPolicy};
+use super::profile_view::{LearnerStates, ProfileDelta, ProfileOverlay, ProfileView};
+use super::scheduler::CorpusFrequency;
+use super::trace::TraceEvent;
+use crate::error::WeaveLangError;
 use crate::profile::LemmaState; 
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use tracing::{debug, debug_span, trace};
+use crate::tokenizer::Tokenizer;
 
 #[derive(Debug, Clone)]
 pub struct SimulationBlockResult {
-    pub profile_state_for_text_generation: NumericalLearnerProfile,
-    pub profile_state_after_block_exposure: NumericalLearnerProfile,
+    pub profile_for_text_generation: ProfileView,
+    pub profile_delta: ProfileDelta, // Applied to the learner's profile by the caller
     pub output_lemma_ids_for_block: Vec<u32>, 
     pub simulation_log_entries: Vec<String>,
-    pub final_ct_for_block: f32,
+    pub final_ct_for_block: f32, // Value of the selected ComprehensibilityMetric
+    pub ct_metric_name: &'static str,
     pub known_lemmas_in_block: usize,
-    pub total_spanish_lemmas_in_block: usize,
+    pub total_target_lemmas_in_block: usize,
+    // Decayed (Known -> Active) lemmas that this block exposes again, ascending.
+    pub resurfaced_lemma_ids: Vec<u32>,
+    // Lemmas New before the block and Active or Known after its exposures, ascending.
+    pub introduced_lemma_ids: Vec<u32>,
+    // The level decision of every sentence, in block order, and how many sentences each level got (L1..L5).
+    pub sentence_levels: Vec<LevelDecision>,
+    pub level_counts: [usize; 5],
+    // The block's regen cycle as structured events, in order (see simulation::trace).
+    pub trace: Vec<TraceEvent>,
 }
 
-// THIS IS THE FUNCTION WE WILL REFINE:
-fn determine_sentence_output_lemma_ids(
-    n_sentence: &NumericalProcessedSentence,
-    profile: &NumericalLearnerProfile,
-) -> Vec<u32> {
-    let mut sentence_output_ids: Vec<u32> = Vec::new();
-    let mut level_determined = false; // This variable helps structure the L1-L5 fallback
-
-    // L1
-    if !n_sentence.adv_s_lemma_ids.is_empty() {
-        if n_sentence.adv_s_lemma_ids.iter().all(|&id| profile.is_lemma_known_or_active(id)) {
-            sentence_output_ids.extend(&n_sentence.adv_s_lemma_ids);
-            level_determined = true;
-        }
+// Diglot entries below this confidence are never substituted. 0.5 keeps plain (Y)/(N) behaviour.
+pub const DEFAULT_MIN_DIGLOT_CONFIDENCE: f32 = 0.5;
+
+// --- Comprehensibility metrics ---
+// The CT that decides whether a block is too easy. Token CT (known tokens / all target
+// tokens) is the default; the others weigh the same exposures differently.
+
+/// Result of a metric over one regen pass. `known`/`total` are in the metric's own unit
+/// (tokens, types or sentences); frequency-weighted CT reports types.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricScore {
+    pub value: f32,
+    pub known: usize,
+    pub total: usize,
+}
+
+pub trait ComprehensibilityMetric {
+    fn name(&self) -> &'static str;
+    /// `sentence_lemma_ids` holds the target lemma IDs each sentence exposes, in block order.
+    fn score(&self, sentence_lemma_ids: &[Vec<u32>], profile: &dyn LearnerStates) -> MetricScore;
+}
+
+fn is_known(profile: &dyn LearnerStates, lemma_id: u32) -> bool {
+    profile.is_lemma_known(lemma_id)
+}
+
+fn ratio(known: usize, total: usize) -> f32 {
+    if total > 0 { known as f32 / total as f32 } else { 0.0 }
+}
+
+/// Known tokens / all target tokens.
+pub struct TokenCt;
+
+impl ComprehensibilityMetric for TokenCt {
+    fn name(&self) -> &'static str { "token" }
+
+    fn score(&self, sentence_lemma_ids: &[Vec<u32>], profile: &dyn LearnerStates) -> MetricScore {
+        let total = sentence_lemma_ids.iter().map(|ids| ids.len()).sum();
+        let known = sentence_lemma_ids.iter().flatten().filter(|&&id| is_known(profile, id)).count();
+        MetricScore { value: ratio(known, total), known, total }
     }
+}
 
-    // L2
-    if !level_determined && !n_sentence.sim_s_original.trim().is_empty() { // SimS text must exist
-        let mut can_do_l2 = true;
-        // If sim_s_lemmas_numerical is empty, it means all words in SimS are non-trackable or too simple.
-        // L2 is possible if all *trackable* lemmas are K/A. If no trackable lemmas, it's vacuously true for L2.
-        if n_sentence.sim_s_lemmas_numerical.is_empty() && !n_sentence.sim_s_segments_numerical.is_empty() {
-            // This state: segments exist, but no overall lemmas for them based on sim_s_lemmas_numerical.
-            // This could happen if all segments are proper nouns, or SimSL was empty for those segments.
-            // This implies we cannot verify L2 based on lemmas for these segments.
-            can_do_l2 = false; 
-        }
-        
-        if can_do_l2 { // Only proceed if L2 still potentially viable
-            for seg_lemmas_num in &n_sentence.sim_s_lemmas_numerical {
-                // An empty seg_lemmas_num.lemma_ids means that specific segment has no trackable lemmas.
-                // This does not automatically disqualify L2 for the *whole sentence* if other segments are fine.
-                for &lemma_id in &seg_lemmas_num.lemma_ids {
-                    if !profile.is_lemma_known_or_active(lemma_id) {
-                        can_do_l2 = false; 
-                        break; // Break from inner lemma loop
-                    }
-                }
-                if !can_do_l2 { 
-                    break; // Break from outer segment loop
-                }
+/// Known distinct lemmas / all distinct target lemmas; repetitions count once.
+pub struct TypeCt;
+
+impl ComprehensibilityMetric for TypeCt {
+    fn name(&self) -> &'static str { "type" }
+
+    fn score(&self, sentence_lemma_ids: &[Vec<u32>], profile: &dyn LearnerStates) -> MetricScore {
+        let types: HashSet<u32> = sentence_lemma_ids.iter().flatten().copied().collect();
+        let known = types.iter().filter(|&&id| is_known(profile, id)).count();
+        MetricScore { value: ratio(known, types.len()), known, total: types.len() }
+    }
+}
+
+/// Type CT where each lemma weighs ln(1 + corpus count), so not knowing a frequent
+/// word hurts more than not knowing a rare one.
+pub struct FrequencyWeightedCt<'a> {
+    pub corpus_frequency: Cow<'a, CorpusFrequency>,
+}
+
+impl ComprehensibilityMetric for FrequencyWeightedCt<'_> {
+    fn name(&self) -> &'static str { "frequency" }
+
+    fn score(&self, sentence_lemma_ids: &[Vec<u32>], profile: &dyn LearnerStates) -> MetricScore {
+        let types: HashSet<u32> = sentence_lemma_ids.iter().flatten().copied().collect();
+        let weight = |id: u32| (1.0 + self.corpus_frequency.count(id).max(1) as f32).ln();
+        let (mut known_weight, mut total_weight, mut known) = (0.0f32, 0.0f32, 0);
+        for &id in &types {
+            total_weight += weight(id);
+            if is_known(profile, id) {
+                known_weight += weight(id);
+                known += 1;
             }
         }
+        let value = if total_weight > 0.0 { known_weight / total_weight } else { 0.0 };
+        MetricScore { value, known, total: types.len() }
+    }
+}
+
+/// Share of sentences (with target lemmas) whose token coverage reaches `min_coverage`,
+/// 0.95 by default: the "95% of words known" comprehension rule applied per sentence.
+pub struct SentenceCoverage {
+    pub min_coverage: f32,
+}
 
-        if can_do_l2 { // If, after checking all segments, L2 is still viable
-            // Collect all lemma IDs from all sim_s_lemmas_numerical segments
-            for seg_lemmas_num in &n_sentence.sim_s_lemmas_numerical {
-                sentence_output_ids.extend(&seg_lemmas_num.lemma_ids);
+impl ComprehensibilityMetric for SentenceCoverage {
+    fn name(&self) -> &'static str { "sentence" }
+
+    fn score(&self, sentence_lemma_ids: &[Vec<u32>], profile: &dyn LearnerStates) -> MetricScore {
+        let mut known = 0;
+        let mut total = 0;
+        for ids in sentence_lemma_ids.iter().filter(|ids| !ids.is_empty()) {
+            total += 1;
+            let known_tokens = ids.iter().filter(|&&id| is_known(profile, id)).count();
+            if ratio(known_tokens, ids.len()) >= self.min_coverage {
+                known += 1;
             }
-            level_determined = true;
         }
+        MetricScore { value: ratio(known, total), known, total }
     }
-    
-    // L3
-    if !level_determined && !n_sentence.sim_s_segments_numerical.is_empty() {
-        let mut temp_l3_ids = Vec::new();
-        let mut l3_produced_any_spanish = false;
-        let mut l3_possible_to_construct = true;
-        for segment_num_data in &n_sentence.sim_s_segments_numerical {
-            if let Some(seg_lemmas_num) = n_sentence.sim_s_lemmas_numerical.iter()
-                .find(|sl_num| sl_num.segment_id_str == segment_num_data.id_str) {
-                let mut use_sim_s_phrase_for_segment = true;
-                if seg_lemmas_num.lemma_ids.is_empty() { 
-                    // Segment has no trackable lemmas, use SimS part (which is text, contributes 0 IDs here)
-                    use_sim_s_phrase_for_segment = true; 
-                } else {
-                    for &lemma_id in &seg_lemmas_num.lemma_ids {
-                        if !profile.is_lemma_known_or_active(lemma_id) {
-                            use_sim_s_phrase_for_segment = false; 
-                            break;
-                        }
-                    }
-                }
-                if use_sim_s_phrase_for_segment {
-                    temp_l3_ids.extend(&seg_lemmas_num.lemma_ids); 
-                    if !seg_lemmas_num.lemma_ids.is_empty() { 
-                        l3_produced_any_spanish = true; 
-                    }
-                } // Else: SimE part chosen (0 IDs added to temp_l3_ids)
-            } else { 
-                l3_possible_to_construct = false; 
-                // eprintln!("[Core L3 Warn] No SimSL for SimS Segment {} in Sent {}", segment_num_data.id_str, n_sentence.sentence_id_str);
-                break; 
-            }
+}
+
+/// Which ComprehensibilityMetric drives the regen loop; selectable from config and CLI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CtMetricKind {
+    #[default]
+    Token,
+    Type,
+    Frequency,
+    Sentence,
+}
+
+impl CtMetricKind {
+    pub const ALL: [CtMetricKind; 4] = [CtMetricKind::Token, CtMetricKind::Type, CtMetricKind::Frequency, CtMetricKind::Sentence];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CtMetricKind::Token => "token",
+            CtMetricKind::Type => "type",
+            CtMetricKind::Frequency => "frequency",
+            CtMetricKind::Sentence => "sentence",
         }
-        if l3_possible_to_construct && l3_produced_any_spanish {
-            sentence_output_ids = temp_l3_ids; 
-            level_determined = true;
+    }
+
+    /// `corpus_frequency` is only used by the frequency-weighted metric.
+    pub fn build<'a>(&self, corpus_frequency: Cow<'a, CorpusFrequency>) -> Box<dyn ComprehensibilityMetric + 'a> {
+        match self {
+            CtMetricKind::Token => Box::new(TokenCt),
+            CtMetricKind::Type => Box::new(TypeCt),
+            CtMetricKind::Frequency => Box::new(FrequencyWeightedCt { corpus_frequency }),
+            CtMetricKind::Sentence => Box::new(SentenceCoverage { min_coverage: 0.95 }),
         }
     }
+}
 
-    // L4
-    if !level_determined && !n_sentence.diglot_map_numerical.is_empty() {
-        let mut temp_l4_ids = Vec::new();
-        let mut substitutions_made_l4 = false;
-        for seg_map_num in &n_sentence.diglot_map_numerical {
-            // L4 logic: substitute *one* "best" (e.g. lowest exposure active, or just first viable active)
-            // word per original SimE segment/phrase boundary that the diglot map corresponds to.
-            // The current diglot_map_numerical is a Vec<NumericalDiglotSegmentMap>, one per original SimS_Segment.
-            let mut best_candidate_for_this_segment: Option<u32> = None;
-            // For this simplified version, we just find *if* any substitution is possible in this segment.
-            // A more advanced version would pick the "best" one if multiple are available.
-            for entry_num in &seg_map_num.entries {
-                if entry_num.viable && profile.is_lemma_known_or_active(entry_num.spa_lemma_id) {
-                    best_candidate_for_this_segment = Some(entry_num.spa_lemma_id);
-                    substitutions_made_l4 = true; 
-                    break; // Found one viable substitution for this segment, move to next segment
-                }
-            }
-            if let Some(lemma_id_to_add) = best_candidate_for_this_segment {
-                temp_l4_ids.push(lemma_id_to_add);
-            }
+impl std::str::FromStr for CtMetricKind {
+    type Err = WeaveLangError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CtMetricKind::ALL.iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| WeaveLangError::config(format!("Invalid CT metric '{}': expected token, type, frequency or sentence.", s)))
+    }
+}
+
+/// How L4 chooses among the viable Known/Active DIGLOT_MAP entries of a segment. Shared by
+/// core_algo (which lemma is counted) and text_generator (which word is substituted).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum L4Strategy {
+    First, // DIGLOT_MAP order
+    // Active lemmas before Known ones, fewest exposures first: the most learning value.
+    #[default]
+    LowestExposure,
+    HighestFrequency, // Most frequent in the corpus read so far
+}
+
+impl L4Strategy {
+    pub const ALL: [L4Strategy; 3] = [L4Strategy::First, L4Strategy::LowestExposure, L4Strategy::HighestFrequency];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            L4Strategy::First => "first",
+            L4Strategy::LowestExposure => "lowest-exposure",
+            L4Strategy::HighestFrequency => "highest-frequency",
         }
-        if substitutions_made_l4 { // If any substitutions were made across all segments
-            temp_l4_ids.sort_unstable(); // Sort before dedup
-            temp_l4_ids.dedup();         // Deduplicate, as same lemma might be chosen for diff segments
-            sentence_output_ids = temp_l4_ids;
-            // level_determined = true; // Last assignment for this, not strictly needed to set if no L5 follows
+    }
+
+    /// Sort key of a candidate lemma: lower is preferred, and ties keep DIGLOT_MAP order.
+    pub fn candidate_key(&self, lemma_id: u32, profile: &dyn LearnerStates, corpus_frequency: Option<&CorpusFrequency>) -> (bool, u32) {
+        match self {
+            L4Strategy::First => (false, 0),
+            L4Strategy::LowestExposure => (profile.is_lemma_known(lemma_id), profile.exposure_count(lemma_id)),
+            L4Strategy::HighestFrequency => (false, u32::MAX - corpus_frequency.map_or(0, |f| f.count(lemma_id))),
         }
     }
-    sentence_output_ids
 }
+
+impl std::str::FromStr for L4Strategy {
+    type Err = WeaveLangError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        L4Strategy::ALL.iter()
+            .find(|strategy| strategy.name().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| WeaveLangError::config(format!("Invalid L4 strategy '{}': expected first, lowest-exposure or highest-frequency.", s)))
+    }
+}
+
+/// What an L4 substitution may use and how the candidates of a segment are ranked.
+#[derive(Clone, Copy)]
+pub struct L4Settings<'a> {
+    pub min_diglot_confidence: f32,
+    // L4 substitutes into the base-language SimE, so matching uses the base language's rules.
+    pub base_tokenizer: &'a dyn Tokenizer,
+    // Target-language rules used to pluralize forms when a DIGLOT_MAP word appears in SimE
+    // in its plural ("dog" in "the dogs"); None only matches the word as written.
+    pub plural_rules: Option<&'a dyn Tokenizer>,
+    pub strategy: L4Strategy,
+    // Counts for L4Strategy::HighestFrequency; the same ones the scheduler ranks with.
+    pub corpus_frequency: Option<&'a CorpusFrequency>,
+}
+
+// "token 82.0%, type 75.0%, ..." for the finalization log line.
+fn format_metric_report(metrics: &[&dyn ComprehensibilityMetric], sentence_lemma_ids: &[Vec<u32>], profile: &dyn LearnerStates) -> String {
+    metrics.iter()
+        .map(|metric| format!("{} {:.2}%", metric.name(), metric.score(sentence_lemma_ids, profile).value * 100.0))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Decayed lemmas are Active again, so every level may use them; listing the ones a
+// block re-exposes lets front-ends report how the forgetting curve is being countered.
+fn collect_resurfaced_lemma_ids(output_lemma_ids: &[u32], profile: &NumericalLearnerProfile) -> Vec<u32> {
+    let mut resurfaced: Vec<u32> = output_lemma_ids.iter()
+        .copied()
+        .filter(|&id| profile.get_lemma_info(id).is_some_and(|info| info.decayed))
+        .collect();
+    resurfaced.sort_unstable();
+    resurfaced.dedup();
+    resurfaced
+}
+
+// A lemma can only leave New through activation or exposure, and both leave it at least
+// Active, so the delta's lists hold every introduced lemma.
+fn collect_introduced_lemma_ids(delta: &ProfileDelta, profile_before: &NumericalLearnerProfile) -> Vec<u32> {
+    let mut introduced: Vec<u32> = delta.activated_lemma_ids.iter()
+        .chain(&delta.exposed_lemma_ids)
+        .copied()
+        .filter(|&id| !profile_before.is_lemma_known_or_active(id))
+        .collect();
+    introduced.sort_unstable();
+    introduced.dedup();
+    introduced
+}
+
+
 // ... (rest of run_simulation_numerical as it was in the last correct version)
 // Make sure to copy the entire run_simulation_numerical function below this point from your working version.
-// The changes below are only for run_simulation_numerical, assuming determine_sentence_output_lemma_ids is now refined.
+// The changes below are only for run_simulation_numerical, assuming level_policy::decide_level is now refined.
+
+/// Tuning of one block's regen loop.
+pub struct BlockSimulationSettings<'a> {
+    pub max_regeneration_attempts_per_block: u32,
+    pub target_ct_comprehensible_threshold: f32, // Ceiling: above it the block activates words
+    pub min_ct_comprehensible_threshold: f32,    // Floor: below it the block withholds Active words (0 = off)
+    pub max_words_to_activate_per_regen_attempt: usize,
+    pub l4: L4Settings<'a>,
+    pub ct_metric: &'a dyn ComprehensibilityMetric,
+    pub level_policy: &'a LevelPolicy,
+}
 
 pub fn run_simulation_numerical(
-    block_sentences_numerical: &[&NumericalProcessedSentence], 
-    initial_profile_for_block_run: NumericalLearnerProfile,
+    block: &Block,
+    initial_profile_for_block_run: &NumericalLearnerProfile,
     available_new_lemma_ids_for_activation: &[(u32, u32)], 
-    max_regeneration_attempts_per_block: u32,
-    target_ct_comprehensible_threshold: f32,
-    max_words_to_activate_per_regen_attempt: usize,
-) -> Result<SimulationBlockResult, String> {
+    settings: &BlockSimulationSettings,
+) -> Result<SimulationBlockResult, WeaveLangError> {
+    let BlockSimulationSettings {
+        max_regeneration_attempts_per_block,
+        target_ct_comprehensible_threshold,
+        min_ct_comprehensible_threshold,
+        max_words_to_activate_per_regen_attempt,
+        l4,
+        ct_metric,
+        level_policy,
+    } = *settings;
 
     let mut simulation_log_entries: Vec<String> = Vec::new();
     simulation_log_entries.push(format!(
         "Core Algo: Processing block of {} sentences. Max regen attempts: {}. Target CT: {:.2}%. Profile K: {}, A: {}",
-        block_sentences_numerical.len(), max_regeneration_attempts_per_block, target_ct_comprehensible_threshold * 100.0,
+        block.len(), max_regeneration_attempts_per_block, target_ct_comprehensible_threshold * 100.0,
         initial_profile_for_block_run.count_known(), initial_profile_for_block_run.count_active_only()
     ));
 
-    let mut profile_being_refined_for_block = initial_profile_for_block_run.clone();
+    debug!(
+        sentences = block.len(), known = initial_profile_for_block_run.count_known(),
+        active = initial_profile_for_block_run.count_active_only(), candidates = available_new_lemma_ids_for_activation.len(),
+        "Simulating block"
+    );
+    let mut trace = vec![TraceEvent::BlockStart {
+        sentences: block.len(),
+        known: initial_profile_for_block_run.count_known(),
+        active_only: initial_profile_for_block_run.count_active_only(),
+        target_ct: target_ct_comprehensible_threshold,
+        min_ct: min_ct_comprehensible_threshold,
+        candidates: available_new_lemma_ids_for_activation.len(),
+    }];
+
+    // Passes read the initial profile through the states this cycle sets; nothing is
+    // cloned until the block is finalized.
+    let mut profile_being_refined_for_block = ProfileOverlay::new(initial_profile_for_block_run);
+    let mut activated_lemma_ids: Vec<u32> = Vec::new();
+    let mut activated_grammar: Vec<String> = Vec::new();
+    let mut withheld_lemma_ids: Vec<u32> = Vec::new(); // Active lemmas set to New for this block's text only
+    let mut last_withheld_count = 0;
+    let mut de_escalation_exhausted = false; // Withholding more would leave the block without target-language text
+    // The selected metric and the built-ins that need no corpus data are logged at finalization.
+    let mut report_metrics: Vec<&dyn ComprehensibilityMetric> = vec![ct_metric];
+    report_metrics.extend([&TokenCt as &dyn ComprehensibilityMetric, &TypeCt, &SentenceCoverage { min_coverage: 0.95 }]
+        .into_iter()
+        .filter(|m| m.name() != ct_metric.name()));
+    let metric_report = |sentence_lemma_ids: &[Vec<u32>], profile: &dyn LearnerStates| {
+        format_metric_report(&report_metrics, sentence_lemma_ids, profile)
+    };
     
     for regen_attempt in 1..=max_regeneration_attempts_per_block {
+        let _attempt_span = debug_span!("regen_attempt", attempt = regen_attempt).entered();
         simulation_log_entries.push(format!(
             "  Regen Attempt: {}/{}",
             regen_attempt, max_regeneration_attempts_per_block
         ));
 
-        let profile_for_this_pass = profile_being_refined_for_block.clone();
+        let profile_for_this_pass = &profile_being_refined_for_block;
         
-        let mut lemma_ids_for_current_pass: Vec<u32> = Vec::new(); 
-        for n_sentence_ref in block_sentences_numerical.iter() { 
-            let n_sentence = *n_sentence_ref; 
-            let sentence_ids = determine_sentence_output_lemma_ids(&n_sentence, &profile_for_this_pass); 
-            lemma_ids_for_current_pass.extend(sentence_ids);
-        }
+        let sentence_levels_this_pass: Vec<LevelDecision> = block.sentences()
+            .map(|(s_sentence, n_sentence)| decide_level(s_sentence, n_sentence, profile_for_this_pass, &l4, level_policy))
+            .collect();
+        let sentence_lemma_ids_this_pass: Vec<Vec<u32>> = sentence_levels_this_pass.iter().map(|decision| decision.lemma_ids().to_vec()).collect();
+        let lemma_ids_for_current_pass: Vec<u32> = sentence_lemma_ids_this_pass.iter().flatten().copied().collect();
 
         let total_spanish_lemmas_this_pass = lemma_ids_for_current_pass.len();
-        let known_lemmas_this_pass = if total_spanish_lemmas_this_pass > 0 {
-            lemma_ids_for_current_pass.iter()
-                .filter(|&&id| profile_for_this_pass.get_lemma_info(id).map_or(false, |info| info.state == LemmaState::Known))
-                .count()
-        } else {
-            0
-        };
-        let actual_ct_this_pass = if total_spanish_lemmas_this_pass > 0 {
-            known_lemmas_this_pass as f32 / total_spanish_lemmas_this_pass as f32
-        } else { 
-            0.0 
-        };
+        let token_score = TokenCt.score(&sentence_lemma_ids_this_pass, profile_for_this_pass);
+        let known_lemmas_this_pass = token_score.known;
+        let metric_score = ct_metric.score(&sentence_lemma_ids_this_pass, profile_for_this_pass);
+        let actual_ct_this_pass = metric_score.value;
 
         simulation_log_entries.push(format!(
-            "    Pass CT: {:.2}% ({}K / {}Total). Profile for pass: K={}, A={}",
-            actual_ct_this_pass * 100.0, known_lemmas_this_pass, total_spanish_lemmas_this_pass,
+            "    Pass CT ({}): {:.2}% ({}K / {}Total). Profile for pass: K={}, A={}",
+            ct_metric.name(), actual_ct_this_pass * 100.0, metric_score.known, metric_score.total,
             profile_for_this_pass.count_known(), profile_for_this_pass.count_active_only()
         ));
+        debug!(
+            ct_metric = ct_metric.name(), ct = actual_ct_this_pass, known = metric_score.known, total = metric_score.total,
+            "Pass measured"
+        );
+        trace.push(TraceEvent::RegenAttempt {
+            attempt: regen_attempt,
+            ct_metric: ct_metric.name().to_string(),
+            ct: actual_ct_this_pass,
+            known_tokens: metric_score.known,
+            total_tokens: metric_score.total,
+        });
 
         let block_is_too_easy = actual_ct_this_pass >= target_ct_comprehensible_threshold && total_spanish_lemmas_this_pass > 0;
+        let block_is_too_hard = actual_ct_this_pass < min_ct_comprehensible_threshold && total_spanish_lemmas_this_pass > 0 && !de_escalation_exhausted;
         let block_has_no_spanish = total_spanish_lemmas_this_pass == 0;
         let is_final_regen_attempt = regen_attempt == max_regeneration_attempts_per_block;
 
+        if block_has_no_spanish && last_withheld_count > 0 && !is_final_regen_attempt {
+            for lemma_id in withheld_lemma_ids.split_off(withheld_lemma_ids.len() - last_withheld_count) {
+                profile_being_refined_for_block.set_lemma_state(lemma_id, LemmaState::Active);
+            }
+            simulation_log_entries.push(format!("    De-escalation left no Spanish content; restored the last {} withheld lemma(s).", last_withheld_count));
+            debug!(restored = last_withheld_count, "De-escalation left no target-language content; restored withheld lemmas");
+            last_withheld_count = 0;
+            de_escalation_exhausted = true;
+            continue;
+        }
+
         // Refined finalization condition
-        let should_finalize = (!block_is_too_easy && !block_has_no_spanish) || // CT good and has Spanish
+        let should_finalize = (!block_is_too_easy && !block_is_too_hard && !block_has_no_spanish) || // CT within the band and has Spanish
                               is_final_regen_attempt ||                      // Last chance
-                              (block_has_no_spanish && regen_attempt > 1 && available_new_lemma_ids_for_activation.is_empty()); // No Spanish, tried activating, but no new words left to try
+                              (block_has_no_spanish && regen_attempt > 1 && available_new_lemma_ids_for_activation.is_empty()) || // No Spanish, tried activating, but no new words left to try
+                              (!withheld_lemma_ids.is_empty() && !block_is_too_hard); // De-escalated: never activate again
 
+        let finalize_reason: String;
         if should_finalize {
-            let mut message = "    Finalizing block: ".to_string();
-            if is_final_regen_attempt && (block_is_too_easy || (block_has_no_spanish && regen_attempt == 1 && !available_new_lemma_ids_for_activation.is_empty())) {
-                 message.push_str("Max regen attempts reached (or was too easy/no_spanish on last try).");
+            finalize_reason = if block_is_too_hard {
+                 format!("CT {:.2}% still below the {:.2}% floor on the final attempt.", actual_ct_this_pass * 100.0, min_ct_comprehensible_threshold * 100.0)
+            } else if is_final_regen_attempt && (block_is_too_easy || (block_has_no_spanish && regen_attempt == 1 && !available_new_lemma_ids_for_activation.is_empty())) {
+                 "Max regen attempts reached (or was too easy/no_spanish on last try).".to_string()
             } else if !block_has_no_spanish {
-                 message.push_str(&format!("CT {:.2}% acceptable or final attempt with Spanish.", actual_ct_this_pass * 100.0));
+                 format!("CT {:.2}% acceptable or final attempt with Spanish.", actual_ct_this_pass * 100.0)
             } else if block_has_no_spanish && available_new_lemma_ids_for_activation.is_empty() {
-                 message.push_str("No Spanish content and no new words left to activate.");
+                 "No Spanish content and no new words left to activate.".to_string()
             } else { // Default finalization message if other specific conditions weren't met for logging
-                 message.push_str("Conditions met for finalization.");
+                 "Conditions met for finalization.".to_string()
+            };
+            simulation_log_entries.push(format!("    Finalizing block: {}", finalize_reason));
+            debug!(reason = %finalize_reason, "Finalizing block");
+        } else if block_is_too_hard { // De-escalation needed
+            simulation_log_entries.push(format!(
+                "    De-escalation Triggered: CT {:.2}% is below the {:.2}% floor.",
+                actual_ct_this_pass * 100.0, min_ct_comprehensible_threshold * 100.0
+            ));
+            debug!(ct = actual_ct_this_pass, floor = min_ct_comprehensible_threshold, "De-escalating: CT below the floor");
+
+            // Withholding an Active lemma for this block drops the sentences that need it to a
+            // lower level (or an L4 without that substitution); the least exposed go first.
+            let mut withhold_candidates: Vec<(u32, u32)> = lemma_ids_for_current_pass.iter()
+                .filter(|&&lemma_id| profile_being_refined_for_block.lemma_state(lemma_id) == LemmaState::Active)
+                .map(|&lemma_id| (profile_being_refined_for_block.exposure_count(lemma_id), lemma_id))
+                .collect();
+            withhold_candidates.sort_unstable();
+            withhold_candidates.dedup();
+            last_withheld_count = 0;
+            for (exposure_count, lemma_id) in withhold_candidates.into_iter().take(max_words_to_activate_per_regen_attempt) {
+                profile_being_refined_for_block.set_lemma_state(lemma_id, LemmaState::New);
+                withheld_lemma_ids.push(lemma_id);
+                simulation_log_entries.push(format!("      Withheld Lemma ID: {} (Exposures: {}) for this block.", lemma_id, exposure_count));
+                trace!(lemma_id, exposures = exposure_count, "Withheld lemma");
+                trace.push(TraceEvent::Withhold { attempt: regen_attempt, lemma_id, exposures: exposure_count });
+                last_withheld_count += 1;
             }
-            simulation_log_entries.push(message);
-            
-            let final_profile_state_for_text_generation_val = profile_for_this_pass; 
-            
-            let mut profile_after_exposure = final_profile_state_for_text_generation_val.clone();
-            profile_after_exposure.record_exposures(&lemma_ids_for_current_pass); 
-            
-            return Ok(SimulationBlockResult {
-                profile_state_for_text_generation: final_profile_state_for_text_generation_val, 
-                profile_state_after_block_exposure: profile_after_exposure,
-                output_lemma_ids_for_block: lemma_ids_for_current_pass, 
-                simulation_log_entries,
-                final_ct_for_block: actual_ct_this_pass,
-                known_lemmas_in_block: known_lemmas_this_pass,
-                total_spanish_lemmas_in_block: total_spanish_lemmas_this_pass,
-            });
+
+            if last_withheld_count > 0 {
+                continue;
+            }
+            finalize_reason = "No Active lemmas left to withhold in this block's output.".to_string();
+            simulation_log_entries.push(format!("    {} Finalizing block.", finalize_reason));
+            debug!(reason = %finalize_reason, "Finalizing block");
         } else { // Activation needed
             let mut activation_needed_message = "    Activation Triggered: ".to_string();
             if block_has_no_spanish { 
@@ -240,41 +459,111 @@ pub fn run_simulation_numerical(
                  activation_needed_message.push_str(&format!("CT {:.2}% is too easy.", actual_ct_this_pass * 100.0));
             }
             simulation_log_entries.push(activation_needed_message);
+            debug!(ct = actual_ct_this_pass, no_target_text = block_has_no_spanish, "Activating new lemmas");
 
             let mut words_activated_count = 0;
             // Ensure we only try to activate from the *provided list* of available new words for *this block's context*
             for (lemma_id, freq) in available_new_lemma_ids_for_activation.iter() {
                 // The list available_new_lemma_ids_for_activation should already contain only 'New' words.
                 // We just need to check if it's already been activated *in this current refinement cycle for the block*.
-                if profile_being_refined_for_block.get_lemma_info(*lemma_id).map_or(true, |info| info.state == LemmaState::New) {
+                if profile_being_refined_for_block.lemma_state(*lemma_id) == LemmaState::New {
                     profile_being_refined_for_block.set_lemma_state(*lemma_id, LemmaState::Active);
+                    activated_lemma_ids.push(*lemma_id);
                     simulation_log_entries.push(format!("      Activated Lemma ID: {} (SourceFreq: {}) to Active.", lemma_id, freq));
+                    trace!(lemma_id, block_frequency = freq, "Activated lemma");
+                    trace.push(TraceEvent::Activation { attempt: regen_attempt, lemma_id: *lemma_id, block_frequency: *freq });
                     words_activated_count += 1;
                     if words_activated_count >= max_words_to_activate_per_regen_attempt { break; }
-                } else if profile_being_refined_for_block.get_lemma_info(*lemma_id).map_or(false, |info| info.state == LemmaState::Active) {
+                } else if profile_being_refined_for_block.lemma_state(*lemma_id) == LemmaState::Active {
                     // Already active (perhaps from a previous regen attempt for this same block), skip.
                 }
             }
 
-            if words_activated_count == 0 {
-                simulation_log_entries.push("    No 'New' words were available from the pre-filtered activation list OR all suitable ones already activated in this block's refinement. Finalizing block.".to_string());
-                
-                let final_profile_state_for_text_generation_val = profile_for_this_pass;
-                let mut profile_after_exposure = final_profile_state_for_text_generation_val.clone();
-                profile_after_exposure.record_exposures(&lemma_ids_for_current_pass);
-
-                return Ok(SimulationBlockResult {
-                    profile_state_for_text_generation: final_profile_state_for_text_generation_val,
-                    profile_state_after_block_exposure: profile_after_exposure,
-                    output_lemma_ids_for_block: lemma_ids_for_current_pass,
-                    simulation_log_entries,
-                    final_ct_for_block: actual_ct_this_pass,
-                    known_lemmas_in_block: known_lemmas_this_pass,
-                    total_spanish_lemmas_in_block: total_spanish_lemmas_this_pass,
-                });
+            // Grammar features count against the same per-attempt limit, after the lemmas.
+            for n_sentence in block.numerical_sentences() {
+                for feature in blocking_grammar_features(n_sentence, &profile_being_refined_for_block, level_policy) {
+                    if words_activated_count >= max_words_to_activate_per_regen_attempt { break; }
+                    profile_being_refined_for_block.set_grammar_state(feature, LemmaState::Active);
+                    activated_grammar.push(feature.to_string());
+                    simulation_log_entries.push(format!("      Activated grammar feature '{}' to Active.", feature));
+                    trace!(feature, "Activated grammar feature");
+                    words_activated_count += 1;
+                }
+            }
+
+            if words_activated_count > 0 {
+                continue;
             }
+            finalize_reason = "No 'New' words were available from the pre-filtered activation list OR all suitable ones already activated in this block's refinement.".to_string();
+            simulation_log_entries.push(format!("    {} Finalizing block.", finalize_reason));
+            debug!(reason = %finalize_reason, "Finalizing block");
+        }
+
+        // Nothing changed since this pass was measured, so its states are what the text renders against.
+        let profile_for_text_generation = ProfileView::new(profile_being_refined_for_block.materialize());
+        simulation_log_entries.push(format!("    Block metrics: {}.", metric_report(&sentence_lemma_ids_this_pass, &profile_for_text_generation)));
+        let resurfaced_lemma_ids = collect_resurfaced_lemma_ids(&lemma_ids_for_current_pass, &profile_for_text_generation);
+        if !resurfaced_lemma_ids.is_empty() {
+            simulation_log_entries.push(format!("    Re-surfaced {} decayed lemma(s).", resurfaced_lemma_ids.len()));
+        }
+        let level_counts = level_counts(&sentence_levels_this_pass);
+        simulation_log_entries.push(format!(
+            "    Sentence levels: L1 {}, L2 {}, L3 {}, L4 {}, L5 {}.",
+            level_counts[0], level_counts[1], level_counts[2], level_counts[3], level_counts[4]
+        ));
+
+        // Withholding only shapes this block's text; the learner keeps those lemmas, and
+        // ones activated and then withheld stay as they were before the block.
+        activated_lemma_ids.retain(|lemma_id| !withheld_lemma_ids.contains(lemma_id));
+        let exposed_grammar = block.numerical_sentences().zip(&sentence_levels_this_pass)
+            .flat_map(|(n_sentence, decision)| match decision {
+                LevelDecision::L1 { .. } => n_sentence.adv_s_grammar.as_slice(),
+                LevelDecision::L2 { .. } => n_sentence.sim_s_grammar.as_slice(),
+                _ => &[],
+            })
+            .cloned()
+            .collect();
+        let profile_delta = ProfileDelta {
+            activated_lemma_ids,
+            activated_grammar,
+            exposed_lemma_ids: lemma_ids_for_current_pass.clone(),
+            exposed_grammar,
+        };
+        let introduced_lemma_ids = collect_introduced_lemma_ids(&profile_delta, initial_profile_for_block_run);
+
+        for (n_sentence, decision) in block.numerical_sentences().zip(&sentence_levels_this_pass) {
+            trace.push(TraceEvent::LevelChoice {
+                sentence_id: n_sentence.sentence_id_str.clone(),
+                level: decision.level(),
+                target_tokens: decision.lemma_ids().len(),
+                known_tokens: decision.lemma_ids().iter().filter(|&&id| is_known(&profile_for_text_generation, id)).count(),
+            });
         }
+        trace.push(TraceEvent::Finalize {
+            attempts: regen_attempt,
+            ct_metric: ct_metric.name().to_string(),
+            ct: actual_ct_this_pass,
+            known_tokens: metric_score.known,
+            total_tokens: metric_score.total,
+            reason: finalize_reason,
+        });
+        
+        return Ok(SimulationBlockResult {
+            profile_for_text_generation,
+            profile_delta,
+            output_lemma_ids_for_block: lemma_ids_for_current_pass, 
+            simulation_log_entries,
+            final_ct_for_block: actual_ct_this_pass,
+            ct_metric_name: ct_metric.name(),
+            known_lemmas_in_block: known_lemmas_this_pass,
+            total_target_lemmas_in_block: total_spanish_lemmas_this_pass,
+            resurfaced_lemma_ids,
+            introduced_lemma_ids,
+            sentence_levels: sentence_levels_this_pass,
+            level_counts,
+            trace,
+        });
     } 
     
-    Err("Core algo loop completed without finalizing a block result (should be unreachable).".to_string())
+    Err(WeaveLangError::simulation("Core algo loop completed without finalizing a block result (should be unreachable)."))
 }
\ No newline at end of file
@@ -1,13 +1,35 @@
 //*** START FILE: src/simulation/dictionary.rs ***//
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::error::WeaveLangError;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
 use crate::types::llm_data::ProcessedChapter; // To populate from a chapter
+use crate::determinism::sorted_map;
+use crate::tokenizer::Tokenizer;
+use crate::unicode_norm::strip_diacritics;
 use serde::{Serialize, Deserialize};
+use tracing::warn;
+
+// Key normalization lives in types so the parser and data types can use it without
+// depending on the simulation; re-exported here for existing callers.
+pub use crate::types::lemma_key::{
+    describe_lemma_key, is_mwe_key, normalize_lemma_key, split_lemma_key, MWE_JOINER, POS_SEPARATOR,
+};
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct GlobalLemmaDictionary {
+    #[serde(serialize_with = "sorted_map")]
     pub str_to_id: HashMap<String, u32>,
     pub id_to_str: Vec<String>, // Index is the u32 ID
     next_id: u32,
+    // Bare lemma -> IDs of every key with that lemma (with or without POS); built on demand.
+    #[serde(skip)]
+    sense_index: OnceLock<HashMap<String, Vec<u32>>>,
+    // Lowercase target-language form -> lemma keys it was seen with ("tengo" -> ["tener"]).
+    // Only filled when form tracking is on; keyed by string so ID remaps leave it alone.
+    #[serde(default)]
+    pub forms: BTreeMap<String, Vec<String>>,
 }
 
 impl GlobalLemmaDictionary {
@@ -16,14 +38,44 @@ impl GlobalLemmaDictionary {
             str_to_id: HashMap::new(),
             id_to_str: Vec::new(),
             next_id: 0, // Start IDs from 0. ID 0 will be the first word encountered.
+            sense_index: OnceLock::new(),
+            forms: BTreeMap::new(),
         }
     }
 
     /// Gets the ID for a lemma string. If the lemma is new, it's added to the
-    /// dictionary and a new ID is assigned.
-    /// Lemma strings are converted to lowercase and trimmed.
+    /// dictionary and a new ID is assigned. Lemma strings are normalized with
+    /// normalize_lemma_key (lowercase, trimmed).
     pub fn get_id_or_insert(&mut self, lemma_str: &str) -> u32 {
-        let cleaned_lemma = lemma_str.trim().to_lowercase();
+        let key = normalize_lemma_key(lemma_str);
+        if let Some(id) = self.str_to_id.get(&key) {
+            return *id;
+        }
+        self.warn_if_partly_tagged(&key);
+        self.insert_key(&key)
+    }
+
+    // A bare "banco" never shares an ID with "banco#noun" or "banco#verb": which sense it
+    // would credit depends on which key the corpus happens to use first. Untagged uses are
+    // tracked on their own ID instead, and the mix is reported once per lemma, when the
+    // lemma first shows up both with and without a tag.
+    fn warn_if_partly_tagged(&self, new_key: &str) {
+        let (lemma, pos) = split_lemma_key(new_key);
+        let senses = self.sense_ids(lemma);
+        let tagged_senses = senses.iter().filter(|&&id| self.get_str(id).is_some_and(|key| key != lemma)).count();
+        let first_mix = match pos {
+            Some(_) => tagged_senses == 0 && senses.len() == 1, // Only the bare key so far
+            None => tagged_senses > 0,
+        };
+        if first_mix {
+            warn!("Lemma '{}' is used both with and without a POS tag; untagged uses are tracked separately from its tagged senses.", lemma);
+        }
+    }
+
+    /// Gets the ID for a lemma key, giving it a new ID if it has none. For rebuilding a
+    /// dictionary from its own id_to_str.
+    pub fn insert_key(&mut self, lemma_str: &str) -> u32 {
+        let cleaned_lemma = normalize_lemma_key(lemma_str);
         // Avoid adding empty strings to the dictionary if they somehow appear.
         // The simulation logic should ideally not process empty lemma strings.
         if cleaned_lemma.is_empty() {
@@ -43,16 +95,36 @@ impl GlobalLemmaDictionary {
         } else {
             let id = self.next_id;
             self.str_to_id.insert(cleaned_lemma.clone(), id);
+            if let Some(index) = self.sense_index.get_mut() {
+                index.entry(split_lemma_key(&cleaned_lemma).0.to_string()).or_default().push(id);
+            }
             self.id_to_str.push(cleaned_lemma); // Store the cleaned (lowercase, trimmed) version
             self.next_id += 1;
             id
         }
     }
 
+    /// Makes `key` another name for `lemma_id`. Keys that already have an ID are left alone.
+    pub fn add_alias(&mut self, key: &str, lemma_id: u32) {
+        let key = normalize_lemma_key(key);
+        if key.is_empty() || self.str_to_id.contains_key(&key) || lemma_id >= self.next_id {
+            return;
+        }
+        self.str_to_id.insert(key, lemma_id);
+    }
+
+    /// Keys that resolve to another key's ID (see add_alias), sorted by key.
+    pub fn aliases(&self) -> BTreeMap<String, u32> {
+        self.str_to_id.iter()
+            .filter(|(key, id)| self.get_str(**id) != Some(*key))
+            .map(|(key, id)| (key.clone(), *id))
+            .collect()
+    }
+
     /// Gets the ID for a lemma string if it exists. Returns None otherwise.
     /// This method does not add new lemmas.
     pub fn get_id(&self, lemma_str: &str) -> Option<u32> {
-        let cleaned_lemma = lemma_str.trim().to_lowercase();
+        let cleaned_lemma = normalize_lemma_key(lemma_str);
         if cleaned_lemma.is_empty() {
             return None;
         }
@@ -65,6 +137,27 @@ impl GlobalLemmaDictionary {
         self.id_to_str.get(lemma_id as usize)
     }
 
+    /// IDs of every key whose lemma is `bare_lemma`, whatever its POS tag, in ID order.
+    pub fn sense_ids(&self, bare_lemma: &str) -> &[u32] {
+        let index = self.sense_index.get_or_init(|| {
+            let mut index: HashMap<String, Vec<u32>> = HashMap::new();
+            for (id, key) in self.id_to_str.iter().enumerate() {
+                index.entry(split_lemma_key(key).0.to_string()).or_default().push(id as u32);
+            }
+            index
+        });
+        index.get(&normalize_lemma_key(split_lemma_key(bare_lemma).0)).map_or(&[], |ids| ids.as_slice())
+    }
+
+    /// ID for a surface lemma without POS: the bare key if present, otherwise the only
+    /// POS-tagged sense. None when the lemma is unknown or ambiguous.
+    pub fn get_id_unambiguous(&self, bare_lemma: &str) -> Option<u32> {
+        self.get_id(bare_lemma).or_else(|| match self.sense_ids(bare_lemma) {
+            [only] => Some(*only),
+            _ => None,
+        })
+    }
+
     /// Returns the total number of unique lemmas in the dictionary.
     pub fn size(&self) -> usize {
         self.id_to_str.len()
@@ -94,5 +187,329 @@ impl GlobalLemmaDictionary {
             }
         }
     }
+
+    /// Records that `form` is an inflection of `lemma_str`. Returns false if the pair was
+    /// already known or either side is empty.
+    pub fn add_form(&mut self, form: &str, lemma_str: &str) -> bool {
+        let form = form.trim().to_lowercase();
+        let key = normalize_lemma_key(lemma_str);
+        if form.is_empty() || key.is_empty() {
+            return false;
+        }
+        let lemmas = self.forms.entry(form).or_default();
+        if lemmas.contains(&key) {
+            return false;
+        }
+        lemmas.push(key);
+        true
+    }
+
+    /// Lemma keys a lowercase form was recorded with; empty if it is not tracked.
+    pub fn lemmas_for_form(&self, form: &str) -> &[String] {
+        self.forms.get(form).map_or(&[], |lemmas| lemmas.as_slice())
+    }
+
+    pub fn form_count(&self) -> usize {
+        self.forms.len()
+    }
+
+    /// Records the single-word forms a chapter pairs with lemmas: DIGLOT_MAP exact forms and
+    /// SimSL lines whose word count matches their segment (tokenized with `target_tokenizer`).
+    /// Returns the number of new form->lemma pairs.
+    pub fn learn_forms_from_chapter(&mut self, chapter_data: &ProcessedChapter, target_tokenizer: &dyn Tokenizer) -> usize {
+        let mut added = 0;
+        for sentence in &chapter_data.sentences {
+            for entry in sentence.diglot_map.iter().flat_map(|dm| &dm.entries) {
+                if !entry.exact_spa_form.trim().contains(' ') && self.add_form(&entry.exact_spa_form, &entry.spa_lemma) {
+                    added += 1;
+                }
+            }
+            for segment in &sentence.sim_s_segments {
+                let Some(sl) = sentence.sim_s_lemmas.iter().find(|sl| sl.segment_id == segment.id) else { continue };
+                let forms = target_tokenizer.tokenize(&segment.text);
+                if forms.len() == sl.lemmas.len() {
+                    for (form, lemma) in forms.iter().zip(&sl.lemmas) {
+                        if self.add_form(form.text, lemma) {
+                            added += 1;
+                        }
+                    }
+                }
+            }
+        }
+        added
+    }
+
+    /// Adds the form->lemma pairs of `other` that this dictionary lacks.
+    pub fn merge_forms_from(&mut self, other: &GlobalLemmaDictionary) {
+        for (form, lemmas) in &other.forms {
+            for lemma in lemmas {
+                self.add_form(form, lemma);
+            }
+        }
+    }
+
+    /// Appends a key exactly as an older snapshot wrote it, so the next ID matches the file's
+    /// numbering even if the key no longer normalizes to something new. Follow with renormalize_keys.
+    pub fn push_legacy_key(&mut self, key: &str) {
+        self.id_to_str.push(key.to_string());
+        self.next_id += 1;
+        self.sense_index = OnceLock::new();
+    }
+
+    /// Re-keys every entry (and the form map) with the current normalize_lemma_key, keeping IDs
+    /// in place. Returns (id, kept_id) for each entry whose new key a lower ID already took;
+    /// such an entry keeps its old spelling and can no longer be looked up by key.
+    pub fn renormalize_keys(&mut self) -> Vec<(u32, u32)> {
+        let mut collisions = Vec::new();
+        let aliases = self.aliases();
+        self.str_to_id.clear();
+        for (id, key) in self.id_to_str.iter_mut().enumerate() {
+            let normalized = normalize_lemma_key(key);
+            match self.str_to_id.get(&normalized) {
+                Some(&kept_id) => collisions.push((id as u32, kept_id)),
+                None => {
+                    self.str_to_id.insert(normalized.clone(), id as u32);
+                    *key = normalized;
+                }
+            }
+        }
+        for lemmas in self.forms.values_mut() {
+            let mut renormalized: Vec<String> = Vec::with_capacity(lemmas.len());
+            for lemma in lemmas.iter().map(|lemma| normalize_lemma_key(lemma)) {
+                if !renormalized.contains(&lemma) {
+                    renormalized.push(lemma);
+                }
+            }
+            *lemmas = renormalized;
+        }
+        self.sense_index = OnceLock::new();
+        for (key, lemma_id) in aliases {
+            self.add_alias(&key, lemma_id);
+        }
+        collisions
+    }
+
+    /// The id<->lemma mapping as TSV: an "id\tlemma" header, then one row per lemma in ID order,
+    /// then one row per alias (a key sharing an earlier row's ID).
+    pub fn to_tsv(&self) -> String {
+        let mut tsv = String::from("id\tlemma\n");
+        for (id, lemma) in self.id_to_str.iter().enumerate() {
+            tsv.push_str(&format!("{}\t{}\n", id, lemma));
+        }
+        for (alias, id) in self.aliases() {
+            tsv.push_str(&format!("{}\t{}\n", id, alias));
+        }
+        tsv
+    }
+
+    pub fn export_tsv(&self, file_path: &Path) -> Result<(), WeaveLangError> {
+        fs::write(file_path, self.to_tsv())
+            .map_err(|e| WeaveLangError::profile_io(format!("Failed to write dictionary TSV {:?}: {}", file_path, e)).with_source(e))?;
+        Ok(())
+    }
+
+    /// Reads a dictionary from TSV. Rows are either "id<TAB>lemma", as written by export_tsv,
+    /// or a bare lemma, which gets the next free ID, so a curated lemma list works as-is.
+    /// Explicit IDs must run 0, 1, 2, ... in file order so the mapping round-trips unchanged;
+    /// a row repeating an earlier ID makes its lemma an alias of that ID.
+    /// Blank lines, '#' comments and the header row are skipped.
+    pub fn import_tsv(file_path: &Path) -> Result<Self, WeaveLangError> {
+        let contents = fs::read_to_string(file_path)
+            .map_err(|e| WeaveLangError::profile_io(format!("Failed to read dictionary TSV {:?}: {}", file_path, e)).with_source(e))?;
+        let mut dictionary = GlobalLemmaDictionary::new();
+        for (line_idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.eq_ignore_ascii_case("id\tlemma") {
+                continue;
+            }
+            let (expected_id, lemma) = match line.split_once('\t') {
+                Some((id, lemma)) => {
+                    let id: u32 = id.trim().parse()
+                        .map_err(|_| WeaveLangError::profile_io(format!("Invalid lemma ID '{}' at {:?} line {}.", id.trim(), file_path, line_idx + 1)))?;
+                    (Some(id), lemma)
+                }
+                None => (None, line),
+            };
+            let key = normalize_lemma_key(lemma);
+            if key.is_empty() {
+                return Err(WeaveLangError::profile_io(format!("Empty lemma at {:?} line {}.", file_path, line_idx + 1)));
+            }
+            if dictionary.str_to_id.contains_key(&key) {
+                return Err(WeaveLangError::profile_io(format!("Duplicate lemma '{}' at {:?} line {}.", key, file_path, line_idx + 1)));
+            }
+            match expected_id {
+                Some(alias_of) if alias_of < dictionary.next_id => dictionary.add_alias(&key, alias_of),
+                Some(expected_id) if expected_id != dictionary.next_id => {
+                    return Err(WeaveLangError::profile_io(format!(
+                        "Lemma ID {} at {:?} line {} is out of sequence (expected {}).",
+                        expected_id, file_path, line_idx + 1, dictionary.next_id
+                    )));
+                }
+                _ => { dictionary.insert_key(&key); }
+            }
+        }
+        Ok(dictionary)
+    }
+
+    /// Adds the lemmas of `seed` in its ID order. Lemmas already present keep their IDs,
+    /// so seeding an existing dictionary only appends. Returns the number of lemmas added.
+    pub fn seed_from(&mut self, seed: &GlobalLemmaDictionary) -> usize {
+        let size_before = self.size();
+        for lemma in &seed.id_to_str {
+            self.insert_key(lemma);
+        }
+        for (alias, id) in seed.aliases() {
+            if let Some(id) = seed.get_str(id).and_then(|lemma| self.get_id(lemma)) {
+                self.add_alias(&alias, id);
+            }
+        }
+        self.size() - size_before
+    }
+}
+/// Lowercase letters with accents and tildes removed ("Nación" -> "nacion").
+pub fn fold_accents(word: &str) -> Vec<char> {
+    strip_diacritics(&word.to_lowercase()).chars().collect()
+}
+
+/// Levenshtein distance (insertions, deletions, substitutions) between two words.
+pub fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Shortest lemma compared by edit distance; short words are too often legitimately close
+// ("pez"/"paz"). Accent-only differences are reported at any length.
+const FUZZY_DUPLICATE_MIN_LENGTH: usize = 5;
+
+// Union-find root with path halving.
+fn cluster_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Groups lemmas that look like annotation variants of each other: keys with the same POS
+/// tag whose lemmas are equal once accents are folded ("él"/"el", "mas"/"más"), or, for
+/// lemmas of FUZZY_DUPLICATE_MIN_LENGTH letters or more that start with the same letter,
+/// at most `max_distance` edits apart ("caminar"/"camminar"). Clusters hold IDs in
+/// ascending order and are sorted by their first ID. Some hits are real words ("el"/"él"),
+/// so review before merging.
+pub fn find_near_duplicates(dictionary: &GlobalLemmaDictionary, max_distance: usize) -> Vec<Vec<u32>> {
+    let folded: Vec<(Vec<char>, Option<&str>)> = dictionary.id_to_str.iter()
+        .map(|key| {
+            let (lemma, pos) = split_lemma_key(key);
+            (fold_accents(lemma), pos)
+        })
+        .collect();
+    // Only lemmas sharing a POS tag and first letter are compared.
+    let mut buckets: HashMap<(Option<&str>, Option<char>), Vec<usize>> = HashMap::new();
+    for (i, (chars, pos)) in folded.iter().enumerate() {
+        buckets.entry((*pos, chars.first().copied())).or_default().push(i);
+    }
+    let mut parents: Vec<usize> = (0..folded.len()).collect();
+    for members in buckets.values() {
+        for (n, &i) in members.iter().enumerate() {
+            for &j in &members[n + 1..] {
+                let (a, b) = (&folded[i].0, &folded[j].0);
+                let similar = a == b || (a.len().min(b.len()) >= FUZZY_DUPLICATE_MIN_LENGTH
+                    && a.len().abs_diff(b.len()) <= max_distance
+                    && edit_distance(a, b) <= max_distance);
+                if similar {
+                    let (root_i, root_j) = (cluster_root(&mut parents, i), cluster_root(&mut parents, j));
+                    parents[root_i.max(root_j)] = root_i.min(root_j);
+                }
+            }
+        }
+    }
+    let mut clusters: BTreeMap<usize, Vec<u32>> = BTreeMap::new();
+    for i in 0..folded.len() {
+        let root = cluster_root(&mut parents, i);
+        clusters.entry(root).or_default().push(i as u32);
+    }
+    clusters.into_values().filter(|ids| ids.len() > 1).collect()
+}
+
+/// Builds a compacted dictionary in which every cluster (from find_near_duplicates) is
+/// folded into its first, lowest-ID lemma. `id_remap` sends each merged-away ID to the
+/// kept one; `removed_lemmas` lists the merged-away lemmas. Form entries follow the merge.
+pub fn merge_lemma_clusters(dictionary: &GlobalLemmaDictionary, clusters: &[Vec<u32>]) -> DictionaryGc {
+    let canonical: HashMap<u32, u32> = clusters.iter()
+        .flat_map(|ids| ids.iter().map(move |&id| (id, ids[0])))
+        .collect();
+    let mut compacted = GlobalLemmaDictionary::new();
+    let mut id_remap: HashMap<u32, u32> = HashMap::new();
+    let mut removed_lemmas: Vec<String> = Vec::new();
+    for (old_id, lemma) in dictionary.id_to_str.iter().enumerate() {
+        let old_id = old_id as u32;
+        match canonical.get(&old_id) {
+            Some(&kept_id) if kept_id != old_id => removed_lemmas.push(lemma.clone()),
+            _ => { id_remap.insert(old_id, compacted.insert_key(lemma)); }
+        }
+    }
+    for (&old_id, &kept_id) in &canonical {
+        id_remap.insert(old_id, id_remap[&kept_id]);
+    }
+    for (alias, old_id) in dictionary.aliases() {
+        compacted.add_alias(&alias, id_remap[&old_id]);
+    }
+    for (form, lemmas) in &dictionary.forms {
+        for lemma in lemmas {
+            let kept = dictionary.get_id(lemma)
+                .and_then(|id| compacted.get_str(id_remap[&id]).cloned());
+            if let Some(kept) = kept {
+                compacted.add_form(form, &kept);
+            }
+        }
+    }
+    DictionaryGc { dictionary: compacted, id_remap, removed_lemmas }
+}
+
+/// Result of compacting a dictionary down to the lemma IDs still in use.
+#[derive(Debug, Clone)]
+pub struct DictionaryGc {
+    pub dictionary: GlobalLemmaDictionary,
+    pub id_remap: HashMap<u32, u32>, // old ID -> new ID, only for kept lemmas
+    pub removed_lemmas: Vec<String>,
+}
+
+/// Builds a compacted dictionary containing only `referenced_ids`, preserving their
+/// relative order, and returns the old->new ID mapping to apply to linked profiles.
+pub fn gc(dictionary: &GlobalLemmaDictionary, referenced_ids: &HashSet<u32>) -> DictionaryGc {
+    let mut compacted = GlobalLemmaDictionary::new();
+    let mut id_remap: HashMap<u32, u32> = HashMap::new();
+    let mut removed_lemmas: Vec<String> = Vec::new();
+
+    for (old_id, lemma) in dictionary.id_to_str.iter().enumerate() {
+        let old_id = old_id as u32;
+        if referenced_ids.contains(&old_id) {
+            let new_id = compacted.insert_key(lemma);
+            id_remap.insert(old_id, new_id);
+        } else {
+            removed_lemmas.push(lemma.clone());
+        }
+    }
+    for (alias, old_id) in dictionary.aliases() {
+        if let Some(&new_id) = id_remap.get(&old_id) {
+            compacted.add_alias(&alias, new_id);
+        }
+    }
+    for (form, lemmas) in &dictionary.forms {
+        let kept: Vec<String> = lemmas.iter().filter(|lemma| compacted.get_id(lemma).is_some()).cloned().collect();
+        if !kept.is_empty() {
+            compacted.forms.insert(form.clone(), kept);
+        }
+    }
+
+    DictionaryGc { dictionary: compacted, id_remap, removed_lemmas }
 }
 //*** END FILE: src/simulation/dictionary.rs ***//
\ No newline at end of file
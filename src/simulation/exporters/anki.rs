@@ -0,0 +1,97 @@
+//*** START FILE: src/simulation/exporters/anki.rs ***//
+// Review decks of the lemmas a book instance activated (New -> Active), written as
+// Anki-importable TSV: one note per lemma with its gloss and the first sentence it
+// was activated in, in both languages. Lemmas no DIGLOT_MAP glosses can take their
+// gloss from the offline lexicon (lexicon_dump_path).
+
+use crate::profile::LemmaState;
+use crate::lexicon::Lexicon;
+use crate::simulation::dictionary::{describe_lemma_key, GlobalLemmaDictionary};
+use crate::simulation::profile_view::LemmaTransition;
+use crate::types::llm_data::ProcessedSentence;
+use crate::error::WeaveLangError;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnkiCard {
+    pub lemma: String, // Display form: "banco (noun)" for POS-tagged keys
+    pub gloss: Option<String>,
+    pub context_target: String, // Target-language sentence (SimS, or AdvS without one)
+    pub context_base: String,   // Its base-language SimE
+    pub sentence_id: String,
+}
+
+/// Lemmas a block took from New to Active or Known, by ascending ID.
+pub fn newly_activated_lemma_ids(transitions: &[LemmaTransition]) -> Vec<u32> {
+    transitions.iter()
+        .filter(|transition| transition.before == LemmaState::New && transition.after != LemmaState::New)
+        .map(|transition| transition.lemma_id)
+        .collect()
+}
+
+fn sentence_mentions_lemma(sentence: &ProcessedSentence, lemma_id: u32, dictionary: &GlobalLemmaDictionary) -> bool {
+    let is_lemma = |lemma_str: &String| dictionary.get_id(lemma_str) == Some(lemma_id);
+    sentence.adv_s_lemmas.iter().any(is_lemma)
+        || sentence.sim_s_lemmas.iter().flat_map(|seg| &seg.lemmas).any(is_lemma)
+        || sentence.diglot_map.iter().flat_map(|dm| &dm.entries).any(|entry| is_lemma(&entry.spa_lemma))
+}
+
+// English word the DIGLOT_MAP pairs with the lemma, if any.
+fn diglot_gloss(sentence: &ProcessedSentence, lemma_id: u32, dictionary: &GlobalLemmaDictionary) -> Option<String> {
+    sentence.diglot_map.iter()
+        .flat_map(|dm| &dm.entries)
+        .find(|entry| dictionary.get_id(&entry.spa_lemma) == Some(lemma_id) && !entry.eng_word.trim().is_empty())
+        .map(|entry| entry.eng_word.trim().to_string())
+}
+
+/// One card for `lemma_id`, using the first of `sentences` that mentions it as context.
+/// The gloss may come from any of the sentences, else from `lexicon`. None if no sentence
+/// mentions the lemma.
+pub fn build_card(
+    lemma_id: u32,
+    sentences: &[&ProcessedSentence],
+    dictionary: &GlobalLemmaDictionary,
+    lexicon: Option<&Lexicon>,
+) -> Option<AnkiCard> {
+    let lemma = dictionary.get_str(lemma_id)?;
+    let context = sentences.iter().find(|s| sentence_mentions_lemma(s, lemma_id, dictionary))?;
+    let gloss = diglot_gloss(context, lemma_id, dictionary)
+        .or_else(|| sentences.iter().find_map(|s| diglot_gloss(s, lemma_id, dictionary)))
+        .or_else(|| lexicon.and_then(|lexicon| lexicon.short_gloss(lemma)));
+    let context_target = if context.sim_s.trim().is_empty() { &context.adv_s } else { &context.sim_s };
+    Some(AnkiCard {
+        lemma: describe_lemma_key(lemma),
+        gloss,
+        context_target: context_target.trim().to_string(),
+        context_base: context.sim_e.trim().to_string(),
+        sentence_id: context.sentence_id.clone(),
+    })
+}
+
+// Tabs and line breaks would split the note.
+fn tsv_field(text: &str) -> String {
+    text.replace(['\t', '\r', '\n'], " ")
+}
+
+/// Writes the cards as TSV with Anki's import header. `tag` (e.g. the book instance ID)
+/// is added to every note; spaces in it are replaced since Anki tags are space-separated.
+pub fn write_tsv_deck(file_path: &Path, cards: &[AnkiCard], tag: &str) -> Result<(), WeaveLangError> {
+    let tag = tag.trim().replace(' ', "_");
+    let mut tsv = String::from("#separator:tab\n#html:false\n#columns:Lemma\tGloss\tSentence\tTranslation\tSentenceID\tTags\n#tags column:6\n");
+    for card in cards {
+        tsv.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\n",
+            tsv_field(&card.lemma),
+            tsv_field(card.gloss.as_deref().unwrap_or("")),
+            tsv_field(&card.context_target),
+            tsv_field(&card.context_base),
+            tsv_field(&card.sentence_id),
+            tag,
+        ));
+    }
+    fs::write(file_path, tsv)
+        .map_err(|e| WeaveLangError::generation(format!("Failed to write Anki deck to {:?}: {}", file_path, e)).with_source(e))?;
+    Ok(())
+}
+//*** END FILE: src/simulation/exporters/anki.rs ***//
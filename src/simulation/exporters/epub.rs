@@ -0,0 +1,203 @@
+//*** START FILE: src/simulation/exporters/epub.rs ***//
+// Packages generated book instances as EPUB 3 files. Chapter bodies are the block
+// fragments from exporters::html, so e-readers get the same gloss spans and styling.
+
+use super::html::{escape_html, STYLESHEET};
+use crate::config::BookMetadata;
+use crate::error::{BoxedError, WeaveLangError};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// How generated blocks are split into EPUB chapters.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EpubChapterMode {
+    /// One chapter per block.
+    Block,
+    /// One chapter per pass through the source chapter.
+    #[default]
+    Source,
+}
+
+impl std::str::FromStr for EpubChapterMode {
+    type Err = WeaveLangError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "block" => Ok(EpubChapterMode::Block),
+            "source" | "chapter" => Ok(EpubChapterMode::Source),
+            _ => Err(WeaveLangError::config(format!("Invalid EPUB chapter mode '{}': expected 'block' or 'source'.", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EpubChapter {
+    pub title: String,
+    pub body_html: String, // Concatenated block fragments from html::render_block_html
+}
+
+/// Book-level fields of the package document.
+#[derive(Debug, Clone)]
+pub struct EpubBook {
+    pub identifier: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub publisher: Option<String>,
+    pub language: String,
+    pub modified: Option<SystemTime>, // dcterms:modified; None = time of writing
+    pub chapters: Vec<EpubChapter>,
+}
+
+impl EpubBook {
+    /// Fills the metadata from the config's `[book_metadata]`, falling back to
+    /// `default_language` when it names no language.
+    pub fn new(book_instance_id: &str, metadata: &BookMetadata, default_language: &str) -> Self {
+        let title = match &metadata.series_title {
+            Some(series_title) => format!("{}: {}", series_title, book_instance_id),
+            None => book_instance_id.to_string(),
+        };
+        Self {
+            identifier: format!("urn:weavelang:{}", book_instance_id),
+            title,
+            author: metadata.author.clone(),
+            publisher: metadata.publisher.clone(),
+            language: metadata.language.clone().unwrap_or_else(|| default_language.to_string()),
+            modified: None,
+            chapters: Vec::new(),
+        }
+    }
+}
+
+// Civil date from days since 1970-01-01 (proleptic Gregorian).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// "2024-05-01T12:00:00Z", as required by dcterms:modified.
+fn utc_timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as i64;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let secs_of_day = secs.rem_euclid(86_400);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, secs_of_day / 3600, secs_of_day % 3600 / 60, secs_of_day % 60)
+}
+
+fn chapter_file_name(index: usize) -> String {
+    format!("chapter_{:03}.xhtml", index + 1)
+}
+
+fn chapter_xhtml(chapter: &EpubChapter, language: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xml:lang=\"{lang}\" lang=\"{lang}\">\n\
+         <head>\n<meta charset=\"utf-8\"/>\n<title>{title}</title>\n\
+         <link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n\
+         <body>\n<h1>{title}</h1>\n{body}</body>\n</html>\n",
+        lang = escape_html(language),
+        title = escape_html(&chapter.title),
+        body = chapter.body_html,
+    )
+}
+
+fn nav_xhtml(book: &EpubBook) -> String {
+    let items: String = book.chapters.iter().enumerate()
+        .map(|(i, chapter)| format!("<li><a href=\"{}\">{}</a></li>\n", chapter_file_name(i), escape_html(&chapter.title)))
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\">\n\
+         <head>\n<meta charset=\"utf-8\"/>\n<title>{title}</title>\n</head>\n\
+         <body>\n<nav epub:type=\"toc\" id=\"toc\">\n<h1>{title}</h1>\n<ol>\n{items}</ol>\n</nav>\n</body>\n</html>\n",
+        title = escape_html(&book.title),
+        items = items,
+    )
+}
+
+fn package_opf(book: &EpubBook, modified: &str) -> String {
+    let mut metadata = format!(
+        "<dc:identifier id=\"book-id\">{}</dc:identifier>\n<dc:title>{}</dc:title>\n<dc:language>{}</dc:language>\n",
+        escape_html(&book.identifier), escape_html(&book.title), escape_html(&book.language)
+    );
+    if let Some(author) = &book.author {
+        metadata.push_str(&format!("<dc:creator>{}</dc:creator>\n", escape_html(author)));
+    }
+    if let Some(publisher) = &book.publisher {
+        metadata.push_str(&format!("<dc:publisher>{}</dc:publisher>\n", escape_html(publisher)));
+    }
+    metadata.push_str(&format!("<meta property=\"dcterms:modified\">{}</meta>\n", modified));
+
+    let mut manifest = String::from(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+         <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n",
+    );
+    let mut spine = String::new();
+    for i in 0..book.chapters.len() {
+        manifest.push_str(&format!(
+            "<item id=\"chapter{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n", i + 1, chapter_file_name(i)
+        ));
+        spine.push_str(&format!("<itemref idref=\"chapter{}\"/>\n", i + 1));
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n{}</metadata>\n\
+         <manifest>\n{}</manifest>\n<spine>\n{}</spine>\n</package>\n",
+        metadata, manifest, spine
+    )
+}
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+/// Writes `book` as an EPUB 3 file.
+pub fn write_epub(book: &EpubBook, file_path: &Path) -> Result<(), WeaveLangError> {
+    let file = File::create(file_path)
+        .map_err(|e| WeaveLangError::generation(format!("Failed to create EPUB {:?}: {}", file_path, e)).with_source(e))?;
+    let mut zip = ZipWriter::new(file);
+    // The mimetype entry must come first and be stored uncompressed.
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut entries: Vec<(String, String)> = vec![
+        ("META-INF/container.xml".to_string(), CONTAINER_XML.to_string()),
+        ("OEBPS/content.opf".to_string(), package_opf(book, &utc_timestamp(book.modified.unwrap_or_else(SystemTime::now)))),
+        ("OEBPS/nav.xhtml".to_string(), nav_xhtml(book)),
+        ("OEBPS/style.css".to_string(), STYLESHEET.to_string()),
+    ];
+    for (i, chapter) in book.chapters.iter().enumerate() {
+        entries.push((format!("OEBPS/{}", chapter_file_name(i)), chapter_xhtml(chapter, &book.language)));
+    }
+
+    let write_result: Result<(), BoxedError> = (|| {
+        zip.start_file("mimetype", stored)?;
+        zip.write_all(b"application/epub+zip")?;
+        for (name, contents) in &entries {
+            zip.start_file(name.as_str(), deflated)?;
+            zip.write_all(contents.as_bytes())?;
+        }
+        zip.finish()?;
+        Ok(())
+    })();
+    write_result.map_err(|e| WeaveLangError::generation(format!("Failed to write EPUB {:?}: {}", file_path, e)).with_source(e))?;
+    Ok(())
+}
+//*** END FILE: src/simulation/exporters/epub.rs ***//
@@ -0,0 +1,241 @@
+//*** START FILE: src/simulation/exporters/html.rs ***//
+// Renders woven blocks as reader-friendly HTML. Every target-language word that can be
+// traced to a lemma is wrapped in a span carrying the lemma, its English gloss and the
+// learner's state for it; hovering a word shows the gloss.
+// Lemmas and glosses come from the sentence's DIGLOT_MAP (exact form -> lemma, English word);
+// words missing from it fall back to a dictionary lookup of the lowercased form, without a gloss
+// (skipped when the form is a homograph with several POS-tagged senses). When an offline
+// lexicon is given, it glosses the lemmas no DIGLOT_MAP entry does.
+
+use crate::lexicon::Lexicon;
+use crate::profile::LemmaState;
+use crate::simulation::dictionary::{normalize_lemma_key, GlobalLemmaDictionary};
+use crate::simulation::numerical_types::NumericalLearnerProfile;
+use crate::simulation::level_policy::SentenceLevel;
+use crate::simulation::text_generator::GeneratedTextBlock;
+use crate::tokenizer::{self, Token};
+use crate::types::llm_data::ProcessedSentence;
+use std::collections::HashMap;
+use crate::error::WeaveLangError;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// Escapes text for use in element content and double-quoted attributes.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            other => escaped.push(other),
+        }
+    }
+    escaped
+}
+
+fn state_class(state: Option<LemmaState>) -> &'static str {
+    match state {
+        Some(LemmaState::Known) => "known",
+        Some(LemmaState::Active) => "active",
+        Some(LemmaState::New) | None => "new",
+    }
+}
+
+fn level_class(level: SentenceLevel) -> &'static str {
+    match level {
+        SentenceLevel::L1 => "wl-l1",
+        SentenceLevel::L2 => "wl-l2",
+        SentenceLevel::L3 => "wl-l3",
+        SentenceLevel::L4 => "wl-l4",
+        SentenceLevel::L5 => "wl-l5",
+    }
+}
+
+// Lemma and optional English gloss for a (lowercased, possibly multi-word) target form.
+struct FormGloss {
+    lemma: String,
+    gloss: Option<String>,
+}
+
+// Target forms of one sentence keyed by their lowercased tokens joined with single spaces.
+fn sentence_form_glosses(sentence: &ProcessedSentence, form_tokenizer: &dyn tokenizer::Tokenizer) -> HashMap<String, FormGloss> {
+    let mut forms: HashMap<String, FormGloss> = HashMap::new();
+    for entry in sentence.diglot_map.iter().flat_map(|segment_map| &segment_map.entries) {
+        let form_key = form_tokenizer.tokenize(&entry.exact_spa_form).iter()
+            .map(|t| t.text.to_lowercase())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if form_key.is_empty() || entry.spa_lemma.trim().is_empty() {
+            continue;
+        }
+        let gloss = Some(entry.eng_word.trim().to_string()).filter(|g| !g.is_empty());
+        forms.entry(form_key).or_insert(FormGloss { lemma: normalize_lemma_key(&entry.spa_lemma), gloss });
+    }
+    forms
+}
+
+fn word_span(surface: &str, lemma: &str, gloss: Option<&str>, state: Option<LemmaState>) -> String {
+    let state = state_class(state);
+    let gloss_attr = gloss.map(|g| format!(" data-gloss=\"{}\"", escape_html(g))).unwrap_or_default();
+    format!(
+        "<span class=\"wl-word wl-{}\" data-lemma=\"{}\" data-state=\"{}\"{}>{}</span>",
+        state, escape_html(lemma), state, gloss_attr, escape_html(surface)
+    )
+}
+
+/// A target-language word (or multi-word form) of a rendered sentence traced to its lemma.
+#[derive(Debug, Clone)]
+pub struct TracedWord {
+    pub range: Range<usize>, // Byte range in the sentence text
+    pub lemma: String,
+    pub gloss: Option<String>,
+    pub state: Option<LemmaState>, // None if the profile has never seen the lemma
+}
+
+/// The target-language words of one rendered sentence (`text`, rendered at `level` from
+/// `sentence`) that can be traced to a lemma, in order. In L4 sentences only forms whose
+/// lemma is Known/Active count, since everything else is still the base-language SimE.
+pub fn trace_sentence_words(
+    sentence: &ProcessedSentence,
+    text: &str,
+    level: SentenceLevel,
+    dictionary: &GlobalLemmaDictionary,
+    profile: &NumericalLearnerProfile,
+    form_tokenizer: &dyn tokenizer::Tokenizer,
+) -> Vec<TracedWord> {
+    if level == SentenceLevel::L5 {
+        return Vec::new();
+    }
+    let forms = sentence_form_glosses(sentence, form_tokenizer);
+    let lemma_state = |lemma: &str| dictionary.get_id(lemma)
+        .and_then(|id| profile.get_lemma_info(id))
+        .map(|info| info.state);
+    let max_form_tokens = forms.keys().map(|k| k.split(' ').count()).max().unwrap_or(1);
+    let tokens: Vec<Token> = form_tokenizer.tokenize(text);
+
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        // Longest DIGLOT_MAP form starting at this token.
+        let form_match = (1..=max_form_tokens.min(tokens.len() - i)).rev().find_map(|len| {
+            let key = tokens[i..i + len].iter().map(|t| t.text.to_lowercase()).collect::<Vec<_>>().join(" ");
+            forms.get(&key).map(|form| (len, form))
+        });
+        let (len, lemma, gloss) = match form_match {
+            Some((len, form)) => (len, form.lemma.clone(), form.gloss.clone()),
+            None => match dictionary.get_id_unambiguous(tokens[i].text).and_then(|id| dictionary.get_str(id)) {
+                Some(lemma) if level != SentenceLevel::L4 => (1, lemma.clone(), None),
+                _ => { i += 1; continue; }
+            },
+        };
+        let state = lemma_state(&lemma);
+        if level == SentenceLevel::L4 && !matches!(state, Some(LemmaState::Known | LemmaState::Active)) {
+            i += 1;
+            continue;
+        }
+        words.push(TracedWord { range: tokens[i].start..tokens[i + len - 1].end, lemma, gloss, state });
+        i += len;
+    }
+    words
+}
+
+// Escapes a rendered sentence, wrapping each traced word in a span.
+fn render_sentence_words(text: &str, words: &[TracedWord]) -> String {
+    let mut html = String::new();
+    let mut copied_up_to = 0;
+    for word in words {
+        html.push_str(&escape_html(&text[copied_up_to..word.range.start]));
+        html.push_str(&word_span(&text[word.range.clone()], &word.lemma, word.gloss.as_deref(), word.state));
+        copied_up_to = word.range.end;
+    }
+    html.push_str(&escape_html(&text[copied_up_to..]));
+    html
+}
+
+/// HTML fragment for one generated block: a `<section>` with one `<p>` per sentence and an
+/// `<h2>` before each sentence that starts a chapter.
+/// `profile` should be the profile the block was rendered against.
+pub fn render_block_html(
+    sentences: &[&ProcessedSentence],
+    generated: &GeneratedTextBlock,
+    dictionary: &GlobalLemmaDictionary,
+    profile: &NumericalLearnerProfile,
+    target_language: &str,
+    block_number: usize,
+    lexicon: Option<&Lexicon>,
+) -> String {
+    let form_tokenizer = tokenizer::tokenizer_for_language(target_language);
+    let mut html = format!("<section class=\"wl-block\" data-block=\"{}\">\n", block_number);
+    for ((sentence, text), record) in sentences.iter().zip(&generated.sentence_texts).zip(&generated.sentence_levels) {
+        let mut words = trace_sentence_words(sentence, text, record.level, dictionary, profile, form_tokenizer.as_ref());
+        if let Some(lexicon) = lexicon {
+            for word in words.iter_mut().filter(|word| word.gloss.is_none()) {
+                word.gloss = lexicon.short_gloss(&word.lemma);
+            }
+        }
+        if let Some(heading) = &sentence.section_heading {
+            html.push_str(&format!("<h2 class=\"wl-heading\">{}</h2>\n", escape_html(heading)));
+        }
+        html.push_str(&format!(
+            "<p class=\"wl-sentence {}\" data-sentence-id=\"{}\" data-level=\"{:?}\">{}</p>\n",
+            level_class(record.level),
+            escape_html(&record.sentence_id),
+            record.level,
+            render_sentence_words(text, &words),
+        ));
+    }
+    html.push_str("</section>\n");
+    html
+}
+
+/// Wraps block fragments from `render_block_html` in a standalone page.
+pub fn render_chapter_document(title: &str, block_fragments: &[String]) -> String {
+    HTML_TEMPLATE
+        .replace("%%STYLE%%", STYLESHEET)
+        .replace("%%TITLE%%", &escape_html(title))
+        .replace("%%BODY%%", &block_fragments.concat())
+}
+
+pub fn write_chapter_html(file_path: &Path, title: &str, block_fragments: &[String]) -> Result<(), WeaveLangError> {
+    fs::write(file_path, render_chapter_document(title, block_fragments))
+        .map_err(|e| WeaveLangError::generation(format!("Failed to write HTML chapter to {:?}: {}", file_path, e)).with_source(e))?;
+    Ok(())
+}
+
+/// Styles for the classes emitted by `render_block_html`, shared with the EPUB and
+/// parallel-text exporters.
+pub const STYLESHEET: &str = r#"body { font-family: Georgia, serif; max-width: 42em; margin: 2em auto; line-height: 1.6; color: #222; }
+h1 { font-family: sans-serif; font-size: 1.3em; }
+h2.wl-heading { font-family: sans-serif; font-size: 1.1em; margin-top: 1.5em; }
+.wl-block { margin-bottom: 1.5em; }
+table.wl-parallel { border-collapse: collapse; width: 100%; }
+.wl-parallel th, .wl-parallel td { border-top: 1px solid #ddd; padding: 0.3em 0.5em; text-align: left; vertical-align: top; }
+.wl-parallel .wl-meta { font: 0.75em sans-serif; color: #777; white-space: nowrap; }
+.wl-word { position: relative; border-bottom: 1px dotted #999; }
+.wl-known { border-bottom-color: #4a4; }
+.wl-active { border-bottom-color: #d90; }
+.wl-new { border-bottom-color: #c33; }
+.wl-word[data-gloss]:hover::after {
+  content: attr(data-gloss); position: absolute; left: 0; top: 1.6em; z-index: 1; white-space: nowrap;
+  background: #333; color: #fff; font: 0.8em sans-serif; padding: 2px 6px; border-radius: 3px;
+}
+"#;
+
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>%%TITLE%%</title>
+<style>
+%%STYLE%%</style>
+</head>
+<body>
+<h1>%%TITLE%%</h1>
+%%BODY%%</body>
+</html>
+"#;
+//*** END FILE: src/simulation/exporters/html.rs ***//
@@ -0,0 +1,101 @@
+//*** START FILE: src/simulation/exporters/manifest.rs ***//
+// Audio alignment manifest written next to a TTS input file (<tts stem>.manifest.json).
+// It lists the sentences in the order they appear in the TTS file with their sentence ID,
+// rendered level and the lemmas the rendered text contains, so tools that get timestamps
+// per sentence from the TTS engine can map audio back to source sentences and vocabulary.
+// Chapter headings (ProcessedSentence::section_heading) precede their sentence in the
+// TTS file as a paragraph of their own; they are recorded on that sentence.
+
+use super::html::trace_sentence_words;
+use crate::simulation::dictionary::GlobalLemmaDictionary;
+use crate::simulation::numerical_types::NumericalLearnerProfile;
+use crate::simulation::level_policy::SentenceLevel;
+use crate::simulation::text_generator::GeneratedTextBlock;
+use crate::tokenizer::{self, Tokenizer};
+use crate::types::llm_data::ProcessedSentence;
+use serde::Serialize;
+use crate::error::WeaveLangError;
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ManifestSentence {
+    pub index: usize, // Position in the TTS file, from 0
+    pub sentence_id: String,
+    pub block_in_book: usize,
+    pub level: SentenceLevel,
+    pub text: String, // As rendered, without a level tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section_heading: Option<String>,
+    pub lemma_ids: Vec<u32>, // Global dictionary IDs, in text order, without repeats
+    pub lemmas: Vec<String>, // The same lemmas as dictionary keys
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct AlignmentManifest {
+    pub book_instance_id: String,
+    pub tts_file: String, // File name of the TTS input the sentences belong to
+    pub target_language: String,
+    pub sentences: Vec<ManifestSentence>,
+}
+
+/// Collects manifest sentences block by block while a book instance is simulated.
+pub struct ManifestBuilder {
+    pub manifest: AlignmentManifest,
+    form_tokenizer: Box<dyn Tokenizer>,
+}
+
+impl ManifestBuilder {
+    pub fn new(book_instance_id: &str, target_language: &str) -> Self {
+        Self {
+            manifest: AlignmentManifest {
+                book_instance_id: book_instance_id.to_string(),
+                target_language: target_language.to_string(),
+                ..AlignmentManifest::default()
+            },
+            form_tokenizer: tokenizer::tokenizer_for_language(target_language),
+        }
+    }
+
+    /// Adds the sentences of one generated block. `profile` should be the profile the
+    /// block was rendered against.
+    pub fn add_block(
+        &mut self,
+        sentences: &[&ProcessedSentence],
+        generated: &GeneratedTextBlock,
+        dictionary: &GlobalLemmaDictionary,
+        profile: &NumericalLearnerProfile,
+        block_in_book: usize,
+    ) {
+        for ((sentence, text), record) in sentences.iter().zip(&generated.sentence_texts).zip(&generated.sentence_levels) {
+            let mut lemma_ids: Vec<u32> = Vec::new();
+            let mut lemmas: Vec<String> = Vec::new();
+            for word in trace_sentence_words(sentence, text, record.level, dictionary, profile, self.form_tokenizer.as_ref()) {
+                if let Some(lemma_id) = dictionary.get_id(&word.lemma) {
+                    if !lemma_ids.contains(&lemma_id) {
+                        lemma_ids.push(lemma_id);
+                        lemmas.push(word.lemma);
+                    }
+                }
+            }
+            self.manifest.sentences.push(ManifestSentence {
+                index: self.manifest.sentences.len(),
+                sentence_id: record.sentence_id.clone(),
+                block_in_book,
+                level: record.level,
+                text: text.clone(),
+                section_heading: sentence.section_heading.clone(),
+                lemma_ids,
+                lemmas,
+            });
+        }
+    }
+}
+
+pub fn write_manifest(file_path: &Path, manifest: &AlignmentManifest) -> Result<(), WeaveLangError> {
+    let json = serde_json::to_string_pretty(manifest)
+        .map_err(|e| WeaveLangError::generation(format!("Failed to serialize audio manifest: {}", e)).with_source(e))?;
+    fs::write(file_path, json).map_err(|e| WeaveLangError::generation(format!("Failed to write audio manifest to {:?}: {}", file_path, e)).with_source(e))?;
+    Ok(())
+}
+//*** END FILE: src/simulation/exporters/manifest.rs ***//
@@ -0,0 +1,100 @@
+//*** START FILE: src/simulation/exporters/parallel.rs ***//
+// Parallel text for reviewers: each woven sentence next to its pure SimE, as TSV or as an
+// HTML table, so one can check that weaving kept the meaning of the sentence.
+
+use super::html::{escape_html, render_chapter_document};
+use crate::simulation::level_policy::SentenceLevel;
+use crate::simulation::text_generator::GeneratedTextBlock;
+use crate::types::llm_data::ProcessedSentence;
+use crate::error::WeaveLangError;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParallelTextFormat {
+    Tsv,
+    Html,
+}
+
+impl ParallelTextFormat {
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            ParallelTextFormat::Tsv => "parallel.tsv",
+            ParallelTextFormat::Html => "parallel.html",
+        }
+    }
+}
+
+impl std::str::FromStr for ParallelTextFormat {
+    type Err = WeaveLangError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "tsv" => Ok(ParallelTextFormat::Tsv),
+            "html" => Ok(ParallelTextFormat::Html),
+            _ => Err(WeaveLangError::config(format!("Invalid parallel text format '{}': expected 'tsv' or 'html'.", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParallelRow {
+    pub sentence_id: String,
+    pub block_in_book: usize,
+    pub level: SentenceLevel,
+    pub woven: String, // As rendered, without a level tag
+    pub sim_e: String,
+}
+
+/// Rows for one generated block, in block order.
+pub fn block_rows(sentences: &[&ProcessedSentence], generated: &GeneratedTextBlock, block_in_book: usize) -> Vec<ParallelRow> {
+    sentences.iter().zip(&generated.sentence_texts).zip(&generated.sentence_levels)
+        .map(|((sentence, text), record)| ParallelRow {
+            sentence_id: record.sentence_id.clone(),
+            block_in_book,
+            level: record.level,
+            woven: text.clone(),
+            sim_e: sentence.sim_e.clone(),
+        })
+        .collect()
+}
+
+// Tabs and line breaks would split a TSV field.
+fn tsv_field(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub fn render_tsv(rows: &[ParallelRow]) -> String {
+    let mut tsv = String::from("sentence_id\tblock\tlevel\twoven\tsim_e\n");
+    for row in rows {
+        tsv.push_str(&format!("{}\t{}\t{:?}\t{}\t{}\n",
+                              tsv_field(&row.sentence_id), row.block_in_book, row.level,
+                              tsv_field(&row.woven), tsv_field(&row.sim_e)));
+    }
+    tsv
+}
+
+pub fn render_html(title: &str, rows: &[ParallelRow]) -> String {
+    let mut table = String::from("<table class=\"wl-parallel\">\n<tr><th></th><th>Woven</th><th>SimE</th></tr>\n");
+    for row in rows {
+        table.push_str(&format!(
+            "<tr data-sentence-id=\"{id}\"><td class=\"wl-meta\">{id}<br/>{level:?}</td><td>{woven}</td><td>{sim_e}</td></tr>\n",
+            id = escape_html(&row.sentence_id),
+            level = row.level,
+            woven = escape_html(&row.woven),
+            sim_e = escape_html(&row.sim_e),
+        ));
+    }
+    table.push_str("</table>\n");
+    render_chapter_document(title, &[table])
+}
+
+pub fn write_parallel_text(file_path: &Path, title: &str, rows: &[ParallelRow], format: ParallelTextFormat) -> Result<(), WeaveLangError> {
+    let contents = match format {
+        ParallelTextFormat::Tsv => render_tsv(rows),
+        ParallelTextFormat::Html => render_html(title, rows),
+    };
+    fs::write(file_path, contents).map_err(|e| WeaveLangError::generation(format!("Failed to write parallel text to {:?}: {}", file_path, e)).with_source(e))?;
+    Ok(())
+}
+//*** END FILE: src/simulation/exporters/parallel.rs ***//
@@ -0,0 +1,81 @@
+//*** START FILE: src/simulation/exporters/ssml.rs ***//
+// SSML rendering of generated blocks for TTS engines. Target-language stretches
+// (GeneratedTextBlock::target_language_ranges) are wrapped in <lang xml:lang="..">, or in
+// <voice name=".."> when a target voice is configured, so they are not read with the
+// base-language voice's pronunciation.
+
+use super::html::escape_html;
+use crate::simulation::text_generator::GeneratedTextBlock;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Default)]
+pub struct SsmlOptions {
+    pub base_language: String,   // xml:lang of the <speak> root
+    pub target_language: String, // xml:lang of target-language stretches
+    pub base_voice: Option<String>,
+    pub target_voice: Option<String>,
+}
+
+// Opening and closing tags around one target-language stretch.
+fn target_tags(options: &SsmlOptions) -> (String, &'static str) {
+    match &options.target_voice {
+        Some(voice) => (format!("<voice name=\"{}\">", escape_html(voice)), "</voice>"),
+        None => (format!("<lang xml:lang=\"{}\">", escape_html(&options.target_language)), "</lang>"),
+    }
+}
+
+/// One sentence with its target-language ranges wrapped. Ranges must be sorted and
+/// non-overlapping byte ranges of `text`; out-of-bounds ranges are ignored.
+pub fn render_sentence_ssml(text: &str, target_ranges: &[Range<usize>], options: &SsmlOptions) -> String {
+    let (open_tag, close_tag) = target_tags(options);
+    let mut ssml = String::new();
+    let mut copied_up_to = 0;
+    for range in target_ranges {
+        let valid = range.start >= copied_up_to && range.end <= text.len() && range.start < range.end
+            && text.is_char_boundary(range.start) && text.is_char_boundary(range.end);
+        if !valid {
+            continue;
+        }
+        ssml.push_str(&escape_html(&text[copied_up_to..range.start]));
+        ssml.push_str(&open_tag);
+        ssml.push_str(&escape_html(&text[range.clone()]));
+        ssml.push_str(close_tag);
+        copied_up_to = range.end;
+    }
+    ssml.push_str(&escape_html(&text[copied_up_to..]));
+    ssml
+}
+
+// Pauses around a chapter heading, so listeners hear where a chapter starts.
+const SECTION_BREAK_BEFORE: &str = "<break time=\"2s\"/>";
+const SECTION_BREAK_AFTER: &str = "<break time=\"1s\"/>";
+
+/// SSML fragment for one block: one <p> per sentence, with chapter headings read as a
+/// paragraph between pauses.
+pub fn render_block_ssml(generated: &GeneratedTextBlock, options: &SsmlOptions) -> String {
+    let mut ssml = String::new();
+    for (sentence_idx, (text, ranges)) in generated.sentence_texts.iter().zip(&generated.target_language_ranges).enumerate() {
+        if let Some(heading) = generated.section_headings.get(sentence_idx).and_then(Option::as_ref) {
+            ssml.push_str(&format!("{}\n<p>{}</p>\n{}\n", SECTION_BREAK_BEFORE, escape_html(heading), SECTION_BREAK_AFTER));
+        }
+        if !text.trim().is_empty() {
+            ssml.push_str(&format!("<p>{}</p>\n", render_sentence_ssml(text, ranges, options)));
+        }
+    }
+    ssml
+}
+
+/// Wraps block fragments from `render_block_ssml` in a <speak> document.
+pub fn render_ssml_document(block_fragments: &[String], options: &SsmlOptions) -> String {
+    let body = block_fragments.concat();
+    let body = match &options.base_voice {
+        Some(voice) => format!("<voice name=\"{}\">\n{}</voice>\n", escape_html(voice), body),
+        None => body,
+    };
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <speak version=\"1.1\" xmlns=\"http://www.w3.org/2001/10/synthesis\" xml:lang=\"{}\">\n{}</speak>\n",
+        escape_html(&options.base_language), body
+    )
+}
+//*** END FILE: src/simulation/exporters/ssml.rs ***//
@@ -0,0 +1,133 @@
+//*** START FILE: src/simulation/exporters/subtitles.rs ***//
+// Subtitles (SRT or WebVTT) for a book instance's woven text, one cue per sentence or
+// chapter heading in TTS order. There is no audio to align against, so timings are
+// estimated from a words-per-minute reading speed; they drift from the real audio on long
+// books, but are close enough to pair the TTS output with its text as a subtitled video.
+
+use crate::error::WeaveLangError;
+use std::fs;
+use std::path::Path;
+
+const MAX_LINE_CHARS: usize = 42; // Usual subtitle line length; longer cues are wrapped
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+        }
+    }
+}
+
+impl std::str::FromStr for SubtitleFormat {
+    type Err = WeaveLangError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "srt" => Ok(SubtitleFormat::Srt),
+            "vtt" | "webvtt" => Ok(SubtitleFormat::Vtt),
+            _ => Err(WeaveLangError::config(format!("Invalid subtitle format '{}': expected 'srt' or 'vtt'.", s))),
+        }
+    }
+}
+
+/// Reading-speed model for cue timings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubtitleTiming {
+    pub words_per_minute: f32,
+    pub gap_seconds: f32,      // Silence between consecutive cues
+    pub min_cue_seconds: f32,  // Floor for short cues, so they stay readable
+}
+
+impl Default for SubtitleTiming {
+    fn default() -> Self {
+        SubtitleTiming { words_per_minute: 150.0, gap_seconds: 0.3, min_cue_seconds: 1.0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleCue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+/// One cue per non-empty text, back to back with `gap_seconds` between them.
+pub fn estimate_cues(texts: &[String], timing: &SubtitleTiming) -> Vec<SubtitleCue> {
+    let ms_per_word = 60_000.0 / timing.words_per_minute.max(1.0);
+    let min_cue_ms = (timing.min_cue_seconds.max(0.0) * 1000.0) as u64;
+    let gap_ms = (timing.gap_seconds.max(0.0) * 1000.0) as u64;
+    let mut cues = Vec::new();
+    let mut clock_ms = 0u64;
+    for text in texts.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        let words = text.split_whitespace().count();
+        let duration_ms = ((words as f32 * ms_per_word).round() as u64).max(min_cue_ms);
+        cues.push(SubtitleCue { start_ms: clock_ms, end_ms: clock_ms + duration_ms, text: text.to_string() });
+        clock_ms += duration_ms + gap_ms;
+    }
+    cues
+}
+
+// "00:01:02,345" (SRT) or "00:01:02.345" (WebVTT).
+fn timestamp(ms: u64, format: SubtitleFormat) -> String {
+    let separator = if format == SubtitleFormat::Srt { ',' } else { '.' };
+    format!("{:02}:{:02}:{:02}{}{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, separator, ms % 1000)
+}
+
+// Greedy word wrap at MAX_LINE_CHARS; a single longer word keeps a line of its own.
+fn wrap_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > MAX_LINE_CHARS {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+// WebVTT cue text treats '&' and '<' as markup.
+fn escape_vtt(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+pub fn render_subtitles(cues: &[SubtitleCue], format: SubtitleFormat) -> String {
+    let mut out = String::new();
+    if format == SubtitleFormat::Vtt {
+        out.push_str("WEBVTT\n\n");
+    }
+    for (cue_idx, cue) in cues.iter().enumerate() {
+        if format == SubtitleFormat::Srt {
+            out.push_str(&format!("{}\n", cue_idx + 1));
+        }
+        out.push_str(&format!("{} --> {}\n", timestamp(cue.start_ms, format), timestamp(cue.end_ms, format)));
+        for line in wrap_lines(&cue.text) {
+            let line = if format == SubtitleFormat::Vtt { escape_vtt(&line) } else { line };
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub fn write_subtitles(file_path: &Path, texts: &[String], timing: &SubtitleTiming, format: SubtitleFormat) -> Result<(), WeaveLangError> {
+    let cues = estimate_cues(texts, timing);
+    fs::write(file_path, render_subtitles(&cues, format))
+        .map_err(|e| WeaveLangError::generation(format!("Failed to write subtitles to {:?}: {}", file_path, e)).with_source(e))?;
+    Ok(())
+}
+//*** END FILE: src/simulation/exporters/subtitles.rs ***//
@@ -0,0 +1,60 @@
+//*** START FILE: src/simulation/lemma_bitset.rs ***//
+// Dense set of lemma IDs, one bit per ID. Lemma IDs are handed out consecutively by the
+// GlobalLemmaDictionary, so a corpus of tens of thousands of lemmas fits in a few KB and a
+// membership test is a shift and a mask instead of a hash lookup.
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LemmaBitset {
+    words: Vec<u64>,
+}
+
+impl LemmaBitset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, lemma_id: u32) -> bool {
+        self.words.get(lemma_id as usize / 64).is_some_and(|word| word & (1 << (lemma_id % 64)) != 0)
+    }
+
+    pub fn insert(&mut self, lemma_id: u32) {
+        let index = lemma_id as usize / 64;
+        if index >= self.words.len() {
+            self.words.resize(index + 1, 0);
+        }
+        self.words[index] |= 1 << (lemma_id % 64);
+    }
+
+    pub fn remove(&mut self, lemma_id: u32) {
+        if let Some(word) = self.words.get_mut(lemma_id as usize / 64) {
+            *word &= !(1 << (lemma_id % 64));
+        }
+    }
+
+    pub fn set(&mut self, lemma_id: u32, present: bool) {
+        if present {
+            self.insert(lemma_id);
+        } else {
+            self.remove(lemma_id);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+}
+
+impl FromIterator<u32> for LemmaBitset {
+    fn from_iter<I: IntoIterator<Item = u32>>(lemma_ids: I) -> Self {
+        let mut bitset = Self::new();
+        for lemma_id in lemma_ids {
+            bitset.insert(lemma_id);
+        }
+        bitset
+    }
+}
+//*** END FILE: src/simulation/lemma_bitset.rs ***//
@@ -0,0 +1,531 @@
+//*** START FILE: src/simulation/level_policy.rs ***//
+// The level decision shared by the simulation and the text generator. decide_level picks
+// the level of one sentence for a learner and returns what the level outputs: the lemma
+// IDs core_algo measures CT on and the rendering plan (which segments go to SimS, which
+// words L4 substitutes) text_generator turns into text. Both sides call it with the same
+// sentence and profile, so the text always contains exactly the words CT was measured on.
+
+use crate::error::WeaveLangError;
+use crate::types::llm_data::ProcessedSentence;
+use super::core_algo::L4Settings;
+use super::numerical_types::{NumericalDiglotEntry, NumericalDiglotSegmentMap, NumericalProcessedSentence};
+use super::profile_view::LearnerStates;
+use crate::tokenizer::Token;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// The fallback level a sentence was rendered at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SentenceLevel { L1, L2, L3, L4, L5 }
+
+impl SentenceLevel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SentenceLevel::L1 => "L1",
+            SentenceLevel::L2 => "L2",
+            SentenceLevel::L3 => "L3",
+            SentenceLevel::L4 => "L4",
+            SentenceLevel::L5 => "L5",
+        }
+    }
+
+    /// Inline tag such as "[L3]".
+    pub fn tag(&self) -> &'static str {
+        match self {
+            SentenceLevel::L1 => "[L1]",
+            SentenceLevel::L2 => "[L2]",
+            SentenceLevel::L3 => "[L3]",
+            SentenceLevel::L4 => "[L4]",
+            SentenceLevel::L5 => "[L5]",
+        }
+    }
+}
+
+impl std::str::FromStr for SentenceLevel {
+    type Err = WeaveLangError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "L1" => Ok(SentenceLevel::L1),
+            "L2" => Ok(SentenceLevel::L2),
+            "L3" => Ok(SentenceLevel::L3),
+            "L4" => Ok(SentenceLevel::L4),
+            "L5" => Ok(SentenceLevel::L5),
+            _ => Err(WeaveLangError::config(format!("Invalid level '{}': expected L1, L2, L3, L4 or L5.", s))),
+        }
+    }
+}
+
+
+/// Which fallback levels a run may use, in the order they are tried. The last level is
+/// the fallback: it is used when no earlier level applies, regardless of the profile, so
+/// it must be one that can always be rendered (L1, L2 or L5). The default is L1..L5.
+/// Examples: "L1,L2,L3,L5" never substitutes diglot words; "L1,L3,L4,L2" never drops to
+/// raw SimE. A level is also skipped when the sentence rendered at it would fall below the
+/// sentence CT floor (with_min_sentence_ct); the floor is not part of the serialized form.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<SentenceLevel>", into = "Vec<SentenceLevel>")]
+pub struct LevelPolicy {
+    levels: Vec<SentenceLevel>,
+    min_sentence_ct: f32,
+}
+
+impl LevelPolicy {
+    pub fn new(levels: Vec<SentenceLevel>) -> Result<Self, WeaveLangError> {
+        let Some(&fallback) = levels.last() else {
+            return Err(WeaveLangError::config("Level policy must enable at least one level."));
+        };
+        for (i, level) in levels.iter().enumerate() {
+            if levels[..i].contains(level) {
+                return Err(WeaveLangError::config(format!("Level policy lists {} more than once.", level.name())));
+            }
+        }
+        if matches!(fallback, SentenceLevel::L3 | SentenceLevel::L4) {
+            return Err(WeaveLangError::config(format!(
+                "Level policy cannot end with {}: the last level is the fallback and must be L1, L2 or L5.",
+                fallback.name()
+            )));
+        }
+        Ok(Self { levels, min_sentence_ct: 0.0 })
+    }
+
+    /// Sets the comprehensible share (sentence_ct) a sentence needs at a level
+    /// other than the fallback. 0 disables the check.
+    pub fn with_min_sentence_ct(mut self, min_sentence_ct: f32) -> Self {
+        self.min_sentence_ct = min_sentence_ct.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn min_sentence_ct(&self) -> f32 {
+        self.min_sentence_ct
+    }
+
+    pub fn levels(&self) -> &[SentenceLevel] {
+        &self.levels
+    }
+
+    pub fn fallback(&self) -> SentenceLevel {
+        *self.levels.last().unwrap_or(&SentenceLevel::L5)
+    }
+
+    pub fn is_enabled(&self, level: SentenceLevel) -> bool {
+        self.levels.contains(&level)
+    }
+}
+
+impl Default for LevelPolicy {
+    fn default() -> Self {
+        Self { levels: vec![SentenceLevel::L1, SentenceLevel::L2, SentenceLevel::L3, SentenceLevel::L4, SentenceLevel::L5], min_sentence_ct: 0.0 }
+    }
+}
+
+impl TryFrom<Vec<SentenceLevel>> for LevelPolicy {
+    type Error = WeaveLangError;
+
+    fn try_from(levels: Vec<SentenceLevel>) -> Result<Self, Self::Error> {
+        Self::new(levels)
+    }
+}
+
+impl From<LevelPolicy> for Vec<SentenceLevel> {
+    fn from(policy: LevelPolicy) -> Self {
+        policy.levels
+    }
+}
+
+/// Comma-separated levels in the order to try them, e.g. "L1,L2,L3,L5".
+impl std::str::FromStr for LevelPolicy {
+    type Err = WeaveLangError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let levels = s.split(',')
+            .map(|part| part.trim().parse::<SentenceLevel>())
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(levels)
+    }
+}
+
+impl std::fmt::Display for LevelPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = self.levels.iter().map(|level| level.name()).collect();
+        write!(f, "{}", names.join(","))
+    }
+}
+
+/// Comprehensible share of one sentence rendered at `level`: Known target tokens plus the
+/// base-language words left in it, over both. L3 and L4 keep roughly the SimE words their
+/// target tokens did not replace; L1 and L2 keep none. 1.0 for a sentence without target tokens.
+pub fn sentence_ct(level: SentenceLevel, n_sentence: &NumericalProcessedSentence, output_lemma_ids: &[u32], profile: &dyn LearnerStates) -> f32 {
+    if output_lemma_ids.is_empty() {
+        return 1.0;
+    }
+    let base_words = match level {
+        SentenceLevel::L3 | SentenceLevel::L4 => n_sentence.sim_e_original.split_whitespace().count().saturating_sub(output_lemma_ids.len()),
+        _ => 0,
+    };
+    let known = output_lemma_ids.iter().filter(|&&id| profile.is_lemma_known(id)).count();
+    (known + base_words) as f32 / (output_lemma_ids.len() + base_words) as f32
+}
+
+/// The level a sentence is rendered at, with what that level outputs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LevelDecision {
+    L1 { lemma_ids: Vec<u32> }, // AdvSL lemmas
+    L2 { lemma_ids: Vec<u32> }, // SimSL lemmas of every segment
+    // sim_s_segments: indexes of the SimS segments rendered in SimS; the others are rendered
+    // with their PHRASE_ALIGN SimE span. lemma_ids: the SimSL lemmas of those segments.
+    L3 { sim_s_segments: Vec<usize>, lemma_ids: Vec<u32> },
+    // lemma_ids: the substitutions' lemmas, ascending and deduplicated
+    L4 { substitutions: Vec<L4Substitution>, lemma_ids: Vec<u32> },
+    L5, // Raw SimE: no target-language lemmas
+}
+
+impl LevelDecision {
+    fn l4(substitutions: Vec<L4Substitution>) -> Self {
+        let mut lemma_ids: Vec<u32> = substitutions.iter().map(|s| s.lemma_id).collect();
+        lemma_ids.sort_unstable(); // Sort before dedup
+        lemma_ids.dedup();         // Deduplicate, as same lemma might be chosen for diff segments
+        LevelDecision::L4 { substitutions, lemma_ids }
+    }
+
+    pub fn level(&self) -> SentenceLevel {
+        match self {
+            LevelDecision::L1 { .. } => SentenceLevel::L1,
+            LevelDecision::L2 { .. } => SentenceLevel::L2,
+            LevelDecision::L3 { .. } => SentenceLevel::L3,
+            LevelDecision::L4 { .. } => SentenceLevel::L4,
+            LevelDecision::L5 => SentenceLevel::L5,
+        }
+    }
+
+    /// The target lemma IDs the sentence exposes.
+    pub fn lemma_ids(&self) -> &[u32] {
+        match self {
+            LevelDecision::L1 { lemma_ids } | LevelDecision::L2 { lemma_ids } | LevelDecision::L3 { lemma_ids, .. } | LevelDecision::L4 { lemma_ids, .. } => lemma_ids,
+            LevelDecision::L5 => &[],
+        }
+    }
+}
+
+/// Sentences per level, L1..L5.
+pub fn level_counts(decisions: &[LevelDecision]) -> [usize; 5] {
+    let mut counts = [0; 5];
+    for decision in decisions {
+        counts[decision.level() as usize] += 1;
+    }
+    counts
+}
+
+// What a sentence outputs at `level`, or None if the level does not apply.
+fn level_decision(
+    level: SentenceLevel,
+    s_sentence: &ProcessedSentence,
+    n_sentence: &NumericalProcessedSentence,
+    profile: &dyn LearnerStates,
+    l4: &L4Settings,
+) -> Option<LevelDecision> {
+    match level {
+        SentenceLevel::L1 => l1_output_ids(n_sentence, profile)
+            .filter(|_| !s_sentence.adv_s.trim().is_empty())
+            .map(|lemma_ids| LevelDecision::L1 { lemma_ids }),
+        SentenceLevel::L2 => l2_output_ids(n_sentence, profile).map(|lemma_ids| LevelDecision::L2 { lemma_ids }),
+        SentenceLevel::L3 => l3_plan(s_sentence, n_sentence, profile),
+        SentenceLevel::L4 => l4_plan(n_sentence, profile, l4).map(LevelDecision::l4),
+        SentenceLevel::L5 => Some(LevelDecision::L5),
+    }
+}
+
+/// Decides the level a sentence is rendered at. Levels are tried in `level_policy` order
+/// (L1..L5 by default); a level whose output would leave the sentence below the policy's
+/// sentence CT floor is skipped like one that does not apply. When none of them applies,
+/// the policy's last level is used regardless of the profile, or L5 when the sentence
+/// has no text for it.
+pub fn decide_level(
+    s_sentence: &ProcessedSentence,
+    n_sentence: &NumericalProcessedSentence,
+    profile: &dyn LearnerStates,
+    l4: &L4Settings,
+    level_policy: &LevelPolicy,
+) -> LevelDecision {
+    for &level in level_policy.levels() {
+        if let Some(decision) = level_decision(level, s_sentence, n_sentence, profile, l4) {
+            if sentence_ct(level, n_sentence, decision.lemma_ids(), profile) >= level_policy.min_sentence_ct() {
+                return decision;
+            }
+        }
+    }
+    match level_policy.fallback() {
+        SentenceLevel::L1 if !s_sentence.adv_s.trim().is_empty() => LevelDecision::L1 { lemma_ids: n_sentence.adv_s_lemma_ids.clone() },
+        SentenceLevel::L2 if n_sentence.has_sim_s => LevelDecision::L2 {
+            lemma_ids: n_sentence.sim_s_lemmas_numerical.iter().flat_map(|seg| seg.lemma_ids.iter().copied()).collect(),
+        },
+        _ => LevelDecision::L5, // LevelPolicy never ends with L3 or L4
+    }
+}
+
+// L1: every AdvSL lemma and AdvS grammar feature is K/A.
+fn l1_output_ids(n_sentence: &NumericalProcessedSentence, profile: &dyn LearnerStates) -> Option<Vec<u32>> {
+    l1_lemma_ids(n_sentence, profile).filter(|_| profile.has_grammar(&n_sentence.adv_s_grammar))
+}
+
+fn l1_lemma_ids(n_sentence: &NumericalProcessedSentence, profile: &dyn LearnerStates) -> Option<Vec<u32>> {
+    (!n_sentence.adv_s_lemma_ids.is_empty()
+        && n_sentence.adv_s_lemma_ids.iter().all(|&id| profile.is_lemma_known_or_active(id)))
+        .then(|| n_sentence.adv_s_lemma_ids.clone())
+}
+
+// L2: as L1, with the SimSL lemmas and SimS grammar features.
+fn l2_output_ids(n_sentence: &NumericalProcessedSentence, profile: &dyn LearnerStates) -> Option<Vec<u32>> {
+    l2_lemma_ids(n_sentence, profile).filter(|_| profile.has_grammar(&n_sentence.sim_s_grammar))
+}
+
+fn l2_lemma_ids(n_sentence: &NumericalProcessedSentence, profile: &dyn LearnerStates) -> Option<Vec<u32>> {
+    if !n_sentence.has_sim_s { // SimS text must exist
+        return None;
+    }
+    // If sim_s_lemmas_numerical is empty, it means all words in SimS are non-trackable or too simple.
+    // L2 is possible if all *trackable* lemmas are K/A. If no trackable lemmas, it's vacuously true for L2.
+    if n_sentence.sim_s_lemmas_numerical.is_empty() && !n_sentence.sim_s_segments_numerical.is_empty() {
+        // This state: segments exist, but no overall lemmas for them based on sim_s_lemmas_numerical.
+        // This could happen if all segments are proper nouns, or SimSL was empty for those segments.
+        // This implies we cannot verify L2 based on lemmas for these segments.
+        return None;
+    }
+    // An empty seg_lemmas_num.lemma_ids means that specific segment has no trackable lemmas.
+    // This does not automatically disqualify L2 for the *whole sentence* if other segments are fine.
+    let can_do_l2 = n_sentence.sim_s_lemmas_numerical.iter()
+        .flat_map(|seg_lemmas_num| &seg_lemmas_num.lemma_ids)
+        .all(|&lemma_id| profile.is_lemma_known_or_active(lemma_id));
+    // Collect all lemma IDs from all sim_s_lemmas_numerical segments
+    can_do_l2.then(|| n_sentence.sim_s_lemmas_numerical.iter()
+        .flat_map(|seg_lemmas_num| seg_lemmas_num.lemma_ids.iter().copied())
+        .collect())
+}
+
+/// GRAM:: features that alone keep a sentence from L1 or L2 (the level's lemmas are all
+/// Known/Active, its grammar is not), for the levels `level_policy` enables. In sentence order.
+pub fn blocking_grammar_features<'a>(
+    n_sentence: &'a NumericalProcessedSentence,
+    profile: &dyn LearnerStates,
+    level_policy: &LevelPolicy,
+) -> Vec<&'a String> {
+    let mut features: Vec<&String> = Vec::new();
+    let levels = [
+        (SentenceLevel::L1, &n_sentence.adv_s_grammar, l1_lemma_ids(n_sentence, profile).is_some()),
+        (SentenceLevel::L2, &n_sentence.sim_s_grammar, l2_lemma_ids(n_sentence, profile).is_some()),
+    ];
+    for (level, level_features, lemmas_ready) in levels {
+        if !lemmas_ready || !level_policy.is_enabled(level) {
+            continue;
+        }
+        for feature in level_features.iter().filter(|f| !profile.is_grammar_known_or_active(f)) {
+            if !features.contains(&feature) {
+                features.push(feature);
+            }
+        }
+    }
+    features
+}
+
+// L3: segments whose SimSL lemmas are all K/A switch to SimS, the others stay in SimE
+// (their PHRASE_ALIGN span), as long as at least one segment brings target lemmas.
+fn l3_plan(s_sentence: &ProcessedSentence, n_sentence: &NumericalProcessedSentence, profile: &dyn LearnerStates) -> Option<LevelDecision> {
+    if n_sentence.sim_s_segments_numerical.is_empty() {
+        return None;
+    }
+    // (segment ID, its lemmas, use the SimS phrase?) per segment, in sentence order.
+    let mut segment_choices: Vec<(&str, &[u32], bool)> = Vec::new();
+    for segment_num_data in &n_sentence.sim_s_segments_numerical {
+        let seg_lemmas_num = n_sentence.sim_s_lemmas_numerical.iter()
+            .find(|sl_num| sl_num.segment_id_str == segment_num_data.id_str)?; // No SimSL for the segment: L3 impossible
+        // A segment without trackable lemmas uses its SimS part (contributes 0 IDs here).
+        let use_sim_s_phrase_for_segment = seg_lemmas_num.lemma_ids.iter()
+            .all(|&lemma_id| profile.is_lemma_known_or_active(lemma_id));
+        segment_choices.push((&segment_num_data.id_str, &seg_lemmas_num.lemma_ids, use_sim_s_phrase_for_segment));
+    }
+    // LOCKED_PHRASE: locked segments switch to SimS together or not at all.
+    if segment_choices.iter().any(|(id, _, use_sim_s)| !use_sim_s && n_sentence.is_segment_locked(id)) {
+        for choice in segment_choices.iter_mut().filter(|(id, _, _)| n_sentence.is_segment_locked(id)) {
+            choice.2 = false;
+        }
+    }
+
+    let mut sim_s_segments = Vec::new();
+    let mut lemma_ids = Vec::new();
+    for (index, (segment_id, segment_lemma_ids, use_sim_s_phrase_for_segment)) in segment_choices.iter().enumerate() {
+        if *use_sim_s_phrase_for_segment {
+            sim_s_segments.push(index);
+            lemma_ids.extend(*segment_lemma_ids);
+        } else if !s_sentence.phrase_alignments.iter().any(|pa| pa.segment_id == *segment_id) {
+            return None; // No SimE span to render the segment with
+        }
+    }
+    (!lemma_ids.is_empty()).then_some(LevelDecision::L3 { sim_s_segments, lemma_ids })
+}
+
+// How a SimE token matched a DIGLOT_MAP word.
+enum TokenMatch {
+    Same,   // Ignoring case
+    Plural, // The SimE token is the word's regular English plural ("dogs", "boxes", "cities")
+}
+
+// Shortest English word the plural rule applies to: "is", "its" and "as" are not the
+// plurals of "I", "it" and "a".
+const MIN_PLURAL_STEM_CHARS: usize = 3;
+
+// English function words have no plural, whatever a SimE word ending in s looks like
+// ("hers" is not two "her").
+const CLOSED_CLASS_WORDS: &[&str] = &[
+    "the", "and", "but", "for", "nor", "yet", "she", "her", "his", "our", "your", "their",
+    "this", "that", "these", "those", "what", "which", "who", "whom", "whose", "there",
+    "here", "where", "when", "then", "than", "other",
+];
+
+// SimE words whose final s is not a plural ending.
+const NOT_PLURALS: &[&str] = &[
+    "news", "always", "perhaps", "besides", "unless", "across", "series", "species", "means",
+    "lens", "gas", "bus", "plus", "thus", "yes", "less", "physics", "politics", "mathematics",
+];
+
+fn match_token(sim_e_token: &str, eng_token: &str, allow_plural: bool) -> Option<TokenMatch> {
+    let token = sim_e_token.to_lowercase();
+    let eng = eng_token.to_lowercase();
+    if token == eng {
+        return Some(TokenMatch::Same);
+    }
+    if eng.chars().count() < MIN_PLURAL_STEM_CHARS
+        || CLOSED_CLASS_WORDS.contains(&eng.as_str())
+        || NOT_PLURALS.contains(&token.as_str())
+    {
+        return None;
+    }
+    let is_plural = token.strip_suffix('s') == Some(eng.as_str())
+        || token.strip_suffix("es") == Some(eng.as_str())
+        || token.strip_suffix("ies").is_some_and(|stem| eng.strip_suffix('y') == Some(stem));
+    (allow_plural && is_plural).then_some(TokenMatch::Plural)
+}
+
+// Capitalizes the form when the SimE word is capitalized but the DIGLOT_MAP word is not
+// ("The" for "the" at the start of a sentence gives "El" for "el").
+fn match_capitalization(spa_form: &str, sim_e_token: &str, eng_token: &str) -> String {
+    let starts_upper = |word: &str| word.chars().next().is_some_and(char::is_uppercase);
+    if !starts_upper(sim_e_token) || starts_upper(eng_token) {
+        return spa_form.to_string();
+    }
+    let mut chars = spa_form.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// One L4 substitution: `eng_span` (bytes of the sentence's SimE) is replaced by `spa_form`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct L4Substitution {
+    pub segment_id: String,
+    pub eng_span: Range<usize>,
+    pub spa_form: String,
+    pub lemma_id: u32,
+}
+
+/// Decides the exact L4 substitutions of a sentence, sorted by position, or None when L4
+/// does not apply. core_algo counts their lemmas and text_generator applies them, so CT is
+/// measured on exactly the words that end up in the text.
+/// Rules: at most one substitution per SimS segment, the best-ranked viable Known/Active
+/// entry (see L4Strategy) whose English words occur in SimE on tokens no other substitution
+/// took and that are not inside a name, a quote or another segment's locked phrase; words
+/// inside a multi-word expression only go in as the whole expression; and LOCKED_PHRASE
+/// segments are substituted together or not at all. Words match ignoring
+/// case, the form takes the capitalization of the SimE word, and with `plural_rules` a
+/// plural SimE word also matches and gets the plural of the form.
+pub fn l4_plan(n_sentence: &NumericalProcessedSentence, profile: &dyn LearnerStates, l4: &L4Settings) -> Option<Vec<L4Substitution>> {
+    if n_sentence.diglot_map_numerical.is_empty() {
+        return None;
+    }
+    let sim_e = &n_sentence.sim_e_original;
+    let sim_e_tokens = l4.base_tokenizer.tokenize(sim_e);
+    let mut taken = vec![false; sim_e_tokens.len()];
+
+    // The substitution of one segment, claiming its SimE tokens in `taken`.
+    let plan_segment = |seg_map_num: &NumericalDiglotSegmentMap, taken: &mut [bool]| -> Option<(L4Substitution, Range<usize>)> {
+        let segment_locked = n_sentence.is_segment_locked(&seg_map_num.segment_id_str);
+        let protected = |token: &Token| n_sentence.protected_sim_e_spans.iter()
+            .any(|span| span.blocks(&(token.start..token.end), segment_locked));
+        let mut candidates: Vec<&NumericalDiglotEntry> = seg_map_num.entries.iter()
+            .filter(|e| !e.inside_mwe
+                && e.is_viable(l4.min_diglot_confidence)
+                && !e.eng_word_original.is_empty()
+                && !e.exact_spa_form_original.is_empty()
+                && profile.is_lemma_known_or_active(e.spa_lemma_id))
+            .collect();
+        candidates.sort_by_key(|e| l4.strategy.candidate_key(e.spa_lemma_id, profile, l4.corpus_frequency));
+        candidates.into_iter().find_map(|entry| {
+            // Token-based matching keeps contractions like "don't" intact.
+            let phrase_tokens = l4.base_tokenizer.tokenize(&entry.eng_word_original);
+            if phrase_tokens.is_empty() {
+                return None;
+            }
+            let last = phrase_tokens.len() - 1;
+            // A plural occurrence whose form has no plural is skipped for a later one.
+            let (first, spa_form) = (0..=sim_e_tokens.len().checked_sub(phrase_tokens.len())?).find_map(|i| {
+                let free = (i..=i + last).all(|t| !taken[t] && !protected(&sim_e_tokens[t]));
+                let leading_words_match = (0..last).all(|k| sim_e_tokens[i + k].text.to_lowercase() == phrase_tokens[k].text.to_lowercase());
+                if !free || !leading_words_match {
+                    return None;
+                }
+                match match_token(sim_e_tokens[i + last].text, phrase_tokens[last].text, l4.plural_rules.is_some())? {
+                    TokenMatch::Plural => Some((i, l4.plural_rules?.pluralize(&entry.exact_spa_form_original)?)),
+                    TokenMatch::Same => Some((i, entry.exact_spa_form_original.clone())),
+                }
+            })?;
+            let tokens = first..first + phrase_tokens.len();
+            taken[tokens.clone()].iter_mut().for_each(|t| *t = true);
+            Some((
+                L4Substitution {
+                    segment_id: seg_map_num.segment_id_str.clone(),
+                    eng_span: sim_e_tokens[first].start..sim_e_tokens[tokens.end - 1].end,
+                    spa_form: match_capitalization(&spa_form, sim_e_tokens[first].text, phrase_tokens[0].text),
+                    lemma_id: entry.spa_lemma_id,
+                },
+                tokens,
+            ))
+        })
+    };
+
+    let mut substitutions = Vec::new();
+    // Locked segments go first so the group is all-or-nothing; locked IDs without a
+    // DIGLOT_MAP line can never be substituted, so they block the group too.
+    if let Some(locked_ids) = &n_sentence.locked_phrase_segment_id_strs {
+        let mut locked = Vec::new();
+        for locked_id in locked_ids {
+            match n_sentence.diglot_map_numerical.iter()
+                .find(|dm| dm.segment_id_str == *locked_id)
+                .and_then(|dm| plan_segment(dm, &mut taken))
+            {
+                Some(planned) => locked.push(planned),
+                None => {
+                    for (_, tokens) in &locked {
+                        taken[tokens.clone()].iter_mut().for_each(|t| *t = false);
+                    }
+                    locked.clear();
+                    break;
+                }
+            }
+        }
+        substitutions.extend(locked.into_iter().map(|(substitution, _)| substitution));
+    }
+    for seg_map_num in &n_sentence.diglot_map_numerical {
+        if n_sentence.is_segment_locked(&seg_map_num.segment_id_str) {
+            continue;
+        }
+        if let Some((substitution, _)) = plan_segment(seg_map_num, &mut taken) {
+            substitutions.push(substitution);
+        }
+    }
+    if substitutions.is_empty() {
+        return None;
+    }
+    substitutions.sort_by_key(|s| s.eng_span.start);
+    Some(substitutions)
+}
+//*** END FILE: src/simulation/level_policy.rs ***//
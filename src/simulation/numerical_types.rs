@@ -1,12 +1,114 @@
 //*** START FILE: src/simulation/numerical_types.rs ***//
-use std::collections::HashMap;
-use crate::profile::{LearnerLemmaInfo, LemmaState}; // Using existing profile structs
+use std::collections::{BTreeMap, HashMap};
+use crate::determinism::sorted_map;
+use std::sync::Arc;
+use crate::profile::{LearnerLemmaInfo, LemmaState, DEFAULT_EXPOSURE_THRESHOLD}; // Using existing profile structs
+use crate::types::protected_span::ProtectedSpan;
+use crate::simulation::dictionary::GlobalLemmaDictionary;
+use crate::simulation::lemma_bitset::LemmaBitset;
+use crate::types::llm_data::ChapterSpan;
 use serde::{Serialize, Deserialize};
 
+// --- Per-lemma exposure thresholds ---
+// Exposures an Active lemma needs to become Known, resolved against the global dictionary
+// (see crate::exposure_thresholds for the file formats).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExposureThresholds {
+    pub default_threshold: u32,
+    pub by_lemma_id: HashMap<u32, u32>,
+}
+
+impl Default for ExposureThresholds {
+    fn default() -> Self {
+        Self { default_threshold: DEFAULT_EXPOSURE_THRESHOLD, by_lemma_id: HashMap::new() }
+    }
+}
+
+impl ExposureThresholds {
+    pub fn threshold_for(&self, lemma_id: u32) -> u32 {
+        self.by_lemma_id.get(&lemma_id).copied().unwrap_or(self.default_threshold).max(1)
+    }
+}
+
+// --- Forgetting curve ---
+// Retention of a lemma not seen for `n` blocks is 0.5^(n / half_life_blocks).
+// Known lemmas whose retention drops below `min_retention` slide back to Active.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecayParams {
+    pub half_life_blocks: f32, // 0 disables decay
+    pub min_retention: f32,
+}
+
+impl Default for DecayParams {
+    fn default() -> Self {
+        Self { half_life_blocks: 0.0, min_retention: 0.5 }
+    }
+}
+
+impl DecayParams {
+    pub fn is_enabled(&self) -> bool {
+        self.half_life_blocks > 0.0
+    }
+
+    pub fn retention(&self, blocks_unseen: u64) -> f32 {
+        if !self.is_enabled() {
+            return 1.0;
+        }
+        0.5f32.powf(blocks_unseen as f32 / self.half_life_blocks)
+    }
+}
+
 // --- Numerical Learner Profile ---
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "NumericalLearnerProfileFields")]
 pub struct NumericalLearnerProfile {
+    #[serde(serialize_with = "sorted_map")]
     pub vocabulary: HashMap<u32, LearnerLemmaInfo>, // Key is lemma_id (u32)
+    // Number of blocks simulated with this profile; exposure times are measured in blocks.
+    #[serde(default)]
+    pub block_clock: u64,
+    // Times each lowercase target-language form was read; only filled when form tracking is on.
+    #[serde(default)]
+    pub form_exposures: BTreeMap<String, u32>,
+    // Grammar features (GRAM:: names) with the same New/Active/Known progression as lemmas.
+    #[serde(default)]
+    pub grammar: BTreeMap<String, LearnerLemmaInfo>,
+    // Not persisted: thresholds come from the run's configuration, see set_exposure_thresholds.
+    #[serde(skip)]
+    pub exposure_thresholds: Option<Arc<ExposureThresholds>>,
+    // Known and Known-or-Active lemma IDs, kept in step with `vocabulary` so the per-token
+    // state lookups of a regen pass are bit tests. Code that edits `vocabulary` directly
+    // calls sync_state_bitsets afterwards.
+    #[serde(skip)]
+    known: LemmaBitset,
+    #[serde(skip)]
+    known_or_active: LemmaBitset,
+}
+
+// The persisted fields of NumericalLearnerProfile; deserializing through it rebuilds the bitsets.
+#[derive(Deserialize)]
+struct NumericalLearnerProfileFields {
+    vocabulary: HashMap<u32, LearnerLemmaInfo>,
+    #[serde(default)]
+    block_clock: u64,
+    #[serde(default)]
+    form_exposures: BTreeMap<String, u32>,
+    #[serde(default)]
+    grammar: BTreeMap<String, LearnerLemmaInfo>,
+}
+
+impl From<NumericalLearnerProfileFields> for NumericalLearnerProfile {
+    fn from(fields: NumericalLearnerProfileFields) -> Self {
+        let mut profile = Self {
+            vocabulary: fields.vocabulary,
+            block_clock: fields.block_clock,
+            form_exposures: fields.form_exposures,
+            grammar: fields.grammar,
+            ..Self::default()
+        };
+        profile.sync_state_bitsets();
+        profile
+    }
 }
 
 impl NumericalLearnerProfile {
@@ -18,18 +120,75 @@ impl NumericalLearnerProfile {
         self.vocabulary.get(&lemma_id)
     }
 
+    /// Callers that change the returned entry's state call sync_lemma_state afterwards.
     pub fn get_lemma_info_mut(&mut self, lemma_id: u32) -> &mut LearnerLemmaInfo {
-        self.vocabulary.entry(lemma_id).or_insert_with(LearnerLemmaInfo::default)
+        let thresholds = &self.exposure_thresholds;
+        self.vocabulary.entry(lemma_id).or_insert_with(|| LearnerLemmaInfo {
+            required_exposure_threshold: thresholds.as_ref().map_or(DEFAULT_EXPOSURE_THRESHOLD, |t| t.threshold_for(lemma_id)),
+            ..LearnerLemmaInfo::default()
+        })
     }
 
-    pub fn is_lemma_known_or_active(&self, lemma_id: u32) -> bool {
+    /// Exposures `lemma_id` needs to become Known: its recorded threshold, or the one it would
+    /// get if it were added now.
+    pub fn required_exposure_threshold(&self, lemma_id: u32) -> u32 {
         match self.get_lemma_info(lemma_id) {
-            Some(info) => info.state == LemmaState::Known || info.state == LemmaState::Active,
-            None => false, // If a lemma_id isn't in the profile, it's effectively "New"
+            Some(info) => info.required_exposure_threshold,
+            None => self.exposure_thresholds.as_ref().map_or(DEFAULT_EXPOSURE_THRESHOLD, |t| t.threshold_for(lemma_id)),
         }
     }
+
+    /// Uses `thresholds` for lemmas added from now on and re-targets lemmas that are not
+    /// Known yet. Active lemmas that already meet their new threshold become Known.
+    pub fn set_exposure_thresholds(&mut self, thresholds: Arc<ExposureThresholds>) {
+        for (&lemma_id, info) in self.vocabulary.iter_mut() {
+            if info.state == LemmaState::Known {
+                continue;
+            }
+            info.required_exposure_threshold = thresholds.threshold_for(lemma_id);
+            if info.state == LemmaState::Active && info.exposure_count >= info.required_exposure_threshold {
+                info.state = LemmaState::Known;
+                self.known.insert(lemma_id);
+            }
+        }
+        self.exposure_thresholds = Some(thresholds);
+    }
+
+    /// Rebuilds the Known and Known-or-Active bitsets from `vocabulary`.
+    pub fn sync_state_bitsets(&mut self) {
+        let lemma_ids_in = |states: &[LemmaState]| self.vocabulary.iter()
+            .filter(|(_, info)| states.contains(&info.state))
+            .map(|(&lemma_id, _)| lemma_id)
+            .collect();
+        self.known = lemma_ids_in(&[LemmaState::Known]);
+        self.known_or_active = lemma_ids_in(&[LemmaState::Known, LemmaState::Active]);
+    }
+
+    /// Updates the bitsets after `lemma_id`'s state changed.
+    pub fn sync_lemma_state(&mut self, lemma_id: u32) {
+        let state = self.get_lemma_info(lemma_id).map_or(LemmaState::New, |info| info.state);
+        self.known.set(lemma_id, state == LemmaState::Known);
+        self.known_or_active.set(lemma_id, state != LemmaState::New);
+    }
+
+    pub fn is_lemma_known(&self, lemma_id: u32) -> bool {
+        self.known.contains(lemma_id)
+    }
+
+    pub fn known_lemmas(&self) -> &LemmaBitset {
+        &self.known
+    }
+
+    pub fn known_or_active_lemmas(&self) -> &LemmaBitset {
+        &self.known_or_active
+    }
+
+    pub fn is_lemma_known_or_active(&self, lemma_id: u32) -> bool {
+        self.known_or_active.contains(lemma_id) // Lemmas missing from the profile are New
+    }
     
     pub fn record_exposures(&mut self, lemma_ids: &[u32]) {
+        let block_clock = self.block_clock;
         for &lemma_id in lemma_ids {
             // It's assumed lemma_id 0 (or any specific ID) could be reserved if empty strings were an issue,
             // but dictionary now tries to avoid adding empty strings.
@@ -37,8 +196,10 @@ impl NumericalLearnerProfile {
             // it would be processed like any other ID.
             let info = self.get_lemma_info_mut(lemma_id);
             info.exposure_count += 1;
+            info.last_exposure_block = block_clock;
+            info.decayed = false;
 
-            // Note: The default LearnerLemmaInfo has required_exposure_threshold = 20.
+            // required_exposure_threshold is DEFAULT_EXPOSURE_THRESHOLD unless a threshold table is set.
             // This logic correctly transitions states.
             if info.state == LemmaState::New && info.exposure_count > 0 {
                 info.state = LemmaState::Active;
@@ -46,20 +207,102 @@ impl NumericalLearnerProfile {
             if info.state == LemmaState::Active && info.exposure_count >= info.required_exposure_threshold {
                 info.state = LemmaState::Known;
             }
+            self.sync_lemma_state(lemma_id);
+        }
+    }
+
+    /// Counts one exposure for each form (lowercased); repeated forms count repeatedly.
+    pub fn record_form_exposures<'a>(&mut self, forms: impl IntoIterator<Item = &'a str>) {
+        for form in forms {
+            *self.form_exposures.entry(form.to_lowercase()).or_insert(0) += 1;
+        }
+    }
+
+    pub fn is_grammar_known_or_active(&self, feature: &str) -> bool {
+        self.grammar.get(feature).is_some_and(|info| matches!(info.state, LemmaState::Known | LemmaState::Active))
+    }
+
+    /// Whether every feature in `features` is Active or Known (vacuously true for none).
+    pub fn has_grammar(&self, features: &[String]) -> bool {
+        features.iter().all(|feature| self.is_grammar_known_or_active(feature))
+    }
+
+    pub fn set_grammar_state(&mut self, feature: &str, new_state: LemmaState) {
+        self.grammar.entry(feature.to_string()).or_default().state = new_state;
+    }
+
+    /// Counts one exposure per feature, moving it New -> Active -> Known like record_exposures.
+    /// Grammar features always use DEFAULT_EXPOSURE_THRESHOLD.
+    pub fn record_grammar_exposures(&mut self, features: &[String]) {
+        let block_clock = self.block_clock;
+        for feature in features {
+            let info = self.grammar.entry(feature.clone()).or_default();
+            info.exposure_count += 1;
+            info.last_exposure_block = block_clock;
+            if info.state == LemmaState::New {
+                info.state = LemmaState::Active;
+            }
+            if info.state == LemmaState::Active && info.exposure_count >= info.required_exposure_threshold {
+                info.state = LemmaState::Known;
+            }
+        }
+    }
+
+    /// (Known, Active only) grammar features.
+    pub fn count_grammar(&self) -> (usize, usize) {
+        let known = self.grammar.values().filter(|info| info.state == LemmaState::Known).count();
+        let active = self.grammar.values().filter(|info| info.state == LemmaState::Active).count();
+        (known, active)
+    }
+
+    pub fn advance_block_clock(&mut self) {
+        self.block_clock += 1;
+    }
+
+    /// Demotes Known lemmas whose retention fell below `decay.min_retention` back to Active.
+    /// Their exposure count is scaled by the retention, so they need fresh exposures to
+    /// become Known again. Returns the demoted IDs in ascending order.
+    pub fn apply_decay(&mut self, decay: &DecayParams) -> Vec<u32> {
+        if !decay.is_enabled() {
+            return Vec::new();
+        }
+        let block_clock = self.block_clock;
+        let mut decayed_ids: Vec<u32> = Vec::new();
+        for (&lemma_id, info) in self.vocabulary.iter_mut() {
+            if info.state != LemmaState::Known {
+                continue;
+            }
+            let retention = decay.retention(block_clock.saturating_sub(info.last_exposure_block));
+            if retention < decay.min_retention {
+                info.state = LemmaState::Active;
+                info.decayed = true;
+                let retained = (info.required_exposure_threshold as f32 * retention).floor() as u32;
+                info.exposure_count = retained.min(info.required_exposure_threshold.saturating_sub(1));
+                decayed_ids.push(lemma_id);
+            }
+        }
+        for &lemma_id in &decayed_ids {
+            self.known.remove(lemma_id);
         }
+        decayed_ids.sort_unstable();
+        decayed_ids
+    }
+
+    pub fn count_decayed(&self) -> usize {
+        self.vocabulary.values().filter(|info| info.decayed).count()
     }
 
     // --- Counting methods ---
     pub fn count_known(&self) -> usize {
-        self.vocabulary.values().filter(|info| info.state == LemmaState::Known).count()
+        self.known.len()
     }
     
     pub fn count_active_only(&self) -> usize {
-        self.vocabulary.values().filter(|info| info.state == LemmaState::Active).count()
+        self.known_or_active.len() - self.known.len()
     }
 
     pub fn count_total_known_or_active(&self) -> usize {
-        self.vocabulary.values().filter(|info| info.state == LemmaState::Known || info.state == LemmaState::Active).count()
+        self.known_or_active.len()
     }
     
     pub fn vocabulary_size(&self) -> usize {
@@ -70,70 +313,148 @@ impl NumericalLearnerProfile {
         self.vocabulary.values().map(|info| info.exposure_count).sum()
     }
 
+    /// Form coverage against `dictionary.forms`: (forms read at least once, forms belonging
+    /// to a Known or Active lemma). The second number is what the learner could recognize if
+    /// knowing a lemma meant knowing all its forms; the first is what they have actually met.
+    pub fn form_coverage(&self, dictionary: &GlobalLemmaDictionary) -> (usize, usize) {
+        let learned_forms: Vec<&String> = dictionary.forms.iter()
+            .filter(|(_, lemmas)| lemmas.iter().any(|lemma| {
+                dictionary.get_id(lemma).is_some_and(|id| self.is_lemma_known_or_active(id))
+            }))
+            .map(|(form, _)| form)
+            .collect();
+        let seen = learned_forms.iter().filter(|form| self.form_exposures.contains_key(form.as_str())).count();
+        (seen, learned_forms.len())
+    }
+
+    /// Rewrites lemma IDs using `id_remap` (old -> new). Entries whose ID is not in the
+    /// map are dropped; returns how many were dropped.
+    pub fn remap_lemma_ids(&mut self, id_remap: &HashMap<u32, u32>) -> usize {
+        let before = self.vocabulary.len();
+        self.vocabulary = std::mem::take(&mut self.vocabulary)
+            .into_iter()
+            .filter_map(|(old_id, info)| id_remap.get(&old_id).map(|&new_id| (new_id, info)))
+            .collect();
+        self.sync_state_bitsets();
+        before - self.vocabulary.len()
+    }
+
     // Helper to set a lemma's state directly, e.g., when activating "New" words
     pub fn set_lemma_state(&mut self, lemma_id: u32, new_state: LemmaState) {
-        let info = self.get_lemma_info_mut(lemma_id);
-        info.state = new_state;
+        self.get_lemma_info_mut(lemma_id).state = new_state;
+        self.sync_lemma_state(lemma_id);
         // Optionally, if transitioning to Active from New, reset exposure count if desired
         // if old_state == LemmaState::New && new_state == LemmaState::Active {
-        //     info.exposure_count = 1; // Or 0, depending on convention
+        //     self.get_lemma_info_mut(lemma_id).exposure_count = 1; // Or 0, depending on convention
         // }
     }
+
+    /// Sets a lemma's state by hand (e.g. to model prior knowledge) and adjusts its exposure
+    /// count so record_exposures keeps it there: Known lemmas meet their threshold and count
+    /// as just seen, so decay does not demote them right away; Active lemmas stay below the
+    /// threshold; New lemmas lose their exposures.
+    pub fn override_lemma_state(&mut self, lemma_id: u32, new_state: LemmaState) {
+        let block_clock = self.block_clock;
+        let info = self.get_lemma_info_mut(lemma_id);
+        info.state = new_state;
+        info.decayed = false;
+        match new_state {
+            LemmaState::New => info.exposure_count = 0,
+            LemmaState::Active => info.exposure_count = info.exposure_count.min(info.required_exposure_threshold.saturating_sub(1)),
+            LemmaState::Known => {
+                info.exposure_count = info.exposure_count.max(info.required_exposure_threshold);
+                info.last_exposure_block = block_clock;
+            }
+        }
+        self.sync_lemma_state(lemma_id);
+    }
 }
 
 // --- Numerical representations of LLM data structures ---
 // These structs remain largely the same as before (definitions only)
 
-#[derive(Debug, Clone, Default)]
+// Segment text and PHRASE_ALIGN spans are not copied: nothing in the simulation reads them,
+// and the string chapter (same sentence index) still has them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NumericalSegmentData {
     pub id_str: String, 
-    pub text_original: String, 
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct NumericalPhraseAlignment {
-    pub segment_id_str: String, 
-    pub adv_s_span_original: String, 
-    pub sim_e_span_original: String,
-}
-
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NumericalSegmentLemmas {
     pub segment_id_str: String, 
     pub lemma_ids: Vec<u32>,   
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NumericalDiglotEntry {
     pub eng_word_original: String,  
     pub spa_lemma_id: u32,          
     pub exact_spa_form_original: String, 
-    pub viable: bool,
+    pub confidence: f32,
+    pub inside_mwe: bool, // Part of a multi-word-expression entry's form; see DiglotSegmentMap::entries_inside_mwe
 }
 
-#[derive(Debug, Clone, Default)]
+impl NumericalDiglotEntry {
+    pub fn is_viable(&self, min_confidence: f32) -> bool {
+        self.confidence > 0.0 && self.confidence >= min_confidence
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NumericalDiglotSegmentMap {
     pub segment_id_str: String, 
     pub entries: Vec<NumericalDiglotEntry>,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+// Only the text the simulation needs is kept here; AdvS, SimS and the segment texts are read
+// from the string chapter's sentence at the same index when rendering, instead of being
+// duplicated for every sentence of every loaded book.
 pub struct NumericalProcessedSentence {
     pub sentence_id_str: String, 
-    pub adv_s_original: String,
-    pub sim_s_original: String,
-    pub sim_e_original: String,
+    pub has_sim_s: bool,          // Non-empty SimS text, which L2 requires
+    pub sim_e_original: String,   // L4 substitutes into it
     pub sim_s_segments_numerical: Vec<NumericalSegmentData>, 
-    pub phrase_alignments_numerical: Vec<NumericalPhraseAlignment>, 
     pub sim_s_lemmas_numerical: Vec<NumericalSegmentLemmas>, 
     pub adv_s_lemma_ids: Vec<u32>,
     pub diglot_map_numerical: Vec<NumericalDiglotSegmentMap>, 
     pub locked_phrase_segment_id_strs: Option<Vec<String>>, 
+    pub adv_s_grammar: Vec<String>, // GRAM:: features L1 / L2 require, by name
+    pub sim_s_grammar: Vec<String>,
+    // Proper nouns, quotes and locked phrases in SimE, which L4 never substitutes into.
+    pub protected_sim_e_spans: Vec<ProtectedSpan>,
 }
 
-#[derive(Debug, Clone, Default)]
+impl NumericalProcessedSentence {
+    /// Whether a segment belongs to the sentence's LOCKED_PHRASE group.
+    pub fn is_segment_locked(&self, segment_id_str: &str) -> bool {
+        self.locked_phrase_segment_id_strs.as_ref().is_some_and(|ids| ids.iter().any(|id| id == segment_id_str))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NumericalChapter {
     pub source_file_name_original: String,
     pub sentences_numerical: Vec<NumericalProcessedSentence>,
 }
+
+impl NumericalChapter {
+    /// A copy of the sentences in `span`, matching ProcessedChapter::slice.
+    pub fn slice(&self, span: &ChapterSpan) -> NumericalChapter {
+        NumericalChapter {
+            source_file_name_original: self.source_file_name_original.clone(),
+            sentences_numerical: self.sentences_numerical[span.sentences.clone()].to_vec(),
+        }
+    }
+
+    /// Every lemma ID the chapter refers to (SimSL, AdvSL and DIGLOT_MAP), with repeats.
+    pub fn lemma_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.sentences_numerical.iter().flat_map(|sentence| {
+            sentence.sim_s_lemmas_numerical.iter().flat_map(|sl| sl.lemma_ids.iter().copied())
+                .chain(sentence.adv_s_lemma_ids.iter().copied())
+                .chain(sentence.diglot_map_numerical.iter().flat_map(|dm| dm.entries.iter().map(|e| e.spa_lemma_id)))
+        })
+    }
+}
 //*** END FILE: src/simulation/numerical_types.rs ***//
\ No newline at end of file
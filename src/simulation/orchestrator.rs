@@ -0,0 +1,623 @@
+//*** START FILE: src/simulation/orchestrator.rs ***//
+use super::block::{check_chapter_pair, Block, BlockBoundaries};
+use super::core_algo::{self, BlockSimulationSettings, CtMetricKind, L4Settings, L4Strategy, SimulationBlockResult};
+use super::dictionary::GlobalLemmaDictionary;
+use super::exporters::html::trace_sentence_words;
+use super::numerical_types::{DecayParams, NumericalChapter, NumericalLearnerProfile};
+use super::profile_view::LemmaTransition;
+use super::scheduler::{ActivationScheduler, CorpusFrequency, SchedulerParams};
+use super::level_policy::LevelPolicy;
+use super::text_generator::{self, GeneratedTextBlock, SentenceLevelRecord};
+use crate::tokenizer;
+use crate::error::WeaveLangError;
+use crate::types::llm_data::{ProcessedChapter, ProcessedSentence};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use tracing::info_span;
+
+#[derive(Debug, Clone)]
+pub struct OrchestratorParams {
+    pub sentences_per_block: usize,
+    // Whether blocks end exactly every sentences_per_block sentences or at a nearby paragraph end.
+    pub block_boundaries: BlockBoundaries,
+    // How many times the chapter is read. With more than one pass, sentence positions
+    // wrap around modularly, so a block can span the end and the start of the chapter.
+    pub passes: usize,
+    pub max_regen_attempts_per_block: u32,
+    pub target_ct_threshold: f32,
+    // Lower end of the CT band: blocks below it are de-escalated instead of accepted. 0 = no floor.
+    pub min_ct_threshold: f32,
+    pub max_words_to_activate_per_regen: usize,
+    pub min_diglot_confidence: f32,
+    // How L4 picks among the viable diglot entries of a segment.
+    pub l4_strategy: L4Strategy,
+    // Let L4 match plural SimE words ("dogs" for "dog") and pluralize the form.
+    pub l4_match_plurals: bool,
+    // Forgetting curve applied at the start of every block; disabled by default.
+    pub decay: DecayParams,
+    // Ranking of New lemmas offered to core_algo for activation.
+    pub scheduler: SchedulerParams,
+    // Comprehensibility metric compared against target_ct_threshold.
+    pub ct_metric: CtMetricKind,
+    // Prefix every rendered sentence with its level tag ("[L3] ...").
+    pub prefix_level_tags: bool,
+    // Levels sentences may be rendered at, in the order they are tried.
+    pub level_policy: LevelPolicy,
+    // GUI stops at the first failing block; the CLI logs and keeps going.
+    pub halt_on_block_error: bool,
+    // Upper bound on lemmas introduced (New -> Active/Known) within any INTRODUCTION_WINDOW_SENTENCES
+    // consecutive sentences, on top of the per-regen-attempt limit. None = no cap.
+    pub max_new_lemmas_per_100_sentences: Option<f32>,
+    // (sentences, lemmas introduced) of the blocks read just before this run, oldest first,
+    // so the cap's window continues across runs; see extend_introduction_history.
+    pub recent_introductions: Vec<(usize, usize)>,
+    // Count exposures per target-language form (profile.form_exposures) as well as per lemma.
+    pub track_forms: bool,
+}
+
+/// Sentences the introduction cap is measured over.
+pub const INTRODUCTION_WINDOW_SENTENCES: usize = 100;
+
+// Sliding window of recent blocks for max_new_lemmas_per_100_sentences. Blocks only partly
+// inside the window count in full, so the cap errs on the strict side.
+struct IntroductionWindow {
+    max_per_window: f32,
+    recent: VecDeque<(usize, usize)>, // (sentences, lemmas introduced), oldest first
+}
+
+impl IntroductionWindow {
+    fn new(max_per_window: f32, history: &[(usize, usize)]) -> Self {
+        let mut window = Self { max_per_window, recent: VecDeque::new() };
+        for &(sentences, introduced) in history {
+            window.record(sentences, introduced);
+        }
+        window
+    }
+
+    // New lemmas a block of `block_sentences` may still introduce. A block longer than the
+    // window gets a proportionally larger allowance.
+    fn allowance(&self, block_sentences: usize) -> usize {
+        let window_sentences = INTRODUCTION_WINDOW_SENTENCES.max(block_sentences);
+        let cap = (self.max_per_window * window_sentences as f32 / INTRODUCTION_WINDOW_SENTENCES as f32).floor() as usize;
+        let mut covered = block_sentences;
+        let mut introduced = 0;
+        for &(sentences, count) in self.recent.iter().rev() {
+            if covered >= window_sentences {
+                break;
+            }
+            covered += sentences;
+            introduced += count;
+        }
+        cap.saturating_sub(introduced)
+    }
+
+    fn record(&mut self, sentences: usize, introduced: usize) {
+        self.recent.push_back((sentences, introduced));
+        trim_introduction_history(&mut self.recent);
+    }
+}
+
+// Drops blocks that no longer reach into the window of the next block.
+fn trim_introduction_history(recent: &mut VecDeque<(usize, usize)>) {
+    let mut total: usize = recent.iter().map(|&(sentences, _)| sentences).sum();
+    while let Some(&(oldest, _)) = recent.front() {
+        if total - oldest < INTRODUCTION_WINDOW_SENTENCES {
+            break;
+        }
+        total -= oldest;
+        recent.pop_front();
+    }
+}
+
+/// Appends the blocks of `results` to `history` (OrchestratorParams::recent_introductions),
+/// keeping only what the introduction cap's window still needs.
+pub fn extend_introduction_history(history: &mut Vec<(usize, usize)>, results: &[ChapterRunResult]) {
+    let mut recent: VecDeque<(usize, usize)> = history.drain(..).collect();
+    for block in results.iter().flat_map(|result| &result.blocks) {
+        recent.push_back((block.info.sentence_count, block.new_lemmas));
+        trim_introduction_history(&mut recent);
+    }
+    history.extend(recent);
+}
+
+/// Describes the block currently being processed, passed to every observer callback.
+#[derive(Debug, Clone)]
+pub struct BlockInfo {
+    pub block_index: usize,             // 1-based
+    pub first_sentence_position: usize, // Position in the (possibly multi-pass) sentence stream
+    pub sentence_count: usize,
+    pub total_sentences: usize,         // Sentences in the whole stream (chapter length * passes)
+    pub chapter_sentence_count: usize,
+    pub chapter_title: Option<String>,  // CHAPTER:: title of the source chapter, if it has one
+}
+
+impl BlockInfo {
+    /// Index of the block's first sentence within the source chapter.
+    pub fn first_chapter_sentence_idx(&self) -> usize {
+        self.first_sentence_position % self.chapter_sentence_count.max(1)
+    }
+
+    /// 1-based pass number the block starts in.
+    pub fn pass_number(&self) -> usize {
+        self.first_sentence_position / self.chapter_sentence_count.max(1) + 1
+    }
+}
+
+/// Hooks for front-ends to report progress and collect rendered text.
+/// All methods default to no-ops.
+pub trait OrchestratorObserver {
+    fn on_block_start(&mut self, _block: &BlockInfo, _profile: &NumericalLearnerProfile) {}
+    /// Called before on_block_start when Known lemmas slid back to Active.
+    fn on_lemmas_decayed(&mut self, _block: &BlockInfo, _lemma_ids: &[u32]) {}
+    /// Called after core_algo finalizes a block and its delta was applied to the profile,
+    /// with the lemmas whose state the block changed.
+    fn on_block_simulated(
+        &mut self,
+        _block: &BlockInfo,
+        _profile_after: &NumericalLearnerProfile,
+        _result: &SimulationBlockResult,
+        _transitions: &[LemmaTransition],
+    ) {}
+    fn on_block_text(&mut self, _block: &BlockInfo, _text: &str) {}
+    /// The level each sentence of the block was rendered at, reported with on_block_text.
+    fn on_block_levels(&mut self, _block: &BlockInfo, _levels: &[SentenceLevelRecord]) {}
+    /// The full rendering of a block with its source sentences and the profile it was
+    /// rendered against, for exporters that need more than plain text.
+    fn on_block_rendered(
+        &mut self,
+        _block: &BlockInfo,
+        _sentences: &[&ProcessedSentence],
+        _generated: &GeneratedTextBlock,
+        _profile_for_text: &NumericalLearnerProfile,
+    ) {}
+    fn on_block_error(&mut self, _block: &BlockInfo, _error: &str) {}
+    /// Asked before every block; returning true ends the run there (summary.cancelled).
+    fn should_stop(&self) -> bool {
+        false
+    }
+}
+
+pub struct NoopObserver;
+impl OrchestratorObserver for NoopObserver {}
+
+#[derive(Debug, Clone, Default)]
+pub struct OrchestratorRunSummary {
+    pub blocks_processed: usize,
+    pub sentences_processed: usize,
+    pub failed_blocks: usize,
+    pub decayed_lemmas: usize,
+    pub new_lemmas: usize,
+    pub capped_blocks: usize, // Blocks offered fewer candidates because of the introduction cap
+    pub halted_on_error: bool,
+    pub cancelled: bool, // The observer asked to stop before every block was processed
+}
+
+/// Drives block slicing, activation-list preparation, core_algo and text generation
+/// for one chapter. Shared by the GUI and the corpus generator.
+pub struct Orchestrator<'a> {
+    string_chapter: &'a ProcessedChapter,
+    numerical_chapter: &'a NumericalChapter,
+    chapter: Block<'a>, // The whole chapter, which every block is a slice of
+    params: OrchestratorParams,
+    corpus_frequency: Option<&'a CorpusFrequency>,
+    remaining_frequency: Option<&'a CorpusFrequency>,
+}
+
+impl<'a> Orchestrator<'a> {
+    pub fn new(
+        string_chapter: &'a ProcessedChapter,
+        numerical_chapter: &'a NumericalChapter,
+        params: OrchestratorParams,
+    ) -> Result<Self, WeaveLangError> {
+        let chapter = Block::whole_chapter(string_chapter, numerical_chapter)?;
+        Ok(Self { string_chapter, numerical_chapter, chapter, params, corpus_frequency: None, remaining_frequency: None })
+    }
+
+    /// Ranks activation candidates against lemma counts from a wider corpus instead
+    /// of this chapter alone.
+    pub fn with_corpus_frequency(mut self, corpus_frequency: &'a CorpusFrequency) -> Self {
+        self.corpus_frequency = Some(corpus_frequency);
+        self
+    }
+
+    /// Counts of this chapter and the rest of the sequence, for the scheduler's payoff term
+    /// (SchedulerParams::remaining_frequency_weight).
+    pub fn with_remaining_frequency(mut self, remaining_frequency: &'a CorpusFrequency) -> Self {
+        self.remaining_frequency = Some(remaining_frequency);
+        self
+    }
+
+    pub fn params(&self) -> &OrchestratorParams {
+        &self.params
+    }
+
+    /// Runs every block of the chapter (for the configured number of passes),
+    /// updating `profile` in place after each successfully simulated block.
+    pub fn run(
+        &self,
+        profile: &mut NumericalLearnerProfile,
+        dictionary: &GlobalLemmaDictionary,
+        observer: &mut dyn OrchestratorObserver,
+    ) -> OrchestratorRunSummary {
+        let mut summary = OrchestratorRunSummary::default();
+        let chapter_sentence_count = self.numerical_chapter.sentences_numerical.len();
+        if chapter_sentence_count == 0 {
+            return summary;
+        }
+        let total_sentences = chapter_sentence_count * self.params.passes.max(1);
+        let sentences_per_block = self.params.sentences_per_block.max(1);
+        let mut scheduler = ActivationScheduler::new(
+            self.numerical_chapter,
+            self.corpus_frequency,
+            self.params.scheduler,
+            self.params.min_diglot_confidence,
+        );
+        if let Some(remaining_frequency) = self.remaining_frequency {
+            scheduler = scheduler.with_remaining_frequency(remaining_frequency);
+        }
+
+        // Frequency-weighted CT and frequency-ranked L4 use the same counts as the scheduler.
+        let needs_frequency = self.params.ct_metric == CtMetricKind::Frequency || self.params.l4_strategy == L4Strategy::HighestFrequency;
+        let chapter_frequency = (self.corpus_frequency.is_none() && needs_frequency).then(|| {
+            let mut own = CorpusFrequency::new();
+            own.add_chapter(self.numerical_chapter, self.params.min_diglot_confidence);
+            own
+        });
+        let frequency = self.corpus_frequency.or(chapter_frequency.as_ref());
+        let ct_metric = self.params.ct_metric.build(frequency.map_or_else(|| Cow::Owned(CorpusFrequency::new()), Cow::Borrowed));
+        let base_tokenizer = tokenizer::tokenizer_for_language(&self.string_chapter.language_pair.base);
+        let target_tokenizer = tokenizer::tokenizer_for_language(&self.string_chapter.language_pair.target);
+        let l4 = L4Settings {
+            min_diglot_confidence: self.params.min_diglot_confidence,
+            base_tokenizer: base_tokenizer.as_ref(),
+            plural_rules: self.params.l4_match_plurals.then_some(target_tokenizer.as_ref()),
+            strategy: self.params.l4_strategy,
+            corpus_frequency: frequency,
+        };
+
+        let mut introduction_window = self.params.max_new_lemmas_per_100_sentences
+            .map(|max| IntroductionWindow::new(max.max(0.0), &self.params.recent_introductions));
+
+        let mut position = 0;
+        while position < total_sentences {
+            if observer.should_stop() {
+                summary.cancelled = true;
+                break;
+            }
+            let end_position = self.chapter.block_end(position, sentences_per_block, self.params.block_boundaries, total_sentences);
+            let block = self.chapter.with_positions(position..end_position);
+
+            let block_info = BlockInfo {
+                block_index: summary.blocks_processed + 1,
+                first_sentence_position: position,
+                sentence_count: end_position - position,
+                total_sentences,
+                chapter_sentence_count,
+                chapter_title: self.string_chapter.chapter_title.clone(),
+            };
+            let _block_span = info_span!("block", index = block_info.block_index, first_sentence = position).entered();
+            profile.advance_block_clock();
+            let decayed_lemma_ids = profile.apply_decay(&self.params.decay);
+            if !decayed_lemma_ids.is_empty() {
+                summary.decayed_lemmas += decayed_lemma_ids.len();
+                observer.on_lemmas_decayed(&block_info, &decayed_lemma_ids);
+            }
+            observer.on_block_start(&block_info, profile);
+
+            let mut activation_candidates = scheduler.rank_candidates(&block, profile, end_position, total_sentences);
+            let mut cap_message = None;
+            if let Some(window) = &introduction_window {
+                let allowance = window.allowance(block_info.sentence_count);
+                if activation_candidates.len() > allowance {
+                    cap_message = Some(format!("  Introduction cap: {} of {} activation candidate(s) offered.", allowance, activation_candidates.len()));
+                    activation_candidates.truncate(allowance);
+                    summary.capped_blocks += 1;
+                }
+            }
+            let block_failed = match core_algo::run_simulation_numerical(
+                &block,
+                profile,
+                &activation_candidates,
+                &BlockSimulationSettings {
+                    max_regeneration_attempts_per_block: self.params.max_regen_attempts_per_block,
+                    target_ct_comprehensible_threshold: self.params.target_ct_threshold,
+                    min_ct_comprehensible_threshold: self.params.min_ct_threshold,
+                    max_words_to_activate_per_regen_attempt: self.params.max_words_to_activate_per_regen,
+                    l4,
+                    ct_metric: ct_metric.as_ref(),
+                    level_policy: &self.params.level_policy,
+                },
+            ) {
+                Ok(mut block_simulation_result) => {
+                    if let Some(message) = cap_message {
+                        block_simulation_result.simulation_log_entries.insert(1, message);
+                    }
+                    scheduler.record_exposures(&block_simulation_result.output_lemma_ids_for_block);
+                    let introduced = block_simulation_result.introduced_lemma_ids.len();
+                    summary.new_lemmas += introduced;
+                    if let Some(window) = &mut introduction_window {
+                        window.record(block_info.sentence_count, introduced);
+                    }
+                    // The exposures happened regardless of whether rendering succeeds.
+                    let transitions = block_simulation_result.profile_delta.apply(profile);
+                    observer.on_block_simulated(&block_info, profile, &block_simulation_result, &transitions);
+                    let text_result = text_generator::generate_final_text_block(
+                        &block,
+                        &block_simulation_result.profile_for_text_generation,
+                        &l4,
+                        self.params.prefix_level_tags,
+                        &self.params.level_policy,
+                    );
+                    match text_result {
+                        Ok(generated_block) => {
+                            if self.params.track_forms {
+                                for ((sentence, text), record) in block.string_sentences()
+                                    .zip(&generated_block.sentence_texts)
+                                    .zip(&generated_block.sentence_levels)
+                                {
+                                    let words = trace_sentence_words(sentence, text, record.level, dictionary,
+                                        &block_simulation_result.profile_for_text_generation, target_tokenizer.as_ref());
+                                    profile.record_form_exposures(words.iter().map(|word| &text[word.range.clone()]));
+                                }
+                            }
+                            observer.on_block_text(&block_info, &generated_block.text);
+                            observer.on_block_levels(&block_info, &generated_block.sentence_levels);
+                            let block_string_sentences: Vec<&ProcessedSentence> = block.string_sentences().collect();
+                            observer.on_block_rendered(
+                                &block_info,
+                                &block_string_sentences,
+                                &generated_block,
+                                &block_simulation_result.profile_for_text_generation,
+                            );
+                            false
+                        }
+                        Err(e) => {
+                            observer.on_block_error(&block_info, &format!("Text generation failed: {}", e));
+                            true
+                        }
+                    }
+                }
+                Err(e) => {
+                    if let Some(window) = &mut introduction_window {
+                        window.record(block_info.sentence_count, 0);
+                    }
+                    observer.on_block_error(&block_info, &format!("Core simulation failed: {}", e));
+                    true
+                }
+            };
+
+            summary.blocks_processed += 1;
+            summary.sentences_processed += block_info.sentence_count;
+            if block_failed {
+                summary.failed_blocks += 1;
+                if self.params.halt_on_block_error {
+                    summary.halted_on_error = true;
+                    break;
+                }
+            }
+            position = end_position;
+        }
+        summary
+    }
+}
+
+/// A chapter in both forms, as produced by the parser and preprocessor::to_numerical_chapter.
+#[derive(Debug, Clone, Copy)]
+pub struct ChapterInput<'a> {
+    pub string_chapter: &'a ProcessedChapter,
+    pub numerical_chapter: &'a NumericalChapter,
+}
+
+/// Outcome of one block, as recorded by run_chapters. run_chapters_observed leaves
+/// `text`, `levels` and `log` empty; its observer receives them as they are produced.
+#[derive(Debug, Clone)]
+pub struct BlockRecord {
+    pub info: BlockInfo,
+    pub ct: f32,
+    pub ct_metric_name: &'static str,
+    pub known_lemmas: usize,
+    pub total_target_lemmas: usize,
+    pub known_after: usize,        // Profile counts after the block's exposures
+    pub active_only_after: usize,
+    pub new_lemmas: usize,         // Lemmas the block introduced (New -> Active/Known)
+    pub text: String,
+    pub levels: Vec<SentenceLevelRecord>,
+    pub log: Vec<String>, // core_algo's log lines for the block
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChapterRunResult {
+    pub source_file_name: String,
+    pub summary: OrchestratorRunSummary,
+    pub blocks: Vec<BlockRecord>,
+}
+
+impl ChapterRunResult {
+    /// The chapter's rendered blocks joined the way the TTS output joins them.
+    pub fn woven_text(&self) -> String {
+        self.blocks.iter()
+            .map(|b| b.text.as_str())
+            .filter(|t| !t.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+// Records every block into a BlockRecord while forwarding all callbacks to `inner`.
+// The block's text, levels and log are only copied with `record_output`.
+struct RecordingObserver<'o> {
+    inner: &'o mut dyn OrchestratorObserver,
+    blocks: Vec<BlockRecord>,
+    record_output: bool,
+}
+
+impl RecordingObserver<'_> {
+    fn current(&mut self, block: &BlockInfo) -> Option<&mut BlockRecord> {
+        self.blocks.last_mut().filter(|record| record.info.block_index == block.block_index)
+    }
+
+    fn current_output(&mut self, block: &BlockInfo) -> Option<&mut BlockRecord> {
+        if self.record_output { self.current(block) } else { None }
+    }
+}
+
+impl OrchestratorObserver for RecordingObserver<'_> {
+    fn on_block_start(&mut self, block: &BlockInfo, profile: &NumericalLearnerProfile) {
+        self.blocks.push(BlockRecord {
+            info: block.clone(),
+            ct: 0.0,
+            ct_metric_name: "",
+            known_lemmas: 0,
+            total_target_lemmas: 0,
+            known_after: profile.count_known(),
+            active_only_after: profile.count_active_only(),
+            new_lemmas: 0,
+            text: String::new(),
+            levels: Vec::new(),
+            log: Vec::new(),
+            error: None,
+        });
+        self.inner.on_block_start(block, profile);
+    }
+
+    fn on_lemmas_decayed(&mut self, block: &BlockInfo, lemma_ids: &[u32]) {
+        self.inner.on_lemmas_decayed(block, lemma_ids);
+    }
+
+    fn on_block_simulated(&mut self, block: &BlockInfo, profile_after: &NumericalLearnerProfile, result: &SimulationBlockResult, transitions: &[LemmaTransition]) {
+        if let Some(record) = self.current(block) {
+            record.ct = result.final_ct_for_block;
+            record.ct_metric_name = result.ct_metric_name;
+            record.known_lemmas = result.known_lemmas_in_block;
+            record.total_target_lemmas = result.total_target_lemmas_in_block;
+            record.known_after = profile_after.count_known();
+            record.active_only_after = profile_after.count_active_only();
+            record.new_lemmas = result.introduced_lemma_ids.len();
+        }
+        if let Some(record) = self.current_output(block) {
+            record.log = result.simulation_log_entries.clone();
+        }
+        self.inner.on_block_simulated(block, profile_after, result, transitions);
+    }
+
+    fn on_block_text(&mut self, block: &BlockInfo, text: &str) {
+        if let Some(record) = self.current_output(block) {
+            record.text = text.to_string();
+        }
+        self.inner.on_block_text(block, text);
+    }
+
+    fn on_block_levels(&mut self, block: &BlockInfo, levels: &[SentenceLevelRecord]) {
+        if let Some(record) = self.current_output(block) {
+            record.levels = levels.to_vec();
+        }
+        self.inner.on_block_levels(block, levels);
+    }
+
+    fn on_block_rendered(
+        &mut self,
+        block: &BlockInfo,
+        sentences: &[&ProcessedSentence],
+        generated: &GeneratedTextBlock,
+        profile_for_text: &NumericalLearnerProfile,
+    ) {
+        self.inner.on_block_rendered(block, sentences, generated, profile_for_text);
+    }
+
+    fn on_block_error(&mut self, block: &BlockInfo, error: &str) {
+        if let Some(record) = self.current(block) {
+            record.error = Some(error.to_string());
+        }
+        self.inner.on_block_error(block, error);
+    }
+
+    fn should_stop(&self) -> bool {
+        self.inner.should_stop()
+    }
+}
+
+/// Simulates `chapters` in order against `profile`, which is updated in place, and
+/// returns what every block produced. Activation candidates are ranked against the
+/// lemma counts of the chapters read so far. The entry point for library users.
+pub fn run_chapters(
+    chapters: &[ChapterInput],
+    profile: &mut NumericalLearnerProfile,
+    dictionary: &GlobalLemmaDictionary,
+    params: &OrchestratorParams,
+) -> Result<Vec<ChapterRunResult>, WeaveLangError> {
+    run_chapters_recorded(chapters, profile, dictionary, params, None, None, &mut NoopObserver, true)
+}
+
+/// run_chapters with per-block callbacks. The observer receives each block's text, levels
+/// and log as they are produced, so the returned BlockRecords leave them empty. With
+/// `corpus_frequency`, candidates are ranked against those counts instead (the caller
+/// keeps them up to date). Likewise for `remaining_frequency`, the counts of these chapters
+/// and whatever follows them, used when the scheduler's remaining_frequency_weight is set;
+/// without it the remainder of `chapters` is used.
+pub fn run_chapters_observed(
+    chapters: &[ChapterInput],
+    profile: &mut NumericalLearnerProfile,
+    dictionary: &GlobalLemmaDictionary,
+    params: &OrchestratorParams,
+    corpus_frequency: Option<&CorpusFrequency>,
+    remaining_frequency: Option<&CorpusFrequency>,
+    observer: &mut dyn OrchestratorObserver,
+) -> Result<Vec<ChapterRunResult>, WeaveLangError> {
+    run_chapters_recorded(chapters, profile, dictionary, params, corpus_frequency, remaining_frequency, observer, false)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_chapters_recorded(
+    chapters: &[ChapterInput],
+    profile: &mut NumericalLearnerProfile,
+    dictionary: &GlobalLemmaDictionary,
+    params: &OrchestratorParams,
+    corpus_frequency: Option<&CorpusFrequency>,
+    remaining_frequency: Option<&CorpusFrequency>,
+    observer: &mut dyn OrchestratorObserver,
+    record_output: bool,
+) -> Result<Vec<ChapterRunResult>, WeaveLangError> {
+    for chapter in chapters {
+        check_chapter_pair(chapter.string_chapter, chapter.numerical_chapter)?;
+    }
+    let mut chapters_remaining = CorpusFrequency::new();
+    let use_chapters_remaining = remaining_frequency.is_none() && params.scheduler.remaining_frequency_weight > 0.0;
+    if use_chapters_remaining {
+        for chapter in chapters {
+            chapters_remaining.add_chapter(chapter.numerical_chapter, params.min_diglot_confidence);
+        }
+    }
+    let mut chapters_read = CorpusFrequency::new();
+    let mut recorder = RecordingObserver { inner: observer, blocks: Vec::new(), record_output };
+    let mut results: Vec<ChapterRunResult> = Vec::with_capacity(chapters.len());
+    let mut recent_introductions = params.recent_introductions.clone();
+    for chapter in chapters {
+        let chapter_params = OrchestratorParams { recent_introductions: recent_introductions.clone(), ..params.clone() };
+        let mut orchestrator = Orchestrator::new(chapter.string_chapter, chapter.numerical_chapter, chapter_params)?;
+        if let Some(remaining) = remaining_frequency.or(use_chapters_remaining.then_some(&chapters_remaining)) {
+            orchestrator = orchestrator.with_remaining_frequency(remaining);
+        }
+        let summary = match corpus_frequency {
+            Some(shared) => orchestrator.with_corpus_frequency(shared).run(profile, dictionary, &mut recorder),
+            None => {
+                chapters_read.add_chapter(chapter.numerical_chapter, params.min_diglot_confidence);
+                orchestrator.with_corpus_frequency(&chapters_read).run(profile, dictionary, &mut recorder)
+            }
+        };
+        if use_chapters_remaining {
+            chapters_remaining.remove_chapter(chapter.numerical_chapter, params.min_diglot_confidence);
+        }
+        results.push(ChapterRunResult {
+            source_file_name: chapter.string_chapter.source_file_name.clone(),
+            summary,
+            blocks: std::mem::take(&mut recorder.blocks),
+        });
+        extend_introduction_history(&mut recent_introductions, &results[results.len() - 1..]);
+        if results[results.len() - 1].summary.cancelled {
+            break;
+        }
+    }
+    Ok(results)
+}
+//*** END FILE: src/simulation/orchestrator.rs ***//
@@ -6,12 +6,16 @@ use crate::types::llm_data::{
     // We don't need to explicitly import their type names here unless we were
     // creating them or using their type names in function signatures within this file.
 };
+use crate::types::llm_data::SegmentLemmas;
+use crate::lemmatizer::Lemmatizer;
+use crate::parsing::validation::{protected_occurrence_kind, protected_sim_e_spans};
+use crate::tokenizer::{self, Tokenizer};
+use tracing::warn;
 use super::dictionary::GlobalLemmaDictionary;
 use super::numerical_types::{
     NumericalChapter,
     NumericalProcessedSentence,
     NumericalSegmentData,
-    NumericalPhraseAlignment,
     NumericalSegmentLemmas,
     NumericalDiglotSegmentMap,
     NumericalDiglotEntry,
@@ -22,8 +26,12 @@ pub fn to_numerical_chapter(
     dictionary: &mut GlobalLemmaDictionary, // Mutable to insert new lemma IDs if encountered
 ) -> NumericalChapter {
     let mut sentences_numerical = Vec::with_capacity(string_chapter.sentences.len());
+    let base_tokenizer = tokenizer::tokenizer_for_language(&string_chapter.language_pair.base);
 
     for s_sentence in &string_chapter.sentences { // s_sentence is &llm_data::ProcessedSentence
+        // Names, quotes and locked phrases in SimE are off limits to L4. Entries whose word only
+        // occurs there are made non-viable (validation reports them as ProtectedDiglotEntry).
+        let protected_sim_e_spans = protected_sim_e_spans(s_sentence, base_tokenizer.as_ref());
         let adv_s_lemma_ids: Vec<u32> = s_sentence
             .adv_s_lemmas
             .iter()
@@ -65,14 +73,31 @@ pub fn to_numerical_chapter(
                 entries: s_diglot_map
                     .entries
                     .iter()
-                    .filter_map(|s_entry| { // s_entry is &llm_data::DiglotEntry
+                    .zip(s_diglot_map.entries_inside_mwe())
+                    .filter_map(|(s_entry, inside_mwe)| { // s_entry is &llm_data::DiglotEntry
                         let cleaned_spa_lemma = s_entry.spa_lemma.trim();
                         if !cleaned_spa_lemma.is_empty() {
                             Some(NumericalDiglotEntry {
                                 eng_word_original: s_entry.eng_word.clone(),
                                 spa_lemma_id: dictionary.get_id_or_insert(cleaned_spa_lemma),
                                 exact_spa_form_original: s_entry.exact_spa_form.clone(),
-                                viable: s_entry.viable,
+                                confidence: match protected_occurrence_kind(
+                                    &s_sentence.sim_e,
+                                    &s_entry.eng_word,
+                                    s_sentence.is_segment_locked(&s_diglot_map.segment_id),
+                                    &protected_sim_e_spans,
+                                    base_tokenizer.as_ref(),
+                                ) {
+                                    Some(kind) => {
+                                        warn!(
+                                            "Sentence {}: DIGLOT_MAP entry '{}' -> '{}' only occurs inside {} in SimE; it will not be substituted.",
+                                            s_sentence.sentence_id, s_entry.eng_word, cleaned_spa_lemma, kind.describe()
+                                        );
+                                        0.0
+                                    }
+                                    None => s_entry.confidence,
+                                },
+                                inside_mwe,
                             })
                         } else {
                             // Optionally log if a diglot entry has an empty spa_lemma
@@ -89,31 +114,21 @@ pub fn to_numerical_chapter(
             .iter()
             .map(|s_seg_data| NumericalSegmentData { // s_seg_data is &llm_data::SegmentData
                 id_str: s_seg_data.id.clone(),
-                text_original: s_seg_data.text.clone(),
-            })
-            .collect();
-
-        let phrase_alignments_numerical: Vec<NumericalPhraseAlignment> = s_sentence
-            .phrase_alignments
-            .iter()
-            .map(|s_pa| NumericalPhraseAlignment { // s_pa is &llm_data::PhraseAlignment
-                segment_id_str: s_pa.segment_id.clone(),
-                adv_s_span_original: s_pa.adv_s_span.clone(),
-                sim_e_span_original: s_pa.sim_e_span.clone(),
             })
             .collect();
 
         let n_sentence = NumericalProcessedSentence {
             sentence_id_str: s_sentence.sentence_id.clone(),
-            adv_s_original: s_sentence.adv_s.clone(),
-            sim_s_original: s_sentence.sim_s.clone(),
+            has_sim_s: !s_sentence.sim_s.trim().is_empty(),
             sim_e_original: s_sentence.sim_e.clone(),
             sim_s_segments_numerical,
-            phrase_alignments_numerical,
             sim_s_lemmas_numerical,
             adv_s_lemma_ids,
             diglot_map_numerical,
             locked_phrase_segment_id_strs: s_sentence.locked_phrases.clone(),
+            adv_s_grammar: s_sentence.adv_s_grammar.clone(),
+            sim_s_grammar: s_sentence.sim_s_grammar.clone(),
+            protected_sim_e_spans,
         };
         sentences_numerical.push(n_sentence);
     }
@@ -123,4 +138,73 @@ pub fn to_numerical_chapter(
         sentences_numerical,
     }
 }
+
+/// Rewrites every lemma ID in `chapter` through `id_map` (index = old ID).
+pub fn remap_chapter_lemma_ids(chapter: &mut NumericalChapter, id_map: &[u32]) {
+    for sentence in &mut chapter.sentences_numerical {
+        for lemma_id in &mut sentence.adv_s_lemma_ids {
+            *lemma_id = id_map[*lemma_id as usize];
+        }
+        for segment_lemmas in &mut sentence.sim_s_lemmas_numerical {
+            for lemma_id in &mut segment_lemmas.lemma_ids {
+                *lemma_id = id_map[*lemma_id as usize];
+            }
+        }
+        for segment_map in &mut sentence.diglot_map_numerical {
+            for entry in &mut segment_map.entries {
+                entry.spa_lemma_id = id_map[entry.spa_lemma_id as usize];
+            }
+        }
+    }
+}
+
+/// Moves a chapter that was converted against its own `local_dictionary` (e.g. on a
+/// worker thread) onto the shared `dictionary`. New lemmas are inserted in the order the
+/// local dictionary first saw them, so the IDs match what to_numerical_chapter would
+/// have assigned against `dictionary` directly.
+pub fn merge_into_dictionary(
+    mut chapter: NumericalChapter,
+    local_dictionary: &GlobalLemmaDictionary,
+    dictionary: &mut GlobalLemmaDictionary,
+) -> NumericalChapter {
+    let id_map: Vec<u32> = local_dictionary.id_to_str.iter()
+        .map(|lemma| dictionary.get_id_or_insert(lemma))
+        .collect();
+    remap_chapter_lemma_ids(&mut chapter, &id_map);
+    chapter
+}
+
+/// SimSL-style lemmas for raw target-language text, one per token after clitic splitting.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RawLemmatization {
+    pub lemmas: Vec<String>,
+    pub guesses: usize, // Words no lemmatizer knew; their lowercased form stands in as the lemma
+}
+
+impl RawLemmatization {
+    pub fn into_segment_lemmas(self, segment_id: &str) -> SegmentLemmas {
+        SegmentLemmas { segment_id: segment_id.to_string(), lemmas: self.lemmas }
+    }
+}
+
+/// Lemmatizes a raw sentence or segment (e.g. "Dámelo mañana."). Each token is looked up
+/// whole first; unknown tokens are split into clitics ("dame" + "lo") and each part looked
+/// up, so "dámelo" -> "dar yo lo" when the parts are known.
+pub fn lemmatize_raw(text: &str, lemmatizer: &dyn Lemmatizer, form_tokenizer: &dyn Tokenizer) -> RawLemmatization {
+    let mut result = RawLemmatization::default();
+    for token in form_tokenizer.tokenize(text) {
+        let form = token.text.to_lowercase();
+        if let Some(lemma) = lemmatizer.lemma_of(&form) {
+            result.lemmas.push(lemma);
+            continue;
+        }
+        for part in form_tokenizer.split_clitics(&form) {
+            match lemmatizer.lemma_of(&part) {
+                Some(lemma) => result.lemmas.push(lemma),
+                None => { result.guesses += 1; result.lemmas.push(part); }
+            }
+        }
+    }
+    result
+}
 //*** END FILE: src/simulation/preprocessor.rs ***//
\ No newline at end of file
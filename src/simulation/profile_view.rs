@@ -0,0 +1,238 @@
+//*** START FILE: src/simulation/profile_view.rs ***//
+// A finalized block leaves two things behind: the learner as its text is to be rendered
+// (ProfileView) and what reading it changes for the learner (ProfileDelta). Only the
+// delta touches the caller's profile, so the learner's state advances in one place.
+//
+// The view is the profile before the block plus the lemmas and grammar features the regen
+// cycle activated, minus the Active lemmas it withheld from this block's text. The delta
+// holds the same activations (withheld lemmas stay with the learner) and one exposure per
+// lemma occurrence and grammar feature in the rendered text.
+//
+// While the regen cycle runs, its passes read the learner through LearnerStates: a
+// ProfileOverlay keeps the states the cycle set on top of the untouched profile, and only
+// the finalized pass materializes them into the view. The overlay answers state queries
+// from copies of the profile's bitsets of the Known and the Known-or-Active lemmas: every
+// pass tests every lemma of every sentence, and the bitsets spare those hash lookups.
+
+use crate::profile::LemmaState;
+use crate::simulation::lemma_bitset::LemmaBitset;
+use crate::simulation::numerical_types::NumericalLearnerProfile;
+use std::collections::HashMap;
+use std::ops::Deref;
+
+/// The learner state a regen pass reads. Lemmas and features the learner has never met are New.
+pub trait LearnerStates {
+    fn lemma_state(&self, lemma_id: u32) -> LemmaState;
+    fn exposure_count(&self, lemma_id: u32) -> u32;
+    fn grammar_state(&self, feature: &str) -> LemmaState;
+
+    fn is_lemma_known(&self, lemma_id: u32) -> bool {
+        self.lemma_state(lemma_id) == LemmaState::Known
+    }
+
+    fn is_lemma_known_or_active(&self, lemma_id: u32) -> bool {
+        matches!(self.lemma_state(lemma_id), LemmaState::Known | LemmaState::Active)
+    }
+
+    fn is_grammar_known_or_active(&self, feature: &str) -> bool {
+        matches!(self.grammar_state(feature), LemmaState::Known | LemmaState::Active)
+    }
+
+    /// Whether every feature in `features` is Active or Known (vacuously true for none).
+    fn has_grammar(&self, features: &[String]) -> bool {
+        features.iter().all(|feature| self.is_grammar_known_or_active(feature))
+    }
+}
+
+impl LearnerStates for NumericalLearnerProfile {
+    fn lemma_state(&self, lemma_id: u32) -> LemmaState {
+        state_of(self, lemma_id)
+    }
+
+    fn is_lemma_known(&self, lemma_id: u32) -> bool {
+        NumericalLearnerProfile::is_lemma_known(self, lemma_id)
+    }
+
+    fn is_lemma_known_or_active(&self, lemma_id: u32) -> bool {
+        NumericalLearnerProfile::is_lemma_known_or_active(self, lemma_id)
+    }
+
+    fn exposure_count(&self, lemma_id: u32) -> u32 {
+        self.get_lemma_info(lemma_id).map_or(0, |info| info.exposure_count)
+    }
+
+    fn grammar_state(&self, feature: &str) -> LemmaState {
+        self.grammar.get(feature).map_or(LemmaState::New, |info| info.state)
+    }
+}
+
+/// States set during a regen cycle, over the profile the block started from. Exposure
+/// counts always come from that profile.
+#[derive(Debug, Clone)]
+pub struct ProfileOverlay<'a> {
+    base: &'a NumericalLearnerProfile,
+    lemma_states: HashMap<u32, LemmaState>,
+    grammar_states: HashMap<String, LemmaState>,
+    known: LemmaBitset,
+    known_or_active: LemmaBitset,
+}
+
+impl<'a> ProfileOverlay<'a> {
+    pub fn new(base: &'a NumericalLearnerProfile) -> Self {
+        Self {
+            base,
+            lemma_states: HashMap::new(),
+            grammar_states: HashMap::new(),
+            known: base.known_lemmas().clone(),
+            known_or_active: base.known_or_active_lemmas().clone(),
+        }
+    }
+
+    pub fn set_lemma_state(&mut self, lemma_id: u32, state: LemmaState) {
+        self.lemma_states.insert(lemma_id, state);
+        self.known.set(lemma_id, state == LemmaState::Known);
+        self.known_or_active.set(lemma_id, state != LemmaState::New);
+    }
+
+    pub fn set_grammar_state(&mut self, feature: &str, state: LemmaState) {
+        self.grammar_states.insert(feature.to_string(), state);
+    }
+
+    pub fn count_known(&self) -> usize {
+        self.known.len()
+    }
+
+    pub fn count_active_only(&self) -> usize {
+        self.known_or_active.len() - self.known.len()
+    }
+
+    /// The base profile with the overlay's states written into it.
+    pub fn materialize(&self) -> NumericalLearnerProfile {
+        let mut profile = self.base.clone();
+        for (&lemma_id, &state) in &self.lemma_states {
+            profile.set_lemma_state(lemma_id, state);
+        }
+        for (feature, &state) in &self.grammar_states {
+            profile.set_grammar_state(feature, state);
+        }
+        profile
+    }
+}
+
+impl LearnerStates for ProfileOverlay<'_> {
+    fn lemma_state(&self, lemma_id: u32) -> LemmaState {
+        if self.known.contains(lemma_id) {
+            LemmaState::Known
+        } else if self.known_or_active.contains(lemma_id) {
+            LemmaState::Active
+        } else {
+            LemmaState::New
+        }
+    }
+
+    fn is_lemma_known(&self, lemma_id: u32) -> bool {
+        self.known.contains(lemma_id)
+    }
+
+    fn is_lemma_known_or_active(&self, lemma_id: u32) -> bool {
+        self.known_or_active.contains(lemma_id)
+    }
+
+    fn exposure_count(&self, lemma_id: u32) -> u32 {
+        self.base.exposure_count(lemma_id)
+    }
+
+    fn grammar_state(&self, feature: &str) -> LemmaState {
+        self.grammar_states.get(feature).copied().unwrap_or_else(|| self.base.grammar_state(feature))
+    }
+}
+
+/// Read-only profile a block's text is rendered against.
+#[derive(Debug, Clone)]
+pub struct ProfileView {
+    profile: NumericalLearnerProfile,
+}
+
+impl ProfileView {
+    pub(crate) fn new(profile: NumericalLearnerProfile) -> Self {
+        Self { profile }
+    }
+}
+
+impl LearnerStates for ProfileView {
+    fn lemma_state(&self, lemma_id: u32) -> LemmaState {
+        self.profile.lemma_state(lemma_id)
+    }
+
+    fn is_lemma_known(&self, lemma_id: u32) -> bool {
+        self.profile.is_lemma_known(lemma_id)
+    }
+
+    fn is_lemma_known_or_active(&self, lemma_id: u32) -> bool {
+        self.profile.is_lemma_known_or_active(lemma_id)
+    }
+
+    fn exposure_count(&self, lemma_id: u32) -> u32 {
+        self.profile.exposure_count(lemma_id)
+    }
+
+    fn grammar_state(&self, feature: &str) -> LemmaState {
+        self.profile.grammar_state(feature)
+    }
+}
+
+impl Deref for ProfileView {
+    type Target = NumericalLearnerProfile;
+
+    fn deref(&self) -> &NumericalLearnerProfile {
+        &self.profile
+    }
+}
+
+/// Changes one block makes to the learner profile; see apply.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProfileDelta {
+    pub activated_lemma_ids: Vec<u32>, // New -> Active in the regen cycle, in activation order
+    pub activated_grammar: Vec<String>,
+    pub exposed_lemma_ids: Vec<u32>, // One entry per occurrence in the block's text
+    pub exposed_grammar: Vec<String>, // Grammar features of the L1/L2 sentences, one entry per occurrence
+}
+
+/// A lemma whose state a block changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LemmaTransition {
+    pub lemma_id: u32,
+    pub before: LemmaState,
+    pub after: LemmaState,
+}
+
+fn state_of(profile: &NumericalLearnerProfile, lemma_id: u32) -> LemmaState {
+    profile.get_lemma_info(lemma_id).map_or(LemmaState::New, |info| info.state)
+}
+
+impl ProfileDelta {
+    /// Applies the activations, then the exposures (which may promote lemmas to Known).
+    /// `profile` should be the one the block was simulated from. Returns the lemmas whose
+    /// state changed, by ascending ID.
+    pub fn apply(&self, profile: &mut NumericalLearnerProfile) -> Vec<LemmaTransition> {
+        let mut touched: Vec<u32> = self.activated_lemma_ids.iter().chain(&self.exposed_lemma_ids).copied().collect();
+        touched.sort_unstable();
+        touched.dedup();
+        let states_before: Vec<LemmaState> = touched.iter().map(|&lemma_id| state_of(profile, lemma_id)).collect();
+
+        for &lemma_id in &self.activated_lemma_ids {
+            profile.set_lemma_state(lemma_id, LemmaState::Active);
+        }
+        for feature in &self.activated_grammar {
+            profile.set_grammar_state(feature, LemmaState::Active);
+        }
+        profile.record_exposures(&self.exposed_lemma_ids);
+        profile.record_grammar_exposures(&self.exposed_grammar);
+
+        touched.into_iter().zip(states_before)
+            .map(|(lemma_id, before)| LemmaTransition { lemma_id, before, after: state_of(profile, lemma_id) })
+            .filter(|transition| transition.before != transition.after)
+            .collect()
+    }
+}
+//*** END FILE: src/simulation/profile_view.rs ***//
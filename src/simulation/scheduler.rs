@@ -0,0 +1,290 @@
+//*** START FILE: src/simulation/scheduler.rs ***//
+// Decides which New lemmas core_algo may activate in a block, in place of a plain
+// in-block frequency sort. Each candidate gets a weighted score from:
+// - block frequency: occurrences in the block being generated,
+// - corpus frequency: occurrences across the chapters seen so far (log-scaled),
+// - recency: the lemma already went by (untranslated) in a recent block,
+// - interval: the lemma recurs soon after the block, so the first review of a newly
+//   activated word lands near the target spacing instead of chapters later,
+// - payoff: block frequency times the lemma's frequency in the rest of the corpus (the
+//   current chapter and those after it), so words that keep paying off win. Needs a
+//   pre-scanned index of the whole sequence; off unless remaining_frequency_weight > 0.
+
+use super::block::Block;
+use super::dictionary::GlobalLemmaDictionary;
+use super::numerical_types::{NumericalChapter, NumericalLearnerProfile, NumericalProcessedSentence};
+use crate::profile::LemmaState;
+use crate::determinism::tie_break_key;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SchedulerParams {
+    pub block_frequency_weight: f32,
+    pub corpus_frequency_weight: f32,
+    pub recency_weight: f32,
+    pub interval_weight: f32,
+    pub remaining_frequency_weight: f32,
+    // Ideal gap, in sentences, between the end of the activation block and the next occurrence.
+    pub target_interval_sentences: usize,
+    // Candidates with equal score and frequency are ordered by lemma ID, or by a hash of
+    // this seed and the lemma ID when set (same seed, same order).
+    pub tie_break_seed: Option<u64>,
+}
+
+impl Default for SchedulerParams {
+    fn default() -> Self {
+        Self {
+            block_frequency_weight: 1.0,
+            corpus_frequency_weight: 0.5,
+            recency_weight: 0.5,
+            interval_weight: 1.0,
+            remaining_frequency_weight: 0.0,
+            target_interval_sentences: 50,
+            tie_break_seed: None,
+        }
+    }
+}
+
+/// Lemma IDs a sentence can expose: AdvSL, SimSL and viable DIGLOT_MAP lemmas.
+pub fn sentence_lemma_ids(sentence: &NumericalProcessedSentence, min_diglot_confidence: f32) -> Vec<u32> {
+    let mut lemma_ids: Vec<u32> = Vec::new();
+    lemma_ids.extend(&sentence.adv_s_lemma_ids);
+    for nsl in &sentence.sim_s_lemmas_numerical {
+        lemma_ids.extend(&nsl.lemma_ids);
+    }
+    for ndsm in &sentence.diglot_map_numerical {
+        for nde in &ndsm.entries {
+            if nde.is_viable(min_diglot_confidence) { lemma_ids.push(nde.spa_lemma_id); }
+        }
+    }
+    lemma_ids
+}
+
+/// Lemma occurrence counts accumulated over one or more chapters.
+#[derive(Debug, Clone, Default)]
+pub struct CorpusFrequency {
+    counts: HashMap<u32, u32>,
+}
+
+impl CorpusFrequency {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_chapter(&mut self, chapter: &NumericalChapter, min_diglot_confidence: f32) {
+        for sentence in &chapter.sentences_numerical {
+            for lemma_id in sentence_lemma_ids(sentence, min_diglot_confidence) {
+                *self.counts.entry(lemma_id).or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// Undoes add_chapter for a chapter that was counted before.
+    pub fn remove_chapter(&mut self, chapter: &NumericalChapter, min_diglot_confidence: f32) {
+        for sentence in &chapter.sentences_numerical {
+            for lemma_id in sentence_lemma_ids(sentence, min_diglot_confidence) {
+                if let Some(count) = self.counts.get_mut(&lemma_id) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+        }
+        self.counts.retain(|_, count| *count > 0);
+    }
+
+    pub fn count(&self, lemma_id: u32) -> u32 {
+        self.counts.get(&lemma_id).copied().unwrap_or(0)
+    }
+
+    pub fn max_count(&self) -> u32 {
+        self.counts.values().copied().max().unwrap_or(0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+/// Lemma counts for the part of a book sequence not read yet, keyed by lemma string so
+/// the whole sequence can be pre-scanned without assigning dictionary IDs ahead of the run.
+#[derive(Debug, Clone, Default)]
+pub struct RemainingCorpusFrequency {
+    counts: HashMap<String, u32>,
+}
+
+impl RemainingCorpusFrequency {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts a chapter whose lemma IDs refer to `dictionary`.
+    pub fn add_chapter(&mut self, chapter: &NumericalChapter, dictionary: &GlobalLemmaDictionary, min_diglot_confidence: f32) {
+        for sentence in &chapter.sentences_numerical {
+            for lemma in sentence_lemma_ids(sentence, min_diglot_confidence).into_iter().filter_map(|id| dictionary.get_str(id)) {
+                *self.counts.entry(lemma.clone()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// Undoes add_chapter once a chapter has been read.
+    pub fn remove_chapter(&mut self, chapter: &NumericalChapter, dictionary: &GlobalLemmaDictionary, min_diglot_confidence: f32) {
+        for sentence in &chapter.sentences_numerical {
+            for lemma in sentence_lemma_ids(sentence, min_diglot_confidence).into_iter().filter_map(|id| dictionary.get_str(id)) {
+                if let Some(count) = self.counts.get_mut(lemma) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+        }
+        self.counts.retain(|_, count| *count > 0);
+    }
+
+    /// The counts of lemmas `dictionary` knows, keyed by their IDs there.
+    pub fn for_dictionary(&self, dictionary: &GlobalLemmaDictionary) -> CorpusFrequency {
+        CorpusFrequency {
+            counts: self.counts.iter()
+                .filter_map(|(lemma, &count)| dictionary.get_id(lemma).map(|id| (id, count)))
+                .collect(),
+        }
+    }
+}
+
+/// Ranks activation candidates block by block for one chapter. Holds the recency
+/// state, so one scheduler should be used for all blocks of a run, and each block's
+/// untranslated lemmas should be passed to record_exposures once it is simulated.
+pub struct ActivationScheduler<'a> {
+    params: SchedulerParams,
+    min_diglot_confidence: f32,
+    corpus_frequency: Cow<'a, CorpusFrequency>,
+    remaining_frequency: Option<&'a CorpusFrequency>,
+    occurrence_positions: HashMap<u32, Vec<usize>>, // Ascending chapter sentence indices
+    chapter_sentence_count: usize,
+    last_seen_block: HashMap<u32, usize>,
+    blocks_ranked: usize,
+}
+
+impl<'a> ActivationScheduler<'a> {
+    /// `corpus_frequency` defaults to the chapter's own counts when not supplied.
+    pub fn new(
+        chapter: &NumericalChapter,
+        corpus_frequency: Option<&'a CorpusFrequency>,
+        params: SchedulerParams,
+        min_diglot_confidence: f32,
+    ) -> Self {
+        let mut occurrence_positions: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (sentence_idx, sentence) in chapter.sentences_numerical.iter().enumerate() {
+            for lemma_id in sentence_lemma_ids(sentence, min_diglot_confidence) {
+                let positions = occurrence_positions.entry(lemma_id).or_default();
+                if positions.last() != Some(&sentence_idx) {
+                    positions.push(sentence_idx);
+                }
+            }
+        }
+        let corpus_frequency = match corpus_frequency {
+            Some(shared) => Cow::Borrowed(shared),
+            None => {
+                let mut own = CorpusFrequency::new();
+                own.add_chapter(chapter, min_diglot_confidence);
+                Cow::Owned(own)
+            }
+        };
+        Self {
+            params,
+            min_diglot_confidence,
+            corpus_frequency,
+            remaining_frequency: None,
+            occurrence_positions,
+            chapter_sentence_count: chapter.sentences_numerical.len(),
+            last_seen_block: HashMap::new(),
+            blocks_ranked: 0,
+        }
+    }
+
+    /// Enables the payoff term: counts of the current chapter and every chapter after it.
+    pub fn with_remaining_frequency(mut self, remaining_frequency: &'a CorpusFrequency) -> Self {
+        self.remaining_frequency = Some(remaining_frequency);
+        self
+    }
+
+    // Sentences from `stream_position` (exclusive end of the block) to the lemma's next
+    // occurrence, looking no further than `stream_end`.
+    fn sentences_until_next_occurrence(&self, lemma_id: u32, stream_position: usize, stream_end: usize) -> Option<usize> {
+        let positions = self.occurrence_positions.get(&lemma_id)?;
+        let chapter_len = self.chapter_sentence_count.max(1);
+        let chapter_idx = stream_position % chapter_len;
+        let gap = match positions.iter().find(|&&p| p >= chapter_idx) {
+            Some(&p) => p - chapter_idx,
+            None => chapter_len - chapter_idx + positions.first()?, // Next pass
+        };
+        (stream_position + gap < stream_end).then_some(gap)
+    }
+
+    /// New lemmas (per `profile`) in the block, best first, as (lemma_id, in-block frequency).
+    /// `block_end_position` and `stream_end` are positions in the (possibly multi-pass)
+    /// sentence stream.
+    pub fn rank_candidates(
+        &mut self,
+        block: &Block,
+        profile: &NumericalLearnerProfile,
+        block_end_position: usize,
+        stream_end: usize,
+    ) -> Vec<(u32, u32)> {
+        let mut block_new_lemma_freq: HashMap<u32, u32> = HashMap::new();
+        for num_sentence_ref in block.numerical_sentences() {
+            for lemma_id in sentence_lemma_ids(num_sentence_ref, self.min_diglot_confidence) {
+                if profile.get_lemma_info(lemma_id).is_none_or(|info| info.state == LemmaState::New) {
+                    *block_new_lemma_freq.entry(lemma_id).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let max_block_freq = block_new_lemma_freq.values().copied().max().unwrap_or(1).max(1) as f32;
+        let max_corpus_log = (1.0 + self.corpus_frequency.max_count() as f32).ln().max(f32::EPSILON);
+        let target_interval = self.params.target_interval_sentences.max(1) as f32;
+        let max_remaining_log = self.remaining_frequency
+            .map_or(f32::EPSILON, |remaining| (1.0 + remaining.max_count() as f32).ln().max(f32::EPSILON));
+
+        let mut scored: Vec<(u32, u32, f32)> = block_new_lemma_freq.into_iter()
+            .map(|(lemma_id, freq)| {
+                let block_score = freq as f32 / max_block_freq;
+                let corpus_score = (1.0 + self.corpus_frequency.count(lemma_id) as f32).ln() / max_corpus_log;
+                let recency_score = self.last_seen_block.get(&lemma_id)
+                    .map_or(0.0, |&seen| 1.0 / (1 + self.blocks_ranked - seen) as f32);
+                let interval_score = self.sentences_until_next_occurrence(lemma_id, block_end_position, stream_end)
+                    .map_or(0.0, |gap| if gap as f32 <= target_interval { 1.0 } else { target_interval / gap as f32 });
+                let payoff_score = self.remaining_frequency
+                    .map_or(0.0, |remaining| block_score * (1.0 + remaining.count(lemma_id) as f32).ln() / max_remaining_log);
+                let score = self.params.block_frequency_weight * block_score
+                    + self.params.corpus_frequency_weight * corpus_score
+                    + self.params.recency_weight * recency_score
+                    + self.params.interval_weight * interval_score
+                    + self.params.remaining_frequency_weight * payoff_score;
+                (lemma_id, freq, score)
+            })
+            .collect();
+        let tie_break = |lemma_id: u32| match self.params.tie_break_seed {
+            Some(seed) => tie_break_key(seed, u64::from(lemma_id)),
+            None => u64::from(lemma_id),
+        };
+        scored.sort_by(|a, b| b.2.total_cmp(&a.2)
+            .then_with(|| b.1.cmp(&a.1))
+            .then_with(|| tie_break(a.0).cmp(&tie_break(b.0)))
+            .then_with(|| a.0.cmp(&b.0)));
+
+        self.blocks_ranked += 1;
+        scored.into_iter().map(|(lemma_id, freq, _)| (lemma_id, freq)).collect()
+    }
+
+    /// Marks the lemmas the last ranked block showed in the target language
+    /// (SimulationBlockResult::output_lemma_ids_for_block) as seen for the recency term.
+    /// Lemmas the block only showed in translation are not recorded.
+    pub fn record_exposures(&mut self, lemma_ids: &[u32]) {
+        for &lemma_id in lemma_ids {
+            self.last_seen_block.insert(lemma_id, self.blocks_ranked);
+        }
+    }
+}
+//*** END FILE: src/simulation/scheduler.rs ***//
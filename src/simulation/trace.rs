@@ -0,0 +1,87 @@
+//*** START FILE: src/simulation/trace.rs ***//
+// Structured counterpart of core_algo's log lines. run_simulation_numerical records one
+// TraceEvent per step of a block's regen cycle, so tools can follow a run without parsing
+// the free-form log. Lemma IDs refer to the dictionary saved with the profiles of the run.
+
+use super::level_policy::SentenceLevel;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event")]
+pub enum TraceEvent {
+    BlockStart {
+        sentences: usize,
+        known: usize,        // Profile counts when the block starts
+        active_only: usize,
+        target_ct: f32,      // Ceiling of the CT band
+        min_ct: f32,         // Floor of the CT band (0 = none)
+        candidates: usize,   // New lemmas offered for activation
+    },
+    RegenAttempt {
+        attempt: u32,        // 1-based
+        ct_metric: String,
+        ct: f32,
+        known_tokens: usize,
+        total_tokens: usize,
+    },
+    Activation {
+        attempt: u32,
+        lemma_id: u32,
+        block_frequency: u32, // Occurrences of the lemma in the block
+    },
+    Withhold {
+        attempt: u32,
+        lemma_id: u32,
+        exposures: u32,
+    },
+    LevelChoice {
+        sentence_id: String,
+        level: SentenceLevel,
+        target_tokens: usize,
+        known_tokens: usize,
+    },
+    Finalize {
+        attempts: u32,
+        ct_metric: String,
+        ct: f32,
+        known_tokens: usize,
+        total_tokens: usize,
+        reason: String,
+    },
+}
+
+impl TraceEvent {
+    /// Every event name, as serialized in the "event" field.
+    pub const KINDS: [&'static str; 6] = ["BlockStart", "RegenAttempt", "Activation", "Withhold", "LevelChoice", "Finalize"];
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TraceEvent::BlockStart { .. } => "BlockStart",
+            TraceEvent::RegenAttempt { .. } => "RegenAttempt",
+            TraceEvent::Activation { .. } => "Activation",
+            TraceEvent::Withhold { .. } => "Withhold",
+            TraceEvent::LevelChoice { .. } => "LevelChoice",
+            TraceEvent::Finalize { .. } => "Finalize",
+        }
+    }
+
+    /// The lemma an Activation or Withhold event is about.
+    pub fn lemma_id(&self) -> Option<u32> {
+        match self {
+            TraceEvent::Activation { lemma_id, .. } | TraceEvent::Withhold { lemma_id, .. } => Some(*lemma_id),
+            _ => None,
+        }
+    }
+}
+
+/// The events of one block, as written (one JSON object per line) to the
+/// <book instance>.trace.jsonl files of `generate --trace`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlockTrace {
+    pub book_instance_id: String,
+    pub block_in_book: usize,
+    pub run_block_index: usize,
+    pub events: Vec<TraceEvent>,
+}
+
+//*** END FILE: src/simulation/trace.rs ***//
@@ -0,0 +1,312 @@
+//*** START FILE: src/stage_repair.rs ***//
+// `repair-stage`: re-normalizes existing .llm.txt files after the format or
+// normalization rules change. Repaired copies are written to a separate directory
+// together with a plain-text change report; the originals are never touched.
+use crate::error::WeaveLangError;
+use crate::parsing::llm_parser::{parse_llm_text_to_chapter, sentence_id_for_block};
+use crate::parsing::llm_writer::{format_diglot_entry, write_sentence_block};
+use crate::parsing::validation::validate_chapter;
+use crate::lemmatizer::{CommandLemmatizer, FormTableLemmatizer, Lemmatizer, LemmatizerChain};
+use crate::simulation::preprocessor::lemmatize_raw;
+use crate::tokenizer::{self, strip_spanish_accents, Tokenizer};
+use crate::types::llm_data::{LanguagePair, ProcessedChapter, ProcessedSentence, SegmentLemmas};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+pub const REPAIR_REPORT_FILE_NAME: &str = "repair_report.txt";
+
+// Spanish words whose accent distinguishes two different lemmas ("el"/"él",
+// "si"/"sí", "como"/"cómo"). Variants of these are never merged.
+const DIACRITIC_DISTINCT_KEYS: [&str; 27] = [
+    "el", "tu", "mi", "si", "se", "de", "te", "mas", "aun", "solo", "que", "quien", "quienes",
+    "como", "cuando", "donde", "adonde", "cual", "cuales", "cuanto", "cuanta", "cuantos", "cuantas",
+    "porque", "este", "ese", "aquel",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct StageFileRepairReport {
+    pub file_name: String,
+    pub output_path: Option<PathBuf>,
+    pub changes: Vec<String>,
+    pub validation_issues: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StageRepairReport {
+    pub files: Vec<StageFileRepairReport>,
+}
+
+impl StageRepairReport {
+    pub fn total_changes(&self) -> usize {
+        self.files.iter().map(|f| f.changes.len()).sum()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::from("WeaveLang stage repair report\n=============================\n");
+        for file in &self.files {
+            out.push_str(&format!("\n## {}\n", file.file_name));
+            if let Some(e) = &file.error {
+                out.push_str(&format!("  ERROR: {}\n", e));
+                continue;
+            }
+            if file.changes.is_empty() {
+                out.push_str("  No changes.\n");
+            }
+            for change in &file.changes {
+                out.push_str(&format!("  {}\n", change));
+            }
+            for issue in &file.validation_issues {
+                out.push_str(&format!("  [validation] {}\n", issue));
+            }
+        }
+        out
+    }
+}
+
+// Spelling-vote key: accents, ñ and ü folded to plain letters. LLM output that drops
+// diacritics ("pequeno") is far more common than the rare real n/ñ minimal pair.
+fn fold_diacritics(lemma: &str) -> String {
+    strip_spanish_accents(lemma).replace('ñ', "n").replace('ü', "u")
+}
+
+fn diacritic_count(lemma: &str) -> usize {
+    lemma.chars().filter(|c| "áéíóúñü".contains(*c)).count()
+}
+
+/// Chooses one spelling per accent-insensitive lemma key: the most frequent variant,
+/// preferring the accented spelling on ties. Only Spanish has folding rules so far;
+/// other target languages get casing normalization only.
+fn build_canonical_lemma_spellings(chapters: &[&ProcessedChapter], target_language: &str) -> HashMap<String, String> {
+    if target_language != "es" {
+        return HashMap::new();
+    }
+    let mut counts: HashMap<String, BTreeMap<String, usize>> = HashMap::new();
+    let mut count = |lemma: &str| {
+        let lower = lemma.trim().to_lowercase();
+        if lower.is_empty() { return; }
+        let key = fold_diacritics(&lower);
+        *counts.entry(key).or_default().entry(lower).or_insert(0) += 1;
+    };
+    for chapter in chapters {
+        for sentence in &chapter.sentences {
+            sentence.adv_s_lemmas.iter().for_each(|l| count(l));
+            sentence.sim_s_lemmas.iter().flat_map(|sl| &sl.lemmas).for_each(|l| count(l));
+            sentence.diglot_map.iter().flat_map(|dm| &dm.entries).for_each(|e| count(&e.spa_lemma));
+        }
+    }
+
+    let mut canonical: HashMap<String, String> = HashMap::new();
+    for (key, variants) in counts {
+        if variants.len() < 2 || DIACRITIC_DISTINCT_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        let best = variants.iter()
+            .max_by_key(|(spelling, n)| (**n, diacritic_count(spelling)))
+            .map(|(spelling, _)| spelling.clone())
+            .unwrap_or_default();
+        for spelling in variants.keys() {
+            if *spelling != best {
+                canonical.insert(spelling.clone(), best.clone());
+            }
+        }
+    }
+    canonical
+}
+
+fn normalize_lemma(lemma: &str, canonical: &HashMap<String, String>) -> String {
+    let lower = lemma.trim().to_lowercase();
+    canonical.get(&lower).cloned().unwrap_or(lower)
+}
+
+fn repair_sentence(
+    sentence: &mut ProcessedSentence,
+    lemmatizer: &dyn Lemmatizer,
+    form_tokenizer: &dyn Tokenizer,
+    canonical: &HashMap<String, String>,
+    changes: &mut Vec<String>,
+) {
+    let id = sentence.sentence_id.clone();
+
+    // Missing SimSL lines for declared segments.
+    for segment in &sentence.sim_s_segments {
+        if sentence.sim_s_lemmas.iter().any(|sl| sl.segment_id == segment.id) {
+            continue;
+        }
+        let raw = lemmatize_raw(&segment.text, lemmatizer, form_tokenizer);
+        let (lemmas, guesses) = (raw.lemmas, raw.guesses);
+        changes.push(format!(
+            "{}: added SimSL {}:: {}{}",
+            id, segment.id, lemmas.join(" "),
+            if guesses > 0 { format!(" ({} unverified)", guesses) } else { String::new() }
+        ));
+        sentence.sim_s_lemmas.push(SegmentLemmas { segment_id: segment.id.clone(), lemmas });
+    }
+    let segment_order: HashMap<&str, usize> = sentence.sim_s_segments.iter().enumerate().map(|(i, s)| (s.id.as_str(), i)).collect();
+    sentence.sim_s_lemmas.sort_by_key(|sl| segment_order.get(sl.segment_id.as_str()).copied().unwrap_or(usize::MAX));
+
+    // Lemma casing and diacritics.
+    let mut respelled: Vec<String> = Vec::new();
+    let mut fix = |lemma: &mut String| {
+        let normalized = normalize_lemma(lemma, canonical);
+        if *lemma != normalized {
+            respelled.push(format!("{} -> {}", lemma, normalized));
+            *lemma = normalized;
+        }
+    };
+    sentence.adv_s_lemmas.iter_mut().for_each(&mut fix);
+    sentence.sim_s_lemmas.iter_mut().flat_map(|sl| sl.lemmas.iter_mut()).for_each(&mut fix);
+    sentence.diglot_map.iter_mut().flat_map(|dm| dm.entries.iter_mut()).for_each(|e| fix(&mut e.spa_lemma));
+    if !respelled.is_empty() {
+        respelled.dedup();
+        changes.push(format!("{}: normalized lemma spelling {}", id, respelled.join(", ")));
+    }
+}
+
+/// Repairs one stage file's contents. Chapter markers and comment blocks are copied
+/// through unchanged; every sentence block is re-written in canonical syntax. Blocks are
+/// paired with parsed sentences by sentence ID, so a block the parser skipped is copied
+/// through unchanged instead of shifting every later sentence onto the wrong block.
+fn repair_stage_text(
+    file_name: &str,
+    contents: &str,
+    chapter: &mut ProcessedChapter,
+    lemmatizer: &dyn Lemmatizer,
+    form_tokenizer: &dyn Tokenizer,
+    canonical: &HashMap<String, String>,
+    changes: &mut Vec<String>,
+) -> String {
+    let raw_blocks: Vec<&str> = contents
+        .split("END_SENTENCE")
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let mut unpaired: HashMap<String, &mut ProcessedSentence> = chapter.sentences.iter_mut()
+        .map(|sentence| (sentence.sentence_id.clone(), sentence))
+        .collect();
+    let mut out_blocks: Vec<String> = Vec::new();
+    for (block_index, raw_block) in raw_blocks.into_iter().enumerate() {
+        if raw_block.starts_with("CHAPTER_MARKER_DIRECT::") || raw_block.starts_with("//") {
+            out_blocks.push(format!("{}\nEND_SENTENCE", raw_block));
+            continue;
+        }
+        let block_sentence_id = sentence_id_for_block(file_name, block_index);
+        let Some(sentence) = unpaired.remove(&block_sentence_id) else {
+            if raw_block.lines().any(|l| l.trim_start().starts_with("AdvS::")) {
+                changes.push(format!("{}: block has no parsed sentence; copied unchanged", block_sentence_id));
+            }
+            out_blocks.push(format!("{}\nEND_SENTENCE", raw_block));
+            continue;
+        };
+        let diglot_before: Vec<String> = sentence.diglot_map.iter()
+            .flat_map(|dm| dm.entries.iter().map(format_diglot_entry))
+            .collect();
+        repair_sentence(sentence, lemmatizer, form_tokenizer, canonical, changes);
+
+        let written = write_sentence_block(sentence);
+        // Syntax-only differences (spacing, lowercase y/n) in the DIGLOT_MAP section.
+        let raw_diglot_lines = raw_block.lines()
+            .map(str::trim)
+            .skip_while(|l| !l.starts_with("DIGLOT_MAP::"))
+            .skip(1)
+            .take_while(|l| !l.starts_with("LOCKED_PHRASE::"))
+            .filter(|l| !l.is_empty());
+        let raw_entries: Vec<String> = raw_diglot_lines
+            .filter_map(|l| l.split_once("::").map(|(_, entries)| entries.to_string()))
+            .flat_map(|entries| entries.split('|').map(|e| e.trim().to_string()).collect::<Vec<_>>())
+            .filter(|e| !e.is_empty())
+            .collect();
+        if raw_entries != diglot_before {
+            changes.push(format!("{}: normalized DIGLOT_MAP syntax", sentence.sentence_id));
+        }
+        out_blocks.push(written);
+    }
+    if !unpaired.is_empty() {
+        let mut sentence_ids: Vec<&String> = unpaired.keys().collect();
+        sentence_ids.sort();
+        warn!("{} has parsed sentences without a matching block ({}); they were dropped.",
+            file_name, sentence_ids.iter().map(|id| id.as_str()).collect::<Vec<_>>().join(", "));
+    }
+    format!("{}\n", out_blocks.join("\n\n"))
+}
+
+fn list_stage_files(stage_dir: &Path) -> Result<Vec<PathBuf>, WeaveLangError> {
+    let mut files: Vec<PathBuf> = fs::read_dir(stage_dir)
+        .map_err(|e| WeaveLangError::parse(format!("Failed to read stage directory {:?}: {}", stage_dir, e)).with_source(e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| p.is_file() && p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.ends_with(".llm.txt")))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Repairs every .llm.txt file in `stage_dir`, writing repaired copies and
+/// repair_report.txt into `output_dir`.
+pub fn repair_stage_directory(
+    stage_dir: &Path,
+    output_dir: &Path,
+    language_pair: &LanguagePair,
+    lemmatizer_command: Option<&str>,
+) -> Result<StageRepairReport, WeaveLangError> {
+    if stage_dir == output_dir {
+        return Err(WeaveLangError::parse("Output directory must differ from the stage directory; originals are never overwritten."));
+    }
+    let stage_files = list_stage_files(stage_dir)?;
+    if stage_files.is_empty() {
+        return Err(WeaveLangError::parse(format!("No .llm.txt files found in {:?}", stage_dir)));
+    }
+    fs::create_dir_all(output_dir)
+        .map_err(|e| WeaveLangError::parse(format!("Failed to create output directory {:?}: {}", output_dir, e)).with_source(e))?;
+
+    // First pass: parse everything so the form table and spelling votes span the whole directory.
+    let mut parsed: Vec<(String, String, Result<ProcessedChapter, WeaveLangError>)> = Vec::new();
+    for path in &stage_files {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| WeaveLangError::parse(format!("Failed to read {:?}: {}", path, e)).with_source(e))?;
+        let chapter = parse_llm_text_to_chapter(&file_name, &contents).map(|mut ch| {
+            ch.language_pair = language_pair.clone();
+            ch
+        });
+        parsed.push((file_name, contents, chapter));
+    }
+    let ok_chapters: Vec<&ProcessedChapter> = parsed.iter().filter_map(|(_, _, c)| c.as_ref().ok()).collect();
+    let mut form_table = FormTableLemmatizer::new(&language_pair.target);
+    for chapter in &ok_chapters {
+        form_table.learn_from_chapter(chapter);
+    }
+    // What the stage files state wins; the external command only fills in unknown words.
+    let mut lemmatizer = LemmatizerChain::new().with(Box::new(form_table));
+    if let Some(command_line) = lemmatizer_command {
+        lemmatizer = lemmatizer.with(Box::new(CommandLemmatizer::spawn(command_line)?));
+    }
+    let form_tokenizer = tokenizer::tokenizer_for_language(&language_pair.target);
+    let canonical = build_canonical_lemma_spellings(&ok_chapters, &language_pair.target);
+
+    let mut report = StageRepairReport::default();
+    for (file_name, contents, chapter_result) in parsed {
+        let mut file_report = StageFileRepairReport { file_name: file_name.clone(), ..Default::default() };
+        match chapter_result {
+            Ok(mut chapter) => {
+                let repaired_text = repair_stage_text(&file_name, &contents, &mut chapter, &lemmatizer, form_tokenizer.as_ref(), &canonical, &mut file_report.changes);
+                file_report.validation_issues = validate_chapter(&chapter).iter().map(|i| i.to_string()).collect();
+                let output_path = output_dir.join(&file_name);
+                fs::write(&output_path, repaired_text)
+                    .map_err(|e| WeaveLangError::parse(format!("Failed to write repaired file {:?}: {}", output_path, e)).with_source(e))?;
+                file_report.output_path = Some(output_path);
+            }
+            Err(e) => file_report.error = Some(format!("Parse failed, file not repaired: {}", e)),
+        }
+        report.files.push(file_report);
+    }
+
+    let report_path = output_dir.join(REPAIR_REPORT_FILE_NAME);
+    fs::write(&report_path, report.to_text())
+        .map_err(|e| WeaveLangError::parse(format!("Failed to write repair report {:?}: {}", report_path, e)).with_source(e))?;
+    Ok(report)
+}
+//*** END FILE: src/stage_repair.rs ***//
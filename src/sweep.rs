@@ -0,0 +1,198 @@
+//*** START FILE: src/sweep.rs ***//
+// `weavelang sweep`: runs the same corpus once per combination of a parameter grid (CT
+// threshold, exposure threshold, activation rate) and tabulates how far each combination
+// got, for comparing acquisition models side by side. Combinations run in parallel, each
+// with its own output directories; everything else comes from the base GenerationArgs.
+
+use crate::config::Config;
+use crate::corpus_generator::{self, CorpusGenerationReport, GenerationArgs};
+use crate::progress::NoProgress;
+use rayon::prelude::*;
+use crate::error::WeaveLangError;
+use std::fs;
+use std::path::Path;
+use tracing::{error, info};
+
+/// Values to try for each swept parameter. An empty list keeps the base run's value.
+#[derive(Debug, Clone, Default)]
+pub struct SweepGrid {
+    pub target_ct_thresholds: Vec<f32>,
+    pub exposure_thresholds: Vec<u32>,
+    pub activation_rates: Vec<usize>, // max_words_to_activate_per_regen
+}
+
+/// One combination of the grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SweepPoint {
+    pub target_ct_threshold: f32,
+    pub exposure_threshold: Option<u32>, // None = the base run's threshold table and default
+    pub activation_rate: usize,
+}
+
+impl SweepPoint {
+    /// Directory name for this combination's outputs, e.g. "ct0.95_exp20_act3".
+    pub fn label(&self) -> String {
+        let exposure = self.exposure_threshold.map_or("default".to_string(), |t| t.to_string());
+        format!("ct{}_exp{}_act{}", self.target_ct_threshold, exposure, self.activation_rate)
+    }
+}
+
+impl SweepGrid {
+    /// Every combination, CT threshold varying slowest and activation rate fastest.
+    pub fn points(&self, base: &GenerationArgs) -> Vec<SweepPoint> {
+        let cts = if self.target_ct_thresholds.is_empty() { vec![base.target_ct_threshold] } else { self.target_ct_thresholds.clone() };
+        let exposures: Vec<Option<u32>> = if self.exposure_thresholds.is_empty() {
+            vec![base.default_exposure_threshold]
+        } else {
+            self.exposure_thresholds.iter().copied().map(Some).collect()
+        };
+        let rates = if self.activation_rates.is_empty() { vec![base.max_words_to_activate_per_regen] } else { self.activation_rates.clone() };
+        let mut points = Vec::with_capacity(cts.len() * exposures.len() * rates.len());
+        for &target_ct_threshold in &cts {
+            for &exposure_threshold in &exposures {
+                for &activation_rate in &rates {
+                    points.push(SweepPoint { target_ct_threshold, exposure_threshold, activation_rate });
+                }
+            }
+        }
+        points
+    }
+}
+
+/// How one combination's run ended. `error` is set when the run failed as a whole;
+/// the counts then stay 0.
+#[derive(Debug, Clone, Default)]
+pub struct SweepRow {
+    pub label: String,
+    pub target_ct_threshold: f32,
+    pub exposure_threshold: Option<u32>,
+    pub activation_rate: usize,
+    pub books: usize,
+    pub skipped_books: usize,
+    pub blocks: usize,
+    pub sentences: usize,
+    pub average_ct: f32, // Block-weighted over all books
+    pub final_known: usize,
+    pub final_active: usize,
+    pub level_counts: [usize; 5], // Sentences rendered at L1..L5 over the whole run
+    pub error: Option<String>,
+}
+
+impl SweepRow {
+    fn new(point: &SweepPoint) -> Self {
+        Self {
+            label: point.label(),
+            target_ct_threshold: point.target_ct_threshold,
+            exposure_threshold: point.exposure_threshold,
+            activation_rate: point.activation_rate,
+            ..Self::default()
+        }
+    }
+
+    fn from_report(point: &SweepPoint, report: &CorpusGenerationReport) -> Self {
+        let mut row = Self::new(point);
+        row.books = report.books.len();
+        row.skipped_books = report.skipped.len();
+        let mut ct_sum = 0.0;
+        for book in &report.books {
+            row.blocks += book.blocks;
+            row.sentences += book.sentences;
+            ct_sum += book.average_ct * book.blocks as f32;
+            for (total, count) in row.level_counts.iter_mut().zip(book.level_counts) {
+                *total += count;
+            }
+        }
+        row.average_ct = if row.blocks > 0 { ct_sum / row.blocks as f32 } else { 0.0 };
+        if let Some(last) = report.books.last() {
+            row.final_known = last.known_after;
+            row.final_active = last.active_after;
+        }
+        row
+    }
+
+    /// Share of the run's sentences rendered at each level; all 0 when none were rendered.
+    pub fn level_shares(&self) -> [f32; 5] {
+        let total: usize = self.level_counts.iter().sum();
+        self.level_counts.map(|count| if total > 0 { count as f32 / total as f32 } else { 0.0 })
+    }
+}
+
+/// Runs the base generation once per point, at most `jobs` at a time (0 = one per CPU).
+/// Each point writes into `<tts_output_dir>/<label>` and `<profiles_dir>/<label>` of the
+/// base args. Rows come back in the order of `points`.
+pub fn run_sweep(config: &Config, base: &GenerationArgs, points: &[SweepPoint], jobs: usize) -> Result<Vec<SweepRow>, WeaveLangError> {
+    if base.dry_run || base.resume {
+        return Err(WeaveLangError::generation("A sweep cannot be a dry run or resume an earlier run."));
+    }
+    if let Some(point) = points.iter().find(|point| point.target_ct_threshold < base.min_ct_threshold) {
+        return Err(WeaveLangError::generation(format!("The target CT threshold {} is below the min CT threshold ({}).",
+                           point.target_ct_threshold, base.min_ct_threshold)));
+    }
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs).build()
+        .map_err(|e| WeaveLangError::generation(format!("Failed to start the sweep worker threads: {}", e)).with_source(e))?;
+    let rows = pool.install(|| {
+        points.par_iter().map(|point| {
+            let label = point.label();
+            let args = GenerationArgs {
+                tts_output_dir: base.tts_output_dir.join(&label),
+                profiles_dir: base.profiles_dir.join(&label),
+                target_ct_threshold: point.target_ct_threshold,
+                default_exposure_threshold: point.exposure_threshold,
+                max_words_to_activate_per_regen: point.activation_rate,
+                // Lookahead books would be prepared on this pool, whose threads are all busy
+                // with combinations waiting for them.
+                parallel_lookahead: 0,
+                ..base.clone()
+            };
+            info!("Sweep: starting {}", label);
+            let row = match corpus_generator::run_corpus_generation_with_progress(config, &args, &mut NoProgress) {
+                Ok(report) => SweepRow::from_report(point, &report),
+                Err(e) => SweepRow { error: Some(e.to_string()), ..SweepRow::new(point) },
+            };
+            match &row.error {
+                Some(error) => error!("Sweep: {} failed: {}", label, error),
+                None => info!("Sweep: finished {} ({} Known, {} Active)", label, row.final_known, row.final_active),
+            }
+            row
+        }).collect()
+    });
+    Ok(rows)
+}
+
+/// The rows as CSV, one line per combination.
+pub fn sweep_csv(rows: &[SweepRow]) -> String {
+    let mut csv = String::from(
+        "label,target_ct_threshold,exposure_threshold,activation_rate,books,skipped_books,blocks,sentences,average_ct,\
+         final_known,final_active,l1,l2,l3,l4,l5,l1_share,l2_share,l3_share,l4_share,l5_share,error\n",
+    );
+    for row in rows {
+        let shares = row.level_shares();
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{:.4},{},{},{},{},{},{},{},{:.4},{:.4},{:.4},{:.4},{:.4},{}\n",
+            row.label, row.target_ct_threshold,
+            row.exposure_threshold.map_or(String::new(), |t| t.to_string()),
+            row.activation_rate, row.books, row.skipped_books, row.blocks, row.sentences, row.average_ct,
+            row.final_known, row.final_active,
+            row.level_counts[0], row.level_counts[1], row.level_counts[2], row.level_counts[3], row.level_counts[4],
+            shares[0], shares[1], shares[2], shares[3], shares[4],
+            csv_field(row.error.as_deref().unwrap_or_default())
+        ));
+    }
+    csv
+}
+
+/// Writes sweep_csv(rows) to `file_path`.
+pub fn write_sweep_csv(rows: &[SweepRow], file_path: &Path) -> Result<(), WeaveLangError> {
+    fs::write(file_path, sweep_csv(rows))
+        .map_err(|e| WeaveLangError::generation(format!("Failed to write sweep CSV to {:?}: {}", file_path, e)).with_source(e))?;
+    Ok(())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+//*** END FILE: src/sweep.rs ***//
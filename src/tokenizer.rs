@@ -0,0 +1,182 @@
+//*** START FILE: src/tokenizer.rs ***//
+// Word segmentation shared by L4 substitution, validation and statistics.
+// Replaces `\b...\b` regex assumptions, which split English contractions
+// ("don't" -> "don" + "t") and know nothing about Spanish clitics.
+
+/// A word token with byte offsets into the original text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '\u{2019}'
+}
+
+/// Splits on anything that is not alphanumeric, keeping apostrophes that sit
+/// between two word characters ("don't", "John's", "d'Artagnan").
+fn tokenize_words(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        if !chars[i].1.is_alphanumeric() {
+            i += 1;
+            continue;
+        }
+        let start = chars[i].0;
+        let mut j = i + 1;
+        while j < chars.len() {
+            let c = chars[j].1;
+            let joins_word = is_apostrophe(c) && chars.get(j + 1).is_some_and(|(_, next)| next.is_alphanumeric());
+            if c.is_alphanumeric() || joins_word {
+                j += 1;
+            } else {
+                break;
+            }
+        }
+        let end = chars.get(j).map_or(text.len(), |(idx, _)| *idx);
+        tokens.push(Token { text: &text[start..end], start, end });
+        i = j;
+    }
+    tokens
+}
+
+pub trait Tokenizer: Send + Sync {
+    /// Word tokens of `text`, in order. Punctuation and whitespace are dropped.
+    fn tokenize<'a>(&self, text: &'a str) -> Vec<Token<'a>>;
+
+    /// Splits attached clitic pronouns off a single word. Languages without
+    /// clitics return the word unchanged.
+    fn split_clitics(&self, word: &str) -> Vec<String> {
+        vec![word.to_string()]
+    }
+
+    /// Byte range of the first occurrence of `phrase` (one or more words) in `text`,
+    /// matched on whole tokens.
+    fn find_phrase(&self, text: &str, phrase: &str) -> Option<(usize, usize)> {
+        let phrase_tokens = self.tokenize(phrase);
+        if phrase_tokens.is_empty() {
+            return None;
+        }
+        let text_tokens = self.tokenize(text);
+        text_tokens
+            .windows(phrase_tokens.len())
+            .find(|window| window.iter().zip(&phrase_tokens).all(|(a, b)| a.text == b.text))
+            .map(|window| (window[0].start, window[window.len() - 1].end))
+    }
+
+    /// Regular plural of a word or phrase (its last word), or None if the language has
+    /// no rules for it.
+    fn pluralize(&self, _phrase: &str) -> Option<String> {
+        None
+    }
+
+    /// Replaces the first whole-token occurrence of `phrase` in `text`.
+    /// Returns None if the phrase does not occur.
+    fn replace_first(&self, text: &str, phrase: &str, replacement: &str) -> Option<String> {
+        let (start, end) = self.find_phrase(text, phrase)?;
+        Some(format!("{}{}{}", &text[..start], replacement, &text[end..]))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnglishTokenizer;
+
+impl Tokenizer for EnglishTokenizer {
+    fn tokenize<'a>(&self, text: &'a str) -> Vec<Token<'a>> {
+        tokenize_words(text)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpanishTokenizer;
+
+const SPANISH_CLITICS: [&str; 11] = ["nos", "los", "las", "les", "os", "me", "te", "se", "lo", "la", "le"];
+
+/// Removes acute accents from vowels (ñ and ü are kept; they are separate letters).
+pub fn strip_spanish_accents(word: &str) -> String {
+    word.chars()
+        .map(|c| match c {
+            'á' => 'a', 'é' => 'e', 'í' => 'i', 'ó' => 'o', 'ú' => 'u',
+            'Á' => 'A', 'É' => 'E', 'Í' => 'I', 'Ó' => 'O', 'Ú' => 'U',
+            other => other,
+        })
+        .collect()
+}
+
+pub fn has_spanish_accent(word: &str) -> bool {
+    word.chars().any(|c| "áéíóúÁÉÍÓÚ".contains(c))
+}
+
+impl Tokenizer for SpanishTokenizer {
+    fn tokenize<'a>(&self, text: &'a str) -> Vec<Token<'a>> {
+        tokenize_words(text)
+    }
+
+    /// Heuristic enclitic splitting: "dámelo" -> ["da", "me", "lo"],
+    /// "comiéndoselo" -> ["comiendo", "se", "lo"], "hacerlo" -> ["hacer", "lo"].
+    /// At most two clitics are removed, and only when the remaining stem looks like an
+    /// infinitive, a gerund, or an imperative that gained a written accent.
+    fn split_clitics(&self, word: &str) -> Vec<String> {
+        let lower = word.to_lowercase();
+        let mut stem = lower.as_str();
+        let mut clitics: Vec<&str> = Vec::new();
+        let mut accepted_on_accent_only = false;
+
+        while clitics.len() < 2 {
+            let Some(clitic) = SPANISH_CLITICS.iter().find(|c| stem.len() > c.len() + 1 && stem.ends_with(*c)) else {
+                break;
+            };
+            let candidate = &stem[..stem.len() - clitic.len()];
+            let plain = strip_spanish_accents(candidate);
+            let infinitive_or_gerund = plain.ends_with("ar") || plain.ends_with("er") || plain.ends_with("ir") || plain.ends_with("ndo");
+            if !infinitive_or_gerund && !has_spanish_accent(candidate) {
+                break;
+            }
+            accepted_on_accent_only = !infinitive_or_gerund;
+            clitics.insert(0, clitic);
+            stem = candidate;
+        }
+
+        // Accented esdrújula nouns ("película", "título") look like imperative + lo/la;
+        // only trust the accent cue when a personal clitic (me, te, se, nos, os) is present.
+        if accepted_on_accent_only && !clitics.iter().any(|c| ["me", "te", "se", "nos", "os"].contains(c)) {
+            clitics.clear();
+        }
+        if clitics.is_empty() {
+            return vec![word.to_string()];
+        }
+        // The written accent was only there because of the attached pronouns.
+        let mut parts = vec![strip_spanish_accents(stem)];
+        parts.extend(clitics.iter().map(|c| c.to_string()));
+        parts
+    }
+
+    /// "perro" -> "perros", "ciudad" -> "ciudades", "luz" -> "luces", "canción" -> "canciones".
+    /// Words already ending in s are left alone ("lunes", "casas").
+    fn pluralize(&self, phrase: &str) -> Option<String> {
+        let word_start = phrase.rfind(' ').map_or(0, |i| i + 1);
+        let (head, word) = phrase.split_at(word_start);
+        let last = word.chars().last()?;
+        let plural = match last {
+            's' | 'S' => word.to_string(),
+            'a' | 'e' | 'i' | 'o' | 'u' | 'á' | 'é' | 'ó' => format!("{}s", word),
+            'z' => format!("{}ces", &word[..word.len() - 1]),
+            _ if word.ends_with("ión") => format!("{}iones", &word[..word.len() - "ión".len()]),
+            _ => format!("{}es", word),
+        };
+        Some(format!("{}{}", head, plural))
+    }
+}
+
+/// Tokenizer for an ISO 639-1 language code; unknown languages use the English rules.
+pub fn tokenizer_for_language(language_code: &str) -> Box<dyn Tokenizer> {
+    match language_code.to_lowercase().as_str() {
+        "es" | "spa" | "spanish" => Box::new(SpanishTokenizer),
+        _ => Box::new(EnglishTokenizer),
+    }
+}
+//*** END FILE: src/tokenizer.rs ***//
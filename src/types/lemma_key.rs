@@ -0,0 +1,51 @@
+//*** START FILE: src/types/lemma_key.rs ***//
+use crate::unicode_norm::normalize_for_key;
+
+// Lemma keys may carry a part-of-speech tag for homographs: "banco#NOUN" and "banco#VERB"
+// get separate IDs (and separate learner progress). A bare "banco" keeps an ID of its own,
+// so untagged uses never credit one of the senses (GlobalLemmaDictionary::get_id_or_insert).
+pub const POS_SEPARATOR: char = '#';
+
+// Multi-word expressions are one key with their words joined by '_' ("por_favor"). SimSL/AdvSL
+// may also write them in brackets ("[por favor]"); both spellings normalize to the same key.
+pub const MWE_JOINER: char = '_';
+
+/// Whether a lemma names a multi-word expression ("por_favor", "[por favor]", "a_pesar_de#adp").
+pub fn is_mwe_key(key: &str) -> bool {
+    split_lemma_key(&normalize_lemma_key(key)).0.contains(MWE_JOINER)
+}
+
+/// Splits a lemma key into its lemma and optional POS tag: "banco#noun" -> ("banco", Some("noun")).
+pub fn split_lemma_key(key: &str) -> (&str, Option<&str>) {
+    match key.split_once(POS_SEPARATOR) {
+        Some((lemma, pos)) if !pos.trim().is_empty() => (lemma.trim(), Some(pos.trim())),
+        Some((lemma, _)) => (lemma.trim(), None),
+        None => (key.trim(), None),
+    }
+}
+
+/// Canonical dictionary key: NFC-composed (diacritics folded in DiacriticMode::Fold),
+/// lowercase, trimmed, no spaces around the separator, and an empty POS tag dropped ("Banco # NOUN" -> "banco#noun", "banco#" -> "banco"). MWE
+/// brackets are removed and inner whitespace joined ("[Por  favor]" -> "por_favor").
+pub fn normalize_lemma_key(lemma_str: &str) -> String {
+    let lemma_str = normalize_for_key(lemma_str);
+    let (lemma, pos) = split_lemma_key(&lemma_str);
+    let lemma = lemma.trim_start_matches('[').trim_end_matches(']')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(&MWE_JOINER.to_string())
+        .to_lowercase();
+    match pos {
+        Some(pos) => format!("{}{}{}", lemma, POS_SEPARATOR, pos.to_lowercase()),
+        None => lemma,
+    }
+}
+
+/// Human-readable form of a key: "banco (noun)" or just "banco".
+pub fn describe_lemma_key(key: &str) -> String {
+    match split_lemma_key(key) {
+        (lemma, Some(pos)) => format!("{} ({})", lemma, pos),
+        (lemma, None) => lemma.to_string(),
+    }
+}
+//*** END FILE: src/types/lemma_key.rs ***//
@@ -1,5 +1,7 @@
 //*** START FILE: src/types/llm_data.rs ***//
-use serde::{Deserialize, Serialize};
+use crate::types::lemma_key::is_mwe_key;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::ops::Range;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct SegmentData {
@@ -25,7 +27,34 @@ pub struct DiglotEntry {
     pub eng_word: String,
     pub spa_lemma: String,
     pub exact_spa_form: String,
-    pub viable: bool,
+    // LLM's confidence that the substitution reads naturally: (Y) = 1.0, (N) = 0.0, (Y:0.8) = 0.8.
+    // JSON written before scores existed has a "viable" flag instead.
+    #[serde(alias = "viable", deserialize_with = "deserialize_confidence")]
+    pub confidence: f32,
+}
+
+// A confidence score, or the boolean `viable` flag it replaced (true = 1.0, false = 0.0).
+// Binary encodings are not self-describing and always hold the score.
+fn deserialize_confidence<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ConfidenceOrFlag {
+        Confidence(f32),
+        Viable(bool),
+    }
+    if !deserializer.is_human_readable() {
+        return f32::deserialize(deserializer);
+    }
+    Ok(match ConfidenceOrFlag::deserialize(deserializer)? {
+        ConfidenceOrFlag::Confidence(confidence) => confidence,
+        ConfidenceOrFlag::Viable(viable) => if viable { 1.0 } else { 0.0 },
+    })
+}
+
+impl DiglotEntry {
+    pub fn is_viable(&self, min_confidence: f32) -> bool {
+        self.confidence > 0.0 && self.confidence >= min_confidence
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -34,7 +63,31 @@ pub struct DiglotSegmentMap {
     pub entries: Vec<DiglotEntry>,
 }
 
+impl DiglotSegmentMap {
+    /// Per entry: whether it is a single-word entry whose form lies inside the form of a
+    /// multi-word-expression entry of the same segment ("favor" inside "por favor"). Such
+    /// entries are not substituted on their own, so the expression is woven whole or not at all.
+    pub fn entries_inside_mwe(&self) -> Vec<bool> {
+        let mwe_forms: Vec<Vec<String>> = self.entries.iter()
+            .filter(|e| is_mwe_key(&e.spa_lemma))
+            .map(|e| form_words(&e.exact_spa_form))
+            .collect();
+        self.entries.iter()
+            .map(|entry| {
+                let words = form_words(&entry.exact_spa_form);
+                !is_mwe_key(&entry.spa_lemma) && !words.is_empty()
+                    && mwe_forms.iter().any(|mwe| mwe.len() > words.len() && mwe.windows(words.len()).any(|w| w == words.as_slice()))
+            })
+            .collect()
+    }
+}
+
+fn form_words(form: &str) -> Vec<String> {
+    form.split_whitespace().map(|w| w.to_lowercase()).collect()
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)] // Sections missing from a JSON stage file are empty, as in an .llm.txt block without them
 pub struct ProcessedSentence {
     pub sentence_id: String,
     pub adv_s: String,
@@ -46,11 +99,96 @@ pub struct ProcessedSentence {
     pub adv_s_lemmas: Vec<String>,
     pub diglot_map: Vec<DiglotSegmentMap>,
     pub locked_phrases: Option<Vec<String>>,
+    // GRAM:: grammar features (lowercase names such as "preterite", "subjunctive") the AdvS
+    // and SimS texts use; the learner needs them Active or Known for L1 / L2.
+    pub adv_s_grammar: Vec<String>,
+    pub sim_s_grammar: Vec<String>,
+    // Title of the CHAPTER_MARKER_DIRECT block(s) right before this sentence: a chapter or
+    // section starts here. Exported as a heading (TTS text, HTML, EPUB) or pause (SSML).
+    pub section_heading: Option<String>,
+    // A paragraph starts at this sentence: PARA:: in .llm.txt, a blank line before it in a
+    // plain-text book. Blocks can be made to end at paragraphs (BlockBoundaries::Paragraphs).
+    pub paragraph_start: bool,
+}
+
+/// The language being learned (target) and the learner's own language (base), as
+/// ISO 639-1 codes. Field names elsewhere (adv_s, sim_e, spa_lemma, eng_word) predate
+/// this and refer to the target and base roles, whatever the actual languages are.
+/// A missing code takes the default's, so `language_pair.target = "fr"` alone keeps English.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LanguagePair {
+    pub target: String,
+    pub base: String,
+}
+
+impl Default for LanguagePair {
+    fn default() -> Self {
+        LanguagePair { target: "es".to_string(), base: "en".to_string() }
+    }
+}
+
+impl ProcessedSentence {
+    /// Whether a segment belongs to the sentence's LOCKED_PHRASE group.
+    pub fn is_segment_locked(&self, segment_id: &str) -> bool {
+        self.locked_phrases.as_ref().is_some_and(|ids| ids.iter().any(|id| id == segment_id))
+    }
+
+    /// Whether a new paragraph starts here; a chapter or section heading always starts one.
+    pub fn starts_paragraph(&self) -> bool {
+        self.paragraph_start || self.section_heading.is_some()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ProcessedChapter {
     pub source_file_name: String,
     pub sentences: Vec<ProcessedSentence>,
+    #[serde(default)]
+    pub language_pair: LanguagePair,
+    // BOOK:: and CHAPTER:: headers of a stage file holding a whole book. A chapter's title is
+    // also the section heading of its first sentence, so exports show where it starts.
+    #[serde(default)]
+    pub book_title: Option<String>,
+    #[serde(default)]
+    pub chapter_title: Option<String>,
+}
+
+/// Where one chapter of a multi-chapter book lies in the book's joined sentence list.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChapterSpan {
+    pub title: Option<String>,
+    pub sentences: Range<usize>,
+}
+
+impl ProcessedChapter {
+    /// A copy of the sentences in `span`, titled like it.
+    pub fn slice(&self, span: &ChapterSpan) -> ProcessedChapter {
+        ProcessedChapter {
+            source_file_name: self.source_file_name.clone(),
+            sentences: self.sentences[span.sentences.clone()].to_vec(),
+            language_pair: self.language_pair.clone(),
+            book_title: self.book_title.clone(),
+            chapter_title: span.title.clone(),
+        }
+    }
+}
+
+/// Joins the chapters of one book into a single chapter, returning where each of them
+/// ended up. The joined chapter keeps the first chapter's file name and book title.
+pub fn join_chapters(chapters: Vec<ProcessedChapter>) -> (ProcessedChapter, Vec<ChapterSpan>) {
+    let mut joined = ProcessedChapter::default();
+    let mut spans = Vec::with_capacity(chapters.len());
+    for (index, chapter) in chapters.into_iter().enumerate() {
+        if index == 0 {
+            joined.source_file_name = chapter.source_file_name;
+            joined.language_pair = chapter.language_pair;
+            joined.book_title = chapter.book_title;
+        }
+        let start = joined.sentences.len();
+        joined.sentences.extend(chapter.sentences);
+        spans.push(ChapterSpan { title: chapter.chapter_title, sentences: start..joined.sentences.len() });
+    }
+    (joined, spans)
 }
 //*** END FILE: src/types/llm_data.rs ***//
\ No newline at end of file
@@ -0,0 +1,36 @@
+//*** START FILE: src/types/protected_span.rs ***//
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// What a protected stretch of SimE is; L4 never substitutes words inside one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProtectedSpanKind {
+    ProperNoun,   // Capitalized words that don't start a sentence ("Bank of America" -> "Bank", "America"), except weekdays, months, languages and nationalities
+    Quote,        // Between double quotes or guillemets
+    LockedPhrase, // SimE span of a LOCKED_PHRASE segment; only protected from other segments' entries
+}
+
+impl ProtectedSpanKind {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ProtectedSpanKind::ProperNoun => "a proper noun",
+            ProtectedSpanKind::Quote => "a quote",
+            ProtectedSpanKind::LockedPhrase => "a locked phrase",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtectedSpan {
+    pub range: Range<usize>, // Bytes of SimE
+    pub kind: ProtectedSpanKind,
+}
+
+impl ProtectedSpan {
+    /// Whether the span blocks a substitution of an entry from a (locked or unlocked) segment.
+    pub fn blocks(&self, range: &Range<usize>, entry_segment_locked: bool) -> bool {
+        let protects = self.kind != ProtectedSpanKind::LockedPhrase || !entry_segment_locked;
+        protects && self.range.start < range.end && range.start < self.range.end
+    }
+}
+//*** END FILE: src/types/protected_span.rs ***//
@@ -0,0 +1,140 @@
+//*** START FILE: src/unicode_norm.rs ***//
+// Unicode normalization for lemma keys and stage text. "á" may arrive precomposed (U+00E1)
+// or as "a" + U+0301 depending on the editor or LLM that wrote it; both must map to the same
+// dictionary key or the learner is counted as knowing two different words.
+//
+// Only canonical composition of Latin letters with a single combining mark is done here
+// (Latin-1 Supplement and Latin Extended-A, which covers the target languages we stage);
+// it is not a full NFC implementation.
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// (base, combining mark, precomposed), sorted by base then mark.
+const COMPOSITIONS: &[(char, char, char)] = &[
+    ('A', '\u{0300}', 'À'), ('A', '\u{0301}', 'Á'), ('A', '\u{0302}', 'Â'), ('A', '\u{0303}', 'Ã'),
+    ('A', '\u{0304}', 'Ā'), ('A', '\u{0306}', 'Ă'), ('A', '\u{0308}', 'Ä'), ('A', '\u{030A}', 'Å'),
+    ('A', '\u{0328}', 'Ą'), ('C', '\u{0301}', 'Ć'), ('C', '\u{0302}', 'Ĉ'), ('C', '\u{0307}', 'Ċ'),
+    ('C', '\u{030C}', 'Č'), ('C', '\u{0327}', 'Ç'), ('D', '\u{030C}', 'Ď'), ('E', '\u{0300}', 'È'),
+    ('E', '\u{0301}', 'É'), ('E', '\u{0302}', 'Ê'), ('E', '\u{0304}', 'Ē'), ('E', '\u{0306}', 'Ĕ'),
+    ('E', '\u{0307}', 'Ė'), ('E', '\u{0308}', 'Ë'), ('E', '\u{030C}', 'Ě'), ('E', '\u{0328}', 'Ę'),
+    ('G', '\u{0302}', 'Ĝ'), ('G', '\u{0306}', 'Ğ'), ('G', '\u{0307}', 'Ġ'), ('G', '\u{0327}', 'Ģ'),
+    ('H', '\u{0302}', 'Ĥ'), ('I', '\u{0300}', 'Ì'), ('I', '\u{0301}', 'Í'), ('I', '\u{0302}', 'Î'),
+    ('I', '\u{0303}', 'Ĩ'), ('I', '\u{0304}', 'Ī'), ('I', '\u{0306}', 'Ĭ'), ('I', '\u{0307}', 'İ'),
+    ('I', '\u{0308}', 'Ï'), ('I', '\u{0328}', 'Į'), ('J', '\u{0302}', 'Ĵ'), ('K', '\u{0327}', 'Ķ'),
+    ('L', '\u{0301}', 'Ĺ'), ('L', '\u{030C}', 'Ľ'), ('L', '\u{0327}', 'Ļ'), ('N', '\u{0301}', 'Ń'),
+    ('N', '\u{0303}', 'Ñ'), ('N', '\u{030C}', 'Ň'), ('N', '\u{0327}', 'Ņ'), ('O', '\u{0300}', 'Ò'),
+    ('O', '\u{0301}', 'Ó'), ('O', '\u{0302}', 'Ô'), ('O', '\u{0303}', 'Õ'), ('O', '\u{0304}', 'Ō'),
+    ('O', '\u{0306}', 'Ŏ'), ('O', '\u{0308}', 'Ö'), ('O', '\u{030B}', 'Ő'), ('R', '\u{0301}', 'Ŕ'),
+    ('R', '\u{030C}', 'Ř'), ('R', '\u{0327}', 'Ŗ'), ('S', '\u{0301}', 'Ś'), ('S', '\u{0302}', 'Ŝ'),
+    ('S', '\u{030C}', 'Š'), ('S', '\u{0327}', 'Ş'), ('T', '\u{030C}', 'Ť'), ('T', '\u{0327}', 'Ţ'),
+    ('U', '\u{0300}', 'Ù'), ('U', '\u{0301}', 'Ú'), ('U', '\u{0302}', 'Û'), ('U', '\u{0303}', 'Ũ'),
+    ('U', '\u{0304}', 'Ū'), ('U', '\u{0306}', 'Ŭ'), ('U', '\u{0308}', 'Ü'), ('U', '\u{030A}', 'Ů'),
+    ('U', '\u{030B}', 'Ű'), ('U', '\u{0328}', 'Ų'), ('W', '\u{0302}', 'Ŵ'), ('Y', '\u{0301}', 'Ý'),
+    ('Y', '\u{0302}', 'Ŷ'), ('Y', '\u{0308}', 'Ÿ'), ('Z', '\u{0301}', 'Ź'), ('Z', '\u{0307}', 'Ż'),
+    ('Z', '\u{030C}', 'Ž'), ('a', '\u{0300}', 'à'), ('a', '\u{0301}', 'á'), ('a', '\u{0302}', 'â'),
+    ('a', '\u{0303}', 'ã'), ('a', '\u{0304}', 'ā'), ('a', '\u{0306}', 'ă'), ('a', '\u{0308}', 'ä'),
+    ('a', '\u{030A}', 'å'), ('a', '\u{0328}', 'ą'), ('c', '\u{0301}', 'ć'), ('c', '\u{0302}', 'ĉ'),
+    ('c', '\u{0307}', 'ċ'), ('c', '\u{030C}', 'č'), ('c', '\u{0327}', 'ç'), ('d', '\u{030C}', 'ď'),
+    ('e', '\u{0300}', 'è'), ('e', '\u{0301}', 'é'), ('e', '\u{0302}', 'ê'), ('e', '\u{0304}', 'ē'),
+    ('e', '\u{0306}', 'ĕ'), ('e', '\u{0307}', 'ė'), ('e', '\u{0308}', 'ë'), ('e', '\u{030C}', 'ě'),
+    ('e', '\u{0328}', 'ę'), ('g', '\u{0302}', 'ĝ'), ('g', '\u{0306}', 'ğ'), ('g', '\u{0307}', 'ġ'),
+    ('g', '\u{0327}', 'ģ'), ('h', '\u{0302}', 'ĥ'), ('i', '\u{0300}', 'ì'), ('i', '\u{0301}', 'í'),
+    ('i', '\u{0302}', 'î'), ('i', '\u{0303}', 'ĩ'), ('i', '\u{0304}', 'ī'), ('i', '\u{0306}', 'ĭ'),
+    ('i', '\u{0308}', 'ï'), ('i', '\u{0328}', 'į'), ('j', '\u{0302}', 'ĵ'), ('k', '\u{0327}', 'ķ'),
+    ('l', '\u{0301}', 'ĺ'), ('l', '\u{030C}', 'ľ'), ('l', '\u{0327}', 'ļ'), ('n', '\u{0301}', 'ń'),
+    ('n', '\u{0303}', 'ñ'), ('n', '\u{030C}', 'ň'), ('n', '\u{0327}', 'ņ'), ('o', '\u{0300}', 'ò'),
+    ('o', '\u{0301}', 'ó'), ('o', '\u{0302}', 'ô'), ('o', '\u{0303}', 'õ'), ('o', '\u{0304}', 'ō'),
+    ('o', '\u{0306}', 'ŏ'), ('o', '\u{0308}', 'ö'), ('o', '\u{030B}', 'ő'), ('r', '\u{0301}', 'ŕ'),
+    ('r', '\u{030C}', 'ř'), ('r', '\u{0327}', 'ŗ'), ('s', '\u{0301}', 'ś'), ('s', '\u{0302}', 'ŝ'),
+    ('s', '\u{030C}', 'š'), ('s', '\u{0327}', 'ş'), ('t', '\u{030C}', 'ť'), ('t', '\u{0327}', 'ţ'),
+    ('u', '\u{0300}', 'ù'), ('u', '\u{0301}', 'ú'), ('u', '\u{0302}', 'û'), ('u', '\u{0303}', 'ũ'),
+    ('u', '\u{0304}', 'ū'), ('u', '\u{0306}', 'ŭ'), ('u', '\u{0308}', 'ü'), ('u', '\u{030A}', 'ů'),
+    ('u', '\u{030B}', 'ű'), ('u', '\u{0328}', 'ų'), ('w', '\u{0302}', 'ŵ'), ('y', '\u{0301}', 'ý'),
+    ('y', '\u{0302}', 'ŷ'), ('y', '\u{0308}', 'ÿ'), ('z', '\u{0301}', 'ź'), ('z', '\u{0307}', 'ż'),
+    ('z', '\u{030C}', 'ž'),
+];
+
+/// How lemma keys treat diacritics: kept (default, "el" and "él" are different lemmas) or
+/// folded away ("el" and "él" share an ID). Selected with `diacritics` in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiacriticMode {
+    #[default]
+    Keep,
+    Fold,
+}
+
+static FOLD_DIACRITICS: AtomicBool = AtomicBool::new(false);
+
+/// Sets the diacritic handling of every lemma key normalized from now on. Called once at
+/// startup from the config; a project must keep the same mode across runs, since snapshots
+/// store the keys as normalized when they were written.
+pub fn set_diacritic_mode(mode: DiacriticMode) {
+    FOLD_DIACRITICS.store(mode == DiacriticMode::Fold, Ordering::Relaxed);
+}
+
+pub fn diacritic_mode() -> DiacriticMode {
+    if FOLD_DIACRITICS.load(Ordering::Relaxed) { DiacriticMode::Fold } else { DiacriticMode::Keep }
+}
+
+fn is_combining_mark(c: char) -> bool {
+    ('\u{0300}'..='\u{036F}').contains(&c)
+}
+
+fn compose(base: char, mark: char) -> Option<char> {
+    COMPOSITIONS.binary_search_by(|&(b, m, _)| (b, m).cmp(&(base, mark)))
+        .ok()
+        .map(|index| COMPOSITIONS[index].2)
+}
+
+/// Composes base letters followed by combining marks into their precomposed form. Text
+/// without combining marks (the usual case) is returned as is.
+pub fn to_nfc(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_combining_mark) {
+        return Cow::Borrowed(text);
+    }
+    let mut composed = String::with_capacity(text.len());
+    let mut pending: Option<char> = None;
+    for c in text.chars() {
+        if let Some(base) = pending {
+            if let Some(precomposed) = compose(base, c) {
+                pending = Some(precomposed);
+                continue;
+            }
+            composed.push(base);
+        }
+        pending = Some(c);
+    }
+    composed.extend(pending);
+    Cow::Owned(composed)
+}
+
+/// Base letter of a precomposed Latin letter ('á' -> 'a', 'Ñ' -> 'N'); other characters
+/// are returned unchanged.
+pub fn strip_diacritic(c: char) -> char {
+    if !('\u{00C0}'..='\u{017F}').contains(&c) {
+        return c;
+    }
+    COMPOSITIONS.iter().find(|&&(_, _, precomposed)| precomposed == c).map_or(c, |&(base, _, _)| base)
+}
+
+/// NFC-composes `text` and removes diacritics from its letters, including stray combining
+/// marks that had no base to compose with.
+pub fn strip_diacritics(text: &str) -> String {
+    to_nfc(text).chars()
+        .filter(|&c| !is_combining_mark(c))
+        .map(strip_diacritic)
+        .collect()
+}
+
+/// NFC form of `text`, with diacritics folded when the process runs in DiacriticMode::Fold.
+pub fn normalize_for_key(text: &str) -> Cow<'_, str> {
+    match diacritic_mode() {
+        DiacriticMode::Keep => to_nfc(text),
+        DiacriticMode::Fold => Cow::Owned(strip_diacritics(text)),
+    }
+}
+
+//*** END FILE: src/unicode_norm.rs ***//
@@ -0,0 +1,39 @@
+//*** START FILE: src/wasm.rs ***//
+// wasm-bindgen exports for running the weaving algorithm in the browser (`--features wasm`):
+//
+//   cargo build --lib --release --target wasm32-unknown-unknown --features wasm
+//   wasm-bindgen --target web target/wasm32-unknown-unknown/release/weavelang_rust_gui.wasm --out-dir pkg
+//
+// Only the in-memory pipeline is exported (parsing, block simulation, text generation);
+// stage directories, profile files and the GUI are left to the host. Requests and results
+// are JSON strings in the shapes of json_api, so JS calls JSON.stringify / JSON.parse
+// around them; errors are thrown as JS Errors.
+
+use crate::error::WeaveLangError;
+use crate::json_api;
+use crate::parsing::chapter_loader;
+use wasm_bindgen::prelude::*;
+
+fn to_js(result: Result<serde_json::Value, WeaveLangError>) -> Result<String, JsError> {
+    result.map(|value| value.to_string()).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// A stage file's contents as one chapter; the format is picked from `file_name`.
+#[wasm_bindgen(js_name = parseLlmTextToChapter)]
+pub fn parse_llm_text_to_chapter(file_name: &str, contents: &str) -> Result<String, JsError> {
+    let chapter = chapter_loader::parse_chapter(file_name, contents).map_err(|e| JsError::new(&e.to_string()))?;
+    serde_json::to_string(&chapter).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Simulates a run of sentences as one block (json_api::simulate_block).
+#[wasm_bindgen(js_name = runSimulationNumerical)]
+pub fn run_simulation_numerical(request: &str) -> Result<String, JsError> {
+    to_js(json_api::simulate_block(request))
+}
+
+/// Renders a run of sentences against a profile without simulating them (json_api::generate_text).
+#[wasm_bindgen(js_name = generateFinalTextBlock)]
+pub fn generate_final_text_block(request: &str) -> Result<String, JsError> {
+    to_js(json_api::generate_text(request))
+}
+//*** END FILE: src/wasm.rs ***//
@@ -0,0 +1,55 @@
+//*** START FILE: tests/decay.rs ***//
+use weavelang_rust_gui::profile::{LemmaState, DEFAULT_EXPOSURE_THRESHOLD};
+use weavelang_rust_gui::simulation::numerical_types::{DecayParams, NumericalLearnerProfile};
+
+const DECAY: DecayParams = DecayParams { half_life_blocks: 2.0, min_retention: 0.5 };
+
+fn known_profile(lemma_ids: &[u32]) -> NumericalLearnerProfile {
+    let mut profile = NumericalLearnerProfile::new();
+    for _ in 0..DEFAULT_EXPOSURE_THRESHOLD {
+        profile.record_exposures(lemma_ids);
+    }
+    profile
+}
+
+#[test]
+fn retention_halves_every_half_life() {
+    assert_eq!(DECAY.retention(0), 1.0);
+    assert_eq!(DECAY.retention(2), 0.5);
+    assert_eq!(DECAY.retention(4), 0.25);
+    assert_eq!(DecayParams::default().retention(1000), 1.0, "decay is off by default");
+}
+
+#[test]
+fn unseen_known_lemmas_slide_back_to_active() {
+    let mut profile = known_profile(&[1, 2]);
+    for _ in 0..3 {
+        profile.advance_block_clock();
+        profile.record_exposures(&[2]);
+    }
+    assert_eq!(profile.apply_decay(&DECAY), [1]);
+
+    let info = profile.get_lemma_info(1).unwrap();
+    assert_eq!(info.state, LemmaState::Active);
+    assert!(info.decayed);
+    assert_eq!(info.exposure_count, (DEFAULT_EXPOSURE_THRESHOLD as f32 * DECAY.retention(3)).floor() as u32);
+    assert!(!profile.is_lemma_known(1));
+    assert!(profile.is_lemma_known_or_active(1));
+    assert!(profile.is_lemma_known(2), "lemmas seen recently stay Known");
+    assert_eq!(profile.count_decayed(), 1);
+}
+
+#[test]
+fn a_fresh_exposure_clears_the_decayed_flag() {
+    let mut profile = known_profile(&[1]);
+    for _ in 0..4 {
+        profile.advance_block_clock();
+    }
+    assert_eq!(profile.apply_decay(&DECAY), [1]);
+    assert!(profile.apply_decay(&DECAY).is_empty(), "Active lemmas do not decay further");
+
+    profile.record_exposures(&[1]);
+    assert!(!profile.get_lemma_info(1).unwrap().decayed);
+    assert_eq!(profile.count_decayed(), 0);
+}
+//*** END FILE: tests/decay.rs ***//
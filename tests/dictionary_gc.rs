@@ -0,0 +1,50 @@
+//*** START FILE: tests/dictionary_gc.rs ***//
+use weavelang_rust_gui::parsing::llm_parser::parse_llm_text_to_chapter;
+use weavelang_rust_gui::profile_io::{gc_profile_snapshots, load_profile_snapshot, save_profile_snapshot};
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+use weavelang_rust_gui::simulation::numerical_types::NumericalLearnerProfile;
+use weavelang_rust_gui::simulation::preprocessor::to_numerical_chapter;
+
+const STAGE: &str = "\
+AdvS:: El perro come.
+SimS:: El perro come.
+SimE:: The dog eats.
+SimS_Segments::
+S1(El perro come.)
+SimSL::
+S1:: el perro comer
+AdvSL:: el perro comer
+DIGLOT_MAP::
+S1:: dog->perro(perro)(Y)
+END_SENTENCE
+";
+
+#[test]
+fn gc_keeps_lemmas_that_chapters_still_use() {
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let gato = dictionary.get_id_or_insert("gato");
+    dictionary.get_id_or_insert("perro");
+    dictionary.get_id_or_insert("casa");
+    let mut profile = NumericalLearnerProfile::new();
+    profile.record_exposures(&[gato]);
+
+    let work_dir = std::env::temp_dir().join(format!("weavelang_dictionary_gc_{}", std::process::id()));
+    std::fs::create_dir_all(&work_dir).unwrap();
+    let snapshot_path = work_dir.join("learner.profile.json");
+    save_profile_snapshot(&profile, &dictionary, &snapshot_path).expect("snapshot saves");
+
+    // The chapter was converted with its own dictionary, so its IDs differ from the snapshot's.
+    let chapter = parse_llm_text_to_chapter("perro.llm.txt", STAGE).expect("stage parses");
+    let mut chapter_dictionary = GlobalLemmaDictionary::new();
+    let numerical_chapter = to_numerical_chapter(&chapter, &mut chapter_dictionary);
+
+    let output_dir = work_dir.join("gc");
+    let report = gc_profile_snapshots(&[snapshot_path], &[(&numerical_chapter, &chapter_dictionary)], &output_dir)
+        .expect("gc runs");
+    let (_, collected) = load_profile_snapshot(&output_dir.join("learner.profile.json")).expect("gc output loads");
+    let _ = std::fs::remove_dir_all(&work_dir);
+    assert_eq!(report.removed_lemmas, ["casa"]);
+    assert!(collected.get_id("gato").is_some());
+    assert!(collected.get_id("perro").is_some(), "the chapter still uses perro");
+}
+//*** END FILE: tests/dictionary_gc.rs ***//
@@ -0,0 +1,55 @@
+//*** START FILE: tests/dictionary_merge.rs ***//
+use weavelang_rust_gui::parsing::llm_parser::parse_llm_text_to_chapter;
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+use weavelang_rust_gui::simulation::preprocessor::{merge_into_dictionary, to_numerical_chapter};
+
+const FIRST: &str = "\
+AdvS:: El perro come.
+SimS:: El perro come.
+SimE:: The dog eats.
+SimS_Segments::
+S1(El perro come.)
+SimSL::
+S1:: el perro comer
+AdvSL:: el perro comer
+DIGLOT_MAP::
+S1:: dog->perro(perro)(Y)
+END_SENTENCE
+";
+
+// Shares "el" and "comer" with FIRST, in a different order than a fresh dictionary would see them.
+const SECOND: &str = "\
+AdvS:: Come el gato.
+SimS:: Come el gato.
+SimE:: The cat eats.
+SimS_Segments::
+S1(Come el gato.)
+SimSL::
+S1:: comer el gato
+AdvSL:: comer el gato
+DIGLOT_MAP::
+S1:: cat->gato(gato)(Y)
+END_SENTENCE
+";
+
+#[test]
+fn merged_chapters_get_the_sequential_lemma_ids() {
+    let first = parse_llm_text_to_chapter("first.llm.txt", FIRST).expect("stage parses");
+    let second = parse_llm_text_to_chapter("second.llm.txt", SECOND).expect("stage parses");
+
+    let mut sequential_dictionary = GlobalLemmaDictionary::new();
+    to_numerical_chapter(&first, &mut sequential_dictionary);
+    let sequential = to_numerical_chapter(&second, &mut sequential_dictionary);
+
+    let mut dictionary = GlobalLemmaDictionary::new();
+    to_numerical_chapter(&first, &mut dictionary);
+    let mut local_dictionary = GlobalLemmaDictionary::new();
+    let local = to_numerical_chapter(&second, &mut local_dictionary);
+    let merged = merge_into_dictionary(local, &local_dictionary, &mut dictionary);
+
+    assert_eq!(dictionary.to_tsv(), sequential_dictionary.to_tsv());
+    assert_eq!(serde_json::to_string(&merged).unwrap(), serde_json::to_string(&sequential).unwrap());
+    let gato = dictionary.get_id("gato").unwrap();
+    assert!(merged.sentences_numerical[0].adv_s_lemma_ids.contains(&gato));
+}
+//*** END FILE: tests/dictionary_merge.rs ***//
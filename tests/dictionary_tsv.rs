@@ -0,0 +1,41 @@
+//*** START FILE: tests/dictionary_tsv.rs ***//
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+
+fn import(name: &str, contents: &str) -> Result<GlobalLemmaDictionary, String> {
+    let path = std::env::temp_dir().join(format!("weavelang_dictionary_{}_{}.tsv", name, std::process::id()));
+    std::fs::write(&path, contents).unwrap();
+    let dictionary = GlobalLemmaDictionary::import_tsv(&path).map_err(|e| e.to_string());
+    std::fs::remove_file(&path).ok();
+    dictionary
+}
+
+#[test]
+fn exported_dictionaries_import_unchanged() {
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let gato = dictionary.get_id_or_insert("gato");
+    dictionary.get_id_or_insert("perro");
+    dictionary.add_alias("gata", gato);
+    let imported = import("roundtrip", &dictionary.to_tsv()).expect("export imports");
+    assert_eq!(imported.to_tsv(), dictionary.to_tsv());
+    assert_eq!(imported.get_id("gata"), Some(gato));
+}
+
+#[test]
+fn bare_lemmas_take_the_next_free_id() {
+    let imported = import("curated", "# curated\nid\tlemma\n0\tser\n\ncasa\n2\tperro\n").expect("mixed rows import");
+    assert_eq!(imported.get_id("ser"), Some(0));
+    assert_eq!(imported.get_id("casa"), Some(1));
+    assert_eq!(imported.get_id("perro"), Some(2));
+    assert_eq!(imported.size(), 3);
+}
+
+#[test]
+fn malformed_rows_are_rejected_with_their_line() {
+    let error = import("gap", "0\tser\n5\tcasa\n").expect_err("IDs must be sequential");
+    assert!(error.contains("line 2") && error.contains("expected 1"), "{}", error);
+    let error = import("duplicate", "ser\ncasa\nser\n").expect_err("lemmas must be unique");
+    assert!(error.contains("Duplicate lemma 'ser'") && error.contains("line 3"), "{}", error);
+    let error = import("bad_id", "x\tser\n").expect_err("IDs must be numbers");
+    assert!(error.contains("Invalid lemma ID 'x'"), "{}", error);
+}
+//*** END FILE: tests/dictionary_tsv.rs ***//
@@ -0,0 +1,85 @@
+//*** START FILE: tests/diglot_syntax.rs ***//
+use weavelang_rust_gui::parsing::llm_parser::{validate_llm_text, DiagnosticSeverity};
+use weavelang_rust_gui::parsing::llm_writer::{format_diglot_entry, format_viability};
+use weavelang_rust_gui::types::llm_data::DiglotEntry;
+
+const MIXED_SYNTAX: &str = "\
+AdvS:: El gato perro pequeño ahora.
+SimS:: El gato perro pequeño.
+SimE:: The cat dog small.
+SimS_Segments::
+S1(El gato)
+S2(perro pequeño)
+PHRASE_ALIGN::
+S1 ~ El gato ~ The cat
+S2 ~ perro pequeño ~ dog small
+SimSL::
+S1:: el gato
+S2:: perro pequeño
+AdvSL:: el gato perro pequeño ahora
+DIGLOT_MAP::
+S1:: cat->gato(gato)(Y)
+S2:: dog->perro [perro] (Y:0.8) | small->pequeño(pequeño)(N)
+END_SENTENCE
+";
+
+#[test]
+fn both_form_delimiters_parse_to_the_same_entries() {
+    let (chapter, diagnostics) = validate_llm_text("mixed.llm.txt", MIXED_SYNTAX);
+    let chapter = chapter.expect("mixed syntax parses");
+    let entries: Vec<String> = chapter.sentences[0].diglot_map.iter()
+        .flat_map(|segment| &segment.entries)
+        .map(format_diglot_entry)
+        .collect();
+    let canonical = MIXED_SYNTAX.replace("dog->perro [perro] (Y:0.8)", "dog->perro(perro)(Y:0.8)");
+    let (canonical_chapter, canonical_diagnostics) = validate_llm_text("canonical.llm.txt", &canonical);
+    let canonical_entries: Vec<String> = canonical_chapter.expect("canonical syntax parses").sentences[0].diglot_map.iter()
+        .flat_map(|segment| &segment.entries)
+        .map(format_diglot_entry)
+        .collect();
+    assert_eq!(entries, canonical_entries);
+    assert_eq!(entries.len(), 3);
+
+    // Only the bracketed entry is reported, as a warning.
+    let bracket_warnings: Vec<_> = diagnostics.iter().filter(|d| d.message.contains("[brackets]")).collect();
+    assert_eq!(bracket_warnings.len(), 1);
+    assert_eq!(bracket_warnings[0].severity, DiagnosticSeverity::Warning);
+    assert_eq!(bracket_warnings[0].marker, "DIGLOT_MAP");
+    assert!(bracket_warnings[0].message.contains("dog->perro [perro] (Y:0.8)"));
+    assert!(!canonical_diagnostics.iter().any(|d| d.message.contains("[brackets]")));
+}
+
+#[test]
+fn viability_flags_and_scores_parse_to_confidences() {
+    let stage = MIXED_SYNTAX.replace(
+        "S2:: dog->perro [perro] (Y:0.8) | small->pequeño(pequeño)(N)",
+        "S2:: dog->perro(perro)(Y) | small->pequeño(pequeño)(N) | now->ahora(ahora)(N:0.8) | the->el(el)(0.7)",
+    );
+    let (chapter, diagnostics) = validate_llm_text("scores.llm.txt", &stage);
+    let chapter = chapter.expect("scored entries parse");
+    let confidences: Vec<f32> = chapter.sentences[0].diglot_map[1].entries.iter().map(|e| e.confidence).collect();
+    assert_eq!(confidences, [1.0, 0.0, 0.0, 0.7]);
+    assert!(!chapter.sentences[0].diglot_map[1].entries[2].is_viable(0.0), "an explicit N is never viable");
+    assert!(diagnostics.iter().any(|d| d.severity == DiagnosticSeverity::Warning && d.message.contains("now->ahora(ahora)(N:0.8)")));
+}
+
+#[test]
+fn written_scores_read_back_unchanged() {
+    for confidence in [0.0, 0.3, 0.8, 1.0] {
+        let entry = DiglotEntry { eng_word: "dog".into(), spa_lemma: "perro".into(), exact_spa_form: "perro".into(), confidence };
+        let stage = MIXED_SYNTAX.replace("dog->perro [perro] (Y:0.8)", &format_diglot_entry(&entry));
+        let (chapter, _) = validate_llm_text("round_trip.llm.txt", &stage);
+        assert_eq!(chapter.expect("written entry parses").sentences[0].diglot_map[1].entries[0].confidence, confidence, "{}", format_viability(confidence));
+    }
+}
+
+#[test]
+fn json_with_the_old_viable_flag_still_loads() {
+    let entry: DiglotEntry = serde_json::from_str(r#"{"eng_word":"dog","spa_lemma":"perro","exact_spa_form":"perro","viable":true}"#).expect("old JSON loads");
+    assert_eq!(entry.confidence, 1.0);
+    let entry: DiglotEntry = serde_json::from_str(r#"{"eng_word":"dog","spa_lemma":"perro","exact_spa_form":"perro","viable":false}"#).expect("old JSON loads");
+    assert_eq!(entry.confidence, 0.0);
+    let entry: DiglotEntry = serde_json::from_str(r#"{"eng_word":"dog","spa_lemma":"perro","exact_spa_form":"perro","confidence":0.8}"#).expect("current JSON loads");
+    assert_eq!(entry.confidence, 0.8);
+}
+//*** END FILE: tests/diglot_syntax.rs ***//
@@ -0,0 +1,23 @@
+//*** START FILE: tests/error_types.rs ***//
+use std::io::ErrorKind;
+use std::path::Path;
+use weavelang_rust_gui::error::WeaveLangError;
+use weavelang_rust_gui::parsing::llm_parser::parse_llm_bytes;
+use weavelang_rust_gui::profile_io::load_profile_snapshot;
+use weavelang_rust_gui::simulation::level_policy::LevelPolicy;
+
+#[test]
+fn errors_carry_the_stage_that_failed_and_their_cause() {
+    let not_utf8 = parse_llm_bytes("broken.llm.txt", &[0xff, 0xfe]).unwrap_err();
+    assert!(matches!(not_utf8, WeaveLangError::ParseError { .. }), "{:?}", not_utf8);
+    assert!(not_utf8.find_source::<std::str::Utf8Error>().is_some());
+
+    let bad_policy = "L3".parse::<LevelPolicy>().unwrap_err();
+    assert!(matches!(bad_policy, WeaveLangError::ConfigError { .. }), "{:?}", bad_policy);
+
+    let missing = load_profile_snapshot(Path::new("does/not/exist.profile.json")).unwrap_err();
+    assert!(matches!(missing, WeaveLangError::ProfileIoError { .. }), "{:?}", missing);
+    assert_eq!(missing.find_source::<std::io::Error>().map(|e| e.kind()), Some(ErrorKind::NotFound));
+    assert!(missing.to_string().contains("does/not/exist.profile.json"));
+}
+//*** END FILE: tests/error_types.rs ***//
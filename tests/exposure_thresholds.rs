@@ -0,0 +1,27 @@
+//*** START FILE: tests/exposure_thresholds.rs ***//
+use weavelang_rust_gui::exposure_thresholds::ThresholdTable;
+
+fn load(name: &str, contents: &str) -> Result<ThresholdTable, String> {
+    let path = std::env::temp_dir().join(format!("weavelang_thresholds_{}_{}.csv", name, std::process::id()));
+    std::fs::write(&path, contents).unwrap();
+    let table = ThresholdTable::load_csv(&path).map_err(|e| e.to_string());
+    std::fs::remove_file(&path).ok();
+    table
+}
+
+#[test]
+fn a_header_is_only_skipped_on_the_first_row() {
+    let table = load("header", "# thresholds\nlemma,threshold\ngato,3\nperro\t5\n").expect("table loads");
+    assert_eq!(table.lookup("gato"), Some(3));
+    assert_eq!(table.lookup("perro"), Some(5));
+
+    let error = load("late_header", "gato,3\nlemma,threshold\n").expect_err("a later header row is malformed");
+    assert!(error.contains("line 2"), "{}", error);
+}
+
+#[test]
+fn every_malformed_row_is_reported() {
+    let error = load("malformed", "gato,3\nperro,many\n\ncasa\nsol,2\n").expect_err("malformed rows fail");
+    assert!(error.contains("line 2, 4"), "{}", error);
+}
+//*** END FILE: tests/exposure_thresholds.rs ***//
@@ -0,0 +1,60 @@
+//*** START FILE: tests/ffi.rs ***//
+// Drives the C ABI the way a host would: JSON strings in, JSON strings out, with the
+// profile and dictionary carried from one call to the next. Only built with
+// `cargo test --features ffi`.
+#![cfg(feature = "ffi")]
+
+use serde_json::{json, Value};
+use std::ffi::{c_char, CStr, CString};
+use std::path::Path;
+use weavelang_rust_gui::ffi::{weavelang_generate_text, weavelang_parse, weavelang_simulate_block, weavelang_string_free};
+
+fn call(function: unsafe extern "C" fn(*const c_char) -> *mut c_char, request: &Value) -> Value {
+    let request = CString::new(request.to_string()).expect("request has no NUL");
+    unsafe {
+        let response = function(request.as_ptr());
+        assert!(!response.is_null());
+        let text = CStr::from_ptr(response).to_str().expect("response is UTF-8").to_string();
+        weavelang_string_free(response);
+        serde_json::from_str(&text).expect("response is JSON")
+    }
+}
+
+#[test]
+fn parse_simulate_and_generate_through_the_c_abi() {
+    let stage = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/corpus/stage/bookA.llm.txt");
+    let contents = std::fs::read_to_string(stage).expect("golden stage file");
+    let parsed = call(weavelang_parse, &json!({ "file_name": "bookA.llm.txt", "contents": contents }));
+    let chapter = parsed["ok"][0].clone();
+    let sentence_count = chapter["sentences"].as_array().expect("sentences").len();
+    assert!(sentence_count >= 4, "{}", parsed);
+
+    let first = call(weavelang_simulate_block, &json!({ "chapter": chapter, "sentence_count": 2 }));
+    let first = &first["ok"];
+    assert_eq!(first["levels"].as_array().map(Vec::len), Some(2), "{}", first);
+    let second = call(weavelang_simulate_block, &json!({
+        "chapter": chapter, "dictionary": first["dictionary"], "profile": first["profile"],
+        "first_sentence": 2, "sentence_count": 2,
+    }));
+    assert!(second["ok"]["text"].is_string(), "{}", second);
+
+    let rendered = call(weavelang_generate_text, &json!({
+        "chapter": chapter, "dictionary": second["ok"]["dictionary"], "profile": second["ok"]["profile"],
+        "first_sentence": 0, "sentence_count": 2, "settings": { "prefix_level_tags": true },
+    }));
+    assert_eq!(rendered["ok"]["sentence_texts"].as_array().map(Vec::len), Some(2), "{}", rendered);
+    assert!(rendered["ok"]["text"].as_str().is_some_and(|text| text.starts_with("[L")), "{}", rendered);
+}
+
+#[test]
+fn bad_requests_come_back_as_errors() {
+    let not_json = call(weavelang_parse, &json!("not a request"));
+    assert!(not_json["error"].is_string(), "{}", not_json);
+    let past_end = call(weavelang_generate_text, &json!({ "chapter": { "source_file_name": "empty.json", "sentences": [] }, "first_sentence": 3 }));
+    assert!(past_end["error"].as_str().is_some_and(|e| e.contains("first_sentence")), "{}", past_end);
+    let null = unsafe { weavelang_simulate_block(std::ptr::null()) };
+    let text = unsafe { CStr::from_ptr(null) }.to_str().expect("UTF-8").to_string();
+    unsafe { weavelang_string_free(null) };
+    assert!(text.contains("null pointer"), "{}", text);
+}
+//*** END FILE: tests/ffi.rs ***//
@@ -0,0 +1,48 @@
+//*** START FILE: tests/golden_corpus.rs ***//
+// Runs the mini-corpus in tests/golden/corpus through the full pipeline and compares it
+// with tests/golden/corpus/expected. Only built with `cargo test --features golden`;
+// set WEAVELANG_UPDATE_GOLDEN=1 to accept an intended change.
+#![cfg(feature = "golden")]
+
+use std::path::Path;
+use weavelang_rust_gui::golden::{check_golden_corpus, golden_generation_args, run_golden_corpus};
+
+#[test]
+fn golden_corpus_matches_expected_output() {
+    let corpus_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/corpus");
+    let work_dir = std::env::temp_dir().join(format!("weavelang_golden_{}", std::process::id()));
+    let result = check_golden_corpus(&corpus_dir, &work_dir);
+    let _ = std::fs::remove_dir_all(&work_dir);
+    if let Err(e) = result {
+        panic!("{}", e);
+    }
+}
+
+#[test]
+fn chapter_cache_leaves_golden_output_unchanged() {
+    let corpus_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/corpus");
+    let work_dir = std::env::temp_dir().join(format!("weavelang_golden_cached_{}", std::process::id()));
+    let cache_dir = std::env::temp_dir().join(format!("weavelang_golden_cache_{}", std::process::id()));
+    let uncached = run_golden_corpus(&corpus_dir, &work_dir, None).expect("uncached run");
+    let cold = run_golden_corpus(&corpus_dir, &work_dir, Some(&cache_dir)).expect("run filling the cache");
+    let cached_books = std::fs::read_dir(&cache_dir).map(|entries| entries.count()).unwrap_or(0);
+    let warm = run_golden_corpus(&corpus_dir, &work_dir, Some(&cache_dir)).expect("run reading the cache");
+    let _ = std::fs::remove_dir_all(&work_dir);
+    let _ = std::fs::remove_dir_all(&cache_dir);
+    assert!(cached_books > 0, "the first cached run stored no books");
+    assert_eq!(cold, uncached);
+    assert_eq!(warm, uncached);
+}
+
+#[test]
+fn same_seed_gives_identical_output() {
+    let corpus_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/corpus");
+    let work_dir = std::env::temp_dir().join(format!("weavelang_golden_seeded_{}", std::process::id()));
+    let args = golden_generation_args(&corpus_dir.join("sequence.txt"), &work_dir);
+    assert_eq!(args.scheduler.tie_break_seed, args.seed, "the run's seed must reach the scheduler tie-break");
+    let first = run_golden_corpus(&corpus_dir, &work_dir, None).expect("first run");
+    let second = run_golden_corpus(&corpus_dir, &work_dir, None).expect("second run");
+    let _ = std::fs::remove_dir_all(&work_dir);
+    assert_eq!(first, second);
+}
+//*** END FILE: tests/golden_corpus.rs ***//
@@ -0,0 +1,22 @@
+//*** START FILE: tests/legacy_profile.rs ***//
+use weavelang_rust_gui::profile::{LearnerProfile, LemmaState};
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+
+#[test]
+fn string_keyed_profiles_round_trip_through_the_dictionary() {
+    let mut legacy = LearnerProfile::new();
+    legacy.get_lemma_info_mut("Casa").state = LemmaState::Known;
+    legacy.record_exposures(&["perro".to_string(), "perro".to_string()]);
+
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let existing_id = dictionary.get_id_or_insert("perro");
+    let numerical = legacy.to_numerical(&mut dictionary);
+    assert_eq!(numerical.vocabulary_size(), 2);
+    assert_eq!(numerical.get_lemma_info(existing_id).map(|info| (info.state, info.exposure_count)), Some((LemmaState::Active, 2)));
+    let casa_id = dictionary.get_id("casa").expect("added to the dictionary");
+    assert!(numerical.get_lemma_info(casa_id).is_some_and(|info| info.state == LemmaState::Known));
+
+    let back = LearnerProfile::from_numerical(&numerical, &dictionary);
+    assert_eq!(back.vocabulary, legacy.vocabulary);
+}
+//*** END FILE: tests/legacy_profile.rs ***//
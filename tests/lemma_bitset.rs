@@ -0,0 +1,22 @@
+//*** START FILE: tests/lemma_bitset.rs ***//
+use weavelang_rust_gui::simulation::lemma_bitset::LemmaBitset;
+
+#[test]
+fn bitset_membership_follows_inserts_and_removals_across_words() {
+    let mut bitset: LemmaBitset = [0, 63, 64, 1000].into_iter().collect();
+    assert_eq!(bitset.len(), 4);
+    assert!(bitset.contains(63) && bitset.contains(64) && bitset.contains(1000));
+    assert!(!bitset.contains(1) && !bitset.contains(999) && !bitset.contains(100_000));
+
+    bitset.remove(64);
+    bitset.remove(5000); // Beyond the last word: nothing to do
+    bitset.set(7, true);
+    assert!(!bitset.contains(64) && bitset.contains(7));
+    assert_eq!(bitset.len(), 4);
+
+    for lemma_id in [0, 7, 63, 1000] {
+        bitset.set(lemma_id, false);
+    }
+    assert!(bitset.is_empty());
+}
+//*** END FILE: tests/lemma_bitset.rs ***//
@@ -0,0 +1,56 @@
+//*** START FILE: tests/lemma_senses.rs ***//
+use std::collections::HashSet;
+use weavelang_rust_gui::simulation::dictionary::{self, GlobalLemmaDictionary};
+
+// Every key gets its own ID whichever order the corpus uses them in, so an untagged
+// "banco" never credits the sense that happened to come first.
+fn dictionary_from(keys: &[&str]) -> GlobalLemmaDictionary {
+    let mut dictionary = GlobalLemmaDictionary::new();
+    for key in keys {
+        dictionary.get_id_or_insert(key);
+    }
+    dictionary
+}
+
+#[test]
+fn bare_and_tagged_keys_never_share_an_id() {
+    for keys in [["banco#NOUN", "banco", "banco#VERB"], ["banco", "banco#noun", "banco#verb"], ["banco#verb", "banco#noun", "banco"]] {
+        let dictionary = dictionary_from(&keys);
+        let ids: HashSet<u32> = ["banco", "banco#noun", "banco#verb"].iter()
+            .map(|key| dictionary.get_id(key).expect("every key has an ID"))
+            .collect();
+        assert_eq!(ids.len(), 3, "order {:?}", keys);
+        assert!(dictionary.aliases().is_empty(), "order {:?}", keys);
+        assert_eq!(dictionary.sense_ids("banco").len(), 3);
+    }
+}
+
+#[test]
+fn a_lone_sense_is_not_found_under_its_bare_lemma() {
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let noun = dictionary.get_id_or_insert("banco#noun");
+    assert_eq!(dictionary.get_id("Banco"), None);
+    assert_eq!(dictionary.get_id_unambiguous("banco"), Some(noun), "display lookups still find the only sense");
+    assert_ne!(dictionary.get_id_or_insert("banco"), noun);
+}
+
+#[test]
+fn aliases_survive_tsv_round_trips_and_gc() {
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let bare = dictionary.get_id_or_insert("banco");
+    dictionary.add_alias("banco#noun", bare);
+    let verb = dictionary.get_id_or_insert("banco#verb");
+
+    let path = std::env::temp_dir().join(format!("weavelang_lemma_senses_{}.tsv", std::process::id()));
+    std::fs::write(&path, dictionary.to_tsv()).unwrap();
+    let imported = GlobalLemmaDictionary::import_tsv(&path).expect("exported TSV imports");
+    std::fs::remove_file(&path).ok();
+    assert_eq!(imported.id_to_str, dictionary.id_to_str);
+    assert_eq!(imported.get_id("banco#noun"), Some(bare));
+    assert_eq!(imported.get_id("banco#verb"), Some(verb));
+
+    let collected = dictionary::gc(&dictionary, &HashSet::from([bare]));
+    assert_eq!(collected.dictionary.get_id("banco#noun"), collected.id_remap.get(&bare).copied());
+    assert_eq!(collected.dictionary.size(), 1);
+}
+//*** END FILE: tests/lemma_senses.rs ***//
@@ -0,0 +1,72 @@
+//*** START FILE: tests/level_decision.rs ***//
+use std::path::Path;
+use weavelang_rust_gui::parsing::llm_parser::parse_llm_bytes;
+use weavelang_rust_gui::profile::LemmaState;
+use weavelang_rust_gui::simulation::block::Block;
+use weavelang_rust_gui::simulation::core_algo::{run_simulation_numerical, BlockSimulationSettings, L4Settings, L4Strategy, TokenCt};
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+use weavelang_rust_gui::simulation::numerical_types::NumericalLearnerProfile;
+use weavelang_rust_gui::simulation::preprocessor::to_numerical_chapter;
+use weavelang_rust_gui::simulation::level_policy::{LevelDecision, LevelPolicy, SentenceLevel};
+use weavelang_rust_gui::simulation::trace::TraceEvent;
+use weavelang_rust_gui::tokenizer::tokenizer_for_language;
+
+#[test]
+fn every_sentence_gets_an_explicit_level_decision_counted_per_block() {
+    let chapter_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/corpus/stage/bookA.llm.txt");
+    let chapter = parse_llm_bytes("bookA.llm.txt", &std::fs::read(&chapter_path).expect("golden stage file")).expect("chapter parses");
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let numerical_chapter = to_numerical_chapter(&chapter, &mut dictionary);
+    let block = Block::whole_chapter(&chapter, &numerical_chapter).expect("chapters pair up");
+    let base_tokenizer = tokenizer_for_language(&chapter.language_pair.base);
+    let level_policy = LevelPolicy::default();
+    let settings = BlockSimulationSettings {
+        max_regeneration_attempts_per_block: 1,
+        target_ct_comprehensible_threshold: 0.98,
+        min_ct_comprehensible_threshold: 0.0,
+        max_words_to_activate_per_regen_attempt: 1,
+        l4: L4Settings {
+            min_diglot_confidence: 0.5,
+            base_tokenizer: base_tokenizer.as_ref(),
+            plural_rules: None,
+            strategy: L4Strategy::default(),
+            corpus_frequency: None,
+        },
+        ct_metric: &TokenCt,
+        level_policy: &level_policy,
+    };
+
+    // A learner who knows nothing reads raw SimE throughout.
+    let result = run_simulation_numerical(&block, &NumericalLearnerProfile::new(), &[], &settings).expect("block simulates");
+    assert_eq!(result.sentence_levels.len(), block.len());
+    assert!(result.sentence_levels.iter().all(|decision| *decision == LevelDecision::L5));
+    assert_eq!(result.level_counts, [0, 0, 0, 0, block.len()]);
+
+    // One who knows every lemma and feature gets target-language sentences.
+    let mut learner = NumericalLearnerProfile::new();
+    for lemma_id in 0..dictionary.size() as u32 {
+        learner.set_lemma_state(lemma_id, LemmaState::Known);
+    }
+    for feature in numerical_chapter.sentences_numerical.iter().flat_map(|s| s.adv_s_grammar.iter().chain(&s.sim_s_grammar)) {
+        learner.set_grammar_state(feature, LemmaState::Known);
+    }
+    let result = run_simulation_numerical(&block, &learner, &[], &settings).expect("block simulates");
+    assert_eq!(result.level_counts.iter().sum::<usize>(), block.len());
+    assert!(result.level_counts[SentenceLevel::L5 as usize] < block.len());
+    let decided_ids: Vec<u32> = result.sentence_levels.iter().flat_map(|decision| decision.lemma_ids().iter().copied()).collect();
+    assert_eq!(decided_ids, result.output_lemma_ids_for_block);
+    let traced_levels: Vec<SentenceLevel> = result.trace.iter()
+        .filter_map(|event| match event {
+            TraceEvent::LevelChoice { level, .. } => Some(*level),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(traced_levels, result.sentence_levels.iter().map(LevelDecision::level).collect::<Vec<_>>());
+    for decision in &result.sentence_levels {
+        if let LevelDecision::L4 { substitutions, lemma_ids } = decision {
+            assert!(!substitutions.is_empty());
+            assert!(lemma_ids.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+}
+//*** END FILE: tests/level_decision.rs ***//
@@ -0,0 +1,173 @@
+//*** START FILE: tests/level_policy.rs ***//
+// core_algo and text_generator must agree on every sentence's level: CT is measured on the
+// decision, the text is rendered from it.
+use std::path::Path;
+use weavelang_rust_gui::parsing::llm_parser::{parse_llm_bytes, parse_llm_text_to_chapter};
+use weavelang_rust_gui::profile::LemmaState;
+use weavelang_rust_gui::simulation::block::Block;
+use weavelang_rust_gui::simulation::core_algo::{run_simulation_numerical, BlockSimulationSettings, L4Settings, L4Strategy, TokenCt};
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+use weavelang_rust_gui::simulation::level_policy::{l4_plan, LevelDecision, LevelPolicy, SentenceLevel};
+use weavelang_rust_gui::simulation::numerical_types::NumericalLearnerProfile;
+use weavelang_rust_gui::simulation::preprocessor::to_numerical_chapter;
+use weavelang_rust_gui::simulation::text_generator::generate_final_text_block;
+use weavelang_rust_gui::tokenizer::{tokenizer_for_language, Tokenizer};
+use weavelang_rust_gui::types::llm_data::ProcessedChapter;
+
+fn golden_chapter() -> ProcessedChapter {
+    let chapter_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/corpus/stage/bookA.llm.txt");
+    parse_llm_bytes("bookA.llm.txt", &std::fs::read(&chapter_path).expect("golden stage file")).expect("chapter parses")
+}
+
+// Simulates the chapter as one block for `learner` and renders it against the resulting
+// view; returns the decisions of both sides.
+fn decide_both_ways(chapter: &ProcessedChapter, learner: impl Fn(&GlobalLemmaDictionary) -> NumericalLearnerProfile, level_policy: &LevelPolicy) -> (Vec<LevelDecision>, Vec<SentenceLevel>, Vec<usize>) {
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let numerical_chapter = to_numerical_chapter(chapter, &mut dictionary);
+    let block = Block::whole_chapter(chapter, &numerical_chapter).expect("chapters pair up");
+    let base_tokenizer = tokenizer_for_language(&chapter.language_pair.base);
+    let l4 = L4Settings {
+        min_diglot_confidence: 0.5,
+        base_tokenizer: base_tokenizer.as_ref(),
+        plural_rules: None,
+        strategy: L4Strategy::default(),
+        corpus_frequency: None,
+    };
+    let settings = BlockSimulationSettings {
+        max_regeneration_attempts_per_block: 1,
+        target_ct_comprehensible_threshold: 0.98,
+        min_ct_comprehensible_threshold: 0.0,
+        max_words_to_activate_per_regen_attempt: 1,
+        l4,
+        ct_metric: &TokenCt,
+        level_policy,
+    };
+    let result = run_simulation_numerical(&block, &learner(&dictionary), &[], &settings).expect("block simulates");
+    let generated = generate_final_text_block(&block, &result.profile_for_text_generation, &l4, false, level_policy).expect("block renders");
+    let target_range_counts = generated.target_language_ranges.iter().map(Vec::len).collect();
+    (result.sentence_levels, generated.sentence_levels.iter().map(|record| record.level).collect(), target_range_counts)
+}
+
+fn seeded_learner(seed: u64) -> impl Fn(&GlobalLemmaDictionary) -> NumericalLearnerProfile {
+    move |dictionary| {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let mut profile = NumericalLearnerProfile::new();
+        for lemma_id in 0..dictionary.size() as u32 {
+            match rng.u8(0..3) {
+                0 => profile.set_lemma_state(lemma_id, LemmaState::Known),
+                1 => profile.set_lemma_state(lemma_id, LemmaState::Active),
+                _ => {}
+            }
+        }
+        profile
+    }
+}
+
+fn assert_parity(decisions: &[LevelDecision], rendered_levels: &[SentenceLevel], target_range_counts: &[usize]) {
+    assert_eq!(decisions.iter().map(LevelDecision::level).collect::<Vec<_>>(), rendered_levels);
+    for (decision, &ranges) in decisions.iter().zip(target_range_counts) {
+        if let LevelDecision::L4 { substitutions, .. } = decision {
+            assert_eq!(ranges, substitutions.len(), "every planned substitution is rendered");
+        }
+    }
+}
+
+#[test]
+fn simulation_and_text_generation_choose_the_same_levels() {
+    let chapter = golden_chapter();
+    let policies = [
+        LevelPolicy::default(),
+        LevelPolicy::default().with_min_sentence_ct(0.8),
+        "L1,L3,L4,L2".parse::<LevelPolicy>().expect("policy"),
+    ];
+    let mut levels_seen = [false; 5];
+    for seed in 0..20 {
+        for level_policy in &policies {
+            let (decisions, rendered_levels, target_range_counts) = decide_both_ways(&chapter, seeded_learner(seed), level_policy);
+            assert_parity(&decisions, &rendered_levels, &target_range_counts);
+            for level in rendered_levels {
+                levels_seen[level as usize] = true;
+            }
+        }
+    }
+    assert!(levels_seen.iter().all(|&seen| seen), "every level is exercised: {:?}", levels_seen);
+}
+
+#[test]
+fn a_segment_without_a_sim_e_span_rules_out_l3_on_both_sides() {
+    let half_active = |dictionary: &GlobalLemmaDictionary| {
+        let mut profile = NumericalLearnerProfile::new();
+        for lemma_id in (0..dictionary.size() as u32).step_by(2) {
+            profile.set_lemma_state(lemma_id, LemmaState::Active);
+        }
+        profile
+    };
+    let mut chapter = golden_chapter();
+    let (_, rendered_levels, _) = decide_both_ways(&chapter, half_active, &LevelPolicy::default());
+    assert!(rendered_levels.contains(&SentenceLevel::L3));
+
+    for sentence in &mut chapter.sentences {
+        sentence.phrase_alignments.clear();
+    }
+    let (decisions, rendered_levels, target_range_counts) = decide_both_ways(&chapter, half_active, &LevelPolicy::default());
+    assert_parity(&decisions, &rendered_levels, &target_range_counts);
+    assert!(!rendered_levels.contains(&SentenceLevel::L3));
+}
+
+// The L4 substitutions of a one-sentence chapter for a learner who knows every lemma.
+fn l4_forms(sim_e: &str, diglot_map: &str, plural_rules: Option<&dyn Tokenizer>) -> Vec<(String, String)> {
+    let stage = format!("\
+AdvS:: Ella ve el perro.
+SimS:: Ella ve el perro.
+SimE:: {}
+SimS_Segments::
+S1(Ella ve el perro.)
+SimSL::
+S1:: ella ver el perro
+AdvSL:: ella ver el perro
+DIGLOT_MAP::
+S1:: {}
+END_SENTENCE
+", sim_e, diglot_map);
+    let chapter = parse_llm_text_to_chapter("plural.llm.txt", &stage).expect("stage parses");
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let numerical_chapter = to_numerical_chapter(&chapter, &mut dictionary);
+    let mut profile = NumericalLearnerProfile::new();
+    for lemma_id in 0..dictionary.size() as u32 {
+        profile.set_lemma_state(lemma_id, LemmaState::Known);
+    }
+    let base_tokenizer = tokenizer_for_language("en");
+    let l4 = L4Settings {
+        min_diglot_confidence: 0.5,
+        base_tokenizer: base_tokenizer.as_ref(),
+        plural_rules,
+        strategy: L4Strategy::default(),
+        corpus_frequency: None,
+    };
+    l4_plan(&numerical_chapter.sentences_numerical[0], &profile, &l4).unwrap_or_default().into_iter()
+        .map(|substitution| (sim_e[substitution.eng_span].to_string(), substitution.spa_form))
+        .collect()
+}
+
+#[test]
+fn l4_plurals_need_a_content_word_stem() {
+    let spanish = tokenizer_for_language("es");
+    let plurals = Some(spanish.as_ref());
+    assert_eq!(l4_forms("She sees the dogs.", "dog->perro(perro)(Y)", plurals), [("dogs".to_string(), "perros".to_string())]);
+    assert!(l4_forms("She is here.", "I->yo(yo)(Y)", plurals).is_empty(), "\"is\" is not the plural of \"I\"");
+    assert!(l4_forms("She sees its tail.", "it->lo(lo)(Y)", plurals).is_empty());
+    assert!(l4_forms("She sees it as bad.", "a->un(un)(Y)", plurals).is_empty());
+    assert!(l4_forms("She sees the news.", "new->nuevo(nuevo)(Y)", plurals).is_empty());
+    assert!(l4_forms("She sees hers.", "her->su(su)(Y)", plurals).is_empty());
+}
+
+#[test]
+fn l4_falls_back_to_a_singular_when_the_form_has_no_plural() {
+    // The English rules have no pluralize, so the plural occurrence cannot take the form.
+    let english = tokenizer_for_language("en");
+    assert_eq!(
+        l4_forms("The dogs see a dog.", "dog->perro(perro)(Y)", Some(english.as_ref())),
+        [("dog".to_string(), "perro".to_string())]
+    );
+}
+//*** END FILE: tests/level_policy.rs ***//
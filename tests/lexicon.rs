@@ -0,0 +1,57 @@
+//*** START FILE: tests/lexicon.rs ***//
+use std::time::{Duration, Instant};
+use weavelang_rust_gui::lexicon::{LazyLexicon, Lexicon, LexiconEntry};
+use weavelang_rust_gui::parsing::llm_parser::parse_llm_text_to_chapter;
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+use weavelang_rust_gui::simulation::exporters::anki;
+
+const STAGE: &str = "\
+AdvS:: El perro come.
+SimS:: El perro come.
+SimE:: The dog eats.
+SimS_Segments::
+S1(El perro come.)
+SimSL::
+S1:: el perro comer
+AdvSL:: el perro comer
+DIGLOT_MAP::
+S1:: dog->perro(perro)(Y)
+END_SENTENCE
+";
+
+#[test]
+fn anki_cards_fall_back_to_lexicon_glosses() {
+    let chapter = parse_llm_text_to_chapter("perro.llm.txt", STAGE).expect("stage parses");
+    let sentences: Vec<_> = chapter.sentences.iter().collect();
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let perro = dictionary.get_id_or_insert("perro");
+    let comer = dictionary.get_id_or_insert("comer");
+    let mut lexicon = Lexicon::default();
+    lexicon.insert("comer", LexiconEntry { pos: "verb".to_string(), glosses: vec!["to eat".to_string()] });
+    lexicon.insert("perro", LexiconEntry { pos: "noun".to_string(), glosses: vec!["hound".to_string()] });
+
+    let card = |lemma_id, lexicon| anki::build_card(lemma_id, &sentences, &dictionary, lexicon).expect("sentence mentions lemma");
+    assert_eq!(card(comer, None).gloss, None);
+    assert_eq!(card(comer, Some(&lexicon)).gloss.as_deref(), Some("to eat (verb)"));
+    assert_eq!(card(perro, Some(&lexicon)).gloss.as_deref(), Some("dog"), "DIGLOT_MAP glosses come first");
+}
+
+#[test]
+fn a_lazy_lexicon_loads_in_the_background() {
+    let work_dir = std::env::temp_dir().join(format!("weavelang_lexicon_{}", std::process::id()));
+    std::fs::create_dir_all(&work_dir).unwrap();
+    let dump_path = work_dir.join("dump.jsonl");
+    std::fs::write(&dump_path, r#"{"word": "gato", "pos": "noun", "lang_code": "es", "senses": [{"glosses": ["cat"]}]}"#).unwrap();
+
+    let lazy = LazyLexicon::for_project(dump_path, &work_dir, "es");
+    assert!(lazy.try_get().is_none());
+    lazy.start_loading();
+    let started = Instant::now();
+    while lazy.try_get().is_none() && started.elapsed() < Duration::from_secs(10) {
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    let gloss = lazy.try_get().and_then(Result::ok).and_then(|lexicon| lexicon.short_gloss("gato"));
+    let _ = std::fs::remove_dir_all(&work_dir);
+    assert_eq!(gloss.as_deref(), Some("cat (noun)"));
+}
+//*** END FILE: tests/lexicon.rs ***//
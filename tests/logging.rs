@@ -0,0 +1,50 @@
+//*** START FILE: tests/logging.rs ***//
+// Simulation events are emitted inside block and regen_attempt spans, so a JSON log
+// (--log-json) can be grouped by block and attempt.
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use weavelang_rust_gui::json_api;
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().expect("log buffer").write(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn regen_passes_are_logged_inside_their_block_and_attempt() {
+    let stage = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/corpus/stage/bookA.llm.txt");
+    let contents = std::fs::read_to_string(stage).expect("golden stage file");
+    let chapters = json_api::parse(&json!({ "file_name": "bookA.llm.txt", "contents": contents }).to_string()).expect("chapter parses");
+
+    let buffer = SharedBuffer::default();
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_span_list(true)
+        .with_max_level(tracing::Level::DEBUG)
+        .with_writer(move || writer.clone())
+        .finish();
+    tracing::subscriber::with_default(subscriber, || {
+        json_api::simulate_block(&json!({ "chapter": chapters[0], "sentence_count": 4 }).to_string()).expect("block simulates");
+    });
+
+    let log = String::from_utf8(buffer.0.lock().expect("log buffer").clone()).expect("log is UTF-8");
+    let events: Vec<Value> = log.lines().map(|line| serde_json::from_str(line).expect("JSON log line")).collect();
+    let pass = events.iter().find(|event| event["fields"]["message"] == "Pass measured").expect("a regen pass was logged");
+    let span_names: Vec<&str> = pass["spans"].as_array().expect("span list").iter().filter_map(|span| span["name"].as_str()).collect();
+    assert_eq!(span_names, ["block", "regen_attempt"], "{}", pass);
+    assert_eq!(pass["spans"][0]["index"], 1);
+    assert_eq!(pass["spans"][1]["attempt"], 1);
+    assert!(pass["fields"]["ct"].is_number(), "{}", pass);
+}
+//*** END FILE: tests/logging.rs ***//
@@ -0,0 +1,55 @@
+//*** START FILE: tests/monte_carlo.rs ***//
+use weavelang_rust_gui::exposure_thresholds::{LearnerVariability, ThresholdDistribution, ThresholdTable};
+use weavelang_rust_gui::monte_carlo::{growth_spread, LearnerRun};
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+
+#[test]
+fn learner_thresholds_are_seeded_per_lemma_and_stay_in_range() {
+    let learner = LearnerVariability { distribution: ThresholdDistribution::Uniform, spread: 0.5, seed: 7 };
+    let other = LearnerVariability { seed: 8, ..learner };
+    let lemmas = ["casa", "perro", "de", "hablar", "banco#noun", "rojo", "tener", "ver"];
+    for lemma in lemmas {
+        let threshold = learner.apply(lemma, 20);
+        assert!((10..=30).contains(&threshold), "{} -> {}", lemma, threshold);
+        assert_eq!(threshold, learner.apply(lemma, 20));
+    }
+    assert!(lemmas.iter().any(|lemma| learner.apply(lemma, 20) != other.apply(lemma, 20)));
+    let lognormal = LearnerVariability { distribution: ThresholdDistribution::LogNormal, ..learner };
+    assert!(lemmas.iter().all(|lemma| lognormal.apply(lemma, 20) >= 1));
+
+    // Thresholds follow the lemma, not the order lemmas entered the dictionary.
+    let table = ThresholdTable::with_variability(None, Some(learner)).expect("table");
+    let mut forward = GlobalLemmaDictionary::new();
+    let mut backward = GlobalLemmaDictionary::new();
+    for lemma in lemmas {
+        forward.get_id_or_insert(lemma);
+    }
+    for lemma in lemmas.iter().rev() {
+        backward.get_id_or_insert(lemma);
+    }
+    let (forward_thresholds, backward_thresholds) = (table.resolve(&forward), table.resolve(&backward));
+    for lemma in lemmas {
+        let forward_id = forward.get_id(lemma).expect("id");
+        let backward_id = backward.get_id(lemma).expect("id");
+        assert_eq!(forward_thresholds.threshold_for(forward_id), backward_thresholds.threshold_for(backward_id));
+        assert_eq!(forward_thresholds.threshold_for(forward_id), learner.apply(lemma, 20));
+    }
+}
+
+#[test]
+fn growth_spread_summarizes_each_book_instance_across_learners() {
+    let run = |learner: usize, known: &[usize]| LearnerRun {
+        learner,
+        known_after: known.iter().enumerate().map(|(i, &k)| (format!("book_inst{:02}", i + 1), k)).collect(),
+        ..LearnerRun::default()
+    };
+    let failed = LearnerRun { learner: 5, error: Some("failed".to_string()), known_after: vec![("book_inst01".to_string(), 1000)], ..LearnerRun::default() };
+    let spread = growth_spread(&[run(1, &[10, 30]), run(2, &[20, 50]), run(3, &[30, 40]), run(4, &[40, 60]), failed]);
+    assert_eq!(spread.len(), 2);
+    assert_eq!(spread[0].book_instance_id, "book_inst01");
+    assert_eq!((spread[0].learners, spread[0].min, spread[0].median, spread[0].max), (4, 10, 20, 40));
+    assert_eq!((spread[0].p10, spread[0].p90), (10, 40));
+    assert!((spread[0].mean - 25.0).abs() < 1e-9);
+    assert!((spread[1].std_dev - 125f64.sqrt()).abs() < 1e-9);
+}
+//*** END FILE: tests/monte_carlo.rs ***//
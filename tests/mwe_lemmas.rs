@@ -0,0 +1,68 @@
+//*** START FILE: tests/mwe_lemmas.rs ***//
+use weavelang_rust_gui::parsing::llm_parser::parse_llm_text_to_chapter;
+use weavelang_rust_gui::profile_io::{load_profile_snapshot, save_profile_snapshot};
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+use weavelang_rust_gui::simulation::numerical_types::NumericalLearnerProfile;
+
+fn parsed_lemmas(sim_sl: &str, adv_sl: &str) -> (Vec<String>, Vec<String>) {
+    let stage = format!("\
+AdvS:: Por favor, dame el libro.
+SimS:: Por favor, dame el libro.
+SimE:: Please give me the book.
+SimS_Segments::
+S1(Por favor, dame el libro.)
+SimSL::
+S1:: {}
+AdvSL:: {}
+END_SENTENCE
+", sim_sl, adv_sl);
+    let chapter = parse_llm_text_to_chapter("mwe.llm.txt", &stage).expect("stage parses");
+    let sentence = &chapter.sentences[0];
+    (sentence.sim_s_lemmas[0].lemmas.clone(), sentence.adv_s_lemmas.clone())
+}
+
+#[test]
+fn a_single_bracket_group_is_one_expression() {
+    let (sim_sl, adv_sl) = parsed_lemmas("[por favor]", "[a pesar de]#ADP");
+    assert_eq!(sim_sl, ["por_favor"]);
+    assert_eq!(adv_sl, ["a_pesar_de#ADP"]);
+}
+
+#[test]
+fn several_bracket_groups_stay_separate_expressions() {
+    let (sim_sl, adv_sl) = parsed_lemmas("[por favor] dar [el libro]", "[por favor] [a pesar de]");
+    assert_eq!(sim_sl, ["por_favor", "dar", "el_libro"]);
+    assert_eq!(adv_sl, ["por_favor", "a_pesar_de"]);
+}
+
+#[test]
+fn unbracketed_lists_split_on_whitespace() {
+    let (sim_sl, adv_sl) = parsed_lemmas("por favor dar", "el libro");
+    assert_eq!(sim_sl, ["por", "favor", "dar"]);
+    assert_eq!(adv_sl, ["el", "libro"]);
+}
+
+#[test]
+fn schema_4_snapshots_are_rekeyed_and_colliding_lemmas_merged() {
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let ids: Vec<u32> = ["a", "b", "c"].iter().map(|key| dictionary.get_id_or_insert(key)).collect();
+    let mut profile = NumericalLearnerProfile::new();
+    profile.record_exposures(&[ids[0], ids[1], ids[1], ids[2]]);
+
+    let path = std::env::temp_dir().join(format!("weavelang_mwe_keys_{}.profile.json", std::process::id()));
+    save_profile_snapshot(&profile, &dictionary, &path).expect("snapshot saves");
+    let mut json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    json["schema_version"] = serde_json::json!(4);
+    json["dictionary"]["id_to_str"] = serde_json::json!(["por favor", "por_favor", "[gracias]"]);
+    json["dictionary"]["str_to_id"] = serde_json::json!({"por favor": 0, "por_favor": 1, "[gracias]": 2});
+    std::fs::write(&path, json.to_string()).unwrap();
+
+    let (loaded, loaded_dictionary) = load_profile_snapshot(&path).expect("schema 4 snapshot loads");
+    std::fs::remove_file(&path).ok();
+    assert_eq!(loaded_dictionary.get_id("[por favor]"), Some(0));
+    assert_eq!(loaded_dictionary.get_id("gracias"), Some(2));
+    assert_eq!(loaded_dictionary.size(), 3); // IDs stay put for delta snapshots
+    assert_eq!(loaded.get_lemma_info(0).map(|info| info.exposure_count), Some(3));
+    assert!(loaded.get_lemma_info(1).is_none());
+}
+//*** END FILE: tests/mwe_lemmas.rs ***//
@@ -0,0 +1,200 @@
+//*** START FILE: tests/parser_fuzz.rs ***//
+// Property tests for the stage file parser: generated "marker soup" (real section markers
+// with malformed, truncated or hostile payloads) must always come back as a Result, never
+// a panic. The strategies below generate it; fuzz/ drives the same entry point with
+// cargo-fuzz for open-ended runs. Failing cases are shrunk, and their seeds are saved to
+// tests/proptest-regressions/parser_fuzz.txt, which is replayed before new cases.
+
+use proptest::prelude::*;
+use proptest::sample::{select, Index};
+use proptest::test_runner::FileFailurePersistence;
+use weavelang_rust_gui::parsing::llm_parser::{parse_llm_bytes, parse_llm_text_to_chapter, parse_llm_text_to_chapters, validate_llm_text};
+use weavelang_rust_gui::parsing::llm_writer::write_chapter_to_llm_text;
+use weavelang_rust_gui::simulation::block::{Block, BlockBoundaries};
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+use weavelang_rust_gui::simulation::preprocessor::to_numerical_chapter;
+use weavelang_rust_gui::types::llm_data::ProcessedChapter;
+
+const CASES: u32 = 500;
+
+const MARKERS: &[&str] = &[
+    "AdvS::", "SimS::", "SimE::", "SimS_Segments::", "PHRASE_ALIGN::", "SimSL::", "AdvSL::",
+    "DIGLOT_MAP::", "LOCKED_PHRASE::", "GRAM::", "AdvTarget::", "SimTarget::", "SimBase::",
+    "SimTarget_Segments::", "SimTargetL::", "AdvTargetL::", "CHAPTER_MARKER_DIRECT::",
+    "BOOK::", "CHAPTER::", "PARA::", "END_SENTENCE", "//",
+];
+
+// Characters the parser splits or matches on, plus multi-byte text to catch byte-index slicing.
+const ALPHABET: &[char] = &[
+    'a', 'e', 'S', '1', '2', '0', '.', ' ', ' ', '\t', '(', ')', '[', ']', '|', ':', '-', '>', '~',
+    '#', '/', 'Y', 'N', 'y', 'n', '\n', '\r', 'á', 'ñ', 'ü', '€', '😀', '\u{0301}', '\u{feff}',
+];
+
+fn config() -> ProptestConfig {
+    ProptestConfig {
+        cases: CASES,
+        failure_persistence: Some(Box::new(FileFailurePersistence::Direct("tests/proptest-regressions/parser_fuzz.txt"))),
+        ..ProptestConfig::default()
+    }
+}
+
+fn junk(max_len: usize) -> impl Strategy<Value = String> {
+    proptest::collection::vec(select(ALPHABET), 0..=max_len).prop_map(String::from_iter)
+}
+
+fn segment_id() -> impl Strategy<Value = String> {
+    prop_oneof![
+        1 => Just(String::new()),
+        1 => junk(4),
+        4 => (0u32..5).prop_map(|n| format!("S{}", n)),
+    ]
+}
+
+fn diglot_entry() -> impl Strategy<Value = String> {
+    let viability = prop_oneof![
+        junk(3).prop_map(|j| format!("({})", j)),
+        (-0.5f32..1.5).prop_map(|c| format!("(Y:{})", c)),
+        Just("(N)".to_string()),
+        (0u32..100).prop_map(|n| format!("(y: .{}", n)),
+        Just("(Y)".to_string()),
+    ];
+    (junk(6), junk(6), junk(6), viability).prop_map(|(eng, form, lemma, viability)| format!("{}->{}({}){}", eng, form, lemma, viability))
+}
+
+// A continuation line shaped for the marker's section.
+fn payload(marker: &'static str) -> BoxedStrategy<String> {
+    match marker {
+        "SimS_Segments::" => (segment_id(), junk(12)).prop_map(|(id, text)| format!("{}({})", id, text)).boxed(),
+        "PHRASE_ALIGN::" => (segment_id(), junk(6), junk(6)).prop_map(|(id, a, b)| format!("{} ~ {} ~ {}", id, a, b)).boxed(),
+        "SimSL::" | "AdvSL::" => (segment_id(), junk(5), junk(5), junk(5))
+            .prop_map(|(id, a, b, c)| format!("{}:: [{} {}] {}", id, a, b, c)).boxed(),
+        "DIGLOT_MAP::" => (segment_id(), proptest::collection::vec(diglot_entry(), 0..4))
+            .prop_map(|(id, entries)| format!("{}:: {}", id, entries.join(" | "))).boxed(),
+        "GRAM::" => (select(&["AdvS", "SimS", ""][..]), junk(10)).prop_map(|(side, features)| format!("{}: {}", side, features)).boxed(),
+        _ => junk(16).boxed(),
+    }
+}
+
+// A marker line with same-line content, its continuation lines and occasional stray junk.
+fn marker_lines() -> impl Strategy<Value = String> {
+    (select(MARKERS), 0usize..3, junk(10)).prop_flat_map(|(marker, indent, content)| {
+        let stray = proptest::option::weighted(0.125, junk(20));
+        (proptest::collection::vec(payload(marker), 0..3), stray).prop_map(move |(payloads, stray)| {
+            let mut text = format!("{}{} {}\n", " ".repeat(indent), marker, content);
+            for payload in payloads {
+                text.push_str(&payload);
+                text.push('\n');
+            }
+            text.push_str(&stray.unwrap_or_default());
+            text
+        })
+    })
+}
+
+/// A stage file of random blocks, occasionally cut at a random char.
+fn marker_soup() -> impl Strategy<Value = String> {
+    let block = proptest::collection::vec(marker_lines(), 0..12).prop_map(|lines| format!("{}END_SENTENCE\n", lines.concat()));
+    (proptest::collection::vec(block, 0..6), proptest::option::weighted(0.25, any::<Index>())).prop_map(|(blocks, cut)| {
+        let text = blocks.concat();
+        match cut {
+            Some(cut) => text.chars().take(cut.index(text.chars().count() + 1)).collect(),
+            None => text,
+        }
+    })
+}
+
+fn paragraph_starts(chapter: &ProcessedChapter) -> Vec<bool> {
+    chapter.sentences.iter().map(|s| s.paragraph_start).collect()
+}
+
+proptest! {
+    #![proptest_config(config())]
+
+    #[test]
+    fn parser_returns_a_result_for_marker_soup(input in marker_soup()) {
+        let _ = parse_llm_bytes("fuzz.llm.txt", input.as_bytes());
+        let _ = validate_llm_text("fuzz.llm.txt", &input);
+    }
+
+    #[test]
+    fn parser_rejects_arbitrary_bytes_without_panicking(
+        input in marker_soup(),
+        corruptions in proptest::collection::vec((any::<Index>(), any::<u8>()), 0..4),
+    ) {
+        let mut bytes = input.into_bytes();
+        for (index, byte) in corruptions {
+            if !bytes.is_empty() {
+                let index = index.index(bytes.len());
+                bytes[index] = byte;
+            }
+        }
+        let _ = parse_llm_bytes("fuzz.llm.txt", &bytes);
+    }
+
+    #[test]
+    fn written_chapters_parse_back_to_the_same_sentences(input in marker_soup()) {
+        if let Ok(chapter) = parse_llm_bytes("fuzz.llm.txt", input.as_bytes()) {
+            let written = write_chapter_to_llm_text(&chapter);
+            let reparsed = parse_llm_bytes("fuzz.llm.txt", written.as_bytes());
+            prop_assert!(reparsed.is_ok(), "written chapter does not parse ({:?}):\n{}", reparsed.err(), written);
+            let reparsed = reparsed.unwrap();
+            prop_assert_eq!(reparsed.sentences.len(), chapter.sentences.len(), "sentence count changed:\n{}", written);
+            prop_assert_eq!(paragraph_starts(&reparsed), paragraph_starts(&chapter), "paragraph starts changed:\n{}", written);
+        }
+    }
+
+    #[test]
+    fn book_chapters_join_back_to_the_single_chapter_parse(input in marker_soup()) {
+        if let Ok(chapters) = parse_llm_text_to_chapters("fuzz.llm.txt", &input) {
+            let whole = parse_llm_text_to_chapter("fuzz.llm.txt", &input);
+            prop_assert!(whole.is_ok(), "whole file fails ({:?})", whole.err());
+            let whole = whole.unwrap();
+            let joined_ids: Vec<&str> = chapters.iter().flat_map(|c| &c.sentences).map(|s| s.sentence_id.as_str()).collect();
+            let whole_ids: Vec<&str> = whole.sentences.iter().map(|s| s.sentence_id.as_str()).collect();
+            prop_assert_eq!(joined_ids, whole_ids, "chapters lost or reordered sentences");
+            prop_assert!(chapters.len() == 1 || chapters.iter().all(|c| !c.sentences.is_empty()), "empty chapter");
+        }
+    }
+}
+
+#[test]
+fn invalid_utf8_is_an_error() {
+    assert!(parse_llm_bytes("fuzz.llm.txt", &[0xff, 0xfe, b'A']).is_err());
+}
+
+#[test]
+fn chapter_headers_split_a_book() {
+    let sentence = |text: &str| format!("AdvS:: {}\nSimS:: {}\nSimE:: {}\nEND_SENTENCE\n", text, text, text);
+    let book = format!(
+        "BOOK:: El libro\n{}CHAPTER:: Uno\n{}{}CHAPTER:: Dos\n{}",
+        sentence("a"), sentence("b"), sentence("c"), sentence("d")
+    );
+    let chapters = parse_llm_text_to_chapters("book.llm.txt", &book).expect("book parses");
+    let titles: Vec<Option<&str>> = chapters.iter().map(|c| c.chapter_title.as_deref()).collect();
+    let sizes: Vec<usize> = chapters.iter().map(|c| c.sentences.len()).collect();
+    assert_eq!(titles, [None, Some("Uno"), Some("Dos")]);
+    assert_eq!(sizes, [1, 2, 1]);
+    assert!(chapters.iter().all(|c| c.book_title.as_deref() == Some("El libro")));
+    assert_eq!(chapters[1].sentences[0].section_heading.as_deref(), Some("Uno"));
+    assert_eq!(chapters[2].sentences[0].sentence_id, "book_4");
+}
+
+#[test]
+fn paragraph_blocks_end_at_the_closest_paragraph_end() {
+    let sentence = |text: &str| format!("AdvS:: {}\nSimS:: {}\nSimE:: {}\nEND_SENTENCE\n", text, text, text);
+    // Paragraphs start at sentences 0, 3 and 7 of 10.
+    let book: String = (0..10).map(|i| format!("{}{}", if [3, 7].contains(&i) { "PARA::\n" } else { "" }, sentence(&i.to_string()))).collect();
+    let chapter = parse_llm_text_to_chapter("paras.llm.txt", &book).expect("book parses");
+    let starts: Vec<usize> = (0..10).filter(|&i| chapter.sentences[i].paragraph_start).collect();
+    assert_eq!(starts, [3, 7]);
+    let numerical = to_numerical_chapter(&chapter, &mut GlobalLemmaDictionary::new());
+    let block = Block::whole_chapter(&chapter, &numerical).expect("chapters pair up");
+    let paragraphs = |tolerance| BlockBoundaries::Paragraphs { tolerance };
+    assert_eq!(block.block_end(0, 4, BlockBoundaries::Fixed, 10), 4);
+    assert_eq!(block.block_end(0, 4, paragraphs(1), 10), 3);
+    assert_eq!(block.block_end(0, 5, paragraphs(1), 10), 5, "no paragraph end within tolerance");
+    assert_eq!(block.block_end(0, 5, paragraphs(2), 10), 3, "ties go to the shorter block");
+    assert_eq!(block.block_end(7, 2, paragraphs(1), 10), 10, "the end of the run is a paragraph end");
+    assert_eq!(block.block_end(7, 4, paragraphs(1), 20), 10, "the chapter start is a paragraph start on the next pass");
+}
+//*** END FILE: tests/parser_fuzz.rs ***//
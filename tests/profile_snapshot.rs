@@ -0,0 +1,37 @@
+//*** START FILE: tests/profile_snapshot.rs ***//
+use weavelang_rust_gui::profile::LemmaState;
+use weavelang_rust_gui::profile_io::{detect_snapshot_format, load_profile_snapshot, save_profile_snapshot_as, SnapshotFormat};
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+use weavelang_rust_gui::simulation::numerical_types::NumericalLearnerProfile;
+
+#[test]
+fn binary_snapshots_round_trip() {
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let gato = dictionary.get_id_or_insert("gato");
+    let perro = dictionary.get_id_or_insert("perro");
+    dictionary.get_id_or_insert("casa");
+    dictionary.add_alias("gata", gato);
+    let mut profile = NumericalLearnerProfile::new();
+    profile.advance_block_clock();
+    profile.record_exposures(&[gato, perro, perro]);
+    profile.set_lemma_state(gato, LemmaState::Known);
+    profile.record_form_exposures(["gatos", "Perro"]);
+    profile.record_grammar_exposures(&["ser_past".to_string()]);
+
+    let work_dir = std::env::temp_dir().join(format!("weavelang_profile_snapshot_{}", std::process::id()));
+    std::fs::create_dir_all(&work_dir).unwrap();
+    let snapshot_path = work_dir.join(format!("learner.{}", SnapshotFormat::Binary.file_suffix()));
+    save_profile_snapshot_as(&profile, &dictionary, &snapshot_path, SnapshotFormat::Binary).expect("snapshot saves");
+    let format = detect_snapshot_format(&snapshot_path).expect("format is detected");
+    let loaded = load_profile_snapshot(&snapshot_path);
+    let _ = std::fs::remove_dir_all(&work_dir);
+    let (loaded_profile, loaded_dictionary) = loaded.expect("binary snapshot loads");
+
+    assert_eq!(format, SnapshotFormat::Binary);
+    assert_eq!(serde_json::to_string(&loaded_profile).unwrap(), serde_json::to_string(&profile).unwrap());
+    assert_eq!(loaded_dictionary.to_tsv(), dictionary.to_tsv());
+    assert_eq!(loaded_dictionary.aliases(), dictionary.aliases());
+    assert!(loaded_profile.is_lemma_known(gato), "the state bitsets are rebuilt on load");
+    assert!(loaded_profile.is_lemma_known_or_active(perro));
+}
+//*** END FILE: tests/profile_snapshot.rs ***//
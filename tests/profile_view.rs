@@ -0,0 +1,84 @@
+//*** START FILE: tests/profile_view.rs ***//
+use weavelang_rust_gui::profile::LemmaState;
+use weavelang_rust_gui::simulation::numerical_types::{DecayParams, ExposureThresholds, NumericalLearnerProfile};
+use weavelang_rust_gui::simulation::profile_view::{LearnerStates, LemmaTransition, ProfileDelta, ProfileOverlay};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[test]
+fn applying_a_delta_reports_the_lemmas_whose_state_changed() {
+    let mut profile = NumericalLearnerProfile::new();
+    profile.set_exposure_thresholds(Arc::new(ExposureThresholds { default_threshold: 2, by_lemma_id: HashMap::new() }));
+    profile.set_lemma_state(1, LemmaState::Active);
+    profile.record_exposures(&[1]);
+    profile.set_lemma_state(2, LemmaState::Active);
+
+    let delta = ProfileDelta {
+        activated_lemma_ids: vec![5],
+        activated_grammar: vec!["preterite".to_string()],
+        exposed_lemma_ids: vec![1, 2, 7, 1],
+        exposed_grammar: vec![],
+    };
+    let transitions = delta.apply(&mut profile);
+
+    let transition = |lemma_id, before, after| LemmaTransition { lemma_id, before, after };
+    assert_eq!(transitions, [
+        transition(1, LemmaState::Active, LemmaState::Known),
+        transition(5, LemmaState::New, LemmaState::Active),
+        transition(7, LemmaState::New, LemmaState::Active),
+    ]);
+    assert_eq!(profile.get_lemma_info(1).map(|info| info.exposure_count), Some(3));
+    assert_eq!(profile.get_lemma_info(2).map(|info| (info.state, info.exposure_count)), Some((LemmaState::Active, 1)));
+    assert!(profile.is_grammar_known_or_active("preterite"));
+}
+#[test]
+fn an_overlay_reads_through_to_its_base_until_materialized() {
+    let mut base = NumericalLearnerProfile::new();
+    base.set_lemma_state(1, LemmaState::Active);
+    base.record_exposures(&[1, 1]);
+    base.set_lemma_state(2, LemmaState::Known);
+
+    let mut overlay = ProfileOverlay::new(&base);
+    overlay.set_lemma_state(1, LemmaState::New); // Withheld
+    overlay.set_lemma_state(3, LemmaState::Active); // Activated
+    overlay.set_grammar_state("subjunctive", LemmaState::Active);
+
+    assert_eq!(overlay.lemma_state(1), LemmaState::New);
+    assert_eq!(overlay.exposure_count(1), 2);
+    assert!(overlay.is_lemma_known(2));
+    assert!(overlay.is_lemma_known_or_active(3));
+    assert!(overlay.has_grammar(&["subjunctive".to_string()]));
+    assert_eq!((overlay.count_known(), overlay.count_active_only()), (1, 1));
+    assert_eq!(base.lemma_state(1), LemmaState::Active);
+    assert!(!base.is_grammar_known_or_active("subjunctive"));
+
+    let materialized = overlay.materialize();
+    assert_eq!((materialized.count_known(), materialized.count_active_only()), (1, 1));
+    assert_eq!(materialized.get_lemma_info(1).map(|info| (info.state, info.exposure_count)), Some((LemmaState::New, 2)));
+    assert!(materialized.is_grammar_known_or_active("subjunctive"));
+}
+#[test]
+fn state_bitsets_follow_the_vocabulary() {
+    let mut profile = NumericalLearnerProfile::new();
+    profile.set_exposure_thresholds(Arc::new(ExposureThresholds { default_threshold: 2, by_lemma_id: HashMap::new() }));
+    profile.record_exposures(&[1, 1, 2]);
+    profile.override_lemma_state(3, LemmaState::Known);
+    assert!(profile.is_lemma_known(1) && profile.is_lemma_known(3));
+    assert!(profile.is_lemma_known_or_active(2) && !profile.is_lemma_known(2));
+    assert_eq!((profile.count_known(), profile.count_active_only()), (2, 1));
+
+    for _ in 0..10 {
+        profile.advance_block_clock();
+    }
+    let decayed = profile.apply_decay(&DecayParams { half_life_blocks: 1.0, min_retention: 0.5 });
+    assert_eq!(decayed, [1, 3]);
+    assert!(!profile.is_lemma_known(1) && profile.is_lemma_known_or_active(1));
+
+    let reloaded: NumericalLearnerProfile = serde_json::from_str(&serde_json::to_string(&profile).unwrap()).unwrap();
+    assert_eq!((reloaded.count_known(), reloaded.count_total_known_or_active()), (0, 3));
+    assert!(ProfileOverlay::new(&reloaded).is_lemma_known_or_active(2));
+
+    profile.remap_lemma_ids(&HashMap::from([(2, 20)]));
+    assert!(profile.is_lemma_known_or_active(20) && !profile.is_lemma_known_or_active(1));
+}
+//*** END FILE: tests/profile_view.rs ***//
@@ -0,0 +1,39 @@
+//*** START FILE: tests/protected_spans.rs ***//
+use weavelang_rust_gui::parsing::llm_parser::parse_llm_text_to_chapter;
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+use weavelang_rust_gui::simulation::preprocessor::to_numerical_chapter;
+
+const STAGE: &str = "\
+AdvS:: El lunes Pedro habla español.
+SimS:: El lunes Pedro habla español.
+SimE:: On Monday Peter speaks Spanish.
+SimS_Segments::
+S1(El lunes Pedro habla español.)
+SimSL::
+S1:: el lunes Pedro hablar español
+AdvSL:: el lunes Pedro hablar español
+DIGLOT_MAP::
+S1:: Monday->lunes(lunes)(Y) | Peter->Pedro(Pedro)(Y) | Spanish->español(español)(Y)
+END_SENTENCE
+";
+
+fn confidence_of(eng_word: &str) -> f32 {
+    let chapter = parse_llm_text_to_chapter("lunes.llm.txt", STAGE).expect("stage parses");
+    let numerical_chapter = to_numerical_chapter(&chapter, &mut GlobalLemmaDictionary::new());
+    numerical_chapter.sentences_numerical[0].diglot_map_numerical[0].entries.iter()
+        .find(|entry| entry.eng_word_original == eng_word)
+        .map(|entry| entry.confidence)
+        .expect("entry exists")
+}
+
+#[test]
+fn names_in_sim_e_are_not_substituted() {
+    assert_eq!(confidence_of("Peter"), 0.0);
+}
+
+#[test]
+fn weekdays_and_languages_are_not_names() {
+    assert!(confidence_of("Monday") > 0.0);
+    assert!(confidence_of("Spanish") > 0.0);
+}
+//*** END FILE: tests/protected_spans.rs ***//
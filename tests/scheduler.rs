@@ -0,0 +1,48 @@
+//*** START FILE: tests/scheduler.rs ***//
+use weavelang_rust_gui::parsing::llm_parser::parse_llm_text_to_chapter;
+use weavelang_rust_gui::simulation::block::Block;
+use weavelang_rust_gui::simulation::dictionary::GlobalLemmaDictionary;
+use weavelang_rust_gui::simulation::numerical_types::NumericalLearnerProfile;
+use weavelang_rust_gui::simulation::preprocessor::to_numerical_chapter;
+use weavelang_rust_gui::simulation::scheduler::{ActivationScheduler, SchedulerParams};
+
+const STAGE: &str = "\
+AdvS:: El perro ve el gato.
+SimS:: El perro ve el gato.
+SimE:: The dog sees the cat.
+SimS_Segments::
+S1(El perro ve el gato.)
+SimSL::
+S1:: el perro ver el gato
+AdvSL:: el perro ver el gato
+DIGLOT_MAP::
+S1:: dog->perro(perro)(Y) | cat->gato(gato)(Y)
+END_SENTENCE
+";
+
+#[test]
+fn recency_only_counts_lemmas_shown_untranslated() {
+    let chapter = parse_llm_text_to_chapter("perro.llm.txt", STAGE).expect("stage parses");
+    let mut dictionary = GlobalLemmaDictionary::new();
+    let numerical_chapter = to_numerical_chapter(&chapter, &mut dictionary);
+    let block = Block::whole_chapter(&chapter, &numerical_chapter).unwrap();
+    let profile = NumericalLearnerProfile::new();
+    let params = SchedulerParams {
+        block_frequency_weight: 0.0,
+        corpus_frequency_weight: 0.0,
+        interval_weight: 0.0,
+        ..SchedulerParams::default()
+    };
+    let perro = dictionary.get_id("perro").unwrap();
+    let gato = dictionary.get_id("gato").unwrap();
+
+    let mut scheduler = ActivationScheduler::new(&numerical_chapter, None, params, 0.5);
+    let first: Vec<u32> = scheduler.rank_candidates(&block, &profile, 1, 1).into_iter().map(|(id, _)| id).collect();
+    assert!(first.iter().position(|&id| id == perro) < first.iter().position(|&id| id == gato), "ties go to the lower lemma ID");
+
+    // The block showed "gato" in Spanish and "perro" only as "dog".
+    scheduler.record_exposures(&[gato]);
+    let second = scheduler.rank_candidates(&block, &profile, 1, 1);
+    assert_eq!(second[0].0, gato);
+}
+//*** END FILE: tests/scheduler.rs ***//
@@ -0,0 +1,67 @@
+//*** START FILE: tests/server.rs ***//
+// Talks to the `serve` HTTP server over a real socket, as a web front-end would.
+
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use weavelang_rust_gui::server;
+
+fn start_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind a free port");
+    let address = listener.local_addr().expect("bound address");
+    std::thread::spawn(move || server::serve(listener));
+    address
+}
+
+// (status, JSON body) of one request.
+fn request(address: SocketAddr, method: &str, path: &str, body: &Value) -> (u16, Value) {
+    let body = body.to_string();
+    let mut stream = TcpStream::connect(address).expect("connect");
+    write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+           method, path, body.len(), body).expect("send");
+    let mut response = String::new();
+    stream.read_to_string(&mut response).expect("receive");
+    let (head, body) = response.split_once("\r\n\r\n").expect("headers end");
+    let status = head.split_whitespace().nth(1).and_then(|code| code.parse().ok()).expect("status code");
+    (status, if body.is_empty() { Value::Null } else { serde_json::from_str(body).expect("JSON body") })
+}
+
+#[test]
+fn serves_parse_simulate_and_generate() {
+    let address = start_server();
+    let stage = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/corpus/stage/bookA.llm.txt");
+    let contents = std::fs::read_to_string(stage).expect("golden stage file");
+
+    let (status, parsed) = request(address, "POST", "/parse", &json!({ "file_name": "bookA.llm.txt", "contents": contents }));
+    assert_eq!(status, 200, "{}", parsed);
+    let chapter = parsed["ok"][0].clone();
+
+    let (status, simulated) = request(address, "POST", "/simulate-block", &json!({ "chapter": chapter, "sentence_count": 3 }));
+    assert_eq!(status, 200, "{}", simulated);
+    assert_eq!(simulated["ok"]["levels"].as_array().map(Vec::len), Some(3));
+
+    let rpc = json!({
+        "jsonrpc": "2.0", "id": 7, "method": "generate_text",
+        "params": { "chapter": chapter, "profile": simulated["ok"]["profile"], "dictionary": simulated["ok"]["dictionary"], "sentence_count": 2 },
+    });
+    let (status, generated) = request(address, "POST", "/rpc", &rpc);
+    assert_eq!(status, 200);
+    assert_eq!(generated["id"], 7, "{}", generated);
+    assert_eq!(generated["result"]["sentence_texts"].as_array().map(Vec::len), Some(2), "{}", generated);
+}
+
+#[test]
+fn reports_errors_as_json() {
+    let address = start_server();
+    let (status, body) = request(address, "POST", "/simulate-block", &json!({ "chapter": 3 }));
+    assert_eq!(status, 400);
+    assert!(body["error"].is_string(), "{}", body);
+    let (status, _) = request(address, "POST", "/nothing-here", &json!({}));
+    assert_eq!(status, 404);
+    let (status, _) = request(address, "GET", "/parse", &Value::Null);
+    assert_eq!(status, 405);
+    let (_, body) = request(address, "POST", "/rpc", &json!({ "jsonrpc": "2.0", "id": 1, "method": "nope" }));
+    assert_eq!(body["error"]["code"], -32601, "{}", body);
+}
+//*** END FILE: tests/server.rs ***//
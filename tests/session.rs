@@ -0,0 +1,27 @@
+//*** START FILE: tests/session.rs ***//
+use weavelang_rust_gui::session::{stage_file_key, GuiSession, StageFileStatus, ValidationOutcome};
+
+#[test]
+fn stage_files_with_the_same_name_keep_separate_records() {
+    let work_dir = std::env::temp_dir().join(format!("weavelang_session_{}", std::process::id()));
+    let project = work_dir.join("project");
+    for dir in [project.join("stage"), project.join("stage/extra"), work_dir.join("elsewhere")] {
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("bookA.llm.txt"), "").unwrap();
+    }
+    let main_key = stage_file_key(&project, &project.join("stage/bookA.llm.txt"));
+    let nested_key = stage_file_key(&project, &project.join("stage/extra/bookA.llm.txt"));
+    let outside_key = stage_file_key(&project, &work_dir.join("elsewhere/bookA.llm.txt"));
+    let _ = std::fs::remove_dir_all(&work_dir);
+    assert_eq!(main_key, "stage/bookA.llm.txt");
+    assert_eq!(nested_key, "stage/extra/bookA.llm.txt");
+    assert!(outside_key.ends_with("bookA.llm.txt") && outside_key != main_key);
+
+    let mut session = GuiSession::default();
+    session.record_stage_file(&main_key, "aaaa".to_string(), ValidationOutcome::Clean);
+    session.record_stage_file(&nested_key, "bbbb".to_string(), ValidationOutcome::ParseError);
+    assert_eq!(session.stage_file_status(&main_key, "aaaa"), StageFileStatus::Unchanged(ValidationOutcome::Clean));
+    assert_eq!(session.stage_file_status(&nested_key, "bbbb"), StageFileStatus::Unchanged(ValidationOutcome::ParseError));
+    assert_eq!(session.stage_file_status(&outside_key, "aaaa"), StageFileStatus::New);
+}
+//*** END FILE: tests/session.rs ***//
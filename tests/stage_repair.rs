@@ -0,0 +1,55 @@
+//*** START FILE: tests/stage_repair.rs ***//
+use weavelang_rust_gui::parsing::llm_parser::parse_llm_text_to_chapter;
+use weavelang_rust_gui::stage_repair::repair_stage_directory;
+use weavelang_rust_gui::types::llm_data::LanguagePair;
+
+// The lone PARA:: block yields no sentence, so the second sentence is block 3.
+const STAGE: &str = "\
+AdvS:: El perro come.
+SimS:: El perro come.
+SimE:: The dog eats.
+SimS_Segments::
+S1(El perro come.)
+SimSL::
+S1:: el perro comer
+AdvSL:: el perro comer
+DIGLOT_MAP::
+S1:: dog->perro(perro)(Y)
+END_SENTENCE
+
+PARA::
+END_SENTENCE
+
+AdvS:: El gato duerme.
+SimS:: El gato duerme.
+SimE:: The cat sleeps.
+SimS_Segments::
+S1(El gato duerme.)
+SimSL::
+S1:: el gato dormir
+AdvSL:: el gato dormir
+DIGLOT_MAP::
+S1:: cat->gato(gato)(Y)
+END_SENTENCE
+";
+
+#[test]
+fn blocks_pair_with_sentences_by_id() {
+    let work_dir = std::env::temp_dir().join(format!("weavelang_stage_repair_{}", std::process::id()));
+    let stage_dir = work_dir.join("stage");
+    std::fs::create_dir_all(&stage_dir).unwrap();
+    std::fs::write(stage_dir.join("animals.llm.txt"), STAGE).unwrap();
+
+    let report = repair_stage_directory(&stage_dir, &work_dir.join("repaired"), &LanguagePair::default(), None).expect("repair runs");
+    let repaired = std::fs::read_to_string(work_dir.join("repaired/animals.llm.txt")).unwrap();
+    let _ = std::fs::remove_dir_all(&work_dir);
+    assert!(report.files[0].error.is_none());
+
+    let original = parse_llm_text_to_chapter("animals.llm.txt", STAGE).unwrap();
+    let chapter = parse_llm_text_to_chapter("animals.llm.txt", &repaired).expect("repaired file parses");
+    let sim_e: Vec<&str> = chapter.sentences.iter().map(|s| s.sim_e.as_str()).collect();
+    assert_eq!(sim_e, ["The dog eats.", "The cat sleeps."]);
+    assert!(chapter.sentences[1].paragraph_start, "the PARA:: block is kept");
+    assert_eq!(chapter.sentences[1].sentence_id, original.sentences[1].sentence_id);
+}
+//*** END FILE: tests/stage_repair.rs ***//
@@ -0,0 +1,37 @@
+//*** START FILE: tests/sweep.rs ***//
+// Sweeps the golden mini-corpus over a small grid. Reuses the golden run settings, so it
+// is only built with `cargo test --features golden`.
+#![cfg(feature = "golden")]
+
+use std::path::Path;
+use weavelang_rust_gui::config::Config;
+use weavelang_rust_gui::golden::golden_generation_args;
+use weavelang_rust_gui::sweep::{run_sweep, sweep_csv, SweepGrid};
+
+#[test]
+fn sweep_runs_every_combination_and_tabulates_it() {
+    let corpus_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/corpus");
+    let work_dir = std::env::temp_dir().join(format!("weavelang_sweep_{}", std::process::id()));
+    let config: Config = toml::from_str(&format!("content_project_dir = {:?}", corpus_dir.display().to_string())).expect("config");
+    let base = golden_generation_args(&corpus_dir.join("sequence.txt"), &work_dir);
+    let grid = SweepGrid { target_ct_thresholds: vec![0.9, 0.98], exposure_thresholds: vec![], activation_rates: vec![1, 3] };
+    let points = grid.points(&base);
+    assert_eq!(points.len(), 4);
+
+    let rows = run_sweep(&config, &base, &points, 2);
+    let _ = std::fs::remove_dir_all(&work_dir);
+    let rows = rows.expect("sweep");
+    assert_eq!(rows.iter().map(|row| row.label.as_str()).collect::<Vec<_>>(),
+               ["ct0.9_expdefault_act1", "ct0.9_expdefault_act3", "ct0.98_expdefault_act1", "ct0.98_expdefault_act3"]);
+    for row in &rows {
+        assert!(row.error.is_none(), "{:?}", row);
+        assert_eq!(row.level_counts.iter().sum::<usize>(), row.sentences, "{:?}", row);
+    }
+    // Activating more words per regeneration never leaves the learner knowing fewer.
+    assert!(rows[1].final_known + rows[1].final_active >= rows[0].final_known + rows[0].final_active);
+
+    let csv = sweep_csv(&rows);
+    assert_eq!(csv.lines().count(), 5);
+    assert!(csv.starts_with("label,target_ct_threshold,exposure_threshold,activation_rate,"));
+}
+//*** END FILE: tests/sweep.rs ***//